@@ -11,7 +11,10 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::buck2::{status::Status, types::ProjectRelativePath};
+use crate::buck2::{
+    status::Status,
+    types::{BuildPriority, BuildRequirements, ProjectRelativePath},
+};
 
 /// Parameters required to build a task.
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -30,6 +33,12 @@ pub struct TaskBuildRequest {
     /// Buck2 target path (e.g. //app:server). Optional for backward compatibility.
     #[serde(default, alias = "targets_path")]
     pub targets: Option<Vec<String>>,
+    /// Worker requirements of this build, merged with the server's routing rules.
+    #[serde(default)]
+    pub requirements: BuildRequirements,
+    /// Scheduling class used to order this build in the queue.
+    #[serde(default)]
+    pub priority: BuildPriority,
 }
 
 impl TaskBuildRequest {
@@ -47,6 +56,9 @@ pub struct RetryBuildRequest {
     /// The list of changed files in the hybrid path contract used by Orion.
     pub changes: Vec<Status<ProjectRelativePath>>,
     pub targets: Option<Vec<String>>,
+    /// Scheduling class of the retried build.
+    #[serde(default)]
+    pub priority: BuildPriority,
 }
 
 /// Result of a task build operation containing status and metadata. Used by Orion-Server
@@ -9,6 +9,7 @@ use utoipa::ToSchema;
 pub enum TaskPhase {
     DownloadingSource,
     RunningBuild,
+    RunningTests,
 }
 
 /// Slash-separated relative path used in Buck2 payloads.
@@ -138,6 +139,91 @@ pub struct LogErrorResponse {
     pub message: String,
 }
 
+/// Outcome of the latest build of a task, as reported by `/v2/latest_build_result/{task_id}`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub enum BuildStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Outcome of a single test reported by `buck2 test`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Skip,
+    Omitted,
+    Timeout,
+    Fatal,
+}
+
+impl TestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestStatus::Pass => "pass",
+            TestStatus::Fail => "fail",
+            TestStatus::Skip => "skip",
+            TestStatus::Omitted => "omitted",
+            TestStatus::Timeout => "timeout",
+            TestStatus::Fatal => "fatal",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pass" => Some(TestStatus::Pass),
+            "fail" => Some(TestStatus::Fail),
+            "skip" => Some(TestStatus::Skip),
+            "omitted" => Some(TestStatus::Omitted),
+            "timeout" => Some(TestStatus::Timeout),
+            "fatal" => Some(TestStatus::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Whether the test makes the test run fail.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            TestStatus::Fail | TestStatus::Timeout | TestStatus::Fatal
+        )
+    }
+}
+
+/// Result of one test of a build, as stored by Orion-Server.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TestResultResponse {
+    pub id: String,
+    pub build_id: String,
+    /// Buck2 test target, e.g. `root//app:app-unittest`.
+    pub target: String,
+    /// Test case name; empty when buck2 only reported the target as a whole.
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: Option<i64>,
+    pub message: Option<String>,
+}
+
+/// Test counts of a build.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct TestSummary {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+/// Test results of a build, failures first.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BuildTestResultsResponse {
+    pub build_id: String,
+    pub summary: TestSummary,
+    pub results: Vec<TestResultResponse>,
+}
+
 /// Target status for buck2 build
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TargetStatusResponse {
@@ -151,3 +237,284 @@ pub struct TargetStatusResponse {
     pub action: String,
     pub status: String,
 }
+
+/// Build history of a target that both passed and failed on the same commit.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct FlakyTarget {
+    /// Repo path the target was built in.
+    pub repo: String,
+    /// Target label, `package:name`, as reported in target statuses.
+    pub target: String,
+    /// Commits (build tasks) that reported a status for the target.
+    pub builds: usize,
+    /// Commits on which the target's last status was a failure.
+    pub failures: usize,
+    /// Commits on which a failed build was followed by a `build_retry` that rebuilt the
+    /// target and passed.
+    pub flaky_commits: usize,
+    /// Failed builds of those commits that a retry turned into a pass.
+    pub passed_on_retry: usize,
+    /// Share of the commits the target was built on that were flaky.
+    pub flakiness: f64,
+    /// RFC 3339 time the most recent of those passing retries finished.
+    pub last_flip_at: Option<String>,
+    /// Whether a quarantine of the repo path covers the target.
+    pub quarantined: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FlakyTargetQuery {
+    /// Only targets built in this repo path or below it.
+    pub path: Option<String>,
+}
+
+/// How a target came to be quarantined.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineSource {
+    Manual,
+    /// Added by Orion-Server after the target crossed the flakiness threshold.
+    Auto,
+}
+
+impl QuarantineSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuarantineSource::Manual => "manual",
+            QuarantineSource::Auto => "auto",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "manual" => Some(QuarantineSource::Manual),
+            "auto" => Some(QuarantineSource::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// A target whose failures the CI status gate reports but ignores for builds of
+/// `path` and the paths below it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct TargetQuarantine {
+    pub id: String,
+    pub path: String,
+    /// Target label, `package:name`.
+    pub target: String,
+    pub reason: Option<String>,
+    pub source: QuarantineSource,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuarantineTargetRequest {
+    pub path: String,
+    pub target: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct QuarantineQuery {
+    /// Only quarantines that apply to builds of this path.
+    pub path: Option<String>,
+}
+
+/// Scheduling class of a build. Queued builds of a more urgent class are
+/// dispatched first; variants are ordered from most to least urgent.
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildPriority {
+    /// Builds gating a merge queue entry.
+    MergeQueue,
+    /// Builds of a change list.
+    #[default]
+    ChangeList,
+    /// Periodic builds started by a build schedule.
+    Scheduled,
+}
+
+impl BuildPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BuildPriority::MergeQueue => "merge_queue",
+            BuildPriority::ChangeList => "change_list",
+            BuildPriority::Scheduled => "scheduled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "merge_queue" => Some(BuildPriority::MergeQueue),
+            "change_list" => Some(BuildPriority::ChangeList),
+            "scheduled" => Some(BuildPriority::Scheduled),
+            _ => None,
+        }
+    }
+}
+
+/// Resources and labels a worker advertises when it registers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct WorkerCapabilities {
+    /// Free-form labels set by the operator, e.g. `large` or `gpu`.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub cpu_cores: Option<u32>,
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// Operating system as reported by `std::env::consts::OS`, e.g. `linux`.
+    #[serde(default)]
+    pub os: Option<String>,
+    /// Installed toolchains, e.g. `rust` or `go`.
+    #[serde(default)]
+    pub toolchains: Vec<String>,
+}
+
+/// What a build needs from the worker running it. Empty requirements match every worker.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct BuildRequirements {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub min_cpu_cores: Option<u32>,
+    #[serde(default)]
+    pub min_memory_mb: Option<u64>,
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub toolchains: Vec<String>,
+}
+
+impl BuildRequirements {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Combine with `other`: labels and toolchains are unioned, minimums take the larger
+    /// value, and an `os` already set wins.
+    pub fn merge(&mut self, other: &BuildRequirements) {
+        for label in &other.labels {
+            if !self.labels.contains(label) {
+                self.labels.push(label.clone());
+            }
+        }
+        for toolchain in &other.toolchains {
+            if !self.toolchains.contains(toolchain) {
+                self.toolchains.push(toolchain.clone());
+            }
+        }
+        self.min_cpu_cores = self.min_cpu_cores.max(other.min_cpu_cores);
+        self.min_memory_mb = self.min_memory_mb.max(other.min_memory_mb);
+        if self.os.is_none() {
+            self.os = other.os.clone();
+        }
+    }
+
+    /// Requirements `capabilities` does not meet, described for logs and API errors.
+    /// A worker that does not report a resource does not meet a minimum on it.
+    pub fn unmet_by(&self, capabilities: &WorkerCapabilities) -> Vec<String> {
+        let mut unmet = Vec::new();
+        for label in &self.labels {
+            if !capabilities.labels.contains(label) {
+                unmet.push(format!("label `{label}`"));
+            }
+        }
+        for toolchain in &self.toolchains {
+            if !capabilities.toolchains.contains(toolchain) {
+                unmet.push(format!("toolchain `{toolchain}`"));
+            }
+        }
+        if let Some(min) = self.min_cpu_cores
+            && capabilities.cpu_cores.is_none_or(|cores| cores < min)
+        {
+            unmet.push(format!("at least {min} CPU cores"));
+        }
+        if let Some(min) = self.min_memory_mb
+            && capabilities.memory_mb.is_none_or(|memory| memory < min)
+        {
+            unmet.push(format!("at least {min} MB memory"));
+        }
+        if let Some(os) = &self.os
+            && !capabilities
+                .os
+                .as_deref()
+                .is_some_and(|worker_os| worker_os.eq_ignore_ascii_case(os))
+        {
+            unmet.push(format!("os `{os}`"));
+        }
+        unmet
+    }
+
+    pub fn is_satisfied_by(&self, capabilities: &WorkerCapabilities) -> bool {
+        self.unmet_by(capabilities).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker() -> WorkerCapabilities {
+        WorkerCapabilities {
+            labels: vec!["large".to_string()],
+            cpu_cores: Some(32),
+            memory_mb: Some(65536),
+            os: Some("linux".to_string()),
+            toolchains: vec!["rust".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_empty_requirements_match_any_worker() {
+        let requirements = BuildRequirements::default();
+        assert!(requirements.is_empty());
+        assert!(requirements.is_satisfied_by(&WorkerCapabilities::default()));
+    }
+
+    #[test]
+    fn test_unmet_requirements_are_listed() {
+        let requirements = BuildRequirements {
+            labels: vec!["large".to_string(), "gpu".to_string()],
+            min_cpu_cores: Some(16),
+            min_memory_mb: Some(131072),
+            os: Some("Linux".to_string()),
+            toolchains: vec!["go".to_string()],
+        };
+        assert_eq!(
+            requirements.unmet_by(&worker()),
+            vec![
+                "label `gpu`".to_string(),
+                "toolchain `go`".to_string(),
+                "at least 131072 MB memory".to_string(),
+            ]
+        );
+        assert_eq!(
+            requirements.unmet_by(&WorkerCapabilities::default()).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_merge_unions_lists_and_keeps_larger_minimums() {
+        let mut requirements = BuildRequirements {
+            labels: vec!["large".to_string()],
+            min_cpu_cores: Some(8),
+            ..Default::default()
+        };
+        requirements.merge(&BuildRequirements {
+            labels: vec!["large".to_string(), "gpu".to_string()],
+            min_cpu_cores: Some(4),
+            min_memory_mb: Some(1024),
+            os: Some("linux".to_string()),
+            toolchains: vec![],
+        });
+        assert_eq!(requirements.labels, vec!["large", "gpu"]);
+        assert_eq!(requirements.min_cpu_cores, Some(8));
+        assert_eq!(requirements.min_memory_mb, Some(1024));
+        assert_eq!(requirements.os.as_deref(), Some("linux"));
+    }
+}
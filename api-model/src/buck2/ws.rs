@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::buck2::{
     status::Status,
-    types::{ProjectRelativePath, TaskPhase},
+    types::{ProjectRelativePath, TaskPhase, TestStatus, WorkerCapabilities},
 };
 
 /// Message protocol for WebSocket communication between worker and server.
@@ -30,11 +30,21 @@ pub enum WSMessage {
         // targets: Vec<Target>,
     },
 
+    /// Ask the worker to stop a running build. The worker kills buck2, releases its mounts and
+    /// answers with [`WSMessage::TaskBuildCancelled`].
+    TaskCancel {
+        build_id: String,
+        reason: String,
+    },
+
     // Worker -> Server messages
     Register {
         id: String,
         hostname: String,
         orion_version: String,
+        /// Older workers do not send capabilities; they only match builds without requirements.
+        #[serde(default)]
+        capabilities: WorkerCapabilities,
     },
 
     // Sent when a task is in the build process and its execution phase changes.
@@ -70,12 +80,33 @@ pub enum WSMessage {
         message: String,
     },
 
+    /// Terminal report for a build stopped by [`WSMessage::TaskCancel`].
+    TaskBuildCancelled {
+        build_id: String,
+        message: String,
+    },
+
+    /// Batch of per-test results parsed from `buck2 test` output.
+    TestResultBatch {
+        build_id: String,
+        results: Vec<WSTestResult>,
+    },
+
     /// Batch of target build status updates for real-time build progress tracking.
     TargetBuildStatusBatch {
         events: Vec<WSTargetBuildStatusEvent>,
     },
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct WSTestResult {
+    pub target: String,
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: Option<u64>,
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WSBuildContext {
     pub task_id: String,
@@ -146,6 +146,15 @@ impl AdminApplicationService {
         _resource_type: callisto::sea_orm_active_enums::ResourceTypeEnum,
         _resource_id: &str,
         required_permission: PermissionEnum,
+    ) -> Result<bool, MegaError> {
+        self.bot_has_scope(bot_id, required_permission).await
+    }
+
+    /// Whether the bot is enabled, installed somewhere, and its scope covers `required_permission`.
+    pub async fn bot_has_scope(
+        &self,
+        bot_id: i64,
+        required_permission: PermissionEnum,
     ) -> Result<bool, MegaError> {
         let bots_storage = self.ctx.storage().bots_storage();
 
@@ -1,6 +1,8 @@
 pub mod bot;
 pub mod group;
 pub mod permissions;
+pub mod policy;
 
 pub use group::EffectiveResourcePermission;
 pub use permissions::ADMIN_FILE;
+pub use policy::POLICY_FILE;
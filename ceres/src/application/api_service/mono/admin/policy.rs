@@ -0,0 +1,727 @@
+//! Directory-scoped Cedar policies committed in the monorepo.
+//!
+//! Access to a path is decided by the built-in `mega_policies.cedar` together with files found
+//! on the main branch in every directory from the root down to that path:
+//! - `.mega_cedar.json` declares entities (users, groups, repositories); deeper files can add
+//!   entities but entities declared closer to the root win
+//! - `.mega_policies.cedar` adds Cedar policies that only apply at and below its directory;
+//!   a `forbid` from any level cannot be lifted by a deeper file
+//!
+//! A clone or fetch of a directory also needs the policies below it to allow the pull, and a
+//! push is checked against the policies of every directory it changes.
+//!
+//! The collected files are cached in Redis under the root main commit, so any new commit on
+//! main makes the next lookup read the tree again.
+
+use std::collections::{BTreeMap, HashMap};
+
+use common::{errors::MegaError, utils::ZERO_ID};
+use git_internal::internal::object::tree::{Tree, TreeItemMode};
+use jupiter::{redis::AsyncCommands, storage::Storage, utils::converter::FromMegaModel};
+use saturn::{
+    ActionEnum,
+    context::{CedarContext, PolicyDecision, SaturnContextError},
+    entitystore::EntityStore,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::application::api_service::{
+    cache::GitObjectCache,
+    mono::{admin::ADMIN_FILE, context::AdminApplicationService},
+};
+
+/// Cedar policy file name, valid in any directory of the monorepo.
+pub const POLICY_FILE: &str = ".mega_policies.cedar";
+
+/// Cache TTL for collected policy files (1 hour); keys are per commit so this only bounds memory.
+const POLICY_CACHE_TTL: u64 = 3600;
+
+/// Redis cache key segment for collected policy files.
+const POLICY_CACHE_KEY_SEGMENT: &str = "cedar:path-policy";
+
+/// Redis cache key segment for the directories with policy files below a path.
+const POLICY_DIRS_CACHE_KEY_SEGMENT: &str = "cedar:policy-dirs";
+
+/// Most directories read to find the policy files below a path.
+const POLICY_DIR_TREE_BUDGET: usize = 50_000;
+
+/// Entity and policy files that apply to one monorepo path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PathPolicy {
+    pub entities: EntityStore,
+    /// `(file path, content)` of every policy file, ordered from root to leaf.
+    pub policy_files: Vec<(String, String)>,
+}
+
+impl PathPolicy {
+    pub fn into_context(self) -> Result<CedarContext, MegaError> {
+        CedarContext::with_policy_files(self.entities, &self.policy_files)
+            .map_err(|e| MegaError::Other(format!("Invalid Cedar policy: {e}")))
+    }
+}
+
+/// Load the policy for `path` at the current main commit, from cache when possible.
+pub async fn load_path_policy(
+    storage: &Storage,
+    cache: &GitObjectCache,
+    path: &str,
+) -> Result<PathPolicy, MegaError> {
+    match MainPolicies::load(storage, Some(cache)).await? {
+        Some(policies) => policies.path_policy(path).await,
+        None => Ok(PathPolicy::default()),
+    }
+}
+
+/// The entity and policy files of one main commit, cached in Redis per commit when a cache
+/// is given.
+pub struct MainPolicies<'a> {
+    storage: &'a Storage,
+    cache: Option<&'a GitObjectCache>,
+    root_commit_hash: String,
+    root_tree_hash: String,
+}
+
+impl<'a> MainPolicies<'a> {
+    /// The policies of the current main commit, `None` while main has no commit.
+    pub async fn load(
+        storage: &'a Storage,
+        cache: Option<&'a GitObjectCache>,
+    ) -> Result<Option<Self>, MegaError> {
+        let Some(root_ref) = storage.mono_storage().get_main_ref("/").await? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            storage,
+            cache,
+            root_commit_hash: root_ref.ref_commit_hash,
+            root_tree_hash: root_ref.ref_tree_hash,
+        }))
+    }
+
+    /// The entity and policy files from the root down to `path`.
+    pub async fn path_policy(&self, path: &str) -> Result<PathPolicy, MegaError> {
+        let path = normalize_path(path);
+        self.cached(
+            POLICY_CACHE_KEY_SEGMENT,
+            &path,
+            collect_path_policy(self.storage, &self.root_tree_hash, &path),
+        )
+        .await
+    }
+
+    /// Directories strictly below `path` that hold entity or policy files, ordered by path.
+    ///
+    /// Fails with [`MegaError::Forbidden`] when `path` has more than
+    /// [`POLICY_DIR_TREE_BUDGET`] directories, as their policies cannot all be checked.
+    pub async fn policy_dirs_below(&self, path: &str) -> Result<Vec<String>, MegaError> {
+        let path = normalize_path(path);
+        self.cached(
+            POLICY_DIRS_CACHE_KEY_SEGMENT,
+            &path,
+            collect_policy_dirs_below(
+                self.storage,
+                &self.root_tree_hash,
+                &path,
+                POLICY_DIR_TREE_BUDGET,
+            ),
+        )
+        .await
+    }
+
+    /// Check that `username` may perform `action` on `path`.
+    pub async fn authorize(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<(), MegaError> {
+        let context = self.path_policy(path).await?.into_context()?;
+        check_path(&context, username, action, path)
+    }
+
+    /// Check that `username` may perform `action` on `path` and on everything below it: every
+    /// directory below `path` with its own entity or policy files, and every repository
+    /// entity declared below `path`, must allow it too.
+    pub async fn authorize_subtree(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<(), MegaError> {
+        let path = normalize_path(path);
+        let dirs = std::iter::once(path.clone()).chain(self.policy_dirs_below(&path).await?);
+        for dir in dirs {
+            let policy = self.path_policy(&dir).await?;
+            let paths: Vec<String> = std::iter::once(dir.clone())
+                .chain(policy.entities.repository_paths_below(&dir))
+                .collect();
+            let context = policy.into_context()?;
+            for path in &paths {
+                check_path(&context, username, action, path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `username` may perform `action` on each of `paths`, which all lie at or
+    /// below `base`. Each path is checked against the files of its own directory chain.
+    pub async fn authorize_paths(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        base: &str,
+        paths: &[String],
+    ) -> Result<(), MegaError> {
+        let base = normalize_path(base);
+        let policy_dirs = self.policy_dirs_below(&base).await?;
+        // paths share the policy of the deepest directory with files that holds them
+        let mut by_policy_dir: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for path in paths {
+            let policy_dir = policy_dirs
+                .iter()
+                .rev()
+                .find(|dir| path_is_under(path, dir))
+                .map_or(base.as_str(), String::as_str);
+            by_policy_dir.entry(policy_dir).or_default().push(path);
+        }
+        for (dir, paths) in by_policy_dir {
+            let context = self.path_policy(dir).await?.into_context()?;
+            for path in paths {
+                check_path(&context, username, action, path)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn cached<T>(
+        &self,
+        segment: &str,
+        path: &str,
+        compute: impl Future<Output = Result<T, MegaError>>,
+    ) -> Result<T, MegaError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let Some(cache) = self.cache else {
+            return compute.await;
+        };
+        let key = format!(
+            "{}:{}:{}:{}",
+            cache.prefix, segment, self.root_commit_hash, path
+        );
+        let mut conn = cache.connection.clone();
+
+        match conn.get::<_, Option<String>>(&key).await {
+            Ok(Some(json)) => match serde_json::from_str(&json) {
+                Ok(value) => return Ok(value),
+                Err(e) => tracing::warn!("Discarding unreadable policy cache {key}: {e}"),
+            },
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read policy cache: {e}"),
+        }
+
+        let value = compute.await?;
+        match serde_json::to_string(&value) {
+            Ok(json) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(&key, json, POLICY_CACHE_TTL).await {
+                    tracing::warn!("Failed to write policy cache: {e}");
+                }
+            }
+            Err(e) => tracing::warn!("Failed to serialize policy for cache: {e}"),
+        }
+        Ok(value)
+    }
+}
+
+/// Denials are [`MegaError::Unauthorized`] for anonymous users and [`MegaError::Forbidden`]
+/// otherwise.
+fn check_path(
+    context: &CedarContext,
+    username: Option<&str>,
+    action: ActionEnum,
+    path: &str,
+) -> Result<(), MegaError> {
+    match context.is_authorized_for_path(username, action, path) {
+        Ok(()) => Ok(()),
+        Err(SaturnContextError::AuthDenied(_)) => {
+            let msg = format!("{action} is not allowed on {path}");
+            Err(match username {
+                Some(_) => MegaError::Forbidden(msg),
+                None => MegaError::Unauthorized(msg),
+            })
+        }
+        Err(e @ SaturnContextError::Request(_)) => Err(MegaError::BadRequest(e.to_string())),
+    }
+}
+
+/// Walk the main tree from the root to `path`, collecting entity and policy files on the way.
+/// Directories that do not exist yet (e.g. the target of a first push) end the walk.
+async fn collect_path_policy(
+    storage: &Storage,
+    root_tree_hash: &str,
+    path: &str,
+) -> Result<PathPolicy, MegaError> {
+    let mono_storage = storage.mono_storage();
+    let mut policy = PathPolicy::default();
+    let mut tree_hash = root_tree_hash.to_owned();
+    let mut dir = String::new();
+    let mut components = path.split('/').filter(|c| !c.is_empty());
+
+    loop {
+        let Some(model) = mono_storage.get_tree_by_hash(&tree_hash).await? else {
+            break;
+        };
+        let tree = Tree::from_mega_model(model);
+
+        for item in tree
+            .tree_items
+            .iter()
+            .filter(|item| item.mode == TreeItemMode::Blob)
+        {
+            if item.name != ADMIN_FILE && item.name != POLICY_FILE {
+                continue;
+            }
+            let file_path = format!("{dir}/{}", item.name);
+            let bytes = storage
+                .git_service
+                .get_object_as_bytes(&item.id.to_string())
+                .await?;
+            if item.name == ADMIN_FILE {
+                let entities: EntityStore = serde_json::from_slice(&bytes)
+                    .map_err(|e| MegaError::Other(format!("Invalid {file_path}: {e}")))?;
+                policy.entities.merge_nested(entities);
+            } else {
+                let content = String::from_utf8(bytes)
+                    .map_err(|e| MegaError::Other(format!("Invalid {file_path}: {e}")))?;
+                policy.policy_files.push((file_path, content));
+            }
+        }
+
+        let Some(name) = components.next() else {
+            break;
+        };
+        match tree
+            .tree_items
+            .iter()
+            .find(|item| item.mode == TreeItemMode::Tree && item.name == name)
+        {
+            Some(item) => {
+                tree_hash = item.id.to_string();
+                dir = format!("{dir}/{name}");
+            }
+            None => break,
+        }
+    }
+    Ok(policy)
+}
+
+/// Walk the main tree below `path` level by level, collecting the directories that hold an
+/// entity or policy file. Reading more than `budget` trees fails closed.
+async fn collect_policy_dirs_below(
+    storage: &Storage,
+    root_tree_hash: &str,
+    path: &str,
+    budget: usize,
+) -> Result<Vec<String>, MegaError> {
+    let mono_storage = storage.mono_storage();
+    let Some(tree_hash) = tree_hash_at(storage, root_tree_hash, path).await? else {
+        return Ok(vec![]);
+    };
+    let base = path.trim_end_matches('/');
+    let mut dirs = vec![];
+    let mut level = vec![(base.to_owned(), tree_hash)];
+    let mut read = 0;
+
+    while !level.is_empty() {
+        read += level.len();
+        if read > budget {
+            return Err(MegaError::Forbidden(format!(
+                "{path} has more than {budget} directories to check policies for, \
+                 use a subdirectory instead"
+            )));
+        }
+        let hashes: Vec<String> = level.iter().map(|(_, hash)| hash.clone()).collect();
+        let trees: HashMap<String, Tree> = mono_storage
+            .get_trees_by_hashes(hashes)
+            .await?
+            .into_iter()
+            .map(|model| {
+                let tree = Tree::from_mega_model(model);
+                (tree.id.to_string(), tree)
+            })
+            .collect();
+
+        let mut next = vec![];
+        for (dir, hash) in level {
+            let tree = trees
+                .get(&hash)
+                .ok_or_else(|| MegaError::NotFound(format!("Tree {hash} not found")))?;
+            for item in &tree.tree_items {
+                match item.mode {
+                    TreeItemMode::Tree => {
+                        next.push((format!("{dir}/{}", item.name), item.id.to_string()));
+                    }
+                    TreeItemMode::Blob
+                        if dir != base && (item.name == ADMIN_FILE || item.name == POLICY_FILE) =>
+                    {
+                        dirs.push(dir.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        level = next;
+    }
+    dirs.sort();
+    dirs.dedup();
+    Ok(dirs)
+}
+
+/// Hash of the main tree at `path`, `None` when the directory does not exist.
+async fn tree_hash_at(
+    storage: &Storage,
+    root_tree_hash: &str,
+    path: &str,
+) -> Result<Option<String>, MegaError> {
+    let mono_storage = storage.mono_storage();
+    let mut tree_hash = root_tree_hash.to_owned();
+    for name in path.split('/').filter(|c| !c.is_empty()) {
+        let Some(model) = mono_storage.get_tree_by_hash(&tree_hash).await? else {
+            return Ok(None);
+        };
+        match Tree::from_mega_model(model)
+            .tree_items
+            .into_iter()
+            .find(|item| item.mode == TreeItemMode::Tree && item.name == name)
+        {
+            Some(item) => tree_hash = item.id.to_string(),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(tree_hash))
+}
+
+/// Directories below `base` whose entries differ between two commits of `base`, including
+/// the directories of added and removed subtrees. [`ZERO_ID`] is an empty tree.
+pub async fn changed_dirs(
+    storage: &Storage,
+    base: &str,
+    old_commit: &str,
+    new_commit: &str,
+) -> Result<Vec<String>, MegaError> {
+    let commit_tree = |hash: &str| {
+        let hash = hash.to_owned();
+        async move {
+            if hash == ZERO_ID {
+                return Ok(None);
+            }
+            storage
+                .mono_storage()
+                .get_commit_by_hash(&hash)
+                .await?
+                .map(|commit| Some(commit.tree))
+                .ok_or_else(|| MegaError::NotFound(format!("Commit {hash} not found")))
+        }
+    };
+    let base = normalize_path(base);
+    let mut dirs = vec![];
+    let mut pending = vec![(
+        base.trim_end_matches('/').to_owned(),
+        commit_tree(old_commit).await?,
+        commit_tree(new_commit).await?,
+    )];
+    while let Some((dir, old, new)) = pending.pop() {
+        if old == new {
+            continue;
+        }
+        let old = entries_of(storage, old.as_deref()).await?;
+        let new = entries_of(storage, new.as_deref()).await?;
+        let mut changed = false;
+        for name in old
+            .keys()
+            .chain(new.keys().filter(|name| !old.contains_key(*name)))
+        {
+            let (old_item, new_item) = (old.get(name), new.get(name));
+            if old_item == new_item {
+                continue;
+            }
+            let subtree = |item: Option<&(TreeItemMode, String)>| {
+                item.filter(|(mode, _)| *mode == TreeItemMode::Tree)
+                    .map(|(_, id)| id.clone())
+            };
+            let (old_tree, new_tree) = (subtree(old_item), subtree(new_item));
+            if old_tree.is_some() || new_tree.is_some() {
+                pending.push((format!("{dir}/{name}"), old_tree, new_tree));
+            }
+            // a subtree replacing a file, or the other way round, also changes this directory
+            changed |= old_item.is_some_and(|(mode, _)| *mode != TreeItemMode::Tree)
+                || new_item.is_some_and(|(mode, _)| *mode != TreeItemMode::Tree);
+        }
+        if changed {
+            dirs.push(normalize_path(&dir));
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Entries of a stored tree by name, empty for `None`.
+async fn entries_of(
+    storage: &Storage,
+    tree_hash: Option<&str>,
+) -> Result<HashMap<String, (TreeItemMode, String)>, MegaError> {
+    let Some(tree_hash) = tree_hash else {
+        return Ok(HashMap::new());
+    };
+    let model = storage
+        .mono_storage()
+        .get_tree_by_hash(tree_hash)
+        .await?
+        .ok_or_else(|| MegaError::NotFound(format!("Tree {tree_hash} not found")))?;
+    Ok(Tree::from_mega_model(model)
+        .tree_items
+        .into_iter()
+        .map(|item| (item.name, (item.mode, item.id.to_string())))
+        .collect())
+}
+
+/// Whether `path` is `dir` itself or lies below it.
+fn path_is_under(path: &str, dir: &str) -> bool {
+    dir == "/"
+        || path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn normalize_path(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+impl AdminApplicationService {
+    /// Check that `username` (anonymous when `None`) may perform `action` on `path`.
+    ///
+    /// Denials are [`MegaError::Unauthorized`] for anonymous users and [`MegaError::Forbidden`]
+    /// otherwise.
+    pub async fn authorize_path(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<(), MegaError> {
+        let context = load_path_policy(self.ctx.storage(), self.ctx.git_object_cache(), path)
+            .await?
+            .into_context()?;
+        check_path(&context, username, action, path)
+    }
+
+    /// Evaluate `action` on `path` without enforcing it and report which policies decided.
+    pub async fn explain_path_policy(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<PolicyDecision, MegaError> {
+        let context = load_path_policy(self.ctx.storage(), self.ctx.git_object_cache(), path)
+            .await?
+            .into_context()?;
+        context
+            .explain_for_path(username, action, path)
+            .map_err(|e| MegaError::BadRequest(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use git_internal::{
+        hash::ObjectHash,
+        internal::object::{blob::Blob, commit::Commit, tree::TreeItem},
+    };
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Declares `/project/secret` a private repository that only alice may read.
+    const SECRET_ENTITIES: &str = r#"{
+        "users": {
+            "User::\"alice\"": {
+                "euid": "User::\"alice\"",
+                "parents": ["UserGroup::\"secret_readers\""]
+            }
+        },
+        "repos": {
+            "Repository::\"/project/secret\"": {
+                "euid": "Repository::\"/project/secret\"",
+                "is_private": true,
+                "admins": "UserGroup::\"secret_readers\"",
+                "maintainers": "UserGroup::\"secret_readers\"",
+                "readers": "UserGroup::\"secret_readers\"",
+                "parents": []
+            }
+        },
+        "user_groups": {
+            "UserGroup::\"secret_readers\"": {
+                "euid": "UserGroup::\"secret_readers\"",
+                "parents": []
+            }
+        },
+        "merge_requests": {},
+        "issues": {}
+    }"#;
+
+    async fn blob(storage: &Storage, content: &str) -> ObjectHash {
+        let id = storage
+            .git_service
+            .save_object_from_raw(Bytes::from(content.to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(id, Blob::from_content(content).id.to_string());
+        Blob::from_content(content).id
+    }
+
+    async fn tree(storage: &Storage, items: Vec<(TreeItemMode, ObjectHash, &str)>) -> ObjectHash {
+        let tree = Tree::from_tree_items(
+            items
+                .into_iter()
+                .map(|(mode, id, name)| TreeItem::new(mode, id, name.to_owned()))
+                .collect(),
+        )
+        .unwrap();
+        let id = tree.id;
+        storage
+            .mono_storage()
+            .save_mega_trees(vec![tree], ObjectHash::default(), None)
+            .await
+            .unwrap();
+        id
+    }
+
+    /// Stores `/project/{pub.txt, secret/{.mega_cedar.json, a.txt}}` as a root commit.
+    async fn commit(storage: &Storage, pub_txt: &str, secret_txt: &str) -> (ObjectHash, String) {
+        let secret = vec![
+            (
+                TreeItemMode::Blob,
+                blob(storage, SECRET_ENTITIES).await,
+                ADMIN_FILE,
+            ),
+            (TreeItemMode::Blob, blob(storage, secret_txt).await, "a.txt"),
+        ];
+        let secret = tree(storage, secret).await;
+        let project = vec![
+            (TreeItemMode::Blob, blob(storage, pub_txt).await, "pub.txt"),
+            (TreeItemMode::Tree, secret, "secret"),
+        ];
+        let project = tree(storage, project).await;
+        let root = tree(storage, vec![(TreeItemMode::Tree, project, "project")]).await;
+        let commit = Commit::from_tree_id(root, vec![], "test");
+        let hash = commit.id.to_string();
+        storage
+            .mono_storage()
+            .save_mega_commits(vec![commit], None)
+            .await
+            .unwrap();
+        (root, hash)
+    }
+
+    fn policies(storage: &Storage, root_tree: ObjectHash) -> MainPolicies<'_> {
+        MainPolicies {
+            storage,
+            cache: None,
+            root_commit_hash: String::new(),
+            root_tree_hash: root_tree.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clone_above_a_protected_directory_checks_its_policy() {
+        let temp_dir = tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let (root, _) = commit(&storage, "public", "secret").await;
+        let policies = policies(&storage, root);
+
+        assert_eq!(
+            policies.policy_dirs_below("/").await.unwrap(),
+            vec!["/project/secret"]
+        );
+        // the root alone does not restrict bob, but what a clone of it contains does
+        assert!(
+            policies
+                .authorize(Some("bob"), ActionEnum::PullRepo, "/")
+                .await
+                .is_ok()
+        );
+        for path in ["/", "/project", "/project/secret"] {
+            let err = policies
+                .authorize_subtree(Some("bob"), ActionEnum::PullRepo, path)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, MegaError::Forbidden(_)), "{path}: {err}");
+            assert!(
+                policies
+                    .authorize_subtree(Some("alice"), ActionEnum::PullRepo, path)
+                    .await
+                    .is_ok()
+            );
+        }
+        let err = policies
+            .authorize_subtree(None, ActionEnum::PullRepo, "/")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Unauthorized(_)));
+
+        let err = collect_policy_dirs_below(&storage, &root.to_string(), "/", 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn test_push_at_root_is_checked_per_changed_directory() {
+        let temp_dir = tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let (root, base) = commit(&storage, "public", "secret").await;
+        let (_, public_edit) = commit(&storage, "public v2", "secret").await;
+        let (_, secret_edit) = commit(&storage, "public", "secret v2").await;
+        let policies = policies(&storage, root);
+
+        let dirs = changed_dirs(&storage, "/", &base, &public_edit)
+            .await
+            .unwrap();
+        assert_eq!(dirs, vec!["/project"]);
+        assert!(
+            policies
+                .authorize_paths(Some("bob"), ActionEnum::PushRepo, "/", &dirs)
+                .await
+                .is_ok()
+        );
+
+        let dirs = changed_dirs(&storage, "/", &base, &secret_edit)
+            .await
+            .unwrap();
+        assert_eq!(dirs, vec!["/project/secret"]);
+        let err = policies
+            .authorize_paths(Some("bob"), ActionEnum::PushRepo, "/", &dirs)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)));
+        assert!(
+            policies
+                .authorize_paths(Some("alice"), ActionEnum::PushRepo, "/", &dirs)
+                .await
+                .is_ok()
+        );
+
+        // a first push adds every directory
+        let dirs = changed_dirs(&storage, "/", ZERO_ID, &base).await.unwrap();
+        assert_eq!(dirs, vec!["/project", "/project/secret"]);
+    }
+
+    #[test]
+    fn normalize_path_adds_root_and_trims_slashes() {
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path("project/a/"), "/project/a");
+        assert_eq!(normalize_path("/project/a"), "/project/a");
+    }
+}
@@ -20,13 +20,11 @@ use jupiter::utils::converter::FromMegaModel;
 use tracing::debug;
 
 use crate::{
-    application::{
-        api_service::mono::{
-            ClApplicationService,
-            types::{ApplyChangeContext, RefUpdate, TreeUpdateResult},
-        },
-        code_edit::utils as edit_utils,
+    application::api_service::mono::{
+        ClApplicationService,
+        types::{ApplyChangeContext, RefUpdate, TreeUpdateResult},
     },
+    merge_checker::merge_conflict_checker::merge_conflicts,
     model::change_list::{ClDiffFile, UpdateBranchStatusRes},
 };
 
@@ -602,44 +600,15 @@ impl ClApplicationService {
         Ok(new_head)
     }
 
-    /// Detect file-level update conflicts between the CL changes and target head.
-    /// A conflict is reported if any file path modified by the CL is also changed
-    /// between `from_hash` and `target_head`.
+    /// Detect file-level update conflicts between the CL changes and target head,
+    /// see [`merge_conflicts`].
     pub(crate) async fn detect_update_conflicts(
         &self,
         cl: &mega_cl::Model,
         target_head: &str,
     ) -> Result<Vec<String>, GitError> {
-        let old_blobs = self
-            .get_commit_blobs(&cl.from_hash)
-            .await
-            .map_err(|e| GitError::CustomError(e.to_string()))?;
-        let new_blobs = self
-            .get_commit_blobs(&cl.to_hash)
-            .await
-            .map_err(|e| GitError::CustomError(e.to_string()))?;
-        // Keep conflict checks path-based so renames cover both old and new paths.
-        let cl_changed = edit_utils::cl_files_list(old_blobs.clone(), new_blobs.clone())
-            .await
-            .map_err(|e| GitError::CustomError(e.to_string()))?;
-
-        let target_blobs = self
-            .get_commit_blobs(target_head)
+        merge_conflicts(self.storage(), &cl.from_hash, &cl.to_hash, target_head)
             .await
-            .map_err(|e| GitError::CustomError(e.to_string()))?;
-        let base_vs_target = edit_utils::cl_files_list(old_blobs.clone(), target_blobs.clone())
-            .await
-            .map_err(|e| GitError::CustomError(e.to_string()))?;
-
-        let cl_paths: std::collections::HashSet<String> = cl_changed
-            .iter()
-            .map(|f| f.path().to_string_lossy().replace('\\', "/"))
-            .collect();
-        let target_paths: std::collections::HashSet<String> = base_vs_target
-            .iter()
-            .map(|f| f.path().to_string_lossy().replace('\\', "/"))
-            .collect();
-
-        Ok(cl_paths.intersection(&target_paths).cloned().collect())
+            .map_err(|e| GitError::CustomError(e.to_string()))
     }
 }
@@ -0,0 +1,218 @@
+//! External check runs reported by bots against the CL head commit.
+
+use callisto::{
+    check_runs, mega_cl,
+    sea_orm_active_enums::{MergeStatusEnum, PermissionEnum},
+};
+use common::errors::MegaError;
+
+use crate::{
+    application::api_service::mono::ClApplicationService,
+    merge_checker::ConditionResult,
+    model::check_run::{CheckRunCondition, CheckRunRes, CreateCheckRunReq, UpdateCheckRunReq},
+};
+
+impl ClApplicationService {
+    /// Creates the named check run on the CL head, or overwrites the run of the same name that
+    /// the same bot already reported for that commit. Another bot's run is not overwritten.
+    pub async fn create_check_run(
+        &self,
+        link: &str,
+        bot_id: i64,
+        req: CreateCheckRunReq,
+    ) -> Result<CheckRunRes, MegaError> {
+        self.ensure_bot_can_report(bot_id).await?;
+        let cl = self.get_reportable_cl(link).await?;
+        if let Some(head_sha) = &req.head_sha
+            && head_sha != &cl.to_hash
+        {
+            return Err(MegaError::conflict(format!(
+                "Check run targets {head_sha}, but the head of CL {link} is {}",
+                cl.to_hash
+            )));
+        }
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(MegaError::bad_request("Check run name must not be empty"));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let run = check_runs::Model {
+            id: common::utils::generate_id(),
+            cl_link: cl.link,
+            commit_id: cl.to_hash,
+            name: name.to_string(),
+            bot_id,
+            status: req.status.to_string(),
+            summary: req.summary,
+            annotations: serde_json::to_string(&req.annotations)?,
+            created_at: now,
+            updated_at: now,
+        };
+        let saved = self.storage().cl_storage().save_check_run(run).await?;
+        Ok(saved.into())
+    }
+
+    /// Updates a check run; only the bot that created it may do so.
+    pub async fn update_check_run(
+        &self,
+        link: &str,
+        id: i64,
+        bot_id: i64,
+        req: UpdateCheckRunReq,
+    ) -> Result<CheckRunRes, MegaError> {
+        self.ensure_bot_can_report(bot_id).await?;
+        self.get_reportable_cl(link).await?;
+        let cl_storage = self.storage().cl_storage();
+        let mut run = cl_storage
+            .get_check_run(id)
+            .await?
+            .filter(|run| run.cl_link == link)
+            .ok_or_else(|| MegaError::NotFound(format!("Check run {id} not found on CL {link}")))?;
+        if run.bot_id != bot_id {
+            return Err(MegaError::forbidden(format!(
+                "Check run {id} was created by another bot"
+            )));
+        }
+
+        if let Some(status) = req.status {
+            run.status = status.to_string();
+        }
+        if let Some(summary) = req.summary {
+            run.summary = summary;
+        }
+        if let Some(annotations) = req.annotations {
+            run.annotations = serde_json::to_string(&annotations)?;
+        }
+        let saved = cl_storage.save_check_run(run).await?;
+        Ok(saved.into())
+    }
+
+    /// Lists the check runs reported for the current head of the CL.
+    pub async fn list_check_runs(&self, link: &str) -> Result<Vec<CheckRunRes>, MegaError> {
+        let cl = self
+            .storage()
+            .cl_storage()
+            .get_cl(link)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("CL not found: {link}")))?;
+        let runs = self
+            .storage()
+            .cl_storage()
+            .list_check_runs(&cl.link, &cl.to_hash)
+            .await?;
+        Ok(runs.into_iter().map(Into::into).collect())
+    }
+
+    /// Check runs of the CL head merged with the check runs its path requires.
+    pub(crate) async fn check_run_conditions(
+        &self,
+        cl: &mega_cl::Model,
+    ) -> Result<Vec<CheckRunCondition>, MegaError> {
+        let cl_storage = self.storage().cl_storage();
+        let required = cl_storage.get_required_check_runs(&cl.path).await?;
+        let runs = cl_storage.list_check_runs(&cl.link, &cl.to_hash).await?;
+        Ok(merge_check_runs(runs, &required))
+    }
+
+    async fn ensure_bot_can_report(&self, bot_id: i64) -> Result<(), MegaError> {
+        if self
+            .admin()
+            .bot_has_scope(bot_id, PermissionEnum::Write)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(MegaError::forbidden(format!(
+                "Bot {bot_id} is not allowed to report check runs"
+            )))
+        }
+    }
+
+    async fn get_reportable_cl(&self, link: &str) -> Result<mega_cl::Model, MegaError> {
+        let cl = self
+            .storage()
+            .cl_storage()
+            .get_cl(link)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("CL not found: {link}")))?;
+        match cl.status {
+            MergeStatusEnum::Open | MergeStatusEnum::Draft => Ok(cl),
+            MergeStatusEnum::Merged | MergeStatusEnum::Closed => {
+                Err(MegaError::conflict(format!("CL {link} is no longer open")))
+            }
+        }
+    }
+}
+
+/// Reported runs first, then required runs that were never reported (as `PENDING`).
+fn merge_check_runs(runs: Vec<check_runs::Model>, required: &[String]) -> Vec<CheckRunCondition> {
+    let mut conditions: Vec<CheckRunCondition> = runs
+        .into_iter()
+        .map(|run| {
+            let run = CheckRunRes::from(run);
+            CheckRunCondition {
+                required: required.contains(&run.name),
+                name: run.name,
+                result: run.status,
+                summary: run.summary,
+            }
+        })
+        .collect();
+    for name in required {
+        if !conditions.iter().any(|c| &c.name == name) {
+            conditions.push(CheckRunCondition {
+                name: name.clone(),
+                required: true,
+                result: ConditionResult::PENDING,
+                summary: "Waiting for the check run to be reported".to_string(),
+            });
+        }
+    }
+    conditions
+}
+
+#[cfg(test)]
+mod tests {
+    use callisto::check_runs;
+
+    use super::merge_check_runs;
+    use crate::merge_checker::ConditionResult;
+
+    fn run(name: &str, status: &str) -> check_runs::Model {
+        let now = chrono::Utc::now().naive_utc();
+        check_runs::Model {
+            id: 1,
+            cl_link: "CL1".to_string(),
+            commit_id: "abc".to_string(),
+            name: name.to_string(),
+            bot_id: 7,
+            status: status.to_string(),
+            summary: String::new(),
+            annotations: "[]".to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_missing_required_runs_are_pending() {
+        let required = vec!["lint".to_string(), "e2e".to_string()];
+        let conditions = merge_check_runs(
+            vec![run("lint", "PASSED"), run("bench", "FAILED")],
+            &required,
+        );
+        let summary: Vec<(&str, bool, ConditionResult)> = conditions
+            .iter()
+            .map(|c| (c.name.as_str(), c.required, c.result.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("lint", true, ConditionResult::PASSED),
+                ("bench", false, ConditionResult::FAILED),
+                ("e2e", true, ConditionResult::PENDING),
+            ]
+        );
+    }
+}
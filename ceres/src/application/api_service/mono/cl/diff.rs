@@ -7,15 +7,12 @@ use std::{
 
 use api_model::common::Pagination;
 use common::errors::MegaError;
-use git_internal::{
-    DiffItem, diff::Diff as GitDiff, errors::GitError, hash::ObjectHash,
-    internal::object::tree::Tree,
-};
-use jupiter::utils::converter::FromMegaModel;
+use git_internal::{DiffItem, diff::Diff as GitDiff, errors::GitError, hash::ObjectHash};
 
 use crate::{
     application::api_service::{ApiHandler, mono::ClApplicationService},
     diff::tree_diff,
+    merge_checker::stored_commit_files,
     model::change_list::{ClDiffFile, ClFilesChangedItemSchema},
 };
 
@@ -402,6 +399,47 @@ impl ClApplicationService {
         }
     }
 
+    /// Files changed by a CL, or by every CL of a merge queue batch when `link` is a batch link.
+    ///
+    /// Build workers resolve the link of the build they run through this, so a batch build
+    /// sees the changes of all its CLs on top of main.
+    pub async fn get_cl_diff_files(&self, link: &str) -> Result<Vec<ClDiffFile>, MegaError> {
+        let cl_store = self.storage().cl_service.cl_store();
+        if let Some(cl) = cl_store.get_cl(link).await? {
+            return self.cl_model_diff_files(&cl).await;
+        }
+
+        let items = self
+            .storage()
+            .merge_queue_service
+            .get_batch_items(link)
+            .await?;
+        if items.is_empty() {
+            return Err(MegaError::NotFound(format!("CL {link} not found")));
+        }
+        let mut files: Vec<ClDiffFile> = Vec::new();
+        for item in items {
+            let cl = cl_store
+                .get_cl(&item.cl_link)
+                .await?
+                .ok_or_else(|| MegaError::NotFound(format!("CL {} not found", item.cl_link)))?;
+            for file in self.cl_model_diff_files(&cl).await? {
+                files.retain(|f| f.path() != file.path());
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+
+    async fn cl_model_diff_files(
+        &self,
+        cl: &callisto::mega_cl::Model,
+    ) -> Result<Vec<ClDiffFile>, MegaError> {
+        let old_files = self.get_commit_blobs(&cl.from_hash).await?;
+        let new_files = self.get_commit_blobs(&cl.to_hash).await?;
+        self.cl_files_list(old_files, new_files).await
+    }
+
     pub async fn get_sorted_changed_file_list(
         &self,
         cl_link: &str,
@@ -495,17 +533,14 @@ impl ClApplicationService {
         &self,
         commit_hash: &str,
     ) -> Result<Vec<(PathBuf, ObjectHash)>, MegaError> {
-        let mut res = vec![];
-        let mono_storage = self.storage().mono_storage();
-        let commit = mono_storage.get_commit_by_hash(commit_hash).await?;
-        if let Some(commit) = commit {
-            let tree = mono_storage.get_tree_by_hash(&commit.tree).await?;
-            if let Some(tree) = tree {
-                let tree: Tree = Tree::from_mega_model(tree);
-                res = self.git_ops().traverse_tree(tree).await?;
-            }
-        }
-        Ok(res)
+        // A commit that is not stored (such as `ZERO_ID`) has no files.
+        let files = stored_commit_files(self.storage(), commit_hash)
+            .await?
+            .unwrap_or_default();
+        Ok(files
+            .into_iter()
+            .map(|(path, id)| (PathBuf::from(path), id))
+            .collect())
     }
 }
 
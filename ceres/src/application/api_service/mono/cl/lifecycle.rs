@@ -72,7 +72,7 @@ impl ClApplicationService {
             .await?;
 
         if let Some(updated_model) = cl_storage.get_cl(&link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClReopened, &updated_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClReopened, &updated_model).await;
         }
         Ok(())
     }
@@ -102,7 +102,7 @@ impl ClApplicationService {
             .await?;
 
         if let Some(updated_model) = cl_storage.get_cl(&link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClClosed, &updated_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClClosed, &updated_model).await;
         }
         Ok(())
     }
@@ -121,7 +121,7 @@ impl ClApplicationService {
         if model.status == MergeStatusEnum::Open {
             self.merge_cl(username, model.clone()).await?;
             if let Some(updated_model) = cl_storage.get_cl(link).await? {
-                dispatch_cl_webhook(self.storage(), WebhookEvent::ClMerged, &updated_model);
+                dispatch_cl_webhook(self.storage(), WebhookEvent::ClMerged, &updated_model).await;
             }
         }
         Ok(())
@@ -143,7 +143,7 @@ impl ClApplicationService {
 
         self.merge_cl("system", model.clone()).await?;
         if let Some(updated_model) = cl_storage.get_cl(link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClMerged, &updated_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClMerged, &updated_model).await;
         }
         Ok(())
     }
@@ -163,7 +163,8 @@ impl ClApplicationService {
                     .into_iter()
                     .map(|m| m.into())
                     .collect();
-                MergeBoxRes::from_condition(check_res)
+                let check_runs = self.check_run_conditions(&cl).await?;
+                MergeBoxRes::from_condition(check_res, check_runs)
             }
             MergeStatusEnum::Draft | MergeStatusEnum::Merged | MergeStatusEnum::Closed => {
                 MergeBoxRes {
@@ -202,7 +203,7 @@ impl ClApplicationService {
         }
 
         if let Some(cl_model) = self.storage().cl_service.cl_store().get_cl(link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClCommentCreated, &cl_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClCommentCreated, &cl_model).await;
         }
         Ok(())
     }
@@ -214,7 +215,7 @@ impl ClApplicationService {
             .edit_title(link, content)
             .await?;
         if let Some(cl_model) = self.storage().cl_service.cl_store().get_cl(link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &cl_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &cl_model).await;
         }
         Ok(())
     }
@@ -257,7 +258,8 @@ impl ClApplicationService {
                     )
                     .await?;
                 if let Some(updated_model) = cl_storage.get_cl(link).await? {
-                    dispatch_cl_webhook(self.storage(), WebhookEvent::ClCreated, &updated_model);
+                    dispatch_cl_webhook(self.storage(), WebhookEvent::ClCreated, &updated_model)
+                        .await;
                 }
             }
             (MergeStatusEnum::Open, MergeStatusEnum::Draft) => {
@@ -275,7 +277,8 @@ impl ClApplicationService {
                     )
                     .await?;
                 if let Some(updated_model) = cl_storage.get_cl(link).await? {
-                    dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &updated_model);
+                    dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &updated_model)
+                        .await;
                 }
             }
             _ => {
@@ -294,7 +297,7 @@ impl ClApplicationService {
     ) -> Result<String, MegaError> {
         let new_head = self.update_branch(username, link).await?;
         if let Some(cl_model) = self.storage().cl_service.cl_store().get_cl(link).await? {
-            dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &cl_model);
+            dispatch_cl_webhook(self.storage(), WebhookEvent::ClUpdated, &cl_model).await;
         }
         Ok(new_head)
     }
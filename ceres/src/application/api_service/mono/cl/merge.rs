@@ -20,13 +20,17 @@ use crate::{
         ApiHandler,
         mono::{ClApplicationService, logic::MonoServiceLogic, types::TreeUpdateResult},
     },
-    merge_checker::CheckerRegistry,
+    merge_checker::{CheckerRegistry, ConditionResult},
 };
 
 impl ClApplicationService {
     // This function is intended to be called before merging a CL to ensure it meets all required checks.
     pub(crate) async fn ensure_cl_mergeable(&self, cl: &mega_cl::Model) -> Result<(), MegaError> {
-        let check_reg = CheckerRegistry::new(self.storage().clone().into(), cl.username.clone());
+        let check_reg = CheckerRegistry::new(
+            self.storage().clone().into(),
+            cl.username.clone(),
+            self.ctx.build_dispatch(),
+        );
         check_reg.run_checks(cl.clone().into()).await?;
 
         let required_check_types = self
@@ -48,12 +52,19 @@ impl ClApplicationService {
             .await?
             .into_iter()
             .filter(|result| {
-                result.status == "FAILED"
+                result.status != "PASSED"
                     && required_check_types
                         .iter()
                         .any(|required_type| required_type == &result.check_type_code)
             })
             .map(|result| format!("{:?}", result.check_type_code))
+            .chain(
+                self.check_run_conditions(cl)
+                    .await?
+                    .into_iter()
+                    .filter(|run| run.required && run.result != ConditionResult::PASSED)
+                    .map(|run| run.name),
+            )
             .collect::<Vec<_>>();
 
         if failed_checks.is_empty() {
@@ -129,10 +140,13 @@ impl ClApplicationService {
     ) -> Result<(), GitError> {
         let storage = self.storage().mono_storage();
 
-        let strategy =
-            crate::application::api_service::mono::cl_merge::resolve_merge_strategy(self, &cl)
-                .await
-                .map_err(|e| GitError::CustomError(e.to_string()))?;
+        let strategy = crate::application::api_service::mono::cl_merge::resolve_merge_strategy(
+            self.storage(),
+            &cl.from_hash,
+            &cl.path,
+        )
+        .await
+        .map_err(|e| GitError::CustomError(e.to_string()))?;
         tracing::info!(
             cl_link = %cl.link,
             cl_path = %cl.path,
@@ -5,11 +5,14 @@ use std::path::{Component, Path};
 use callisto::mega_cl;
 use common::{errors::MegaError, utils::ZERO_ID};
 use git_internal::{errors::GitError, internal::object::commit::Commit};
-use jupiter::utils::converter::FromMegaModel;
+use jupiter::{storage::Storage, utils::converter::FromMegaModel};
 
-use crate::application::api_service::{
-    mono::{ClApplicationService, GitOpsPort},
-    tree_ops,
+use crate::{
+    application::api_service::{
+        mono::{ClApplicationService, GitOpsPort},
+        tree_ops,
+    },
+    merge_checker::commit_files,
 };
 
 /// How a CL should be applied onto monorepo main.
@@ -145,39 +148,39 @@ pub async fn prepare_cl_path_for_merge(
     Ok(())
 }
 
+/// Picks how the CL will be applied; only needs storage so merge checks can predict it too.
 pub async fn resolve_merge_strategy(
-    cl_svc: &ClApplicationService,
-    cl: &mega_cl::Model,
+    storage: &Storage,
+    from_hash: &str,
+    path: &str,
 ) -> Result<ClMergeStrategy, MegaError> {
-    let git = cl_svc.git_ops();
-    if cl.from_hash == ZERO_ID {
+    if from_hash == ZERO_ID {
         return Ok(ClMergeStrategy::SubtreeReplace);
     }
 
-    if path_lacks_main_ref(git, &cl.path).await? {
+    if storage.mono_storage().get_main_ref(path).await?.is_none() {
         return Ok(ClMergeStrategy::SubtreeReplace);
     }
 
-    if is_gitkeep_baseline(cl_svc, &cl.from_hash).await? {
+    if is_gitkeep_baseline(storage, from_hash).await? {
         return Ok(ClMergeStrategy::SubtreeReplace);
     }
 
     Ok(ClMergeStrategy::FileDiff)
 }
 
-async fn is_gitkeep_baseline(
-    cl_svc: &ClApplicationService,
-    commit_hash: &str,
-) -> Result<bool, MegaError> {
-    let blobs = cl_svc.get_commit_blobs(commit_hash).await?;
-    if blobs.is_empty() {
-        return Ok(true);
-    }
-    Ok(blobs.len() == 1
-        && blobs[0]
-            .0
-            .file_name()
-            .is_some_and(|name| name == ".gitkeep"))
+async fn is_gitkeep_baseline(storage: &Storage, commit_hash: &str) -> Result<bool, MegaError> {
+    let files = match commit_files(storage, commit_hash).await {
+        Ok(files) => files,
+        Err(MegaError::NotFound(_)) => return Ok(true),
+        Err(e) => return Err(e),
+    };
+    let mut paths = files.keys();
+    Ok(match (paths.next(), paths.next()) {
+        (None, _) => true,
+        (Some(path), None) => path.rsplit('/').next() == Some(".gitkeep"),
+        _ => false,
+    })
 }
 
 /// Returns true when the final path segment is not yet present in the monorepo tree.
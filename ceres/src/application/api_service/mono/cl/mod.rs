@@ -1,8 +1,10 @@
 //! Change-list domain: merge, branch update, diff, queue.
 
 pub mod branch;
+pub mod check_run;
 pub mod diff;
 pub mod lifecycle;
 pub mod merge;
 pub mod merge_strategy;
+pub mod protection_rule;
 pub mod queue;
@@ -0,0 +1,137 @@
+//! Admin management of per-path protection rules, enforced by the BranchProtection checker.
+
+use callisto::path_protection_rules;
+use common::errors::MegaError;
+
+use crate::{
+    application::api_service::mono::{
+        ClApplicationService, MonoServiceLogic, cl_merge::ClMergeStrategy,
+    },
+    model::protection_rule::{CreateProtectionRuleReq, ProtectionRuleRes, UpdateProtectionRuleReq},
+};
+
+impl ClApplicationService {
+    /// Lists the protection rules on `path` or below it.
+    pub async fn list_protection_rules(
+        &self,
+        path: &str,
+    ) -> Result<Vec<ProtectionRuleRes>, MegaError> {
+        let path = if path.trim().is_empty() {
+            "/".to_string()
+        } else {
+            MonoServiceLogic::normalize_repo_path(path)?
+        };
+        let rules = self
+            .storage()
+            .cl_storage()
+            .list_protection_rules(&path)
+            .await?;
+        Ok(rules.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates the protection rule of a path; fails when the path already has one.
+    pub async fn create_protection_rule(
+        &self,
+        req: CreateProtectionRuleReq,
+    ) -> Result<ProtectionRuleRes, MegaError> {
+        let path = MonoServiceLogic::normalize_repo_path(&req.path)?;
+        let cl_storage = self.storage().cl_storage();
+        if cl_storage.get_protection_rule(&path).await?.is_some() {
+            return Err(MegaError::conflict(format!(
+                "Path {path} already has a protection rule"
+            )));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let rule = path_protection_rules::Model {
+            id: common::utils::generate_id(),
+            path: path.clone(),
+            allowed_merge_strategies: strategies_json(&req.allowed_merge_strategies)?,
+            required_approvals: approvals(req.required_approvals)?,
+            forbid_self_approval: req.forbid_self_approval,
+            require_linear_history: req.require_linear_history,
+            block_direct_push: req.block_direct_push,
+            created_at: now,
+            updated_at: now,
+        };
+        cl_storage.save_protection_rule(rule).await?;
+        self.get_protection_rule(&path).await
+    }
+
+    /// Updates the protection rule of a path.
+    pub async fn update_protection_rule(
+        &self,
+        req: UpdateProtectionRuleReq,
+    ) -> Result<ProtectionRuleRes, MegaError> {
+        let path = MonoServiceLogic::normalize_repo_path(&req.path)?;
+        let cl_storage = self.storage().cl_storage();
+        let mut rule = cl_storage
+            .get_protection_rule(&path)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("No protection rule on path {path}")))?;
+
+        if let Some(strategies) = req.allowed_merge_strategies {
+            rule.allowed_merge_strategies = strategies_json(&strategies)?;
+        }
+        if let Some(required_approvals) = req.required_approvals {
+            rule.required_approvals = approvals(required_approvals)?;
+        }
+        if let Some(forbid_self_approval) = req.forbid_self_approval {
+            rule.forbid_self_approval = forbid_self_approval;
+        }
+        if let Some(require_linear_history) = req.require_linear_history {
+            rule.require_linear_history = require_linear_history;
+        }
+        if let Some(block_direct_push) = req.block_direct_push {
+            rule.block_direct_push = block_direct_push;
+        }
+        cl_storage.save_protection_rule(rule).await?;
+        self.get_protection_rule(&path).await
+    }
+
+    async fn get_protection_rule(&self, path: &str) -> Result<ProtectionRuleRes, MegaError> {
+        self.storage()
+            .cl_storage()
+            .get_protection_rule(path)
+            .await?
+            .map(Into::into)
+            .ok_or_else(|| MegaError::NotFound(format!("No protection rule on path {path}")))
+    }
+}
+
+/// Stored form of the allowed merge strategies, rejecting unknown strategy names.
+fn strategies_json(strategies: &[String]) -> Result<String, MegaError> {
+    let known = [ClMergeStrategy::FileDiff, ClMergeStrategy::SubtreeReplace].map(|s| s.as_str());
+    if let Some(unknown) = strategies.iter().find(|s| !known.contains(&s.as_str())) {
+        return Err(MegaError::bad_request(format!(
+            "Unknown merge strategy {unknown}, expected one of {}",
+            known.join(", ")
+        )));
+    }
+    Ok(serde_json::to_string(strategies)?)
+}
+
+fn approvals(required_approvals: i32) -> Result<i32, MegaError> {
+    if required_approvals < 0 {
+        return Err(MegaError::bad_request(
+            "Required approvals must not be negative",
+        ));
+    }
+    Ok(required_approvals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{approvals, strategies_json};
+
+    #[test]
+    fn test_rule_fields_are_validated() {
+        assert_eq!(
+            strategies_json(&["file_diff".to_string()]).unwrap(),
+            r#"["file_diff"]"#
+        );
+        assert!(strategies_json(&["squash".to_string()]).is_err());
+        assert_eq!(approvals(2).unwrap(), 2);
+        assert!(approvals(-1).is_err());
+    }
+}
@@ -1,13 +1,31 @@
 //! Merge queue background processor for [`ClApplicationService`](super::service::ClApplicationService).
 
-use std::time::Duration;
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
 
-use callisto::sea_orm_active_enums::{MergeStatusEnum, QueueFailureTypeEnum, QueueStatusEnum};
+use api_model::buck2::types::TargetStatusResponse;
+use callisto::{
+    mega_cl,
+    sea_orm_active_enums::{MergeStatusEnum, QueueFailureTypeEnum, QueueStatusEnum},
+};
 use common::errors::MegaError;
 use tracing;
 
 use crate::{
-    application::api_service::mono::ClApplicationService,
+    application::{
+        api_service::mono::ClApplicationService,
+        build_trigger::{BuildTriggerService, SharedBuildDispatch, TriggerContext},
+        code_edit::utils as edit_utils,
+        webhook::{
+            MergeQueueItemPayload, WebhookEvent, WebhookEventPayload, dispatch_webhook_event,
+        },
+    },
+    merge_checker::{
+        ConditionResult,
+        ci_status_checker::{BuildReport, fetch_build, fetch_quarantined, target_label},
+    },
     model::merge_queue::{
         AddToQueueResponse, QueueItem, QueueListResponse, QueueStatsResponse, QueueStatus,
         QueueStatusResponse,
@@ -23,6 +41,15 @@ impl ClApplicationService {
     /// Error backoff interval in seconds after processing failure
     const ERROR_BACKOFF_SECS: u64 = 30;
 
+    /// Maximum number of CLs validated by a single speculative build
+    const MAX_BATCH_SIZE: u64 = 8;
+
+    /// Interval in seconds between polls of a running batch build
+    const BUILD_POLL_INTERVAL_SECS: u64 = 15;
+
+    /// Batch builds still running after this many seconds fail their CLs with a timeout
+    const BUILD_TIMEOUT_SECS: u64 = 60 * 60;
+
     /// Adds a CL to the merge queue and ensures the background processor is running.
     ///
     /// This method validates the CL status before adding to queue and automatically
@@ -57,6 +84,7 @@ impl ClApplicationService {
             .merge_queue_service
             .add_to_queue(cl_link)
             .await?;
+        dispatch_queue_webhook(self, WebhookEvent::MergeQueueAdded, &model, None).await;
 
         // Ensure the background processor is running
         self.ensure_merge_processor_running();
@@ -118,10 +146,16 @@ impl ClApplicationService {
     }
 
     pub async fn remove_from_merge_queue(&self, cl_link: &str) -> Result<bool, MegaError> {
-        self.storage()
+        let removed = self
+            .storage()
             .merge_queue_service
             .remove_from_queue(cl_link)
-            .await
+            .await?;
+        if removed {
+            self.dispatch_queue_webhook_for(WebhookEvent::MergeQueueRemoved, cl_link, None)
+                .await;
+        }
+        Ok(removed)
     }
 
     pub async fn get_merge_queue_list(&self) -> Result<QueueListResponse, MegaError> {
@@ -234,84 +268,305 @@ impl ClApplicationService {
         }
     }
 
-    /// Processes the next item in the merge queue.
+    /// Processes the next batch of the merge queue.
+    ///
+    /// Waiting CLs at the head of the queue that touch disjoint files of the same path are
+    /// grouped into one batch and validated by a single speculative build.
     ///
     /// # Returns
-    /// * `Ok(true)` - An item was processed (success or failure)
+    /// * `Ok(true)` - Items were processed (success or failure)
     /// * `Ok(false)` - No items to process
     /// * `Err(MegaError)` - System error occurred
     async fn process_next_queue_item(&self) -> Result<bool, MegaError> {
         let queue_service = &self.storage().merge_queue_service;
 
-        // Get next waiting item from queue
-        let next_item = queue_service.get_next_waiting_item().await?;
+        let items = queue_service
+            .get_waiting_items(Self::MAX_BATCH_SIZE)
+            .await?;
+        if items.is_empty() {
+            return Ok(false);
+        }
 
-        if let Some(item) = next_item {
-            let cl_link = item.cl_link.clone();
+        let mut cls = Vec::with_capacity(items.len());
+        let mut candidates = Vec::with_capacity(items.len());
+        for item in items {
+            let loaded = match self.load_queued_cl(&item.cl_link).await {
+                Ok(cl) => match self.get_sorted_changed_file_list(&cl.link, None).await {
+                    Ok(files) => Ok((cl, files)),
+                    Err(e) => Err((
+                        QueueFailureTypeEnum::SystemError,
+                        format!("Failed to list changed files: {e}"),
+                    )),
+                },
+                Err(e) => Err(e),
+            };
+            match loaded {
+                Ok((cl, files)) => {
+                    candidates.push(BatchCandidate {
+                        path: cl.path.clone(),
+                        files,
+                    });
+                    cls.push(cl);
+                }
+                Err((failure_type, message)) => {
+                    self.fail_queue_item(&item.cl_link, failure_type, message)
+                        .await;
+                }
+            }
+        }
+        cls.truncate(batch_prefix_len(&candidates));
+
+        let mut batch = Vec::with_capacity(cls.len());
+        for cl in cls {
+            // Items cancelled since they were fetched are left out of the batch
+            if queue_service
+                .update_item_status(&cl.link, QueueStatusEnum::Testing)
+                .await?
+            {
+                batch.push(cl);
+            }
+        }
+        if !batch.is_empty() {
+            self.validate_and_merge(batch).await?;
+        }
+        Ok(true)
+    }
 
-            // Update status to Testing
-            let updated = queue_service
-                .update_item_status(&cl_link, QueueStatusEnum::Testing)
-                .await?;
+    /// Builds `batch` on top of main and merges its CLs when the build passes.
+    ///
+    /// A failing batch is bisected until the CLs breaking the build are isolated. The halves
+    /// are validated in queue order, so the right half is rebuilt on top of whatever the left
+    /// half merged.
+    async fn validate_and_merge(&self, batch: Vec<mega_cl::Model>) -> Result<(), MegaError> {
+        let links: Vec<String> = batch.iter().map(|cl| cl.link.clone()).collect();
+        let batch_link = format!("mq-{}", common::utils::generate_id());
+        self.storage()
+            .merge_queue_service
+            .assign_batch(&links, &batch_link)
+            .await?;
 
-            // Item was cancelled before we could start processing
-            if !updated {
-                return Ok(false);
+        match self.run_batch_build(&batch[0], &batch_link).await {
+            BatchBuildOutcome::Passed => {
+                for link in &links {
+                    self.merge_queued_cl(link).await;
+                }
             }
-
-            // Execute the merge workflow
-            match self.execute_merge_workflow(&cl_link).await {
-                Ok(()) => {
-                    // Success - status already updated to Merged in workflow
-                    Ok(true)
+            BatchBuildOutcome::Failed(_, message) if batch.len() > 1 => {
+                tracing::info!(
+                    "Merge queue batch {} failed, bisecting {} CLs: {}",
+                    batch_link,
+                    batch.len(),
+                    message
+                );
+                let mut left = batch;
+                let right = left.split_off(left.len() / 2);
+                Box::pin(self.validate_and_merge(left)).await?;
+                Box::pin(self.validate_and_merge(right)).await?;
+            }
+            BatchBuildOutcome::Pending(message) => {
+                tracing::info!(
+                    "Merge queue batch {} was not validated, requeueing {} CLs: {}",
+                    batch_link,
+                    batch.len(),
+                    message
+                );
+                for link in &links {
+                    if let Err(e) = self
+                        .storage()
+                        .merge_queue_service
+                        .move_item_to_tail(link)
+                        .await
+                    {
+                        tracing::warn!("Failed to requeue item {}: {}", link, e);
+                    }
                 }
-                Err((failure_type, message)) => {
-                    if matches!(failure_type, QueueFailureTypeEnum::Conflict) {
-                        // Conflict - move to tail of queue for retry
-                        if let Err(e) = queue_service.move_item_to_tail(&cl_link).await {
-                            tracing::warn!(
-                                "Failed to move conflicting item {} to tail: {}",
-                                cl_link,
-                                e
-                            );
-                        }
-                        Ok(false)
-                    } else {
-                        // Other failure - mark as failed
-                        if let Err(e) = queue_service
-                            .update_item_status_with_error(&cl_link, failure_type, message)
-                            .await
-                        {
-                            tracing::error!(
-                                "Failed to update item {} status to failed: {}",
-                                cl_link,
-                                e
+                tokio::time::sleep(Duration::from_secs(Self::BUILD_POLL_INTERVAL_SECS)).await;
+            }
+            BatchBuildOutcome::Failed(failure_type, message)
+            | BatchBuildOutcome::Aborted(failure_type, message) => {
+                for link in &links {
+                    self.fail_queue_item(link, failure_type.clone(), message.clone())
+                        .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the speculative build of a batch and waits for its outcome.
+    ///
+    /// Batches pass without a build when the build system is disabled. A batch whose build
+    /// task was not created has not passed and is retried later.
+    async fn run_batch_build(&self, head: &mega_cl::Model, batch_link: &str) -> BatchBuildOutcome {
+        let Some(build_dispatch) = self.ctx.build_dispatch().filter(|d| d.enable_build()) else {
+            tracing::debug!(
+                "Build system disabled, merging batch {} without validation",
+                batch_link
+            );
+            return BatchBuildOutcome::Passed;
+        };
+
+        let (trigger_id, task_id) = match self
+            .dispatch_batch_build(&build_dispatch, head, batch_link)
+            .await
+        {
+            Ok(Some(build)) => build,
+            Ok(None) => {
+                return BatchBuildOutcome::Pending(format!(
+                    "No build task was created for batch {batch_link}"
+                ));
+            }
+            Err(e) => {
+                return BatchBuildOutcome::Aborted(
+                    QueueFailureTypeEnum::SystemError,
+                    format!("Failed to start the build of batch {batch_link}: {e}"),
+                );
+            }
+        };
+
+        let build_link = trigger_link(trigger_id);
+        let quarantined = fetch_quarantined(&build_dispatch, &head.path).await;
+        let deadline = Instant::now() + Duration::from_secs(Self::BUILD_TIMEOUT_SECS);
+        loop {
+            match fetch_build(&build_dispatch, &task_id).await {
+                Ok(report) => {
+                    let (status, message) =
+                        report.evaluate(&build_dispatch.required_targets(&head.path), &quarantined);
+                    match status {
+                        ConditionResult::PASSED => return BatchBuildOutcome::Passed,
+                        ConditionResult::FAILED => {
+                            return BatchBuildOutcome::Failed(
+                                classify_build_failure(&report, &quarantined),
+                                format!("{message} (build: {build_link})"),
                             );
                         }
-                        Ok(true)
+                        ConditionResult::PENDING => {}
                     }
                 }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch build task {}: {}", task_id, e);
+                }
             }
-        } else {
-            Ok(false)
+            if Instant::now() >= deadline {
+                return BatchBuildOutcome::Aborted(
+                    QueueFailureTypeEnum::Timeout,
+                    format!(
+                        "Build {build_link} did not finish within {} seconds",
+                        Self::BUILD_TIMEOUT_SECS
+                    ),
+                );
+            }
+            tokio::time::sleep(Duration::from_secs(Self::BUILD_POLL_INTERVAL_SECS)).await;
         }
     }
 
-    /// Executes the complete merge workflow for a CL.
-    ///
-    /// Workflow steps:
-    /// 1. Validate CL exists and is in valid status
-    /// 2. Run tests (TODO: Buck2 integration)
-    /// 3. Check for conflicts
-    /// 4. Execute merge
-    /// 5. Update statuses
-    async fn execute_merge_workflow(
+    /// Triggers the build of a batch on the current main head and records its task on the
+    /// batch items. Returns the trigger and task ids, or `None` when no build task was
+    /// created.
+    async fn dispatch_batch_build(
+        &self,
+        build_dispatch: &SharedBuildDispatch,
+        head: &mega_cl::Model,
+        batch_link: &str,
+    ) -> Result<Option<(i64, String)>, MegaError> {
+        let storage = self.storage();
+        let repo_path = edit_utils::resolve_build_repo_root(storage, &head.path).await?;
+        let main_head = storage
+            .mono_storage()
+            .get_main_ref(&head.path)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("Main ref of {} not found", head.path)))?
+            .ref_commit_hash;
+        let context =
+            TriggerContext::from_merge_queue(repo_path, main_head, batch_link.to_string());
+        let Some(trigger_id) = BuildTriggerService::build_by_context(
+            storage.clone(),
+            self.ctx.git_object_cache().clone(),
+            build_dispatch.clone(),
+            context,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let Some(task_id) = storage
+            .build_trigger_storage()
+            .get_by_id(trigger_id)
+            .await?
+            .and_then(|trigger| trigger.task_id)
+            .map(|id| id.to_string())
+        else {
+            return Ok(None);
+        };
+        storage
+            .merge_queue_service
+            .set_batch_build(batch_link, &task_id)
+            .await?;
+        Ok(Some((trigger_id, task_id)))
+    }
+
+    /// Merges a validated CL; conflicting CLs go back to the tail of the queue.
+    async fn merge_queued_cl(&self, cl_link: &str) {
+        match self.execute_merge_workflow(cl_link).await {
+            Ok(()) => {
+                self.dispatch_queue_webhook_for(WebhookEvent::MergeQueueMerged, cl_link, None)
+                    .await;
+            }
+            Err((QueueFailureTypeEnum::Conflict, message)) => {
+                tracing::info!("Moving conflicting item {} to tail: {}", cl_link, message);
+                if let Err(e) = self
+                    .storage()
+                    .merge_queue_service
+                    .move_item_to_tail(cl_link)
+                    .await
+                {
+                    tracing::warn!("Failed to move conflicting item {} to tail: {}", cl_link, e);
+                }
+            }
+            Err((failure_type, message)) => {
+                self.fail_queue_item(cl_link, failure_type, message).await;
+            }
+        }
+    }
+
+    async fn fail_queue_item(
         &self,
         cl_link: &str,
-    ) -> Result<(), (QueueFailureTypeEnum, String)> {
-        let queue_service = &self.storage().merge_queue_service;
+        failure_type: QueueFailureTypeEnum,
+        message: String,
+    ) {
+        if let Err(e) = self
+            .storage()
+            .merge_queue_service
+            .update_item_status_with_error(cl_link, failure_type, message.clone())
+            .await
+        {
+            tracing::error!("Failed to update item {} status to failed: {}", cl_link, e);
+        }
+        self.dispatch_queue_webhook_for(WebhookEvent::MergeQueueFailed, cl_link, Some(message))
+            .await;
+    }
 
-        // Step 1: Validate CL still exists and is not closed
+    /// Loads CL `cl_link` and sends a merge queue event about it to the subscribed webhooks.
+    async fn dispatch_queue_webhook_for(
+        &self,
+        event_type: WebhookEvent,
+        cl_link: &str,
+        error_message: Option<String>,
+    ) {
+        match self.storage().cl_service.cl_store().get_cl(cl_link).await {
+            Ok(Some(cl)) => dispatch_queue_webhook(self, event_type, &cl, error_message).await,
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to load CL {} for webhook: {}", cl_link, e),
+        }
+    }
+
+    /// Loads a queued CL, failing when it can no longer be merged.
+    async fn load_queued_cl(
+        &self,
+        cl_link: &str,
+    ) -> Result<mega_cl::Model, (QueueFailureTypeEnum, String)> {
         let cl = self
             .storage()
             .cl_service
@@ -325,7 +580,7 @@ impl ClApplicationService {
                 )
             })?;
 
-        let cl_model = match cl {
+        match cl {
             Some(model) => {
                 if model.status == MergeStatusEnum::Closed {
                     return Err((
@@ -339,15 +594,55 @@ impl ClApplicationService {
                         "CL is in draft status, cannot merge".to_string(),
                     ));
                 }
-                model
+                Ok(model)
             }
-            None => {
-                return Err((
+            None => Err((
+                QueueFailureTypeEnum::SystemError,
+                "CL no longer exists, cannot merge".to_string(),
+            )),
+        }
+    }
+
+    /// Executes the merge workflow for a CL whose batch passed validation.
+    ///
+    /// Workflow steps:
+    /// 1. Validate CL exists and is in valid status
+    /// 2. Update the CL onto main when CLs ahead of it were merged since it was opened
+    /// 3. Execute merge
+    /// 4. Update statuses
+    async fn execute_merge_workflow(
+        &self,
+        cl_link: &str,
+    ) -> Result<(), (QueueFailureTypeEnum, String)> {
+        let queue_service = &self.storage().merge_queue_service;
+
+        // Step 1: Validate CL still exists and is not closed
+        let mut cl_model = self.load_queued_cl(cl_link).await?;
+
+        // Step 2: The batch build already covered the CL on top of main
+        let main_ref = self
+            .storage()
+            .mono_storage()
+            .get_main_ref(&cl_model.path)
+            .await
+            .map_err(|e| {
+                (
                     QueueFailureTypeEnum::SystemError,
-                    "CL no longer exists, cannot merge".to_string(),
-                ));
-            }
-        };
+                    format!("Failed to get main ref: {}", e),
+                )
+            })?;
+        if main_ref.is_some_and(|r| r.ref_commit_hash != cl_model.from_hash) {
+            self.update_branch("system", cl_link).await.map_err(|e| {
+                let message = e.to_string();
+                let failure_type = if message.contains("conflict") {
+                    QueueFailureTypeEnum::Conflict
+                } else {
+                    QueueFailureTypeEnum::MergeFailure
+                };
+                (failure_type, format!("Failed to update branch: {message}"))
+            })?;
+            cl_model = self.load_queued_cl(cl_link).await?;
+        }
 
         let updated = queue_service
             .update_item_status(cl_link, QueueStatusEnum::Merging)
@@ -366,6 +661,7 @@ impl ClApplicationService {
             ));
         }
 
+        // Step 3: Execute merge
         let merge_result = self.merge_cl("system", cl_model).await;
 
         if let Err(e) = merge_result {
@@ -380,7 +676,7 @@ impl ClApplicationService {
             return Err((failure_type, format!("Merge failed: {message}")));
         }
 
-        // Step 6: Update queue status to Merged
+        // Step 4: Update queue status to Merged
         queue_service
             .update_item_status(cl_link, QueueStatusEnum::Merged)
             .await
@@ -394,3 +690,230 @@ impl ClApplicationService {
         Ok(())
     }
 }
+
+async fn dispatch_queue_webhook(
+    service: &ClApplicationService,
+    event_type: WebhookEvent,
+    cl: &mega_cl::Model,
+    error_message: Option<String>,
+) {
+    dispatch_webhook_event(
+        service.storage(),
+        event_type,
+        &cl.path,
+        WebhookEventPayload::MergeQueue(MergeQueueItemPayload {
+            cl: cl.into(),
+            error_message,
+        }),
+    )
+    .await;
+}
+
+/// API link to the build trigger of a batch, which reports its task and status.
+fn trigger_link(trigger_id: i64) -> String {
+    format!("/api/v1/triggers/{trigger_id}")
+}
+
+/// Outcome of the speculative build of a merge queue batch.
+enum BatchBuildOutcome {
+    Passed,
+    /// No build was started, so the batch has not passed yet and goes back to the queue.
+    Pending(String),
+    /// The build finished and broke; bisecting the batch isolates the culprit.
+    Failed(QueueFailureTypeEnum, String),
+    /// The build could not be evaluated, e.g. it never started or timed out.
+    Aborted(QueueFailureTypeEnum, String),
+}
+
+/// A waiting CL as seen by batch selection.
+struct BatchCandidate {
+    path: String,
+    files: Vec<String>,
+}
+
+/// Length of the longest queue prefix that can be built as one batch: CLs of the same path
+/// that change disjoint files, at most `MAX_BATCH_SIZE` of them.
+fn batch_prefix_len(candidates: &[BatchCandidate]) -> usize {
+    let Some(first) = candidates.first() else {
+        return 0;
+    };
+    let mut touched: HashSet<&str> = HashSet::new();
+    let mut len = 0;
+    for candidate in candidates
+        .iter()
+        .take(ClApplicationService::MAX_BATCH_SIZE as usize)
+    {
+        if candidate.path != first.path
+            || candidate.files.iter().any(|f| touched.contains(f.as_str()))
+        {
+            break;
+        }
+        touched.extend(candidate.files.iter().map(String::as_str));
+        len += 1;
+    }
+    len
+}
+
+/// A failed build is a test failure when only tests failed: a failed test result or a failed
+/// target of a `*_test` rule, and no other target. Failures of `quarantined` targets don't count.
+fn classify_build_failure(report: &BuildReport, quarantined: &[String]) -> QueueFailureTypeEnum {
+    let failed_targets: Vec<&TargetStatusResponse> = report
+        .targets
+        .iter()
+        .filter(|t| t.status == "FAILED" && !quarantined.contains(&target_label(t)))
+        .collect();
+    let is_test_rule = |t: &TargetStatusResponse| t.category.ends_with("_test");
+    let failed_tests = report
+        .tests
+        .iter()
+        .any(|t| t.status.is_failure() && !quarantined.contains(&t.target));
+    if failed_targets.iter().all(|t| is_test_rule(t))
+        && (failed_tests || !failed_targets.is_empty())
+    {
+        QueueFailureTypeEnum::TestFailure
+    } else {
+        QueueFailureTypeEnum::BuildFailure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use api_model::buck2::types::{
+        BuildStatus, TargetStatusResponse, TestResultResponse, TestStatus,
+    };
+    use callisto::sea_orm_active_enums::QueueFailureTypeEnum;
+
+    use super::{BatchCandidate, BuildReport, batch_prefix_len, classify_build_failure};
+
+    fn candidate(path: &str, files: &[&str]) -> BatchCandidate {
+        BatchCandidate {
+            path: path.to_string(),
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    fn target(name: &str, category: &str, status: &str) -> TargetStatusResponse {
+        TargetStatusResponse {
+            id: String::new(),
+            task_id: String::new(),
+            package: "root//app".to_string(),
+            name: name.to_string(),
+            configuration: String::new(),
+            category: category.to_string(),
+            identifier: String::new(),
+            action: String::new(),
+            status: status.to_string(),
+        }
+    }
+
+    fn test_result(target: &str, status: TestStatus) -> TestResultResponse {
+        TestResultResponse {
+            id: String::new(),
+            build_id: String::new(),
+            target: target.to_string(),
+            name: "case".to_string(),
+            status,
+            duration_ms: None,
+            message: None,
+        }
+    }
+
+    fn failed_build(
+        targets: Vec<TargetStatusResponse>,
+        tests: Vec<TestResultResponse>,
+    ) -> BuildReport {
+        BuildReport {
+            build: Some(BuildStatus::Failed),
+            targets,
+            tests,
+        }
+    }
+
+    #[test]
+    fn test_batch_stops_at_overlapping_files() {
+        let candidates = vec![
+            candidate("/project", &["a.rs"]),
+            candidate("/project", &["b.rs", "c.rs"]),
+            candidate("/project", &["c.rs"]),
+            candidate("/project", &["d.rs"]),
+        ];
+        assert_eq!(batch_prefix_len(&candidates), 2);
+    }
+
+    #[test]
+    fn test_batch_stops_at_other_path() {
+        let candidates = vec![
+            candidate("/project", &["a.rs"]),
+            candidate("/other", &["b.rs"]),
+        ];
+        assert_eq!(batch_prefix_len(&candidates), 1);
+        assert_eq!(batch_prefix_len(&[]), 0);
+    }
+
+    #[test]
+    fn test_batch_is_capped() {
+        let candidates: Vec<BatchCandidate> = (0..20)
+            .map(|i| candidate("/project", &[&format!("{i}.rs")]))
+            .collect();
+        assert_eq!(batch_prefix_len(&candidates), 8);
+    }
+
+    #[test]
+    fn test_classify_build_failure() {
+        // A failed test rule target.
+        let report = failed_build(
+            vec![
+                target("lib", "rust_library", "SUCCEEDED"),
+                target("lib_unit", "rust_test", "FAILED"),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            classify_build_failure(&report, &[]),
+            QueueFailureTypeEnum::TestFailure
+        );
+
+        // A failed test case reported by `buck2 test`.
+        let report = failed_build(
+            vec![target("lib", "rust_library", "SUCCEEDED")],
+            vec![test_result("root//app:lib_unit", TestStatus::Fail)],
+        );
+        assert_eq!(
+            classify_build_failure(&report, &[]),
+            QueueFailureTypeEnum::TestFailure
+        );
+
+        // Names that look like tests don't make a library a test.
+        let report = failed_build(
+            vec![
+                target("integration_tests", "rust_library", "FAILED"),
+                target("lib_unit", "rust_test", "SUCCEEDED"),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            classify_build_failure(&report, &[]),
+            QueueFailureTypeEnum::BuildFailure
+        );
+
+        // A broken library is a build failure even if tests failed too.
+        let report = failed_build(
+            vec![target("lib", "rust_library", "FAILED")],
+            vec![test_result("root//app:lib_unit", TestStatus::Fail)],
+        );
+        assert_eq!(
+            classify_build_failure(&report, &[]),
+            QueueFailureTypeEnum::BuildFailure
+        );
+
+        // Quarantined failures are ignored.
+        let report = failed_build(
+            vec![target("lib", "rust_library", "FAILED")],
+            vec![test_result("root//app:lib_unit", TestStatus::Fail)],
+        );
+        assert_eq!(
+            classify_build_failure(&report, &["root//app:lib".to_string()]),
+            QueueFailureTypeEnum::TestFailure
+        );
+    }
+}
@@ -2,7 +2,10 @@ use api_model::common::{CommonPage, Pagination};
 use common::errors::MegaError;
 
 use super::context::ClApplicationService;
-use crate::model::{change_list::ListPayload, issue::ItemRes};
+use crate::model::{
+    change_list::{ClCommitRes, ListPayload},
+    issue::ItemRes,
+};
 
 impl ClApplicationService {
     pub async fn get_cl_list(
@@ -30,4 +33,17 @@ impl ClApplicationService {
             .await?
             .ok_or_else(|| MegaError::NotFound(format!("CL {link} not found")))
     }
+
+    /// Commits pushed to the CL, oldest first.
+    pub async fn get_cl_commits(&self, link: &str) -> Result<Vec<ClCommitRes>, MegaError> {
+        Ok(self
+            .storage()
+            .cl_service
+            .cl_store()
+            .get_cl_commits(link)
+            .await?
+            .into_iter()
+            .map(ClCommitRes::from)
+            .collect())
+    }
 }
@@ -104,8 +104,11 @@ impl UserApplicationService {
             return Ok(());
         }
 
-        let check_reg =
-            CheckerRegistry::new(self.ctx.storage().clone().into(), username.to_string());
+        let check_reg = CheckerRegistry::new(
+            self.ctx.storage().clone().into(),
+            username.to_string(),
+            self.ctx.build_dispatch(),
+        );
         for cl in open_cls {
             check_reg.run_checks(cl.into()).await?;
         }
@@ -2,12 +2,18 @@ use api_model::common::Pagination;
 use common::errors::MegaError;
 
 use super::context::IssueApplicationService;
-use crate::model::{
-    change_list::ListPayload,
-    issue::{IssueDetailRes, IssueSuggestions, ItemRes},
-    label::LabelItem,
+use crate::{
+    application::webhook::{WebhookEvent, WebhookEventPayload, dispatch_webhook_event},
+    model::{
+        change_list::ListPayload,
+        issue::{IssueDetailRes, IssueSuggestions, ItemRes},
+        label::LabelItem,
+    },
 };
 
+/// Issues are not scoped to a path, so their webhooks only reach unfiltered subscriptions.
+const ISSUE_WEBHOOK_PATH: &str = "/";
+
 impl IssueApplicationService {
     pub async fn get_issue_details(
         &self,
@@ -60,12 +66,21 @@ impl IssueApplicationService {
         username: &str,
         title: &str,
     ) -> Result<callisto::mega_issue::Model, MegaError> {
-        self.ctx
+        let issue = self
+            .ctx
             .storage()
             .issue_service
             .issue_store()
             .save_issue(username, title)
-            .await
+            .await?;
+        dispatch_webhook_event(
+            self.ctx.storage(),
+            WebhookEvent::IssueCreated,
+            ISSUE_WEBHOOK_PATH,
+            WebhookEventPayload::Issue((&issue).into()),
+        )
+        .await;
+        Ok(issue)
     }
 
     pub async fn close_issue(&self, link: &str) -> Result<(), MegaError> {
@@ -74,7 +89,10 @@ impl IssueApplicationService {
             .issue_service
             .issue_store()
             .close_issue(link)
-            .await
+            .await?;
+        self.dispatch_issue_webhook(WebhookEvent::IssueClosed, link)
+            .await;
+        Ok(())
     }
 
     pub async fn reopen_issue(&self, link: &str) -> Result<(), MegaError> {
@@ -83,7 +101,10 @@ impl IssueApplicationService {
             .issue_service
             .issue_store()
             .reopen_issue(link)
-            .await
+            .await?;
+        self.dispatch_issue_webhook(WebhookEvent::IssueReopened, link)
+            .await;
+        Ok(())
     }
 
     pub async fn edit_issue_title(&self, link: &str, title: &str) -> Result<(), MegaError> {
@@ -92,7 +113,34 @@ impl IssueApplicationService {
             .issue_service
             .issue_store()
             .edit_title(link, title)
+            .await?;
+        self.dispatch_issue_webhook(WebhookEvent::IssueUpdated, link)
+            .await;
+        Ok(())
+    }
+
+    /// Sends the current state of issue `link` to the webhooks subscribed to `event_type`.
+    async fn dispatch_issue_webhook(&self, event_type: WebhookEvent, link: &str) {
+        match self
+            .ctx
+            .storage()
+            .issue_service
+            .issue_store()
+            .get_issue(link)
             .await
+        {
+            Ok(Some(issue)) => {
+                dispatch_webhook_event(
+                    self.ctx.storage(),
+                    event_type,
+                    ISSUE_WEBHOOK_PATH,
+                    WebhookEventPayload::Issue((&issue).into()),
+                )
+                .await
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to load issue {} for webhook: {}", link, e),
+        }
     }
 
     pub async fn list_labels_by_page(
@@ -3,14 +3,16 @@ use std::pin::Pin;
 use bytes::Bytes;
 use common::errors::{GitLFSError, MegaError};
 use futures::Stream;
+use io_orbit::object_storage::ObjectByteStream;
 
 use super::context::LfsApplicationService;
 use crate::lfs::{
     handler,
     lfs_structs::{
-        BatchRequest, BatchResponse, Lock, LockList, LockListQuery, LockRequest, RequestObject,
-        UnlockRequest, VerifiableLockList, VerifiableLockRequest,
+        BatchRequest, BatchResponse, LfsUploadScope, Lock, LockList, LockListQuery, LockRequest,
+        RequestObject, UnlockRequest, VerifiableLockList, VerifiableLockRequest,
     },
+    tus::{self, TusUploadStatus},
 };
 
 impl LfsApplicationService {
@@ -26,19 +28,26 @@ impl LfsApplicationService {
     }
 
     pub async fn lfs_create_lock(&self, req: LockRequest) -> Result<Lock, GitLFSError> {
-        handler::lfs_create_lock(self.ctx.storage().lfs_db_storage(), req).await
+        handler::lfs_create_lock(self.ctx.storage().lfs_db_storage(), req, None).await
     }
 
     pub async fn lfs_delete_lock(&self, id: &str, req: UnlockRequest) -> Result<Lock, GitLFSError> {
-        handler::lfs_delete_lock(self.ctx.storage().lfs_db_storage(), id, req).await
+        handler::lfs_delete_lock(self.ctx.storage().lfs_db_storage(), id, req, None).await
     }
 
+    /// `repo_path` is the repository the client addressed, used to apply root directory
+    /// quotas; requests made directly against the LFS API carry none.
     pub async fn lfs_process_batch(
         &self,
         request: BatchRequest,
         listen_addr: &str,
+        repo_path: Option<&str>,
     ) -> Result<BatchResponse, GitLFSError> {
-        handler::lfs_process_batch(&self.ctx.storage().lfs_service, request, listen_addr).await
+        let storage = self.ctx.storage();
+        let scope = repo_path
+            .map(|path| LfsUploadScope::for_repo(&storage.config().lfs, path))
+            .unwrap_or_default();
+        handler::lfs_process_batch(&storage.lfs_service, request, listen_addr, &scope).await
     }
 
     pub async fn lfs_download_object(
@@ -53,8 +62,21 @@ impl LfsApplicationService {
     pub async fn lfs_upload_object(
         &self,
         req_obj: &RequestObject,
-        body_bytes: Vec<u8>,
+        body: ObjectByteStream,
     ) -> Result<(), GitLFSError> {
-        handler::lfs_upload_object(&self.ctx.storage().lfs_service, req_obj, body_bytes).await
+        handler::lfs_upload_object(&self.ctx.storage().lfs_service, req_obj, body).await
+    }
+
+    pub async fn lfs_tus_status(&self, oid: &str) -> Result<TusUploadStatus, GitLFSError> {
+        tus::lfs_tus_status(&self.ctx.storage().lfs_service, oid).await
+    }
+
+    pub async fn lfs_tus_append(
+        &self,
+        oid: &str,
+        offset: i64,
+        body: ObjectByteStream,
+    ) -> Result<i64, GitLFSError> {
+        tus::lfs_tus_append(&self.ctx.storage().lfs_service, oid, offset, body).await
     }
 }
@@ -1,7 +1,12 @@
 use common::errors::MegaError;
 
 use super::context::ReviewerApplicationService;
-use crate::model::change_list::{ReviewerInfo, ReviewersResponse};
+use crate::{
+    application::webhook::{
+        AuthorPayload, ReviewPayload, WebhookEvent, WebhookEventPayload, dispatch_webhook_event,
+    },
+    model::change_list::{ReviewerInfo, ReviewersResponse},
+};
 
 impl ReviewerApplicationService {
     pub async fn add_reviewers(&self, link: &str, reviewers: Vec<String>) -> Result<(), MegaError> {
@@ -51,7 +56,28 @@ impl ReviewerApplicationService {
             .storage()
             .reviewer_storage()
             .reviewer_change_state(link, username, approved)
-            .await
+            .await?;
+        if approved {
+            match self.ctx.storage().cl_service.cl_store().get_cl(link).await {
+                Ok(Some(cl)) => {
+                    dispatch_webhook_event(
+                        self.ctx.storage(),
+                        WebhookEvent::ReviewApproved,
+                        &cl.path,
+                        WebhookEventPayload::Review(ReviewPayload {
+                            cl: (&cl).into(),
+                            reviewer: AuthorPayload {
+                                name: username.to_string(),
+                            },
+                        }),
+                    )
+                    .await
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to load CL {} for webhook: {}", link, e),
+            }
+        }
+        Ok(())
     }
 
     pub async fn is_reviewer(&self, link: &str, username: &str) -> Result<bool, MegaError> {
@@ -26,6 +26,7 @@ use crate::{
     application::{
         api_service::{ApiHandler, cache::GitObjectCache, tree_ops},
         build_trigger::SharedBuildDispatch,
+        webhook::{TagPayload, WebhookEvent, WebhookEventPayload, dispatch_webhook_event},
     },
     infra::TransportContext,
     model::git::{CreateEntryInfo, CreateEntryResult, EditFilePayload, EditFileResult},
@@ -236,8 +237,23 @@ impl ApiHandler for MonoApiService {
         tagger_email: Option<String>,
         message: Option<String>,
     ) -> Result<crate::model::tag::TagInfo, GitError> {
-        self.create_tag_impl(repo_path, name, target, tagger_name, tagger_email, message)
-            .await
+        let path = repo_path.clone().unwrap_or_else(|| "/".to_string());
+        let tag = self
+            .create_tag_impl(repo_path, name, target, tagger_name, tagger_email, message)
+            .await?;
+        dispatch_webhook_event(
+            self.storage(),
+            WebhookEvent::TagCreated,
+            &path,
+            WebhookEventPayload::Tag(TagPayload {
+                name: tag.name.clone(),
+                target: Some(tag.object_id.clone()),
+                tagger: Some(tag.tagger.clone()).filter(|t| !t.is_empty()),
+                message: Some(tag.message.clone()).filter(|m| !m.is_empty()),
+            }),
+        )
+        .await;
+        Ok(tag)
     }
 
     async fn list_tags(
@@ -257,6 +273,20 @@ impl ApiHandler for MonoApiService {
     }
 
     async fn delete_tag(&self, repo_path: Option<String>, name: String) -> Result<(), GitError> {
-        self.delete_tag_impl(repo_path, name).await
+        let path = repo_path.clone().unwrap_or_else(|| "/".to_string());
+        self.delete_tag_impl(repo_path, name.clone()).await?;
+        dispatch_webhook_event(
+            self.storage(),
+            WebhookEvent::TagDeleted,
+            &path,
+            WebhookEventPayload::Tag(TagPayload {
+                name,
+                target: None,
+                tagger: None,
+                message: None,
+            }),
+        )
+        .await;
+        Ok(())
     }
 }
@@ -164,7 +164,7 @@ impl MonoApiService {
         let fetch_depth = if mega_path.starts_with(&import_dir) {
             None
         } else {
-            // MonoRepo receive-pack only accepts a single commit per push.
+            // Only the upstream tip is imported into the monorepo, not its history.
             Some(1)
         };
 
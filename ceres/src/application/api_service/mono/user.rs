@@ -6,7 +6,7 @@ use crate::model::{
         NotificationEventTypeInfo, UpdateUserNotificationConfig, UserNotificationConfig,
         UserNotificationPreferenceItem,
     },
-    user::{ListSSHKey, ListToken},
+    user::{GenerateTokenPayload, GeneratedToken, ListSSHKey, ListToken},
 };
 
 impl UserApplicationService {
@@ -42,12 +42,40 @@ impl UserApplicationService {
         Ok(keys.into_iter().map(|k| k.into()).collect())
     }
 
-    pub async fn generate_user_token(&self, username: String) -> Result<String, MegaError> {
-        self.ctx
+    pub async fn generate_user_token(
+        &self,
+        username: String,
+        payload: GenerateTokenPayload,
+    ) -> Result<GeneratedToken, MegaError> {
+        let mut scopes = Vec::with_capacity(payload.scopes.len());
+        for scope in payload.scopes {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        if scopes.is_empty() {
+            return Err(MegaError::BadRequest(
+                "a token needs at least one scope".to_string(),
+            ));
+        }
+        let expires_at = match payload.expires_in_days {
+            Some(0) => {
+                return Err(MegaError::BadRequest(
+                    "expires_in_days must be positive".to_string(),
+                ));
+            }
+            Some(days) => {
+                Some(chrono::Utc::now().naive_utc() + chrono::TimeDelta::days(i64::from(days)))
+            }
+            None => None,
+        };
+        let (model, token) = self
+            .ctx
             .storage()
             .user_storage()
-            .generate_token(username)
-            .await
+            .generate_token(username, &scopes, expires_at)
+            .await?;
+        Ok(GeneratedToken::new(model, token))
     }
 
     pub async fn delete_user_token(&self, username: String, key_id: i64) -> Result<(), MegaError> {
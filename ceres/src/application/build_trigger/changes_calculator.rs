@@ -206,6 +206,33 @@ impl<P: ChangesPort> ChangesCalculator<P> {
         Ok(changes)
     }
 
+    /// Every file of `repo_path` at `commit_hash`, reported as added, for builds of the
+    /// whole tree. `tree_root` is the monorepo directory the commit's tree is rooted at.
+    pub async fn get_builds_for_tree(
+        &self,
+        repo_path: &str,
+        tree_root: &str,
+        commit_hash: &str,
+    ) -> Result<Vec<Status<ProjectRelativePath>>, MegaError> {
+        let tree_root = Path::new(tree_root.trim_matches('/'));
+        let subtree = Path::new(repo_path.trim_matches('/'))
+            .strip_prefix(tree_root)
+            .map_err(|_| {
+                MegaError::Other(format!(
+                    "{repo_path} is not below the tree root {}",
+                    tree_root.display()
+                ))
+            })?;
+        let files = self
+            .get_commit_blobs(commit_hash)
+            .await?
+            .into_iter()
+            .filter(|(path, _)| path.starts_with(subtree))
+            .map(|(path, hash)| ClDiffFile::New(tree_root.join(path), hash))
+            .collect();
+        build_changes_for_repo(repo_path, files)
+    }
+
     async fn get_commit_blobs(
         &self,
         commit_hash: &str,
@@ -429,4 +456,65 @@ mod tests {
             None
         );
     }
+
+    struct CommitFiles(Vec<&'static str>);
+
+    #[async_trait::async_trait]
+    impl ChangesPort for CommitFiles {
+        async fn get_commit_blobs(
+            &self,
+            _commit_hash: &str,
+        ) -> Result<Vec<(PathBuf, ObjectHash)>, MegaError> {
+            Ok(self
+                .0
+                .iter()
+                .map(|path| (PathBuf::from(path), ObjectHash::default()))
+                .collect())
+        }
+
+        async fn cl_files_list(
+            &self,
+            _old_files: Vec<(PathBuf, ObjectHash)>,
+            _new_files: Vec<(PathBuf, ObjectHash)>,
+        ) -> Result<Vec<ClDiffFile>, MegaError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builds_for_tree_cover_every_file_of_the_path() {
+        let root_commit = ChangesCalculator::new(CommitFiles(vec![
+            "project/app/BUCK",
+            "project/app/src/main.rs",
+            "project/application/BUCK",
+            "doc/README.md",
+        ]));
+        assert_eq!(
+            root_commit
+                .get_builds_for_tree("/project/app", "/", "head")
+                .await
+                .unwrap(),
+            vec![
+                Status::Added(ProjectRelativePath::new("BUCK")),
+                Status::Added(ProjectRelativePath::new("src/main.rs")),
+            ]
+        );
+
+        // A commit of a directory with its own main ref is rooted at that directory.
+        let project_commit =
+            ChangesCalculator::new(CommitFiles(vec!["app/src/main.rs", "lib/BUCK"]));
+        assert_eq!(
+            project_commit
+                .get_builds_for_tree("/project/app", "/project", "head")
+                .await
+                .unwrap(),
+            vec![Status::Added(ProjectRelativePath::new("src/main.rs"))]
+        );
+        assert!(
+            project_commit
+                .get_builds_for_tree("/doc", "/project", "head")
+                .await
+                .is_err()
+        );
+    }
 }
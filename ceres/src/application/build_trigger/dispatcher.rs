@@ -1,8 +1,21 @@
-use api_model::buck2::{api::TaskBuildRequest, status::Status, types::ProjectRelativePath};
+use api_model::buck2::{
+    api::TaskBuildRequest,
+    status::Status,
+    types::{BuildPriority, BuildStatus, ProjectRelativePath},
+};
 use common::errors::MegaError;
 use jupiter::storage::Storage;
 
-use crate::application::build_trigger::{BuildTrigger, BuildTriggerPayload, SharedBuildDispatch};
+use crate::application::{
+    build_trigger::{BuildTrigger, BuildTriggerPayload, SharedBuildDispatch},
+    webhook::{BuildPayload, WebhookEvent, WebhookEventPayload, dispatch_webhook_event},
+};
+
+/// Builds still running after this long stop being watched for `build.completed` webhooks.
+const BUILD_WATCH_TIMEOUT: chrono::Duration = chrono::Duration::hours(6);
+
+/// Watched builds read per page by [`BuildDispatcher::notify_completed_builds`].
+const BUILD_WATCH_BATCH: u64 = 100;
 
 /// Handles dispatching build triggers to the build execution layer.
 pub struct BuildDispatcher {
@@ -19,6 +32,20 @@ fn payload_to_task_request(payload: &BuildTriggerPayload) -> Result<TaskBuildReq
         BuildTriggerPayload::Schedule(p) => (&p.cl_link, &p.repo, &p.builds, p.cl_id),
         BuildTriggerPayload::WebEdit(p) => (&p.cl_link, &p.repo, &p.builds, p.cl_id),
         BuildTriggerPayload::BuckFileUpload(p) => (&p.cl_link, &p.repo, &p.builds, p.cl_id),
+        BuildTriggerPayload::MergeQueue(p) => (&p.cl_link, &p.repo, &p.builds, None),
+    };
+    // Explicitly requested targets, so target-based routing rules can match them.
+    let targets = match payload {
+        BuildTriggerPayload::Manual(p) => p.params.as_ref(),
+        BuildTriggerPayload::Webhook(p) => p.params.as_ref(),
+        _ => None,
+    }
+    .and_then(|params| params.build_target.clone())
+    .map(|target| vec![target]);
+    let priority = match payload {
+        BuildTriggerPayload::MergeQueue(_) => BuildPriority::MergeQueue,
+        BuildTriggerPayload::Schedule(_) => BuildPriority::Scheduled,
+        _ => BuildPriority::ChangeList,
     };
 
     let changes: Vec<Status<ProjectRelativePath>> = serde_json::from_value(builds_json.clone())
@@ -32,7 +59,9 @@ fn payload_to_task_request(payload: &BuildTriggerPayload) -> Result<TaskBuildReq
         cl_link: cl_link.to_string(),
         cl_id: cl_id.unwrap_or(0),
         changes,
-        targets: None,
+        targets,
+        requirements: Default::default(),
+        priority,
     })
 }
 
@@ -88,17 +117,154 @@ impl BuildDispatcher {
             task_id
         );
 
+        if task_id.is_some() {
+            self.watch_build_completion(db_record.id, &trigger).await;
+        }
+
         Ok(db_record.id)
     }
+
+    /// Watches the build of a trigger so that [`Self::notify_completed_builds`] emits
+    /// `build.completed` once Orion reports it as finished.
+    ///
+    /// Builds are only watched when a webhook subscribes to the event for their path.
+    async fn watch_build_completion(&self, trigger_id: i64, trigger: &BuildTrigger) {
+        let repo = trigger.payload.repo_path();
+        match self
+            .storage
+            .webhook_service
+            .has_subscribers(WebhookEvent::BuildCompleted, repo)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                tracing::warn!("Failed to look up build.completed webhooks: {}", e);
+                return;
+            }
+        }
+
+        let until = chrono::Utc::now().naive_utc() + BUILD_WATCH_TIMEOUT;
+        if let Err(e) = self
+            .storage
+            .build_trigger_storage()
+            .watch_completion(trigger_id, until)
+            .await
+        {
+            tracing::warn!("Failed to watch build of trigger {}: {}", trigger_id, e);
+        }
+    }
+
+    /// Emits `build.completed` for the watched builds that Orion reports as finished and
+    /// returns how many were reported. Builds still running past their watch are dropped.
+    ///
+    /// Every watched build is polled, a page of [`BUILD_WATCH_BATCH`] at a time.
+    pub async fn notify_completed_builds(&self) -> Result<usize, MegaError> {
+        let trigger_storage = self.storage.build_trigger_storage();
+        let now = chrono::Utc::now().naive_utc();
+        let mut reported = 0;
+        let mut after = None;
+        loop {
+            let page = trigger_storage
+                .list_completion_watches(after, BUILD_WATCH_BATCH)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = Some(last.id);
+            reported += self.report_completed_page(page, now).await?;
+        }
+        Ok(reported)
+    }
+
+    async fn report_completed_page(
+        &self,
+        page: Vec<callisto::build_triggers::Model>,
+        now: chrono::NaiveDateTime,
+    ) -> Result<usize, MegaError> {
+        let trigger_storage = self.storage.build_trigger_storage();
+        let mut reported = 0;
+        for record in page {
+            let (Some(until), Some(task_id)) = (record.completion_watch_until, record.task_id)
+            else {
+                continue;
+            };
+            let status = match self
+                .build_dispatch
+                .latest_build_result(&task_id.to_string())
+                .await
+            {
+                Ok(Some(BuildStatus::Completed)) => "completed",
+                Ok(Some(BuildStatus::Failed)) => "failed",
+                Ok(Some(BuildStatus::Cancelled)) => "cancelled",
+                Ok(Some(BuildStatus::Running) | None) if until > now => continue,
+                Ok(Some(BuildStatus::Running) | None) => {
+                    if trigger_storage
+                        .end_completion_watch(record.id, until)
+                        .await?
+                    {
+                        tracing::warn!(
+                            "Stopped watching build {} after {} seconds",
+                            task_id,
+                            BUILD_WATCH_TIMEOUT.num_seconds()
+                        );
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to poll build {}: {}", task_id, e);
+                    continue;
+                }
+            };
+            // Claimed first so that concurrent instances report each build once.
+            if !trigger_storage
+                .end_completion_watch(record.id, until)
+                .await?
+            {
+                continue;
+            }
+            let payload: BuildTriggerPayload = match serde_json::from_value(record.trigger_payload)
+            {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::error!("Invalid payload of build trigger {}: {}", record.id, e);
+                    continue;
+                }
+            };
+            dispatch_webhook_event(
+                &self.storage,
+                WebhookEvent::BuildCompleted,
+                payload.repo_path(),
+                WebhookEventPayload::Build(BuildPayload {
+                    trigger_id: record.id,
+                    task_id: task_id.to_string(),
+                    trigger_type: record.trigger_type,
+                    status: status.to_string(),
+                    commit: payload.commit_hash().to_string(),
+                    cl_link: payload.cl_link().to_string(),
+                }),
+            )
+            .await;
+            reported += 1;
+        }
+        Ok(reported)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{sync::Arc, time::Duration};
 
-    use api_model::buck2::ws::WSMessage;
+    use api_model::buck2::{
+        types::{BuildStatus, TargetStatusResponse, TestResultResponse},
+        ws::WSMessage,
+    };
     use async_trait::async_trait;
-    use axum::{Json, Router, extract::State, routing::post};
+    use axum::{
+        Json, Router,
+        extract::State,
+        routing::{get, post},
+    };
     use chrono::Utc;
     use common::config::BuildConfig;
     use orion_client::OrionBuildClient;
@@ -107,7 +273,8 @@ mod tests {
 
     use super::*;
     use crate::application::build_trigger::{
-        BuildDispatchPort, BuildTriggerType, TriggerSource, WebEditPayload,
+        BuildDispatchPort, BuildParams, BuildTriggerType, ManualPayload, TriggerSource,
+        WebEditPayload,
     };
 
     struct TestOrionDispatch(Arc<OrionBuildClient>);
@@ -124,6 +291,44 @@ mod tests {
                 .await
                 .map_err(|e| MegaError::Other(e.to_string()))
         }
+
+        fn required_targets(&self, path: &str) -> Vec<String> {
+            self.0.required_targets(path).to_vec()
+        }
+
+        async fn latest_build_result(
+            &self,
+            task_id: &str,
+        ) -> Result<Option<BuildStatus>, MegaError> {
+            self.0
+                .latest_build_result(task_id)
+                .await
+                .map_err(|e| MegaError::Other(e.to_string()))
+        }
+
+        async fn target_statuses(
+            &self,
+            task_id: &str,
+        ) -> Result<Vec<TargetStatusResponse>, MegaError> {
+            self.0
+                .target_statuses(task_id)
+                .await
+                .map_err(|e| MegaError::Other(e.to_string()))
+        }
+
+        async fn test_results(&self, task_id: &str) -> Result<Vec<TestResultResponse>, MegaError> {
+            self.0
+                .test_results(task_id)
+                .await
+                .map_err(|e| MegaError::Other(e.to_string()))
+        }
+
+        async fn quarantined_targets(&self, path: &str) -> Result<Vec<String>, MegaError> {
+            self.0
+                .quarantined_targets(path)
+                .await
+                .map_err(|e| MegaError::Other(e.to_string()))
+        }
     }
 
     fn test_dispatch(client: Arc<OrionBuildClient>) -> SharedBuildDispatch {
@@ -155,6 +360,10 @@ mod tests {
     ) -> (String, tokio::task::JoinHandle<()>) {
         let app = Router::new()
             .route("/v2/task", post(mock_task_handler))
+            .route(
+                "/v2/latest_build_result/{task_id}",
+                get(|| async { Json(BuildStatus::Completed) }),
+            )
             .with_state(MockOrionState { worker_tx, task_id });
         let listener = TcpListener::bind("127.0.0.1:0")
             .await
@@ -272,6 +481,29 @@ mod tests {
             req.changes,
             vec![Status::Modified(ProjectRelativePath::new("src/main.rs"))]
         );
+        assert_eq!(req.priority, BuildPriority::ChangeList);
+        assert_eq!(req.targets, None);
+    }
+
+    #[test]
+    fn test_payload_to_task_request_sends_requested_target() {
+        let payload = BuildTriggerPayload::Manual(ManualPayload {
+            repo: "/project/buck2_test".to_string(),
+            commit_hash: "2".repeat(40),
+            triggered_by: "jackie".to_string(),
+            builds: serde_json::json!([]),
+            params: Some(BuildParams {
+                build_target: Some("//app:server".to_string()),
+                extra: Default::default(),
+            }),
+            cl_link: "HVKM7CXI".to_string(),
+            cl_id: None,
+            ref_name: None,
+            ref_type: None,
+        });
+
+        let req = payload_to_task_request(&payload).expect("build task request");
+        assert_eq!(req.targets, Some(vec!["//app:server".to_string()]));
     }
 
     #[tokio::test]
@@ -325,4 +557,83 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn test_finished_builds_emit_build_completed_once() {
+        use api_model::common::Pagination;
+        use callisto::{mega_webhook, sea_orm_active_enums::WebhookEventTypeEnum};
+
+        let temp_dir = tempdir().expect("create temp dir");
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let now = Utc::now().naive_utc();
+        let webhook = storage
+            .webhook_storage()
+            .create_webhook(
+                mega_webhook::Model {
+                    id: common::utils::generate_id(),
+                    target_url: "https://example.com/hook".to_string(),
+                    secret: "s1".to_string(),
+                    event_types: "[]".to_string(),
+                    path_filter: None,
+                    active: true,
+                    created_at: now,
+                    updated_at: now,
+                },
+                vec![WebhookEventTypeEnum::BuildCompleted],
+            )
+            .await
+            .expect("create webhook")
+            .webhook;
+
+        let (worker_tx, _worker_rx) = mpsc::unbounded_channel();
+        let (orion_base, mock_orion_handle) =
+            spawn_mock_orion(worker_tx, uuid::Uuid::now_v7().to_string()).await;
+        let orion_client = Arc::new(OrionBuildClient::new(BuildConfig {
+            enable_build: true,
+            orion_server: orion_base,
+            ..Default::default()
+        }));
+        let dispatcher = BuildDispatcher::new(storage.clone(), test_dispatch(orion_client));
+        let trigger = web_edit_trigger(
+            "/project/buck2_test",
+            "WATCHBUILD",
+            vec![Status::Modified(ProjectRelativePath::new("src/main.rs"))],
+        );
+        let trigger_id = dispatcher.dispatch(trigger).await.expect("dispatch");
+        let trigger_storage = storage.build_trigger_storage();
+        let record = trigger_storage
+            .get_by_id(trigger_id)
+            .await
+            .expect("read trigger")
+            .expect("trigger exists");
+        assert!(record.completion_watch_until.is_some());
+
+        assert_eq!(dispatcher.notify_completed_builds().await.unwrap(), 1);
+        let record = trigger_storage
+            .get_by_id(trigger_id)
+            .await
+            .expect("read trigger")
+            .expect("trigger exists");
+        assert!(record.completion_watch_until.is_none());
+        // Reported once
+        assert_eq!(dispatcher.notify_completed_builds().await.unwrap(), 0);
+
+        let (deliveries, total) = storage
+            .webhook_storage()
+            .list_deliveries(
+                webhook.id,
+                Pagination {
+                    page: 1,
+                    per_page: 20,
+                },
+            )
+            .await
+            .expect("list deliveries");
+        assert_eq!(total, 1);
+        let payload: serde_json::Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+        assert_eq!(payload["build"]["status"], "completed");
+        assert_eq!(payload["build"]["cl_link"], "WATCHBUILD");
+
+        mock_orion_handle.abort();
+    }
 }
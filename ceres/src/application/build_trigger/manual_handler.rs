@@ -11,6 +11,29 @@ use crate::application::build_trigger::{
     TriggerContext, TriggerHandler,
 };
 
+/// Get the parent commit hash for a given commit.
+/// Returns the first parent if available, otherwise returns the same commit hash.
+pub(super) async fn parent_commit(
+    storage: &Storage,
+    commit_hash: &str,
+) -> Result<String, MegaError> {
+    let commit = storage
+        .mono_storage()
+        .get_commit_by_hash(commit_hash)
+        .await?
+        .ok_or_else(|| MegaError::NotFound(format!("Commit not found: {commit_hash}")))?;
+
+    // Parse parents_id JSON array
+    let parent_ids: Vec<String> =
+        serde_json::from_value(commit.parents_id.clone()).unwrap_or_default();
+
+    // Use first parent if available, otherwise return same hash (initial commit case)
+    Ok(parent_ids
+        .first()
+        .cloned()
+        .unwrap_or_else(|| commit_hash.to_string()))
+}
+
 /// Handler for manual build triggers.
 pub struct ManualHandler {
     storage: Storage,
@@ -24,34 +47,13 @@ impl ManualHandler {
             changes_calculator: MonoChangesCalculator::new(changes_port),
         }
     }
-
-    /// Get the parent commit hash for a given commit.
-    /// Returns the first parent if available, otherwise returns the same commit hash.
-    async fn get_parent_commit(&self, commit_hash: &str) -> Result<String, MegaError> {
-        let commit = self
-            .storage
-            .mono_storage()
-            .get_commit_by_hash(commit_hash)
-            .await?
-            .ok_or_else(|| MegaError::NotFound(format!("Commit not found: {commit_hash}")))?;
-
-        // Parse parents_id JSON array
-        let parent_ids: Vec<String> =
-            serde_json::from_value(commit.parents_id.clone()).unwrap_or_default();
-
-        // Use first parent if available, otherwise return same hash (initial commit case)
-        Ok(parent_ids
-            .first()
-            .cloned()
-            .unwrap_or_else(|| commit_hash.to_string()))
-    }
 }
 
 #[async_trait]
 impl TriggerHandler for ManualHandler {
     async fn handle(&self, context: &TriggerContext) -> Result<BuildTrigger, MegaError> {
         let from_hash = if context.from_hash == context.commit_hash {
-            self.get_parent_commit(&context.commit_hash).await?
+            parent_commit(&self.storage, &context.commit_hash).await?
         } else {
             context.from_hash.clone()
         };
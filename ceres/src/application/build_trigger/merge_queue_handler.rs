@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use api_model::buck2::{status::Status, types::ProjectRelativePath};
+use async_trait::async_trait;
+use chrono::Utc;
+use common::errors::MegaError;
+use jupiter::storage::Storage;
+
+use super::changes_calculator::MonoChangesCalculator;
+use crate::application::build_trigger::{
+    BuildTrigger, BuildTriggerPayload, BuildTriggerType, ChangesPort, MergeQueuePayload,
+    TriggerContext, TriggerHandler,
+};
+
+/// Handler for merge queue batch builds: the changes of every CL in the batch are built
+/// together on top of the current main head.
+pub struct MergeQueueHandler {
+    storage: Storage,
+    changes_calculator: MonoChangesCalculator,
+}
+
+impl MergeQueueHandler {
+    pub fn new(storage: Storage, changes_port: Arc<dyn ChangesPort>) -> Self {
+        Self {
+            storage,
+            changes_calculator: MonoChangesCalculator::new(changes_port),
+        }
+    }
+}
+
+/// Appends `changes` to `builds`, skipping entries that are already present.
+fn merge_changes(
+    builds: &mut Vec<Status<ProjectRelativePath>>,
+    changes: Vec<Status<ProjectRelativePath>>,
+) {
+    for change in changes {
+        if !builds.contains(&change) {
+            builds.push(change);
+        }
+    }
+}
+
+#[async_trait]
+impl TriggerHandler for MergeQueueHandler {
+    async fn handle(&self, context: &TriggerContext) -> Result<BuildTrigger, MegaError> {
+        let batch_link = context
+            .cl_link
+            .clone()
+            .ok_or_else(|| MegaError::Other("Merge queue build requires a batch link".into()))?;
+        let items = self
+            .storage
+            .merge_queue_service
+            .get_batch_items(&batch_link)
+            .await?;
+        if items.is_empty() {
+            return Err(MegaError::NotFound(format!(
+                "Merge queue batch {batch_link} not found"
+            )));
+        }
+
+        let mut builds = Vec::new();
+        let mut cl_links = Vec::with_capacity(items.len());
+        for item in items {
+            let cl = self
+                .storage
+                .cl_storage()
+                .get_cl(&item.cl_link)
+                .await?
+                .ok_or_else(|| MegaError::NotFound(format!("CL not found: {}", item.cl_link)))?;
+            let mut cl_context: TriggerContext = cl.into();
+            cl_context.repo_path = context.repo_path.clone();
+            let changes = self
+                .changes_calculator
+                .get_builds_for_commit(&cl_context)
+                .await?;
+            merge_changes(&mut builds, changes);
+            cl_links.push(item.cl_link);
+        }
+
+        Ok(BuildTrigger {
+            trigger_type: context.trigger_type,
+            trigger_source: context.trigger_source,
+            trigger_time: Utc::now(),
+            payload: BuildTriggerPayload::MergeQueue(MergeQueuePayload {
+                repo: context.repo_path.clone(),
+                commit_hash: context.commit_hash.clone(),
+                cl_link: batch_link,
+                cl_links,
+                builds: serde_json::to_value(&builds)
+                    .map_err(|e| MegaError::Other(format!("Failed to serialize builds: {}", e)))?,
+            }),
+        })
+    }
+
+    fn trigger_type(&self) -> BuildTriggerType {
+        BuildTriggerType::MergeQueue
+    }
+}
@@ -10,25 +10,32 @@ mod changes_port;
 mod dispatcher;
 mod git_push_handler;
 mod manual_handler;
+mod merge_queue_handler;
 mod model;
 mod port;
 mod ref_resolver;
 mod retry_handler;
+mod schedule;
+mod schedule_handler;
 mod web_edit_handler;
+mod webhook_handler;
 
 // Export all models from the single model file
 pub use changes_port::ChangesPort;
 pub use model::*;
 pub use port::{BuildDispatchPort, SharedBuildDispatch};
-pub use ref_resolver::{RefResolver, RefType, ResolvedRef};
+pub use ref_resolver::{PathHead, RefResolver, RefType, ResolvedRef};
 pub mod service;
 use buck_upload_handler::BuckFileUploadHandler;
 use dispatcher::BuildDispatcher;
 use git_push_handler::GitPushHandler;
 use manual_handler::ManualHandler;
+use merge_queue_handler::MergeQueueHandler;
 use retry_handler::RetryHandler;
+use schedule_handler::ScheduleHandler;
 pub use service::BuildTriggerService;
 use web_edit_handler::WebEditHandler;
+use webhook_handler::WebhookHandler;
 
 /// Trait for handling different types of build triggers.
 #[async_trait]
@@ -68,7 +75,16 @@ impl TriggerRegistry {
         )));
         registry.register(Box::new(RetryHandler::new(changes_port.clone())));
         registry.register(Box::new(WebEditHandler::new(changes_port.clone())));
-        registry.register(Box::new(BuckFileUploadHandler::new(changes_port)));
+        registry.register(Box::new(BuckFileUploadHandler::new(changes_port.clone())));
+        registry.register(Box::new(ScheduleHandler::new(
+            storage.clone(),
+            changes_port.clone(),
+        )));
+        registry.register(Box::new(WebhookHandler::new(
+            storage.clone(),
+            changes_port.clone(),
+        )));
+        registry.register(Box::new(MergeQueueHandler::new(storage, changes_port)));
 
         registry
     }
@@ -109,4 +125,9 @@ impl TriggerRegistry {
 
         Ok(trigger_id)
     }
+
+    /// Emits `build.completed` for the watched builds that have finished.
+    pub async fn notify_completed_builds(&self) -> Result<usize, MegaError> {
+        self.dispatcher.notify_completed_builds().await
+    }
 }
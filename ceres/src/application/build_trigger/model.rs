@@ -4,7 +4,7 @@ pub use api_model::buck2::{status::Status, types::ProjectRelativePath};
 use callisto::mega_cl;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -16,6 +16,7 @@ pub enum BuildTriggerType {
     Schedule,
     WebEdit,
     BuckFileUpload,
+    MergeQueue,
 }
 
 impl fmt::Display for BuildTriggerType {
@@ -28,6 +29,7 @@ impl fmt::Display for BuildTriggerType {
             BuildTriggerType::Schedule => "schedule",
             BuildTriggerType::WebEdit => "webedit",
             BuildTriggerType::BuckFileUpload => "buck_file_upload",
+            BuildTriggerType::MergeQueue => "merge_queue",
         };
         write!(f, "{}", s)
     }
@@ -128,9 +130,15 @@ pub struct RetryPayload {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookPayload {
     pub repo: String,
+    #[serde(default)]
+    pub from_hash: String,
     pub commit_hash: String,
     pub builds: serde_json::Value,
     pub webhook_source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<BuildParams>,
     pub cl_link: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cl_id: Option<i64>,
@@ -141,10 +149,15 @@ pub struct WebhookPayload {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchedulePayload {
     pub repo: String,
+    /// Commit built by the previous run of the schedule
+    #[serde(default)]
+    pub from_hash: String,
     pub commit_hash: String,
     pub builds: serde_json::Value,
     pub schedule_name: String,
     pub cron_expression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<i64>,
     pub cl_link: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cl_id: Option<i64>,
@@ -183,6 +196,19 @@ pub struct BuckFileUploadPayload {
     pub ref_type: Option<String>,
 }
 
+/// Payload for the speculative build of a merge queue batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeQueuePayload {
+    pub repo: String,
+    /// Main head the batch is built on
+    pub commit_hash: String,
+    /// Batch link; the worker resolves it to the union of the batch CL changes
+    pub cl_link: String,
+    /// CLs of the batch in queue order
+    pub cl_links: Vec<String>,
+    pub builds: serde_json::Value,
+}
+
 /// Trigger payload - stores context specific to each trigger type
 /// This enum is serialized to JSON and stored in database's trigger_payload column
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -195,6 +221,7 @@ pub enum BuildTriggerPayload {
     Schedule(SchedulePayload),
     WebEdit(WebEditPayload),
     BuckFileUpload(BuckFileUploadPayload),
+    MergeQueue(MergeQueuePayload),
 }
 
 impl BuildTriggerPayload {
@@ -207,6 +234,7 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::Schedule(p) => &p.repo,
             BuildTriggerPayload::WebEdit(p) => &p.repo,
             BuildTriggerPayload::BuckFileUpload(p) => &p.repo,
+            BuildTriggerPayload::MergeQueue(p) => &p.repo,
         }
     }
 
@@ -219,6 +247,7 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::Schedule(p) => &p.commit_hash,
             BuildTriggerPayload::WebEdit(p) => &p.commit_hash,
             BuildTriggerPayload::BuckFileUpload(p) => &p.commit_hash,
+            BuildTriggerPayload::MergeQueue(p) => &p.commit_hash,
         }
     }
 
@@ -231,6 +260,7 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::Schedule(p) => &p.cl_link,
             BuildTriggerPayload::WebEdit(p) => &p.cl_link,
             BuildTriggerPayload::BuckFileUpload(p) => &p.cl_link,
+            BuildTriggerPayload::MergeQueue(p) => &p.cl_link,
         }
     }
 
@@ -243,6 +273,7 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::Schedule(p) => p.cl_id,
             BuildTriggerPayload::WebEdit(p) => p.cl_id,
             BuildTriggerPayload::BuckFileUpload(p) => p.cl_id,
+            BuildTriggerPayload::MergeQueue(_) => None,
         }
     }
 
@@ -255,6 +286,7 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::Schedule(_) => None,
             BuildTriggerPayload::WebEdit(p) => p.triggered_by.as_deref(),
             BuildTriggerPayload::BuckFileUpload(p) => p.triggered_by.as_deref(),
+            BuildTriggerPayload::MergeQueue(_) => None,
         }
     }
 
@@ -263,10 +295,11 @@ impl BuildTriggerPayload {
             BuildTriggerPayload::GitPush(p) => &p.from_hash,
             BuildTriggerPayload::Manual(p) => &p.commit_hash,
             BuildTriggerPayload::Retry(p) => &p.from_hash,
-            BuildTriggerPayload::Webhook(_) => "",
-            BuildTriggerPayload::Schedule(_) => "",
+            BuildTriggerPayload::Webhook(p) => &p.from_hash,
+            BuildTriggerPayload::Schedule(p) => &p.from_hash,
             BuildTriggerPayload::WebEdit(p) => &p.from_hash,
             BuildTriggerPayload::BuckFileUpload(p) => &p.from_hash,
+            BuildTriggerPayload::MergeQueue(p) => &p.commit_hash,
         }
     }
 }
@@ -326,6 +359,24 @@ pub struct TriggerContext {
     pub original_trigger_id: Option<i64>,
     pub ref_name: Option<String>,
     pub ref_type: Option<String>,
+    pub schedule: Option<ScheduleRef>,
+    pub webhook: Option<WebhookRef>,
+}
+
+/// Build schedule that fired a trigger
+#[derive(Debug, Clone)]
+pub struct ScheduleRef {
+    pub id: i64,
+    pub name: String,
+    pub cron_expression: String,
+}
+
+/// Inbound webhook delivery that fired a trigger
+#[derive(Debug, Clone)]
+pub struct WebhookRef {
+    pub source: String,
+    pub delivery_id: String,
+    pub raw_payload: serde_json::Value,
 }
 
 impl TriggerContext {
@@ -354,6 +405,8 @@ impl TriggerContext {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         }
     }
 
@@ -376,6 +429,8 @@ impl TriggerContext {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         }
     }
 
@@ -401,6 +456,8 @@ impl TriggerContext {
             original_trigger_id: Some(original_trigger_id),
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         }
     }
 
@@ -431,6 +488,82 @@ impl TriggerContext {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
+        }
+    }
+
+    /// Build context for the speculative build of a merge queue batch on top of `main_head`.
+    pub fn from_merge_queue(repo_path: String, main_head: String, batch_link: String) -> Self {
+        Self {
+            trigger_type: BuildTriggerType::MergeQueue,
+            trigger_source: TriggerSource::System,
+            triggered_by: None,
+            repo_path,
+            from_hash: main_head.clone(),
+            commit_hash: main_head,
+            cl_link: Some(batch_link),
+            cl_id: None,
+            params: None,
+            original_trigger_id: None,
+            ref_name: None,
+            ref_type: None,
+            schedule: None,
+            webhook: None,
+        }
+    }
+}
+
+impl TriggerContext {
+    /// Build context for a schedule firing against the main commit `commit_hash`.
+    pub fn from_schedule(
+        repo_path: String,
+        from_hash: String,
+        commit_hash: String,
+        schedule: ScheduleRef,
+    ) -> Self {
+        Self {
+            trigger_type: BuildTriggerType::Schedule,
+            trigger_source: TriggerSource::System,
+            triggered_by: None,
+            repo_path,
+            from_hash,
+            commit_hash,
+            cl_link: None,
+            cl_id: None,
+            params: None,
+            original_trigger_id: None,
+            ref_name: Some("main".to_string()),
+            ref_type: Some("branch".to_string()),
+            schedule: Some(schedule),
+            webhook: None,
+        }
+    }
+}
+
+impl TriggerContext {
+    /// Build context for a verified inbound webhook delivery.
+    pub fn from_webhook(
+        repo_path: String,
+        commit_hash: String,
+        params: Option<BuildParams>,
+        webhook: WebhookRef,
+    ) -> Self {
+        Self {
+            trigger_type: BuildTriggerType::Webhook,
+            trigger_source: TriggerSource::Service,
+            triggered_by: None,
+            repo_path,
+            from_hash: commit_hash.clone(),
+            commit_hash,
+            cl_link: None,
+            cl_id: None,
+            params,
+            original_trigger_id: None,
+            ref_name: None,
+            ref_type: None,
+            schedule: None,
+            webhook: Some(webhook),
         }
     }
 }
@@ -450,6 +583,8 @@ impl From<mega_cl::Model> for TriggerContext {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         }
     }
 }
@@ -464,6 +599,60 @@ pub struct CreateTriggerRequest {
     pub params: Option<BuildParams>,
 }
 
+/// Body of an inbound webhook delivery requesting a build
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WebhookTriggerRequest {
+    pub repo_path: String,
+    /// Commit to build; takes precedence over `ref_name`
+    #[serde(default)]
+    pub commit_hash: Option<String>,
+    /// Branch, tag or CL link to build when no commit is given; defaults to "main"
+    #[serde(default)]
+    pub ref_name: Option<String>,
+    #[serde(default)]
+    pub params: Option<BuildParams>,
+}
+
+/// Register an external system allowed to trigger builds through inbound webhooks
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookSourceRequest {
+    /// Unique name, used in the inbound webhook URL
+    pub name: String,
+    /// Shared secret the sender signs request bodies with (HMAC-SHA256)
+    pub secret: String,
+    /// When set, the source may only trigger builds of this path or paths below it
+    #[serde(default)]
+    pub repo_path: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// Inbound webhook source response; the secret is never returned
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookSourceResponse {
+    pub id: i64,
+    pub name: String,
+    pub repo_path: Option<String>,
+    pub enabled: bool,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<callisto::build_webhook_sources::Model> for WebhookSourceResponse {
+    fn from(model: callisto::build_webhook_sources::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            repo_path: model.repo_path,
+            enabled: model.enabled,
+            created_by: model.created_by,
+            created_at: model.created_at.and_utc(),
+            updated_at: model.updated_at.and_utc(),
+        }
+    }
+}
+
 /// Trigger detail response (new RESTful API)
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TriggerResponse {
@@ -517,7 +706,15 @@ impl TriggerResponse {
                 p.ref_name.clone(),
                 p.ref_type.clone(),
             ),
-            BuildTriggerPayload::Webhook(p) => (p.raw_payload.clone(), None, None, None),
+            BuildTriggerPayload::Webhook(p) => (
+                p.params
+                    .as_ref()
+                    .and_then(|p| serde_json::to_value(p).ok())
+                    .or_else(|| p.raw_payload.clone()),
+                None,
+                None,
+                None,
+            ),
             BuildTriggerPayload::BuckFileUpload(p) => {
                 (None, None, p.ref_name.clone(), p.ref_type.clone())
             }
@@ -549,7 +746,7 @@ pub struct ListTriggersParams {
     /// Filter by repository path
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repo_path: Option<String>,
-    /// Filter by trigger type (git_push, manual, retry, webhook, schedule, buck_file_upload)
+    /// Filter by trigger type (git_push, manual, retry, webhook, schedule, buck_file_upload, merge_queue)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trigger_type: Option<String>,
     /// Filter by trigger source (user, system, service)
@@ -558,6 +755,9 @@ pub struct ListTriggersParams {
     /// Filter by who triggered (username)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub triggered_by: Option<String>,
+    /// Filter by the build schedule that fired the trigger
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<i64>,
     /// Filter by time range start (ISO 8601 format)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_time: Option<DateTime<Utc>>,
@@ -573,8 +773,77 @@ impl From<ListTriggersParams> for jupiter::storage::build_trigger_storage::ListT
             trigger_type: params.trigger_type,
             trigger_source: params.trigger_source,
             triggered_by: params.triggered_by,
+            schedule_id: params.schedule_id,
             start_time: params.start_time,
             end_time: params.end_time,
         }
     }
 }
+
+/// Create build schedule request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateScheduleRequest {
+    pub repo_path: String,
+    /// Unique per repository path
+    pub name: String,
+    /// Cron expression evaluated in UTC, either `min hour day month weekday` or with a leading
+    /// seconds field
+    pub cron_expression: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Query for listing build schedules
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct ListSchedulesQuery {
+    /// Only return schedules of this repository path
+    pub repo_path: Option<String>,
+}
+
+/// Update build schedule request; absent fields are left unchanged
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateScheduleRequest {
+    pub name: Option<String>,
+    pub cron_expression: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Build schedule response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduleResponse {
+    pub id: i64,
+    pub repo_path: String,
+    pub name: String,
+    pub cron_expression: String,
+    pub enabled: bool,
+    pub created_by: String,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_commit_hash: Option<String>,
+    pub last_trigger_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<callisto::build_schedules::Model> for ScheduleResponse {
+    fn from(model: callisto::build_schedules::Model) -> Self {
+        Self {
+            id: model.id,
+            repo_path: model.repo_path,
+            name: model.name,
+            cron_expression: model.cron_expression,
+            enabled: model.enabled,
+            created_by: model.created_by,
+            next_run_at: model.next_run_at.map(|t| t.and_utc()),
+            last_run_at: model.last_run_at.map(|t| t.and_utc()),
+            last_commit_hash: model.last_commit_hash,
+            last_trigger_id: model.last_trigger_id,
+            created_at: model.created_at.and_utc(),
+            updated_at: model.updated_at.and_utc(),
+        }
+    }
+}
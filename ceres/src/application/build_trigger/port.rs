@@ -1,15 +1,34 @@
 use std::sync::Arc;
 
-use api_model::buck2::api::TaskBuildRequest;
+use api_model::buck2::{
+    api::TaskBuildRequest,
+    types::{BuildStatus, TargetStatusResponse, TestResultResponse},
+};
 use async_trait::async_trait;
 use common::errors::MegaError;
 
-/// Dispatches build tasks to an external execution layer (e.g. Orion).
+/// Dispatches build tasks to an external execution layer (e.g. Orion) and reads back
+/// their results.
 #[async_trait]
 pub trait BuildDispatchPort: Send + Sync {
     fn enable_build(&self) -> bool;
 
     async fn dispatch_build(&self, req: TaskBuildRequest) -> Result<String, MegaError>;
+
+    /// Targets (`package:name`) that must succeed for a CL of `path` to pass CI; empty
+    /// means every built target.
+    fn required_targets(&self, path: &str) -> Vec<String>;
+
+    /// Returns `None` when the task has not started building yet.
+    async fn latest_build_result(&self, task_id: &str) -> Result<Option<BuildStatus>, MegaError>;
+
+    async fn target_statuses(&self, task_id: &str) -> Result<Vec<TargetStatusResponse>, MegaError>;
+
+    /// Test results of the latest build of the task; empty when it reported none.
+    async fn test_results(&self, task_id: &str) -> Result<Vec<TestResultResponse>, MegaError>;
+
+    /// Targets (`package:name`) whose failures CI ignores for builds of `path`.
+    async fn quarantined_targets(&self, path: &str) -> Result<Vec<String>, MegaError>;
 }
 
 pub type SharedBuildDispatch = Arc<dyn BuildDispatchPort>;
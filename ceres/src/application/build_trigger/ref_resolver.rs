@@ -8,9 +8,13 @@
 //! - Default resolution to "main" branch when no ref specified
 //! - Ambiguous ref handling (branch and tag with same name - prefer branch)
 
+use std::path::Path;
+
 use common::errors::MegaError;
 use jupiter::storage::Storage;
 
+use crate::application::api_service::mono::MonoServiceLogic;
+
 /// Result of ref resolution containing the commit hash and metadata
 #[derive(Debug, Clone)]
 pub struct ResolvedRef {
@@ -40,6 +44,16 @@ impl RefType {
     }
 }
 
+/// Head of main for a monorepo path
+#[derive(Debug, Clone)]
+pub struct PathHead {
+    /// The path, or its nearest ancestor, that owns the main ref; the commit's tree is
+    /// rooted at this directory
+    pub ref_path: String,
+    /// The commit main points to
+    pub commit_hash: String,
+}
+
 /// Service for resolving git references to commit hashes
 pub struct RefResolver {
     storage: Storage,
@@ -86,6 +100,26 @@ impl RefResolver {
         )))
     }
 
+    /// Resolve the head of main for a monorepo path.
+    ///
+    /// Directories with their own main ref use it; any other path uses the ref of its
+    /// nearest ancestor, falling back to the root.
+    pub async fn resolve_path_head(&self, path: &str) -> Result<PathHead, MegaError> {
+        let normalized = MonoServiceLogic::normalize_repo_path(path)?;
+        let mono_storage = self.storage.mono_storage();
+        for candidate in MonoServiceLogic::repo_root_candidates(Path::new(&normalized)) {
+            if let Some(main_ref) = mono_storage.get_main_ref(&candidate).await? {
+                return Ok(PathHead {
+                    ref_path: candidate,
+                    commit_hash: main_ref.ref_commit_hash,
+                });
+            }
+        }
+        Err(MegaError::NotFound(format!(
+            "No main ref found for path {normalized}"
+        )))
+    }
+
     /// Try to resolve ref as a branch name
     async fn try_resolve_as_branch(
         &self,
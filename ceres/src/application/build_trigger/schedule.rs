@@ -0,0 +1,58 @@
+//! Cron evaluation for build schedules.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use common::errors::MegaError;
+use cron::Schedule;
+
+/// Parses a cron expression, accepting the common five-field form (`min hour day month weekday`)
+/// as well as the six- and seven-field forms with seconds (and years).
+pub fn parse_cron(expression: &str) -> Result<Schedule, MegaError> {
+    let expression = expression.trim();
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {expression}")
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&normalized)
+        .map_err(|e| MegaError::bad_request(format!("Invalid cron expression '{expression}': {e}")))
+}
+
+/// First fire time of `schedule` strictly after `after`, in UTC.
+pub fn next_run_after(schedule: &Schedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    schedule.after(&after).next()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{next_run_after, parse_cron};
+
+    #[test]
+    fn test_five_field_expression_runs_at_minute_precision() {
+        let schedule = parse_cron("30 2 * * *").unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 3, 0, 0).unwrap();
+        assert_eq!(
+            next_run_after(&schedule, now),
+            Some(Utc.with_ymd_and_hms(2026, 10, 19, 2, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_seconds_field_is_accepted() {
+        let schedule = parse_cron("0 0 */6 * * *").unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 6, 0, 0).unwrap();
+        assert_eq!(
+            next_run_after(&schedule, now),
+            Some(Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_invalid_expression_is_rejected() {
+        assert!(parse_cron("every night").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+    }
+}
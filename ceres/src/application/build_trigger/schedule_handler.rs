@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use common::errors::MegaError;
+use jupiter::storage::Storage;
+
+use super::changes_calculator::MonoChangesCalculator;
+use crate::application::build_trigger::{
+    BuildTrigger, BuildTriggerPayload, BuildTriggerType, ChangesPort, RefResolver, SchedulePayload,
+    TriggerContext, TriggerHandler,
+};
+
+/// Handler for builds fired by a build schedule.
+///
+/// The build covers the whole tree of the schedule's path at the head of main; `from_hash`
+/// records the commit built by the previous run.
+pub struct ScheduleHandler {
+    storage: Storage,
+    changes_calculator: MonoChangesCalculator,
+}
+
+impl ScheduleHandler {
+    pub fn new(storage: Storage, changes_port: Arc<dyn ChangesPort>) -> Self {
+        Self {
+            storage,
+            changes_calculator: MonoChangesCalculator::new(changes_port),
+        }
+    }
+}
+
+#[async_trait]
+impl TriggerHandler for ScheduleHandler {
+    async fn handle(&self, context: &TriggerContext) -> Result<BuildTrigger, MegaError> {
+        let schedule = context
+            .schedule
+            .clone()
+            .ok_or_else(|| MegaError::Other("Scheduled build requires a schedule".to_string()))?;
+
+        let head = RefResolver::new(self.storage.clone())
+            .resolve_path_head(&context.repo_path)
+            .await?;
+        let builds = self
+            .changes_calculator
+            .get_builds_for_tree(&context.repo_path, &head.ref_path, &context.commit_hash)
+            .await?;
+
+        let now = Utc::now();
+        let cl_link = format!(
+            "schedule-{}-{}",
+            now.timestamp_millis(),
+            &context.commit_hash[..8.min(context.commit_hash.len())]
+        );
+
+        Ok(BuildTrigger {
+            trigger_type: BuildTriggerType::Schedule,
+            trigger_source: context.trigger_source,
+            trigger_time: now,
+            payload: BuildTriggerPayload::Schedule(SchedulePayload {
+                repo: context.repo_path.clone(),
+                from_hash: context.from_hash.clone(),
+                commit_hash: context.commit_hash.clone(),
+                builds: serde_json::to_value(&builds)
+                    .map_err(|e| MegaError::Other(format!("Failed to serialize builds: {}", e)))?,
+                schedule_name: schedule.name,
+                cron_expression: schedule.cron_expression,
+                schedule_id: Some(schedule.id),
+                cl_link,
+                cl_id: None,
+            }),
+        })
+    }
+
+    fn trigger_type(&self) -> BuildTriggerType {
+        BuildTriggerType::Schedule
+    }
+}
@@ -20,6 +20,14 @@
 //! - Manual: `service.create_manual_trigger(...)`
 //! - Retry: `service.retry_trigger(...)`
 //!
+//! ### 4. Scheduled Builds
+//! Named cron schedules per path are managed with `service.create_schedule(...)` and
+//! friends; a background task calls `service.run_due_schedules()` to fire them.
+//!
+//! ### 5. Inbound Webhooks
+//! External systems registered with `service.create_webhook_source(...)` request builds
+//! with HMAC-signed deliveries handled by `service.trigger_for_webhook(...)`.
+//!
 //! ## Architecture Note
 //! This service acts as a Facade, encapsulating internal logic such as change
 //! calculation and Orion integration. External callers should only interact
@@ -29,10 +37,21 @@ use std::sync::Arc;
 
 use api_model::common::Pagination;
 use common::errors::MegaError;
-use jupiter::storage::Storage;
+use jupiter::{
+    service::webhook_service::{
+        decrypt_webhook_secret, encrypt_webhook_secret, verify_webhook_signature,
+    },
+    storage::Storage,
+};
 
-use super::model::{
-    BuildParams, GitPushEvent, ListTriggersParams, TriggerContext, TriggerRecord, TriggerResponse,
+use super::{
+    model::{
+        BuildParams, CreateScheduleRequest, CreateWebhookSourceRequest, GitPushEvent,
+        ListTriggersParams, ScheduleRef, ScheduleResponse, TriggerContext, TriggerRecord,
+        TriggerResponse, UpdateScheduleRequest, WebhookRef, WebhookSourceResponse,
+        WebhookTriggerRequest,
+    },
+    schedule::{next_run_after, parse_cron},
 };
 use crate::application::{
     api_service::cache::GitObjectCache,
@@ -257,6 +276,259 @@ impl BuildTriggerService {
         Ok((responses, total as i64))
     }
 
+    /// Creates a named build schedule for a repository path.
+    pub async fn create_schedule(
+        &self,
+        req: CreateScheduleRequest,
+        created_by: String,
+    ) -> Result<ScheduleResponse, MegaError> {
+        self.check_build_enabled()?;
+
+        let name = req.name.trim().to_string();
+        if name.is_empty() {
+            return Err(MegaError::bad_request("Schedule name must not be empty"));
+        }
+        let cron = parse_cron(&req.cron_expression)?;
+        let trigger_storage = self.storage.build_trigger_storage();
+        if trigger_storage
+            .get_schedule_by_name(&req.repo_path, &name)
+            .await?
+            .is_some()
+        {
+            return Err(MegaError::conflict(format!(
+                "Schedule {name} already exists for {}",
+                req.repo_path
+            )));
+        }
+
+        let now = chrono::Utc::now();
+        let schedule = callisto::build_schedules::Model {
+            id: common::utils::generate_id(),
+            repo_path: req.repo_path,
+            name,
+            cron_expression: req.cron_expression.trim().to_string(),
+            enabled: req.enabled,
+            created_by,
+            next_run_at: req
+                .enabled
+                .then(|| next_run_after(&cron, now))
+                .flatten()
+                .map(|t| t.naive_utc()),
+            last_run_at: None,
+            last_commit_hash: None,
+            last_trigger_id: None,
+            created_at: now.naive_utc(),
+            updated_at: now.naive_utc(),
+        };
+        Ok(trigger_storage.insert_schedule(schedule).await?.into())
+    }
+
+    /// Lists build schedules, optionally only those of one repository path.
+    pub async fn list_schedules(
+        &self,
+        repo_path: Option<String>,
+    ) -> Result<Vec<ScheduleResponse>, MegaError> {
+        self.check_build_enabled()?;
+        let schedules = self
+            .storage
+            .build_trigger_storage()
+            .list_schedules(repo_path.as_deref())
+            .await?;
+        Ok(schedules.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn get_schedule(&self, id: i64) -> Result<ScheduleResponse, MegaError> {
+        self.check_build_enabled()?;
+        Ok(self.find_schedule(id).await?.into())
+    }
+
+    /// Renames, reschedules, enables or disables a build schedule.
+    ///
+    /// Only the schedule's creator or an admin may change it.
+    pub async fn update_schedule(
+        &self,
+        id: i64,
+        req: UpdateScheduleRequest,
+        actor: &str,
+        is_admin: bool,
+    ) -> Result<ScheduleResponse, MegaError> {
+        self.check_build_enabled()?;
+        let mut schedule = self.find_schedule(id).await?;
+        ensure_schedule_owner(&schedule, actor, is_admin)?;
+        let trigger_storage = self.storage.build_trigger_storage();
+
+        if let Some(name) = req.name {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(MegaError::bad_request("Schedule name must not be empty"));
+            }
+            if name != schedule.name
+                && trigger_storage
+                    .get_schedule_by_name(&schedule.repo_path, &name)
+                    .await?
+                    .is_some()
+            {
+                return Err(MegaError::conflict(format!(
+                    "Schedule {name} already exists for {}",
+                    schedule.repo_path
+                )));
+            }
+            schedule.name = name;
+        }
+        if let Some(cron_expression) = req.cron_expression {
+            parse_cron(&cron_expression)?;
+            schedule.cron_expression = cron_expression.trim().to_string();
+        }
+        if let Some(enabled) = req.enabled {
+            schedule.enabled = enabled;
+        }
+
+        let now = chrono::Utc::now();
+        schedule.next_run_at = if schedule.enabled {
+            next_run_after(&parse_cron(&schedule.cron_expression)?, now).map(|t| t.naive_utc())
+        } else {
+            None
+        };
+        schedule.updated_at = now.naive_utc();
+        Ok(trigger_storage.update_schedule(schedule).await?.into())
+    }
+
+    /// Deletes a build schedule. Only the schedule's creator or an admin may delete it.
+    pub async fn delete_schedule(
+        &self,
+        id: i64,
+        actor: &str,
+        is_admin: bool,
+    ) -> Result<(), MegaError> {
+        self.check_build_enabled()?;
+        let schedule = self.find_schedule(id).await?;
+        ensure_schedule_owner(&schedule, actor, is_admin)?;
+        if self
+            .storage
+            .build_trigger_storage()
+            .delete_schedule(id)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(MegaError::NotFound(format!("Schedule not found: {id}")))
+        }
+    }
+
+    /// Fires every enabled schedule whose next run time has passed and returns how many
+    /// builds were triggered.
+    ///
+    /// Each due run is first claimed by moving the schedule to its next run after now, so
+    /// concurrent schedulers dispatch it once. A schedule that finds main unchanged since its
+    /// previous run is skipped. Missed runs are not caught up.
+    pub async fn run_due_schedules(&self) -> Result<usize, MegaError> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+        let now = chrono::Utc::now();
+        let trigger_storage = self.storage.build_trigger_storage();
+        let mut fired = 0;
+        for mut schedule in trigger_storage.get_due_schedules(now.naive_utc()).await? {
+            let cron = match parse_cron(&schedule.cron_expression) {
+                Ok(cron) => cron,
+                Err(e) => {
+                    tracing::warn!("Disabling build schedule {}: {}", schedule.id, e);
+                    schedule.enabled = false;
+                    schedule.next_run_at = None;
+                    schedule.updated_at = now.naive_utc();
+                    trigger_storage.update_schedule(schedule).await?;
+                    continue;
+                }
+            };
+
+            let Some(due_at) = schedule.next_run_at else {
+                continue;
+            };
+            let next_run_at = next_run_after(&cron, now).map(|t| t.naive_utc());
+            if !trigger_storage
+                .claim_schedule_run(schedule.id, due_at, next_run_at, now.naive_utc())
+                .await?
+            {
+                tracing::debug!(
+                    "Build schedule {} was claimed by another scheduler",
+                    schedule.id
+                );
+                continue;
+            }
+
+            match self.fire_schedule(&schedule).await {
+                Ok(Some((commit_hash, trigger_id))) => {
+                    schedule.next_run_at = next_run_at;
+                    schedule.last_run_at = Some(now.naive_utc());
+                    schedule.last_commit_hash = Some(commit_hash);
+                    schedule.last_trigger_id = Some(trigger_id);
+                    schedule.updated_at = now.naive_utc();
+                    trigger_storage.update_schedule(schedule).await?;
+                    fired += 1;
+                }
+                Ok(None) => {
+                    tracing::info!(
+                        "Build schedule {} skipped, main has not changed since the last run",
+                        schedule.id
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Build schedule {} failed to fire: {}", schedule.id, e);
+                }
+            }
+        }
+        Ok(fired)
+    }
+
+    /// Emits `build.completed` webhooks for the watched builds that Orion reports as
+    /// finished and returns how many were reported.
+    pub async fn notify_completed_builds(&self) -> Result<usize, MegaError> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+        self.registry.notify_completed_builds().await
+    }
+
+    /// Triggers the build of a schedule on the head of main for its path, returning the
+    /// commit and trigger id; `None` when main has not moved since the previous run.
+    async fn fire_schedule(
+        &self,
+        schedule: &callisto::build_schedules::Model,
+    ) -> Result<Option<(String, i64)>, MegaError> {
+        let head = RefResolver::new(self.storage.clone())
+            .resolve_path_head(&schedule.repo_path)
+            .await?
+            .commit_hash;
+        if schedule.last_commit_hash.as_deref() == Some(head.as_str()) {
+            return Ok(None);
+        }
+
+        let from_hash = schedule
+            .last_commit_hash
+            .clone()
+            .unwrap_or_else(|| head.clone());
+        let context = TriggerContext::from_schedule(
+            schedule.repo_path.clone(),
+            from_hash,
+            head.clone(),
+            ScheduleRef {
+                id: schedule.id,
+                name: schedule.name.clone(),
+                cron_expression: schedule.cron_expression.clone(),
+            },
+        );
+        let trigger_id = self.registry.trigger_build(context).await?;
+        Ok(Some((head, trigger_id)))
+    }
+
+    async fn find_schedule(&self, id: i64) -> Result<callisto::build_schedules::Model, MegaError> {
+        self.storage
+            .build_trigger_storage()
+            .get_schedule(id)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("Schedule not found: {id}")))
+    }
+
     /// Low-level interface for direct build triggering via context.
     #[allow(dead_code)]
     pub(crate) async fn trigger_with_context(
@@ -267,6 +539,187 @@ impl BuildTriggerService {
         self.registry.trigger_build(context).await
     }
 
+    /// Registers an external system that may trigger builds through inbound webhooks.
+    pub async fn create_webhook_source(
+        &self,
+        req: CreateWebhookSourceRequest,
+        created_by: String,
+    ) -> Result<WebhookSourceResponse, MegaError> {
+        let name = req.name.trim().to_string();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(MegaError::bad_request(
+                "Webhook source name must be non-empty and contain only letters, digits, '-' or '_'",
+            ));
+        }
+        if req.secret.is_empty() {
+            return Err(MegaError::bad_request("Webhook secret must not be empty"));
+        }
+        let trigger_storage = self.storage.build_trigger_storage();
+        if trigger_storage
+            .get_webhook_source_by_name(&name)
+            .await?
+            .is_some()
+        {
+            return Err(MegaError::conflict(format!(
+                "Webhook source {name} already exists"
+            )));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let source = callisto::build_webhook_sources::Model {
+            id: common::utils::generate_id(),
+            name,
+            secret: encrypt_webhook_secret(&req.secret)?,
+            repo_path: req.repo_path,
+            enabled: req.enabled,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        };
+        Ok(trigger_storage.insert_webhook_source(source).await?.into())
+    }
+
+    pub async fn list_webhook_sources(&self) -> Result<Vec<WebhookSourceResponse>, MegaError> {
+        let sources = self
+            .storage
+            .build_trigger_storage()
+            .list_webhook_sources()
+            .await?;
+        Ok(sources.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn delete_webhook_source(&self, id: i64) -> Result<(), MegaError> {
+        if self
+            .storage
+            .build_trigger_storage()
+            .delete_webhook_source(id)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(MegaError::NotFound(format!(
+                "Webhook source not found: {id}"
+            )))
+        }
+    }
+
+    /// Triggers a build for an inbound webhook delivery.
+    ///
+    /// `body` must be signed with the source's secret (`sha256=<hex hmac>`). A delivery id
+    /// that was already accepted returns the trigger it created instead of building again.
+    pub async fn trigger_for_webhook(
+        &self,
+        source_name: &str,
+        delivery_id: &str,
+        signature: &str,
+        body: &[u8],
+    ) -> Result<TriggerResponse, MegaError> {
+        self.check_build_enabled()?;
+
+        let trigger_storage = self.storage.build_trigger_storage();
+        // Unknown, disabled and badly signed sources are indistinguishable to the sender.
+        let source = trigger_storage
+            .get_webhook_source_by_name(source_name)
+            .await?
+            .filter(|source| source.enabled)
+            .ok_or_else(|| MegaError::unauthorized("Invalid webhook signature"))?;
+        let secret = decrypt_webhook_secret(&source.secret)?;
+        if !verify_webhook_signature(&secret, body, signature) {
+            return Err(MegaError::unauthorized("Invalid webhook signature"));
+        }
+
+        let raw_payload: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| MegaError::bad_request(format!("Invalid webhook payload: {e}")))?;
+        let req: WebhookTriggerRequest = serde_json::from_value(raw_payload.clone())
+            .map_err(|e| MegaError::bad_request(format!("Invalid webhook payload: {e}")))?;
+        if let Some(allowed) = &source.repo_path
+            && !path_is_within(&req.repo_path, allowed)
+        {
+            return Err(MegaError::forbidden(format!(
+                "Webhook source {} may not trigger builds for {}",
+                source.name, req.repo_path
+            )));
+        }
+        let delivery_id = delivery_id.trim();
+        if delivery_id.is_empty() {
+            return Err(MegaError::bad_request("Missing webhook delivery id"));
+        }
+
+        let delivery = callisto::build_webhook_deliveries::Model {
+            id: common::utils::generate_id(),
+            source_id: source.id,
+            delivery_id: delivery_id.to_string(),
+            trigger_id: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        if let Some(existing) = trigger_storage
+            .claim_webhook_delivery(delivery.clone())
+            .await?
+        {
+            return match existing.trigger_id {
+                Some(trigger_id) => self.get_trigger_response(trigger_id).await,
+                None => Err(MegaError::conflict(format!(
+                    "Delivery {delivery_id} is already being processed"
+                ))),
+            };
+        }
+
+        let result = self
+            .trigger_webhook_delivery(req, raw_payload, &source.name, delivery_id)
+            .await;
+        match result {
+            Ok(trigger_id) => {
+                trigger_storage
+                    .set_webhook_delivery_trigger(delivery.id, trigger_id)
+                    .await?;
+                self.get_trigger_response(trigger_id).await
+            }
+            Err(e) => {
+                // Let the sender redeliver after a failure.
+                if let Err(cleanup) = trigger_storage.delete_webhook_delivery(delivery.id).await {
+                    tracing::warn!(
+                        "Failed to release webhook delivery {delivery_id} of {}: {cleanup}",
+                        source.name
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn trigger_webhook_delivery(
+        &self,
+        req: WebhookTriggerRequest,
+        raw_payload: serde_json::Value,
+        source: &str,
+        delivery_id: &str,
+    ) -> Result<i64, MegaError> {
+        let requested = req.commit_hash.or(req.ref_name);
+        let resolved = RefResolver::new(self.storage.clone())
+            .resolve(requested.as_deref())
+            .await
+            .map_err(|_| {
+                let ref_str = requested.unwrap_or_else(|| "main".to_string());
+                MegaError::NotFound(format!("Reference not found: {ref_str}"))
+            })?;
+
+        let context = TriggerContext::from_webhook(
+            req.repo_path,
+            resolved.commit_hash,
+            req.params,
+            WebhookRef {
+                source: source.to_string(),
+                delivery_id: delivery_id.to_string(),
+                raw_payload,
+            },
+        );
+        self.registry.trigger_build(context).await
+    }
+
     async fn get_trigger_response(&self, trigger_id: i64) -> Result<TriggerResponse, MegaError> {
         let model = self
             .storage
@@ -281,6 +734,39 @@ impl BuildTriggerService {
     }
 }
 
+/// Rejects changes to a schedule by anyone other than its creator or an admin.
+fn ensure_schedule_owner(
+    schedule: &callisto::build_schedules::Model,
+    actor: &str,
+    is_admin: bool,
+) -> Result<(), MegaError> {
+    if is_admin || schedule.created_by == actor {
+        Ok(())
+    } else {
+        Err(MegaError::Forbidden(format!(
+            "Schedule {} belongs to {}",
+            schedule.id, schedule.created_by
+        )))
+    }
+}
+
+/// Whether `path` is `root` or lies below it. Paths with `.` or `..` segments are rejected
+/// so they cannot climb out of `root` after passing the prefix check.
+fn path_is_within(path: &str, root: &str) -> bool {
+    if path
+        .split('/')
+        .any(|segment| segment == ".." || segment == ".")
+    {
+        return false;
+    }
+    let root = root.trim_end_matches('/');
+    root.is_empty()
+        || path == root
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::tempdir;
@@ -350,4 +836,237 @@ mod tests {
 
         assert!(err.to_string().contains("No repository root found"));
     }
+
+    struct StubDispatch;
+
+    #[async_trait::async_trait]
+    impl crate::application::build_trigger::BuildDispatchPort for StubDispatch {
+        fn enable_build(&self) -> bool {
+            true
+        }
+
+        async fn dispatch_build(
+            &self,
+            _req: api_model::buck2::api::TaskBuildRequest,
+        ) -> Result<String, MegaError> {
+            Ok(uuid::Uuid::new_v4().to_string())
+        }
+
+        fn required_targets(&self, _path: &str) -> Vec<String> {
+            vec![]
+        }
+
+        async fn latest_build_result(
+            &self,
+            _task_id: &str,
+        ) -> Result<Option<api_model::buck2::types::BuildStatus>, MegaError> {
+            Ok(None)
+        }
+
+        async fn target_statuses(
+            &self,
+            _task_id: &str,
+        ) -> Result<Vec<api_model::buck2::types::TargetStatusResponse>, MegaError> {
+            Ok(vec![])
+        }
+
+        async fn test_results(
+            &self,
+            _task_id: &str,
+        ) -> Result<Vec<api_model::buck2::types::TestResultResponse>, MegaError> {
+            Ok(vec![])
+        }
+
+        async fn quarantined_targets(&self, _path: &str) -> Result<Vec<String>, MegaError> {
+            Ok(vec![])
+        }
+    }
+
+    struct NoChanges;
+
+    #[async_trait::async_trait]
+    impl ChangesPort for NoChanges {
+        async fn get_commit_blobs(
+            &self,
+            _commit_hash: &str,
+        ) -> Result<Vec<(std::path::PathBuf, git_internal::hash::ObjectHash)>, MegaError> {
+            Ok(vec![])
+        }
+
+        async fn cl_files_list(
+            &self,
+            _old_files: Vec<(std::path::PathBuf, git_internal::hash::ObjectHash)>,
+            _new_files: Vec<(std::path::PathBuf, git_internal::hash::ObjectHash)>,
+        ) -> Result<Vec<crate::model::change_list::ClDiffFile>, MegaError> {
+            Ok(vec![])
+        }
+    }
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        format!(
+            "sha256={}",
+            jupiter::service::webhook_service::compute_hmac_signature(secret, body)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_webhook_trigger_verifies_signature_and_deduplicates_deliveries() {
+        use std::str::FromStr;
+
+        use git_internal::{hash::ObjectHash, internal::object::commit::Commit};
+
+        let temp_dir = tempdir().expect("create temp dir");
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let tree = ObjectHash::from_str(&"1".repeat(40)).unwrap();
+        let commit = Commit::from_tree_id(tree, vec![], "initial");
+        let commit_hash = commit.id.to_string();
+        storage
+            .mono_storage()
+            .save_mega_commits(vec![commit], None)
+            .await
+            .expect("save commit");
+        let now = chrono::Utc::now().naive_utc();
+        storage
+            .build_trigger_storage()
+            .insert_webhook_source(callisto::build_webhook_sources::Model {
+                id: common::utils::generate_id(),
+                name: "ci".to_string(),
+                // Plaintext secrets are still accepted by `decrypt_webhook_secret`.
+                secret: "s3cret".to_string(),
+                repo_path: Some("/project".to_string()),
+                enabled: true,
+                created_by: "admin".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .expect("insert source");
+        let service =
+            BuildTriggerService::new(storage.clone(), Arc::new(StubDispatch), Arc::new(NoChanges));
+
+        let body = serde_json::json!({
+            "repo_path": "/project/app",
+            "commit_hash": commit_hash,
+            "params": { "build_target": "//app:all" },
+        })
+        .to_string();
+        let err = service
+            .trigger_for_webhook(
+                "ci",
+                "d-1",
+                &sign("wrong", body.as_bytes()),
+                body.as_bytes(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Unauthorized(_)), "{err:?}");
+        let err = service
+            .trigger_for_webhook(
+                "nope",
+                "d-1",
+                &sign("s3cret", body.as_bytes()),
+                body.as_bytes(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Unauthorized(_)), "{err:?}");
+
+        let signature = sign("s3cret", body.as_bytes());
+        let first = service
+            .trigger_for_webhook("ci", "d-1", &signature, body.as_bytes())
+            .await
+            .expect("trigger build");
+        assert_eq!(first.trigger_type, "webhook");
+        assert_eq!(first.trigger_source, "service");
+        assert_eq!(first.commit_hash, commit_hash);
+        assert_eq!(first.params.unwrap()["build_target"], "//app:all");
+        assert!(first.task_id.is_some());
+
+        let again = service
+            .trigger_for_webhook("ci", "d-1", &signature, body.as_bytes())
+            .await
+            .expect("redelivery");
+        assert_eq!(again.id, first.id);
+        let next = service
+            .trigger_for_webhook("ci", "d-2", &signature, body.as_bytes())
+            .await
+            .expect("new delivery");
+        assert_ne!(next.id, first.id);
+
+        let outside =
+            serde_json::json!({ "repo_path": "/doc", "commit_hash": commit_hash }).to_string();
+        let err = service
+            .trigger_for_webhook(
+                "ci",
+                "d-3",
+                &sign("s3cret", outside.as_bytes()),
+                outside.as_bytes(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_schedule_changes_are_limited_to_creator_and_admins() {
+        let temp_dir = tempdir().expect("create temp dir");
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service =
+            BuildTriggerService::new(storage.clone(), Arc::new(StubDispatch), Arc::new(NoChanges));
+        let schedule = service
+            .create_schedule(
+                CreateScheduleRequest {
+                    repo_path: "/project".to_string(),
+                    name: "nightly".to_string(),
+                    cron_expression: "0 0 2 * * *".to_string(),
+                    enabled: true,
+                },
+                "alice".to_string(),
+            )
+            .await
+            .expect("create schedule");
+        let rename = || UpdateScheduleRequest {
+            name: Some("renamed".to_string()),
+            cron_expression: None,
+            enabled: None,
+        };
+
+        let err = service
+            .update_schedule(schedule.id, rename(), "mallory", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)), "{err:?}");
+        let err = service
+            .delete_schedule(schedule.id, "mallory", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)), "{err:?}");
+        assert_eq!(
+            service.get_schedule(schedule.id).await.unwrap().name,
+            "nightly"
+        );
+
+        let updated = service
+            .update_schedule(schedule.id, rename(), "alice", false)
+            .await
+            .expect("creator may edit");
+        assert_eq!(updated.name, "renamed");
+        service
+            .delete_schedule(schedule.id, "root", true)
+            .await
+            .expect("admin may delete");
+    }
+
+    #[test]
+    fn test_path_is_within() {
+        assert!(path_is_within("/project/app", "/project"));
+        assert!(path_is_within("/project", "/project/"));
+        assert!(path_is_within("/anything", "/"));
+        assert!(!path_is_within("/projectx", "/project"));
+        assert!(!path_is_within("/doc", "/project"));
+        assert!(!path_is_within("/project/../secret", "/project"));
+        assert!(!path_is_within("/project/./..", "/project"));
+        assert!(!path_is_within("/project/app/..", "/project"));
+        assert!(path_is_within("/project/app..v2", "/project"));
+    }
 }
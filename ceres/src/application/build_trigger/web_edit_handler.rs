@@ -98,6 +98,8 @@ mod tests {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         };
 
         assert_eq!(resolve_cl_link(&context, 1_700_000_000_000), "HVKM7CXI");
@@ -118,6 +120,8 @@ mod tests {
             original_trigger_id: None,
             ref_name: None,
             ref_type: None,
+            schedule: None,
+            webhook: None,
         };
 
         assert_eq!(
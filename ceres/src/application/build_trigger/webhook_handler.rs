@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use common::errors::MegaError;
+use jupiter::storage::Storage;
+
+use super::{changes_calculator::MonoChangesCalculator, manual_handler::parent_commit};
+use crate::application::build_trigger::{
+    BuildTrigger, BuildTriggerPayload, BuildTriggerType, ChangesPort, TriggerContext,
+    TriggerHandler, WebhookPayload,
+};
+
+/// Handler for builds requested by an external system through an inbound webhook.
+///
+/// Like a manual trigger, the build covers the changes of the requested commit.
+pub struct WebhookHandler {
+    storage: Storage,
+    changes_calculator: MonoChangesCalculator,
+}
+
+impl WebhookHandler {
+    pub fn new(storage: Storage, changes_port: Arc<dyn ChangesPort>) -> Self {
+        Self {
+            storage,
+            changes_calculator: MonoChangesCalculator::new(changes_port),
+        }
+    }
+}
+
+#[async_trait]
+impl TriggerHandler for WebhookHandler {
+    async fn handle(&self, context: &TriggerContext) -> Result<BuildTrigger, MegaError> {
+        let webhook = context
+            .webhook
+            .clone()
+            .ok_or_else(|| MegaError::Other("Webhook build requires a delivery".to_string()))?;
+
+        let from_hash = if context.from_hash == context.commit_hash {
+            parent_commit(&self.storage, &context.commit_hash).await?
+        } else {
+            context.from_hash.clone()
+        };
+        let adjusted_context = TriggerContext {
+            from_hash: from_hash.clone(),
+            ..context.clone()
+        };
+        let builds = self
+            .changes_calculator
+            .get_builds_for_commit(&adjusted_context)
+            .await?;
+
+        let now = Utc::now();
+        let cl_link = format!(
+            "webhook-{}-{}",
+            now.timestamp_millis(),
+            &context.commit_hash[..8.min(context.commit_hash.len())]
+        );
+
+        Ok(BuildTrigger {
+            trigger_type: BuildTriggerType::Webhook,
+            trigger_source: context.trigger_source,
+            trigger_time: now,
+            payload: BuildTriggerPayload::Webhook(WebhookPayload {
+                repo: context.repo_path.clone(),
+                from_hash,
+                commit_hash: context.commit_hash.clone(),
+                builds: serde_json::to_value(&builds)
+                    .map_err(|e| MegaError::Other(format!("Failed to serialize builds: {}", e)))?,
+                webhook_source: webhook.source,
+                delivery_id: Some(webhook.delivery_id),
+                params: context.params.clone(),
+                cl_link,
+                cl_id: None,
+                raw_payload: Some(webhook.raw_payload),
+            }),
+        })
+    }
+
+    fn trigger_type(&self) -> BuildTriggerType {
+        BuildTriggerType::Webhook
+    }
+}
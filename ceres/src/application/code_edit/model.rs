@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use callisto::{entity_ext::generate_link, mega_cl, mega_refs, sea_orm_active_enums::ConvTypeEnum};
 use common::errors::MegaError;
@@ -93,10 +93,11 @@ pub(crate) trait Checker {
     async fn check(
         &self,
         storage: Storage,
+        build_dispatch: Option<SharedBuildDispatch>,
         username: &str,
         cl: &mega_cl::Model,
     ) -> Result<(), MegaError> {
-        let check_reg = CheckerRegistry::new(storage.into(), username.to_string());
+        let check_reg = CheckerRegistry::new(storage.into(), username.to_string(), build_dispatch);
         check_reg.run_checks(cl.clone().into()).await?;
         Ok(())
     }
@@ -154,6 +155,16 @@ fn fresh_or_fallback_cl(
     }
 }
 
+/// Timeline text for one push of `commits` (parents first), or `None` when nothing is new.
+fn push_summary(commits: &[Commit]) -> Option<String> {
+    let tip = commits.last()?;
+    let tip_line = format!("{} {}", &tip.id.to_string()[..7], tip.format_message());
+    Some(match commits.len() {
+        1 => tip_line,
+        n => format!("pushed {n} commits, latest: {tip_line}"),
+    })
+}
+
 pub(crate) struct CodeEditService<FMT, VT, AC, TCB, CK, HD, DR>
 where
     FMT: ConversationMessageFormater,
@@ -325,7 +336,7 @@ impl<
                 ConvTypeEnum::Comment,
             )
             .await?;
-        dispatch_cl_webhook(storage, WebhookEvent::ClCreated, &cl);
+        dispatch_cl_webhook(storage, WebhookEvent::ClCreated, &cl).await;
         Ok(cl)
     }
 
@@ -360,6 +371,39 @@ impl<
         }
     }
 
+    /// Attaches the pushed commits to the CL and adds one timeline entry summarizing the
+    /// commits the CL has not seen yet. The per-commit list is kept by `save_cl_commits`.
+    pub async fn record_cl_commits(
+        &self,
+        storage: &Storage,
+        cl: &mega_cl::Model,
+        commits: Vec<Commit>,
+        username: &str,
+    ) -> Result<(), MegaError> {
+        let known: HashSet<String> = storage
+            .cl_storage()
+            .get_cl_commits(&cl.link)
+            .await?
+            .into_iter()
+            .map(|m| m.commit_sha)
+            .collect();
+        let new_commits: Vec<Commit> = commits
+            .into_iter()
+            .filter(|c| !known.contains(&c.id.to_string()))
+            .collect();
+        let Some(summary) = push_summary(&new_commits) else {
+            return Ok(());
+        };
+        storage
+            .conversation_storage()
+            .add_conversation(&cl.link, username, Some(summary), ConvTypeEnum::Commit)
+            .await?;
+        storage
+            .cl_storage()
+            .save_cl_commits(&cl.link, new_commits)
+            .await
+    }
+
     pub async fn trigger_build(
         &self,
         storage: Storage,
@@ -376,10 +420,13 @@ impl<
     pub async fn trigger_check(
         &self,
         storage: Storage,
+        build_dispatch: Option<SharedBuildDispatch>,
         username: &str,
         cl: &mega_cl::Model,
     ) -> Result<(), MegaError> {
-        self.checker.check(storage, username, cl).await
+        self.checker
+            .check(storage, build_dispatch, username, cl)
+            .await
     }
 
     pub async fn assign_reviewer(
@@ -398,9 +445,16 @@ impl<
         cl: &mega_cl::Model,
         username: &str,
     ) -> Result<(), MegaError> {
-        self.trigger_build(storage.clone(), git_cache, build_dispatch, cl, username)
+        self.trigger_build(
+            storage.clone(),
+            git_cache,
+            build_dispatch.clone(),
+            cl,
+            username,
+        )
+        .await?;
+        self.trigger_check(storage, Some(build_dispatch), username, cl)
             .await?;
-        self.trigger_check(storage, username, cl).await?;
         Ok(())
     }
 }
@@ -409,7 +463,9 @@ impl<
 mod tests {
     use callisto::sea_orm_active_enums::MergeStatusEnum;
 
-    use super::{cl_with_latest_to_hash, fresh_or_fallback_cl};
+    use git_internal::{hash::ObjectHash, internal::object::commit::Commit};
+
+    use super::{cl_with_latest_to_hash, fresh_or_fallback_cl, push_summary};
 
     fn sample_cl(to_hash: &str) -> callisto::mega_cl::Model {
         callisto::mega_cl::Model {
@@ -457,4 +513,19 @@ mod tests {
         assert_eq!(updated.link, original.link);
         assert_eq!(updated.path, original.path);
     }
+
+    #[test]
+    fn test_push_summary_is_one_entry_per_push() {
+        let first = Commit::from_tree_id(ObjectHash::default(), vec![], "first");
+        let second = Commit::from_tree_id(ObjectHash::default(), vec![first.id], "second");
+
+        assert_eq!(push_summary(&[]), None);
+        let single = push_summary(std::slice::from_ref(&first)).unwrap();
+        assert!(single.starts_with(&first.id.to_string()[..7]));
+        assert!(single.ends_with("first"));
+
+        let series = push_summary(&[first, second.clone()]).unwrap();
+        assert!(series.starts_with("pushed 2 commits, latest: "));
+        assert!(series.contains(&second.id.to_string()[..7]));
+    }
 }
@@ -9,6 +9,7 @@ use async_trait::async_trait;
 use callisto::{mega_cl, mega_code_review_anchor};
 use common::{errors::MegaError, utils::ZERO_ID};
 use futures::{StreamExt, stream};
+use git_internal::internal::object::commit::Commit;
 use jupiter::storage::Storage;
 
 use crate::{
@@ -19,6 +20,10 @@ use crate::{
         },
         build_trigger::SharedBuildDispatch,
         code_edit::{on_push::OnpushCodeEdit, utils::get_changed_files},
+        webhook::{
+            AuthorPayload, PushCommitPayload, PushPayload, WebhookEvent, WebhookEventPayload,
+            dispatch_webhook_event,
+        },
     },
     bus::{ApplicationEventHandler, TransportEvent},
 };
@@ -36,6 +41,7 @@ pub async fn dispatch_mono_receive_pack_finalized(
     from_hash: String,
     to_hash: String,
     username: Option<String>,
+    commits: Vec<Commit>,
 ) -> Result<(), MegaError> {
     let username = username.unwrap_or_else(|| String::from("Anonymous"));
     let repo_path_str = repo_path
@@ -46,6 +52,35 @@ pub async fn dispatch_mono_receive_pack_finalized(
     let cl_model = editor
         .update_or_create_cl(&storage, &from_hash, &to_hash, &username)
         .await?;
+    let push_payload = PushPayload {
+        branch: base_branch.clone(),
+        before: from_hash.clone(),
+        after: to_hash.clone(),
+        pusher: AuthorPayload {
+            name: username.clone(),
+        },
+        commits: commits
+            .iter()
+            .map(|commit| PushCommitPayload {
+                id: commit.id.to_string(),
+                message: commit.format_message(),
+                author: AuthorPayload {
+                    name: commit.author.name.clone(),
+                },
+            })
+            .collect(),
+        cl_link: Some(cl_model.link.clone()),
+    };
+    editor
+        .record_cl_commits(&storage, &cl_model, commits, &username)
+        .await?;
+    dispatch_webhook_event(
+        &storage,
+        WebhookEvent::Push,
+        repo_path_str,
+        WebhookEventPayload::Push(push_payload),
+    )
+    .await;
 
     if from_hash == ZERO_ID && repo_path_str.starts_with("/project/") {
         cl_merge::bootstrap_monorepo_path(git, repo_path_str, Some(&cl_model)).await?;
@@ -223,6 +258,7 @@ impl ApplicationEventHandler for RuntimeApplicationHandler {
                 from_hash,
                 to_hash,
                 username,
+                commits,
             } => {
                 dispatch_mono_receive_pack_finalized(
                     self.git.storage().clone(),
@@ -235,6 +271,7 @@ impl ApplicationEventHandler for RuntimeApplicationHandler {
                     from_hash,
                     to_hash,
                     username,
+                    commits,
                 )
                 .await
             }
@@ -10,7 +10,9 @@ use jupiter::{
 
 use crate::{
     application::api_service::mono::WebhookApplicationService,
-    model::webhook::{CreateWebhookRequest, WebhookResponse, parse_webhook_event_types},
+    model::webhook::{
+        CreateWebhookRequest, WebhookDeliveryResponse, WebhookResponse, parse_webhook_event_types,
+    },
 };
 
 impl WebhookApplicationService {
@@ -65,4 +67,60 @@ impl WebhookApplicationService {
     pub async fn delete_webhook(&self, id: i64) -> Result<(), MegaError> {
         self.storage().webhook_storage().delete_webhook(id).await
     }
+
+    pub async fn list_webhook_deliveries(
+        &self,
+        webhook_id: i64,
+        pagination: Pagination,
+    ) -> Result<(Vec<WebhookDeliveryResponse>, u64), MegaError> {
+        self.get_webhook_or_not_found(webhook_id).await?;
+        let (deliveries, total) = self
+            .storage()
+            .webhook_storage()
+            .list_deliveries(webhook_id, pagination)
+            .await?;
+        Ok((deliveries.into_iter().map(Into::into).collect(), total))
+    }
+
+    /// Queues the payload of delivery `delivery_id` for another delivery to `webhook_id`.
+    pub async fn redeliver_webhook_delivery(
+        &self,
+        webhook_id: i64,
+        delivery_id: i64,
+    ) -> Result<WebhookDeliveryResponse, MegaError> {
+        let delivery = self
+            .storage()
+            .webhook_storage()
+            .get_delivery(delivery_id)
+            .await?
+            .filter(|d| d.webhook_id == webhook_id)
+            .ok_or_else(|| {
+                MegaError::NotFound(format!(
+                    "Delivery `{delivery_id}` of webhook `{webhook_id}` not found"
+                ))
+            })?;
+        let queued = self.storage().webhook_service.redeliver(&delivery).await?;
+        Ok(queued.into())
+    }
+
+    /// Queues a `ping` event to webhook `webhook_id`.
+    pub async fn ping_webhook(
+        &self,
+        webhook_id: i64,
+    ) -> Result<WebhookDeliveryResponse, MegaError> {
+        let webhook = self.get_webhook_or_not_found(webhook_id).await?;
+        let queued = self.storage().webhook_service.ping(&webhook).await?;
+        Ok(queued.into())
+    }
+
+    async fn get_webhook_or_not_found(
+        &self,
+        webhook_id: i64,
+    ) -> Result<callisto::mega_webhook::Model, MegaError> {
+        self.storage()
+            .webhook_storage()
+            .get_webhook(webhook_id)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("Webhook with id `{webhook_id}` not found")))
+    }
 }
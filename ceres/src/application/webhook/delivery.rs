@@ -1,13 +1,15 @@
-//! CL lifecycle webhook delivery.
+//! Webhook event delivery.
 
 use callisto::mega_cl;
 use common::errors::MegaError;
 pub use jupiter::service::webhook_service::{
-    ClPayload, RepositoryPayload, WebhookEvent, WebhookPayload,
+    AuthorPayload, BuildPayload, ClPayload, IssuePayload, MergeQueueItemPayload, PushCommitPayload,
+    PushPayload, RepositoryPayload, ReviewPayload, TagPayload, WebhookEvent, WebhookEventPayload,
+    WebhookPayload,
 };
 use jupiter::{service::webhook_service::WebhookService, storage::Storage};
 
-/// Dispatches CL webhook events asynchronously.
+/// Queues CL webhook events for delivery.
 #[derive(Clone)]
 pub struct WebhookDispatcher {
     service: WebhookService,
@@ -20,11 +22,28 @@ impl WebhookDispatcher {
         })
     }
 
-    pub fn dispatch(&self, event_type: WebhookEvent, cl_model: &mega_cl::Model) {
-        self.service.dispatch(event_type, cl_model);
+    pub async fn dispatch(&self, event_type: WebhookEvent, cl_model: &mega_cl::Model) {
+        self.service.dispatch(event_type, cl_model).await;
     }
 }
 
-pub fn dispatch_cl_webhook(storage: &Storage, event_type: WebhookEvent, cl_model: &mega_cl::Model) {
-    storage.webhook_service.dispatch(event_type, cl_model);
+pub async fn dispatch_cl_webhook(
+    storage: &Storage,
+    event_type: WebhookEvent,
+    cl_model: &mega_cl::Model,
+) {
+    storage.webhook_service.dispatch(event_type, cl_model).await;
+}
+
+/// Dispatches a non-CL event to the webhooks whose path filter covers `path`.
+pub async fn dispatch_webhook_event(
+    storage: &Storage,
+    event_type: WebhookEvent,
+    path: &str,
+    body: WebhookEventPayload,
+) {
+    storage
+        .webhook_service
+        .dispatch_event(event_type, path, body)
+        .await;
 }
@@ -1,9 +1,12 @@
-//! Webhook orchestration: CL event delivery and HTTP admin CRUD.
+//! Webhook orchestration: event delivery, the delivery outbox worker and HTTP admin CRUD.
 
 pub mod admin;
 pub mod delivery;
+pub mod outbox;
 
 pub use delivery::{
-    ClPayload, RepositoryPayload, WebhookDispatcher, WebhookEvent, WebhookPayload,
-    dispatch_cl_webhook,
+    AuthorPayload, BuildPayload, ClPayload, IssuePayload, MergeQueueItemPayload, PushCommitPayload,
+    PushPayload, RepositoryPayload, ReviewPayload, TagPayload, WebhookDispatcher, WebhookEvent,
+    WebhookEventPayload, WebhookPayload, dispatch_cl_webhook, dispatch_webhook_event,
 };
+pub use outbox::WebhookOutboxWorker;
@@ -0,0 +1,56 @@
+//! Background worker draining the webhook delivery outbox.
+
+use jupiter::{service::webhook_service::WebhookService, storage::Storage};
+use tokio::time::{Duration, interval};
+use tracing::{info, warn};
+
+/// Fallback poll interval for retries that come due while nothing new is queued.
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Deliveries attempted per batch.
+const OUTBOX_BATCH_SIZE: u64 = 50;
+
+/// Sends queued webhook deliveries and their retries.
+///
+/// Deliveries live in `mega_webhook_delivery`, so a restart only delays them.
+pub struct WebhookOutboxWorker {
+    service: WebhookService,
+}
+
+impl WebhookOutboxWorker {
+    pub fn from_storage(storage: &Storage) -> Self {
+        Self {
+            service: storage.webhook_service.clone(),
+        }
+    }
+
+    pub async fn run(self, shutdown: tokio_util::sync::CancellationToken) {
+        let mut tick = interval(OUTBOX_POLL_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => {
+                    info!("webhook outbox worker shutting down");
+                    break;
+                }
+                _ = tick.tick() => {}
+                _ = self.service.deliveries_queued() => {}
+            }
+            self.drain().await;
+        }
+    }
+
+    /// Attempts due deliveries until none are left.
+    pub async fn drain(&self) {
+        loop {
+            match self.service.process_due_deliveries(OUTBOX_BATCH_SIZE).await {
+                Ok(attempted) if attempted as u64 == OUTBOX_BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("webhook outbox tick error: {e}");
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use git_internal::internal::object::commit::Commit;
 use jupiter::redis::lock::RedLock;
 
 use crate::transport::protocol::import_refs::RefCommand;
@@ -15,6 +16,8 @@ pub enum TransportEvent {
         from_hash: String,
         to_hash: String,
         username: Option<String>,
+        /// Commits received in the push, parents before children.
+        commits: Vec<Commit>,
     },
     ImportReceivePackFinalized {
         repo_path: PathBuf,
@@ -0,0 +1,522 @@
+//! Garbage collection for Git LFS objects.
+//!
+//! Mark: every commit reachable from a monorepo ref or an imported repository ref is walked
+//! down to its blobs, and pointer-sized blobs are parsed as LFS pointer files. Later passes
+//! only walk the commits pushed in between, see [`LfsGcMarks`].
+//! Sweep: `lfs_objects` rows outside the grace window whose oid was not marked are removed
+//! together with their bytes in `ObjectNamespace::Lfs`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use common::errors::MegaError;
+use git_internal::{
+    hash::ObjectHash,
+    internal::object::{
+        ObjectTrait,
+        tree::{Tree, TreeItemMode},
+    },
+};
+use jupiter::storage::{Storage, git_db_storage::GitDbStorage, mono_storage::MonoStorage};
+
+use crate::lfs::{handler::lfs_object_key, tus::discard_tus_upload};
+
+/// Pointer files are always smaller than this, see the Git LFS spec.
+const LFS_POINTER_MAX_SIZE: usize = 1024;
+const QUERY_CHUNK: usize = 500;
+const CANDIDATE_PAGE: u64 = 500;
+/// How long [`LfsGcMarks`] are extended incrementally before being rebuilt.
+const FULL_WALK_INTERVAL: Duration = Duration::from_secs(7 * 86_400);
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LfsObjectGcStats {
+    pub candidates: u32,
+    pub deleted: u32,
+    pub skipped_still_referenced: u32,
+    /// Rows touched by a batch request after they were listed.
+    pub skipped_recently_seen: u32,
+    pub storage_delete_errors: u32,
+    pub db_delete_errors: u32,
+    /// Resumable uploads dropped after receiving no data for the grace period.
+    pub aborted_uploads: u32,
+}
+
+/// One GC pass: mark referenced oids, then delete up to `batch_limit` unreferenced objects
+/// whose `last_seen_at` is older than `now - grace`. Resumable uploads idle for longer than
+/// `grace` are aborted first. `marks` carries the marking over to the next pass.
+///
+/// Order per object: re-check `last_seen_at` → delete bytes in object storage → delete DB row.
+/// On object-store errors other than “not found”, the DB row is retained for retry.
+pub async fn gc_unreferenced_lfs_objects_once(
+    storage: &Storage,
+    marks: &mut LfsGcMarks,
+    grace: Duration,
+    batch_limit: u64,
+) -> Result<LfsObjectGcStats, MegaError> {
+    let grace_chrono =
+        chrono::Duration::from_std(grace).unwrap_or_else(|_| chrono::Duration::zero());
+    let cutoff = Utc::now()
+        .naive_utc()
+        .checked_sub_signed(grace_chrono)
+        .unwrap_or_else(|| Utc::now().naive_utc());
+
+    let lfs_storage = storage.lfs_db_storage();
+    let mut stats = LfsObjectGcStats::default();
+
+    for upload in lfs_storage
+        .list_stale_tus_uploads(cutoff, batch_limit)
+        .await?
+    {
+        match discard_tus_upload(&storage.lfs_service.obj_storage, &lfs_storage, &upload).await {
+            Ok(()) => stats.aborted_uploads += 1,
+            Err(e) => tracing::warn!(
+                oid = %upload.oid,
+                error = %e,
+                "LFS GC: failed to abort stale resumable upload"
+            ),
+        }
+    }
+
+    let mut page = lfs_storage
+        .list_lfs_gc_candidates(cutoff, None, CANDIDATE_PAGE)
+        .await?;
+    if page.is_empty() {
+        return Ok(stats);
+    }
+    // The walk is the expensive part, so it only runs when something could be collected.
+    let referenced = marks.update(storage).await?;
+
+    'sweep: while !page.is_empty() {
+        let after = page.last().map(|row| row.oid.clone());
+        for row in page {
+            stats.candidates += 1;
+            if referenced.contains(&row.oid) {
+                stats.skipped_still_referenced += 1;
+                continue;
+            }
+            match lfs_storage.get_lfs_object(&row.oid).await? {
+                Some(current) if current.last_seen_at.is_none_or(|seen| seen < cutoff) => {}
+                _ => {
+                    stats.skipped_recently_seen += 1;
+                    continue;
+                }
+            }
+
+            match storage
+                .lfs_service
+                .obj_storage
+                .inner
+                .delete(&lfs_object_key(&row.oid))
+                .await
+            {
+                Ok(()) => {}
+                Err(MegaError::ObjStorageNotFound(_)) => {
+                    tracing::debug!(oid = %row.oid, "LFS GC: object absent in store; dropping DB row");
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        oid = %row.oid,
+                        error = %e,
+                        "LFS GC: object store delete failed; retaining DB row"
+                    );
+                    stats.storage_delete_errors += 1;
+                    continue;
+                }
+            }
+
+            if let Err(e) = lfs_storage.delete_lfs_object(row.oid.clone()).await {
+                tracing::error!(
+                    oid = %row.oid,
+                    error = %e,
+                    "LFS GC: DB delete failed after successful store delete"
+                );
+                stats.db_delete_errors += 1;
+            } else {
+                stats.deleted += 1;
+                if u64::from(stats.deleted) >= batch_limit {
+                    break 'sweep;
+                }
+            }
+        }
+        page = lfs_storage
+            .list_lfs_gc_candidates(cutoff, after.as_deref(), CANDIDATE_PAGE)
+            .await?;
+    }
+
+    Ok(stats)
+}
+
+/// Oids of the LFS pointer files in commits reachable from any ref, kept between GC passes
+/// so that each pass only walks the objects that became reachable since the previous one.
+///
+/// Objects stay marked after a ref moves away from them, so the marks are rebuilt from scratch
+/// every [`FULL_WALK_INTERVAL`] to let such objects be collected.
+#[derive(Default)]
+pub struct LfsGcMarks {
+    started_at: Option<Instant>,
+    mono: ReachableObjects,
+    imports: HashMap<i64, ReachableObjects>,
+    oids: HashSet<String>,
+}
+
+impl LfsGcMarks {
+    /// Walks the objects reachable from the current refs that were not walked before.
+    ///
+    /// Any object that cannot be read fails the pass instead of being treated as unreferenced,
+    /// and the marks are dropped since the walk may have skipped objects it already counted.
+    async fn update(&mut self, storage: &Storage) -> Result<&HashSet<String>, MegaError> {
+        if self
+            .started_at
+            .is_none_or(|started| started.elapsed() >= FULL_WALK_INTERVAL)
+        {
+            *self = Self {
+                started_at: Some(Instant::now()),
+                ..Default::default()
+            };
+        }
+        if let Err(e) = self.walk(storage).await {
+            *self = Self::default();
+            return Err(e);
+        }
+        Ok(&self.oids)
+    }
+
+    async fn walk(&mut self, storage: &Storage) -> Result<(), MegaError> {
+        let mut pointer_blobs = HashSet::new();
+
+        let mono = storage.mono_storage();
+        for r in mono.list_all_refs().await? {
+            self.mono.queue_commit(r.ref_commit_hash);
+            self.mono.queue_tree(r.ref_tree_hash);
+        }
+        pointer_blobs.extend(walk_monorepo(&mono, &mut self.mono).await?);
+
+        let git = storage.git_db_storage();
+        let mut import_refs = git.list_all_refs().await?;
+        import_refs.sort_by_key(|r| r.repo_id);
+        for repo_refs in import_refs.chunk_by(|a, b| a.repo_id == b.repo_id) {
+            let repo_id = repo_refs[0].repo_id;
+            let walk = self.imports.entry(repo_id).or_default();
+            for r in repo_refs {
+                walk.queue_commit(r.ref_git_id.clone());
+            }
+            pointer_blobs.extend(walk_import_repo(&git, repo_id, walk).await?);
+        }
+
+        for blob_id in pointer_blobs {
+            let data = storage.git_service.get_object_as_bytes(&blob_id).await?;
+            if let Some(oid) = parse_lfs_pointer(&data) {
+                self.oids.insert(oid);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Walks the monorepo graph and returns the newly reached blobs small enough to be pointer
+/// files.
+async fn walk_monorepo(
+    mono: &MonoStorage,
+    walk: &mut ReachableObjects,
+) -> Result<HashSet<String>, MegaError> {
+    while walk.has_pending() {
+        for chunk in walk.take_commits().chunks(QUERY_CHUNK) {
+            let commits = mono.get_commits_by_hashes(&chunk.to_vec()).await?;
+            ensure_found(
+                "commit",
+                chunk,
+                commits.iter().map(|c| c.commit_id.as_str()),
+            )?;
+            for commit in commits {
+                walk.visit_commit(commit.tree, &commit.parents_id);
+            }
+        }
+        for chunk in walk.take_trees().chunks(QUERY_CHUNK) {
+            let trees = mono.get_trees_by_hashes(chunk.to_vec()).await?;
+            ensure_found("tree", chunk, trees.iter().map(|t| t.tree_id.as_str()))?;
+            for tree in trees {
+                walk.visit_tree(&tree.tree_id, &tree.sub_trees)?;
+            }
+        }
+    }
+
+    let mut blobs = walk.take_blobs();
+    let ids: Vec<String> = blobs.iter().cloned().collect();
+    for chunk in ids.chunks(QUERY_CHUNK) {
+        for blob in mono.get_mega_blobs_by_hashes(chunk.to_vec()).await? {
+            if blob.size as usize > LFS_POINTER_MAX_SIZE {
+                blobs.remove(&blob.blob_id);
+            }
+        }
+    }
+    Ok(blobs)
+}
+
+/// Same as [`walk_monorepo`] for one imported repository.
+async fn walk_import_repo(
+    git: &GitDbStorage,
+    repo_id: i64,
+    walk: &mut ReachableObjects,
+) -> Result<HashSet<String>, MegaError> {
+    while walk.has_pending() {
+        for chunk in walk.take_commits().chunks(QUERY_CHUNK) {
+            let commits = git.get_commits_by_hashes(repo_id, &chunk.to_vec()).await?;
+            ensure_found(
+                "commit",
+                chunk,
+                commits.iter().map(|c| c.commit_id.as_str()),
+            )?;
+            for commit in commits {
+                walk.visit_commit(commit.tree, &commit.parents_id);
+            }
+        }
+        for chunk in walk.take_trees().chunks(QUERY_CHUNK) {
+            let trees = git.get_trees_by_hashes(repo_id, chunk.to_vec()).await?;
+            ensure_found("tree", chunk, trees.iter().map(|t| t.tree_id.as_str()))?;
+            for tree in trees {
+                walk.visit_tree(&tree.tree_id, &tree.sub_trees)?;
+            }
+        }
+    }
+
+    let mut blobs = walk.take_blobs();
+    let ids: Vec<String> = blobs.iter().cloned().collect();
+    for chunk in ids.chunks(QUERY_CHUNK) {
+        for blob in git.get_blobs_by_hashes(repo_id, chunk.to_vec()).await? {
+            if blob.size as usize > LFS_POINTER_MAX_SIZE {
+                blobs.remove(&blob.blob_id);
+            }
+        }
+    }
+    Ok(blobs)
+}
+
+/// Fails the walk when a requested commit or tree is missing from storage, since the
+/// blobs below it would otherwise look unreferenced.
+fn ensure_found<'a>(
+    kind: &str,
+    requested: &[String],
+    found: impl Iterator<Item = &'a str>,
+) -> Result<(), MegaError> {
+    let found: HashSet<&str> = found.collect();
+    match requested.iter().find(|id| !found.contains(id.as_str())) {
+        Some(missing) => Err(MegaError::Other(format!(
+            "LFS GC: {kind} {missing} is referenced but missing from storage"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Breadth-first walk state shared by the monorepo and import graphs.
+#[derive(Default)]
+struct ReachableObjects {
+    seen_commits: HashSet<String>,
+    seen_trees: HashSet<String>,
+    seen_blobs: HashSet<String>,
+    pending_commits: Vec<String>,
+    pending_trees: Vec<String>,
+    /// Blobs reached since the last [`take_blobs`](Self::take_blobs).
+    blobs: HashSet<String>,
+}
+
+impl ReachableObjects {
+    fn queue_commit(&mut self, id: String) {
+        if self.seen_commits.insert(id.clone()) {
+            self.pending_commits.push(id);
+        }
+    }
+
+    fn queue_tree(&mut self, id: String) {
+        if self.seen_trees.insert(id.clone()) {
+            self.pending_trees.push(id);
+        }
+    }
+
+    fn has_pending(&self) -> bool {
+        !self.pending_commits.is_empty() || !self.pending_trees.is_empty()
+    }
+
+    fn take_commits(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_commits)
+    }
+
+    fn take_trees(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_trees)
+    }
+
+    fn take_blobs(&mut self) -> HashSet<String> {
+        std::mem::take(&mut self.blobs)
+    }
+
+    fn visit_commit(&mut self, tree: String, parents_id: &serde_json::Value) {
+        self.queue_tree(tree);
+        let parents: Vec<String> = serde_json::from_value(parents_id.clone()).unwrap_or_default();
+        for parent in parents {
+            self.queue_commit(parent);
+        }
+    }
+
+    fn visit_tree(&mut self, tree_id: &str, sub_trees: &[u8]) -> Result<(), MegaError> {
+        let id = ObjectHash::from_str(tree_id)
+            .map_err(|e| MegaError::Other(format!("Invalid tree id {tree_id}: {e}")))?;
+        let tree = Tree::from_bytes(sub_trees, id)
+            .map_err(|e| MegaError::Other(format!("Failed to parse tree {tree_id}: {e}")))?;
+        for item in tree.tree_items {
+            match item.mode {
+                TreeItemMode::Tree => self.queue_tree(item.id.to_string()),
+                TreeItemMode::Blob | TreeItemMode::BlobExecutable => {
+                    let id = item.id.to_string();
+                    if self.seen_blobs.insert(id.clone()) {
+                        self.blobs.insert(id);
+                    }
+                }
+                TreeItemMode::Commit | TreeItemMode::Link => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the sha256 oid of a Git LFS pointer file, or `None` if `data` is not one.
+///
+/// Reference: [Git LFS Specification](https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md)
+pub fn parse_lfs_pointer(data: &[u8]) -> Option<String> {
+    if data.len() > LFS_POINTER_MAX_SIZE {
+        return None;
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    let mut lines = text.lines();
+    let version = lines.next()?.strip_prefix("version ")?;
+    if version != "https://git-lfs.github.com/spec/v1"
+        && version != "https://hawser.github.com/spec/v1"
+    {
+        return None;
+    }
+
+    let mut oid = None;
+    let mut has_size = false;
+    for line in lines {
+        if let Some(hex) = line.strip_prefix("oid sha256:") {
+            if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                oid = Some(hex.to_ascii_lowercase());
+            }
+        } else if let Some(size) = line.strip_prefix("size ") {
+            has_size = size.parse::<u64>().is_ok();
+        }
+    }
+    oid.filter(|_| has_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    #[test]
+    fn test_parse_lfs_pointer() {
+        let pointer =
+            format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize 12345\n");
+        assert_eq!(parse_lfs_pointer(pointer.as_bytes()), Some(OID.to_string()));
+    }
+
+    #[test]
+    fn test_parse_lfs_pointer_rejects_other_content() {
+        assert_eq!(parse_lfs_pointer(b"fn main() {}\n"), None);
+        // Missing size.
+        let pointer = format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\n");
+        assert_eq!(parse_lfs_pointer(pointer.as_bytes()), None);
+        // Truncated oid.
+        let pointer =
+            "version https://git-lfs.github.com/spec/v1\noid sha256:4d7a2146\nsize 1\n".to_string();
+        assert_eq!(parse_lfs_pointer(pointer.as_bytes()), None);
+    }
+
+    #[tokio::test]
+    async fn test_gc_deletes_stale_unreferenced_objects() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let lfs = storage.lfs_db_storage();
+        let now = Utc::now().naive_utc();
+        let object = |oid: &str, last_seen_at| callisto::lfs_objects::Model {
+            oid: oid.to_string(),
+            size: 1,
+            exist: true,
+            last_seen_at,
+            root_dir: None,
+        };
+        let stale = "a".repeat(64);
+        let legacy = "b".repeat(64);
+        let fresh = "c".repeat(64);
+        lfs.new_lfs_object(object(&stale, Some(now - chrono::Duration::days(30))))
+            .await
+            .unwrap();
+        lfs.new_lfs_object(object(&legacy, None)).await.unwrap();
+        lfs.new_lfs_object(object(&fresh, Some(now))).await.unwrap();
+
+        let stats = gc_unreferenced_lfs_objects_once(
+            &storage,
+            &mut LfsGcMarks::default(),
+            Duration::from_secs(86_400),
+            100,
+        )
+        .await
+        .unwrap();
+        assert_eq!(stats.candidates, 2);
+        assert_eq!(stats.deleted, 2);
+        assert!(lfs.get_lfs_object(&stale).await.unwrap().is_none());
+        assert!(lfs.get_lfs_object(&legacy).await.unwrap().is_none());
+        assert!(lfs.get_lfs_object(&fresh).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_walk_fails_on_missing_objects() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+
+        let mut walk = ReachableObjects::default();
+        walk.queue_commit("d".repeat(40));
+        assert!(
+            walk_monorepo(&storage.mono_storage(), &mut walk)
+                .await
+                .is_err()
+        );
+
+        let mut walk = ReachableObjects::default();
+        walk.queue_tree("e".repeat(40));
+        assert!(
+            walk_import_repo(&storage.git_db_storage(), 1, &mut walk)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_visit_tree_collects_blobs_and_subtrees() {
+        use git_internal::internal::object::tree::TreeItem;
+
+        let blob = ObjectHash::from_str("8ab686eafeb1f44702738c8b0f24f2567c36da6d").unwrap();
+        let sub = ObjectHash::from_str("1ab686eafeb1f44702738c8b0f24f2567c36da6d").unwrap();
+        let tree = Tree::from_tree_items(vec![
+            TreeItem::new(TreeItemMode::Blob, blob, "asset.bin".to_string()),
+            TreeItem::new(TreeItemMode::Tree, sub, "docs".to_string()),
+        ])
+        .unwrap();
+        let data = tree.to_data().unwrap();
+
+        let mut walk = ReachableObjects::default();
+        walk.visit_tree(&tree.id.to_string(), &data).unwrap();
+        assert_eq!(walk.take_blobs(), HashSet::from([blob.to_string()]));
+        assert_eq!(walk.take_trees(), vec![sub.to_string()]);
+
+        // Trees reached twice are only walked once, and blobs only reported once.
+        walk.queue_tree(sub.to_string());
+        assert!(!walk.has_pending());
+        walk.visit_tree(&tree.id.to_string(), &data).unwrap();
+        assert!(walk.take_blobs().is_empty());
+    }
+}
@@ -1,27 +1,31 @@
-use std::{cmp::min, time::Duration};
+use std::{
+    cmp::min,
+    collections::HashSet,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
 use anyhow::Result;
 use bytes::Bytes;
-use callisto::lfs_locks;
+use callisto::{lfs_locks, lfs_objects};
 use chrono::prelude::*;
 use common::errors::{GitLFSError, MegaError};
 use futures::{Stream, StreamExt};
 use io_orbit::{
     factory::MegaObjectStorageWrapper,
-    object_storage::{ObjectKey, ObjectMeta, ObjectNamespace},
-};
-use jupiter::{
-    service::lfs_service::LfsService, storage::lfs_db_storage::LfsDbStorage,
-    utils::into_obj_stream::IntoObjectStream,
+    object_storage::{ObjectByteStream, ObjectKey, ObjectMeta, ObjectNamespace},
 };
+use jupiter::{service::lfs_service::LfsService, storage::lfs_db_storage::LfsDbStorage};
 use rand::prelude::*;
 use reqwest::Method;
+use sha2::{Digest, Sha256};
 
 use crate::lfs::lfs_structs::{
-    BatchRequest, BatchResponse, Lock, LockList, LockListQuery, LockRequest, MetaObject,
-    ObjectError, Operation, RequestObject, ResCondition, ResponseObject, TransferMode,
-    UnlockRequest, VerifiableLockList, VerifiableLockRequest,
+    BatchRequest, BatchResponse, LfsUploadScope, Lock, LockList, LockListQuery, LockRequest,
+    LockUser, MetaObject, ObjectError, Operation, RequestObject, ResCondition, ResponseObject,
+    TransferMode, UnlockRequest, VerifiableLockList, VerifiableLockRequest,
 };
+use crate::lfs::tus::TUS_TRANSFER;
 
 pub async fn lfs_retrieve_lock(
     storage: LfsDbStorage,
@@ -90,7 +94,12 @@ pub async fn lfs_verify_lock(
     Ok(lock_list)
 }
 
-pub async fn lfs_create_lock(storage: LfsDbStorage, req: LockRequest) -> Result<Lock, GitLFSError> {
+/// Creates a lock on `req.path`, owned by `owner` when the caller is known.
+pub async fn lfs_create_lock(
+    storage: LfsDbStorage,
+    req: LockRequest,
+    owner: Option<String>,
+) -> Result<Lock, GitLFSError> {
     let res = lfs_get_filtered_locks(
         storage.clone(),
         &req.refs.name,
@@ -123,7 +132,7 @@ pub async fn lfs_create_lock(storage: LfsDbStorage, req: LockRequest) -> Result<
             random_num
         },
         path: req.path.to_owned(),
-        owner: None,
+        owner: owner.map(|name| LockUser { name }),
         locked_at: {
             let locked_at: DateTime<Utc> = Utc::now();
             locked_at.to_rfc3339()
@@ -138,43 +147,34 @@ pub async fn lfs_create_lock(storage: LfsDbStorage, req: LockRequest) -> Result<
     }
 }
 
+/// Deletes a lock. Locks owned by someone other than `user` are only deleted with `force`.
 pub async fn lfs_delete_lock(
     storage: LfsDbStorage,
     id: &str,
     unlock_request: UnlockRequest,
+    user: Option<String>,
 ) -> Result<Lock, GitLFSError> {
     if id.is_empty() {
-        return Err(GitLFSError::GeneralError("Invalid lock id!".to_string()));
+        return Err(GitLFSError::InvalidInput("lock id is empty".to_string()));
     }
-    let res = delete_lock(
+    delete_lock(
         storage,
         &unlock_request.refs.name,
-        None,
+        user,
         id,
         unlock_request.force.unwrap_or(false),
     )
-    .await;
-    match res {
-        Ok(deleted_lock) => {
-            if deleted_lock.id.is_empty()
-                && deleted_lock.path.is_empty()
-                && deleted_lock.owner.is_none()
-                && deleted_lock.locked_at == DateTime::<Utc>::MIN_UTC.to_rfc3339()
-            {
-                Err(GitLFSError::GeneralError(
-                    "Unable to find lock!".to_string(),
-                ))
-            } else {
-                Ok(deleted_lock)
-            }
-        }
-        Err(_) => Err(GitLFSError::GeneralError(
-            "Delete operation failed!".to_string(),
-        )),
-    }
+    .await
+    .map_err(|e| match e {
+        GitLFSError::NotFound(_) | GitLFSError::Forbidden(_) => e,
+        _ => GitLFSError::GeneralError("Delete operation failed!".to_string()),
+    })
 }
 
-///
+/// Uploads of objects the server does not know yet are attributed to `scope.root_dir` and
+/// rejected as a whole with [`GitLFSError::InsufficientStorage`] when they would push that
+/// directory over its quota. Every object named in an upload batch has its GC grace window
+/// restarted.
 ///
 /// Reference:
 ///     1. [Git LFS Batch API](https://github.com/git-lfs/git-lfs/blob/main/docs/api/batch.md)
@@ -182,22 +182,44 @@ pub async fn lfs_process_batch(
     service: &LfsService,
     request: BatchRequest,
     listen_addr: &str,
+    scope: &LfsUploadScope,
 ) -> Result<BatchResponse, GitLFSError> {
-    let objects = request.objects;
+    let mut objects = Vec::with_capacity(request.objects.len());
+    for object in request.objects {
+        let meta = lfs_get_meta(&service.lfs_storage, &object.oid).await?;
+        objects.push((object, meta));
+    }
+    if request.operation == Operation::Upload {
+        check_lfs_quota(&service.lfs_storage, &objects, scope).await?;
+    }
+    // Uploads go through the resumable tus endpoint when the client can speak it.
+    let use_tus = request.operation == Operation::Upload
+        && request.transfers.iter().any(|t| t == TUS_TRANSFER);
 
     let mut response_objects = Vec::new();
     let file_storage = service.obj_storage.clone();
     let db_storage = service.lfs_storage.clone();
-    for object in objects {
-        let meta_res = lfs_get_meta(&db_storage, &object.oid).await?;
+    let now = Utc::now().naive_utc();
+    for (object, meta_res) in objects {
         let meta = match meta_res {
-            Some(meta) => meta,
+            Some(meta) => {
+                if request.operation == Operation::Upload
+                    && let Err(e) = db_storage.touch_lfs_object(&meta.oid, now).await
+                {
+                    tracing::warn!("Failed to refresh lfs object {}: {}", meta.oid, e);
+                }
+                meta
+            }
             None => {
                 if request.operation == Operation::Upload {
                     // Save to database if not exist.
                     let meta = MetaObject::new(&object);
                     db_storage
-                        .new_lfs_object(meta.clone().into())
+                        .new_lfs_object(lfs_objects::Model {
+                            last_seen_at: Some(now),
+                            root_dir: scope.root_dir.clone(),
+                            ..meta.clone().into()
+                        })
                         .await
                         .unwrap();
                     meta
@@ -228,7 +250,8 @@ pub async fn lfs_process_batch(
                 continue;
             }
         };
-        let upload_url = match lfs_upload_url(&file_storage, &meta.oid, listen_addr).await {
+        let upload_url = match lfs_upload_url(&file_storage, &meta.oid, listen_addr, use_tus).await
+        {
             Ok(url) => url,
             Err(e) => {
                 tracing::error!("Failed to generate upload URL for {}: {}", meta.oid, e);
@@ -248,7 +271,7 @@ pub async fn lfs_process_batch(
             ResCondition {
                 file_exist,
                 operation: request.operation.clone(),
-                use_tus: false,
+                use_tus,
             },
             &download_url,
             &upload_url,
@@ -256,18 +279,57 @@ pub async fn lfs_process_batch(
     }
 
     Ok(BatchResponse {
-        transfer: TransferMode::BASIC,
+        transfer: if use_tus {
+            TransferMode::TUS
+        } else {
+            TransferMode::BASIC
+        },
         objects: response_objects,
         hash_algo: "sha256".to_string(),
     })
 }
 
-/// Upload object to storage.
-/// if server enable split, split the object and upload each part to storage, save the relationship to database.
+/// Rejects an upload batch whose new objects do not fit in the root directory's quota.
+async fn check_lfs_quota(
+    storage: &LfsDbStorage,
+    objects: &[(RequestObject, Option<MetaObject>)],
+    scope: &LfsUploadScope,
+) -> Result<(), GitLFSError> {
+    let (Some(root_dir), Some(quota)) = (&scope.root_dir, scope.quota_bytes) else {
+        return Ok(());
+    };
+    let mut new_oids = HashSet::new();
+    let needed: u64 = objects
+        .iter()
+        .filter(|(object, meta)| meta.is_none() && new_oids.insert(object.oid.as_str()))
+        .map(|(object, _)| object.size.max(0) as u64)
+        .sum();
+    if needed == 0 {
+        return Ok(());
+    }
+
+    let used = storage
+        .lfs_usage_by_root_dir(root_dir)
+        .await
+        .map_err(|e| GitLFSError::GeneralError(e.to_string()))?
+        .max(0) as u64;
+    if used.saturating_add(needed) > quota {
+        return Err(GitLFSError::InsufficientStorage(format!(
+            "LFS quota for /{root_dir} exceeded: {used} of {quota} bytes used, upload needs {needed} more"
+        )));
+    }
+    Ok(())
+}
+
+/// Streams an uploaded object into storage, recomputing its SHA-256 on the way.
+///
+/// The upload is rejected when the body does not match the size and oid announced in the
+/// batch request; in that case, and on any storage error, both the stored object and its
+/// `lfs_objects` row are removed so the client can retry from scratch.
 pub async fn lfs_upload_object(
     service: &LfsService,
     req_obj: &RequestObject,
-    body_bytes: Vec<u8>,
+    body: ObjectByteStream,
 ) -> Result<(), GitLFSError> {
     let db_storage: LfsDbStorage = service.lfs_storage.clone();
 
@@ -275,36 +337,110 @@ pub async fn lfs_upload_object(
         tracing::debug!("upload lfs object {} size: {}", meta.oid, meta.size);
         meta
     } else {
-        return Err(GitLFSError::GeneralError(String::from("Not found ")));
+        return Err(GitLFSError::NotFound(format!("object {}", req_obj.oid)));
     };
 
+    // Objects are content addressed, so an already stored object must not be replaced by
+    // whatever a later upload sends.
+    if lfs_object_exists(&service.obj_storage, &meta.oid).await {
+        tracing::debug!("lfs object {} already stored, skipping upload", meta.oid);
+        return Ok(());
+    }
+
     let key = lfs_object_key(&meta.oid);
-    let size = meta.size;
+    let rejection = Arc::new(OnceLock::new());
     let res = service
         .obj_storage
         .inner
         .put_stream(
             &key,
-            body_bytes.into_stream(),
+            verify_upload_stream(body, meta.oid.clone(), meta.size, rejection.clone()),
             ObjectMeta {
-                size,
+                size: meta.size,
+                checksum: Some(meta.oid.clone()),
                 ..Default::default()
             },
         )
         .await;
-    if let Err(_e) = res {
-        if let Err(delete_err) = lfs_delete_meta(&db_storage, req_obj).await {
-            tracing::error!(
-                "Failed to cleanup LFS metadata for oid {} after upload failure: {}",
-                meta.oid,
-                delete_err
-            );
+
+    let err = match (res, rejection.get()) {
+        (Ok(()), None) => return Ok(()),
+        (_, Some(reason)) => GitLFSError::InvalidInput(format!("object {}: {}", meta.oid, reason)),
+        (Err(e), None) => {
+            GitLFSError::GeneralError(format!("Failed to store object {}: {}", meta.oid, e))
         }
-        return Err(GitLFSError::GeneralError(String::from(
-            "Header not acceptable!",
-        )));
+    };
+    tracing::warn!("LFS upload of {} failed: {}", meta.oid, err);
+    if let Err(delete_err) = service.obj_storage.inner.delete(&key).await {
+        tracing::debug!(
+            "No stored data to remove for oid {} after upload failure: {}",
+            meta.oid,
+            delete_err
+        );
     }
-    Ok(())
+    if let Err(delete_err) = lfs_delete_meta(&db_storage, req_obj).await {
+        tracing::error!(
+            "Failed to cleanup LFS metadata for oid {} after upload failure: {}",
+            meta.oid,
+            delete_err
+        );
+    }
+    Err(err)
+}
+
+/// Passes `data` through while hashing it, failing the stream as soon as it grows past
+/// `expected_size` or when it ends with a different size or SHA-256 than `oid`.
+///
+/// The reason of a rejection is recorded in `rejection`, so callers can tell a bad upload
+/// apart from a storage failure.
+pub(crate) fn verify_upload_stream(
+    data: ObjectByteStream,
+    oid: String,
+    expected_size: i64,
+    rejection: Arc<OnceLock<String>>,
+) -> ObjectByteStream {
+    let reject = move |reason: String| {
+        let _ = rejection.set(reason.clone());
+        Some(std::io::Error::new(std::io::ErrorKind::InvalidData, reason))
+    };
+    let state = (data, Sha256::new(), 0i64, false);
+    Box::pin(futures::stream::unfold(
+        state,
+        move |(mut data, mut hasher, mut received, done)| {
+            let oid = oid.clone();
+            let reject = reject.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                let error = match data.next().await {
+                    Some(Ok(chunk)) => {
+                        received += chunk.len() as i64;
+                        if received <= expected_size {
+                            hasher.update(&chunk);
+                            return Some((Ok(chunk), (data, hasher, received, false)));
+                        }
+                        reject(format!(
+                            "size mismatch, expected {expected_size} bytes but received more"
+                        ))
+                    }
+                    Some(Err(e)) => Some(e),
+                    None if received != expected_size => reject(format!(
+                        "size mismatch, expected {expected_size} bytes but received {received}"
+                    )),
+                    None => {
+                        let digest = hex::encode(hasher.clone().finalize());
+                        if digest == oid {
+                            None
+                        } else {
+                            reject(format!("content hashes to {digest}"))
+                        }
+                    }
+                }?;
+                Some((Err(error), (data, hasher, received, true)))
+            }
+        },
+    ))
 }
 
 /// Download object from storage.
@@ -348,6 +484,18 @@ pub async fn lfs_download_object(
     }
 }
 
+/// Metadata of an object that has been fully uploaded, or `None` if it is unknown or its
+/// content is not in object storage yet.
+pub async fn lfs_stored_object(
+    service: &LfsService,
+    oid: &str,
+) -> Result<Option<MetaObject>, GitLFSError> {
+    match lfs_get_meta(&service.lfs_storage, oid).await? {
+        Some(meta) if lfs_object_exists(&service.obj_storage, oid).await => Ok(Some(meta)),
+        _ => Ok(None),
+    }
+}
+
 async fn lfs_get_filtered_locks(
     storage: LfsDbStorage,
     refspec: &str,
@@ -476,7 +624,7 @@ async fn lfs_add_lock(
     }
 }
 
-async fn lfs_get_meta(
+pub(crate) async fn lfs_get_meta(
     storage: &LfsDbStorage,
     oid: &str,
 ) -> Result<Option<MetaObject>, GitLFSError> {
@@ -494,14 +642,14 @@ async fn lfs_delete_meta(
     }
 }
 
-fn lfs_object_key(oid: &str) -> ObjectKey {
+pub(crate) fn lfs_object_key(oid: &str) -> ObjectKey {
     ObjectKey {
         namespace: ObjectNamespace::Lfs,
         key: oid.to_string(),
     }
 }
 
-async fn lfs_object_exists(storage: &MegaObjectStorageWrapper, oid: &str) -> bool {
+pub(crate) async fn lfs_object_exists(storage: &MegaObjectStorageWrapper, oid: &str) -> bool {
     let key = lfs_object_key(oid);
 
     match storage.inner.exists(&key).await {
@@ -535,7 +683,13 @@ async fn lfs_upload_url(
     storage: &MegaObjectStorageWrapper,
     oid: &str,
     hostname: &str,
+    use_tus: bool,
 ) -> Result<String, MegaError> {
+    // Resumable uploads keep their progress on the server, so they can't go straight to
+    // a presigned URL.
+    if use_tus {
+        return Ok(format!("{}/info/lfs/objects/{}/tus", hostname, oid));
+    }
     let key = lfs_object_key(oid);
 
     if let Some(url) = storage
@@ -552,7 +706,7 @@ async fn lfs_upload_url(
 async fn delete_lock(
     storage: LfsDbStorage,
     repo: &str,
-    _user: Option<String>,
+    user: Option<String>,
     id: &str,
     force: bool,
 ) -> Result<Lock, GitLFSError> {
@@ -581,8 +735,14 @@ async fn delete_lock(
 
             for lock in locks_from_data.iter() {
                 if lock.id == *id {
-                    if Option::is_some(&lock.owner) && !force {
-                        return Err(GitLFSError::GeneralError("".to_string()));
+                    if let Some(owner) = &lock.owner
+                        && !force
+                        && user.as_deref() != Some(owner.name.as_str())
+                    {
+                        return Err(GitLFSError::Forbidden(format!(
+                            "lock {id} is owned by {}",
+                            owner.name
+                        )));
                     }
                     lock.id.clone_into(&mut lock_to_delete.id);
                     lock.path.clone_into(&mut lock_to_delete.path);
@@ -598,7 +758,7 @@ async fn delete_lock(
                 }
             }
             if lock_to_delete.id.is_empty() {
-                return Err(GitLFSError::GeneralError("".to_string()));
+                return Err(GitLFSError::NotFound(format!("lock {id}")));
             }
 
             // No locks remains, delete the repo from database.
@@ -616,7 +776,7 @@ async fn delete_lock(
             }
         }
         // Not exist, error.
-        None => Err(GitLFSError::GeneralError("".to_string())),
+        None => Err(GitLFSError::NotFound(format!("lock {id}"))),
     }
 }
 
@@ -625,6 +785,48 @@ mod tests {
     use super::*;
     use crate::lfs::lfs_structs::{Action, Ref, ResCondition, ResponseObject};
 
+    async fn drain_verified(chunks: Vec<&'static [u8]>, oid: &str, size: i64) -> Option<String> {
+        let data: ObjectByteStream = Box::pin(futures::stream::iter(
+            chunks.into_iter().map(|c| Ok(Bytes::from_static(c))),
+        ));
+        let rejection = Arc::new(OnceLock::new());
+        let results: Vec<_> = verify_upload_stream(data, oid.to_string(), size, rejection.clone())
+            .collect()
+            .await;
+        assert_eq!(
+            results.iter().any(|r| r.is_err()),
+            rejection.get().is_some()
+        );
+        rejection.get().cloned()
+    }
+
+    #[tokio::test]
+    async fn verify_upload_stream_accepts_matching_content() {
+        let oid = hex::encode(Sha256::digest(b"hello world"));
+        assert_eq!(
+            drain_verified(vec![b"hello ", b"world"], &oid, 11).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_upload_stream_rejects_wrong_size() {
+        let oid = hex::encode(Sha256::digest(b"hello world"));
+        let too_long = drain_verified(vec![b"hello ", b"world", b"!"], &oid, 11).await;
+        assert!(too_long.unwrap().contains("received more"));
+        let too_short = drain_verified(vec![b"hello"], &oid, 11).await;
+        assert!(too_short.unwrap().contains("received 5"));
+    }
+
+    #[tokio::test]
+    async fn verify_upload_stream_rejects_wrong_oid() {
+        let oid = hex::encode(Sha256::digest(b"hello world"));
+        let reason = drain_verified(vec![b"hello there"], &oid, 11)
+            .await
+            .unwrap();
+        assert!(reason.contains(&hex::encode(Sha256::digest(b"hello there"))));
+    }
+
     #[test]
     fn response_object_download_existing() {
         let meta = MetaObject {
@@ -692,6 +894,94 @@ mod tests {
         assert_eq!(res.error.unwrap().code, 404);
     }
 
+    #[tokio::test]
+    async fn process_batch_enforces_root_dir_quota() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = LfsService {
+            lfs_storage: storage.lfs_db_storage(),
+            obj_storage: MegaObjectStorageWrapper::mock(),
+        };
+        let scope = LfsUploadScope {
+            root_dir: Some("project".to_string()),
+            quota_bytes: Some(100),
+        };
+        let upload = |oid: &str, size: i64| BatchRequest {
+            operation: Operation::Upload,
+            transfers: vec!["basic".to_string()],
+            objects: vec![RequestObject {
+                oid: oid.to_string(),
+                size,
+                ..Default::default()
+            }],
+            hash_algo: "sha256".to_string(),
+        };
+        let first = "1".repeat(64);
+        let second = "2".repeat(64);
+
+        lfs_process_batch(&service, upload(&first, 60), "http://localhost", &scope)
+            .await
+            .unwrap();
+        // Objects the server already knows are not charged again.
+        lfs_process_batch(&service, upload(&first, 60), "http://localhost", &scope)
+            .await
+            .unwrap();
+        assert_eq!(
+            service
+                .lfs_storage
+                .lfs_usage_by_root_dir("project")
+                .await
+                .unwrap(),
+            60
+        );
+
+        let err = lfs_process_batch(&service, upload(&second, 50), "http://localhost", &scope)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, GitLFSError::InsufficientStorage(_)));
+        assert!(
+            service
+                .lfs_storage
+                .get_lfs_object(&second)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let other = LfsUploadScope {
+            root_dir: Some("doc".to_string()),
+            quota_bytes: None,
+        };
+        lfs_process_batch(&service, upload(&second, 50), "http://localhost", &other)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn upload_scope_uses_first_path_component() {
+        let mut config = common::config::LFSConfig::default();
+        config.quotas.insert("project".to_string(), 42);
+
+        assert_eq!(
+            LfsUploadScope::for_repo(&config, "/project/app.git"),
+            LfsUploadScope {
+                root_dir: Some("project".to_string()),
+                quota_bytes: Some(42),
+            }
+        );
+        assert_eq!(
+            LfsUploadScope::for_repo(&config, "/doc.git")
+                .root_dir
+                .as_deref(),
+            Some("doc")
+        );
+        assert_eq!(
+            LfsUploadScope::for_repo(&config, "/"),
+            LfsUploadScope::default()
+        );
+    }
+
     #[test]
     fn unlock_request_defaults() {
         let req = UnlockRequest::default();
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use callisto::lfs_objects;
 use chrono::{DateTime, Duration, Utc};
+use common::config::LFSConfig;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -12,6 +13,8 @@ pub enum TransferMode {
     BASIC,
     #[serde(rename = "multipart")]
     MULTIPART,
+    #[serde(rename = "tus")]
+    TUS,
     //not implement yet
     STREAMING,
 }
@@ -61,6 +64,8 @@ impl From<MetaObject> for lfs_objects::Model {
             oid: value.oid,
             size: value.size,
             exist: value.exist,
+            last_seen_at: None,
+            root_dir: None,
         }
     }
 }
@@ -75,6 +80,33 @@ impl MetaObject {
     }
 }
 
+/// Monorepo root directory that new uploads are attributed to, and its byte quota.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LfsUploadScope {
+    pub root_dir: Option<String>,
+    pub quota_bytes: Option<u64>,
+}
+
+impl LfsUploadScope {
+    /// Resolves the scope of a repository path such as `/project/app.git`.
+    pub fn for_repo(config: &LFSConfig, repo_path: &str) -> Self {
+        let root_dir = repo_path
+            .trim_end_matches(".git")
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            .filter(|dir| !dir.is_empty())
+            .map(str::to_owned);
+        let quota_bytes = root_dir
+            .as_ref()
+            .and_then(|dir| config.quotas.get(dir).copied());
+        Self {
+            root_dir,
+            quota_bytes,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
 /// Request object for LFS operations
 pub struct RequestObject {
@@ -1,2 +1,5 @@
+pub mod gc;
 pub mod handler;
 pub mod lfs_structs;
+pub mod transfer;
+pub mod tus;
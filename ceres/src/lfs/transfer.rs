@@ -0,0 +1,935 @@
+//! Server side of the pure SSH Git LFS transfer protocol (`git-lfs-transfer`).
+//!
+//! The client runs `git-lfs-transfer <path> <upload|download>` over SSH and then talks
+//! pkt-lines: after the version handshake every request is a command line, optional
+//! `key=value` arguments, an optional delimiter followed by data lines, and a flush packet.
+//! Every response starts with a `status <code>` line and uses the same layout.
+//!
+//! Reference: [SSH adapter](https://github.com/git-lfs/git-lfs/blob/main/docs/proposals/ssh_adapter.md)
+
+use std::collections::HashMap;
+
+use bytes::{Buf, Bytes, BytesMut};
+use common::errors::GitLFSError;
+use futures::{StreamExt, stream::BoxStream};
+use jupiter::service::lfs_service::LfsService;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::lfs::{
+    handler,
+    lfs_structs::{
+        BatchRequest, LfsUploadScope, Lock, LockListQuery, LockRequest, Operation, Ref,
+        RequestObject, UnlockRequest,
+    },
+};
+
+const FLUSH_PKT: &[u8] = b"0000";
+const DELIM_PKT: &[u8] = b"0001";
+/// Largest payload of a single pkt-line.
+const MAX_PKT_PAYLOAD: usize = 65516;
+/// Chunks of an object upload buffered between the SSH channel and object storage.
+const UPLOAD_CHANNEL_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Packet {
+    Flush,
+    Delim,
+    Data(Bytes),
+}
+
+/// Splits the next complete pkt-line off `buf`, or returns `None` if more data is needed.
+fn next_packet(buf: &mut BytesMut) -> Result<Option<Packet>, GitLFSError> {
+    if buf.len() < 4 {
+        return Ok(None);
+    }
+    let len = std::str::from_utf8(&buf[..4])
+        .ok()
+        .and_then(|s| usize::from_str_radix(s, 16).ok())
+        .ok_or_else(|| GitLFSError::GeneralError("Invalid pkt-line length".to_string()))?;
+    match len {
+        0 => {
+            buf.advance(4);
+            Ok(Some(Packet::Flush))
+        }
+        1 => {
+            buf.advance(4);
+            Ok(Some(Packet::Delim))
+        }
+        2 | 3 => Err(GitLFSError::GeneralError(format!(
+            "Unexpected pkt-line length {len}"
+        ))),
+        _ if buf.len() < len => Ok(None),
+        _ => {
+            let mut pkt = buf.split_to(len);
+            pkt.advance(4);
+            Ok(Some(Packet::Data(pkt.freeze())))
+        }
+    }
+}
+
+fn packet_text(data: &[u8]) -> String {
+    let text = String::from_utf8_lossy(data);
+    text.strip_suffix('\n').unwrap_or(&text).to_string()
+}
+
+fn write_text(out: &mut BytesMut, line: &str) {
+    out.extend_from_slice(format!("{:04x}", line.len() + 5).as_bytes());
+    out.extend_from_slice(line.as_bytes());
+    out.extend_from_slice(b"\n");
+}
+
+fn write_data(out: &mut BytesMut, data: &[u8]) {
+    for chunk in data.chunks(MAX_PKT_PAYLOAD) {
+        out.extend_from_slice(format!("{:04x}", chunk.len() + 4).as_bytes());
+        out.extend_from_slice(chunk);
+    }
+}
+
+/// Builds a response made of a status line and arguments.
+fn response(code: u16, args: &[String]) -> Bytes {
+    let mut out = BytesMut::new();
+    write_text(&mut out, &format!("status {code}"));
+    for arg in args {
+        write_text(&mut out, arg);
+    }
+    out.extend_from_slice(FLUSH_PKT);
+    out.freeze()
+}
+
+/// Builds a response whose arguments are followed by a delimiter and data lines.
+fn response_with_lines(code: u16, args: &[String], lines: &[String]) -> Bytes {
+    let mut out = BytesMut::new();
+    write_text(&mut out, &format!("status {code}"));
+    for arg in args {
+        write_text(&mut out, arg);
+    }
+    out.extend_from_slice(DELIM_PKT);
+    for line in lines {
+        write_text(&mut out, line);
+    }
+    out.extend_from_slice(FLUSH_PKT);
+    out.freeze()
+}
+
+fn error_response(code: u16, message: &str) -> Bytes {
+    response_with_lines(code, &[], &[message.to_string()])
+}
+
+/// Reply to one request. Object contents are framed chunk by chunk as they are read from
+/// storage, so a download never holds the whole object in memory.
+pub enum TransferResponse {
+    Complete(Bytes),
+    Streaming(BoxStream<'static, Result<Bytes, GitLFSError>>),
+}
+
+impl From<Bytes> for TransferResponse {
+    fn from(bytes: Bytes) -> Self {
+        TransferResponse::Complete(bytes)
+    }
+}
+
+/// A request read up to its delimiter or flush packet.
+#[derive(Debug, Default)]
+struct Request {
+    command: String,
+    args: HashMap<String, String>,
+    lines: Vec<String>,
+}
+
+impl Request {
+    fn arg(&self, key: &str) -> &str {
+        self.args.get(key).map(String::as_str).unwrap_or_default()
+    }
+}
+
+fn parse_request(packets: &[Packet]) -> Request {
+    let mut request = Request::default();
+    let mut in_lines = false;
+    for (i, packet) in packets.iter().enumerate() {
+        match packet {
+            Packet::Delim => in_lines = true,
+            Packet::Flush => break,
+            Packet::Data(data) if i == 0 => request.command = packet_text(data),
+            Packet::Data(data) if in_lines => request.lines.push(packet_text(data)),
+            Packet::Data(data) => {
+                let arg = packet_text(data);
+                let (key, value) = arg.split_once('=').unwrap_or((arg.as_str(), ""));
+                request.args.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    request
+}
+
+fn lock_args(lock: &Lock) -> Vec<String> {
+    let mut args = vec![
+        format!("id={}", lock.id),
+        format!("path={}", lock.path),
+        format!("locked-at={}", lock.locked_at),
+    ];
+    if let Some(owner) = &lock.owner {
+        args.push(format!("ownername={}", owner.name));
+    }
+    args
+}
+
+fn lock_lines(lock: &Lock, with_owner: bool, username: Option<&str>) -> Vec<String> {
+    let mut lines = vec![
+        format!("lock {}", lock.id),
+        format!("path {} {}", lock.id, lock.path),
+        format!("locked-at {} {}", lock.id, lock.locked_at),
+    ];
+    if let Some(owner) = &lock.owner {
+        lines.push(format!("ownername {} {}", lock.id, owner.name));
+    }
+    if with_owner {
+        let ours = lock
+            .owner
+            .as_ref()
+            .is_some_and(|owner| username == Some(owner.name.as_str()));
+        let owner = if ours { "ours" } else { "theirs" };
+        lines.push(format!("owner {} {}", lock.id, owner));
+    }
+    lines
+}
+
+enum State {
+    /// Waiting for the client's version request; tracks whether it asked for version 1.
+    Handshake(bool),
+    /// Collecting the packets of the next request.
+    Idle(Vec<Packet>),
+    /// Forwarding the data of a `put-object` request to object storage.
+    Uploading {
+        tx: mpsc::Sender<Result<Bytes, std::io::Error>>,
+        upload: JoinHandle<Result<(), GitLFSError>>,
+    },
+    Closed,
+}
+
+/// One `git-lfs-transfer` invocation, fed with the raw bytes received on the SSH channel.
+pub struct LfsTransferSession {
+    service: LfsService,
+    operation: Operation,
+    scope: LfsUploadScope,
+    /// Authenticated SSH user; owns the locks created in this session.
+    username: Option<String>,
+    buf: BytesMut,
+    state: State,
+}
+
+impl LfsTransferSession {
+    pub fn new(
+        service: LfsService,
+        operation: Operation,
+        scope: LfsUploadScope,
+        username: Option<String>,
+    ) -> Self {
+        Self {
+            service,
+            operation,
+            scope,
+            username,
+            buf: BytesMut::new(),
+            state: State::Handshake(false),
+        }
+    }
+
+    /// Capability advertisement sent as soon as the command starts.
+    pub fn capabilities() -> Bytes {
+        let mut out = BytesMut::new();
+        write_text(&mut out, "version=1");
+        out.extend_from_slice(FLUSH_PKT);
+        out.freeze()
+    }
+
+    /// Whether the client ended the session with `quit`.
+    pub fn is_closed(&self) -> bool {
+        matches!(self.state, State::Closed)
+    }
+
+    /// Consumes channel data and returns the responses to the requests it completed.
+    ///
+    /// Malformed pkt-lines are fatal; failures of individual requests are reported to the
+    /// client as error statuses. A storage error in the middle of a streamed object is
+    /// yielded by the stream and is fatal as well, since its status was already sent.
+    pub async fn receive(&mut self, data: &[u8]) -> Result<Vec<TransferResponse>, GitLFSError> {
+        self.buf.extend_from_slice(data);
+        let mut responses = Vec::new();
+        while !self.is_closed()
+            && let Some(packet) = next_packet(&mut self.buf)?
+        {
+            if let Some(response) = self.on_packet(packet).await {
+                responses.push(response);
+            }
+        }
+        Ok(responses)
+    }
+
+    async fn on_packet(&mut self, packet: Packet) -> Option<TransferResponse> {
+        match std::mem::replace(&mut self.state, State::Closed) {
+            State::Handshake(supported) => match packet {
+                Packet::Data(data) => {
+                    self.state = State::Handshake(packet_text(&data) == "version 1");
+                    None
+                }
+                Packet::Flush if supported => {
+                    self.state = State::Idle(Vec::new());
+                    Some(response(200, &[]).into())
+                }
+                _ => {
+                    self.state = State::Handshake(false);
+                    Some(error_response(400, "unsupported protocol version").into())
+                }
+            },
+            State::Idle(mut packets) => {
+                let starts_upload = packet == Packet::Delim
+                    && matches!(packets.first(), Some(Packet::Data(d)) if packet_text(d).starts_with("put-object "));
+                if starts_upload {
+                    let request = parse_request(&packets);
+                    self.start_upload(request);
+                    return None;
+                }
+                let done = packet == Packet::Flush;
+                packets.push(packet);
+                if !done {
+                    self.state = State::Idle(packets);
+                    return None;
+                }
+                let request = parse_request(&packets);
+                self.state = State::Idle(Vec::new());
+                Some(self.handle(request).await)
+            }
+            State::Uploading { tx, upload } => match packet {
+                Packet::Data(data) => {
+                    // The upload stops reading once it has failed; keep draining the request
+                    // so the error can be reported after the flush.
+                    let _ = tx.send(Ok(data)).await;
+                    self.state = State::Uploading { tx, upload };
+                    None
+                }
+                _ => {
+                    drop(tx);
+                    self.state = State::Idle(Vec::new());
+                    let res = match upload.await {
+                        Ok(Ok(())) => response(200, &[]),
+                        Ok(Err(e)) => upload_error_response(&e),
+                        Err(e) => error_response(500, &format!("upload task failed: {e}")),
+                    };
+                    Some(res.into())
+                }
+            },
+            State::Closed => None,
+        }
+    }
+
+    fn start_upload(&mut self, request: Request) {
+        let oid = request
+            .command
+            .trim_start_matches("put-object ")
+            .to_string();
+        let (tx, rx) = mpsc::channel(UPLOAD_CHANNEL_CAPACITY);
+        let service = self.service.clone();
+        let req_obj = RequestObject {
+            oid,
+            size: request.arg("size").parse().unwrap_or_default(),
+            ..Default::default()
+        };
+        let writable = self.operation == Operation::Upload;
+        let upload = tokio::spawn(async move {
+            if !writable {
+                return Err(GitLFSError::GeneralError(
+                    "Forbidden: objects can only be uploaded in an upload session".to_string(),
+                ));
+            }
+            handler::lfs_upload_object(&service, &req_obj, Box::pin(ReceiverStream::new(rx))).await
+        });
+        self.state = State::Uploading { tx, upload };
+    }
+
+    async fn handle(&mut self, request: Request) -> TransferResponse {
+        let (command, target) = request
+            .command
+            .split_once(' ')
+            .unwrap_or((request.command.as_str(), ""));
+        if command == "get-object" {
+            return self.get_object(target).await;
+        }
+        let res = match command {
+            "batch" => self.batch(&request).await,
+            "verify-object" => self.verify_object(target, request.arg("size")).await,
+            "lock" => self.lock(&request).await,
+            "list-lock" => self.list_locks(&request).await,
+            "unlock" => self.unlock(target, &request).await,
+            "quit" => {
+                self.state = State::Closed;
+                response(200, &[])
+            }
+            _ => error_response(400, &format!("unknown command: {}", request.command)),
+        };
+        res.into()
+    }
+
+    fn check_writable(&self) -> Result<(), Bytes> {
+        if self.operation == Operation::Upload {
+            Ok(())
+        } else {
+            Err(error_response(403, "operation requires an upload session"))
+        }
+    }
+
+    async fn batch(&self, request: &Request) -> Bytes {
+        let hash_algo = request.arg("hash-algo");
+        if !hash_algo.is_empty() && hash_algo != "sha256" {
+            return error_response(409, &format!("unsupported hash algorithm: {hash_algo}"));
+        }
+        let mut objects = Vec::with_capacity(request.lines.len());
+        for line in &request.lines {
+            let Some((oid, size)) = line
+                .split_once(' ')
+                .and_then(|(oid, size)| Some((oid, size.parse::<i64>().ok()?)))
+            else {
+                return error_response(400, &format!("invalid batch object: {line}"));
+            };
+            objects.push(RequestObject {
+                oid: oid.to_string(),
+                size,
+                ..Default::default()
+            });
+        }
+
+        let batch = BatchRequest {
+            operation: self.operation.clone(),
+            transfers: vec!["basic".to_string()],
+            objects,
+            hash_algo: "sha256".to_string(),
+        };
+        // Transfer URLs are not used over SSH, objects move through this session.
+        let res = match handler::lfs_process_batch(&self.service, batch, "", &self.scope).await {
+            Ok(res) => res,
+            Err(e @ GitLFSError::InsufficientStorage(_)) => {
+                return error_response(507, &e.to_string());
+            }
+            Err(e) => return error_response(500, &e.to_string()),
+        };
+        let lines: Vec<String> = res
+            .objects
+            .iter()
+            .map(|object| {
+                let action = match (&object.actions, &self.operation) {
+                    (Some(_), Operation::Upload) => "upload",
+                    (Some(_), Operation::Download) => "download",
+                    (None, _) => "noop",
+                };
+                format!("{} {} {}", object.oid, object.size, action)
+            })
+            .collect();
+        response_with_lines(200, &["hash-algo=sha256".to_string()], &lines)
+    }
+
+    async fn get_object(&self, oid: &str) -> TransferResponse {
+        let meta = match handler::lfs_stored_object(&self.service, oid).await {
+            Ok(Some(meta)) => meta,
+            Ok(None) => return error_response(404, &format!("object not found: {oid}")).into(),
+            Err(e) => return error_response(500, &e.to_string()).into(),
+        };
+        let stream = match handler::lfs_download_object(self.service.clone(), oid.to_string()).await
+        {
+            Ok(stream) => stream,
+            Err(e) => return error_response(500, &e.to_string()).into(),
+        };
+
+        let mut header = BytesMut::new();
+        write_text(&mut header, "status 200");
+        write_text(&mut header, &format!("size={}", meta.size));
+        header.extend_from_slice(DELIM_PKT);
+        let data = stream.map(|chunk| {
+            chunk.map(|chunk| {
+                let mut out = BytesMut::with_capacity(chunk.len() + 4);
+                write_data(&mut out, &chunk);
+                out.freeze()
+            })
+        });
+        let framed = futures::stream::once(async move { Ok(header.freeze()) })
+            .chain(data)
+            .chain(futures::stream::once(async {
+                Ok(Bytes::from_static(FLUSH_PKT))
+            }));
+        TransferResponse::Streaming(framed.boxed())
+    }
+
+    async fn verify_object(&self, oid: &str, size: &str) -> Bytes {
+        match handler::lfs_stored_object(&self.service, oid).await {
+            Ok(Some(meta)) if meta.size.to_string() == size => response(200, &[]),
+            Ok(Some(meta)) => error_response(
+                409,
+                &format!("object {oid} has size {}, not {size}", meta.size),
+            ),
+            Ok(None) => error_response(404, &format!("object not found: {oid}")),
+            Err(e) => error_response(500, &e.to_string()),
+        }
+    }
+
+    async fn lock(&self, request: &Request) -> Bytes {
+        if let Err(res) = self.check_writable() {
+            return res;
+        }
+        let path = request.arg("path").to_string();
+        let refname = request.arg("refname").to_string();
+        if path.is_empty() {
+            return error_response(400, "missing lock path");
+        }
+        let req = LockRequest {
+            path: path.clone(),
+            refs: Ref {
+                name: refname.clone(),
+            },
+        };
+        match handler::lfs_create_lock(self.service.lfs_storage.clone(), req, self.username.clone())
+            .await
+        {
+            Ok(lock) => response(201, &lock_args(&lock)),
+            Err(e) => {
+                let query = LockListQuery {
+                    path,
+                    id: String::new(),
+                    cursor: String::new(),
+                    limit: "1".to_string(),
+                    refspec: refname,
+                };
+                match handler::lfs_retrieve_lock(self.service.lfs_storage.clone(), query).await {
+                    Ok(list) if !list.locks.is_empty() => response_with_lines(
+                        409,
+                        &lock_args(&list.locks[0]),
+                        &["lock already exists".to_string()],
+                    ),
+                    _ => error_response(500, &e.to_string()),
+                }
+            }
+        }
+    }
+
+    async fn list_locks(&self, request: &Request) -> Bytes {
+        let limit = request.arg("limit");
+        if !limit.is_empty() && limit.parse::<u32>().is_err() {
+            return error_response(400, &format!("invalid limit: {limit}"));
+        }
+        let query = LockListQuery {
+            path: request.arg("path").to_string(),
+            id: request.arg("id").to_string(),
+            cursor: request.arg("cursor").to_string(),
+            limit: limit.to_string(),
+            refspec: request.arg("refname").to_string(),
+        };
+        let id = query.id.clone();
+        let list = match handler::lfs_retrieve_lock(self.service.lfs_storage.clone(), query).await {
+            Ok(list) => list,
+            Err(e) => return error_response(500, &e.to_string()),
+        };
+
+        let with_owner = self.operation == Operation::Upload;
+        let lines: Vec<String> = list
+            .locks
+            .iter()
+            .filter(|lock| id.is_empty() || lock.id == id)
+            .flat_map(|lock| lock_lines(lock, with_owner, self.username.as_deref()))
+            .collect();
+        let mut args = Vec::new();
+        if !list.next_cursor.is_empty() {
+            args.push(format!("next-cursor={}", list.next_cursor));
+        }
+        response_with_lines(200, &args, &lines)
+    }
+
+    async fn unlock(&self, id: &str, request: &Request) -> Bytes {
+        if let Err(res) = self.check_writable() {
+            return res;
+        }
+        let unlock = UnlockRequest {
+            force: Some(request.arg("force") == "true"),
+            refs: Ref {
+                name: request.arg("refname").to_string(),
+            },
+        };
+        match handler::lfs_delete_lock(
+            self.service.lfs_storage.clone(),
+            id,
+            unlock,
+            self.username.clone(),
+        )
+        .await
+        {
+            Ok(lock) => response(200, &lock_args(&lock)),
+            Err(GitLFSError::NotFound(_)) => error_response(404, &format!("lock not found: {id}")),
+            Err(e @ GitLFSError::InvalidInput(_)) => error_response(400, &e.to_string()),
+            Err(e @ GitLFSError::Forbidden(_)) => error_response(403, &e.to_string()),
+            Err(e) => error_response(500, &e.to_string()),
+        }
+    }
+}
+
+/// Maps upload failures to the status codes used by the HTTP upload endpoint.
+fn upload_error_response(err: &GitLFSError) -> Bytes {
+    let code = match err {
+        GitLFSError::NotFound(_) => 404,
+        GitLFSError::InvalidInput(_) => 400,
+        GitLFSError::Forbidden(_) => 403,
+        GitLFSError::Conflict(_) => 409,
+        GitLFSError::InsufficientStorage(_) => 507,
+        GitLFSError::GeneralError(_) => 500,
+    };
+    error_response(code, &err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use io_orbit::factory::MegaObjectStorageWrapper;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::lfs::lfs_structs::LockUser;
+
+    fn pkt(text: &str) -> Vec<u8> {
+        let mut out = BytesMut::new();
+        write_text(&mut out, text);
+        out.to_vec()
+    }
+
+    fn request(lines: &[&str]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for line in lines {
+            out.extend_from_slice(&pkt(line));
+        }
+        out.extend_from_slice(FLUSH_PKT);
+        out
+    }
+
+    /// Feeds `data` to the session and concatenates the responses, draining streamed ones.
+    async fn exchange(session: &mut LfsTransferSession, data: &[u8]) -> Vec<Bytes> {
+        let mut out = Vec::new();
+        for response in session.receive(data).await.unwrap() {
+            match response {
+                TransferResponse::Complete(bytes) => out.push(bytes),
+                TransferResponse::Streaming(mut stream) => {
+                    let mut bytes = BytesMut::new();
+                    while let Some(chunk) = stream.next().await {
+                        bytes.extend_from_slice(&chunk.unwrap());
+                    }
+                    out.push(bytes.freeze());
+                }
+            }
+        }
+        out
+    }
+
+    /// Splits a response into its packets.
+    fn packets(bytes: &Bytes) -> Vec<Packet> {
+        let mut buf = BytesMut::from(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = next_packet(&mut buf).unwrap() {
+            packets.push(packet);
+        }
+        assert!(buf.is_empty());
+        packets
+    }
+
+    #[test]
+    fn test_next_packet_waits_for_complete_lines() {
+        let mut buf = BytesMut::from(&b"000eversion"[..]);
+        assert_eq!(next_packet(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b" 1\n0000");
+        assert_eq!(
+            next_packet(&mut buf).unwrap(),
+            Some(Packet::Data(Bytes::from_static(b"version 1\n")))
+        );
+        assert_eq!(next_packet(&mut buf).unwrap(), Some(Packet::Flush));
+        assert!(next_packet(&mut BytesMut::from(&b"zzzz"[..])).is_err());
+    }
+
+    #[test]
+    fn test_parse_request_splits_args_and_lines() {
+        let mut buf = BytesMut::new();
+        for text in ["batch", "transfer=basic", "hash-algo=sha256"] {
+            buf.extend_from_slice(&pkt(text));
+        }
+        buf.extend_from_slice(DELIM_PKT);
+        buf.extend_from_slice(&pkt("abc 12"));
+        buf.extend_from_slice(FLUSH_PKT);
+        let mut packets = Vec::new();
+        while let Some(packet) = next_packet(&mut buf).unwrap() {
+            packets.push(packet);
+        }
+
+        let request = parse_request(&packets);
+        assert_eq!(request.command, "batch");
+        assert_eq!(request.arg("hash-algo"), "sha256");
+        assert_eq!(request.arg("refname"), "");
+        assert_eq!(request.lines, vec!["abc 12".to_string()]);
+    }
+
+    #[test]
+    fn test_lock_lines_report_ownership_for_upload_sessions() {
+        let mut lock = Lock {
+            id: "42".to_string(),
+            path: "assets/a.bin".to_string(),
+            locked_at: "2026-10-18T00:00:00+00:00".to_string(),
+            owner: Some(LockUser {
+                name: "alice".to_string(),
+            }),
+        };
+        assert_eq!(
+            lock_lines(&lock, true, Some("alice")),
+            vec![
+                "lock 42",
+                "path 42 assets/a.bin",
+                "locked-at 42 2026-10-18T00:00:00+00:00",
+                "ownername 42 alice",
+                "owner 42 ours",
+            ]
+        );
+        assert_eq!(lock_lines(&lock, true, Some("bob"))[4], "owner 42 theirs");
+        assert_eq!(lock_lines(&lock, true, None)[4], "owner 42 theirs");
+        assert_eq!(lock_lines(&lock, false, Some("alice")).len(), 4);
+
+        // A lock without an owner is nobody's.
+        lock.owner = None;
+        assert_eq!(lock_lines(&lock, true, Some("alice"))[3], "owner 42 theirs");
+    }
+
+    #[tokio::test]
+    async fn test_locks_belong_to_the_session_user() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = LfsService {
+            lfs_storage: storage.lfs_db_storage(),
+            obj_storage: MegaObjectStorageWrapper::mock(),
+        };
+        let session = |user: &str| {
+            LfsTransferSession::new(
+                service.clone(),
+                Operation::Upload,
+                LfsUploadScope::default(),
+                Some(user.to_string()),
+            )
+        };
+        let mut alice = session("alice");
+        let mut bob = session("bob");
+        exchange(&mut alice, &request(&["version 1"])).await;
+        exchange(&mut bob, &request(&["version 1"])).await;
+
+        let responses = exchange(
+            &mut alice,
+            &request(&["lock", "path=assets/a.bin", "refname=refs/heads/main"]),
+        )
+        .await;
+        let created = packets(&responses[0]);
+        assert_eq!(
+            created[0],
+            Packet::Data(Bytes::from_static(b"status 201\n"))
+        );
+        let Packet::Data(id) = &created[1] else {
+            panic!("missing lock id: {created:?}");
+        };
+        let id = packet_text(id).strip_prefix("id=").unwrap().to_string();
+
+        let list = request(&["list-lock", "refname=refs/heads/main"]);
+        let owner_line = |responses: Vec<Bytes>| {
+            packets(&responses[0])
+                .into_iter()
+                .find_map(|p| match p {
+                    Packet::Data(d) if packet_text(&d).starts_with("owner ") => {
+                        Some(packet_text(&d).to_string())
+                    }
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            owner_line(exchange(&mut alice, &list).await),
+            format!("owner {id} ours")
+        );
+        assert_eq!(
+            owner_line(exchange(&mut bob, &list).await),
+            format!("owner {id} theirs")
+        );
+
+        let unlock = request(&[&format!("unlock {id}"), "refname=refs/heads/main"]);
+        assert!(exchange(&mut bob, &unlock).await[0].starts_with(b"000fstatus 403\n"));
+        assert!(exchange(&mut alice, &unlock).await[0].starts_with(b"000fstatus 200\n"));
+    }
+
+    fn git(dir: &std::path::Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env(
+                "GIT_SSH_COMMAND",
+                "ssh -p 2222 -o StrictHostKeyChecking=no -o UserKnownHostsFile=/dev/null",
+            )
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    /// Drives a real `git-lfs` client over SSH against a running mono server
+    /// (`ssh://git@localhost:2222`, with the caller's key registered).
+    #[test]
+    #[ignore]
+    fn test_git_lfs_client_over_ssh() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path();
+        git(repo, &["init", "--initial-branch=main"]);
+        git(repo, &["lfs", "install", "--local"]);
+        git(repo, &["config", "commit.gpgsign", "false"]);
+        git(repo, &["config", "lfs.ssh.automultiplex", "false"]);
+        git(
+            repo,
+            &[
+                "remote",
+                "add",
+                "origin",
+                "ssh://git@localhost:2222/project.git",
+            ],
+        );
+        git(repo, &["lfs", "track", "*.bin"]);
+        let seed = Utc::now().timestamp_nanos_opt().unwrap().to_le_bytes();
+        std::fs::write(repo.join("asset.bin"), seed.repeat(1024)).unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-m", "lfs over ssh"]);
+
+        // Upload through git-lfs-transfer; the pointer's oid must then be stored.
+        let oid = git(repo, &["lfs", "ls-files", "--long"])
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string();
+        git(repo, &["lfs", "push", "--object-id", "origin", &oid]);
+
+        // Locks created over SSH are ours, and can be released again.
+        git(repo, &["lfs", "lock", "asset.bin"]);
+        let locks = git(repo, &["lfs", "locks", "--verify"]);
+        assert!(locks.contains("O asset.bin"), "{locks}");
+        git(repo, &["lfs", "unlock", "asset.bin"]);
+
+        // Download it again into an empty object store.
+        std::fs::remove_dir_all(repo.join(".git/lfs/objects")).unwrap();
+        git(
+            repo,
+            &["lfs", "fetch", "origin", "--include", "asset.bin", "main"],
+        );
+        assert!(git(repo, &["lfs", "fsck", "--objects"]).contains("ok"));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_quit() {
+        let mut session = LfsTransferSession::new(
+            LfsService::mock(),
+            Operation::Download,
+            LfsUploadScope::default(),
+            None,
+        );
+        let responses = exchange(&mut session, &request(&["version 1"])).await;
+        assert_eq!(responses, vec![response(200, &[])]);
+
+        let mut unknown = request(&["frobnicate"]);
+        unknown.extend_from_slice(&request(&["quit"]));
+        let responses = exchange(&mut session, &unknown).await;
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0].starts_with(b"000fstatus 400\n"));
+        assert_eq!(responses[1], response(200, &[]));
+        assert!(session.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_object_round_trips_through_upload_and_download_sessions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = LfsService {
+            lfs_storage: storage.lfs_db_storage(),
+            obj_storage: MegaObjectStorageWrapper::mock(),
+        };
+        // Unique content so runs sharing the mock store don't see each other's objects, and
+        // large enough to span several pkt-lines.
+        let seed = Utc::now().timestamp_nanos_opt().unwrap().to_le_bytes();
+        let data: Vec<u8> = (0..MAX_PKT_PAYLOAD * 2 + 100)
+            .map(|i| seed[i % seed.len()] ^ (i / 11) as u8)
+            .collect();
+        let oid = hex::encode(Sha256::digest(&data));
+        let size = data.len().to_string();
+
+        let mut upload = LfsTransferSession::new(
+            service.clone(),
+            Operation::Upload,
+            LfsUploadScope::default(),
+            Some("alice".to_string()),
+        );
+        exchange(&mut upload, &request(&["version 1"])).await;
+        let mut batch = pkt("batch");
+        batch.extend_from_slice(DELIM_PKT);
+        batch.extend_from_slice(&request(&[&format!("{oid} {size}")]));
+        let responses = exchange(&mut upload, &batch).await;
+        assert_eq!(
+            responses,
+            vec![response_with_lines(
+                200,
+                &["hash-algo=sha256".to_string()],
+                &[format!("{oid} {size} upload")]
+            )]
+        );
+
+        let mut put = pkt(&format!("put-object {oid}"));
+        put.extend_from_slice(&pkt(&format!("size={size}")));
+        put.extend_from_slice(DELIM_PKT);
+        let mut body = BytesMut::new();
+        write_data(&mut body, &data);
+        put.extend_from_slice(&body);
+        put.extend_from_slice(FLUSH_PKT);
+        // Split the request mid-packet, as an SSH channel may deliver it.
+        let (head, tail) = put.split_at(put.len() / 2);
+        assert!(exchange(&mut upload, head).await.is_empty());
+        assert_eq!(exchange(&mut upload, tail).await, vec![response(200, &[])]);
+        let verify = request(&[&format!("verify-object {oid}"), &format!("size={size}")]);
+        assert_eq!(
+            exchange(&mut upload, &verify).await,
+            vec![response(200, &[])]
+        );
+
+        let mut download = LfsTransferSession::new(
+            service,
+            Operation::Download,
+            LfsUploadScope::default(),
+            None,
+        );
+        exchange(&mut download, &request(&["version 1"])).await;
+        let responses = exchange(&mut download, &request(&[&format!("get-object {oid}")])).await;
+        assert_eq!(responses.len(), 1);
+        let packets = packets(&responses[0]);
+        assert_eq!(
+            packets[..3],
+            [
+                Packet::Data(Bytes::from_static(b"status 200\n")),
+                Packet::Data(Bytes::from(format!("size={size}\n"))),
+                Packet::Delim,
+            ]
+        );
+        assert_eq!(packets.last(), Some(&Packet::Flush));
+        let received: Vec<u8> = packets[3..packets.len() - 1]
+            .iter()
+            .flat_map(|packet| match packet {
+                Packet::Data(chunk) => chunk.to_vec(),
+                other => panic!("unexpected packet {other:?}"),
+            })
+            .collect();
+        assert_eq!(received, data);
+
+        let missing = request(&[&format!("get-object {}", "0".repeat(64))]);
+        let responses = exchange(&mut download, &missing).await;
+        assert!(responses[0].starts_with(b"000fstatus 404\n"));
+    }
+}
@@ -0,0 +1,600 @@
+//! Resumable uploads for the Git LFS `tus` transfer adapter.
+//!
+//! A client first asks for the current `Upload-Offset` of an object with `HEAD` and then
+//! `PATCH`es the remaining bytes starting at that offset. Whatever arrives is persisted
+//! through [`ResumableUploadStorage`], so an interrupted upload can be resumed by a later
+//! request, even one served by another process:
+//!
+//! - complete parts of [`RESUMABLE_PART_SIZE`] bytes are written as upload parts,
+//! - the remainder is kept in a tail object until it fills a part or the upload ends,
+//! - the offset, upload id and part ids live in the `lfs_tus_uploads` table.
+//!
+//! Progress is only recorded if the row is still at the offset the request started from, so
+//! of two requests appending to the same object one fails with a conflict. Parts and tails
+//! are keyed by their position in the object, so the loser cannot clobber what the winner
+//! recorded.
+//!
+//! Once every byte has arrived the parts are assembled into a staging object, whose size
+//! and SHA-256 are checked before it is moved to the object's final key.
+//!
+//! Reference:
+//!     1. [tus resumable upload protocol](https://tus.io/protocols/resumable-upload)
+//!     2. [Git LFS tus adapter](https://github.com/git-lfs/git-lfs/blob/main/docs/api/basic-transfers.md)
+
+use std::sync::{Arc, OnceLock};
+
+use bytes::{Bytes, BytesMut};
+use callisto::lfs_tus_uploads;
+use chrono::Utc;
+use common::errors::{GitLFSError, MegaError};
+use futures::{StreamExt, TryStreamExt};
+use io_orbit::{
+    factory::MegaObjectStorageWrapper,
+    object_storage::{ObjectByteStream, ObjectKey, ObjectMeta, ObjectNamespace},
+    resumable_upload::RESUMABLE_PART_SIZE,
+};
+use jupiter::{service::lfs_service::LfsService, storage::lfs_db_storage::LfsDbStorage};
+
+use crate::lfs::handler::{lfs_get_meta, lfs_object_exists, lfs_object_key, verify_upload_stream};
+
+/// The only tus protocol version the server speaks.
+pub const TUS_VERSION: &str = "1.0.0";
+
+/// Name of the transfer adapter in batch requests and responses.
+pub const TUS_TRANSFER: &str = "tus";
+
+/// Progress of a resumable upload, as reported by `HEAD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TusUploadStatus {
+    pub offset: i64,
+    pub size: i64,
+}
+
+/// Key of the object the parts of `oid` are assembled into before verification.
+fn tus_staging_key(oid: &str) -> ObjectKey {
+    ObjectKey {
+        namespace: ObjectNamespace::Lfs,
+        key: format!("{oid}.tus"),
+    }
+}
+
+/// Key of the bytes received for `oid` up to `offset` that do not fill a part yet.
+fn tus_tail_key(oid: &str, offset: i64) -> ObjectKey {
+    ObjectKey {
+        namespace: ObjectNamespace::Lfs,
+        key: format!("{oid}.tus-tail.{offset}"),
+    }
+}
+
+fn moved_conflict(oid: &str) -> GitLFSError {
+    GitLFSError::Conflict(format!("Upload of {oid} was moved on by another request"))
+}
+
+fn storage_error(oid: &str, e: MegaError) -> GitLFSError {
+    GitLFSError::GeneralError(format!("Failed to store object {oid}: {e}"))
+}
+
+/// Returns how many bytes of `oid` the server already holds.
+pub async fn lfs_tus_status(
+    service: &LfsService,
+    oid: &str,
+) -> Result<TusUploadStatus, GitLFSError> {
+    let Some(meta) = lfs_get_meta(&service.lfs_storage, oid).await? else {
+        return Err(GitLFSError::GeneralError(String::from("Not found")));
+    };
+    if lfs_object_exists(&service.obj_storage, oid).await {
+        return Ok(TusUploadStatus {
+            offset: meta.size,
+            size: meta.size,
+        });
+    }
+    let offset = service
+        .lfs_storage
+        .get_tus_upload(oid)
+        .await
+        .map_err(|e| GitLFSError::GeneralError(e.to_string()))?
+        .map_or(0, |upload| upload.upload_offset);
+    Ok(TusUploadStatus {
+        offset,
+        size: meta.size,
+    })
+}
+
+/// Appends `body` to the upload of `oid` at `offset` and returns the new offset.
+///
+/// Fails with [`GitLFSError::Conflict`] when `offset` is not where the upload currently
+/// ends or another request records progress on the same object first. Bytes received before a
+/// failing body stream are kept, so the client can resume after them.
+pub async fn lfs_tus_append(
+    service: &LfsService,
+    oid: &str,
+    offset: i64,
+    body: ObjectByteStream,
+) -> Result<i64, GitLFSError> {
+    let Some(meta) = lfs_get_meta(&service.lfs_storage, oid).await? else {
+        return Err(GitLFSError::GeneralError(String::from("Not found")));
+    };
+    if lfs_object_exists(&service.obj_storage, oid).await {
+        return if offset == meta.size {
+            Ok(meta.size)
+        } else {
+            Err(GitLFSError::Conflict(format!(
+                "Object {oid} is already complete at offset {}",
+                meta.size
+            )))
+        };
+    }
+
+    let db_storage = &service.lfs_storage;
+    let obj_storage = &service.obj_storage;
+    let upload = match db_storage
+        .get_tus_upload(oid)
+        .await
+        .map_err(|e| GitLFSError::GeneralError(e.to_string()))?
+    {
+        Some(upload) => upload,
+        None => {
+            let upload_id = obj_storage
+                .inner
+                .create_upload(&tus_staging_key(oid))
+                .await
+                .map_err(|e| storage_error(oid, e))?;
+            let now = Utc::now().naive_utc();
+            let upload = lfs_tus_uploads::Model {
+                oid: oid.to_owned(),
+                upload_id,
+                size: meta.size,
+                upload_offset: 0,
+                part_size: RESUMABLE_PART_SIZE as i64,
+                parts: serde_json::json!([]),
+                created_at: now,
+                updated_at: now,
+            };
+            let saved = db_storage
+                .save_tus_upload(upload.clone())
+                .await
+                .map_err(|e| GitLFSError::GeneralError(e.to_string()))?;
+            if !saved {
+                if let Err(e) = obj_storage
+                    .inner
+                    .abort_upload(&tus_staging_key(oid), &upload.upload_id)
+                    .await
+                {
+                    tracing::debug!("Failed to abort unused upload of {}: {}", oid, e);
+                }
+                return Err(GitLFSError::Conflict(format!(
+                    "Another upload of {oid} started first"
+                )));
+            }
+            upload
+        }
+    };
+    if upload.size > 0 && upload.upload_offset == upload.size {
+        return Err(GitLFSError::Conflict(format!(
+            "Upload of {oid} is being completed by another request"
+        )));
+    }
+    if offset != upload.upload_offset {
+        return Err(GitLFSError::Conflict(format!(
+            "Upload of {oid} is at offset {}, not {offset}",
+            upload.upload_offset
+        )));
+    }
+
+    let mut session = TusSession::resume(obj_storage, db_storage, upload).await?;
+    let received = session.append(body).await;
+    if received.is_ok() && session.offset == session.upload.size {
+        return session.finish().await;
+    }
+    session.save_tail().await?;
+    received?;
+    Ok(session.offset)
+}
+
+/// An upload being appended to by the current request.
+struct TusSession<'a> {
+    obj_storage: &'a MegaObjectStorageWrapper,
+    db_storage: &'a LfsDbStorage,
+    upload: lfs_tus_uploads::Model,
+    parts: Vec<String>,
+    /// Bytes after the last stored part.
+    tail: BytesMut,
+    offset: i64,
+}
+
+impl<'a> TusSession<'a> {
+    async fn resume(
+        obj_storage: &'a MegaObjectStorageWrapper,
+        db_storage: &'a LfsDbStorage,
+        upload: lfs_tus_uploads::Model,
+    ) -> Result<Self, GitLFSError> {
+        let oid = upload.oid.clone();
+        let parts: Vec<String> = serde_json::from_value(upload.parts.clone())
+            .map_err(|e| GitLFSError::GeneralError(format!("Corrupt upload of {oid}: {e}")))?;
+        let tail_len = (upload.upload_offset - parts.len() as i64 * upload.part_size) as u64;
+        let mut tail = BytesMut::with_capacity(upload.part_size as usize);
+        if tail_len > 0 {
+            let (data, _) = obj_storage
+                .inner
+                .get_range_stream(&tus_tail_key(&oid, upload.upload_offset), 0, Some(tail_len))
+                .await
+                .map_err(|e| storage_error(&oid, e))?;
+            let chunks: Vec<Bytes> = data
+                .try_collect()
+                .await
+                .map_err(|e| GitLFSError::GeneralError(format!("Failed to resume {oid}: {e}")))?;
+            chunks
+                .iter()
+                .for_each(|chunk| tail.extend_from_slice(chunk));
+            tail.truncate(tail_len as usize);
+        }
+        Ok(Self {
+            obj_storage,
+            db_storage,
+            offset: upload.upload_offset,
+            upload,
+            parts,
+            tail,
+        })
+    }
+
+    /// Consumes `body`, storing every part it completes. Stops at the first error of the
+    /// body stream or of the storage; bytes received up to then stay in the session.
+    async fn append(&mut self, mut body: ObjectByteStream) -> Result<(), GitLFSError> {
+        let part_size = self.upload.part_size as usize;
+        while let Some(chunk) = body.next().await {
+            let mut chunk = chunk.map_err(|e| {
+                GitLFSError::GeneralError(format!("Upload of {} interrupted: {e}", self.upload.oid))
+            })?;
+            if self.offset + chunk.len() as i64 > self.upload.size {
+                return Err(GitLFSError::GeneralError(format!(
+                    "Invalid object {}: size mismatch, expected {} bytes but received more",
+                    self.upload.oid, self.upload.size
+                )));
+            }
+            while !chunk.is_empty() {
+                let take = chunk.len().min(part_size - self.tail.len());
+                self.tail.extend_from_slice(&chunk.split_to(take));
+                self.offset += take as i64;
+                if self.tail.len() == part_size {
+                    self.flush_part().await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered tail as the next part and records it.
+    async fn flush_part(&mut self) -> Result<(), GitLFSError> {
+        let oid = self.upload.oid.clone();
+        let data = Bytes::copy_from_slice(&self.tail);
+        let part = self
+            .obj_storage
+            .inner
+            .put_upload_part(
+                &tus_staging_key(&oid),
+                &self.upload.upload_id,
+                self.parts.len(),
+                data,
+            )
+            .await
+            .map_err(|e| storage_error(&oid, e))?;
+        self.tail.clear();
+        self.parts.push(part);
+        self.record_progress().await
+    }
+
+    /// Persists the bytes that do not fill a part yet, so a later request can resume.
+    async fn save_tail(&mut self) -> Result<(), GitLFSError> {
+        if self.offset == self.upload.upload_offset {
+            return Ok(());
+        }
+        let oid = self.upload.oid.clone();
+        let data = self.tail.clone().freeze();
+        let size = data.len() as i64;
+        if size > 0 {
+            self.obj_storage
+                .inner
+                .put_stream(
+                    &tus_tail_key(&oid, self.offset),
+                    Box::pin(futures::stream::once(async move { Ok(data) })),
+                    ObjectMeta {
+                        size,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| storage_error(&oid, e))?;
+        }
+        self.record_progress().await
+    }
+
+    /// Moves the `lfs_tus_uploads` row from the offset this session last recorded to the
+    /// current one, then drops the tail the old offset pointed to.
+    async fn record_progress(&mut self) -> Result<(), GitLFSError> {
+        let oid = self.upload.oid.clone();
+        let recorded = self
+            .db_storage
+            .update_tus_upload_progress(
+                &oid,
+                self.upload.upload_offset,
+                self.offset,
+                serde_json::json!(self.parts),
+            )
+            .await
+            .map_err(|e| GitLFSError::GeneralError(e.to_string()))?;
+        if !recorded {
+            return Err(moved_conflict(&oid));
+        }
+        let previous = std::mem::replace(&mut self.upload.upload_offset, self.offset);
+        if let Err(e) = remove_tail(self.obj_storage, &self.upload, previous).await {
+            tracing::debug!("Failed to remove old tail of {}: {}", oid, e);
+        }
+        Ok(())
+    }
+
+    /// Assembles the received bytes and moves them to the object's key once they match
+    /// its oid. A mismatching upload is discarded so the client can start over.
+    async fn finish(mut self) -> Result<i64, GitLFSError> {
+        let oid = self.upload.oid.clone();
+        if !self.tail.is_empty() || self.parts.is_empty() {
+            self.flush_part().await?;
+        }
+        let staging = tus_staging_key(&oid);
+        let storage = &self.obj_storage.inner;
+        storage
+            .complete_upload(&staging, &self.upload.upload_id, self.parts.clone())
+            .await
+            .map_err(|e| storage_error(&oid, e))?;
+
+        let rejection = Arc::new(OnceLock::new());
+        let verified = match storage.get_stream(&staging).await {
+            Ok((data, _)) => {
+                let mut data =
+                    verify_upload_stream(data, oid.clone(), self.upload.size, rejection.clone());
+                let mut res = Ok(());
+                while let Some(chunk) = data.next().await {
+                    if let Err(e) = chunk {
+                        res = Err(e);
+                        break;
+                    }
+                }
+                res.map_err(|e| e.to_string())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        let res = match (verified, rejection.get()) {
+            (Ok(()), None) => storage
+                .rename(&staging, &lfs_object_key(&oid))
+                .await
+                .map_err(|e| storage_error(&oid, e)),
+            (_, Some(reason)) => Err(GitLFSError::GeneralError(format!(
+                "Invalid object {oid}: {reason}"
+            ))),
+            (Err(e), None) => Err(GitLFSError::GeneralError(format!(
+                "Failed to store object {oid}: {e}"
+            ))),
+        };
+        if let Err(e) = &res {
+            tracing::warn!("Resumable LFS upload of {} failed: {}", oid, e);
+            if let Err(e) = storage.delete(&staging).await {
+                tracing::debug!("No staged data to remove for oid {}: {}", oid, e);
+            }
+        }
+        // The parts belong to the completed upload now, only the bookkeeping is left.
+        if let Err(e) = forget_tus_upload(self.obj_storage, self.db_storage, &self.upload).await {
+            tracing::warn!("Failed to clean up resumable upload of {}: {}", oid, e);
+        }
+        res.map(|_| self.upload.size)
+    }
+}
+
+/// Drops the stored parts, tail and progress of an upload.
+pub async fn discard_tus_upload(
+    obj_storage: &MegaObjectStorageWrapper,
+    db_storage: &LfsDbStorage,
+    upload: &lfs_tus_uploads::Model,
+) -> Result<(), MegaError> {
+    obj_storage
+        .inner
+        .abort_upload(&tus_staging_key(&upload.oid), &upload.upload_id)
+        .await?;
+    forget_tus_upload(obj_storage, db_storage, upload).await
+}
+
+/// Removes the tail and progress of an upload whose parts are already gone.
+async fn forget_tus_upload(
+    obj_storage: &MegaObjectStorageWrapper,
+    db_storage: &LfsDbStorage,
+    upload: &lfs_tus_uploads::Model,
+) -> Result<(), MegaError> {
+    remove_tail(obj_storage, upload, upload.upload_offset).await?;
+    db_storage.delete_tus_upload(&upload.oid).await
+}
+
+/// Deletes the tail object recorded for `upload` at `offset`, if there is one.
+async fn remove_tail(
+    obj_storage: &MegaObjectStorageWrapper,
+    upload: &lfs_tus_uploads::Model,
+    offset: i64,
+) -> Result<(), MegaError> {
+    if offset % upload.part_size == 0 {
+        return Ok(());
+    }
+    let tail = tus_tail_key(&upload.oid, offset);
+    if obj_storage.inner.exists(&tail).await? {
+        obj_storage.inner.delete(&tail).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::lfs::{
+        handler::lfs_process_batch,
+        lfs_structs::{BatchRequest, LfsUploadScope, Operation, RequestObject, TransferMode},
+    };
+
+    fn body(data: &[u8]) -> ObjectByteStream {
+        let chunks: Vec<_> = data
+            .chunks(1024 * 1024)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect();
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    async fn announce(service: &LfsService, oid: &str, size: i64) {
+        let res = lfs_process_batch(
+            service,
+            BatchRequest {
+                operation: Operation::Upload,
+                transfers: vec!["basic".to_string(), TUS_TRANSFER.to_string()],
+                objects: vec![RequestObject {
+                    oid: oid.to_string(),
+                    size,
+                    ..Default::default()
+                }],
+                hash_algo: "sha256".to_string(),
+            },
+            "http://localhost",
+            &LfsUploadScope::default(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(res.transfer, TransferMode::TUS));
+    }
+
+    fn test_service(storage: &jupiter::storage::Storage) -> LfsService {
+        LfsService {
+            lfs_storage: storage.lfs_db_storage(),
+            obj_storage: MegaObjectStorageWrapper::mock(),
+        }
+    }
+
+    #[tokio::test]
+    async fn tus_upload_resumes_across_requests() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = test_service(&storage);
+        // Unique content so runs sharing the mock store don't see each other's objects.
+        let seed = Utc::now().timestamp_nanos_opt().unwrap().to_le_bytes();
+        let data: Vec<u8> = (0..RESUMABLE_PART_SIZE + 4096)
+            .map(|i| seed[i % seed.len()] ^ (i / 7) as u8)
+            .collect();
+        let oid = hex::encode(Sha256::digest(&data));
+        let size = data.len() as i64;
+        announce(&service, &oid, size).await;
+
+        let first = RESUMABLE_PART_SIZE - 100;
+        let offset = lfs_tus_append(&service, &oid, 0, body(&data[..first]))
+            .await
+            .unwrap();
+        assert_eq!(offset, first as i64);
+        assert_eq!(
+            lfs_tus_status(&service, &oid).await.unwrap(),
+            TusUploadStatus {
+                offset: first as i64,
+                size
+            }
+        );
+
+        let err = lfs_tus_append(&service, &oid, 0, body(&data[..10]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, GitLFSError::Conflict(_)));
+
+        let offset = lfs_tus_append(&service, &oid, first as i64, body(&data[first..]))
+            .await
+            .unwrap();
+        assert_eq!(offset, size);
+        assert!(lfs_object_exists(&service.obj_storage, &oid).await);
+        assert!(
+            service
+                .lfs_storage
+                .get_tus_upload(&oid)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let (stored, _) = service
+            .obj_storage
+            .inner
+            .get_stream(&lfs_object_key(&oid))
+            .await
+            .unwrap();
+        let stored: Vec<Bytes> = stored.try_collect().await.unwrap();
+        assert_eq!(stored.concat(), data);
+    }
+
+    #[tokio::test]
+    async fn tus_concurrent_appends_record_progress_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = test_service(&storage);
+        let seed = Utc::now().timestamp_nanos_opt().unwrap().to_le_bytes();
+        let data: Vec<u8> = (0..4096).map(|i| seed[i % seed.len()] ^ i as u8).collect();
+        let oid = hex::encode(Sha256::digest(&data));
+        let size = data.len() as i64;
+        announce(&service, &oid, size).await;
+        lfs_tus_append(&service, &oid, 0, body(&data[..100]))
+            .await
+            .unwrap();
+
+        // Two requests that both read the upload at offset 100.
+        let upload = service
+            .lfs_storage
+            .get_tus_upload(&oid)
+            .await
+            .unwrap()
+            .unwrap();
+        let db_storage = &service.lfs_storage;
+        let obj_storage = &service.obj_storage;
+        let mut winner = TusSession::resume(obj_storage, db_storage, upload.clone())
+            .await
+            .unwrap();
+        let mut loser = TusSession::resume(obj_storage, db_storage, upload)
+            .await
+            .unwrap();
+        winner.append(body(&data[100..300])).await.unwrap();
+        winner.save_tail().await.unwrap();
+        loser.append(body(&data[100..200])).await.unwrap();
+        let err = loser.save_tail().await.unwrap_err();
+        assert!(matches!(err, GitLFSError::Conflict(_)));
+        assert_eq!(lfs_tus_status(&service, &oid).await.unwrap().offset, 300);
+
+        let offset = lfs_tus_append(&service, &oid, 300, body(&data[300..]))
+            .await
+            .unwrap();
+        assert_eq!(offset, size);
+        let (stored, _) = service
+            .obj_storage
+            .inner
+            .get_stream(&lfs_object_key(&oid))
+            .await
+            .unwrap();
+        let stored: Vec<Bytes> = stored.try_collect().await.unwrap();
+        assert_eq!(stored.concat(), data);
+    }
+
+    #[tokio::test]
+    async fn tus_upload_rejects_content_not_matching_oid() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let service = test_service(&storage);
+        let seed = Utc::now().timestamp_nanos_opt().unwrap().to_string();
+        let oid = hex::encode(Sha256::digest(format!("expected {seed}")));
+        let sent = format!("received {seed}");
+        announce(&service, &oid, sent.len() as i64).await;
+
+        let err = lfs_tus_append(&service, &oid, 0, body(sent.as_bytes()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid object"));
+        assert!(!lfs_object_exists(&service.obj_storage, &oid).await);
+        assert_eq!(lfs_tus_status(&service, &oid).await.unwrap().offset, 0);
+    }
+}
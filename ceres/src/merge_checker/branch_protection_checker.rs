@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use callisto::path_protection_rules;
+use common::errors::MegaError;
+use git_internal::internal::object::commit::Commit;
+use jupiter::{model::cl_dto::ClInfoDto, storage::Storage, utils::converter::FromMegaModel};
+use serde::Deserialize;
+
+use crate::{
+    application::api_service::mono::cl_merge::{ClMergeStrategy, resolve_merge_strategy},
+    merge_checker::{CheckResult, CheckType, Checker, ConditionResult},
+};
+
+/// Evaluates the protection rule of the closest protected ancestor of the CL path: allowed merge
+/// strategies, required approvals, self-approval and linear history.
+pub struct BranchProtectionChecker {
+    pub storage: Arc<Storage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BranchProtectionParams {
+    cl_link: String,
+    path: String,
+    author: String,
+    from_hash: String,
+    to_hash: String,
+}
+
+impl BranchProtectionParams {
+    fn from_value(v: &serde_json::Value) -> anyhow::Result<Self> {
+        Ok(serde_json::from_value(v.clone())?)
+    }
+}
+
+/// What a CL looks like from the point of view of a protection rule.
+struct ClFacts {
+    author: String,
+    strategy: ClMergeStrategy,
+    approvers: Vec<String>,
+    merge_commits: Vec<String>,
+}
+
+#[async_trait]
+impl Checker for BranchProtectionChecker {
+    async fn run(&self, params: &serde_json::Value) -> CheckResult {
+        let params = BranchProtectionParams::from_value(params).expect("parse params err");
+        let mut res = CheckResult {
+            check_type_code: CheckType::BranchProtection,
+            status: ConditionResult::FAILED,
+            message: String::new(),
+        };
+
+        match self.find_violations(&params).await {
+            Ok(violations) if violations.is_empty() => {
+                res.status = ConditionResult::PASSED;
+            }
+            Ok(violations) => {
+                res.message = violations.join("\n");
+            }
+            Err(e) => {
+                res.message = format!("Error while evaluating branch protection: {e}");
+            }
+        }
+        res
+    }
+
+    async fn build_params(&self, cl_info: &ClInfoDto) -> Result<serde_json::Value, MegaError> {
+        Ok(serde_json::json!({
+            "cl_link": cl_info.link,
+            "path": cl_info.path,
+            "author": cl_info.username,
+            "from_hash": cl_info.from_hash,
+            "to_hash": cl_info.to_hash,
+        }))
+    }
+}
+
+impl BranchProtectionChecker {
+    async fn find_violations(
+        &self,
+        params: &BranchProtectionParams,
+    ) -> Result<Vec<String>, MegaError> {
+        let Some(rule) = self
+            .storage
+            .cl_storage()
+            .get_protection_rule_by_path(&params.path)
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let allowed: Vec<String> = serde_json::from_str(&rule.allowed_merge_strategies)?;
+
+        let strategy =
+            resolve_merge_strategy(&self.storage, &params.from_hash, &params.path).await?;
+        let approvers = self
+            .storage
+            .reviewer_storage()
+            .list_reviewers(&params.cl_link)
+            .await?
+            .into_iter()
+            .filter(|reviewer| reviewer.approved)
+            .map(|reviewer| reviewer.username)
+            .collect();
+        let merge_commits = if rule.require_linear_history {
+            self.merge_commits(params).await?
+        } else {
+            vec![]
+        };
+
+        let facts = ClFacts {
+            author: params.author.clone(),
+            strategy,
+            approvers,
+            merge_commits,
+        };
+        Ok(evaluate_rule(&rule, &allowed, &facts))
+    }
+
+    /// Merge commits among the commits pushed to the CL and its head.
+    async fn merge_commits(
+        &self,
+        params: &BranchProtectionParams,
+    ) -> Result<Vec<String>, MegaError> {
+        let mut hashes: Vec<String> = self
+            .storage
+            .cl_storage()
+            .get_cl_commits(&params.cl_link)
+            .await?
+            .into_iter()
+            .map(|commit| commit.commit_sha)
+            .collect();
+        if !hashes.contains(&params.to_hash) {
+            hashes.push(params.to_hash.clone());
+        }
+        let commits = self
+            .storage
+            .mono_storage()
+            .get_commits_by_hashes(&hashes)
+            .await?;
+        let mut merges: Vec<String> = commits
+            .into_iter()
+            .map(Commit::from_mega_model)
+            .filter(|commit| commit.parent_commit_ids.len() > 1)
+            .map(|commit| commit.id.to_string())
+            .collect();
+        merges.sort();
+        Ok(merges)
+    }
+}
+
+/// Lists every way the CL breaks `rule`; `allowed` is the parsed strategy list, empty meaning any.
+fn evaluate_rule(
+    rule: &path_protection_rules::Model,
+    allowed: &[String],
+    facts: &ClFacts,
+) -> Vec<String> {
+    let mut violations = vec![];
+
+    if !allowed.is_empty() && !allowed.iter().any(|s| s == facts.strategy.as_str()) {
+        violations.push(format!(
+            "Merge strategy {} is not allowed under {}, allowed: {}",
+            facts.strategy.as_str(),
+            rule.path,
+            allowed.join(", ")
+        ));
+    }
+
+    let self_approved = facts.approvers.contains(&facts.author);
+    if rule.forbid_self_approval && self_approved {
+        violations.push(format!(
+            "CL author {} cannot approve their own CL",
+            facts.author
+        ));
+    }
+    let approvals = facts
+        .approvers
+        .iter()
+        .filter(|approver| !rule.forbid_self_approval || **approver != facts.author)
+        .count();
+    if approvals < rule.required_approvals.max(0) as usize {
+        violations.push(format!(
+            "Requires {} approval(s), got {approvals}",
+            rule.required_approvals
+        ));
+    }
+
+    if rule.require_linear_history && !facts.merge_commits.is_empty() {
+        violations.push(format!(
+            "Linear history is required, found merge commit(s): {}",
+            facts.merge_commits.join(", ")
+        ));
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use callisto::path_protection_rules;
+
+    use super::{ClFacts, evaluate_rule};
+    use crate::application::api_service::mono::cl_merge::ClMergeStrategy;
+
+    fn rule() -> path_protection_rules::Model {
+        let now = chrono::Utc::now().naive_utc();
+        path_protection_rules::Model {
+            id: 1,
+            path: "/project".to_string(),
+            allowed_merge_strategies: "[]".to_string(),
+            required_approvals: 0,
+            forbid_self_approval: false,
+            require_linear_history: false,
+            block_direct_push: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn facts(approvers: &[&str]) -> ClFacts {
+        ClFacts {
+            author: "alice".to_string(),
+            strategy: ClMergeStrategy::FileDiff,
+            approvers: approvers.iter().map(|a| a.to_string()).collect(),
+            merge_commits: vec![],
+        }
+    }
+
+    #[test]
+    fn test_empty_rule_passes() {
+        assert!(evaluate_rule(&rule(), &[], &facts(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_merge_strategy_must_be_allowed() {
+        let allowed = vec!["subtree_replace".to_string()];
+        let violations = evaluate_rule(&rule(), &allowed, &facts(&[]));
+        assert_eq!(
+            violations,
+            vec![
+                "Merge strategy file_diff is not allowed under /project, allowed: subtree_replace"
+            ]
+        );
+        let allowed = vec!["file_diff".to_string()];
+        assert!(evaluate_rule(&rule(), &allowed, &facts(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_self_approval_does_not_count() {
+        let mut rule = rule();
+        rule.required_approvals = 2;
+        // without the restriction the author's approval counts
+        assert!(evaluate_rule(&rule, &[], &facts(&["alice", "bob"])).is_empty());
+
+        rule.forbid_self_approval = true;
+        let violations = evaluate_rule(&rule, &[], &facts(&["alice", "bob"]));
+        assert_eq!(
+            violations,
+            vec![
+                "CL author alice cannot approve their own CL",
+                "Requires 2 approval(s), got 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_linear_history() {
+        let mut rule = rule();
+        let mut facts = facts(&[]);
+        facts.merge_commits = vec!["abc".to_string()];
+        assert!(evaluate_rule(&rule, &[], &facts).is_empty());
+        rule.require_linear_history = true;
+        assert_eq!(
+            evaluate_rule(&rule, &[], &facts),
+            vec!["Linear history is required, found merge commit(s): abc"]
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use std::sync::Arc;
+
+use api_model::buck2::types::{BuildStatus, TargetStatusResponse, TestResultResponse};
+use async_trait::async_trait;
+use common::errors::MegaError;
+use jupiter::{model::cl_dto::ClInfoDto, storage::Storage};
+use serde::Deserialize;
+
+use crate::{
+    application::build_trigger::SharedBuildDispatch,
+    merge_checker::{CheckResult, CheckType, Checker, ConditionResult},
+};
+
+/// Passes when the latest Orion build of the CL head commit succeeded for the required targets
+/// of the CL path (`build.required_targets`, or every built target when no rule covers the
+/// path). Failures of targets quarantined for the CL path are reported but do not fail the
+/// check.
+pub struct CiStatusChecker {
+    pub storage: Arc<Storage>,
+    pub build_dispatch: Option<SharedBuildDispatch>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CiStatusParams {
+    cl_to: String,
+    task_id: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+impl CiStatusParams {
+    fn from_value(v: &serde_json::Value) -> anyhow::Result<Self> {
+        Ok(serde_json::from_value(v.clone())?)
+    }
+}
+
+#[async_trait]
+impl Checker for CiStatusChecker {
+    async fn run(&self, params: &serde_json::Value) -> CheckResult {
+        let params = CiStatusParams::from_value(params).expect("parse params err");
+        let Some(build_dispatch) = &self.build_dispatch else {
+            return CheckResult {
+                check_type_code: CheckType::CiStatus,
+                status: ConditionResult::FAILED,
+                message: "Orion build is not configured".to_string(),
+            };
+        };
+        let (status, message) = match params.task_id {
+            None => (
+                ConditionResult::PENDING,
+                format!(
+                    "No Orion build has been triggered for commit {}",
+                    params.cl_to
+                ),
+            ),
+            Some(task_id) => match fetch_build(build_dispatch, &task_id).await {
+                Ok(report) => {
+                    let quarantined = match &params.path {
+                        Some(path) => fetch_quarantined(build_dispatch, path).await,
+                        None => vec![],
+                    };
+                    let required =
+                        build_dispatch.required_targets(params.path.as_deref().unwrap_or("/"));
+                    report.evaluate(&required, &quarantined)
+                }
+                Err(e) => (
+                    ConditionResult::FAILED,
+                    format!("Error while fetching Orion build {task_id}: {e}"),
+                ),
+            },
+        };
+        CheckResult {
+            check_type_code: CheckType::CiStatus,
+            status,
+            message,
+        }
+    }
+
+    async fn build_params(&self, cl_info: &ClInfoDto) -> Result<serde_json::Value, MegaError> {
+        let task_id = self
+            .storage
+            .build_trigger_storage()
+            .get_latest_task_trigger(&cl_info.link, &cl_info.to_hash)
+            .await?
+            .and_then(|trigger| trigger.task_id)
+            .map(|id| id.to_string());
+        Ok(serde_json::json!({
+            "cl_to": cl_info.to_hash,
+            "task_id": task_id,
+            "path": cl_info.path,
+        }))
+    }
+}
+
+/// Latest build of a task, with its target states and test results once it finished.
+pub(crate) struct BuildReport {
+    pub build: Option<BuildStatus>,
+    pub targets: Vec<TargetStatusResponse>,
+    pub tests: Vec<TestResultResponse>,
+}
+
+impl BuildReport {
+    pub(crate) fn evaluate(
+        &self,
+        required: &[String],
+        quarantined: &[String],
+    ) -> (ConditionResult, String) {
+        evaluate_build(
+            self.build,
+            &self.targets,
+            &self.tests,
+            required,
+            quarantined,
+        )
+    }
+}
+
+pub(crate) async fn fetch_build(
+    build_dispatch: &SharedBuildDispatch,
+    task_id: &str,
+) -> Result<BuildReport, MegaError> {
+    let build = build_dispatch.latest_build_result(task_id).await?;
+    let (targets, tests) = match build {
+        Some(BuildStatus::Completed | BuildStatus::Failed) => (
+            build_dispatch.target_statuses(task_id).await?,
+            build_dispatch.test_results(task_id).await?,
+        ),
+        _ => (vec![], vec![]),
+    };
+    Ok(BuildReport {
+        build,
+        targets,
+        tests,
+    })
+}
+
+/// Targets quarantined for builds of `path`. When they cannot be read every target is
+/// enforced, so an Orion outage cannot let a failure through.
+pub(crate) async fn fetch_quarantined(
+    build_dispatch: &SharedBuildDispatch,
+    path: &str,
+) -> Vec<String> {
+    build_dispatch
+        .quarantined_targets(path)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to fetch quarantined targets for {}: {}", path, e);
+            vec![]
+        })
+}
+
+pub(crate) fn target_label(target: &TargetStatusResponse) -> String {
+    format!("{}:{}", target.package, target.name)
+}
+
+/// Decides the check outcome from the latest build of the head commit, its target states and
+/// its test results. Failures of `quarantined` targets are listed in the message but never fail
+/// the check; a failed build only passes when every failure it reported is one of them.
+pub(crate) fn evaluate_build(
+    build: Option<BuildStatus>,
+    targets: &[TargetStatusResponse],
+    tests: &[TestResultResponse],
+    required: &[String],
+    quarantined: &[String],
+) -> (ConditionResult, String) {
+    let build_failed = match build {
+        None => {
+            return (
+                ConditionResult::PENDING,
+                "Orion build has not started yet".to_string(),
+            );
+        }
+        Some(BuildStatus::Running) => {
+            return (
+                ConditionResult::PENDING,
+                "Orion build is still running".to_string(),
+            );
+        }
+        Some(BuildStatus::Cancelled) => {
+            return (
+                ConditionResult::FAILED,
+                "Orion build was cancelled".to_string(),
+            );
+        }
+        Some(BuildStatus::Failed) => true,
+        Some(BuildStatus::Completed) => false,
+    };
+
+    let is_relevant = |label: &String| required.is_empty() || required.contains(label);
+    let relevant: Vec<&TargetStatusResponse> = targets
+        .iter()
+        .filter(|t| is_relevant(&target_label(t)))
+        .collect();
+    let labels_with = |status: &str| {
+        relevant
+            .iter()
+            .filter(|t| t.status == status)
+            .map(|t| target_label(t))
+            .collect::<Vec<_>>()
+    };
+
+    let (mut ignored, failed): (Vec<String>, Vec<String>) = labels_with("FAILED")
+        .into_iter()
+        .partition(|label| quarantined.contains(label));
+    let (ignored_tests, failed_tests): (Vec<&TestResultResponse>, Vec<&TestResultResponse>) = tests
+        .iter()
+        .filter(|t| t.status.is_failure() && is_relevant(&t.target))
+        .partition(|t| quarantined.contains(&t.target));
+    for test in ignored_tests {
+        if !ignored.contains(&test.target) {
+            ignored.push(test.target.clone());
+        }
+    }
+    let ignored_note = if ignored.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Ignored failures of quarantined targets: {}",
+            ignored.join(", ")
+        )
+    };
+    let with_note = |message: String| {
+        if ignored_note.is_empty() {
+            message
+        } else {
+            format!("{message}; {ignored_note}")
+        }
+    };
+
+    let mut failures = Vec::new();
+    if !failed.is_empty() {
+        failures.push(format!("Failed targets: {}", failed.join(", ")));
+    }
+    if !failed_tests.is_empty() {
+        let labels: Vec<String> = failed_tests.iter().map(|t| test_label(t)).collect();
+        failures.push(format!("Failed tests: {}", labels.join(", ")));
+    }
+    if !failures.is_empty() {
+        return (ConditionResult::FAILED, with_note(failures.join("; ")));
+    }
+    let missing: Vec<&str> = required
+        .iter()
+        .filter(|r| !relevant.iter().any(|t| &target_label(t) == *r))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return (
+            ConditionResult::FAILED,
+            format!("Required targets were not built: {}", missing.join(", ")),
+        );
+    }
+    let unfinished: Vec<String> = [labels_with("PENDING"), labels_with("RUNNING")].concat();
+    if !unfinished.is_empty() {
+        return (
+            ConditionResult::PENDING,
+            format!("Targets still building: {}", unfinished.join(", ")),
+        );
+    }
+    // Every failure the build reported is quarantined. A failed build that reported none
+    // failed for a reason the targets and tests do not show.
+    if build_failed && required.is_empty() && ignored.is_empty() {
+        return (ConditionResult::FAILED, "Orion build failed".to_string());
+    }
+    (ConditionResult::PASSED, ignored_note)
+}
+
+fn test_label(test: &TestResultResponse) -> String {
+    if test.name.is_empty() {
+        test.target.clone()
+    } else {
+        format!("{} {}", test.target, test.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use api_model::buck2::types::{
+        BuildStatus, TargetStatusResponse, TestResultResponse, TestStatus,
+    };
+
+    use super::evaluate_build;
+    use crate::merge_checker::ConditionResult;
+
+    fn test_result(target: &str, name: &str, status: TestStatus) -> TestResultResponse {
+        TestResultResponse {
+            id: String::new(),
+            build_id: String::new(),
+            target: target.to_string(),
+            name: name.to_string(),
+            status,
+            duration_ms: None,
+            message: None,
+        }
+    }
+
+    fn target(name: &str, status: &str) -> TargetStatusResponse {
+        TargetStatusResponse {
+            id: name.to_string(),
+            task_id: "task".to_string(),
+            package: "root//app".to_string(),
+            name: name.to_string(),
+            configuration: String::new(),
+            category: String::new(),
+            identifier: String::new(),
+            action: String::new(),
+            status: status.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cancelled_build_fails_the_check() {
+        let (status, message) = evaluate_build(Some(BuildStatus::Cancelled), &[], &[], &[], &[]);
+        assert_eq!(status, ConditionResult::FAILED);
+        assert!(message.contains("cancelled"));
+    }
+
+    #[test]
+    fn test_pending_builds_are_not_failures() {
+        let (status, _) = evaluate_build(None, &[], &[], &[], &[]);
+        assert_eq!(status, ConditionResult::PENDING);
+        let (status, _) = evaluate_build(Some(BuildStatus::Running), &[], &[], &[], &[]);
+        assert_eq!(status, ConditionResult::PENDING);
+        let targets = [target("lib", "SUCCESS"), target("bin", "RUNNING")];
+        let (status, message) =
+            evaluate_build(Some(BuildStatus::Completed), &targets, &[], &[], &[]);
+        assert_eq!(status, ConditionResult::PENDING);
+        assert!(message.contains("root//app:bin"));
+    }
+
+    #[test]
+    fn test_all_targets_required_by_default() {
+        let targets = [target("lib", "SUCCESS"), target("bin", "FAILED")];
+        let (status, message) = evaluate_build(Some(BuildStatus::Failed), &targets, &[], &[], &[]);
+        assert_eq!(status, ConditionResult::FAILED);
+        assert_eq!(message, "Failed targets: root//app:bin");
+
+        let targets = [target("lib", "SUCCESS")];
+        let (status, _) = evaluate_build(Some(BuildStatus::Completed), &targets, &[], &[], &[]);
+        assert_eq!(status, ConditionResult::PASSED);
+        let (status, _) = evaluate_build(Some(BuildStatus::Failed), &targets, &[], &[], &[]);
+        assert_eq!(status, ConditionResult::FAILED);
+    }
+
+    #[test]
+    fn test_only_configured_targets_are_required() {
+        let targets = [target("lib", "SUCCESS"), target("bin", "FAILED")];
+        let required = vec!["root//app:lib".to_string()];
+        let (status, _) = evaluate_build(Some(BuildStatus::Failed), &targets, &[], &required, &[]);
+        assert_eq!(status, ConditionResult::PASSED);
+
+        let required = vec!["root//app:lib".to_string(), "root//app:test".to_string()];
+        let (status, message) =
+            evaluate_build(Some(BuildStatus::Completed), &targets, &[], &required, &[]);
+        assert_eq!(status, ConditionResult::FAILED);
+        assert_eq!(message, "Required targets were not built: root//app:test");
+    }
+
+    #[test]
+    fn test_quarantined_failures_are_reported_but_ignored() {
+        let quarantined = vec!["root//app:flaky".to_string()];
+        let targets = [target("lib", "SUCCESS"), target("flaky", "FAILED")];
+        let (status, message) =
+            evaluate_build(Some(BuildStatus::Failed), &targets, &[], &[], &quarantined);
+        assert_eq!(status, ConditionResult::PASSED);
+        assert_eq!(
+            message,
+            "Ignored failures of quarantined targets: root//app:flaky"
+        );
+
+        let targets = [target("bin", "FAILED"), target("flaky", "FAILED")];
+        let (status, message) =
+            evaluate_build(Some(BuildStatus::Failed), &targets, &[], &[], &quarantined);
+        assert_eq!(status, ConditionResult::FAILED);
+        assert_eq!(
+            message,
+            "Failed targets: root//app:bin; Ignored failures of quarantined targets: root//app:flaky"
+        );
+    }
+
+    #[test]
+    fn test_failed_tests_of_other_targets_fail_the_check() {
+        let quarantined = vec!["root//app:flaky".to_string()];
+        let targets = [target("lib", "SUCCESS"), target("flaky", "FAILED")];
+        let tests = [
+            test_result("root//app:lib-test", "parses", TestStatus::Fail),
+            test_result("root//app:flaky", "races", TestStatus::Timeout),
+        ];
+        let (status, message) = evaluate_build(
+            Some(BuildStatus::Failed),
+            &targets,
+            &tests,
+            &[],
+            &quarantined,
+        );
+        assert_eq!(status, ConditionResult::FAILED);
+        assert_eq!(
+            message,
+            "Failed tests: root//app:lib-test parses; Ignored failures of quarantined targets: root//app:flaky"
+        );
+
+        let (status, message) = evaluate_build(
+            Some(BuildStatus::Failed),
+            &targets,
+            &tests[1..],
+            &[],
+            &quarantined,
+        );
+        assert_eq!(status, ConditionResult::PASSED);
+        assert_eq!(
+            message,
+            "Ignored failures of quarantined targets: root//app:flaky"
+        );
+    }
+}
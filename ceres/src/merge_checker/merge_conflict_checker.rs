@@ -0,0 +1,286 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use common::{errors::MegaError, utils::ZERO_ID};
+use git_internal::hash::ObjectHash;
+use jupiter::{model::cl_dto::ClInfoDto, storage::Storage};
+use serde::Deserialize;
+
+use crate::merge_checker::{CheckResult, CheckType, Checker, ConditionResult, commit_files};
+
+/// Three-way merges the CL (`from_hash` → `to_hash`) against the current main ref of the
+/// CL path and fails when any file cannot be merged cleanly.
+pub struct MergeConflictChecker {
+    pub storage: Arc<Storage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MergeConflictParams {
+    base: String,
+    ours: String,
+    theirs: String,
+}
+
+impl MergeConflictParams {
+    fn from_value(v: &serde_json::Value) -> anyhow::Result<Self> {
+        Ok(serde_json::from_value(v.clone())?)
+    }
+}
+
+#[async_trait]
+impl Checker for MergeConflictChecker {
+    async fn run(&self, params: &serde_json::Value) -> CheckResult {
+        let params = MergeConflictParams::from_value(params).expect("parse params err");
+        let mut res = CheckResult {
+            check_type_code: CheckType::MergeConflict,
+            status: ConditionResult::FAILED,
+            message: String::new(),
+        };
+
+        match self.find_conflicts(&params).await {
+            Ok(conflicts) if conflicts.is_empty() => {
+                res.status = ConditionResult::PASSED;
+            }
+            Ok(conflicts) => {
+                res.message = format!("Merge conflict on files: {}", conflicts.join(", "));
+            }
+            Err(e) => {
+                res.message = format!("Error during merge conflict detection: {e}");
+            }
+        }
+        res
+    }
+
+    async fn build_params(&self, cl_info: &ClInfoDto) -> Result<serde_json::Value, MegaError> {
+        let theirs = match self
+            .storage
+            .mono_storage()
+            .get_main_ref(&cl_info.path)
+            .await?
+        {
+            Some(refs) => refs.ref_commit_hash,
+            None if cl_info.from_hash == ZERO_ID => ZERO_ID.to_string(),
+            None => {
+                return Err(MegaError::Other(format!(
+                    "Main ref not found for CL path {}",
+                    cl_info.path
+                )));
+            }
+        };
+        Ok(serde_json::json!({
+            "base": cl_info.from_hash,
+            "ours": cl_info.to_hash,
+            "theirs": theirs,
+        }))
+    }
+}
+
+impl MergeConflictChecker {
+    async fn find_conflicts(&self, params: &MergeConflictParams) -> Result<Vec<String>, MegaError> {
+        // Nothing moved on main since the CL was created, so the merge is a fast-forward.
+        if params.base == params.theirs {
+            return Ok(vec![]);
+        }
+        merge_conflicts(&self.storage, &params.base, &params.ours, &params.theirs).await
+    }
+}
+
+/// File paths that conflict when merging commit `ours` and commit `theirs` from `base`,
+/// see [`three_way_conflicts`]. Used by both this checker and the CL branch update.
+pub(crate) async fn merge_conflicts(
+    storage: &Storage,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> Result<Vec<String>, MegaError> {
+    let base = commit_files(storage, base).await?;
+    let ours = commit_files(storage, ours).await?;
+    let theirs = commit_files(storage, theirs).await?;
+    Ok(three_way_conflicts(&base, &ours, &theirs))
+}
+
+/// Returns the sorted file paths that conflict when merging `ours` and `theirs` from `base`.
+///
+/// A file conflicts when both sides changed it differently (including modify/delete and
+/// add/add with different content). A file also conflicts when the merged result would need
+/// it to be both a file and a directory.
+pub(crate) fn three_way_conflicts(
+    base: &BTreeMap<String, ObjectHash>,
+    ours: &BTreeMap<String, ObjectHash>,
+    theirs: &BTreeMap<String, ObjectHash>,
+) -> Vec<String> {
+    let paths: BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    let mut conflicts = BTreeSet::new();
+    let mut merged = BTreeSet::new();
+
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+        let resolved = if o == t || o == b {
+            t
+        } else if t == b {
+            o
+        } else {
+            conflicts.insert(path.clone());
+            continue;
+        };
+        if resolved.is_some() {
+            merged.insert(path.as_str());
+        }
+    }
+
+    for path in &merged {
+        let mut prefix = path.rsplit_once('/').map(|(dir, _)| dir);
+        while let Some(dir) = prefix {
+            if merged.contains(dir) {
+                conflicts.insert(dir.to_string());
+                conflicts.insert(path.to_string());
+            }
+            prefix = dir.rsplit_once('/').map(|(parent, _)| parent);
+        }
+    }
+
+    conflicts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use git_internal::{
+        hash::ObjectHash,
+        internal::object::{
+            commit::Commit,
+            tree::{Tree, TreeItem, TreeItemMode},
+        },
+    };
+    use jupiter::storage::Storage;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> BTreeMap<String, ObjectHash> {
+        entries
+            .iter()
+            .map(|(path, content)| (path.to_string(), ObjectHash::new(content.as_bytes())))
+            .collect()
+    }
+
+    #[test]
+    fn test_three_way_clean_merges() {
+        let base = files(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+        // ours edits a.txt, theirs edits b.txt and deletes c.txt
+        let ours = files(&[("a.txt", "a2"), ("b.txt", "b"), ("c.txt", "c")]);
+        let theirs = files(&[("a.txt", "a"), ("b.txt", "b2")]);
+        assert!(three_way_conflicts(&base, &ours, &theirs).is_empty());
+
+        // both sides made the same change
+        let ours = files(&[("a.txt", "a2"), ("b.txt", "b"), ("c.txt", "c")]);
+        let theirs = ours.clone();
+        assert!(three_way_conflicts(&base, &ours, &theirs).is_empty());
+    }
+
+    #[test]
+    fn test_three_way_reports_each_conflicting_path() {
+        let base = files(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+        let ours = files(&[
+            ("a.txt", "a-ours"),
+            ("b.txt", "b-ours"),
+            ("c.txt", "c"),
+            ("new.txt", "ours"),
+        ]);
+        let theirs = files(&[("a.txt", "a-theirs"), ("c.txt", "c"), ("new.txt", "theirs")]);
+        assert_eq!(
+            three_way_conflicts(&base, &ours, &theirs),
+            vec!["a.txt", "b.txt", "new.txt"]
+        );
+    }
+
+    #[test]
+    fn test_three_way_file_directory_conflict() {
+        let base = files(&[("keep.txt", "k")]);
+        let ours = files(&[("keep.txt", "k"), ("docs", "file")]);
+        let theirs = files(&[("keep.txt", "k"), ("docs/readme.md", "dir")]);
+        assert_eq!(
+            three_way_conflicts(&base, &ours, &theirs),
+            vec!["docs", "docs/readme.md"]
+        );
+    }
+
+    /// Stores a single-directory commit with the given files and returns its hash.
+    async fn commit(storage: &Storage, files: &[(&str, &str)]) -> String {
+        let items = files
+            .iter()
+            .map(|(name, content)| {
+                TreeItem::new(
+                    TreeItemMode::Blob,
+                    ObjectHash::new(content.as_bytes()),
+                    name.to_string(),
+                )
+            })
+            .collect();
+        let tree = Tree::from_tree_items(items).unwrap();
+        let commit = Commit::from_tree_id(tree.id, vec![], "test");
+        let mono = storage.mono_storage();
+        mono.save_mega_trees(vec![tree], commit.id, None)
+            .await
+            .unwrap();
+        let hash = commit.id.to_string();
+        mono.save_mega_commits(vec![commit], None).await.unwrap();
+        hash
+    }
+
+    #[tokio::test]
+    async fn test_merge_conflicts_compare_stored_commits() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Arc::new(jupiter::tests::test_storage(temp_dir.path()).await);
+        let checker = MergeConflictChecker {
+            storage: storage.clone(),
+        };
+        let base = commit(&storage, &[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]).await;
+        // the CL edits a.txt and b.txt
+        let ours = commit(
+            &storage,
+            &[("a.txt", "a2"), ("b.txt", "b2"), ("c.txt", "c")],
+        )
+        .await;
+        // main edited b.txt and deleted c.txt meanwhile
+        let theirs = commit(&storage, &[("a.txt", "a"), ("b.txt", "b3")]).await;
+        // main made the same edit to a.txt as the CL
+        let same = commit(&storage, &[("a.txt", "a2"), ("b.txt", "b"), ("c.txt", "c")]).await;
+
+        let params = |theirs: &str| MergeConflictParams {
+            base: base.clone(),
+            ours: ours.clone(),
+            theirs: theirs.to_string(),
+        };
+        assert_eq!(
+            checker.find_conflicts(&params(&theirs)).await.unwrap(),
+            vec!["b.txt"]
+        );
+        assert!(
+            checker
+                .find_conflicts(&params(&same))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            checker
+                .find_conflicts(&params(&base))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let res = checker
+            .run(&serde_json::json!({"base": base, "ours": ours, "theirs": theirs}))
+            .await;
+        assert_eq!(res.status, ConditionResult::FAILED);
+        assert_eq!(res.message, "Merge conflict on files: b.txt");
+    }
+}
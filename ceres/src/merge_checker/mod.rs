@@ -1,22 +1,37 @@
-use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use callisto::{check_result, sea_orm_active_enums::CheckTypeEnum};
-use common::errors::MegaError;
-use jupiter::{model::cl_dto::ClInfoDto, storage::Storage};
-use serde::Serialize;
+use common::{errors::MegaError, utils::ZERO_ID};
+use git_internal::{hash::ObjectHash, internal::object::tree::Tree};
+use jupiter::{model::cl_dto::ClInfoDto, storage::Storage, utils::converter::FromMegaModel};
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::merge_checker::{
-    cl_sync_checker::ClSyncChecker, cla_sign_checker::ClaSignChecker,
-    commit_message_checker::CommitMessageChecker, gpg_signature_checker::GpgSignatureChecker,
+use crate::{
+    application::build_trigger::SharedBuildDispatch,
+    merge_checker::{
+        branch_protection_checker::BranchProtectionChecker, ci_status_checker::CiStatusChecker,
+        cl_sync_checker::ClSyncChecker, cla_sign_checker::ClaSignChecker,
+        commit_message_checker::CommitMessageChecker, gpg_signature_checker::GpgSignatureChecker,
+        merge_conflict_checker::MergeConflictChecker,
+    },
 };
 
+mod branch_protection_checker;
+pub(crate) mod ci_status_checker;
 pub mod cl_sync_checker;
 mod cla_sign_checker;
 mod code_review_checker;
 mod commit_message_checker;
 pub(crate) mod gpg_signature_checker;
+pub(crate) mod merge_conflict_checker;
 
 #[async_trait]
 pub trait Checker: Send + Sync {
@@ -37,10 +52,12 @@ pub enum CheckType {
     ClaSign,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum ConditionResult {
     FAILED,
     PASSED,
+    /// The check depends on something that has not finished yet, e.g. a running build.
+    PENDING,
 }
 
 impl fmt::Display for ConditionResult {
@@ -48,6 +65,7 @@ impl fmt::Display for ConditionResult {
         let s = match self {
             ConditionResult::FAILED => "FAILED",
             ConditionResult::PASSED => "PASSED",
+            ConditionResult::PENDING => "PENDING",
         };
         write!(f, "{}", s)
     }
@@ -60,6 +78,7 @@ impl FromStr for ConditionResult {
         match s {
             "PASSED" => Ok(ConditionResult::PASSED),
             "FAILED" => Ok(ConditionResult::FAILED),
+            "PENDING" => Ok(ConditionResult::PENDING),
             _ => Err(()),
         }
     }
@@ -85,7 +104,7 @@ impl CheckType {
                 "Verify whether the commit has a valid GPG signature and the key is trusted"
             }
             CheckType::BranchProtection => {
-                "Ensure the merge target complies with branch protection policies, such as no direct merges to main and requiring squash or rebase"
+                "Ensure the CL complies with the protection rule of its path, such as allowed merge strategies, required approvals and linear history"
             }
             CheckType::CommitMessage => {
                 "Verify whether the commit message follows Conventional Commits or the internal agreed-upon format"
@@ -152,7 +171,11 @@ pub struct CheckerRegistry {
 }
 
 impl CheckerRegistry {
-    pub fn new(storage: Arc<Storage>, username: String) -> Self {
+    pub fn new(
+        storage: Arc<Storage>,
+        username: String,
+        build_dispatch: Option<SharedBuildDispatch>,
+    ) -> Self {
         let mut r = CheckerRegistry {
             checkers: HashMap::new(),
             storage: storage.clone(),
@@ -183,6 +206,25 @@ impl CheckerRegistry {
             }),
         );
         r.register(CheckType::CommitMessage, Box::new(CommitMessageChecker));
+        r.register(
+            CheckType::BranchProtection,
+            Box::new(BranchProtectionChecker {
+                storage: storage.clone(),
+            }),
+        );
+        r.register(
+            CheckType::CiStatus,
+            Box::new(CiStatusChecker {
+                storage: storage.clone(),
+                build_dispatch,
+            }),
+        );
+        r.register(
+            CheckType::MergeConflict,
+            Box::new(MergeConflictChecker {
+                storage: storage.clone(),
+            }),
+        );
 
         r
     }
@@ -221,3 +263,45 @@ impl CheckerRegistry {
         Ok(())
     }
 }
+
+/// Lists every file of a commit as `path -> blob id`; [`ZERO_ID`] is an empty tree.
+pub(crate) async fn commit_files(
+    storage: &Storage,
+    commit_hash: &str,
+) -> Result<BTreeMap<String, ObjectHash>, MegaError> {
+    if commit_hash == ZERO_ID {
+        return Ok(BTreeMap::new());
+    }
+    stored_commit_files(storage, commit_hash)
+        .await?
+        .ok_or_else(|| MegaError::NotFound(format!("Commit {commit_hash} not found")))
+}
+
+/// Same as [`commit_files`], or `None` when the commit is not stored.
+pub(crate) async fn stored_commit_files(
+    storage: &Storage,
+    commit_hash: &str,
+) -> Result<Option<BTreeMap<String, ObjectHash>>, MegaError> {
+    let storage = storage.mono_storage();
+    let Some(commit) = storage.get_commit_by_hash(commit_hash).await? else {
+        return Ok(None);
+    };
+
+    let mut files = BTreeMap::new();
+    let mut stack = vec![(PathBuf::new(), commit.tree)];
+    while let Some((base_path, tree_hash)) = stack.pop() {
+        let tree = storage
+            .get_tree_by_hash(&tree_hash)
+            .await?
+            .ok_or_else(|| MegaError::NotFound(format!("Tree {tree_hash} not found")))?;
+        for item in Tree::from_mega_model(tree).tree_items {
+            let path = base_path.join(&item.name);
+            if item.is_tree() {
+                stack.push((path, item.id.to_string()));
+            } else {
+                files.insert(path.to_string_lossy().replace('\\', "/"), item.id);
+            }
+        }
+    }
+    Ok(Some(files))
+}
@@ -1,4 +1,5 @@
-use serde::Serialize;
+use saturn::context::PolicyDecision;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 #[derive(Serialize, ToSchema)]
@@ -10,3 +11,64 @@ pub struct IsAdminResponse {
 pub struct AdminListResponse {
     pub admins: Vec<String>,
 }
+
+#[derive(Deserialize, ToSchema)]
+pub struct PolicyExplainRequest {
+    /// User to evaluate, defaults to the caller. Only admins may evaluate other users;
+    /// `anonymous` evaluates an unauthenticated request.
+    pub username: Option<String>,
+    /// Cedar action, e.g. `pullRepo`, `pushRepo` or `approveMergeRequest`.
+    pub action: String,
+    /// Monorepo path the action targets.
+    pub path: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PolicyExplainResponse {
+    pub username: String,
+    pub action: String,
+    pub path: String,
+    pub allowed: bool,
+    /// Whether policies are currently enforced (`monorepo.enforce_cedar_policies`).
+    pub enforced: bool,
+    pub resource: Option<String>,
+    pub policies: Vec<String>,
+    pub errors: Vec<String>,
+    pub explanation: String,
+}
+
+impl PolicyExplainResponse {
+    pub fn new(
+        username: String,
+        action: String,
+        path: String,
+        enforced: bool,
+        decision: PolicyDecision,
+    ) -> Self {
+        let explanation = match (&decision.resource, decision.allowed) {
+            (None, _) => format!("No repository entity covers {path}, the request is unrestricted"),
+            (Some(resource), true) => format!(
+                "{action} on {resource} is permitted by {}",
+                decision.policies.join(", ")
+            ),
+            (Some(resource), false) if decision.policies.is_empty() => {
+                format!("No policy permits {action} on {resource} for {username}")
+            }
+            (Some(resource), false) => format!(
+                "{action} on {resource} is forbidden by {}",
+                decision.policies.join(", ")
+            ),
+        };
+        Self {
+            username,
+            action,
+            path,
+            allowed: decision.allowed,
+            enforced,
+            resource: decision.resource,
+            policies: decision.policies,
+            errors: decision.errors,
+            explanation,
+        }
+    }
+}
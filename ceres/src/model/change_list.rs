@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use api_model::common::CommonPage;
-use callisto::{check_result, sea_orm_active_enums::MergeStatusEnum};
+use callisto::{check_result, mega_cl_commits, sea_orm_active_enums::MergeStatusEnum};
 use git_internal::{DiffItem, hash::ObjectHash};
 use jupiter::model::{cl_dto::CLDetails, common::ListParams};
 use serde::{Deserialize, Serialize};
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::{
     merge_checker::{CheckType, ConditionResult},
-    model::{conversation::ConversationItem, label::LabelItem},
+    model::{check_run::CheckRunCondition, conversation::ConversationItem, label::LabelItem},
 };
 
 #[derive(Deserialize, ToSchema)]
@@ -217,6 +217,25 @@ impl From<ClDiffFile> for ClFilesRes {
         }
     }
 }
+#[derive(Serialize, ToSchema)]
+pub struct ClCommitRes {
+    pub sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub message: String,
+}
+
+impl From<mega_cl_commits::Model> for ClCommitRes {
+    fn from(value: mega_cl_commits::Model) -> Self {
+        Self {
+            sha: value.commit_sha,
+            author_name: value.author_name,
+            author_email: value.author_email,
+            message: value.message,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
 pub enum MergeStatus {
     Open,
@@ -253,15 +272,24 @@ pub struct MergeBoxRes {
 }
 
 impl MergeBoxRes {
-    pub fn from_condition(conditions: Vec<Condition>) -> Self {
+    pub fn from_condition(conditions: Vec<Condition>, check_runs: Vec<CheckRunCondition>) -> Self {
         let mut state = RequirementsState::MERGEABLE;
         for cond in &conditions {
-            if cond.result == ConditionResult::FAILED {
+            if cond.result != ConditionResult::PASSED {
+                state = RequirementsState::UNMERGEABLE
+            }
+        }
+        for run in &check_runs {
+            if run.required && run.result != ConditionResult::PASSED {
                 state = RequirementsState::UNMERGEABLE
             }
         }
         MergeBoxRes {
-            merge_requirements: Some(MergeRequirements { conditions, state }),
+            merge_requirements: Some(MergeRequirements {
+                conditions,
+                check_runs,
+                state,
+            }),
         }
     }
 }
@@ -269,6 +297,8 @@ impl MergeBoxRes {
 #[derive(Serialize, ToSchema)]
 pub struct MergeRequirements {
     pub conditions: Vec<Condition>,
+    /// External checks reported by bots on the CL head commit.
+    pub check_runs: Vec<CheckRunCondition>,
     pub state: RequirementsState,
 }
 
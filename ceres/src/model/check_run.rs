@@ -0,0 +1,84 @@
+use callisto::check_runs;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::merge_checker::ConditionResult;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+/// A finding reported by an external check, pointing at a file (and optionally a line range).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckRunAnnotation {
+    pub path: String,
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+    pub level: AnnotationLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCheckRunReq {
+    pub name: String,
+    /// Commit the check ran against; rejected when it is no longer the CL head.
+    pub head_sha: Option<String>,
+    pub status: ConditionResult,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub annotations: Vec<CheckRunAnnotation>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCheckRunReq {
+    pub status: Option<ConditionResult>,
+    pub summary: Option<String>,
+    /// Replaces the annotations of the run when present.
+    pub annotations: Option<Vec<CheckRunAnnotation>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CheckRunRes {
+    pub id: i64,
+    pub name: String,
+    pub cl_link: String,
+    pub commit_id: String,
+    pub bot_id: i64,
+    pub status: ConditionResult,
+    pub summary: String,
+    pub annotations: Vec<CheckRunAnnotation>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<check_runs::Model> for CheckRunRes {
+    fn from(value: check_runs::Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            cl_link: value.cl_link,
+            commit_id: value.commit_id,
+            bot_id: value.bot_id,
+            status: value.status.parse().unwrap_or(ConditionResult::FAILED),
+            summary: value.summary,
+            annotations: serde_json::from_str(&value.annotations).unwrap_or_default(),
+            created_at: value.created_at.and_utc().timestamp(),
+            updated_at: value.updated_at.and_utc().timestamp(),
+        }
+    }
+}
+
+/// State of one external check in the merge box; required checks that were never reported
+/// show up as `PENDING`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CheckRunCondition {
+    pub name: String,
+    pub required: bool,
+    pub result: ConditionResult,
+    pub summary: String,
+}
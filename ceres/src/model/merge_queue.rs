@@ -45,6 +45,10 @@ pub struct QueueItem {
     pub updated_at: String,
     pub retry_count: i32,
     pub error: Option<QueueError>,
+    /// Batch the item is (or was last) validated in
+    pub batch_link: Option<String>,
+    /// Orion task of the latest speculative build covering the item
+    pub build_task_id: Option<String>,
 }
 
 /// Queue statistics for API
@@ -154,6 +158,8 @@ impl From<Model> for QueueItem {
                 .to_string(),
             retry_count: item.retry_count,
             error,
+            batch_link: item.batch_link,
+            build_task_id: item.build_task_id,
         }
     }
 }
@@ -3,6 +3,7 @@ pub mod blame;
 pub mod bots;
 pub mod buck;
 pub mod change_list;
+pub mod check_run;
 pub mod code_review;
 pub mod commit;
 pub mod conversation;
@@ -15,6 +16,7 @@ pub mod label;
 pub mod merge_queue;
 pub mod note;
 pub mod notification;
+pub mod protection_rule;
 pub mod tag;
 pub mod third_party;
 pub mod user;
@@ -0,0 +1,68 @@
+use callisto::path_protection_rules;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateProtectionRuleReq {
+    /// Monorepo path the rule protects, together with everything below it.
+    pub path: String,
+    /// Merge strategies CLs may use (`file_diff`, `subtree_replace`); empty allows any.
+    #[serde(default)]
+    pub allowed_merge_strategies: Vec<String>,
+    #[serde(default)]
+    pub required_approvals: i32,
+    #[serde(default)]
+    pub forbid_self_approval: bool,
+    #[serde(default)]
+    pub require_linear_history: bool,
+    #[serde(default)]
+    pub block_direct_push: bool,
+}
+
+/// Changes to the rule of `path`; absent fields are left as they are.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateProtectionRuleReq {
+    pub path: String,
+    pub allowed_merge_strategies: Option<Vec<String>>,
+    pub required_approvals: Option<i32>,
+    pub forbid_self_approval: Option<bool>,
+    pub require_linear_history: Option<bool>,
+    pub block_direct_push: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ProtectionRuleQuery {
+    /// Lists the rules on this path or below it, defaults to the whole monorepo.
+    #[serde(default)]
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProtectionRuleRes {
+    pub id: i64,
+    pub path: String,
+    pub allowed_merge_strategies: Vec<String>,
+    pub required_approvals: i32,
+    pub forbid_self_approval: bool,
+    pub require_linear_history: bool,
+    pub block_direct_push: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<path_protection_rules::Model> for ProtectionRuleRes {
+    fn from(value: path_protection_rules::Model) -> Self {
+        Self {
+            id: value.id,
+            path: value.path,
+            allowed_merge_strategies: serde_json::from_str(&value.allowed_merge_strategies)
+                .unwrap_or_default(),
+            required_approvals: value.required_approvals,
+            forbid_self_approval: value.forbid_self_approval,
+            require_linear_history: value.require_linear_history,
+            block_direct_push: value.block_direct_push,
+            created_at: value.created_at.and_utc().timestamp(),
+            updated_at: value.updated_at.and_utc().timestamp(),
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use callisto::{access_token, ssh_keys};
+use common::enums::TokenScope;
+use jupiter::storage::user_storage::token_scopes;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -29,20 +31,58 @@ impl From<ssh_keys::Model> for ListSSHKey {
     }
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GenerateTokenPayload {
+    /// Permissions granted to the token: `repo:read`, `repo:write`, `cl:write` or `admin`.
+    #[schema(value_type = Vec<String>)]
+    pub scopes: Vec<TokenScope>,
+    /// Lifetime in days; the token never expires when omitted.
+    pub expires_in_days: Option<u32>,
+}
+
+/// A newly created token. `token` is the only time the plaintext is returned.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub struct ListToken {
+pub struct GeneratedToken {
     pub id: i64,
     pub token: String,
+    #[schema(value_type = Vec<String>)]
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+impl GeneratedToken {
+    pub fn new(model: access_token::Model, token: String) -> Self {
+        Self {
+            id: model.id,
+            token,
+            scopes: token_scopes(&model),
+            expires_at: model.expires_at.map(|t| t.and_utc().timestamp()),
+            created_at: model.created_at.and_utc().timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListToken {
+    pub id: i64,
+    /// Leading characters of the token, enough to tell tokens apart.
+    pub token_prefix: String,
+    #[schema(value_type = Vec<String>)]
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
     pub created_at: i64,
 }
 
 impl From<access_token::Model> for ListToken {
     fn from(value: access_token::Model) -> Self {
-        let mut mask_token = value.token;
-        mask_token.replace_range(7..32, "-******-");
         Self {
             id: value.id,
-            token: mask_token,
+            scopes: token_scopes(&value),
+            token_prefix: value.token_prefix,
+            expires_at: value.expires_at.map(|t| t.and_utc().timestamp()),
+            last_used_at: value.last_used_at.map(|t| t.and_utc().timestamp()),
             created_at: value.created_at.and_utc().timestamp(),
         }
     }
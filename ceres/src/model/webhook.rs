@@ -7,7 +7,10 @@ use utoipa::ToSchema;
 pub struct CreateWebhookRequest {
     pub target_url: String,
     pub secret: String,
-    /// Event types: "cl.created", "cl.updated", "cl.merged", "cl.closed", "cl.reopened", "cl.comment.created", "*"
+    /// Event types: "cl.created", "cl.updated", "cl.merged", "cl.closed", "cl.reopened", "cl.comment.created",
+    /// "push", "tag.created", "tag.deleted", "issue.created", "issue.updated", "issue.closed", "issue.reopened",
+    /// "review.approved", "merge_queue.added", "merge_queue.removed", "merge_queue.merged", "merge_queue.failed",
+    /// "build.completed", "all"
     pub event_types: Vec<String>,
     pub path_filter: Option<String>,
     pub active: Option<bool>,
@@ -24,6 +27,48 @@ pub struct WebhookResponse {
     pub updated_at: String,
 }
 
+/// One delivery of an event to a webhook, including what was sent and what came back.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_type: String,
+    /// `pending`, `sending`, `delivered` or `failed`
+    pub status: String,
+    /// Attempts made so far
+    pub attempt: i32,
+    /// JSON request body
+    pub payload: String,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error_message: Option<String>,
+    /// Delivery this one was manually redelivered from
+    pub redelivery_of: Option<i64>,
+    pub next_retry_at: Option<String>,
+    pub last_attempt_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<callisto::mega_webhook_delivery::Model> for WebhookDeliveryResponse {
+    fn from(m: callisto::mega_webhook_delivery::Model) -> Self {
+        Self {
+            id: m.id,
+            webhook_id: m.webhook_id,
+            event_type: m.event_type.to_value(),
+            status: m.status,
+            attempt: m.attempt,
+            payload: m.payload,
+            response_status: m.response_status,
+            response_body: m.response_body,
+            error_message: m.error_message,
+            redelivery_of: m.redelivery_of,
+            next_retry_at: m.next_retry_at.map(|t| t.to_string()),
+            last_attempt_at: m.last_attempt_at.map(|t| t.to_string()),
+            created_at: m.created_at.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListWebhooksQuery {
     pub page: Option<u64>,
@@ -73,6 +118,26 @@ mod tests {
         assert_eq!(parsed[1], WebhookEventTypeEnum::All);
     }
 
+    #[test]
+    fn parse_webhook_event_types_accepts_non_cl_events() {
+        let parsed = parse_webhook_event_types(vec![
+            "push".to_string(),
+            "tag.deleted".to_string(),
+            "merge_queue.failed".to_string(),
+            "build.completed".to_string(),
+        ])
+        .expect("valid event types");
+        assert_eq!(
+            parsed,
+            vec![
+                WebhookEventTypeEnum::Push,
+                WebhookEventTypeEnum::TagDeleted,
+                WebhookEventTypeEnum::MergeQueueFailed,
+                WebhookEventTypeEnum::BuildCompleted,
+            ]
+        );
+    }
+
     #[test]
     fn parse_webhook_event_types_rejects_unknown_values() {
         let err = parse_webhook_event_types(vec!["not.a.real.event".to_string()])
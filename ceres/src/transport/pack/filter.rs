@@ -0,0 +1,136 @@
+//! Object filters for partial clones, as sent in `filter <spec>` upload-pack lines.
+//!
+//! The supported specs follow `git rev-list --filter`, see
+//! <https://git-scm.com/docs/git-rev-list#Documentation/git-rev-list.txt---filterltfilter-specgt>.
+//! Objects the client asks for by name are never filtered, which is how promisor remotes
+//! fetch the omitted trees and blobs later on.
+
+use std::{fmt, str::FromStr};
+
+use common::errors::ProtocolError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFilter {
+    /// `blob:none`: omit all blobs.
+    BlobNone,
+    /// `blob:limit=<n>[kmg]`: omit blobs of at least `n` bytes.
+    BlobLimit(usize),
+    /// `tree:<depth>`: omit trees and blobs at `depth` or deeper, the root tree being at 0.
+    TreeDepth(usize),
+}
+
+impl ObjectFilter {
+    /// Whether a tree `depth` levels below a commit's root tree is packed.
+    pub fn allows_tree(&self, depth: usize) -> bool {
+        match self {
+            ObjectFilter::TreeDepth(max) => depth < *max,
+            ObjectFilter::BlobNone | ObjectFilter::BlobLimit(_) => true,
+        }
+    }
+
+    /// Whether a blob `depth` levels below a commit's root tree is packed, without
+    /// looking at its size.
+    pub fn allows_blob(&self, depth: usize) -> bool {
+        match self {
+            ObjectFilter::BlobNone => false,
+            ObjectFilter::BlobLimit(_) => true,
+            ObjectFilter::TreeDepth(max) => depth < *max,
+        }
+    }
+
+    /// Whether a blob of `size` bytes passes the filter.
+    pub fn allows_blob_size(&self, size: usize) -> bool {
+        match self {
+            ObjectFilter::BlobLimit(limit) => size < *limit,
+            ObjectFilter::BlobNone | ObjectFilter::TreeDepth(_) => true,
+        }
+    }
+}
+
+impl FromStr for ObjectFilter {
+    type Err = ProtocolError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || ProtocolError::InvalidInput(format!("unsupported filter spec: {spec}"));
+        if spec == "blob:none" {
+            Ok(ObjectFilter::BlobNone)
+        } else if let Some(limit) = spec.strip_prefix("blob:limit=") {
+            let (digits, unit) = match limit.char_indices().last() {
+                Some((i, c)) if c.is_ascii_alphabetic() => (&limit[..i], c.to_ascii_lowercase()),
+                _ => (limit, 'b'),
+            };
+            let factor: usize = match unit {
+                'b' => 1,
+                'k' => 1 << 10,
+                'm' => 1 << 20,
+                'g' => 1 << 30,
+                _ => return Err(invalid()),
+            };
+            let value: usize = digits.parse().map_err(|_| invalid())?;
+            Ok(ObjectFilter::BlobLimit(
+                value.checked_mul(factor).ok_or_else(invalid)?,
+            ))
+        } else if let Some(depth) = spec.strip_prefix("tree:") {
+            Ok(ObjectFilter::TreeDepth(
+                depth.parse().map_err(|_| invalid())?,
+            ))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for ObjectFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectFilter::BlobNone => write!(f, "blob:none"),
+            ObjectFilter::BlobLimit(limit) => write!(f, "blob:limit={limit}"),
+            ObjectFilter::TreeDepth(depth) => write!(f, "tree:{depth}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObjectFilter;
+
+    #[test]
+    fn test_parse_filter_spec() {
+        assert_eq!(
+            "blob:none".parse::<ObjectFilter>().unwrap(),
+            ObjectFilter::BlobNone
+        );
+        assert_eq!(
+            "blob:limit=100".parse::<ObjectFilter>().unwrap(),
+            ObjectFilter::BlobLimit(100)
+        );
+        assert_eq!(
+            "blob:limit=2k".parse::<ObjectFilter>().unwrap(),
+            ObjectFilter::BlobLimit(2048)
+        );
+        assert_eq!(
+            "blob:limit=1M".parse::<ObjectFilter>().unwrap(),
+            ObjectFilter::BlobLimit(1 << 20)
+        );
+        assert_eq!(
+            "tree:0".parse::<ObjectFilter>().unwrap(),
+            ObjectFilter::TreeDepth(0)
+        );
+        assert!("blob:limit=".parse::<ObjectFilter>().is_err());
+        assert!("blob:limit=1x".parse::<ObjectFilter>().is_err());
+        assert!("sparse:oid=abc".parse::<ObjectFilter>().is_err());
+        assert!("combine:blob:none+tree:1".parse::<ObjectFilter>().is_err());
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let tree0 = ObjectFilter::TreeDepth(0);
+        assert!(!tree0.allows_tree(0));
+        let tree1 = ObjectFilter::TreeDepth(1);
+        assert!(tree1.allows_tree(0) && !tree1.allows_tree(1) && !tree1.allows_blob(1));
+        assert!(!ObjectFilter::BlobNone.allows_blob(1));
+        assert!(ObjectFilter::BlobNone.allows_tree(5));
+        let limit = ObjectFilter::BlobLimit(10);
+        assert!(limit.allows_blob(1) && limit.allows_blob_size(9) && !limit.allows_blob_size(10));
+    }
+}
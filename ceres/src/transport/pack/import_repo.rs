@@ -37,7 +37,7 @@ use crate::{
     bus::{ApplicationEventHandler, TransportEvent},
     infra::cache::GitObjectCache,
     transport::{
-        pack::RepoHandler,
+        pack::{RepoHandler, filter::ObjectFilter},
         protocol::{
             import_refs::{CommandType, RefCommand, Refs},
             repo::Repo,
@@ -165,7 +165,18 @@ impl RepoHandler for ImportRepo {
         Ok(())
     }
 
-    async fn full_pack(&self, _: Vec<String>) -> Result<ReceiverStream<Vec<u8>>, GitError> {
+    async fn full_pack(
+        &self,
+        want: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
+    ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
+        // Sending every object of the repo is only correct without a shallow boundary or filter.
+        if !shallow.is_empty() || filter.is_some() {
+            return self
+                .incremental_pack(want, Vec::new(), shallow, filter)
+                .await;
+        }
         let pack_config = &self.storage.config().pack;
         let (entry_tx, entry_rx) = mpsc::channel(pack_config.channel_message_size);
         let (stream_tx, stream_rx) = mpsc::channel(pack_config.channel_message_size);
@@ -190,6 +201,8 @@ impl RepoHandler for ImportRepo {
         &self,
         want: Vec<String>,
         have: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
     ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
         let mut want_clone = want.clone();
         let pack_config = &self.storage.config().pack;
@@ -209,6 +222,10 @@ impl RepoHandler for ImportRepo {
 
         // traverse commit's all parents to find the commit that client does not have
         while let Some(temp) = traversal_list.pop() {
+            // parents of the shallow boundary stay on the server
+            if shallow.contains(&temp.id.to_string()) {
+                continue;
+            }
             for p_commit_id in temp.parent_commit_ids {
                 let p_commit_id = p_commit_id.to_string();
 
@@ -227,6 +244,17 @@ impl RepoHandler for ImportRepo {
             }
         }
 
+        // Wants that are not commits come from promisor remotes fetching omitted objects.
+        let commit_ids: HashSet<String> = want_commits.iter().map(|c| c.id.to_string()).collect();
+        let (object_trees, object_blobs) = self
+            .find_object_wants(
+                want.iter()
+                    .filter(|w| !commit_ids.contains(*w))
+                    .cloned()
+                    .collect(),
+            )
+            .await?;
+
         let want_tree_ids = want_commits.iter().map(|c| c.tree_id.to_string()).collect();
         let want_trees: HashMap<ObjectHash, Tree> = storage
             .get_trees_by_hashes(self.repo.repo_id, want_tree_ids)
@@ -241,7 +269,7 @@ impl RepoHandler for ImportRepo {
             })
             .collect();
 
-        obj_num.fetch_add(want_commits.len(), Ordering::SeqCst);
+        obj_num.fetch_add(want_commits.len() + object_blobs.len(), Ordering::SeqCst);
 
         let have_commits = storage
             .get_commits_by_hashes(self.repo.repo_id, &have)
@@ -256,33 +284,58 @@ impl RepoHandler for ImportRepo {
             .unwrap();
         // traverse to get exist_objs
         for have_tree in have_trees {
-            self.traverse(Tree::from_git_model(have_tree), &mut exist_objs, None)
-                .await?;
+            self.traverse(
+                Tree::from_git_model(have_tree),
+                &mut exist_objs,
+                None,
+                None,
+                0,
+            )
+            .await?;
         }
 
-        let mut counted_obj = HashSet::new();
+        let mut counted_obj: HashSet<String> = object_blobs.iter().cloned().collect();
         // traverse for get obj nums
+        for tree in object_trees.clone() {
+            self.traverse_for_count(tree, &exist_objs, &mut counted_obj, &obj_num, filter, 0)
+                .await?;
+        }
         for c in want_commits.clone() {
+            if filter.is_some_and(|f| !f.allows_tree(0)) {
+                continue;
+            }
             self.traverse_for_count(
                 want_trees.get(&c.tree_id).unwrap().clone(),
                 &exist_objs,
                 &mut counted_obj,
                 &obj_num,
+                filter,
+                0,
             )
-            .await;
+            .await?;
         }
         let (entry_tx, entry_rx) = mpsc::channel(pack_config.channel_message_size);
         let (stream_tx, stream_rx) = mpsc::channel(pack_config.channel_message_size);
         let encoder = PackEncoder::new(obj_num.into_inner(), 0, stream_tx);
         encoder.encode_async(entry_rx).await.unwrap();
 
+        exist_objs.extend(object_blobs.iter().cloned());
+        self.send_blobs(object_blobs, None, &entry_tx).await?;
+        for tree in object_trees {
+            self.traverse(tree, &mut exist_objs, Some(&entry_tx), filter, 0)
+                .await?;
+        }
         for c in want_commits {
-            self.traverse(
-                want_trees.get(&c.tree_id).unwrap().clone(),
-                &mut exist_objs,
-                Some(&entry_tx),
-            )
-            .await?;
+            if filter.is_none_or(|f| f.allows_tree(0)) {
+                self.traverse(
+                    want_trees.get(&c.tree_id).unwrap().clone(),
+                    &mut exist_objs,
+                    Some(&entry_tx),
+                    filter,
+                    0,
+                )
+                .await?;
+            }
             entry_tx
                 .send(MetaAttached {
                     inner: c.into(),
@@ -296,6 +349,17 @@ impl RepoHandler for ImportRepo {
         Ok(ReceiverStream::new(stream_rx))
     }
 
+    async fn get_commits_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError> {
+        Ok(self
+            .storage
+            .git_db_storage()
+            .get_commits_by_hashes(self.repo.repo_id, &hashes)
+            .await?
+            .into_iter()
+            .map(Commit::from_git_model)
+            .collect())
+    }
+
     async fn get_trees_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Tree>, MegaError> {
         Ok(self
             .storage
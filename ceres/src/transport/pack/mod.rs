@@ -13,14 +13,16 @@ use common::{
     errors::{MegaError, ProtocolError},
     utils::ZERO_ID,
 };
-use futures::{TryStreamExt, future::join_all};
+use futures::{StreamExt, TryStreamExt, future::join_all};
 use git_internal::{
     errors::GitError,
     hash::ObjectHash,
     internal::{
         metadata::{EntryMeta, MetaAttached},
         object::{
+            ObjectTrait,
             blob::Blob,
+            commit::Commit,
             tree::{Tree, TreeItemMode},
         },
         pack::{Pack, entry::Entry},
@@ -31,13 +33,23 @@ use sysinfo::System;
 use tokio::sync::{Semaphore, mpsc::UnboundedReceiver};
 use tokio_stream::wrappers::ReceiverStream;
 
-use crate::transport::protocol::import_refs::{RefCommand, Refs};
+use crate::transport::{
+    pack::filter::ObjectFilter,
+    protocol::import_refs::{RefCommand, Refs},
+};
 
+pub mod filter;
 pub mod import_repo;
 pub mod monorepo;
+#[cfg(test)]
+pub(crate) mod test_handler;
 
 pub use crate::infra::pack_stream::{PackByteStream, PackStreamError, into_pack_byte_stream};
 
+/// Most commits and trees read to prove that the objects wanted by name are reachable from
+/// an advertised ref.
+const REACHABILITY_OBJECT_BUDGET: usize = 100_000;
+
 #[async_trait]
 pub trait RepoHandler: Send + Sync + 'static {
     fn is_monorepo(&self) -> bool;
@@ -61,6 +73,12 @@ pub trait RepoHandler: Send + Sync + 'static {
     /// so without this, metadata updated in `git_receive_pack_stream` would be stale at finalize.
     fn sync_commands_after_unpack(&self, _commands: &[RefCommand]) {}
 
+    /// Rejects a branch update that is not allowed for this repository, e.g. a push to a
+    /// protected branch. Runs after unpack and before any ref is persisted.
+    async fn check_ref_update(&self, _command: &RefCommand) -> Result<(), MegaError> {
+        Ok(())
+    }
+
     async fn refs_with_head_hash(&self) -> (String, Vec<Refs>);
 
     async fn receiver_handler(
@@ -207,17 +225,32 @@ pub trait RepoHandler: Send + Sync + 'static {
     /// a single binary vector. There is no need to build the entire tree; the function
     /// only sends all the data related to this repository.
     ///
+    /// History walking stops at the commits in `shallow`: they are packed, their parents
+    /// are not. It holds the boundary negotiated for a shallow client and is empty otherwise.
+    /// A partial clone `filter` omits trees and blobs, except those wanted by name.
+    ///
     /// # Returns
     /// * `Result<Vec<u8>, GitError>` - The packed binary data as a vector of bytes.
     ///
-    async fn full_pack(&self, want: Vec<String>) -> Result<ReceiverStream<Vec<u8>>, GitError>;
+    async fn full_pack(
+        &self,
+        want: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
+    ) -> Result<ReceiverStream<Vec<u8>>, GitError>;
 
+    /// Packs the history of `want` that the client does not have, see [`RepoHandler::full_pack`]
+    /// for `shallow` and `filter`.
     async fn incremental_pack(
         &self,
         want: Vec<String>,
         have: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
     ) -> Result<ReceiverStream<Vec<u8>>, GitError>;
 
+    async fn get_commits_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError>;
+
     async fn get_trees_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Tree>, MegaError>;
 
     async fn get_blobs_by_hashes(
@@ -230,6 +263,57 @@ pub trait RepoHandler: Send + Sync + 'static {
         hashes: Vec<String>,
     ) -> Result<HashMap<String, EntryMeta>, MegaError>;
 
+    /// Resolve the uncompressed size of each object, as reported by protocol v2 `object-info`.
+    ///
+    /// Hashes that cannot be found are omitted from the returned map.
+    async fn get_object_sizes(
+        &self,
+        hashes: Vec<String>,
+    ) -> Result<HashMap<String, usize>, MegaError> {
+        let mut sizes = HashMap::new();
+        for commit in self.get_commits_by_hashes(hashes.clone()).await? {
+            sizes.insert(commit.id.to_string(), commit.get_size());
+        }
+        let remaining: Vec<String> = hashes
+            .into_iter()
+            .filter(|h| !sizes.contains_key(h))
+            .collect();
+        for tree in self.get_trees_by_hashes(remaining.clone()).await? {
+            sizes.insert(tree.id.to_string(), tree.get_size());
+        }
+        let remaining: Vec<String> = remaining
+            .into_iter()
+            .filter(|h| !sizes.contains_key(h))
+            .collect();
+        sizes.extend(self.get_blob_sizes(remaining).await?);
+        Ok(sizes)
+    }
+
+    /// Resolve blob sizes by reading their content. Hashes that cannot be found are omitted.
+    async fn get_blob_sizes(
+        &self,
+        hashes: Vec<String>,
+    ) -> Result<HashMap<String, usize>, MegaError> {
+        let mut sizes = HashMap::new();
+        if hashes.is_empty() {
+            return Ok(sizes);
+        }
+        let mut blobs = self.get_blobs_by_hashes(hashes).await?;
+        while let Some(item) = blobs.next().await {
+            // Missing objects surface as per-item errors; they are simply left unresolved.
+            let Ok((key, stream, _)) = item else {
+                continue;
+            };
+            if let Ok(size) = stream
+                .try_fold(0usize, |acc, bytes| async move { Ok(acc + bytes.len()) })
+                .await
+            {
+                sizes.insert(key.key, size);
+            }
+        }
+        Ok(sizes)
+    }
+
     async fn update_refs(&self, refs: &RefCommand) -> Result<(), GitError>;
 
     async fn check_commit_exist(&self, hash: &str) -> bool;
@@ -283,32 +367,43 @@ pub trait RepoHandler: Send + Sync + 'static {
         Ok((receiver, pack_id_receiver))
     }
 
+    /// Counts the objects [`RepoHandler::traverse`] sends for `tree`, which sits `depth`
+    /// levels below a commit's root tree. Both must apply the same `filter`.
     async fn traverse_for_count(
         &self,
         tree: Tree,
         exist_objs: &HashSet<String>,
         counted_obj: &mut HashSet<String>,
         obj_num: &AtomicUsize,
-    ) {
+        filter: Option<ObjectFilter>,
+        depth: usize,
+    ) -> Result<(), MegaError> {
         let mut search_tree_ids = vec![];
         let mut search_blob_ids = vec![];
         for item in &tree.tree_items {
             let hash = item.id.to_string();
             if !exist_objs.contains(&hash) && counted_obj.insert(hash.clone()) {
                 if item.mode == TreeItemMode::Tree {
-                    search_tree_ids.push(hash.clone())
-                } else {
+                    if filter.is_none_or(|f| f.allows_tree(depth + 1)) {
+                        search_tree_ids.push(hash.clone())
+                    }
+                } else if filter.is_none_or(|f| f.allows_blob(depth + 1)) {
                     search_blob_ids.push(hash.clone());
                 }
             }
         }
+        if let Some(filter) = filter.filter(|f| matches!(f, ObjectFilter::BlobLimit(_))) {
+            let sizes = self.get_blob_sizes(search_blob_ids.clone()).await?;
+            search_blob_ids.retain(|h| sizes.get(h).is_some_and(|s| filter.allows_blob_size(*s)));
+        }
         obj_num.fetch_add(search_blob_ids.len(), Ordering::SeqCst);
-        let trees = self.get_trees_by_hashes(search_tree_ids).await.unwrap();
+        let trees = self.get_trees_by_hashes(search_tree_ids).await?;
         for t in trees {
-            self.traverse_for_count(t, exist_objs, counted_obj, obj_num)
-                .await;
+            self.traverse_for_count(t, exist_objs, counted_obj, obj_num, filter, depth + 1)
+                .await?;
         }
         obj_num.fetch_add(1, Ordering::SeqCst);
+        Ok(())
     }
 
     /// Traverse a tree structure asynchronously.
@@ -322,6 +417,8 @@ pub trait RepoHandler: Send + Sync + 'static {
     /// - `tree`: The tree structure to traverse.
     /// - `exist_objs`: A mutable reference to a set containing already processed object IDs.
     /// - `sender`: An optional sender for sending traversal data.
+    /// - `filter`: The partial clone filter; `tree` itself is always sent.
+    /// - `depth`: How far `tree` is below a commit's root tree, which `tree:<depth>` filters on.
     ///
     /// # Details
     /// - The function processes tree items, distinguishing between tree and blob items.
@@ -334,6 +431,8 @@ pub trait RepoHandler: Send + Sync + 'static {
         tree: Tree,
         exist_objs: &mut HashSet<String>,
         sender: Option<&tokio::sync::mpsc::Sender<MetaAttached<Entry, EntryMeta>>>,
+        filter: Option<ObjectFilter>,
+        depth: usize,
     ) -> Result<(), MegaError> {
         let mut search_tree_ids = vec![];
         let mut search_blob_ids = vec![];
@@ -342,48 +441,23 @@ pub trait RepoHandler: Send + Sync + 'static {
             let hash = item.id.to_string();
             if exist_objs.insert(hash.clone()) {
                 if item.mode == TreeItemMode::Tree {
-                    search_tree_ids.push(hash);
-                } else {
+                    if filter.is_none_or(|f| f.allows_tree(depth + 1)) {
+                        search_tree_ids.push(hash);
+                    }
+                } else if filter.is_none_or(|f| f.allows_blob(depth + 1)) {
                     search_blob_ids.push(hash);
                 }
             }
         }
 
         if let Some(sender) = sender {
-            let blobs = self.get_blobs_by_hashes(search_blob_ids.clone()).await?;
-            let blobs_ext_data = self
-                .get_blob_metadata_by_hashes(search_blob_ids.clone())
-                .await?;
-
-            let default_meta = EntryMeta::default();
-            blobs
-                .try_for_each_concurrent(16, |(_, stream, _)| async {
-                    let data = stream
-                        .try_fold(Vec::new(), |mut acc, bytes| async move {
-                            acc.extend_from_slice(&bytes);
-                            Ok(acc)
-                        })
-                        .await?;
-                    let blob = Blob::from_content_bytes(data);
-                    let ext_data = blobs_ext_data
-                        .get(&blob.id.to_string())
-                        .unwrap_or(&default_meta);
-                    sender
-                        .send(MetaAttached {
-                            inner: blob.into(),
-                            meta: ext_data.to_owned(),
-                        })
-                        .await
-                        .unwrap();
-
-                    Ok(())
-                })
-                .await?;
+            self.send_blobs(search_blob_ids, filter, sender).await?;
         }
 
         let trees = self.get_trees_by_hashes(search_tree_ids).await?;
         for t in trees {
-            self.traverse(t, exist_objs, sender).await?;
+            self.traverse(t, exist_objs, sender, filter, depth + 1)
+                .await?;
         }
 
         if let Some(sender) = sender {
@@ -398,5 +472,242 @@ pub trait RepoHandler: Send + Sync + 'static {
         Ok(())
     }
 
+    /// Sends blobs with their pack metadata, skipping the ones a `blob:limit` filter omits.
+    async fn send_blobs(
+        &self,
+        hashes: Vec<String>,
+        filter: Option<ObjectFilter>,
+        sender: &tokio::sync::mpsc::Sender<MetaAttached<Entry, EntryMeta>>,
+    ) -> Result<(), MegaError> {
+        let blobs = self.get_blobs_by_hashes(hashes.clone()).await?;
+        let blobs_ext_data = self.get_blob_metadata_by_hashes(hashes).await?;
+
+        let default_meta = EntryMeta::default();
+        blobs
+            .try_for_each_concurrent(16, |(_, stream, _)| async {
+                let data = stream
+                    .try_fold(Vec::new(), |mut acc, bytes| async move {
+                        acc.extend_from_slice(&bytes);
+                        Ok(acc)
+                    })
+                    .await?;
+                if filter.is_some_and(|f| !f.allows_blob_size(data.len())) {
+                    return Ok(());
+                }
+                let blob = Blob::from_content_bytes(data);
+                let ext_data = blobs_ext_data
+                    .get(&blob.id.to_string())
+                    .unwrap_or(&default_meta);
+                sender
+                    .send(MetaAttached {
+                        inner: blob.into(),
+                        meta: ext_data.to_owned(),
+                    })
+                    .await
+                    .unwrap();
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Splits wants that are not commits into the trees and blobs this repo has.
+    ///
+    /// Promisor remotes of a partial clone fetch the objects a filter omitted by asking
+    /// for them by name; such objects are packed regardless of the filter. Only objects
+    /// reachable from an advertised ref may be asked for (`allow-reachable-sha1-in-want`).
+    async fn find_object_wants(
+        &self,
+        hashes: Vec<String>,
+    ) -> Result<(Vec<Tree>, Vec<String>), MegaError> {
+        if hashes.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let unreachable = self
+            .unreachable_object_wants(hashes.iter().cloned().collect(), REACHABILITY_OBJECT_BUDGET)
+            .await?;
+        if let Some(hash) = unreachable.iter().next() {
+            return Err(MegaError::Forbidden(format!(
+                "upload-pack: not our ref {hash}"
+            )));
+        }
+        let trees = self.get_trees_by_hashes(hashes.clone()).await?;
+        let tree_ids: HashSet<String> = trees.iter().map(|t| t.id.to_string()).collect();
+        let remaining: Vec<String> = hashes
+            .into_iter()
+            .filter(|h| !tree_ids.contains(h))
+            .collect();
+        let blobs = self
+            .get_blob_metadata_by_hashes(remaining)
+            .await?
+            .into_keys()
+            .collect();
+        Ok((trees, blobs))
+    }
+
+    /// Returns the objects of `wants` that no commit reachable from an advertised ref
+    /// contains. Walks the history from the refs generation by generation, so the trees of
+    /// the ref tips are searched first, reading every tree once, and stops as soon as all
+    /// wants are found.
+    ///
+    /// Fails closed once more than `budget` commits and trees have been read with wants left
+    /// unfound, so a want of an old or unknown object cannot make a fetch walk the whole
+    /// history.
+    async fn unreachable_object_wants(
+        &self,
+        mut wants: HashSet<String>,
+        budget: usize,
+    ) -> Result<HashSet<String>, MegaError> {
+        let (_, refs) = self.refs_with_head_hash().await;
+        let mut commit_ids: Vec<String> = refs
+            .into_iter()
+            .map(|r| r.ref_hash)
+            .filter(|h| h != ZERO_ID)
+            .collect();
+        let mut seen_commits: HashSet<String> = commit_ids.iter().cloned().collect();
+        let mut seen_trees: HashSet<String> = HashSet::new();
+        let mut read = 0;
+        let mut spend = |count: usize, wants: &HashSet<String>| {
+            read += count;
+            if read > budget {
+                return Err(MegaError::Forbidden(format!(
+                    "upload-pack: {} wanted objects not found within {budget} objects \
+                     reachable from the advertised refs",
+                    wants.len()
+                )));
+            }
+            Ok(())
+        };
+
+        while !wants.is_empty() && !commit_ids.is_empty() {
+            spend(commit_ids.len(), &wants)?;
+            let mut tree_ids = vec![];
+            for commit in self
+                .get_commits_by_hashes(std::mem::take(&mut commit_ids))
+                .await?
+            {
+                let tree_id = commit.tree_id.to_string();
+                if seen_trees.insert(tree_id.clone()) {
+                    tree_ids.push(tree_id);
+                }
+                for parent in commit.parent_commit_ids {
+                    let parent = parent.to_string();
+                    if seen_commits.insert(parent.clone()) {
+                        commit_ids.push(parent);
+                    }
+                }
+            }
+            while !wants.is_empty() && !tree_ids.is_empty() {
+                tree_ids.iter().for_each(|id| {
+                    wants.remove(id);
+                });
+                if wants.is_empty() {
+                    break;
+                }
+                spend(tree_ids.len(), &wants)?;
+                for tree in self
+                    .get_trees_by_hashes(std::mem::take(&mut tree_ids))
+                    .await?
+                {
+                    for item in tree.tree_items {
+                        let id = item.id.to_string();
+                        wants.remove(&id);
+                        if item.mode == TreeItemMode::Tree && seen_trees.insert(id.clone()) {
+                            tree_ids.push(id);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(wants)
+    }
+
     async fn traverses_tree_and_update_filepath(&self) -> Result<(), MegaError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use git_internal::internal::object::tree::TreeItem;
+
+    use super::*;
+    use crate::transport::pack::test_handler::MemoryRepo;
+
+    fn tree(items: Vec<(TreeItemMode, ObjectHash, &str)>) -> Tree {
+        Tree::from_tree_items(
+            items
+                .into_iter()
+                .map(|(mode, id, name)| TreeItem::new(mode, id, name.to_owned()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_object_wants_must_be_reachable_from_refs() {
+        let old = Blob::from_content("removed in the second commit");
+        let kept = Blob::from_content("kept");
+        let hidden = Blob::from_content("only reachable from an unadvertised commit");
+        let src_v1 = tree(vec![(TreeItemMode::Blob, old.id, "old.txt")]);
+        let root_v1 = tree(vec![(TreeItemMode::Tree, src_v1.id, "src")]);
+        let root_v2 = tree(vec![(TreeItemMode::Blob, kept.id, "kept.txt")]);
+        let root_hidden = tree(vec![(TreeItemMode::Blob, hidden.id, "hidden.txt")]);
+        let c1 = Commit::from_tree_id(root_v1.id, vec![], "c1");
+        let c2 = Commit::from_tree_id(root_v2.id, vec![c1.id], "c2");
+        let unadvertised = Commit::from_tree_id(root_hidden.id, vec![], "unadvertised");
+
+        let repo = MemoryRepo {
+            commits: [&c1, &c2, &unadvertised]
+                .into_iter()
+                .map(|c| (c.id.to_string(), c.clone()))
+                .collect(),
+            trees: [&src_v1, &root_v1, &root_v2, &root_hidden]
+                .into_iter()
+                .map(|t| (t.id.to_string(), t.clone()))
+                .collect(),
+            blobs: [&old, &kept, &hidden]
+                .into_iter()
+                .map(|b| b.id.to_string())
+                .collect(),
+            refs: vec![Refs {
+                id: 0,
+                ref_name: "refs/heads/main".to_owned(),
+                ref_hash: c2.id.to_string(),
+                default_branch: true,
+            }],
+            ..Default::default()
+        };
+
+        let (trees, blobs) = repo
+            .find_object_wants(vec![old.id.to_string(), src_v1.id.to_string()])
+            .await
+            .unwrap();
+        assert_eq!(trees, vec![src_v1]);
+        assert_eq!(blobs, vec![old.id.to_string()]);
+
+        for want in [hidden.id.to_string(), root_hidden.id.to_string()] {
+            let err = repo.find_object_wants(vec![want]).await.unwrap_err();
+            assert!(matches!(err, MegaError::Forbidden(_)));
+        }
+
+        // c2 and its tree are enough to find a blob of the tip, but finding one that only the
+        // first commit has needs c1 and its trees too
+        let wants = |hash: ObjectHash| HashSet::from([hash.to_string()]);
+        assert!(
+            repo.unreachable_object_wants(wants(kept.id), 2)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let err = repo
+            .unreachable_object_wants(wants(old.id), 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)));
+        assert!(
+            repo.unreachable_object_wants(wants(old.id), 5)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -31,14 +31,16 @@ use git_internal::{
 };
 use io_orbit::object_storage::MultiObjectByteStream;
 use jupiter::{sea_orm::DatabaseTransaction, storage::Storage, utils::converter::FromMegaModel};
+use saturn::ActionEnum;
 use tokio::sync::{RwLock, mpsc};
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
+    application::api_service::mono::admin::policy::{MainPolicies, changed_dirs},
     bus::{ApplicationEventHandler, TransportEvent},
     infra::cache::GitObjectCache,
     transport::{
-        pack::RepoHandler,
+        pack::{RepoHandler, filter::ObjectFilter},
         protocol::import_refs::{RefCommand, Refs},
     },
 };
@@ -52,7 +54,10 @@ pub struct MonoRepo {
     pub to_hash: String,
     // current_commit only exists when an unpack operation occurs.
     // When only a branch is updated and the pack file is empty, this value will be None.
+    // For multi-commit pushes it holds the pushed tip (`to_hash`).
     pub current_commit: Arc<RwLock<Option<Commit>>>,
+    /// Every commit received in this push, in pack order.
+    pub pushed_commits: Arc<RwLock<Vec<Commit>>>,
     pub cl_link: Arc<RwLock<Option<String>>>,
     pub application: Arc<dyn ApplicationEventHandler>,
     pub username: Option<String>,
@@ -77,6 +82,28 @@ impl RepoHandler for MonoRepo {
             .expect("command_list lock poisoned") = commands.to_vec();
     }
 
+    async fn check_ref_update(&self, command: &RefCommand) -> Result<(), MegaError> {
+        if command.ref_type != RefTypeEnum::Branch {
+            return Ok(());
+        }
+        self.authorize_changed_dirs(command).await?;
+        if command.ref_name != common::utils::MEGA_BRANCH_NAME {
+            return Ok(());
+        }
+        let rule = self
+            .storage
+            .cl_storage()
+            .get_protection_rule_by_path(&self.path.to_string_lossy())
+            .await?;
+        match rule {
+            Some(rule) if rule.block_direct_push => Err(MegaError::Other(format!(
+                "{} is protected under {}, push to another branch to open a CL",
+                command.ref_name, rule.path
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     async fn refs_with_head_hash(&self) -> (String, Vec<Refs>) {
         let storage = self.storage.mono_storage();
 
@@ -171,6 +198,7 @@ impl RepoHandler for MonoRepo {
                 from_hash: self.from_hash.clone(),
                 to_hash: self.to_hash.clone(),
                 username: self.username.clone(),
+                commits: self.ordered_pushed_commits().await,
             })
             .await
     }
@@ -216,29 +244,34 @@ impl RepoHandler for MonoRepo {
     }
 
     async fn check_entry(&self, entry: &Entry) -> Result<(), GitError> {
-        if self.current_commit.read().await.is_none() {
-            if entry.obj_type == ObjectType::Commit {
-                let commit = Commit::from_bytes(&entry.data, entry.hash).unwrap();
-                let mut current = self.current_commit.write().await;
-                *current = Some(commit);
+        if entry.obj_type == ObjectType::Commit {
+            let commit = Commit::from_bytes(&entry.data, entry.hash)?;
+            let mut current = self.current_commit.write().await;
+            if current.is_none() || commit.id.to_string() == self.to_hash {
+                *current = Some(commit.clone());
             }
-        } else if entry.obj_type == ObjectType::Commit {
-            return Err(GitError::CustomError(
-                "only single commit support in each push".to_string(),
-            ));
+            self.pushed_commits.write().await.push(commit);
         }
         Ok(())
     }
 
     // monorepo full pack should follow the shallow clone command 'git clone --depth=1'
-    async fn full_pack(&self, want: Vec<String>) -> Result<ReceiverStream<Vec<u8>>, GitError> {
-        self.incremental_pack(want, Vec::new()).await
+    async fn full_pack(
+        &self,
+        want: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
+    ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
+        self.incremental_pack(want, Vec::new(), shallow, filter)
+            .await
     }
 
     async fn incremental_pack(
         &self,
         want: Vec<String>,
         have: Vec<String>,
+        shallow: &HashSet<String>,
+        filter: Option<ObjectFilter>,
     ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
         let mut want_clone = want.clone();
         let pack_config = &self.storage.config().pack;
@@ -258,6 +291,10 @@ impl RepoHandler for MonoRepo {
 
         // traverse commit's all parents to find the commit that client does not have
         while let Some(temp) = traversal_list.pop() {
+            // parents of the shallow boundary stay on the server
+            if shallow.contains(&temp.id.to_string()) {
+                continue;
+            }
             for p_commit_id in temp.parent_commit_ids {
                 let p_commit_id = p_commit_id.to_string();
 
@@ -276,6 +313,17 @@ impl RepoHandler for MonoRepo {
             }
         }
 
+        // Wants that are not commits come from promisor remotes fetching omitted objects.
+        let commit_ids: HashSet<String> = want_commits.iter().map(|c| c.id.to_string()).collect();
+        let (object_trees, object_blobs) = self
+            .find_object_wants(
+                want.iter()
+                    .filter(|w| !commit_ids.contains(*w))
+                    .cloned()
+                    .collect(),
+            )
+            .await?;
+
         let want_tree_ids = want_commits.iter().map(|c| c.tree_id.to_string()).collect();
         let want_trees: HashMap<ObjectHash, Tree> = storage
             .get_trees_by_hashes(want_tree_ids)
@@ -290,7 +338,7 @@ impl RepoHandler for MonoRepo {
             })
             .collect();
 
-        obj_num.fetch_add(want_commits.len(), Ordering::SeqCst);
+        obj_num.fetch_add(want_commits.len() + object_blobs.len(), Ordering::SeqCst);
 
         let have_commits = storage.get_commits_by_hashes(&have).await.unwrap();
         let have_trees = storage
@@ -298,33 +346,58 @@ impl RepoHandler for MonoRepo {
             .await
             .unwrap();
         for have_tree in have_trees {
-            self.traverse(Tree::from_mega_model(have_tree), &mut exist_objs, None)
-                .await?;
+            self.traverse(
+                Tree::from_mega_model(have_tree),
+                &mut exist_objs,
+                None,
+                None,
+                0,
+            )
+            .await?;
         }
 
-        let mut counted_obj = HashSet::new();
+        let mut counted_obj: HashSet<String> = object_blobs.iter().cloned().collect();
         // traverse for get obj nums
+        for tree in object_trees.clone() {
+            self.traverse_for_count(tree, &exist_objs, &mut counted_obj, &obj_num, filter, 0)
+                .await?;
+        }
         for c in want_commits.clone() {
+            if filter.is_some_and(|f| !f.allows_tree(0)) {
+                continue;
+            }
             self.traverse_for_count(
                 want_trees.get(&c.tree_id).unwrap().clone(),
                 &exist_objs,
                 &mut counted_obj,
                 &obj_num,
+                filter,
+                0,
             )
-            .await;
+            .await?;
         }
         let (entry_tx, entry_rx) = mpsc::channel(pack_config.channel_message_size);
         let (stream_tx, stream_rx) = mpsc::channel(pack_config.channel_message_size);
         let encoder = PackEncoder::new(obj_num.into_inner(), 0, stream_tx);
         encoder.encode_async(entry_rx).await.unwrap();
         // todo: For now, send metadata only for blob objects.
+        exist_objs.extend(object_blobs.iter().cloned());
+        self.send_blobs(object_blobs, None, &entry_tx).await?;
+        for tree in object_trees {
+            self.traverse(tree, &mut exist_objs, Some(&entry_tx), filter, 0)
+                .await?;
+        }
         for c in want_commits {
-            self.traverse(
-                want_trees.get(&c.tree_id).unwrap().clone(),
-                &mut exist_objs,
-                Some(&entry_tx),
-            )
-            .await?;
+            if filter.is_none_or(|f| f.allows_tree(0)) {
+                self.traverse(
+                    want_trees.get(&c.tree_id).unwrap().clone(),
+                    &mut exist_objs,
+                    Some(&entry_tx),
+                    filter,
+                    0,
+                )
+                .await?;
+            }
             entry_tx
                 .send(MetaAttached {
                     inner: c.into(),
@@ -338,6 +411,17 @@ impl RepoHandler for MonoRepo {
         Ok(ReceiverStream::new(stream_rx))
     }
 
+    async fn get_commits_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError> {
+        Ok(self
+            .storage
+            .mono_storage()
+            .get_commits_by_hashes(&hashes)
+            .await?
+            .into_iter()
+            .map(Commit::from_mega_model)
+            .collect())
+    }
+
     async fn get_trees_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Tree>, MegaError> {
         Ok(self
             .storage
@@ -468,6 +552,39 @@ impl RepoHandler for MonoRepo {
 }
 
 impl MonoRepo {
+    /// Checks the pushed changes against the Cedar policies of every directory they touch, so
+    /// a push to a parent path cannot modify a subdirectory the pusher may not push to. A new
+    /// branch is compared with the main branch of the path.
+    async fn authorize_changed_dirs(&self, command: &RefCommand) -> Result<(), MegaError> {
+        if !self.storage.config().monorepo.enforce_cedar_policies || command.new_id == ZERO_ID {
+            return Ok(());
+        }
+        let Some(policies) =
+            MainPolicies::load(&self.storage, Some(&self.git_object_cache)).await?
+        else {
+            return Ok(());
+        };
+        let path = self.path.to_string_lossy();
+        let old_id = if command.old_id == ZERO_ID {
+            self.storage
+                .mono_storage()
+                .get_main_ref(&path)
+                .await?
+                .map_or_else(|| ZERO_ID.to_string(), |r| r.ref_commit_hash)
+        } else {
+            command.old_id.clone()
+        };
+        let dirs = changed_dirs(&self.storage, &path, &old_id, &command.new_id).await?;
+        policies
+            .authorize_paths(self.username.as_deref(), ActionEnum::PushRepo, &path, &dirs)
+            .await
+    }
+
+    /// Commits received in this push, parents before children.
+    async fn ordered_pushed_commits(&self) -> Vec<Commit> {
+        order_parents_first(self.pushed_commits.read().await.clone())
+    }
+
     /// All branch commands update CL `mega_refs` in **one** DB transaction (same idea as import’s single-txn metadata commit).
     async fn persist_mono_branch_cl_mega_refs_transaction(&self) -> Result<(), MegaError> {
         let cmds = self
@@ -618,3 +735,61 @@ impl MonoRepo {
         self.username.clone().unwrap_or(String::from("Anonymous"))
     }
 }
+
+/// Orders a pushed commit series so every commit comes after the parents that are part of the
+/// same series, keeping the original order otherwise. Merge commits are supported.
+fn order_parents_first(commits: Vec<Commit>) -> Vec<Commit> {
+    let mut pending: HashMap<ObjectHash, Commit> =
+        commits.iter().map(|c| (c.id, c.clone())).collect();
+    let mut ordered = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let mut stack = vec![(commit.id, false)];
+        while let Some((id, parents_done)) = stack.pop() {
+            if parents_done {
+                if let Some(c) = pending.remove(&id) {
+                    ordered.push(c);
+                }
+                continue;
+            }
+            let Some(c) = pending.get(&id) else {
+                continue;
+            };
+            stack.push((id, true));
+            for parent in c.parent_commit_ids.iter().rev() {
+                if pending.contains_key(parent) {
+                    stack.push((*parent, false));
+                }
+            }
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use git_internal::{hash::ObjectHash, internal::object::commit::Commit};
+
+    use super::order_parents_first;
+
+    fn commit(parents: &[&Commit], message: &str) -> Commit {
+        Commit::from_tree_id(
+            ObjectHash::default(),
+            parents.iter().map(|p| p.id).collect(),
+            message,
+        )
+    }
+
+    #[test]
+    fn test_order_parents_first() {
+        let base = commit(&[], "base");
+        let a = commit(&[&base], "a");
+        let b = commit(&[&a], "b");
+        let side = commit(&[&base], "side");
+        let merge = commit(&[&b, &side], "merge");
+
+        // git packs commits newest first
+        let ordered = order_parents_first(vec![merge.clone(), b.clone(), side.clone(), a.clone()]);
+        let ids: Vec<_> = ordered.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![a.id, b.id, side.id, merge.id]);
+    }
+}
@@ -0,0 +1,190 @@
+//! In-memory [`RepoHandler`] that drives the protocol layer in tests without storage.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use common::errors::MegaError;
+use git_internal::{
+    errors::GitError,
+    hash::ObjectHash,
+    internal::{
+        metadata::{EntryMeta, MetaAttached},
+        object::{commit::Commit, tree::Tree},
+        pack::entry::Entry,
+    },
+};
+use io_orbit::object_storage::MultiObjectByteStream;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::transport::{
+    pack::{RepoHandler, filter::ObjectFilter},
+    protocol::import_refs::{RefCommand, Refs},
+};
+
+/// Arguments of a `full_pack` or `incremental_pack` call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PackCall {
+    pub want: HashSet<String>,
+    pub have: HashSet<String>,
+    pub shallow: HashSet<String>,
+}
+
+/// Commit graph with a single `main` branch; packs are recorded and come back empty.
+#[derive(Default)]
+pub(crate) struct MemoryRepo {
+    pub commits: HashMap<String, Commit>,
+    pub trees: HashMap<String, Tree>,
+    /// Blobs the repo has; their content is never read.
+    pub blobs: HashSet<String>,
+    pub refs: Vec<Refs>,
+    pub packs: Mutex<Vec<PackCall>>,
+}
+
+impl MemoryRepo {
+    /// Linear history `c0 <- c1 <- ... <- c{n-1}` with `refs/heads/main` at the last commit.
+    pub fn linear(n: usize) -> (Self, Vec<String>) {
+        let mut commits = HashMap::new();
+        let mut hashes: Vec<String> = Vec::new();
+        for i in 0..n {
+            let parents = hashes
+                .last()
+                .map(|h| vec![h.parse::<ObjectHash>().unwrap()])
+                .unwrap_or_default();
+            let mut commit = Commit::from_tree_id(ObjectHash::default(), parents, &format!("c{i}"));
+            commit.committer.timestamp = i;
+            hashes.push(commit.id.to_string());
+            commits.insert(commit.id.to_string(), commit);
+        }
+        let refs = vec![Refs {
+            id: 0,
+            ref_name: "refs/heads/main".to_owned(),
+            ref_hash: hashes.last().cloned().unwrap_or_default(),
+            default_branch: true,
+        }];
+        let repo = MemoryRepo {
+            commits,
+            refs,
+            ..Default::default()
+        };
+        (repo, hashes)
+    }
+
+    pub fn packs(&self) -> Vec<PackCall> {
+        self.packs.lock().unwrap().clone()
+    }
+
+    fn record_pack(
+        &self,
+        want: Vec<String>,
+        have: Vec<String>,
+        shallow: &HashSet<String>,
+    ) -> ReceiverStream<Vec<u8>> {
+        self.packs.lock().unwrap().push(PackCall {
+            want: want.into_iter().collect(),
+            have: have.into_iter().collect(),
+            shallow: shallow.clone(),
+        });
+        let (_, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(1);
+        ReceiverStream::new(rx)
+    }
+}
+
+#[async_trait]
+impl RepoHandler for MemoryRepo {
+    fn is_monorepo(&self) -> bool {
+        true
+    }
+
+    async fn refs_with_head_hash(&self) -> (String, Vec<Refs>) {
+        self.find_head_hash(self.refs.clone())
+    }
+
+    async fn finalize_receive_pack(&self) -> Result<(), MegaError> {
+        unimplemented!()
+    }
+
+    async fn save_entry(
+        &self,
+        _entry_list: Vec<MetaAttached<Entry, EntryMeta>>,
+    ) -> Result<(), MegaError> {
+        unimplemented!()
+    }
+
+    async fn update_pack_id(&self, _temp_pack_id: &str, _pack_id: &str) -> Result<(), MegaError> {
+        unimplemented!()
+    }
+
+    async fn check_entry(&self, _entry: &Entry) -> Result<(), GitError> {
+        unimplemented!()
+    }
+
+    async fn full_pack(
+        &self,
+        want: Vec<String>,
+        shallow: &HashSet<String>,
+        _filter: Option<ObjectFilter>,
+    ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
+        Ok(self.record_pack(want, Vec::new(), shallow))
+    }
+
+    async fn incremental_pack(
+        &self,
+        want: Vec<String>,
+        have: Vec<String>,
+        shallow: &HashSet<String>,
+        _filter: Option<ObjectFilter>,
+    ) -> Result<ReceiverStream<Vec<u8>>, GitError> {
+        Ok(self.record_pack(want, have, shallow))
+    }
+
+    async fn get_commits_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError> {
+        Ok(hashes
+            .iter()
+            .filter_map(|h| self.commits.get(h).cloned())
+            .collect())
+    }
+
+    async fn get_trees_by_hashes(&self, hashes: Vec<String>) -> Result<Vec<Tree>, MegaError> {
+        Ok(hashes
+            .iter()
+            .filter_map(|h| self.trees.get(h).cloned())
+            .collect())
+    }
+
+    async fn get_blobs_by_hashes(
+        &self,
+        _hashes: Vec<String>,
+    ) -> Result<MultiObjectByteStream<'_>, MegaError> {
+        unimplemented!()
+    }
+
+    async fn get_blob_metadata_by_hashes(
+        &self,
+        hashes: Vec<String>,
+    ) -> Result<HashMap<String, EntryMeta>, MegaError> {
+        Ok(hashes
+            .into_iter()
+            .filter(|h| self.blobs.contains(h))
+            .map(|h| (h, EntryMeta::new()))
+            .collect())
+    }
+
+    async fn update_refs(&self, _refs: &RefCommand) -> Result<(), GitError> {
+        unimplemented!()
+    }
+
+    async fn check_commit_exist(&self, hash: &str) -> bool {
+        self.commits.contains_key(hash)
+    }
+
+    async fn check_default_branch(&self) -> bool {
+        true
+    }
+
+    async fn traverses_tree_and_update_filepath(&self) -> Result<(), MegaError> {
+        unimplemented!()
+    }
+}
@@ -0,0 +1,60 @@
+//! Cedar authorization for Git transport operations.
+//!
+//! When `monorepo.enforce_cedar_policies` is enabled, every upload-pack and receive-pack
+//! session is checked against the policies that apply to the target path, see
+//! [`crate::application::api_service::mono::admin::policy`]. The deepest `Repository` entity
+//! covering the target path is the resource being accessed. An upload-pack also sends what
+//! lies below the target path, so the policies below it must allow the pull as well; a
+//! receive-pack is further checked per changed directory once its pack is unpacked.
+
+use common::errors::{MegaError, ProtocolError};
+use saturn::ActionEnum;
+
+use crate::{
+    application::api_service::mono::admin::policy::MainPolicies,
+    bus::TransportRuntime,
+    transport::protocol::{ServiceType, SmartSession},
+};
+
+impl SmartSession {
+    /// Authorize the session user for its service on `repo_path`.
+    ///
+    /// Anonymous users that are denied get [`ProtocolError::Deny`] so that HTTP clients retry
+    /// with credentials; authenticated users get [`ProtocolError::Forbidden`].
+    pub async fn authorize(&self, state: &TransportRuntime) -> Result<(), ProtocolError> {
+        if !state.storage.config().monorepo.enforce_cedar_policies {
+            return Ok(());
+        }
+        let path = self.repo_path.to_string_lossy();
+        let username = self.auth.username.as_deref();
+
+        let result = match MainPolicies::load(&state.storage, Some(&state.git_object_cache)).await {
+            Ok(Some(policies)) => match self.service_type {
+                ServiceType::UploadPack => {
+                    policies
+                        .authorize_subtree(username, ActionEnum::PullRepo, &path)
+                        .await
+                }
+                ServiceType::ReceivePack => {
+                    policies
+                        .authorize(username, ActionEnum::PushRepo, &path)
+                        .await
+                }
+            },
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+
+        result.map_err(|e| {
+            if matches!(e, MegaError::Forbidden(_) | MegaError::Unauthorized(_)) {
+                tracing::info!("{e} for {}", username.unwrap_or("anonymous user"));
+            }
+            match e {
+                MegaError::Forbidden(msg) => ProtocolError::Forbidden(msg),
+                MegaError::Unauthorized(msg) => ProtocolError::Deny(msg),
+                MegaError::BadRequest(msg) => ProtocolError::InvalidInput(msg),
+                e => ProtocolError::IO(std::io::Error::other(e.to_string())),
+            }
+        })
+    }
+}
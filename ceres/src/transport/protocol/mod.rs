@@ -18,9 +18,12 @@ use crate::{
     transport::pack::{RepoHandler, import_repo::ImportRepo, monorepo::MonoRepo},
 };
 
+pub mod authz;
 pub mod import_refs;
 pub mod repo;
+pub mod shallow;
 pub mod smart;
+pub mod smart_v2;
 
 pub use common::utils::ZERO_ID;
 
@@ -44,6 +47,9 @@ pub struct SmartSession {
     pub transport_protocol: TransportProtocol,
     pub auth: AuthContext,
     pub capabilities: HashSet<Capability>,
+    pub protocol_version: ProtocolVersion,
+    /// Protocol v0 upload-pack negotiation carried across stateful (SSH) rounds.
+    pub upload_request: smart::UploadPackRequest,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
@@ -54,6 +60,32 @@ pub enum TransportProtocol {
     Ssh,
 }
 
+/// Wire protocol version negotiated through `GIT_PROTOCOL` (SSH env) or the
+/// `Git-Protocol` HTTP header. Version 1 is identical to version 0 apart from
+/// an extra `version 1` line, so it is served as version 0.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ProtocolVersion {
+    #[default]
+    V0,
+    V2,
+}
+
+impl ProtocolVersion {
+    /// Parses a colon-separated `GIT_PROTOCOL` value such as `version=2:object-format=sha1`.
+    ///
+    /// Unknown keys are ignored; when several `version=` entries are present the highest
+    /// supported one wins, mirroring `git`'s own server behaviour.
+    pub fn from_git_protocol(value: &str) -> Self {
+        value
+            .split(':')
+            .filter_map(|kv| kv.trim().strip_prefix("version="))
+            .fold(ProtocolVersion::V0, |acc, v| match v {
+                "2" => ProtocolVersion::V2,
+                _ => acc,
+            })
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ServiceType {
     UploadPack,
@@ -92,8 +124,10 @@ pub enum Capability {
     ReportStatus,
     ReportStatusv2,
     OfsDelta,
+    Shallow,
     DeepenSince,
     DeepenNot,
+    DeepenRelative,
 }
 
 impl FromStr for Capability {
@@ -111,6 +145,8 @@ impl FromStr for Capability {
             "no-done" => Ok(Capability::NoDone),
             "deepen-since" => Ok(Capability::DeepenSince),
             "deepen-not" => Ok(Capability::DeepenNot),
+            "shallow" => Ok(Capability::Shallow),
+            "deepen-relative" => Ok(Capability::DeepenRelative),
             _ => Err(()),
         }
     }
@@ -153,6 +189,8 @@ impl SmartSession {
                 authenticated_user: None,
             },
             capabilities: HashSet::new(),
+            protocol_version: ProtocolVersion::V0,
+            upload_request: smart::UploadPackRequest::default(),
         }
     }
 
@@ -161,6 +199,7 @@ impl SmartSession {
         state: &TransportRuntime,
         commands: Vec<RefCommand>,
     ) -> Result<Arc<dyn RepoHandler>, ProtocolError> {
+        self.authorize(state).await?;
         let config = state.storage.config();
         let import_dir = config.monorepo.import_dir.clone();
 
@@ -208,6 +247,7 @@ impl SmartSession {
                 from_hash: String::new(),
                 to_hash: String::new(),
                 current_commit: Arc::new(RwLock::new(None)),
+                pushed_commits: Arc::new(RwLock::new(Vec::new())),
                 cl_link: Arc::new(RwLock::new(None)),
                 application: state.application.clone(),
                 username: self.auth.username.clone(),
@@ -231,7 +271,7 @@ impl SmartSession {
 mod tests {
     use std::str::FromStr;
 
-    use super::{Capability, ServiceType, SideBind};
+    use super::{Capability, ProtocolVersion, ServiceType, SideBind};
 
     #[test]
     fn service_type_from_str_parses_known_services() {
@@ -255,6 +295,23 @@ mod tests {
         assert!(Capability::from_str("unknown-cap").is_err());
     }
 
+    #[test]
+    fn protocol_version_from_git_protocol() {
+        assert_eq!(
+            ProtocolVersion::from_git_protocol("version=2"),
+            ProtocolVersion::V2
+        );
+        assert_eq!(
+            ProtocolVersion::from_git_protocol("object-format=sha1:version=2"),
+            ProtocolVersion::V2
+        );
+        assert_eq!(
+            ProtocolVersion::from_git_protocol("version=1"),
+            ProtocolVersion::V0
+        );
+        assert_eq!(ProtocolVersion::from_git_protocol(""), ProtocolVersion::V0);
+    }
+
     #[test]
     fn side_bind_values_match_git_sideband_codes() {
         assert_eq!(SideBind::PackfileData.value(), 1);
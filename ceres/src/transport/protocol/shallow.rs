@@ -0,0 +1,510 @@
+//! Shallow clone negotiation for upload-pack.
+//!
+//! Clients limit the history they receive with `deepen <depth>`, `deepen-since <timestamp>`
+//! or `deepen-not <ref>`, and report the boundary of an existing shallow repository with
+//! `shallow <oid>`. The server answers with `shallow`/`unshallow` lines and the pack stops
+//! walking history at the resulting boundary. See the shallow sections of
+//! <https://git-scm.com/docs/pack-protocol> and <https://git-scm.com/docs/protocol-v2#_fetch>.
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use common::errors::{MegaError, ProtocolError, mega_to_protocol_error};
+use git_internal::{hash::ObjectHash, internal::object::commit::Commit};
+
+use crate::transport::{
+    pack::RepoHandler,
+    protocol::{import_refs::Refs, smart::add_pkt_line_string},
+};
+
+/// Depth sent by `git fetch --unshallow`.
+pub const INFINITE_DEPTH: usize = 0x7fff_ffff;
+
+/// Shallow related lines of an upload-pack request.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ShallowRequest {
+    /// Commits the client already has without their parents (`shallow <oid>`).
+    pub client_shallows: Vec<String>,
+    /// `deepen <depth>`.
+    pub depth: Option<usize>,
+    /// `deepen-since <timestamp>`.
+    pub deepen_since: Option<usize>,
+    /// `deepen-not <ref>`, may be repeated.
+    pub deepen_not: Vec<String>,
+    /// The depth counts from the client's current shallow boundary instead of the wants.
+    pub deepen_relative: bool,
+}
+
+impl ShallowRequest {
+    /// Records `line` if it is a shallow or deepen line, returning `Ok(false)` otherwise.
+    ///
+    /// `deepen-relative` is a capability in protocol v0 and an argument in protocol v2;
+    /// both end up in [`ShallowRequest::deepen_relative`].
+    pub fn parse_line(&mut self, line: &str) -> Result<bool, ProtocolError> {
+        let line = line.trim_end_matches('\n');
+        let invalid = || ProtocolError::InvalidInput(format!("invalid shallow request: {line}"));
+        if let Some(hash) = line.strip_prefix("shallow ") {
+            ObjectHash::from_str(hash).map_err(|_| invalid())?;
+            if !self.client_shallows.iter().any(|h| h == hash) {
+                self.client_shallows.push(hash.to_owned());
+            }
+        } else if let Some(depth) = line.strip_prefix("deepen ") {
+            let depth = depth.parse::<usize>().map_err(|_| invalid())?;
+            if depth == 0 {
+                return Err(invalid());
+            }
+            self.depth = Some(depth);
+        } else if let Some(timestamp) = line.strip_prefix("deepen-since ") {
+            self.deepen_since = Some(timestamp.parse().map_err(|_| invalid())?);
+        } else if let Some(name) = line.strip_prefix("deepen-not ") {
+            self.deepen_not.push(name.to_owned());
+        } else if line == "deepen-relative" {
+            self.deepen_relative = true;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Whether the client asked to change the depth of its history.
+    pub fn is_deepen(&self) -> bool {
+        self.depth.is_some() || self.deepen_since.is_some() || !self.deepen_not.is_empty()
+    }
+
+    /// Whether the request neither deepens nor comes from a shallow client.
+    pub fn is_empty(&self) -> bool {
+        !self.is_deepen() && self.client_shallows.is_empty()
+    }
+}
+
+/// Result of shallow negotiation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ShallowUpdate {
+    /// Commits that become shallow on the client, announced with `shallow <oid>`.
+    pub shallow: Vec<String>,
+    /// Client shallow commits whose parents are sent now, announced with `unshallow <oid>`.
+    pub unshallow: Vec<String>,
+    /// Commits whose parents must not be packed: the new boundary plus the client's own.
+    pub boundary: HashSet<String>,
+    /// Parents of unshallowed commits, which have to be packed on top of the wants.
+    pub extra_want: Vec<String>,
+}
+
+impl ShallowUpdate {
+    /// Appends the `shallow`/`unshallow` lines, without a terminating flush or delimiter.
+    pub fn write_pkt_lines(&self, buf: &mut BytesMut) {
+        for hash in &self.shallow {
+            add_pkt_line_string(buf, format!("shallow {hash}\n"));
+        }
+        for hash in &self.unshallow {
+            add_pkt_line_string(buf, format!("unshallow {hash}\n"));
+        }
+    }
+}
+
+/// The commit graph access needed by shallow negotiation.
+#[async_trait]
+pub trait CommitGraph: Send + Sync {
+    /// Loads the given commits, silently skipping unknown hashes.
+    async fn commits(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError>;
+
+    async fn refs(&self) -> Vec<Refs>;
+}
+
+#[async_trait]
+impl<T: RepoHandler + ?Sized> CommitGraph for T {
+    async fn commits(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError> {
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_commits_by_hashes(hashes).await
+    }
+
+    async fn refs(&self) -> Vec<Refs> {
+        self.refs_with_head_hash().await.1
+    }
+}
+
+/// Computes the shallow boundary for `want` and how it changes the client's one.
+///
+/// Mirrors `git upload-pack`: only a commit the client reported as shallow can be
+/// unshallowed, and the client's shallow commits always stay part of the boundary so
+/// the pack does not resend history the client already has below them.
+pub async fn negotiate_shallow<G: CommitGraph + ?Sized>(
+    graph: &G,
+    want: &[String],
+    request: &ShallowRequest,
+) -> Result<ShallowUpdate, ProtocolError> {
+    let (shallow, not_shallow) = if let Some(depth) = request.depth {
+        if request.deepen_since.is_some() || !request.deepen_not.is_empty() {
+            return Err(ProtocolError::InvalidInput(
+                "deepen and deepen-since (or deepen-not) cannot be used together".to_owned(),
+            ));
+        }
+        if depth >= INFINITE_DEPTH {
+            (
+                Vec::new(),
+                request.client_shallows.iter().cloned().collect(),
+            )
+        } else if request.deepen_relative {
+            let heads = graph
+                .commits(request.client_shallows.clone())
+                .await
+                .map_err(mega_to_protocol_error)?
+                .into_iter()
+                .map(|c| c.id.to_string())
+                .collect();
+            shallow_by_depth(graph, heads, depth + 1).await?
+        } else {
+            shallow_by_depth(graph, want.to_vec(), depth).await?
+        }
+    } else if request.is_deepen() {
+        let mut excluded_heads = Vec::new();
+        if !request.deepen_not.is_empty() {
+            let refs = graph.refs().await;
+            for name in &request.deepen_not {
+                let git_ref = resolve_ref(&refs, name).ok_or_else(|| {
+                    ProtocolError::InvalidInput(format!("deepen-not: unknown ref {name}"))
+                })?;
+                excluded_heads.push(git_ref.ref_hash.clone());
+            }
+        }
+        let excluded = ancestors(graph, excluded_heads).await?;
+        let (shallow, not_shallow) =
+            shallow_by_rev_list(graph, want.to_vec(), request.deepen_since, &excluded).await?;
+        if shallow.is_empty() && not_shallow.is_empty() {
+            return Err(ProtocolError::InvalidInput(
+                "no commits selected for shallow requests".to_owned(),
+            ));
+        }
+        (shallow, not_shallow)
+    } else {
+        (Vec::new(), HashSet::new())
+    };
+
+    let mut update = ShallowUpdate::default();
+    for hash in shallow {
+        if !request.client_shallows.contains(&hash) && !not_shallow.contains(&hash) {
+            update.boundary.insert(hash.clone());
+            update.shallow.push(hash);
+        }
+    }
+    for hash in &request.client_shallows {
+        if not_shallow.contains(hash) {
+            update.unshallow.push(hash.clone());
+        }
+        update.boundary.insert(hash.clone());
+    }
+    for commit in graph
+        .commits(update.unshallow.clone())
+        .await
+        .map_err(mega_to_protocol_error)?
+    {
+        for parent in commit.parent_commit_ids {
+            let parent = parent.to_string();
+            if !want.contains(&parent) && !update.extra_want.contains(&parent) {
+                update.extra_want.push(parent);
+            }
+        }
+    }
+    Ok(update)
+}
+
+/// Walks `depth` generations from `heads`. Commits of the last generation that have
+/// parents are shallow, the ones before are not shallow.
+async fn shallow_by_depth<G: CommitGraph + ?Sized>(
+    graph: &G,
+    heads: Vec<String>,
+    depth: usize,
+) -> Result<(Vec<String>, HashSet<String>), ProtocolError> {
+    let mut shallow = Vec::new();
+    let mut not_shallow = HashSet::new();
+    let mut visited: HashSet<String> = heads.iter().cloned().collect();
+    let mut generation = heads;
+    for level in 1..=depth {
+        if generation.is_empty() {
+            break;
+        }
+        let commits = graph
+            .commits(std::mem::take(&mut generation))
+            .await
+            .map_err(mega_to_protocol_error)?;
+        for commit in commits {
+            let hash = commit.id.to_string();
+            if level == depth {
+                if !commit.parent_commit_ids.is_empty() {
+                    shallow.push(hash);
+                }
+                continue;
+            }
+            not_shallow.insert(hash);
+            for parent in commit.parent_commit_ids {
+                let parent = parent.to_string();
+                if visited.insert(parent.clone()) {
+                    generation.push(parent);
+                }
+            }
+        }
+    }
+    shallow.sort();
+    Ok((shallow, not_shallow))
+}
+
+/// Selects the commits reachable from `want` that are not older than `since` and not in
+/// `excluded`. Selected commits with a parent outside the selection are shallow.
+async fn shallow_by_rev_list<G: CommitGraph + ?Sized>(
+    graph: &G,
+    want: Vec<String>,
+    since: Option<usize>,
+    excluded: &HashSet<String>,
+) -> Result<(Vec<String>, HashSet<String>), ProtocolError> {
+    let mut selected: HashMap<String, Vec<String>> = HashMap::new();
+    let mut visited: HashSet<String> = want.iter().cloned().collect();
+    let mut pending: Vec<String> = want.into_iter().filter(|h| !excluded.contains(h)).collect();
+    while !pending.is_empty() {
+        let commits = graph
+            .commits(std::mem::take(&mut pending))
+            .await
+            .map_err(mega_to_protocol_error)?;
+        for commit in commits {
+            if since.is_some_and(|since| commit.committer.timestamp < since) {
+                continue;
+            }
+            let parents: Vec<String> = commit
+                .parent_commit_ids
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+            for parent in &parents {
+                if !excluded.contains(parent) && visited.insert(parent.clone()) {
+                    pending.push(parent.clone());
+                }
+            }
+            selected.insert(commit.id.to_string(), parents);
+        }
+    }
+
+    let mut shallow: Vec<String> = selected
+        .iter()
+        .filter(|(_, parents)| parents.iter().any(|p| !selected.contains_key(p)))
+        .map(|(hash, _)| hash.clone())
+        .collect();
+    shallow.sort();
+    let not_shallow = selected
+        .into_keys()
+        .filter(|hash| shallow.binary_search(hash).is_err())
+        .collect();
+    Ok((shallow, not_shallow))
+}
+
+/// All commits reachable from `heads`, including the heads themselves.
+async fn ancestors<G: CommitGraph + ?Sized>(
+    graph: &G,
+    heads: Vec<String>,
+) -> Result<HashSet<String>, ProtocolError> {
+    let mut visited: HashSet<String> = heads.iter().cloned().collect();
+    let mut pending = heads;
+    while !pending.is_empty() {
+        let commits = graph
+            .commits(std::mem::take(&mut pending))
+            .await
+            .map_err(mega_to_protocol_error)?;
+        for commit in commits {
+            for parent in commit.parent_commit_ids {
+                let parent = parent.to_string();
+                if visited.insert(parent.clone()) {
+                    pending.push(parent);
+                }
+            }
+        }
+    }
+    Ok(visited)
+}
+
+/// Resolves a `deepen-not` argument the way git expands a short ref name.
+fn resolve_ref<'a>(refs: &'a [Refs], name: &str) -> Option<&'a Refs> {
+    [
+        name.to_owned(),
+        format!("refs/{name}"),
+        format!("refs/tags/{name}"),
+        format!("refs/heads/{name}"),
+        format!("refs/remotes/{name}"),
+        format!("refs/remotes/{name}/HEAD"),
+    ]
+    .iter()
+    .find_map(|candidate| refs.iter().find(|r| &r.ref_name == candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+    use common::errors::MegaError;
+    use git_internal::{hash::ObjectHash, internal::object::commit::Commit};
+
+    use super::{CommitGraph, INFINITE_DEPTH, ShallowRequest, negotiate_shallow};
+    use crate::transport::protocol::import_refs::Refs;
+
+    struct Graph {
+        commits: HashMap<String, Commit>,
+        refs: Vec<Refs>,
+    }
+
+    #[async_trait]
+    impl CommitGraph for Graph {
+        async fn commits(&self, hashes: Vec<String>) -> Result<Vec<Commit>, MegaError> {
+            Ok(hashes
+                .iter()
+                .filter_map(|h| self.commits.get(h).cloned())
+                .collect())
+        }
+
+        async fn refs(&self) -> Vec<Refs> {
+            self.refs.clone()
+        }
+    }
+
+    /// Linear history `c0 <- c1 <- ... <- c{n-1}`, committed at timestamps `0..n`.
+    fn linear_graph(n: usize) -> (Graph, Vec<String>) {
+        let mut commits = HashMap::new();
+        let mut hashes: Vec<String> = Vec::new();
+        for i in 0..n {
+            let parents = hashes
+                .last()
+                .map(|h| vec![h.parse::<ObjectHash>().unwrap()])
+                .unwrap_or_default();
+            let mut commit = Commit::from_tree_id(ObjectHash::default(), parents, &format!("c{i}"));
+            commit.committer.timestamp = i;
+            hashes.push(commit.id.to_string());
+            commits.insert(commit.id.to_string(), commit);
+        }
+        let refs = vec![Refs {
+            id: 0,
+            ref_name: "refs/tags/v1".to_owned(),
+            ref_hash: hashes[1].clone(),
+            default_branch: false,
+        }];
+        (Graph { commits, refs }, hashes)
+    }
+
+    #[test]
+    fn test_parse_shallow_lines() {
+        let mut request = ShallowRequest::default();
+        let hash = "8c9d0a7b3f0a5a0e9d4c1b2a3f4e5d6c7b8a9f0e";
+        assert!(request.parse_line(&format!("shallow {hash}\n")).unwrap());
+        assert!(request.parse_line("deepen 3\n").unwrap());
+        assert!(request.parse_line("deepen-not v1").unwrap());
+        assert!(request.parse_line("deepen-relative").unwrap());
+        assert!(!request.parse_line("have 1234").unwrap());
+        assert_eq!(request.client_shallows, vec![hash.to_owned()]);
+        assert_eq!(request.depth, Some(3));
+        assert_eq!(request.deepen_not, vec!["v1".to_owned()]);
+        assert!(request.deepen_relative);
+
+        assert!(request.parse_line("deepen 0").is_err());
+        assert!(request.parse_line("shallow not-a-hash").is_err());
+        assert!(request.parse_line("deepen-since yesterday").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_depth() {
+        let (graph, c) = linear_graph(5);
+        let want = vec![c[4].clone()];
+        let request = ShallowRequest {
+            depth: Some(1),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[4].clone()]);
+        assert!(update.unshallow.is_empty());
+        assert!(update.boundary.contains(&c[4]));
+
+        let request = ShallowRequest {
+            depth: Some(2),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[3].clone()]);
+
+        // history shorter than the depth has no shallow commits
+        let request = ShallowRequest {
+            depth: Some(10),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert!(update.shallow.is_empty() && update.boundary.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_deepen_unshallows_client_boundary() {
+        let (graph, c) = linear_graph(5);
+        let want = vec![c[4].clone()];
+        let request = ShallowRequest {
+            client_shallows: vec![c[4].clone()],
+            depth: Some(3),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[2].clone()]);
+        assert_eq!(update.unshallow, vec![c[4].clone()]);
+        assert_eq!(update.extra_want, vec![c[3].clone()]);
+        assert!(update.boundary.contains(&c[2]) && update.boundary.contains(&c[4]));
+
+        let request = ShallowRequest {
+            client_shallows: vec![c[3].clone()],
+            depth: Some(1),
+            deepen_relative: true,
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[2].clone()]);
+        assert_eq!(update.unshallow, vec![c[3].clone()]);
+
+        let request = ShallowRequest {
+            client_shallows: vec![c[3].clone()],
+            depth: Some(INFINITE_DEPTH),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert!(update.shallow.is_empty());
+        assert_eq!(update.unshallow, vec![c[3].clone()]);
+        assert_eq!(update.extra_want, vec![c[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_deepen_since_and_not() {
+        let (graph, c) = linear_graph(5);
+        let want = vec![c[4].clone()];
+        let request = ShallowRequest {
+            deepen_since: Some(3),
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[3].clone()]);
+
+        let request = ShallowRequest {
+            deepen_not: vec!["v1".to_owned()],
+            ..Default::default()
+        };
+        let update = negotiate_shallow(&graph, &want, &request).await.unwrap();
+        assert_eq!(update.shallow, vec![c[2].clone()]);
+
+        let request = ShallowRequest {
+            deepen_since: Some(10),
+            ..Default::default()
+        };
+        assert!(negotiate_shallow(&graph, &want, &request).await.is_err());
+
+        let request = ShallowRequest {
+            depth: Some(1),
+            deepen_not: vec!["v1".to_owned()],
+            ..Default::default()
+        };
+        assert!(negotiate_shallow(&graph, &want, &request).await.is_err());
+    }
+}
@@ -12,10 +12,12 @@ use tokio_stream::wrappers::ReceiverStream;
 use crate::{
     bus::TransportRuntime,
     transport::{
-        pack::PackByteStream,
+        pack::{PackByteStream, RepoHandler, filter::ObjectFilter},
         protocol::{
-            Capability, ServiceType, SideBind, SmartSession, TransportProtocol, ZERO_ID,
+            Capability, ProtocolVersion, ServiceType, SideBind, SmartSession, TransportProtocol,
+            ZERO_ID,
             import_refs::RefCommand,
+            shallow::{ShallowRequest, ShallowUpdate, negotiate_shallow},
         },
     },
 };
@@ -38,7 +40,18 @@ const RECEIVE_CAP_LIST: &str = "report-status report-status-v2 delete-refs quiet
 const COMMON_CAP_LIST: &str = "side-band-64k ofs-delta agent=mega/0.1.0";
 
 // All other capabilities are only recognized by the upload-pack (fetch from server) process.
-const UPLOAD_CAP_LIST: &str = "multi_ack_detailed no-done include-tag ";
+// allow-reachable-sha1-in-want lets promisor remotes of a partial clone fetch omitted objects.
+const UPLOAD_CAP_LIST: &str = "multi_ack_detailed no-done include-tag shallow deepen-since deepen-not deepen-relative filter allow-tip-sha1-in-want allow-reachable-sha1-in-want ";
+
+/// Wants, shallow lines and filter of a protocol v0 upload-pack negotiation in progress.
+#[derive(Clone, Debug, Default)]
+pub struct UploadPackRequest {
+    want: HashSet<String>,
+    shallow: ShallowRequest,
+    filter: Option<ObjectFilter>,
+    /// The shallow update is sent once, in the round that ends the want list.
+    shallow_sent: bool,
+}
 
 impl SmartSession {
     /// # Retrieves the information about Git references (refs) for the specified service type.
@@ -65,9 +78,15 @@ impl SmartSession {
     /// Tracing information is logged regarding the response packet line stream.
     ///
     /// Finally, the constructed packet line stream is returned.
+    ///
+    /// Upload-pack sessions that negotiated protocol v2 get the v2 capability advertisement
+    /// instead, see [`SmartSession::git_info_refs_v2`].
     pub async fn git_info_refs(&self, state: &TransportRuntime) -> Result<BytesMut, ProtocolError> {
         let repo_handler = self.repo_handler_with_commands(state, Vec::new()).await?;
         let service_type = self.service_type;
+        if self.protocol_version == ProtocolVersion::V2 && service_type == ServiceType::UploadPack {
+            return Ok(self.git_info_refs_v2());
+        }
 
         // The stream MUST include capability declarations behind a NUL on the first ref.
         let (head_hash, git_refs) = repo_handler.refs_with_head_hash().await;
@@ -92,18 +111,35 @@ impl SmartSession {
         Ok(pkt_line_stream)
     }
 
+    /// # Handles a protocol v0 upload-pack request.
+    ///
+    /// Returns the pack data stream and the protocol buffer that precedes it. A stateless
+    /// deepen request that stops after the want list only gets the shallow update and no
+    /// pack: the client sends its haves (or `done`) in the next request.
     pub async fn git_upload_pack(
         &mut self,
         state: &TransportRuntime,
         upload_request: &mut Bytes,
-    ) -> Result<(ReceiverStream<Vec<u8>>, BytesMut), ProtocolError> {
+    ) -> Result<(Option<ReceiverStream<Vec<u8>>>, BytesMut), ProtocolError> {
         let repo_handler = self.repo_handler_with_commands(state, Vec::new()).await?;
+        self.upload_pack_with(repo_handler.as_ref(), upload_request)
+            .await
+    }
 
-        let mut want: HashSet<String> = HashSet::new();
+    /// Answers one round of a protocol v0 upload-pack negotiation.
+    ///
+    /// Wants, shallow and deepen lines and the filter are kept on the session until the
+    /// pack is sent: over SSH the negotiation is stateful and later rounds only carry
+    /// haves or `done`.
+    pub(crate) async fn upload_pack_with(
+        &mut self,
+        repo_handler: &dyn RepoHandler,
+        upload_request: &mut Bytes,
+    ) -> Result<(Option<ReceiverStream<Vec<u8>>>, BytesMut), ProtocolError> {
         let mut have: HashSet<String> = HashSet::new();
+        let mut done = false;
         let mut last_common_commit = String::new();
 
-        let mut read_first_line = false;
         loop {
             let (bytes_take, pkt_line) = read_pkt_line(upload_request);
             // read 0000 to continue and read empty str to break
@@ -115,16 +151,33 @@ impl SmartSession {
                 }
             }
             let dst = pkt_line.to_vec();
+            let line = String::from_utf8_lossy(&dst);
+            if self.upload_request.shallow.parse_line(&line)? {
+                continue;
+            }
+            if let Some(spec) = line.strip_prefix("filter ") {
+                self.upload_request.filter = Some(spec.trim_end().parse()?);
+                continue;
+            }
             let commands = &dst[0..4];
 
             match commands {
                 b"want" => {
-                    want.insert(String::from_utf8(dst[5..45].to_vec()).unwrap());
+                    // Capabilities follow the first want line of the negotiation.
+                    if self.upload_request.want.is_empty() && dst.len() > 46 {
+                        self.parse_capabilities(core::str::from_utf8(&dst[46..]).unwrap());
+                    }
+                    self.upload_request
+                        .want
+                        .insert(String::from_utf8(dst[5..45].to_vec()).unwrap());
                 }
                 b"have" => {
                     have.insert(String::from_utf8(dst[5..45].to_vec()).unwrap());
                 }
-                b"done" => break,
+                b"done" => {
+                    done = true;
+                    break;
+                }
                 other => {
                     tracing::error!(
                         "unsupported command: {:?}",
@@ -133,27 +186,51 @@ impl SmartSession {
                     continue;
                 }
             };
-            if !read_first_line {
-                self.parse_capabilities(core::str::from_utf8(&dst[46..]).unwrap());
-                read_first_line = true;
-            }
         }
 
+        let mut shallow_request = self.upload_request.shallow.clone();
+        let filter = self.upload_request.filter;
+        shallow_request.deepen_relative = self.capabilities.contains(&Capability::DeepenRelative);
         tracing::info!(
-            "want commands: {:?}\n have commands: {:?}\n caps:{:?}",
-            want,
+            "want commands: {:?}\n have commands: {:?}\n shallow: {:?}\n filter: {:?}\n caps:{:?}",
+            self.upload_request.want,
             have,
+            shallow_request,
+            filter,
             self.capabilities
         );
 
         let pack_data;
         let mut protocol_buf = BytesMut::new();
 
-        let want: Vec<String> = want.into_iter().collect();
+        let want: Vec<String> = self.upload_request.want.iter().cloned().collect();
         let have: Vec<String> = have.into_iter().collect();
 
+        // The shallow update precedes the ACK/NAK lines and is only sent for deepen requests.
+        let shallow_update = if shallow_request.is_empty() {
+            ShallowUpdate::default()
+        } else {
+            negotiate_shallow(repo_handler, &want, &shallow_request).await?
+        };
+        if shallow_request.is_deepen() && !self.upload_request.shallow_sent {
+            shallow_update.write_pkt_lines(&mut protocol_buf);
+            protocol_buf.put(&PKT_LINE_END_MARKER[..]);
+            self.upload_request.shallow_sent = true;
+        }
+        if shallow_request.is_deepen() && have.is_empty() && !done {
+            return Ok((None, protocol_buf));
+        }
+        // The pack ends the negotiation.
+        self.upload_request = UploadPackRequest::default();
+        let shallow = shallow_update.boundary;
+        let mut pack_want = want.clone();
+        pack_want.extend(shallow_update.extra_want);
+
         if have.is_empty() {
-            pack_data = repo_handler.full_pack(want).await.unwrap();
+            pack_data = repo_handler
+                .full_pack(pack_want, &shallow, filter)
+                .await
+                .unwrap();
             add_pkt_line_string(&mut protocol_buf, String::from("NAK\n"));
         } else {
             if self.capabilities.contains(&Capability::MultiAckDetailed) {
@@ -170,7 +247,7 @@ impl SmartSession {
                     }
                 }
                 pack_data = repo_handler
-                    .incremental_pack(want.clone(), have)
+                    .incremental_pack(pack_want, have, &shallow, filter)
                     .await
                     .unwrap();
 
@@ -178,7 +255,7 @@ impl SmartSession {
                     //send NAK if missing common commit
                     add_pkt_line_string(&mut protocol_buf, String::from("NAK\n"));
                     // need to handle rebase option, still need pack data when has no common commit
-                    return Ok((pack_data, protocol_buf));
+                    return Ok((Some(pack_data), protocol_buf));
                 }
 
                 for hash in want {
@@ -196,7 +273,7 @@ impl SmartSession {
             }
             add_pkt_line_string(&mut protocol_buf, format!("ACK {last_common_commit} \n"));
         }
-        Ok((pack_data, protocol_buf))
+        Ok((Some(pack_data), protocol_buf))
     }
 
     pub fn parse_receive_pack_commands(&mut self, mut protocol_bytes: Bytes) -> Vec<RefCommand> {
@@ -259,6 +336,7 @@ impl SmartSession {
         let mut default_exist = repo_handler.check_default_branch().await;
 
         let mut unpack_failed = false;
+        let mut update_rejected = false;
 
         // 2. Tags: persist immediately. Branches: only unpack / default-branch flags here;
         //    mono and import both persist branch refs inside `finalize_receive_pack`.
@@ -275,7 +353,10 @@ impl SmartSession {
                 // c.Also, some references can be updated while others can be rejected.
                 match unpack_result {
                     Ok(_) => {
-                        if !default_exist {
+                        if let Err(e) = repo_handler.check_ref_update(command).await {
+                            command.failed(e.to_string());
+                            update_rejected = true;
+                        } else if !default_exist {
                             command.default_branch = true;
                             default_exist = true;
                         }
@@ -294,7 +375,7 @@ impl SmartSession {
 
         let mut finalize_ms: Option<u128> = None;
         let mut bind_ms: Option<u128> = None;
-        if !unpack_failed {
+        if !unpack_failed && !update_rejected {
             let t_finalize = Instant::now();
             if let Err(e) = repo_handler.finalize_receive_pack().await {
                 let msg = e.to_string();
@@ -549,18 +630,23 @@ pub fn read_pkt_line(bytes: &mut Bytes) -> (usize, Bytes) {
 
 #[cfg(test)]
 pub mod test {
-    use std::{process::Command, time::Duration};
+    use std::{collections::HashSet, process::Command, time::Duration};
 
-    use bytes::{Bytes, BytesMut};
+    use bytes::{BufMut, Bytes, BytesMut};
     use callisto::sea_orm_active_enums::RefTypeEnum;
     use futures::future;
     use tempfile::TempDir;
     use tokio::{task, time::sleep};
 
-    use crate::transport::protocol::{
-        Capability, ServiceType, SmartSession, TransportProtocol,
-        import_refs::{CommandType, RefCommand},
-        smart::{add_pkt_line_string, read_pkt_line, read_until_white_space},
+    use crate::transport::{
+        pack::test_handler::{MemoryRepo, PackCall},
+        protocol::{
+            Capability, ServiceType, SmartSession, TransportProtocol,
+            import_refs::{CommandType, RefCommand},
+            smart::{
+                PKT_LINE_END_MARKER, add_pkt_line_string, read_pkt_line, read_until_white_space,
+            },
+        },
     };
 
     #[test]
@@ -737,4 +823,57 @@ pub mod test {
 
         Ok(())
     }
+
+    fn want_line(hash: &str, caps: &str) -> String {
+        if caps.is_empty() {
+            format!("want {hash}\n")
+        } else {
+            format!("want {hash} {caps}\n")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shallow_negotiation_spans_stateful_rounds() {
+        let (repo, hashes) = MemoryRepo::linear(4);
+        let tip = hashes[3].clone();
+        let mut session = SmartSession::new(
+            std::path::PathBuf::from("/project"),
+            ServiceType::UploadPack,
+            TransportProtocol::Ssh,
+        );
+
+        // Round 1: wants, capabilities and depth, then a flush; only the shallow update comes back.
+        let mut buf = BytesMut::new();
+        add_pkt_line_string(
+            &mut buf,
+            want_line(&tip, "multi_ack_detailed no-done shallow"),
+        );
+        add_pkt_line_string(&mut buf, "deepen 1\n".to_owned());
+        buf.put(&PKT_LINE_END_MARKER[..]);
+        let mut request = buf.freeze();
+        let (pack, protocol_buf) = session.upload_pack_with(&repo, &mut request).await.unwrap();
+        assert!(pack.is_none());
+        assert!(
+            String::from_utf8_lossy(&protocol_buf).contains(&format!("shallow {tip}")),
+            "{protocol_buf:?}"
+        );
+        assert!(repo.packs().is_empty());
+
+        // Round 2: the client only says `done`; the pack still honours round 1.
+        let mut buf = BytesMut::new();
+        add_pkt_line_string(&mut buf, "done\n".to_owned());
+        let mut request = buf.freeze();
+        let (pack, protocol_buf) = session.upload_pack_with(&repo, &mut request).await.unwrap();
+        assert!(pack.is_some());
+        assert_eq!(&protocol_buf[..], b"0008NAK\n");
+        assert_eq!(
+            repo.packs(),
+            vec![PackCall {
+                want: HashSet::from([tip.clone()]),
+                have: HashSet::new(),
+                shallow: HashSet::from([tip]),
+            }]
+        );
+        assert!(session.capabilities.contains(&Capability::MultiAckDetailed));
+    }
 }
@@ -0,0 +1,587 @@
+//! Git wire protocol version 2 for upload-pack.
+//!
+//! See <https://git-scm.com/docs/protocol-v2>. Unlike v0, the server only advertises its
+//! capabilities up front; refs are listed on demand through `ls-refs`, which lets clients
+//! restrict the advertisement with `ref-prefix` instead of paying for every CL ref.
+//! Receive-pack has no v2 definition and keeps using the v0 code path.
+
+use std::sync::Arc;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use common::errors::{ProtocolError, mega_to_protocol_error};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    bus::TransportRuntime,
+    transport::{
+        pack::{RepoHandler, filter::ObjectFilter},
+        protocol::{
+            Capability, SmartSession, ZERO_ID,
+            shallow::{ShallowRequest, ShallowUpdate, negotiate_shallow},
+            smart::{PKT_LINE_END_MARKER, add_pkt_line_string},
+        },
+    },
+};
+
+/// Delimiter packet separating the capability list from command arguments (and response sections).
+pub const PKT_LINE_DELIM_MARKER: &[u8; 4] = b"0001";
+
+/// Commands and capabilities advertised to v2 clients, one per pkt-line.
+const V2_CAP_LIST: [&str; 5] = [
+    "version 2",
+    "agent=mega/0.1.0",
+    "ls-refs",
+    "fetch=shallow filter",
+    "object-info",
+];
+
+/// A single pkt-line as seen by protocol v2, which adds special packets besides flush.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PktLine {
+    /// `0000`: end of a message.
+    Flush,
+    /// `0001`: separates sections of a message.
+    Delim,
+    /// `0002`: end of a response in stateless connections.
+    ResponseEnd,
+    Data(Bytes),
+}
+
+/// A command request sent by a v2 client:
+/// `command=<key>`, capability lines, an optional delimiter and command arguments, then flush.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CommandRequest {
+    pub command: String,
+    pub capabilities: Vec<String>,
+    pub args: Vec<String>,
+}
+
+impl SmartSession {
+    /// # Builds the protocol v2 capability advertisement.
+    ///
+    /// The advertisement is the same for HTTP and SSH: v2 omits the `# service=` preamble
+    /// and does not list any refs, clients issue `ls-refs` to discover them.
+    pub fn git_info_refs_v2(&self) -> BytesMut {
+        let mut pkt_line_stream = BytesMut::new();
+        for cap in V2_CAP_LIST {
+            add_pkt_line_string(&mut pkt_line_stream, format!("{cap}\n"));
+        }
+        pkt_line_stream.put(&PKT_LINE_END_MARKER[..]);
+        pkt_line_stream
+    }
+
+    /// # Executes a single protocol v2 command request.
+    ///
+    /// Returns the pack data stream (only produced by `fetch` when a packfile section follows)
+    /// and the protocol buffer that must be sent before it. When pack data is returned the
+    /// caller is responsible for framing it in sideband 1 and terminating the response with a
+    /// flush packet; otherwise the protocol buffer is already a complete response.
+    ///
+    /// An empty request (a lone flush packet) yields `None`, which tells stateful transports
+    /// such as SSH that the client is done.
+    pub async fn git_command_v2(
+        &mut self,
+        state: &TransportRuntime,
+        request: &mut Bytes,
+    ) -> Result<Option<(Option<ReceiverStream<Vec<u8>>>, BytesMut)>, ProtocolError> {
+        let Some(command) = parse_command_request(request)? else {
+            return Ok(None);
+        };
+        tracing::info!(
+            "protocol v2 command: {}, capabilities: {:?}, args: {:?}",
+            command.command,
+            command.capabilities,
+            command.args
+        );
+        let repo_handler = self.repo_handler_with_commands(state, Vec::new()).await?;
+        self.run_command_v2(repo_handler, &command).await.map(Some)
+    }
+
+    /// Runs a parsed v2 command against `repo_handler`, see [`Self::git_command_v2`].
+    async fn run_command_v2(
+        &mut self,
+        repo_handler: Arc<dyn RepoHandler>,
+        command: &CommandRequest,
+    ) -> Result<(Option<ReceiverStream<Vec<u8>>>, BytesMut), ProtocolError> {
+        let response = match command.command.as_str() {
+            "ls-refs" => (None, ls_refs(repo_handler, &command.args).await),
+            "fetch" => self.fetch(repo_handler, &command.args).await?,
+            "object-info" => (None, object_info(repo_handler, &command.args).await?),
+            other => {
+                return Err(ProtocolError::InvalidInput(format!(
+                    "unknown protocol v2 command: {other}"
+                )));
+            }
+        };
+        Ok(response)
+    }
+
+    /// Handles the v2 `fetch` command.
+    ///
+    /// Without `done`, an `acknowledgments` section is sent first; the packfile only follows
+    /// once a common commit has been found (`ready`). A `shallow-info` section precedes the
+    /// packfile for shallow clients and deepen requests. The packfile section is always
+    /// multiplexed over sideband 64k, so the capability is enabled for the session here.
+    async fn fetch(
+        &mut self,
+        repo_handler: Arc<dyn RepoHandler>,
+        args: &[String],
+    ) -> Result<(Option<ReceiverStream<Vec<u8>>>, BytesMut), ProtocolError> {
+        let mut want: Vec<String> = Vec::new();
+        let mut have: Vec<String> = Vec::new();
+        let mut shallow_request = ShallowRequest::default();
+        let mut filter: Option<ObjectFilter> = None;
+        let mut done = false;
+        for arg in args {
+            if shallow_request.parse_line(arg)? {
+                continue;
+            }
+            if let Some(spec) = arg.strip_prefix("filter ") {
+                filter = Some(spec.parse()?);
+                continue;
+            }
+            if let Some(hash) = arg.strip_prefix("want ") {
+                if !want.iter().any(|w| w == hash) {
+                    want.push(hash.to_owned());
+                }
+            } else if let Some(hash) = arg.strip_prefix("have ") {
+                if !have.iter().any(|h| h == hash) {
+                    have.push(hash.to_owned());
+                }
+            } else if arg == "done" {
+                done = true;
+            } else {
+                // thin-pack, no-progress, include-tag and ofs-delta only relax what the
+                // server may send, so ignoring them still produces a valid pack.
+                tracing::debug!("ignored fetch argument: {}", arg);
+            }
+        }
+        if want.is_empty() {
+            return Err(ProtocolError::InvalidInput(
+                "fetch requires at least one want".to_owned(),
+            ));
+        }
+
+        let mut common = Vec::new();
+        for hash in have {
+            if repo_handler.check_commit_exist(&hash).await {
+                common.push(hash);
+            }
+        }
+
+        let mut protocol_buf = BytesMut::new();
+        if !done {
+            add_pkt_line_string(&mut protocol_buf, "acknowledgments\n".to_owned());
+            if common.is_empty() {
+                add_pkt_line_string(&mut protocol_buf, "NAK\n".to_owned());
+                protocol_buf.put(&PKT_LINE_END_MARKER[..]);
+                return Ok((None, protocol_buf));
+            }
+            for hash in &common {
+                add_pkt_line_string(&mut protocol_buf, format!("ACK {hash}\n"));
+            }
+            add_pkt_line_string(&mut protocol_buf, "ready\n".to_owned());
+            protocol_buf.put(&PKT_LINE_DELIM_MARKER[..]);
+        }
+
+        let shallow_update = if shallow_request.is_empty() {
+            ShallowUpdate::default()
+        } else {
+            add_pkt_line_string(&mut protocol_buf, "shallow-info\n".to_owned());
+            let update = negotiate_shallow(repo_handler.as_ref(), &want, &shallow_request).await?;
+            update.write_pkt_lines(&mut protocol_buf);
+            protocol_buf.put(&PKT_LINE_DELIM_MARKER[..]);
+            update
+        };
+        want.extend(shallow_update.extra_want);
+
+        add_pkt_line_string(&mut protocol_buf, "packfile\n".to_owned());
+        self.capabilities.insert(Capability::SideBand64k);
+        let pack_data = if common.is_empty() {
+            repo_handler
+                .full_pack(want, &shallow_update.boundary, filter)
+                .await
+        } else {
+            repo_handler
+                .incremental_pack(want, common, &shallow_update.boundary, filter)
+                .await
+        }
+        .map_err(|e| ProtocolError::IO(std::io::Error::other(e)))?;
+        Ok((Some(pack_data), protocol_buf))
+    }
+}
+
+/// Handles the v2 `ls-refs` command: `symrefs`, `peel` and `ref-prefix <prefix>` arguments.
+///
+/// `HEAD` is listed first when the default branch exists. Tags are not peeled since the
+/// v0 advertisement does not peel them either.
+async fn ls_refs(repo_handler: Arc<dyn RepoHandler>, args: &[String]) -> BytesMut {
+    let symrefs = args.iter().any(|a| a == "symrefs");
+    let prefixes: Vec<&str> = args
+        .iter()
+        .filter_map(|a| a.strip_prefix("ref-prefix "))
+        .collect();
+    let matches_prefix =
+        |name: &str| prefixes.is_empty() || prefixes.iter().any(|p| name.starts_with(p));
+
+    let (head_hash, git_refs) = repo_handler.refs_with_head_hash().await;
+    let mut pkt_line_stream = BytesMut::new();
+    if head_hash != ZERO_ID && matches_prefix("HEAD") {
+        let mut line = format!("{head_hash} HEAD");
+        if symrefs && let Some(default) = git_refs.iter().find(|r| r.default_branch) {
+            line.push_str(&format!(" symref-target:{}", default.ref_name));
+        }
+        line.push('\n');
+        add_pkt_line_string(&mut pkt_line_stream, line);
+    }
+    for git_ref in git_refs {
+        if matches_prefix(&git_ref.ref_name) {
+            add_pkt_line_string(
+                &mut pkt_line_stream,
+                format!("{} {}\n", git_ref.ref_hash, git_ref.ref_name),
+            );
+        }
+    }
+    pkt_line_stream.put(&PKT_LINE_END_MARKER[..]);
+    pkt_line_stream
+}
+
+/// Handles the v2 `object-info` command. Only the `size` attribute is defined by git;
+/// objects that cannot be found are reported with an empty size.
+async fn object_info(
+    repo_handler: Arc<dyn RepoHandler>,
+    args: &[String],
+) -> Result<BytesMut, ProtocolError> {
+    let want_size = args.iter().any(|a| a == "size");
+    let oids: Vec<String> = args
+        .iter()
+        .filter_map(|a| a.strip_prefix("oid "))
+        .map(str::to_owned)
+        .collect();
+
+    let mut pkt_line_stream = BytesMut::new();
+    if want_size {
+        add_pkt_line_string(&mut pkt_line_stream, "size\n".to_owned());
+        let sizes = repo_handler
+            .get_object_sizes(oids.clone())
+            .await
+            .map_err(mega_to_protocol_error)?;
+        for oid in oids {
+            let line = match sizes.get(&oid) {
+                Some(size) => format!("{oid} {size}\n"),
+                None => format!("{oid} \n"),
+            };
+            add_pkt_line_string(&mut pkt_line_stream, line);
+        }
+    }
+    pkt_line_stream.put(&PKT_LINE_END_MARKER[..]);
+    Ok(pkt_line_stream)
+}
+
+/// Read one pkt-line, recognising the v2 special packets (`0000`, `0001`, `0002`).
+///
+/// Returns `Ok(None)` once the buffer is exhausted.
+pub fn read_pkt_line_v2(bytes: &mut Bytes) -> Result<Option<PktLine>, ProtocolError> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    if bytes.len() < 4 {
+        return Err(ProtocolError::InvalidInput(
+            "truncated pkt-line length".to_owned(),
+        ));
+    }
+    let pkt_length = bytes.copy_to_bytes(4);
+    let pkt_length = core::str::from_utf8(&pkt_length)
+        .ok()
+        .and_then(|s| usize::from_str_radix(s, 16).ok())
+        .ok_or_else(|| {
+            ProtocolError::InvalidInput(format!("invalid pkt-line length: {pkt_length:?}"))
+        })?;
+    match pkt_length {
+        0 => Ok(Some(PktLine::Flush)),
+        1 => Ok(Some(PktLine::Delim)),
+        2 => Ok(Some(PktLine::ResponseEnd)),
+        3 => Err(ProtocolError::InvalidInput(
+            "invalid pkt-line length: 3".to_owned(),
+        )),
+        len if len - 4 > bytes.len() => Err(ProtocolError::InvalidInput(
+            "truncated pkt-line payload".to_owned(),
+        )),
+        len => Ok(Some(PktLine::Data(bytes.copy_to_bytes(len - 4)))),
+    }
+}
+
+/// Parse a v2 command request, consuming it from `bytes` up to and including its flush packet.
+///
+/// Returns `Ok(None)` for an empty request, i.e. a lone flush packet or no data at all.
+pub fn parse_command_request(bytes: &mut Bytes) -> Result<Option<CommandRequest>, ProtocolError> {
+    let mut request = CommandRequest::default();
+    let mut in_args = false;
+    loop {
+        let line = match read_pkt_line_v2(bytes)? {
+            None | Some(PktLine::Flush) => break,
+            Some(PktLine::Delim) => {
+                in_args = true;
+                continue;
+            }
+            Some(PktLine::ResponseEnd) => continue,
+            Some(PktLine::Data(data)) => String::from_utf8(data.to_vec())
+                .map_err(|e| ProtocolError::InvalidInput(e.to_string()))?,
+        };
+        let line = line.trim_end_matches('\n').to_owned();
+        if in_args {
+            request.args.push(line);
+        } else if let Some(command) = line.strip_prefix("command=") {
+            request.command = command.to_owned();
+        } else {
+            request.capabilities.push(line);
+        }
+    }
+    if request.command.is_empty() {
+        if request.capabilities.is_empty() && request.args.is_empty() {
+            return Ok(None);
+        }
+        return Err(ProtocolError::InvalidInput(
+            "protocol v2 request without command".to_owned(),
+        ));
+    }
+    Ok(Some(request))
+}
+
+/// Split the next complete command request (terminated by a flush packet) off `buf`.
+///
+/// Stateful transports receive requests in arbitrary chunks; this returns `Ok(None)` until a
+/// whole request has been buffered, leaving `buf` untouched. An invalid pkt-line length is
+/// an error since the rest of the stream cannot be framed.
+pub fn split_command_request(buf: &mut BytesMut) -> Result<Option<Bytes>, ProtocolError> {
+    let mut offset = 0;
+    while buf.len() >= offset + 4 {
+        let pkt_length = &buf[offset..offset + 4];
+        let pkt_length = core::str::from_utf8(pkt_length)
+            .ok()
+            .and_then(|s| usize::from_str_radix(s, 16).ok())
+            .ok_or_else(|| {
+                ProtocolError::InvalidInput(format!("invalid pkt-line length: {pkt_length:?}"))
+            })?;
+        match pkt_length {
+            0 => return Ok(Some(buf.split_to(offset + 4).freeze())),
+            1 | 2 => offset += 4,
+            3 => {
+                return Err(ProtocolError::InvalidInput(
+                    "invalid pkt-line length: 3".to_owned(),
+                ));
+            }
+            len => offset += len,
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bytes::{BufMut, Bytes, BytesMut};
+
+    use super::{
+        CommandRequest, PKT_LINE_DELIM_MARKER, PktLine, parse_command_request, read_pkt_line_v2,
+        split_command_request,
+    };
+    use crate::transport::{
+        pack::test_handler::{MemoryRepo, PackCall},
+        protocol::{
+            ServiceType, SmartSession, TransportProtocol,
+            smart::{PKT_LINE_END_MARKER, add_pkt_line_string},
+        },
+    };
+
+    const LS_REFS_REQUEST: &[u8] = b"0014command=ls-refs\n0015agent=git/2.43.0\n00010009peel\n000csymrefs\n001bref-prefix refs/heads/\n0000";
+
+    #[test]
+    fn test_read_pkt_line_v2_special_packets() {
+        let mut bytes = Bytes::from_static(b"000100020000000ahello\n");
+        assert_eq!(read_pkt_line_v2(&mut bytes).unwrap(), Some(PktLine::Delim));
+        assert_eq!(
+            read_pkt_line_v2(&mut bytes).unwrap(),
+            Some(PktLine::ResponseEnd)
+        );
+        assert_eq!(read_pkt_line_v2(&mut bytes).unwrap(), Some(PktLine::Flush));
+        assert_eq!(
+            read_pkt_line_v2(&mut bytes).unwrap(),
+            Some(PktLine::Data(Bytes::from_static(b"hello\n")))
+        );
+        assert_eq!(read_pkt_line_v2(&mut bytes).unwrap(), None);
+
+        assert!(read_pkt_line_v2(&mut Bytes::from_static(b"0003")).is_err());
+        assert!(read_pkt_line_v2(&mut Bytes::from_static(b"00ffshort")).is_err());
+    }
+
+    #[test]
+    fn test_parse_command_request() {
+        let mut bytes = Bytes::from_static(LS_REFS_REQUEST);
+        let request = parse_command_request(&mut bytes).unwrap().unwrap();
+        assert_eq!(
+            request,
+            CommandRequest {
+                command: String::from("ls-refs"),
+                capabilities: vec![String::from("agent=git/2.43.0")],
+                args: vec![
+                    String::from("peel"),
+                    String::from("symrefs"),
+                    String::from("ref-prefix refs/heads/"),
+                ],
+            }
+        );
+        assert!(bytes.is_empty());
+
+        let mut empty = Bytes::from_static(b"0000");
+        assert!(parse_command_request(&mut empty).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_split_command_request() {
+        let mut buf = BytesMut::from(&LS_REFS_REQUEST[..20]);
+        assert!(split_command_request(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&LS_REFS_REQUEST[20..]);
+        buf.extend_from_slice(b"0000");
+        let request = split_command_request(&mut buf).unwrap().unwrap();
+        assert_eq!(&request[..], LS_REFS_REQUEST);
+        assert_eq!(&buf[..], b"0000");
+        assert_eq!(
+            &split_command_request(&mut buf).unwrap().unwrap()[..],
+            b"0000"
+        );
+        assert!(buf.is_empty());
+
+        assert!(
+            split_command_request(&mut BytesMut::from(&b"0014command=ls-refs\n0003"[..])).is_err()
+        );
+        assert!(split_command_request(&mut BytesMut::from(&b"zzzz"[..])).is_err());
+    }
+
+    #[test]
+    fn test_git_info_refs_v2() {
+        let session = SmartSession::new(
+            std::path::PathBuf::new(),
+            ServiceType::UploadPack,
+            TransportProtocol::Http,
+        );
+        assert_eq!(
+            &session.git_info_refs_v2()[..],
+            b"000eversion 2\n0015agent=mega/0.1.0\n000cls-refs\n0019fetch=shallow filter\n0010object-info\n0000"
+        );
+    }
+
+    /// Builds a v2 command request with the given arguments.
+    fn command_request(command: &str, args: &[String]) -> Bytes {
+        let mut buf = BytesMut::new();
+        add_pkt_line_string(&mut buf, format!("command={command}\n"));
+        add_pkt_line_string(&mut buf, "agent=git/2.43.0\n".to_owned());
+        buf.put(&PKT_LINE_DELIM_MARKER[..]);
+        for arg in args {
+            add_pkt_line_string(&mut buf, format!("{arg}\n"));
+        }
+        buf.put(&PKT_LINE_END_MARKER[..]);
+        buf.freeze()
+    }
+
+    async fn run(session: &mut SmartSession, repo: &Arc<MemoryRepo>, request: Bytes) -> BytesMut {
+        let mut buf = BytesMut::from(&request[..]);
+        let mut request = split_command_request(&mut buf).unwrap().unwrap();
+        let command = parse_command_request(&mut request).unwrap().unwrap();
+        let (_, response) = session
+            .run_command_v2(repo.clone(), &command)
+            .await
+            .unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_ls_refs_and_fetch_round_trip() {
+        let (repo, hashes) = MemoryRepo::linear(3);
+        let repo = Arc::new(repo);
+        let tip = &hashes[2];
+        let mut session = SmartSession::new(
+            std::path::PathBuf::new(),
+            ServiceType::UploadPack,
+            TransportProtocol::Ssh,
+        );
+
+        let response = run(&mut session, &repo, Bytes::from_static(LS_REFS_REQUEST)).await;
+        let mut expected = BytesMut::new();
+        add_pkt_line_string(&mut expected, format!("{tip} refs/heads/main\n"));
+        expected.put(&PKT_LINE_END_MARKER[..]);
+        assert_eq!(response, expected);
+
+        // Negotiation round: the server acknowledges the common commit and is ready.
+        let args = vec![format!("want {tip}"), format!("have {}", hashes[0])];
+        let response = run(&mut session, &repo, command_request("fetch", &args)).await;
+        let mut expected = BytesMut::new();
+        add_pkt_line_string(&mut expected, "acknowledgments\n".to_owned());
+        add_pkt_line_string(&mut expected, format!("ACK {}\n", hashes[0]));
+        add_pkt_line_string(&mut expected, "ready\n".to_owned());
+        expected.put(&PKT_LINE_DELIM_MARKER[..]);
+        add_pkt_line_string(&mut expected, "packfile\n".to_owned());
+        assert_eq!(response, expected);
+        assert_eq!(
+            repo.packs(),
+            vec![PackCall {
+                want: HashSet::from([tip.clone()]),
+                have: HashSet::from([hashes[0].clone()]),
+                shallow: HashSet::new(),
+            }]
+        );
+
+        // A clone has nothing in common and says done: the packfile follows right away.
+        let args = vec![format!("want {tip}"), "done".to_owned()];
+        let response = run(&mut session, &repo, command_request("fetch", &args)).await;
+        let mut expected = BytesMut::new();
+        add_pkt_line_string(&mut expected, "packfile\n".to_owned());
+        assert_eq!(response, expected);
+        assert_eq!(repo.packs()[1].want, HashSet::from([tip.clone()]));
+        assert!(repo.packs()[1].have.is_empty());
+    }
+
+    /// Requires a mono server listening on `localhost:8000` with `/project` initialized.
+    #[test]
+    #[ignore]
+    fn test_git_client_protocol_v2() {
+        use std::process::Command;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "protocol.version=2",
+                "ls-remote",
+                "http://localhost:8000/project.git",
+                "refs/heads/*",
+            ])
+            .env("GIT_TRACE_PACKET", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let trace = String::from_utf8_lossy(&output.stderr);
+        assert!(trace.contains("version 2"));
+        assert!(trace.contains("ref-prefix refs/heads/"));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("refs/heads/main"));
+
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "protocol.version=2",
+                "clone",
+                "http://localhost:8000/project.git",
+            ])
+            .current_dir(tmp.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let status = Command::new("git")
+            .args(["-c", "protocol.version=2", "fetch", "origin"])
+            .current_dir(tmp.path().join("project"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
@@ -1,4 +1,8 @@
-use api_model::buck2::api::TaskBuildRequest;
+use api_model::buck2::{
+    api::TaskBuildRequest,
+    types::{BuildStatus, BuildTestResultsResponse, TargetQuarantine, TargetStatusResponse},
+};
+use reqwest::StatusCode;
 use serde::Deserialize;
 
 /// Response from Orion task handler containing the assigned task ID.
@@ -49,4 +53,64 @@ impl OrionTaskHttpClient {
             Err(anyhow::anyhow!("Failed to trigger build: {}", res.status()))
         }
     }
+
+    /// Returns `None` when the task has no build events yet.
+    pub async fn latest_build_result(&self, task_id: &str) -> anyhow::Result<Option<BuildStatus>> {
+        let url = format!("{}/v2/latest_build_result/{}", self.base_url, task_id);
+        let res = self.client.get(&url).send().await?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(res.json().await?)),
+            status => Err(anyhow::anyhow!(
+                "Failed to fetch latest build result for task {task_id}: {status}"
+            )),
+        }
+    }
+
+    /// Returns `None` when the task has not started building yet.
+    pub async fn latest_build_tests(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Option<BuildTestResultsResponse>> {
+        let url = format!("{}/v2/latest_build_tests/{}", self.base_url, task_id);
+        let res = self.client.get(&url).send().await?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(res.json().await?)),
+            status => Err(anyhow::anyhow!(
+                "Failed to fetch test results for task {task_id}: {status}"
+            )),
+        }
+    }
+
+    /// Returns an empty list when no target has reported a status yet.
+    pub async fn target_statuses(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<TargetStatusResponse>> {
+        let url = format!("{}/v2/all-target-status/{}", self.base_url, task_id);
+        let res = self.client.get(&url).send().await?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(vec![]),
+            status if status.is_success() => Ok(res.json().await?),
+            status => Err(anyhow::anyhow!(
+                "Failed to fetch target status for task {task_id}: {status}"
+            )),
+        }
+    }
+
+    /// Quarantined targets that apply to builds of `path`.
+    pub async fn quarantined_targets(&self, path: &str) -> anyhow::Result<Vec<TargetQuarantine>> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/v2/quarantine", self.base_url),
+            &[("path", path)],
+        )?;
+        let res = self.client.get(url).send().await?;
+        match res.status() {
+            status if status.is_success() => Ok(res.json().await?),
+            status => Err(anyhow::anyhow!(
+                "Failed to fetch quarantined targets for {path}: {status}"
+            )),
+        }
+    }
 }
@@ -7,7 +7,10 @@
 
 mod http_client;
 
-use api_model::buck2::api::TaskBuildRequest;
+use api_model::buck2::{
+    api::TaskBuildRequest,
+    types::{BuildStatus, TargetStatusResponse, TestResultResponse},
+};
 use common::config::BuildConfig;
 pub use http_client::TaskResponse;
 
@@ -33,4 +36,42 @@ impl OrionBuildClient {
         let task_id = self.http.trigger_build(req).await?;
         Ok(task_id)
     }
+
+    /// Targets that must succeed for the CI status check of `path`; empty means every
+    /// built target.
+    pub fn required_targets(&self, path: &str) -> &[String] {
+        self.build_config.required_targets_for(path)
+    }
+
+    pub async fn latest_build_result(&self, task_id: &str) -> anyhow::Result<Option<BuildStatus>> {
+        self.http.latest_build_result(task_id).await
+    }
+
+    pub async fn target_statuses(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<TargetStatusResponse>> {
+        self.http.target_statuses(task_id).await
+    }
+
+    /// Test results of the latest build of the task; empty when it has none.
+    pub async fn test_results(&self, task_id: &str) -> anyhow::Result<Vec<TestResultResponse>> {
+        Ok(self
+            .http
+            .latest_build_tests(task_id)
+            .await?
+            .map(|tests| tests.results)
+            .unwrap_or_default())
+    }
+
+    /// Labels (`package:name`) of the targets quarantined for builds of `path`.
+    pub async fn quarantined_targets(&self, path: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .http
+            .quarantined_targets(path)
+            .await?
+            .into_iter()
+            .map(|quarantine| quarantine.target)
+            .collect())
+    }
 }
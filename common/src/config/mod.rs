@@ -337,6 +337,10 @@ pub struct MonoConfig {
     pub root_dirs: Vec<String>,
     #[serde(default)]
     pub rename: RenameConfig,
+    /// Evaluate Cedar policies committed in the monorepo for Git push and pull and for guarded
+    /// REST endpoints.
+    #[serde(default)]
+    pub enforce_cedar_policies: bool,
 }
 
 impl Default for MonoConfig {
@@ -352,6 +356,7 @@ impl Default for MonoConfig {
                 "release".to_string(),
             ],
             rename: RenameConfig::default(),
+            enforce_cedar_policies: false,
         }
     }
 }
@@ -579,6 +584,51 @@ impl PackConfig {
 pub struct LFSConfig {
     pub local: LFSLocalConfig,
     pub ssh: LFSSshConfig,
+    #[serde(default)]
+    pub gc: LfsGcConfig,
+    /// Byte quotas for LFS uploads, keyed by monorepo root directory (e.g. `project`).
+    /// Root directories without an entry are unlimited.
+    #[serde(default)]
+    pub quotas: HashMap<String, u64>,
+}
+
+/// Periodic garbage collection for `lfs_objects` whose oid is no longer referenced by a
+/// pointer file in any commit reachable from a ref.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LfsGcConfig {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(default = "default_lfs_gc_interval_secs")]
+    pub interval_secs: u64,
+    /// Skip objects created within the last `grace_secs`, so uploads whose pointer commit
+    /// has not been pushed yet survive.
+    #[serde(default = "default_lfs_gc_grace_secs")]
+    pub grace_secs: u64,
+    #[serde(default = "default_lfs_gc_batch_limit")]
+    pub batch_limit: u64,
+}
+
+fn default_lfs_gc_interval_secs() -> u64 {
+    86_400
+}
+
+fn default_lfs_gc_grace_secs() -> u64 {
+    7 * 86_400
+}
+
+fn default_lfs_gc_batch_limit() -> u64 {
+    1000
+}
+
+impl Default for LfsGcConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            interval_secs: default_lfs_gc_interval_secs(),
+            grace_secs: default_lfs_gc_grace_secs(),
+            batch_limit: default_lfs_gc_batch_limit(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -732,6 +782,45 @@ pub struct BuildConfig {
     pub orion_server: String,
     #[serde(default)]
     pub orion_preheat_shallow_depth: usize,
+    /// Buck targets that must succeed for the `CiStatus` merge check, per path. Paths
+    /// without a rule require every target of the build to succeed.
+    #[serde(default)]
+    pub required_targets: Vec<RequiredTargetsRule>,
+    /// Orion worker: run `buck2 test` on the impacted targets after a successful build.
+    #[serde(default)]
+    pub orion_run_tests: bool,
+}
+
+impl BuildConfig {
+    /// Required targets for CLs of `path`, from the rule with the longest `path_prefix`
+    /// that `path` is at or below. Empty means every target of the build.
+    pub fn required_targets_for(&self, path: &str) -> &[String] {
+        self.required_targets
+            .iter()
+            .filter(|rule| rule.covers(path))
+            .max_by_key(|rule| rule.path_prefix.trim_end_matches('/').len())
+            .map_or(&[], |rule| rule.targets.as_slice())
+    }
+}
+
+/// Buck targets (`package:name`) that must succeed for the `CiStatus` merge check of CLs
+/// whose path is `path_prefix` or below it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RequiredTargetsRule {
+    pub path_prefix: String,
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+impl RequiredTargetsRule {
+    fn covers(&self, path: &str) -> bool {
+        let prefix = self.path_prefix.trim_end_matches('/');
+        prefix.is_empty()
+            || path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
 }
 
 /// Orion Server configuration (flat structure)
@@ -757,6 +846,78 @@ pub struct OrionServerConfig {
     /// Mono server base URL for file/blob API (e.g. file blob endpoint). Replaces MONOBASE_URL env.
     #[serde(default = "default_monobase_url")]
     pub monobase_url: String,
+
+    /// Worker requirements of builds, by repo path or Buck target prefix.
+    #[serde(default)]
+    pub routing_rules: Vec<BuildRoutingRule>,
+
+    /// Automatic quarantine of flaky build targets.
+    #[serde(default)]
+    pub flaky_quarantine: FlakyQuarantineConfig,
+}
+
+/// Quarantines a target under its repo path once its build history shows it flaky on
+/// at least `min_flaky_commits` commits and on `threshold` of the commits it was built on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FlakyQuarantineConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_flaky_quarantine_threshold")]
+    pub threshold: f64,
+    #[serde(default = "default_flaky_quarantine_min_flaky_commits")]
+    pub min_flaky_commits: usize,
+    /// How far back the build history is analyzed.
+    #[serde(default = "default_flaky_history_days")]
+    pub history_days: i64,
+    /// Bearer token required to add or release quarantines through the API. Manual
+    /// quarantine management is rejected while it is unset.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+impl Default for FlakyQuarantineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: default_flaky_quarantine_threshold(),
+            min_flaky_commits: default_flaky_quarantine_min_flaky_commits(),
+            history_days: default_flaky_history_days(),
+            admin_token: None,
+        }
+    }
+}
+
+fn default_flaky_quarantine_threshold() -> f64 {
+    0.2
+}
+
+fn default_flaky_quarantine_min_flaky_commits() -> usize {
+    2
+}
+
+fn default_flaky_history_days() -> i64 {
+    14
+}
+
+/// Worker requirements for builds whose repo path starts with `path_prefix` and, when
+/// `target_prefix` is set, that request a target starting with it. Requirements of all
+/// matching rules are combined.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BuildRoutingRule {
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    #[serde(default)]
+    pub target_prefix: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub min_cpu_cores: Option<u32>,
+    #[serde(default)]
+    pub min_memory_mb: Option<u64>,
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub toolchains: Vec<String>,
 }
 
 fn default_monobase_url() -> String {
@@ -792,6 +953,8 @@ impl Default for OrionServerConfig {
             db_url: default_db_url(),
             port: default_port(),
             monobase_url: default_monobase_url(),
+            routing_rules: Vec::new(),
+            flaky_quarantine: FlakyQuarantineConfig::default(),
         }
     }
 }
@@ -1141,6 +1304,40 @@ mod test {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_required_targets_use_the_most_specific_path_rule() {
+        let rule = |path_prefix: &str, target: &str| RequiredTargetsRule {
+            path_prefix: path_prefix.to_string(),
+            targets: vec![target.to_string()],
+        };
+        let config = BuildConfig {
+            required_targets: vec![
+                rule("/", "root//:all"),
+                rule("/project/app/", "root//app:app"),
+                rule("/project", "root//project:lib"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.required_targets_for("/project/app"),
+            ["root//app:app"]
+        );
+        assert_eq!(
+            config.required_targets_for("/project/app/src"),
+            ["root//app:app"]
+        );
+        assert_eq!(
+            config.required_targets_for("/project/apps"),
+            ["root//project:lib"]
+        );
+        assert_eq!(config.required_targets_for("/doc"), ["root//:all"]);
+        assert!(
+            BuildConfig::default()
+                .required_targets_for("/project")
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_mail_config_deserialize() {
         use serde::Deserialize;
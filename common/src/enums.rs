@@ -7,7 +7,9 @@
 //! consistency, especially when multiple modules need to work with the same
 //! set of enum variants.
 
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
 
 /// An enum representing different oauth types.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -25,3 +27,67 @@ impl FromStr for SupportOauthType {
         }
     }
 }
+
+/// Permission carried by a personal access token.
+///
+/// Scopes are stored as their string form (`repo:read`, `repo:write`, `cl:write`, `admin`)
+/// so that new scopes can be added without a schema change.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum TokenScope {
+    #[serde(rename = "repo:read")]
+    RepoRead,
+    #[serde(rename = "repo:write")]
+    RepoWrite,
+    #[serde(rename = "cl:write")]
+    ClWrite,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 4] = [
+        TokenScope::RepoRead,
+        TokenScope::RepoWrite,
+        TokenScope::ClWrite,
+        TokenScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::RepoRead => "repo:read",
+            TokenScope::RepoWrite => "repo:write",
+            TokenScope::ClWrite => "cl:write",
+            TokenScope::Admin => "admin",
+        }
+    }
+
+    /// Whether a token holding `self` may perform an action that requires `required`.
+    /// `admin` covers every scope and `repo:write` covers `repo:read`.
+    pub fn grants(&self, required: TokenScope) -> bool {
+        *self == required
+            || *self == TokenScope::Admin
+            || (*self == TokenScope::RepoWrite && required == TokenScope::RepoRead)
+    }
+
+    /// Whether any scope in `scopes` grants `required`.
+    pub fn any_grants(scopes: &[TokenScope], required: TokenScope) -> bool {
+        scopes.iter().any(|scope| scope.grants(required))
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TokenScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("'{s}' is not a valid token scope"))
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -277,6 +277,26 @@ impl From<MegaError> for GitError {
 pub enum GitLFSError {
     #[error("Something went wrong in Git LFS: {0}")]
     GeneralError(String),
+
+    /// Upload rejected because it would exceed a configured LFS quota.
+    #[error("Insufficient storage: {0}")]
+    InsufficientStorage(String),
+
+    /// Request does not match the current state of a resumable upload.
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// The requested object or lock does not exist.
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// The request is malformed, or uploaded content does not match it.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The caller may not change the lock or object, e.g. a lock owned by someone else.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
 }
 
 /// Buck upload API errors
@@ -322,6 +342,8 @@ pub enum ProtocolError {
     IO(#[from] std::io::Error),
     #[error("Authentication failed: {0}")]
     Deny(String),
+    #[error("Permission denied: {0}")]
+    Forbidden(String),
     #[error("Repository not found: {0}")]
     NotFound(String),
     #[error("PackFile too large: {0}")]
@@ -337,6 +359,7 @@ pub fn protocol_error_http_status(err: &ProtocolError) -> u16 {
         ProtocolError::NotFound(_) => 404,
         ProtocolError::InvalidInput(_) => 400,
         ProtocolError::Deny(_) => 401,
+        ProtocolError::Forbidden(_) => 403,
         ProtocolError::TooLarge(_) => 413,
         ProtocolError::Disabled => 403,
         ProtocolError::IO(_) => 500,
@@ -354,7 +377,7 @@ pub fn mega_to_protocol_error(err: MegaError) -> ProtocolError {
         MegaError::NotFound(msg) => ProtocolError::NotFound(msg),
         MegaError::BadRequest(msg) => ProtocolError::InvalidInput(msg),
         MegaError::Unauthorized(msg) => ProtocolError::Deny(msg),
-        MegaError::Forbidden(msg) => ProtocolError::InvalidInput(msg),
+        MegaError::Forbidden(msg) => ProtocolError::Forbidden(msg),
         MegaError::Unavailable(msg) => ProtocolError::InvalidInput(msg),
         MegaError::Conflict(msg) => ProtocolError::InvalidInput(msg),
         MegaError::Io(e) => ProtocolError::IO(e),
@@ -407,6 +430,10 @@ mod tests {
     #[test]
     fn protocol_error_http_status_maps_disabled() {
         assert_eq!(protocol_error_http_status(&ProtocolError::Disabled), 403);
+        assert_eq!(
+            protocol_error_http_status(&ProtocolError::Forbidden("read-only".into())),
+            403
+        );
     }
 
     #[test]
@@ -7,8 +7,12 @@ use bytes::{Bytes, BytesMut};
 use common::errors::MegaError;
 use futures::{StreamExt, TryStreamExt, stream};
 use object_store::{
-    ObjectStore, ObjectStoreExt, PutMode, PutOptions, PutPayload, UpdateVersion, aws::AmazonS3,
-    gcp::GoogleCloudStorage, local::LocalFileSystem, signer::Signer,
+    ObjectStore, ObjectStoreExt, PutMode, PutOptions, PutPayload, UpdateVersion,
+    aws::AmazonS3,
+    gcp::GoogleCloudStorage,
+    local::LocalFileSystem,
+    multipart::{MultipartStore, PartId},
+    signer::Signer,
 };
 use reqwest::Method;
 
@@ -16,6 +20,7 @@ use crate::{
     error::IoOrbitError,
     log_storage::{LogManifest, LogSegmentMeta, LogStorage},
     object_storage::{MegaObjectStorage, ObjectByteStream, ObjectKey, ObjectMeta, ObjectNamespace},
+    resumable_upload::ResumableUploadStorage,
 };
 
 /// Strategy used for uploading objects to the underlying [`BackendStore`].
@@ -182,6 +187,148 @@ impl MegaObjectStorage for ObjectStoreAdapter {
             .map_err(IoOrbitError::from)?;
         Ok(())
     }
+
+    async fn rename(&self, from: &ObjectKey, to: &ObjectKey) -> Result<(), MegaError> {
+        self.to_store()
+            .rename(&from.to_object_store_path(), &to.to_object_store_path())
+            .await
+            .map_err(IoOrbitError::from)?;
+        Ok(())
+    }
+}
+
+/// Prefix under which the local backend keeps the parts of upload `upload_id`.
+fn upload_parts_key(key: &ObjectKey, upload_id: &str) -> ObjectKey {
+    ObjectKey {
+        namespace: key.namespace,
+        key: format!("{}.upload/{}", key.key, upload_id),
+    }
+}
+
+/// Temporary object holding one part of a local resumable upload.
+fn upload_part_key(key: &ObjectKey, upload_id: &str, part_idx: usize) -> ObjectKey {
+    ObjectKey {
+        namespace: key.namespace,
+        key: format!("{}/{:05}", upload_parts_key(key, upload_id).key, part_idx),
+    }
+}
+
+#[async_trait::async_trait]
+impl ResumableUploadStorage for ObjectStoreAdapter {
+    async fn create_upload(&self, key: &ObjectKey) -> Result<String, MegaError> {
+        let path = key.to_object_store_path();
+        let id = match &self.store {
+            BackendStore::S3(s3) => s3.create_multipart(&path).await,
+            BackendStore::Gcs(gcs) => gcs.create_multipart(&path).await,
+            BackendStore::Local(_) => {
+                let nanos = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_else(|_| Duration::from_millis(0))
+                    .as_nanos();
+                Ok(format!("{nanos:x}"))
+            }
+        }
+        .map_err(IoOrbitError::from)?;
+        Ok(id)
+    }
+
+    async fn put_upload_part(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_idx: usize,
+        data: Bytes,
+    ) -> Result<String, MegaError> {
+        let path = key.to_object_store_path();
+        let upload_id = upload_id.to_owned();
+        let part = match &self.store {
+            BackendStore::S3(s3) => s3.put_part(&path, &upload_id, part_idx, data.into()).await,
+            BackendStore::Gcs(gcs) => gcs.put_part(&path, &upload_id, part_idx, data.into()).await,
+            BackendStore::Local(local) => {
+                let part_key = upload_part_key(key, &upload_id, part_idx);
+                local
+                    .put(&part_key.to_object_store_path(), data.into())
+                    .await
+                    .map(|_| PartId {
+                        content_id: part_key.key,
+                    })
+            }
+        }
+        .map_err(IoOrbitError::from)?;
+        Ok(part.content_id)
+    }
+
+    async fn complete_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        parts: Vec<String>,
+    ) -> Result<(), MegaError> {
+        let path = key.to_object_store_path();
+        let upload_id = upload_id.to_owned();
+        let parts: Vec<PartId> = parts
+            .into_iter()
+            .map(|content_id| PartId { content_id })
+            .collect();
+        match &self.store {
+            BackendStore::S3(s3) => s3
+                .complete_multipart(&path, &upload_id, parts)
+                .await
+                .map(|_| ())
+                .map_err(IoOrbitError::from)?,
+            BackendStore::Gcs(gcs) => gcs
+                .complete_multipart(&path, &upload_id, parts)
+                .await
+                .map(|_| ())
+                .map_err(IoOrbitError::from)?,
+            BackendStore::Local(local) => {
+                // Parts are read one at a time, so memory stays bounded by the part size.
+                let local = local.clone();
+                let namespace = key.namespace;
+                let data = stream::iter(parts)
+                    .then(move |part| {
+                        let local = local.clone();
+                        async move {
+                            let part_key = ObjectKey {
+                                namespace,
+                                key: part.content_id,
+                            };
+                            local
+                                .get(&part_key.to_object_store_path())
+                                .await?
+                                .bytes()
+                                .await
+                        }
+                    })
+                    .map_err(std::io::Error::other);
+                self.put_multipart(&path, Box::pin(data)).await?;
+                self.abort_upload(key, &upload_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn abort_upload(&self, key: &ObjectKey, upload_id: &str) -> Result<(), MegaError> {
+        let path = key.to_object_store_path();
+        let upload_id = upload_id.to_owned();
+        match &self.store {
+            BackendStore::S3(s3) => s3.abort_multipart(&path, &upload_id).await,
+            BackendStore::Gcs(gcs) => gcs.abort_multipart(&path, &upload_id).await,
+            BackendStore::Local(local) => {
+                let prefix = upload_parts_key(key, &upload_id).to_object_store_path();
+                let mut parts = local.list(Some(&prefix));
+                while let Some(part) = parts.try_next().await.map_err(IoOrbitError::from)? {
+                    local
+                        .delete(&part.location)
+                        .await
+                        .map_err(IoOrbitError::from)?;
+                }
+                Ok(())
+            }
+        }
+        .map_err(IoOrbitError::from)?;
+        Ok(())
+    }
 }
 
 /// Derives the manifest [`ObjectKey`] for a log identified by `key`.
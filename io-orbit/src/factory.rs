@@ -13,11 +13,15 @@ use crate::{
     adapter::{BackendStore, ObjectStoreAdapter, UploadStrategy},
     log_storage::LogStorage,
     object_storage::MegaObjectStorage,
+    resumable_upload::ResumableUploadStorage,
 };
 
-pub trait MegaObjectStorageWithLog: MegaObjectStorage + LogStorage {}
+pub trait MegaObjectStorageWithLog:
+    MegaObjectStorage + LogStorage + ResumableUploadStorage
+{
+}
 
-impl<T: MegaObjectStorage + LogStorage> MegaObjectStorageWithLog for T {}
+impl<T: MegaObjectStorage + LogStorage + ResumableUploadStorage> MegaObjectStorageWithLog for T {}
 
 #[derive(Clone)]
 pub struct MegaObjectStorageWrapper {
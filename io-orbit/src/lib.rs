@@ -3,5 +3,6 @@ pub mod error;
 pub mod factory;
 pub mod log_storage;
 pub mod object_storage;
+pub mod resumable_upload;
 
 pub use log_storage::{LogManifest, LogSegmentMeta, LogStorage};
@@ -276,6 +276,11 @@ pub trait MegaObjectStorage: Send + Sync {
     /// - `Ok(())` if the object is deleted successfully
     /// - `Err(MegaError)` if the object does not exist or deletion fails
     async fn delete(&self, key: &ObjectKey) -> Result<(), MegaError>;
+
+    /// Move an object to a new key, replacing any object already stored there.
+    ///
+    /// Backends without a native rename implement this as copy followed by delete.
+    async fn rename(&self, from: &ObjectKey, to: &ObjectKey) -> Result<(), MegaError>;
 }
 
 pub fn dump_error_chain(err: &(dyn std::error::Error + 'static)) -> String {
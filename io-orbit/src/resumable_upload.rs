@@ -0,0 +1,48 @@
+//! Resumable upload abstraction over [`MegaObjectStorage`].
+//!
+//! An upload is assembled from parts written by separate calls, possibly from different
+//! requests or server processes. Callers persist the upload id and the returned part ids
+//! between calls and hand them back on completion.
+
+use bytes::Bytes;
+use common::errors::MegaError;
+
+use crate::object_storage::{MegaObjectStorage, ObjectKey};
+
+/// Size of every part except the last. Above the 5 MiB minimum of S3 multipart uploads,
+/// and identical for all parts as required by some S3-compatible stores.
+pub const RESUMABLE_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Multipart uploads whose state lives outside the storage process.
+///
+/// Notes:
+/// - S3 and GCS map onto their native multipart APIs.
+/// - The local filesystem writes each part to a temporary object and concatenates them
+///   on completion.
+/// - Every part except the last must be exactly [`RESUMABLE_PART_SIZE`] bytes.
+#[async_trait::async_trait]
+pub trait ResumableUploadStorage: MegaObjectStorage {
+    /// Starts an upload that will be written to `key` and returns its id.
+    async fn create_upload(&self, key: &ObjectKey) -> Result<String, MegaError>;
+
+    /// Stores part `part_idx` (0-based) of the upload and returns the part id needed
+    /// by [`Self::complete_upload`]. Re-uploading an index replaces that part.
+    async fn put_upload_part(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        part_idx: usize,
+        data: Bytes,
+    ) -> Result<String, MegaError>;
+
+    /// Assembles `parts`, in order, into the object at `key`.
+    async fn complete_upload(
+        &self,
+        key: &ObjectKey,
+        upload_id: &str,
+        parts: Vec<String>,
+    ) -> Result<(), MegaError>;
+
+    /// Discards an unfinished upload and the parts stored for it.
+    async fn abort_upload(&self, key: &ObjectKey, upload_id: &str) -> Result<(), MegaError>;
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::migration::pk_bigint;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PathProtectionRules::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(PathProtectionRules::Id))
+                    .col(string(PathProtectionRules::Path))
+                    .col(text(PathProtectionRules::AllowedMergeStrategies))
+                    .col(integer(PathProtectionRules::RequiredApprovals))
+                    .col(boolean(PathProtectionRules::ForbidSelfApproval))
+                    .col(boolean(PathProtectionRules::RequireLinearHistory))
+                    .col(boolean(PathProtectionRules::BlockDirectPush))
+                    .col(date_time(PathProtectionRules::CreatedAt))
+                    .col(date_time(PathProtectionRules::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_path_protection_rules_path")
+                    .table(PathProtectionRules::Table)
+                    .col(PathProtectionRules::Path)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PathProtectionRules::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PathProtectionRules {
+    Table,
+    Id,
+    Path,
+    AllowedMergeStrategies,
+    RequiredApprovals,
+    ForbidSelfApproval,
+    RequireLinearHistory,
+    BlockDirectPush,
+    CreatedAt,
+    UpdatedAt,
+}
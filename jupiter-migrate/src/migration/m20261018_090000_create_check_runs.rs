@@ -0,0 +1,111 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::migration::pk_bigint;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CheckRuns::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(CheckRuns::Id))
+                    .col(string(CheckRuns::ClLink))
+                    .col(string(CheckRuns::CommitId))
+                    .col(string(CheckRuns::Name))
+                    .col(big_integer(CheckRuns::BotId))
+                    .col(string(CheckRuns::Status))
+                    .col(text(CheckRuns::Summary))
+                    .col(text(CheckRuns::Annotations))
+                    .col(date_time(CheckRuns::CreatedAt))
+                    .col(date_time(CheckRuns::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_check_runs_cl_commit_name")
+                    .table(CheckRuns::Table)
+                    .col(CheckRuns::ClLink)
+                    .col(CheckRuns::CommitId)
+                    .col(CheckRuns::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PathCheckRunConfigs::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(PathCheckRunConfigs::Id))
+                    .col(string(PathCheckRunConfigs::Path))
+                    .col(string(PathCheckRunConfigs::Name))
+                    .col(boolean(PathCheckRunConfigs::Required))
+                    .col(date_time(PathCheckRunConfigs::CreatedAt))
+                    .col(date_time(PathCheckRunConfigs::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_path_check_run_configs_path_name")
+                    .table(PathCheckRunConfigs::Table)
+                    .col(PathCheckRunConfigs::Path)
+                    .col(PathCheckRunConfigs::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PathCheckRunConfigs::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(CheckRuns::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CheckRuns {
+    Table,
+    Id,
+    ClLink,
+    CommitId,
+    Name,
+    BotId,
+    Status,
+    Summary,
+    Annotations,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum PathCheckRunConfigs {
+    Table,
+    Id,
+    Path,
+    Name,
+    Required,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::migration::pk_bigint;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildSchedules::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(BuildSchedules::Id))
+                    .col(string(BuildSchedules::RepoPath))
+                    .col(string(BuildSchedules::Name))
+                    .col(string(BuildSchedules::CronExpression))
+                    .col(boolean(BuildSchedules::Enabled))
+                    .col(string(BuildSchedules::CreatedBy))
+                    .col(date_time_null(BuildSchedules::NextRunAt))
+                    .col(date_time_null(BuildSchedules::LastRunAt))
+                    .col(string_null(BuildSchedules::LastCommitHash))
+                    .col(big_integer_null(BuildSchedules::LastTriggerId))
+                    .col(date_time(BuildSchedules::CreatedAt))
+                    .col(date_time(BuildSchedules::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_build_schedules_repo_path_name")
+                    .table(BuildSchedules::Table)
+                    .col(BuildSchedules::RepoPath)
+                    .col(BuildSchedules::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_build_schedules_next_run_at")
+                    .table(BuildSchedules::Table)
+                    .col(BuildSchedules::NextRunAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BuildSchedules::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BuildSchedules {
+    Table,
+    Id,
+    RepoPath,
+    Name,
+    CronExpression,
+    Enabled,
+    CreatedBy,
+    NextRunAt,
+    LastRunAt,
+    LastCommitHash,
+    LastTriggerId,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Rows written before this migration keep NULL and are treated as outside the GC
+        // grace window and unattributed to any root directory.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LfsObjects::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(LfsObjects::LastSeenAt).date_time().null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LfsObjects::Table)
+                    .add_column_if_not_exists(ColumnDef::new(LfsObjects::RootDir).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_lfs_objects_root_dir")
+                    .table(LfsObjects::Table)
+                    .col(LfsObjects::RootDir)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LfsObjects {
+    Table,
+    LastSeenAt,
+    RootDir,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LfsTusUploads::Table)
+                    .if_not_exists()
+                    .col(string(LfsTusUploads::Oid).primary_key())
+                    .col(string(LfsTusUploads::UploadId))
+                    .col(big_integer(LfsTusUploads::Size))
+                    .col(big_integer(LfsTusUploads::UploadOffset))
+                    .col(big_integer(LfsTusUploads::PartSize))
+                    .col(json(LfsTusUploads::Parts))
+                    .col(date_time(LfsTusUploads::CreatedAt))
+                    .col(date_time(LfsTusUploads::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_lfs_tus_uploads_updated_at")
+                    .table(LfsTusUploads::Table)
+                    .col(LfsTusUploads::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LfsTusUploads::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LfsTusUploads {
+    Table,
+    Oid,
+    UploadId,
+    Size,
+    UploadOffset,
+    PartSize,
+    Parts,
+    CreatedAt,
+    UpdatedAt,
+}
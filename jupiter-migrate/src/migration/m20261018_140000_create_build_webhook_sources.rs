@@ -0,0 +1,107 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::migration::pk_bigint;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildWebhookSources::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(BuildWebhookSources::Id))
+                    .col(string(BuildWebhookSources::Name))
+                    .col(text(BuildWebhookSources::Secret))
+                    .col(string_null(BuildWebhookSources::RepoPath))
+                    .col(boolean(BuildWebhookSources::Enabled))
+                    .col(string(BuildWebhookSources::CreatedBy))
+                    .col(date_time(BuildWebhookSources::CreatedAt))
+                    .col(date_time(BuildWebhookSources::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_build_webhook_sources_name")
+                    .table(BuildWebhookSources::Table)
+                    .col(BuildWebhookSources::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildWebhookDeliveries::Table)
+                    .if_not_exists()
+                    .col(pk_bigint(BuildWebhookDeliveries::Id))
+                    .col(big_integer(BuildWebhookDeliveries::SourceId))
+                    .col(string(BuildWebhookDeliveries::DeliveryId))
+                    .col(big_integer_null(BuildWebhookDeliveries::TriggerId))
+                    .col(date_time(BuildWebhookDeliveries::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("ux_build_webhook_deliveries_source_delivery")
+                    .table(BuildWebhookDeliveries::Table)
+                    .col(BuildWebhookDeliveries::SourceId)
+                    .col(BuildWebhookDeliveries::DeliveryId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(BuildWebhookDeliveries::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BuildWebhookSources::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BuildWebhookSources {
+    Table,
+    Id,
+    Name,
+    Secret,
+    RepoPath,
+    Enabled,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum BuildWebhookDeliveries {
+    Table,
+    Id,
+    SourceId,
+    DeliveryId,
+    TriggerId,
+    CreatedAt,
+}
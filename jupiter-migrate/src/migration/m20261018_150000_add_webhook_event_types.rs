@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Event types added for push, tag, issue, review, merge queue and build webhooks.
+const NEW_EVENT_TYPES: &[&str] = &[
+    "push",
+    "tag.created",
+    "tag.deleted",
+    "issue.created",
+    "issue.updated",
+    "issue.closed",
+    "issue.reopened",
+    "review.approved",
+    "merge_queue.added",
+    "merge_queue.removed",
+    "merge_queue.merged",
+    "merge_queue.failed",
+    "build.completed",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                let conn = manager.get_connection();
+                for value in NEW_EVENT_TYPES {
+                    conn.execute_unprepared(&format!(
+                        "ALTER TYPE webhook_event_type_enum ADD VALUE IF NOT EXISTS '{value}';"
+                    ))
+                    .await?;
+                }
+            }
+            DatabaseBackend::Sqlite | DatabaseBackend::MySql => {}
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _: &SchemaManager) -> Result<(), DbErr> {
+        // Note: PostgreSQL does not support removing enum values directly
+        // This migration cannot be fully reversed without recreating the enum
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Deliveries become outbox rows that the delivery worker picks up by status and
+        // `next_retry_at`; rows written before this migration are finished attempts.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaWebhookDelivery::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(MegaWebhookDelivery::Status)
+                            .string()
+                            .not_null()
+                            .default("delivered"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaWebhookDelivery::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(MegaWebhookDelivery::NextRetryAt)
+                            .date_time()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaWebhookDelivery::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(MegaWebhookDelivery::LastAttemptAt)
+                            .date_time()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaWebhookDelivery::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(MegaWebhookDelivery::RedeliveryOf)
+                            .big_integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE mega_webhook_delivery SET status = 'failed', last_attempt_at = created_at \
+                 WHERE success = false",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE mega_webhook_delivery SET last_attempt_at = created_at \
+                 WHERE last_attempt_at IS NULL",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_mega_webhook_delivery_status_next_retry")
+                    .table(MegaWebhookDelivery::Table)
+                    .col(MegaWebhookDelivery::Status)
+                    .col(MegaWebhookDelivery::NextRetryAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_mega_webhook_delivery_webhook_created")
+                    .table(MegaWebhookDelivery::Table)
+                    .col(MegaWebhookDelivery::WebhookId)
+                    .col(MegaWebhookDelivery::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        if let DatabaseBackend::Postgres = manager.get_database_backend() {
+            manager
+                .get_connection()
+                .execute_unprepared(
+                    "ALTER TYPE webhook_event_type_enum ADD VALUE IF NOT EXISTS 'ping';",
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MegaWebhookDelivery {
+    Table,
+    WebhookId,
+    Status,
+    NextRetryAt,
+    LastAttemptAt,
+    RedeliveryOf,
+    CreatedAt,
+}
@@ -0,0 +1,216 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use sea_orm::{FromQueryResult, Statement};
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+use sha2::{Digest, Sha256};
+
+/// Scopes granted to tokens that predate scoping.
+const ALL_SCOPES: &str = r#"["repo:read","repo:write","cl:write","admin"]"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Debug, FromQueryResult)]
+struct TokenRow {
+    id: i64,
+    token: String,
+}
+
+fn random_salt() -> Result<[u8; 16], DbErr> {
+    let mut salt = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| DbErr::Custom("failed to generate access token salt".to_owned()))?;
+    Ok(salt)
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Personal access tokens are looked up by a short non-secret prefix and verified
+        // against `sha256(salt || token)`; the plaintext column is dropped at the end.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::TokenPrefix)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::TokenSalt)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::TokenHash)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::Scopes)
+                            .json()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::ExpiresAt).date_time().null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(AccessToken::LastUsedAt).date_time().null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let conn = manager.get_connection();
+        match manager.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                // Existing tokens keep working with every scope so upgrades do not break
+                // pushes; users can rotate them into narrower tokens afterwards.
+                conn.execute_unprepared(
+                    "UPDATE access_token SET \
+                     token_prefix = left(token, 12), \
+                     token_salt = md5(random()::text || clock_timestamp()::text || id::text), \
+                     scopes = '[\"repo:read\",\"repo:write\",\"cl:write\",\"admin\"]'::json",
+                )
+                .await?;
+                conn.execute_unprepared(
+                    "UPDATE access_token SET \
+                     token_hash = encode(sha256(convert_to(token_salt || token, 'UTF8')), 'hex')",
+                )
+                .await?;
+            }
+            DatabaseBackend::MySql => {
+                conn.execute_unprepared(
+                    "UPDATE access_token SET \
+                     token_prefix = LEFT(token, 12), \
+                     token_salt = MD5(CONCAT(RAND(), NOW(6), id)), \
+                     scopes = JSON_ARRAY('repo:read', 'repo:write', 'cl:write', 'admin')",
+                )
+                .await?;
+                conn.execute_unprepared(
+                    "UPDATE access_token SET token_hash = SHA2(CONCAT(token_salt, token), 256)",
+                )
+                .await?;
+            }
+            DatabaseBackend::Sqlite => {
+                // SQLite has no built-in SHA-256, so the rows are hashed here.
+                let tokens: Vec<TokenRow> = TokenRow::find_by_statement(Statement::from_string(
+                    DatabaseBackend::Sqlite,
+                    "SELECT id, token FROM access_token".to_owned(),
+                ))
+                .all(conn)
+                .await?;
+                for row in tokens {
+                    let salt = hex::encode(random_salt()?);
+                    let mut hasher = Sha256::new();
+                    hasher.update(salt.as_bytes());
+                    hasher.update(row.token.as_bytes());
+                    let update = Query::update()
+                        .table(AccessToken::Table)
+                        .values([
+                            (
+                                AccessToken::TokenPrefix,
+                                row.token.chars().take(12).collect::<String>().into(),
+                            ),
+                            (
+                                AccessToken::TokenHash,
+                                hex::encode(hasher.finalize()).into(),
+                            ),
+                            (AccessToken::TokenSalt, salt.into()),
+                            (AccessToken::Scopes, ALL_SCOPES.into()),
+                        ])
+                        .and_where(Expr::col(AccessToken::Id).eq(row.id))
+                        .to_owned();
+                    manager.exec_stmt(update).await?;
+                }
+            }
+        }
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .if_exists()
+                    .name("idx_token")
+                    .table(AccessToken::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessToken::Table)
+                    .drop_column(AccessToken::Token)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_access_token_prefix")
+                    .table(AccessToken::Table)
+                    .col(AccessToken::TokenPrefix)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccessToken {
+    Table,
+    Id,
+    Token,
+    TokenPrefix,
+    TokenSalt,
+    TokenHash,
+    Scopes,
+    ExpiresAt,
+    LastUsedAt,
+}
@@ -0,0 +1,122 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Per-test results reported by `buck2 test` for a build event.
+        manager
+            .create_table(
+                Table::create()
+                    .table(BuildTestResults::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BuildTestResults::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildTestResults::BuildEventId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BuildTestResults::TaskId).uuid().not_null())
+                    .col(ColumnDef::new(BuildTestResults::Target).text().not_null())
+                    .col(ColumnDef::new(BuildTestResults::TestName).text().not_null())
+                    .col(
+                        ColumnDef::new(BuildTestResults::Status)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BuildTestResults::DurationMs)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(BuildTestResults::Message).text().null())
+                    .col(
+                        ColumnDef::new(BuildTestResults::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(BuildTestResults::Table, BuildTestResults::BuildEventId)
+                            .to(BuildEvents::Table, BuildEvents::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_build_test_results_build_event_id")
+                    .table(BuildTestResults::Table)
+                    .col(BuildTestResults::BuildEventId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_build_test_results_target_test_name")
+                    .table(BuildTestResults::Table)
+                    .col(BuildTestResults::Target)
+                    .col(BuildTestResults::TestName)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_build_test_results_target_test_name")
+                    .table(BuildTestResults::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_build_test_results_build_event_id")
+                    .table(BuildTestResults::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BuildTestResults::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BuildTestResults {
+    Table,
+    Id,
+    BuildEventId,
+    TaskId,
+    Target,
+    TestName,
+    Status,
+    DurationMs,
+    Message,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum BuildEvents {
+    Table,
+    Id,
+}
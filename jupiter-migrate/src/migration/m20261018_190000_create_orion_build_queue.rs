@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Builds waiting for a worker, so orion-server can restore its queue on restart.
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrionBuildQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::BuildEventId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OrionBuildQueue::TaskId).uuid().not_null())
+                    .col(ColumnDef::new(OrionBuildQueue::Repo).text().not_null())
+                    .col(ColumnDef::new(OrionBuildQueue::ClLink).text().not_null())
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::BuildTargetId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::TargetPath)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::Changes)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::Requirements)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::Priority)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::RetryCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(OrionBuildQueue::EnqueuedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OrionBuildQueue::Table, OrionBuildQueue::BuildEventId)
+                            .to(BuildEvents::Table, BuildEvents::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_orion_build_queue_enqueued_at")
+                    .table(OrionBuildQueue::Table)
+                    .col(OrionBuildQueue::EnqueuedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_orion_build_queue_enqueued_at")
+                    .table(OrionBuildQueue::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(OrionBuildQueue::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum OrionBuildQueue {
+    Table,
+    BuildEventId,
+    TaskId,
+    Repo,
+    ClLink,
+    BuildTargetId,
+    TargetPath,
+    Changes,
+    Requirements,
+    Priority,
+    RetryCount,
+    EnqueuedAt,
+}
+
+#[derive(DeriveIden)]
+enum BuildEvents {
+    Table,
+    Id,
+}
@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Targets whose failures the CI status gate ignores under a path.
+        manager
+            .create_table(
+                Table::create()
+                    .table(TargetQuarantines::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TargetQuarantines::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TargetQuarantines::Path).text().not_null())
+                    .col(ColumnDef::new(TargetQuarantines::Target).text().not_null())
+                    .col(ColumnDef::new(TargetQuarantines::Reason).text().null())
+                    .col(
+                        ColumnDef::new(TargetQuarantines::Source)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TargetQuarantines::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ux_target_quarantines_path_target")
+                    .table(TargetQuarantines::Table)
+                    .col(TargetQuarantines::Path)
+                    .col(TargetQuarantines::Target)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("ux_target_quarantines_path_target")
+                    .table(TargetQuarantines::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TargetQuarantines::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TargetQuarantines {
+    Table,
+    Id,
+    Path,
+    Target,
+    Reason,
+    Source,
+    CreatedAt,
+}
@@ -0,0 +1,30 @@
+use sea_orm::{DatabaseBackend, Statement};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // m20250812_022434_alter_mega_mr skipped the `user_id` -> `username` renames for
+        // SQLite, which left token and SSH key lookups by username broken there.
+        if manager.get_database_backend() != DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+        for table in ["access_token", "ssh_keys"] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Sqlite,
+                    format!("ALTER TABLE {table} RENAME COLUMN user_id TO username"),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
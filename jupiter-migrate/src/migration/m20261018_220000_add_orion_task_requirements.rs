@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Worker requirements resolved when the task was submitted, reused by build retries.
+        // Tasks created before this migration keep NULL.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrionTasks::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(OrionTasks::Requirements)
+                            .json_binary()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrionTasks::Table)
+                    .drop_column(OrionTasks::Requirements)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OrionTasks {
+    Table,
+    Requirements,
+}
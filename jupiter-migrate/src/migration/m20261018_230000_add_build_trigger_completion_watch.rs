@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Set while a `build.completed` webhook is owed for the build of the trigger,
+        // until when Orion is polled for its result; cleared once the event is queued.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BuildTriggers::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(BuildTriggers::CompletionWatchUntil)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_build_triggers_completion_watch_until")
+                    .table(BuildTriggers::Table)
+                    .col(BuildTriggers::CompletionWatchUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .if_exists()
+                    .name("idx_build_triggers_completion_watch_until")
+                    .table(BuildTriggers::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BuildTriggers::Table)
+                    .drop_column(BuildTriggers::CompletionWatchUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BuildTriggers {
+    Table,
+    CompletionWatchUntil,
+}
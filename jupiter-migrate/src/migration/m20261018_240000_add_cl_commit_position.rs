@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Order of the commits within their CL, oldest first, continuing across pushes.
+        // Commits recorded before this migration keep 0 and fall back to `created_at`.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaClCommits::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(MegaClCommits::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MegaClCommits::Table)
+                    .drop_column(MegaClCommits::Position)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MegaClCommits {
+    Table,
+    Position,
+}
@@ -98,6 +98,23 @@ mod m20260324_033322_fix_migration;
 mod m20260327_034553_drop_legacy_tasks;
 mod m20260413_033315_create_artifact_tables;
 mod m20260612_011232_drop_build_events_log;
+mod m20261018_080000_create_path_protection_rules;
+mod m20261018_090000_create_check_runs;
+mod m20261018_100000_add_merge_queue_build_columns;
+mod m20261018_110000_create_build_schedules;
+mod m20261018_120000_add_lfs_object_gc_columns;
+mod m20261018_130000_create_lfs_tus_uploads;
+mod m20261018_140000_create_build_webhook_sources;
+mod m20261018_150000_add_webhook_event_types;
+mod m20261018_160000_add_webhook_delivery_outbox;
+mod m20261018_170000_hash_access_tokens;
+mod m20261018_180000_create_build_test_results;
+mod m20261018_190000_create_orion_build_queue;
+mod m20261018_200000_create_target_flakiness_tables;
+mod m20261018_210000_rename_sqlite_user_id_columns;
+mod m20261018_220000_add_orion_task_requirements;
+mod m20261018_230000_add_build_trigger_completion_watch;
+mod m20261018_240000_add_cl_commit_position;
 mod runner;
 pub use runner::apply_migrations;
 
@@ -183,6 +200,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20260327_034553_drop_legacy_tasks::Migration),
             Box::new(m20260413_033315_create_artifact_tables::Migration),
             Box::new(m20260612_011232_drop_build_events_log::Migration),
+            Box::new(m20261018_080000_create_path_protection_rules::Migration),
+            Box::new(m20261018_090000_create_check_runs::Migration),
+            Box::new(m20261018_100000_add_merge_queue_build_columns::Migration),
+            Box::new(m20261018_110000_create_build_schedules::Migration),
+            Box::new(m20261018_120000_add_lfs_object_gc_columns::Migration),
+            Box::new(m20261018_130000_create_lfs_tus_uploads::Migration),
+            Box::new(m20261018_140000_create_build_webhook_sources::Migration),
+            Box::new(m20261018_150000_add_webhook_event_types::Migration),
+            Box::new(m20261018_160000_add_webhook_delivery_outbox::Migration),
+            Box::new(m20261018_170000_hash_access_tokens::Migration),
+            Box::new(m20261018_180000_create_build_test_results::Migration),
+            Box::new(m20261018_190000_create_orion_build_queue::Migration),
+            Box::new(m20261018_200000_create_target_flakiness_tables::Migration),
+            Box::new(m20261018_210000_rename_sqlite_user_id_columns::Migration),
+            Box::new(m20261018_220000_add_orion_task_requirements::Migration),
+            Box::new(m20261018_230000_add_build_trigger_completion_watch::Migration),
+            Box::new(m20261018_240000_add_cl_commit_position::Migration),
         ]
     }
 }
@@ -20,13 +20,15 @@ pub async fn apply_migrations(db: &DatabaseConnection, refresh: bool) -> Result<
 #[cfg(test)]
 mod tests {
     use callisto::{
-        email_jobs, notification_event_types, user_notification_preferences,
+        access_token, email_jobs, notification_event_types, user_notification_preferences,
         user_notification_settings,
     };
     use sea_orm::{
-        ActiveModelTrait, ConnectOptions, ConnectionTrait, Database, DbBackend, Set, Statement,
+        ActiveModelTrait, ConnectOptions, ConnectionTrait, Database, DbBackend, EntityTrait, Set,
+        Statement,
     };
     use sea_orm_migration::prelude::MigratorTrait;
+    use sha2::{Digest, Sha256};
 
     use super::*;
 
@@ -175,4 +177,45 @@ mod tests {
         .await;
         assert!(res.is_err(), "expected FK violation for unknown event type");
     }
+
+    #[tokio::test]
+    async fn test_sqlite_access_tokens_are_hashed_in_place() {
+        let temp_dir = tempfile::TempDir::new().expect("Failed to create temporary directory");
+        let db = test_db_connection(temp_dir.path()).await;
+        let before_hash = Migrator::migrations()
+            .iter()
+            .position(|m| m.name() == "m20261018_170000_hash_access_tokens")
+            .expect("hash migration is registered");
+        Migrator::up(&db, Some(before_hash as u32))
+            .await
+            .expect("migrations before hashing should apply");
+
+        let token = "a1b2c3d4-e5f6-7890-abcd-ef1234567890";
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!(
+                "INSERT INTO access_token (id, user_id, token, created_at) \
+                 VALUES (1, 'alice', '{token}', '2026-01-01 00:00:00')"
+            ),
+        ))
+        .await
+        .expect("insert plaintext token");
+
+        apply_migrations(&db, false)
+            .await
+            .expect("remaining migrations should apply");
+
+        let row = access_token::Entity::find_by_id(1)
+            .one(&db)
+            .await
+            .expect("query access_token")
+            .expect("token survives the migration");
+        assert_eq!(row.username, "alice");
+        assert_eq!(row.token_prefix, "a1b2c3d4-e5f");
+        let mut hasher = Sha256::new();
+        hasher.update(row.token_salt.as_bytes());
+        hasher.update(token.as_bytes());
+        assert_eq!(row.token_hash, hex::encode(hasher.finalize()));
+        assert_eq!(row.scopes.as_array().map(Vec::len), Some(4));
+    }
 }
@@ -9,8 +9,12 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: i64,
     pub username: String,
-    #[sea_orm(column_type = "Text")]
-    pub token: String,
+    pub token_prefix: String,
+    pub token_salt: String,
+    pub token_hash: String,
+    pub scopes: Json,
+    pub expires_at: Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
     pub created_at: DateTime,
 }
 
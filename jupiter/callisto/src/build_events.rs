@@ -26,6 +26,10 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     OrionTasks,
+    #[sea_orm(has_many = "super::build_test_results::Entity")]
+    BuildTestResults,
+    #[sea_orm(has_one = "super::orion_build_queue::Entity")]
+    OrionBuildQueue,
     #[sea_orm(has_many = "super::target_state_histories::Entity")]
     TargetStateHistories,
 }
@@ -36,6 +40,18 @@ impl Related<super::orion_tasks::Entity> for Entity {
     }
 }
 
+impl Related<super::build_test_results::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BuildTestResults.def()
+    }
+}
+
+impl Related<super::orion_build_queue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrionBuildQueue.def()
+    }
+}
+
 impl Related<super::target_state_histories::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::TargetStateHistories.def()
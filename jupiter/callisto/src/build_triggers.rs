@@ -15,6 +15,7 @@ pub struct Model {
     pub trigger_time: DateTime,
     pub task_id: Option<Uuid>,
     pub updated_at: DateTime,
+    pub completion_watch_until: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -10,6 +10,8 @@ pub struct Model {
     pub oid: String,
     pub size: i64,
     pub exist: bool,
+    pub last_seen_at: Option<DateTime>,
+    pub root_dir: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -16,6 +16,7 @@ pub struct Model {
     pub author_email: String,
     #[sea_orm(column_type = "Text")]
     pub message: String,
+    pub position: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +22,10 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub error_message: Option<String>,
     pub created_at: DateTime,
+    pub status: String,
+    pub next_retry_at: Option<DateTime>,
+    pub last_attempt_at: Option<DateTime>,
+    pub redelivery_of: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
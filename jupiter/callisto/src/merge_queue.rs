@@ -19,6 +19,8 @@ pub struct Model {
     pub failure_type: Option<QueueFailureTypeEnum>,
     #[sea_orm(column_type = "Text", nullable)]
     pub error_message: Option<String>,
+    pub batch_link: Option<String>,
+    pub build_task_id: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -14,9 +14,14 @@ pub mod bots;
 pub mod buck_session;
 pub mod buck_session_file;
 pub mod build_events;
+pub mod build_schedules;
 pub mod build_targets;
+pub mod build_test_results;
 pub mod build_triggers;
+pub mod build_webhook_deliveries;
+pub mod build_webhook_sources;
 pub mod check_result;
+pub mod check_runs;
 pub mod cla_sign_status;
 pub mod commit_auths;
 pub mod dynamic_sidebar;
@@ -37,6 +42,7 @@ pub mod item_labels;
 pub mod label;
 pub mod lfs_locks;
 pub mod lfs_objects;
+pub mod lfs_tus_uploads;
 pub mod mega_blob;
 pub mod mega_cl;
 pub mod mega_cl_commits;
@@ -62,12 +68,16 @@ pub mod non_member_note_views;
 pub mod note_views;
 pub mod notes;
 pub mod notification_event_types;
+pub mod orion_build_queue;
 pub mod orion_tasks;
 pub mod path_check_configs;
+pub mod path_check_run_configs;
+pub mod path_protection_rules;
 pub mod reactions;
 pub mod sea_orm_active_enums;
 pub mod ssh_keys;
 pub mod target_build_status;
+pub mod target_quarantines;
 pub mod target_state_histories;
 pub mod user_notification_preferences;
 pub mod user_notification_settings;
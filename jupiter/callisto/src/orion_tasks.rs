@@ -13,6 +13,8 @@ pub struct Model {
     pub repo_name: String,
     pub cl: String,
     pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub requirements: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -6,17 +6,22 @@ pub use super::{
     audit_logs::Entity as AuditLogs, bot_installations::Entity as BotInstallations,
     bot_keys::Entity as BotKeys, bot_tokens::Entity as BotTokens, bots::Entity as Bots,
     buck_session::Entity as BuckSession, buck_session_file::Entity as BuckSessionFile,
-    build_events::Entity as BuildEvents, build_targets::Entity as BuildTargets,
-    build_triggers::Entity as BuildTriggers, check_result::Entity as CheckResult,
-    cla_sign_status::Entity as ClaSignStatus, commit_auths::Entity as CommitAuths,
-    dynamic_sidebar::Entity as DynamicSidebar, email_jobs::Entity as EmailJobs,
-    git_blob::Entity as GitBlob, git_commit::Entity as GitCommit, git_issue::Entity as GitIssue,
-    git_pr::Entity as GitPr, git_repo::Entity as GitRepo, git_tag::Entity as GitTag,
-    git_tree::Entity as GitTree, gpg_key::Entity as GpgKey, import_refs::Entity as ImportRefs,
-    issue_cl_references::Entity as IssueClReferences, item_assignees::Entity as ItemAssignees,
-    item_labels::Entity as ItemLabels, label::Entity as Label, lfs_locks::Entity as LfsLocks,
-    lfs_objects::Entity as LfsObjects, mega_blob::Entity as MegaBlob, mega_cl::Entity as MegaCl,
-    mega_cl_commits::Entity as MegaClCommits, mega_cl_reviewer::Entity as MegaClReviewer,
+    build_events::Entity as BuildEvents, build_schedules::Entity as BuildSchedules,
+    build_targets::Entity as BuildTargets, build_test_results::Entity as BuildTestResults,
+    build_triggers::Entity as BuildTriggers,
+    build_webhook_deliveries::Entity as BuildWebhookDeliveries,
+    build_webhook_sources::Entity as BuildWebhookSources, check_result::Entity as CheckResult,
+    check_runs::Entity as CheckRuns, cla_sign_status::Entity as ClaSignStatus,
+    commit_auths::Entity as CommitAuths, dynamic_sidebar::Entity as DynamicSidebar,
+    email_jobs::Entity as EmailJobs, git_blob::Entity as GitBlob, git_commit::Entity as GitCommit,
+    git_issue::Entity as GitIssue, git_pr::Entity as GitPr, git_repo::Entity as GitRepo,
+    git_tag::Entity as GitTag, git_tree::Entity as GitTree, gpg_key::Entity as GpgKey,
+    import_refs::Entity as ImportRefs, issue_cl_references::Entity as IssueClReferences,
+    item_assignees::Entity as ItemAssignees, item_labels::Entity as ItemLabels,
+    label::Entity as Label, lfs_locks::Entity as LfsLocks, lfs_objects::Entity as LfsObjects,
+    lfs_tus_uploads::Entity as LfsTusUploads, mega_blob::Entity as MegaBlob,
+    mega_cl::Entity as MegaCl, mega_cl_commits::Entity as MegaClCommits,
+    mega_cl_reviewer::Entity as MegaClReviewer,
     mega_code_review_anchor::Entity as MegaCodeReviewAnchor,
     mega_code_review_comment::Entity as MegaCodeReviewComment,
     mega_code_review_position::Entity as MegaCodeReviewPosition,
@@ -29,9 +34,12 @@ pub use super::{
     mega_webhook_event_type::Entity as MegaWebhookEventType, merge_queue::Entity as MergeQueue,
     non_member_note_views::Entity as NonMemberNoteViews, note_views::Entity as NoteViews,
     notes::Entity as Notes, notification_event_types::Entity as NotificationEventTypes,
-    orion_tasks::Entity as OrionTasks, path_check_configs::Entity as PathCheckConfigs,
-    reactions::Entity as Reactions, ssh_keys::Entity as SshKeys,
-    target_build_status::Entity as TargetBuildStatus,
+    orion_build_queue::Entity as OrionBuildQueue, orion_tasks::Entity as OrionTasks,
+    path_check_configs::Entity as PathCheckConfigs,
+    path_check_run_configs::Entity as PathCheckRunConfigs,
+    path_protection_rules::Entity as PathProtectionRules, reactions::Entity as Reactions,
+    ssh_keys::Entity as SshKeys, target_build_status::Entity as TargetBuildStatus,
+    target_quarantines::Entity as TargetQuarantines,
     target_state_histories::Entity as TargetStateHistories,
     user_notification_preferences::Entity as UserNotificationPreferences,
     user_notification_settings::Entity as UserNotificationSettings, vault::Entity as Vault,
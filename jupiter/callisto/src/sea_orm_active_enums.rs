@@ -308,6 +308,34 @@ pub enum WebhookEventTypeEnum {
     ClReopened,
     #[sea_orm(string_value = "cl.comment.created")]
     ClCommentCreated,
+    #[sea_orm(string_value = "push")]
+    Push,
+    #[sea_orm(string_value = "tag.created")]
+    TagCreated,
+    #[sea_orm(string_value = "tag.deleted")]
+    TagDeleted,
+    #[sea_orm(string_value = "issue.created")]
+    IssueCreated,
+    #[sea_orm(string_value = "issue.updated")]
+    IssueUpdated,
+    #[sea_orm(string_value = "issue.closed")]
+    IssueClosed,
+    #[sea_orm(string_value = "issue.reopened")]
+    IssueReopened,
+    #[sea_orm(string_value = "review.approved")]
+    ReviewApproved,
+    #[sea_orm(string_value = "merge_queue.added")]
+    MergeQueueAdded,
+    #[sea_orm(string_value = "merge_queue.removed")]
+    MergeQueueRemoved,
+    #[sea_orm(string_value = "merge_queue.merged")]
+    MergeQueueMerged,
+    #[sea_orm(string_value = "merge_queue.failed")]
+    MergeQueueFailed,
+    #[sea_orm(string_value = "build.completed")]
+    BuildCompleted,
+    #[sea_orm(string_value = "ping")]
+    Ping,
     #[sea_orm(string_value = "all")]
     All,
 }
@@ -112,6 +112,48 @@ impl MergeQueueService {
             .map_err(MegaError::Other)
     }
 
+    /// Gets up to `limit` waiting items in queue order, the candidates for the next batch.
+    pub async fn get_waiting_items(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<callisto::merge_queue::Model>, MegaError> {
+        self.merge_queue_storage
+            .get_waiting_items(limit)
+            .await
+            .map_err(MegaError::Other)
+    }
+
+    /// Groups queue items into a batch that is validated by a single build.
+    pub async fn assign_batch(
+        &self,
+        cl_links: &[String],
+        batch_link: &str,
+    ) -> Result<(), MegaError> {
+        self.merge_queue_storage
+            .assign_batch(cl_links, batch_link)
+            .await
+            .map_err(MegaError::Other)
+    }
+
+    /// Records the build task validating a batch.
+    pub async fn set_batch_build(&self, batch_link: &str, task_id: &str) -> Result<(), MegaError> {
+        self.merge_queue_storage
+            .set_batch_build(batch_link, task_id)
+            .await
+            .map_err(MegaError::Other)
+    }
+
+    /// Gets the items of a batch in queue order.
+    pub async fn get_batch_items(
+        &self,
+        batch_link: &str,
+    ) -> Result<Vec<callisto::merge_queue::Model>, MegaError> {
+        self.merge_queue_storage
+            .get_batch_items(batch_link)
+            .await
+            .map_err(MegaError::Other)
+    }
+
     /// Updates the status of a queue item.
     ///
     /// Returns true if update was successful, false if item was cancelled/not found.
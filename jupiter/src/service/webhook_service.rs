@@ -1,11 +1,12 @@
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
     time::Duration,
 };
 
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use callisto::{
-    mega_cl,
+    mega_cl, mega_issue, mega_webhook, mega_webhook_delivery,
     sea_orm_active_enums::{MergeStatusEnum, WebhookEventTypeEnum},
 };
 use chrono::Utc;
@@ -19,17 +20,24 @@ use ring::{
 use sea_orm::ActiveEnum;
 use serde::Serialize;
 use sha2::Sha256;
-use tokio::net::lookup_host;
+use tokio::{net::lookup_host, sync::Notify};
 use url::{Host, Url};
 
-use crate::storage::webhook_storage::WebhookStorage;
+use crate::storage::webhook_storage::{
+    DELIVERY_STATUS_DELIVERED, DELIVERY_STATUS_FAILED, DELIVERY_STATUS_PENDING, WebhookStorage,
+};
 
 const WEBHOOK_SECRET_ENC_PREFIX: &str = "enc:v1:";
 const WEBHOOK_SECRET_ENC_KEY_ENV: &str = "MEGA_WEBHOOK_SECRET_ENC_KEY";
 const WEBHOOK_SECRET_KEY_LEN: usize = 32;
 const WEBHOOK_SECRET_NONCE_LEN: usize = 12;
-const WEBHOOK_DELIVERY_MAX_ATTEMPTS: i32 = 3;
-const WEBHOOK_DELIVERY_BACKOFF_BASE_SECS: u64 = 2;
+const WEBHOOK_DELIVERY_MAX_ATTEMPTS: i32 = 6;
+const WEBHOOK_DELIVERY_BACKOFF_BASE_SECS: i64 = 30;
+const WEBHOOK_DELIVERY_MAX_BACKOFF_SECS: i64 = 60 * 60;
+/// How long a claimed delivery is reserved before another worker may retry it.
+const WEBHOOK_DELIVERY_LEASE_SECS: i64 = 60;
+/// Response bodies are stored up to this many bytes.
+const WEBHOOK_RESPONSE_BODY_LIMIT: usize = 16 * 1024;
 
 pub type WebhookEvent = WebhookEventTypeEnum;
 
@@ -38,10 +46,26 @@ pub struct WebhookPayload {
     pub mega_version: String,
     pub event: String,
     pub timestamp: i64,
-    pub cl: ClPayload,
+    #[serde(flatten)]
+    pub body: WebhookEventPayload,
     pub repository: RepositoryPayload,
 }
 
+/// Event-specific part of a [`WebhookPayload`], serialized under its own key
+/// (`cl`, `push`, `tag`, `issue`, `review`, `merge_queue`, `build` or `ping`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventPayload {
+    Cl(ClPayload),
+    Push(PushPayload),
+    Tag(TagPayload),
+    Issue(IssuePayload),
+    Review(ReviewPayload),
+    MergeQueue(MergeQueueItemPayload),
+    Build(BuildPayload),
+    Ping(PingPayload),
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct RepositoryPayload {
     pub path: String,
@@ -82,10 +106,95 @@ impl From<&mega_cl::Model> for ClPayload {
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct PushPayload {
+    pub branch: String,
+    pub before: String,
+    pub after: String,
+    pub pusher: AuthorPayload,
+    /// Pushed commits, parents before children.
+    pub commits: Vec<PushCommitPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cl_link: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PushCommitPayload {
+    pub id: String,
+    pub message: String,
+    pub author: AuthorPayload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagPayload {
+    pub name: String,
+    /// Object the tag points to; unknown for deleted tags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tagger: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuePayload {
+    pub id: i64,
+    pub link: String,
+    pub title: String,
+    pub author: AuthorPayload,
+    pub status: String,
+}
+
+impl From<&mega_issue::Model> for IssuePayload {
+    fn from(model: &mega_issue::Model) -> Self {
+        Self {
+            id: model.id,
+            link: model.link.clone(),
+            title: model.title.clone(),
+            author: AuthorPayload {
+                name: model.author.clone(),
+            },
+            status: model.status.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewPayload {
+    pub cl: ClPayload,
+    pub reviewer: AuthorPayload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeQueueItemPayload {
+    pub cl: ClPayload,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildPayload {
+    pub trigger_id: i64,
+    pub task_id: String,
+    pub trigger_type: String,
+    /// `completed`, `failed` or `cancelled`.
+    pub status: String,
+    pub commit: String,
+    pub cl_link: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PingPayload {
+    pub webhook_id: i64,
+}
+
 #[derive(Clone)]
 pub struct WebhookService {
     storage: WebhookStorage,
     client: reqwest::Client,
+    /// Wakes the delivery worker when deliveries are queued.
+    queued: Arc<Notify>,
 }
 
 impl WebhookService {
@@ -97,136 +206,228 @@ impl WebhookService {
             .map_err(|e| {
                 common::errors::MegaError::Other(format!("failed to build reqwest client: {e}"))
             })?;
-        Ok(Self { storage, client })
+        Ok(Self {
+            storage,
+            client,
+            queued: Arc::new(Notify::new()),
+        })
     }
 
     pub fn mock(storage: WebhookStorage) -> Self {
         Self {
             storage,
             client: reqwest::Client::new(),
+            queued: Arc::new(Notify::new()),
         }
     }
 
-    pub fn dispatch(&self, event_type: WebhookEvent, cl_model: &mega_cl::Model) {
-        let svc = self.clone();
-        let cl_payload = ClPayload::from(cl_model);
-        let path = cl_model.path.clone();
+    pub async fn dispatch(&self, event_type: WebhookEvent, cl_model: &mega_cl::Model) {
+        self.dispatch_event(
+            event_type,
+            &cl_model.path,
+            WebhookEventPayload::Cl(ClPayload::from(cl_model)),
+        )
+        .await;
+    }
 
-        tokio::spawn(async move {
-            if let Err(e) = svc.dispatch_inner(event_type, cl_payload, &path).await {
-                tracing::error!("webhook dispatch error: {e}");
-            }
-        });
+    /// Queues `body` for the active webhooks subscribed to `event_type` whose path filter
+    /// covers `path`; the deliveries are stored when this returns and the delivery worker
+    /// sends them. Failing to queue is logged, it never fails the operation that raised the event.
+    pub async fn dispatch_event(
+        &self,
+        event_type: WebhookEvent,
+        path: &str,
+        body: WebhookEventPayload,
+    ) {
+        if let Err(e) = self.dispatch_inner(event_type, body, path).await {
+            tracing::error!("webhook dispatch error: {e}");
+        }
+    }
+
+    /// Whether any active webhook would receive `event_type` for `path`.
+    pub async fn has_subscribers(
+        &self,
+        event_type: WebhookEvent,
+        path: &str,
+    ) -> Result<bool, common::errors::MegaError> {
+        Ok(!self
+            .storage
+            .find_matching_webhooks(event_type, path)
+            .await?
+            .is_empty())
+    }
+
+    /// Resolves once deliveries have been queued since the previous call.
+    pub async fn deliveries_queued(&self) {
+        self.queued.notified().await;
     }
 
     async fn dispatch_inner(
         &self,
         event_type: WebhookEvent,
-        cl_payload: ClPayload,
+        body: WebhookEventPayload,
         path: &str,
     ) -> Result<(), common::errors::MegaError> {
-        let event_type_str = event_type.to_value();
         let webhooks = self
             .storage
             .find_matching_webhooks(event_type.clone(), path)
             .await?;
+        if webhooks.is_empty() {
+            return Ok(());
+        }
 
+        let payload_json = serde_json::to_string(&build_payload(&event_type, body, path))?;
         for webhook in webhooks {
-            let repo_name = extract_repo_name(path).to_string();
-            let payload = WebhookPayload {
-                mega_version: env!("CARGO_PKG_VERSION").to_string(),
-                event: event_type_str.clone(),
-                timestamp: Utc::now().timestamp(),
-                cl: cl_payload.clone(),
-                repository: RepositoryPayload {
-                    path: path.to_string(),
-                    name: repo_name,
-                },
-            };
-
-            let payload_json = serde_json::to_string(&payload)?;
-
-            let mut last_failure_message = None;
-            let mut delivered_successfully = false;
-            for attempt in 1..=WEBHOOK_DELIVERY_MAX_ATTEMPTS {
-                let should_retry = match self
-                    .deliver(
-                        &webhook.target_url,
-                        &webhook.secret,
-                        &event_type_str,
-                        &payload_json,
-                    )
-                    .await
-                {
-                    Ok((status, body)) => {
-                        let success = (200..300).contains(&status);
-                        let error_message = if success {
-                            None
-                        } else {
-                            Some(format!("webhook endpoint returned HTTP {status}"))
-                        };
-                        let delivery = callisto::mega_webhook_delivery::Model {
-                            id: IdInstance::next_id(),
-                            webhook_id: webhook.id,
-                            event_type: event_type.clone(),
-                            payload: payload_json.clone(),
-                            response_status: Some(status as i32),
-                            response_body: Some(body),
-                            success,
-                            attempt,
-                            error_message: error_message.clone(),
-                            created_at: Utc::now().naive_utc(),
-                        };
-                        if let Err(e) = self.storage.save_delivery(delivery).await {
-                            tracing::warn!("failed to save webhook delivery record: {e}");
-                        }
-                        if success {
-                            delivered_successfully = true;
-                            last_failure_message = None;
-                            break;
-                        }
-                        last_failure_message = error_message;
-                        true
-                    }
-                    Err(e) => {
-                        let error_message = e.to_string();
-                        let delivery = callisto::mega_webhook_delivery::Model {
-                            id: IdInstance::next_id(),
-                            webhook_id: webhook.id,
-                            event_type: event_type.clone(),
-                            payload: payload_json.clone(),
-                            response_status: None,
-                            response_body: None,
-                            success: false,
-                            attempt,
-                            error_message: Some(error_message.clone()),
-                            created_at: Utc::now().naive_utc(),
-                        };
-                        if let Err(save_err) = self.storage.save_delivery(delivery).await {
-                            tracing::warn!("failed to save webhook delivery record: {save_err}");
-                        }
-                        last_failure_message = Some(error_message);
-                        true
-                    }
-                };
-
-                if should_retry && attempt < WEBHOOK_DELIVERY_MAX_ATTEMPTS {
-                    let backoff =
-                        Duration::from_secs(WEBHOOK_DELIVERY_BACKOFF_BASE_SECS.pow(attempt as u32));
-                    tokio::time::sleep(backoff).await;
+            self.storage
+                .save_delivery(queued_delivery(
+                    webhook.id,
+                    event_type.clone(),
+                    payload_json.clone(),
+                    None,
+                ))
+                .await?;
+        }
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Queues the payload of `delivery` again as a new delivery.
+    pub async fn redeliver(
+        &self,
+        delivery: &mega_webhook_delivery::Model,
+    ) -> Result<mega_webhook_delivery::Model, common::errors::MegaError> {
+        let queued = self
+            .storage
+            .save_delivery(queued_delivery(
+                delivery.webhook_id,
+                delivery.event_type.clone(),
+                delivery.payload.clone(),
+                Some(delivery.id),
+            ))
+            .await?;
+        self.queued.notify_one();
+        Ok(queued)
+    }
+
+    /// Queues a `ping` delivery to `webhook`, whatever events it subscribes to.
+    pub async fn ping(
+        &self,
+        webhook: &mega_webhook::Model,
+    ) -> Result<mega_webhook_delivery::Model, common::errors::MegaError> {
+        let path = webhook.path_filter.as_deref().unwrap_or("/");
+        let body = WebhookEventPayload::Ping(PingPayload {
+            webhook_id: webhook.id,
+        });
+        let payload_json =
+            serde_json::to_string(&build_payload(&WebhookEventTypeEnum::Ping, body, path))?;
+        let queued = self
+            .storage
+            .save_delivery(queued_delivery(
+                webhook.id,
+                WebhookEventTypeEnum::Ping,
+                payload_json,
+                None,
+            ))
+            .await?;
+        self.queued.notify_one();
+        Ok(queued)
+    }
+
+    /// Attempts up to `limit` due deliveries and returns how many were attempted.
+    pub async fn process_due_deliveries(
+        &self,
+        limit: u64,
+    ) -> Result<usize, common::errors::MegaError> {
+        let mut attempted = 0;
+        for delivery in self.storage.fetch_due_deliveries(limit).await? {
+            let lease_until =
+                Utc::now().naive_utc() + chrono::Duration::seconds(WEBHOOK_DELIVERY_LEASE_SECS);
+            let delivery_id = delivery.id;
+            match self
+                .storage
+                .try_claim_delivery(&delivery, lease_until)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::error!("failed to claim webhook delivery {delivery_id}: {e}");
+                    continue;
                 }
             }
+            // The lease expires if the attempt cannot be recorded, so it is retried later.
+            if let Err(e) = self.attempt_delivery(delivery).await {
+                tracing::error!("webhook delivery {delivery_id} failed: {e}");
+            }
+            attempted += 1;
+        }
+        Ok(attempted)
+    }
+
+    async fn attempt_delivery(
+        &self,
+        mut delivery: mega_webhook_delivery::Model,
+    ) -> Result<(), common::errors::MegaError> {
+        let webhook = match self.storage.get_webhook(delivery.webhook_id).await? {
+            Some(webhook)
+                if webhook.active || delivery.event_type == WebhookEventTypeEnum::Ping =>
+            {
+                webhook
+            }
+            _ => {
+                delivery.status = DELIVERY_STATUS_FAILED.to_string();
+                delivery.next_retry_at = None;
+                delivery.error_message = Some("webhook is inactive or was deleted".to_string());
+                self.storage.update_delivery(delivery).await?;
+                return Ok(());
+            }
+        };
 
-            if !delivered_successfully && let Some(error_message) = last_failure_message {
-                tracing::warn!(
-                    "webhook delivery failed after {} attempts for webhook_id={}: {}",
-                    WEBHOOK_DELIVERY_MAX_ATTEMPTS,
-                    webhook.id,
-                    error_message
-                );
+        let now = Utc::now().naive_utc();
+        delivery.attempt += 1;
+        delivery.last_attempt_at = Some(now);
+        match self
+            .deliver(
+                &webhook.target_url,
+                &webhook.secret,
+                &delivery.event_type.to_value(),
+                &delivery.payload,
+            )
+            .await
+        {
+            Ok((status, body)) => {
+                delivery.success = (200..300).contains(&status);
+                delivery.response_status = Some(status as i32);
+                delivery.response_body = Some(truncate_response_body(body));
+                delivery.error_message =
+                    (!delivery.success).then(|| format!("webhook endpoint returned HTTP {status}"));
+            }
+            Err(e) => {
+                delivery.success = false;
+                delivery.response_status = None;
+                delivery.response_body = None;
+                delivery.error_message = Some(e.to_string());
             }
         }
 
+        if delivery.success {
+            delivery.status = DELIVERY_STATUS_DELIVERED.to_string();
+            delivery.next_retry_at = None;
+        } else if delivery.attempt >= WEBHOOK_DELIVERY_MAX_ATTEMPTS {
+            tracing::warn!(
+                "webhook delivery {} failed after {} attempts for webhook_id={}: {}",
+                delivery.id,
+                delivery.attempt,
+                webhook.id,
+                delivery.error_message.as_deref().unwrap_or_default()
+            );
+            delivery.status = DELIVERY_STATUS_FAILED.to_string();
+            delivery.next_retry_at = None;
+        } else {
+            delivery.status = DELIVERY_STATUS_PENDING.to_string();
+            delivery.next_retry_at = Some(now + retry_delay(delivery.attempt));
+        }
+        self.storage.update_delivery(delivery).await?;
         Ok(())
     }
 
@@ -274,11 +475,89 @@ fn extract_repo_name(path: &str) -> &str {
         .unwrap_or("unknown")
 }
 
-fn compute_hmac_signature(secret: &str, payload: &str) -> String {
+fn build_payload(
+    event_type: &WebhookEvent,
+    body: WebhookEventPayload,
+    path: &str,
+) -> WebhookPayload {
+    WebhookPayload {
+        mega_version: env!("CARGO_PKG_VERSION").to_string(),
+        event: event_type.to_value(),
+        timestamp: Utc::now().timestamp(),
+        body,
+        repository: RepositoryPayload {
+            path: path.to_string(),
+            name: extract_repo_name(path).to_string(),
+        },
+    }
+}
+
+/// A delivery that the worker attempts as soon as it polls.
+fn queued_delivery(
+    webhook_id: i64,
+    event_type: WebhookEvent,
+    payload: String,
+    redelivery_of: Option<i64>,
+) -> mega_webhook_delivery::Model {
+    let now = Utc::now().naive_utc();
+    mega_webhook_delivery::Model {
+        id: IdInstance::next_id(),
+        webhook_id,
+        event_type,
+        payload,
+        response_status: None,
+        response_body: None,
+        success: false,
+        attempt: 0,
+        error_message: None,
+        created_at: now,
+        status: DELIVERY_STATUS_PENDING.to_string(),
+        next_retry_at: Some(now),
+        last_attempt_at: None,
+        redelivery_of,
+    }
+}
+
+/// Delay before retrying a delivery whose `attempt`-th attempt failed: the base delay,
+/// doubled for every further attempt and capped at [`WEBHOOK_DELIVERY_MAX_BACKOFF_SECS`].
+fn retry_delay(attempt: i32) -> chrono::Duration {
+    let doublings = attempt.saturating_sub(1).clamp(0, 16) as u32;
+    let secs =
+        (WEBHOOK_DELIVERY_BACKOFF_BASE_SECS << doublings).min(WEBHOOK_DELIVERY_MAX_BACKOFF_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+fn truncate_response_body(mut body: String) -> String {
+    if body.len() > WEBHOOK_RESPONSE_BODY_LIMIT {
+        let mut end = WEBHOOK_RESPONSE_BODY_LIMIT;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+    body
+}
+
+/// Checks an `X-Mega-Signature` style value (`sha256=<hex hmac>`) against `payload`.
+///
+/// The comparison runs in constant time; malformed signatures never match.
+pub fn verify_webhook_signature(secret: &str, payload: &[u8], signature: &str) -> bool {
+    type HmacSha256 = Hmac<Sha256>;
+    let Some(Ok(expected)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(payload);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Hex HMAC-SHA256 of `payload`, as sent in `X-Mega-Signature: sha256=<hex>`.
+pub fn compute_hmac_signature(secret: &str, payload: impl AsRef<[u8]>) -> String {
     type HmacSha256 = Hmac<Sha256>;
     let mut mac =
         HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
-    mac.update(payload.as_bytes());
+    mac.update(payload.as_ref());
     hex::encode(mac.finalize().into_bytes())
 }
 
@@ -529,10 +808,129 @@ fn is_forbidden_ipv6(ip: Ipv6Addr) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use api_model::common::Pagination;
     use callisto::sea_orm_active_enums::MergeStatusEnum;
     use chrono::DateTime;
+    use jupiter_migrate::apply_migrations;
+    use tempfile::TempDir;
 
     use super::*;
+    use crate::{
+        storage::base_storage::{BaseStorage, StorageConnector},
+        tests::test_db_connection,
+    };
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_cap() {
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(2).num_seconds(), 60);
+        assert_eq!(retry_delay(3).num_seconds(), 120);
+        assert_eq!(retry_delay(10).num_seconds(), 3600);
+        assert_eq!(retry_delay(i32::MAX).num_seconds(), 3600);
+    }
+
+    #[test]
+    fn test_truncate_response_body_respects_char_boundaries() {
+        let body = "é".repeat(WEBHOOK_RESPONSE_BODY_LIMIT);
+        let truncated = truncate_response_body(body);
+        assert!(truncated.len() <= WEBHOOK_RESPONSE_BODY_LIMIT);
+        assert!(truncated.chars().all(|c| c == 'é'));
+        assert_eq!(truncate_response_body("ok".to_string()), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_rescheduled_and_can_be_redelivered() {
+        let temp_dir = TempDir::new().unwrap();
+        let conn = test_db_connection(temp_dir.path()).await;
+        apply_migrations(&conn, true).await.unwrap();
+        let storage = WebhookStorage {
+            base: BaseStorage::new(Arc::new(conn)),
+        };
+        let service = WebhookService::mock(storage.clone());
+
+        let now = Utc::now().naive_utc();
+        let webhook = storage
+            .create_webhook(
+                mega_webhook::Model {
+                    id: IdInstance::next_id(),
+                    // Rejected before any request is sent
+                    target_url: "https://127.0.0.1/hook".to_string(),
+                    secret: "s1".to_string(),
+                    event_types: "[]".to_string(),
+                    path_filter: None,
+                    active: true,
+                    created_at: now,
+                    updated_at: now,
+                },
+                vec![WebhookEventTypeEnum::TagCreated],
+            )
+            .await
+            .unwrap()
+            .webhook;
+
+        let tag = WebhookEventPayload::Tag(TagPayload {
+            name: "v1.0".to_string(),
+            target: None,
+            tagger: None,
+            message: None,
+        });
+        // Queued before dispatch_event returns
+        service
+            .dispatch_event(WebhookEventTypeEnum::TagCreated, "/", tag)
+            .await;
+        let page = Pagination {
+            page: 1,
+            per_page: 20,
+        };
+        let (queued, total) = storage
+            .list_deliveries(webhook.id, page.clone())
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(queued[0].status, DELIVERY_STATUS_PENDING);
+        assert_eq!(queued[0].attempt, 0);
+
+        assert_eq!(service.process_due_deliveries(10).await.unwrap(), 1);
+        let attempted = storage.get_delivery(queued[0].id).await.unwrap().unwrap();
+        assert_eq!(attempted.status, DELIVERY_STATUS_PENDING);
+        assert_eq!(attempted.attempt, 1);
+        assert!(!attempted.success);
+        assert!(attempted.error_message.is_some());
+        assert!(attempted.next_retry_at.unwrap() > now + chrono::Duration::seconds(20));
+        // Not due again until the backoff elapses
+        assert_eq!(service.process_due_deliveries(10).await.unwrap(), 0);
+
+        let redelivery = service.redeliver(&attempted).await.unwrap();
+        assert_eq!(redelivery.redelivery_of, Some(attempted.id));
+        assert_eq!(redelivery.payload, attempted.payload);
+        assert_eq!(redelivery.status, DELIVERY_STATUS_PENDING);
+
+        let ping = service.ping(&webhook).await.unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&ping.payload).unwrap();
+        assert_eq!(payload["event"], "ping");
+        assert_eq!(payload["ping"]["webhook_id"], webhook.id);
+
+        let (_, total) = storage.list_deliveries(webhook.id, page).await.unwrap();
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn test_verify_webhook_signature_round_trip() {
+        let signature = format!("sha256={}", compute_hmac_signature("s3cret", "{\"a\":1}"));
+        assert!(verify_webhook_signature("s3cret", b"{\"a\":1}", &signature));
+        assert!(!verify_webhook_signature("other", b"{\"a\":1}", &signature));
+        assert!(!verify_webhook_signature(
+            "s3cret",
+            b"{\"a\":2}",
+            &signature
+        ));
+        assert!(!verify_webhook_signature(
+            "s3cret",
+            b"{\"a\":1}",
+            signature.trim_start_matches("sha256=")
+        ));
+        assert!(!verify_webhook_signature("s3cret", b"", "sha256=zz"));
+    }
 
     #[test]
     fn test_validate_webhook_target_url_accepts_public_https_domain() {
@@ -636,4 +1034,31 @@ mod tests {
         assert_eq!(payload.status, "open");
         assert_eq!(payload.path, "/repo/path");
     }
+
+    #[test]
+    fn test_webhook_payload_nests_event_body_under_its_key() {
+        let payload = WebhookPayload {
+            mega_version: "0.1.0".to_string(),
+            event: "tag.created".to_string(),
+            timestamp: 0,
+            body: WebhookEventPayload::Tag(TagPayload {
+                name: "v1.0".to_string(),
+                target: Some("abc".to_string()),
+                tagger: None,
+                message: None,
+            }),
+            repository: RepositoryPayload {
+                path: "/".to_string(),
+                name: "unknown".to_string(),
+            },
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["event"], "tag.created");
+        assert_eq!(json["tag"]["name"], "v1.0");
+        assert_eq!(json["tag"]["target"], "abc");
+        assert!(json["tag"].get("tagger").is_none());
+        assert!(json.get("cl").is_none());
+        assert_eq!(json["repository"]["path"], "/");
+    }
 }
@@ -1,9 +1,10 @@
 use api_model::common::Pagination;
-use callisto::build_triggers;
+use callisto::{build_schedules, build_triggers, build_webhook_deliveries, build_webhook_sources};
 use common::errors::MegaError;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, EntityTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect,
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseBackend,
+    EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Value,
+    sea_query::{Expr, OnConflict},
 };
 
 use crate::storage::base_storage::{BaseStorage, StorageConnector};
@@ -33,6 +34,7 @@ impl BuildTriggerStorage {
             trigger_time: ActiveValue::Set(now),
             task_id: ActiveValue::Set(task_id),
             updated_at: ActiveValue::Set(now),
+            completion_watch_until: ActiveValue::Set(None),
         };
 
         trigger
@@ -41,6 +43,68 @@ impl BuildTriggerStorage {
             .map_err(MegaError::Db)
     }
 
+    /// Marks the build of a trigger as owing a `build.completed` webhook until `until`.
+    pub async fn watch_completion(
+        &self,
+        id: i64,
+        until: chrono::NaiveDateTime,
+    ) -> Result<(), MegaError> {
+        build_triggers::Entity::update_many()
+            .col_expr(
+                build_triggers::Column::CompletionWatchUntil,
+                Expr::value(Some(until)),
+            )
+            .filter(build_triggers::Column::Id.eq(id))
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(())
+    }
+
+    /// A page of the triggers whose build completion is watched, ordered by id.
+    ///
+    /// Pass the id of the last trigger of the previous page as `after` to read the next one,
+    /// so that builds watched for a long time do not hide the ones behind them.
+    pub async fn list_completion_watches(
+        &self,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<build_triggers::Model>, MegaError> {
+        let mut query = build_triggers::Entity::find()
+            .filter(build_triggers::Column::CompletionWatchUntil.is_not_null());
+        if let Some(after) = after {
+            query = query.filter(build_triggers::Column::Id.gt(after));
+        }
+        query
+            .order_by_asc(build_triggers::Column::Id)
+            .limit(limit)
+            .all(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    /// Stops watching the build of a trigger if it is still watched until `until`.
+    ///
+    /// Returns `false` when another instance ended the watch first, so the completion is
+    /// reported once.
+    pub async fn end_completion_watch(
+        &self,
+        id: i64,
+        until: chrono::NaiveDateTime,
+    ) -> Result<bool, MegaError> {
+        let res = build_triggers::Entity::update_many()
+            .col_expr(
+                build_triggers::Column::CompletionWatchUntil,
+                Expr::value(Option::<chrono::NaiveDateTime>::None),
+            )
+            .filter(build_triggers::Column::Id.eq(id))
+            .filter(build_triggers::Column::CompletionWatchUntil.eq(until))
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(res.rows_affected > 0)
+    }
+
     /// Get trigger by ID
     pub async fn get_by_id(&self, id: i64) -> Result<Option<build_triggers::Model>, MegaError> {
         build_triggers::Entity::find_by_id(id)
@@ -59,6 +123,35 @@ impl BuildTriggerStorage {
             .map_err(MegaError::Db)
     }
 
+    /// Latest trigger of a CL at `commit_hash` that was handed to Orion (has a task id).
+    pub async fn get_latest_task_trigger(
+        &self,
+        cl_link: &str,
+        commit_hash: &str,
+    ) -> Result<Option<build_triggers::Model>, MegaError> {
+        let conn = self.base.get_connection();
+        // Both backends support `->>`; only the placeholder syntax differs.
+        let payload_filter = match conn.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                "(trigger_payload ->> 'cl_link') = $1 AND (trigger_payload ->> 'commit_hash') = $2"
+            }
+            _ => "(trigger_payload ->> 'cl_link') = ? AND (trigger_payload ->> 'commit_hash') = ?",
+        };
+        build_triggers::Entity::find()
+            .filter(build_triggers::Column::TaskId.is_not_null())
+            .filter(Expr::cust_with_values(
+                payload_filter,
+                [
+                    Value::String(Some(Box::new(cl_link.to_owned()))),
+                    Value::String(Some(Box::new(commit_hash.to_owned()))),
+                ],
+            ))
+            .order_by_desc(build_triggers::Column::TriggerTime)
+            .one(conn)
+            .await
+            .map_err(MegaError::Db)
+    }
+
     /// Get triggers with pagination and filters (project standard pattern)
     pub async fn get_trigger_list(
         &self,
@@ -78,6 +171,18 @@ impl BuildTriggerStorage {
             condition = condition.add(build_triggers::Column::TriggerSource.eq(trigger_source));
         }
 
+        // Filter by the schedule that fired the trigger
+        if let Some(schedule_id) = filter.schedule_id {
+            let payload_filter = match self.base.get_connection().get_database_backend() {
+                DatabaseBackend::Postgres => "(trigger_payload ->> 'schedule_id') = $1",
+                _ => "CAST(trigger_payload ->> 'schedule_id' AS TEXT) = ?",
+            };
+            condition = condition.add(Expr::cust_with_values(
+                payload_filter,
+                [Value::String(Some(Box::new(schedule_id.to_string())))],
+            ));
+        }
+
         // Filter by time range
         if let Some(start_time) = filter.start_time {
             condition =
@@ -136,6 +241,215 @@ impl BuildTriggerStorage {
 
         Ok((items, total))
     }
+
+    pub async fn insert_schedule(
+        &self,
+        schedule: build_schedules::Model,
+    ) -> Result<build_schedules::Model, MegaError> {
+        schedule
+            .into_active_model()
+            .insert(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn get_schedule(&self, id: i64) -> Result<Option<build_schedules::Model>, MegaError> {
+        build_schedules::Entity::find_by_id(id)
+            .one(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn get_schedule_by_name(
+        &self,
+        repo_path: &str,
+        name: &str,
+    ) -> Result<Option<build_schedules::Model>, MegaError> {
+        build_schedules::Entity::find()
+            .filter(build_schedules::Column::RepoPath.eq(repo_path))
+            .filter(build_schedules::Column::Name.eq(name))
+            .one(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    /// Schedules ordered by path and name, optionally limited to one path.
+    pub async fn list_schedules(
+        &self,
+        repo_path: Option<&str>,
+    ) -> Result<Vec<build_schedules::Model>, MegaError> {
+        let mut query = build_schedules::Entity::find();
+        if let Some(repo_path) = repo_path {
+            query = query.filter(build_schedules::Column::RepoPath.eq(repo_path));
+        }
+        query
+            .order_by_asc(build_schedules::Column::RepoPath)
+            .order_by_asc(build_schedules::Column::Name)
+            .all(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    /// Overwrites every column of the schedule with `schedule`.
+    pub async fn update_schedule(
+        &self,
+        schedule: build_schedules::Model,
+    ) -> Result<build_schedules::Model, MegaError> {
+        schedule
+            .into_active_model()
+            .reset_all()
+            .update(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn delete_schedule(&self, id: i64) -> Result<bool, MegaError> {
+        let res = build_schedules::Entity::delete_by_id(id)
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Enabled schedules whose next run is at or before `now`.
+    pub async fn get_due_schedules(
+        &self,
+        now: chrono::NaiveDateTime,
+    ) -> Result<Vec<build_schedules::Model>, MegaError> {
+        build_schedules::Entity::find()
+            .filter(build_schedules::Column::Enabled.eq(true))
+            .filter(build_schedules::Column::NextRunAt.lte(now))
+            .order_by_asc(build_schedules::Column::NextRunAt)
+            .all(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    /// Moves a due schedule to `next_run_at` if its next run is still `due_at`.
+    ///
+    /// Returns `false` when another scheduler instance claimed the run first, so each due
+    /// run is dispatched once.
+    pub async fn claim_schedule_run(
+        &self,
+        id: i64,
+        due_at: chrono::NaiveDateTime,
+        next_run_at: Option<chrono::NaiveDateTime>,
+        now: chrono::NaiveDateTime,
+    ) -> Result<bool, MegaError> {
+        let res = build_schedules::Entity::update_many()
+            .col_expr(build_schedules::Column::NextRunAt, Expr::value(next_run_at))
+            .col_expr(build_schedules::Column::UpdatedAt, Expr::value(now))
+            .filter(build_schedules::Column::Id.eq(id))
+            .filter(build_schedules::Column::NextRunAt.eq(due_at))
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn insert_webhook_source(
+        &self,
+        source: build_webhook_sources::Model,
+    ) -> Result<build_webhook_sources::Model, MegaError> {
+        source
+            .into_active_model()
+            .insert(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn get_webhook_source_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<build_webhook_sources::Model>, MegaError> {
+        build_webhook_sources::Entity::find()
+            .filter(build_webhook_sources::Column::Name.eq(name))
+            .one(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn list_webhook_sources(
+        &self,
+    ) -> Result<Vec<build_webhook_sources::Model>, MegaError> {
+        build_webhook_sources::Entity::find()
+            .order_by_asc(build_webhook_sources::Column::Name)
+            .all(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    /// Deletes a webhook source together with its delivery log.
+    pub async fn delete_webhook_source(&self, id: i64) -> Result<bool, MegaError> {
+        let conn = self.base.get_connection();
+        build_webhook_deliveries::Entity::delete_many()
+            .filter(build_webhook_deliveries::Column::SourceId.eq(id))
+            .exec(conn)
+            .await
+            .map_err(MegaError::Db)?;
+        let res = build_webhook_sources::Entity::delete_by_id(id)
+            .exec(conn)
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Records a delivery unless one with the same source and delivery id exists.
+    ///
+    /// Returns the already recorded delivery when `delivery` is a duplicate.
+    pub async fn claim_webhook_delivery(
+        &self,
+        delivery: build_webhook_deliveries::Model,
+    ) -> Result<Option<build_webhook_deliveries::Model>, MegaError> {
+        let conn = self.base.get_connection();
+        let (source_id, delivery_id) = (delivery.source_id, delivery.delivery_id.clone());
+        let inserted = build_webhook_deliveries::Entity::insert(delivery.into_active_model())
+            .on_conflict(
+                OnConflict::columns([
+                    build_webhook_deliveries::Column::SourceId,
+                    build_webhook_deliveries::Column::DeliveryId,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(conn)
+            .await
+            .map_err(MegaError::Db)?;
+        if inserted > 0 {
+            return Ok(None);
+        }
+        build_webhook_deliveries::Entity::find()
+            .filter(build_webhook_deliveries::Column::SourceId.eq(source_id))
+            .filter(build_webhook_deliveries::Column::DeliveryId.eq(delivery_id))
+            .one(conn)
+            .await
+            .map_err(MegaError::Db)
+    }
+
+    pub async fn set_webhook_delivery_trigger(
+        &self,
+        id: i64,
+        trigger_id: i64,
+    ) -> Result<(), MegaError> {
+        build_webhook_deliveries::Entity::update_many()
+            .col_expr(
+                build_webhook_deliveries::Column::TriggerId,
+                Expr::value(trigger_id),
+            )
+            .filter(build_webhook_deliveries::Column::Id.eq(id))
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(())
+    }
+
+    pub async fn delete_webhook_delivery(&self, id: i64) -> Result<(), MegaError> {
+        build_webhook_deliveries::Entity::delete_by_id(id)
+            .exec(self.base.get_connection())
+            .await
+            .map_err(MegaError::Db)?;
+        Ok(())
+    }
 }
 
 /// Filter parameters for listing triggers
@@ -145,6 +459,7 @@ pub struct ListTriggersFilter {
     pub trigger_type: Option<String>,
     pub trigger_source: Option<String>,
     pub triggered_by: Option<String>,
+    pub schedule_id: Option<i64>,
     pub start_time: Option<chrono::DateTime<chrono::Utc>>,
     pub end_time: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -223,6 +538,166 @@ mod tests {
         assert_eq!(retrieved.task_id, Some(task_id));
     }
 
+    #[tokio::test]
+    async fn test_get_latest_task_trigger() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let trigger_storage = storage.build_trigger_storage();
+
+        let payload = |commit: &str| {
+            serde_json::json!({
+                "type": "git_push",
+                "repo": "/project",
+                "commit_hash": commit,
+                "cl_link": "cl_link",
+                "builds": []
+            })
+        };
+        let task_id = uuid::Uuid::new_v4();
+        trigger_storage
+            .insert("git_push".into(), "user".into(), payload("abc"), None)
+            .await
+            .unwrap();
+        trigger_storage
+            .insert(
+                "git_push".into(),
+                "user".into(),
+                payload("abc"),
+                Some(task_id),
+            )
+            .await
+            .unwrap();
+        trigger_storage
+            .insert(
+                "git_push".into(),
+                "user".into(),
+                payload("def"),
+                Some(uuid::Uuid::new_v4()),
+            )
+            .await
+            .unwrap();
+
+        let found = trigger_storage
+            .get_latest_task_trigger("cl_link", "abc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.task_id, Some(task_id));
+        assert!(
+            trigger_storage
+                .get_latest_task_trigger("other_link", "abc")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schedule_run_is_claimed_once() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let trigger_storage = storage.build_trigger_storage();
+
+        let now = chrono::Utc::now().naive_utc();
+        let due_at = now - chrono::Duration::minutes(1);
+        let next_run_at = now + chrono::Duration::hours(1);
+        let schedule = trigger_storage
+            .insert_schedule(build_schedules::Model {
+                id: 1,
+                repo_path: "/project".to_string(),
+                name: "nightly".to_string(),
+                cron_expression: "0 2 * * *".to_string(),
+                enabled: true,
+                next_run_at: Some(due_at),
+                last_run_at: None,
+                last_commit_hash: None,
+                last_trigger_id: None,
+                created_by: "admin".to_string(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+
+        // Both scheduler instances saw the schedule as due at the stored time.
+        let due_at = trigger_storage
+            .get_schedule(schedule.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .next_run_at
+            .unwrap();
+        assert!(
+            trigger_storage
+                .claim_schedule_run(schedule.id, due_at, Some(next_run_at), now)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !trigger_storage
+                .claim_schedule_run(schedule.id, due_at, Some(next_run_at), now)
+                .await
+                .unwrap()
+        );
+        let claimed = trigger_storage
+            .get_schedule(schedule.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.next_run_at, Some(next_run_at));
+        assert!(
+            trigger_storage
+                .get_due_schedules(now)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_completion_watches_are_paged_by_id() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let trigger_storage = storage.build_trigger_storage();
+        let until = chrono::Utc::now().naive_utc() + chrono::Duration::hours(1);
+
+        let mut watched = vec![];
+        for i in 0..5 {
+            let trigger = trigger_storage
+                .insert(
+                    "manual".to_string(),
+                    "user".to_string(),
+                    serde_json::json!({}),
+                    None,
+                )
+                .await
+                .unwrap();
+            if i != 2 {
+                trigger_storage
+                    .watch_completion(trigger.id, until)
+                    .await
+                    .unwrap();
+                watched.push(trigger.id);
+            }
+        }
+        watched.sort();
+
+        let mut seen = vec![];
+        let mut after = None;
+        loop {
+            let page = trigger_storage
+                .list_completion_watches(after, 3)
+                .await
+                .unwrap();
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = Some(last.id);
+            seen.extend(page.iter().map(|t| t.id));
+        }
+        assert_eq!(seen, watched);
+    }
+
     #[tokio::test]
     async fn test_get_recent() {
         let temp_dir = tempdir().unwrap();
@@ -322,4 +797,88 @@ mod tests {
         assert_eq!(items.len(), 1);
         assert_eq!(total, 5);
     }
+
+    #[tokio::test]
+    async fn test_due_schedules_and_schedule_history() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let trigger_storage = storage.build_trigger_storage();
+
+        let now = chrono::Utc::now().naive_utc();
+        let schedule =
+            |id: i64, name: &str, enabled: bool, offset_mins: i64| build_schedules::Model {
+                id,
+                repo_path: "/project".to_string(),
+                name: name.to_string(),
+                cron_expression: "0 2 * * *".to_string(),
+                enabled,
+                created_by: "user".to_string(),
+                next_run_at: Some(now + chrono::Duration::minutes(offset_mins)),
+                last_run_at: None,
+                last_commit_hash: None,
+                last_trigger_id: None,
+                created_at: now,
+                updated_at: now,
+            };
+        trigger_storage
+            .insert_schedule(schedule(1, "nightly", true, -1))
+            .await
+            .unwrap();
+        trigger_storage
+            .insert_schedule(schedule(2, "later", true, 60))
+            .await
+            .unwrap();
+        trigger_storage
+            .insert_schedule(schedule(3, "paused", false, -1))
+            .await
+            .unwrap();
+
+        let due = trigger_storage.get_due_schedules(now).await.unwrap();
+        assert_eq!(due.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1]);
+        assert!(
+            trigger_storage
+                .get_schedule_by_name("/project", "later")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let payload = |schedule_id: i64| {
+            serde_json::json!({
+                "type": "schedule",
+                "repo": "/project",
+                "commit_hash": "abc",
+                "cl_link": "schedule-link",
+                "builds": [],
+                "schedule_name": "nightly",
+                "cron_expression": "0 2 * * *",
+                "schedule_id": schedule_id
+            })
+        };
+        for schedule_id in [1, 1, 2] {
+            trigger_storage
+                .insert(
+                    "schedule".into(),
+                    "system".into(),
+                    payload(schedule_id),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let filter = ListTriggersFilter {
+            schedule_id: Some(1),
+            ..Default::default()
+        };
+        let (items, total) = trigger_storage
+            .get_trigger_list(filter, Pagination::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(items.len(), 2);
+
+        assert!(trigger_storage.delete_schedule(1).await.unwrap());
+        assert!(!trigger_storage.delete_schedule(1).await.unwrap());
+    }
 }
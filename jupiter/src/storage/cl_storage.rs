@@ -1,8 +1,12 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Deref,
+};
 
 use api_model::common::Pagination;
 use callisto::{
-    check_result, item_assignees, label, mega_cl, mega_conversation, path_check_configs,
+    check_result, check_runs, item_assignees, label, mega_cl, mega_conversation,
+    path_check_configs, path_check_run_configs, path_protection_rules,
     sea_orm_active_enums::MergeStatusEnum,
 };
 use common::errors::MegaError;
@@ -394,6 +398,180 @@ impl ClStorage {
         Ok(models)
     }
 
+    /// Returns the protection rule of the closest configured ancestor of `path`, if any.
+    pub async fn get_protection_rule_by_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<path_protection_rules::Model>, MegaError> {
+        let rules = path_protection_rules::Entity::find()
+            .all(self.get_connection())
+            .await?;
+        Ok(rules
+            .into_iter()
+            .filter(|rule| path_is_under(path, &rule.path))
+            .max_by_key(|rule| rule.path.trim_end_matches('/').len()))
+    }
+
+    /// Returns the protection rule configured exactly on `path`.
+    pub async fn get_protection_rule(
+        &self,
+        path: &str,
+    ) -> Result<Option<path_protection_rules::Model>, MegaError> {
+        Ok(path_protection_rules::Entity::find()
+            .filter(path_protection_rules::Column::Path.eq(path))
+            .one(self.get_connection())
+            .await?)
+    }
+
+    /// Lists the protection rules configured on `path` or below it, ordered by path.
+    pub async fn list_protection_rules(
+        &self,
+        path: &str,
+    ) -> Result<Vec<path_protection_rules::Model>, MegaError> {
+        let rules = path_protection_rules::Entity::find()
+            .order_by_asc(path_protection_rules::Column::Path)
+            .all(self.get_connection())
+            .await?;
+        Ok(rules
+            .into_iter()
+            .filter(|rule| path_is_under(&rule.path, path))
+            .collect())
+    }
+
+    /// Inserts the rule, or replaces the existing rule of the same path.
+    pub async fn save_protection_rule(
+        &self,
+        rule: path_protection_rules::Model,
+    ) -> Result<(), MegaError> {
+        let existing = path_protection_rules::Entity::find()
+            .filter(path_protection_rules::Column::Path.eq(&rule.path))
+            .one(self.get_connection())
+            .await?;
+        match existing {
+            Some(existing) => {
+                let mut a_model = existing.into_active_model();
+                a_model.allowed_merge_strategies = Set(rule.allowed_merge_strategies);
+                a_model.required_approvals = Set(rule.required_approvals);
+                a_model.forbid_self_approval = Set(rule.forbid_self_approval);
+                a_model.require_linear_history = Set(rule.require_linear_history);
+                a_model.block_direct_push = Set(rule.block_direct_push);
+                a_model.updated_at = Set(chrono::Utc::now().naive_utc());
+                a_model.update(self.get_connection()).await?;
+            }
+            None => {
+                rule.into_active_model()
+                    .insert(self.get_connection())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts the check run, or updates the run with the same name on the same CL commit.
+    /// Only the bot that created a run may update it.
+    pub async fn save_check_run(
+        &self,
+        run: check_runs::Model,
+    ) -> Result<check_runs::Model, MegaError> {
+        let existing = check_runs::Entity::find()
+            .filter(check_runs::Column::ClLink.eq(&run.cl_link))
+            .filter(check_runs::Column::CommitId.eq(&run.commit_id))
+            .filter(check_runs::Column::Name.eq(&run.name))
+            .one(self.get_connection())
+            .await?;
+        let model = match existing {
+            Some(existing) if existing.bot_id != run.bot_id => {
+                return Err(MegaError::forbidden(format!(
+                    "Check run '{}' on {} was created by another bot",
+                    run.name, run.commit_id
+                )));
+            }
+            Some(existing) => {
+                let mut a_model = existing.into_active_model();
+                a_model.status = Set(run.status);
+                a_model.summary = Set(run.summary);
+                a_model.annotations = Set(run.annotations);
+                a_model.updated_at = Set(chrono::Utc::now().naive_utc());
+                a_model.update(self.get_connection()).await?
+            }
+            None => {
+                run.into_active_model()
+                    .insert(self.get_connection())
+                    .await?
+            }
+        };
+        Ok(model)
+    }
+
+    pub async fn get_check_run(&self, id: i64) -> Result<Option<check_runs::Model>, MegaError> {
+        let model = check_runs::Entity::find_by_id(id)
+            .one(self.get_connection())
+            .await?;
+        Ok(model)
+    }
+
+    pub async fn list_check_runs(
+        &self,
+        cl_link: &str,
+        commit_id: &str,
+    ) -> Result<Vec<check_runs::Model>, MegaError> {
+        let models = check_runs::Entity::find()
+            .filter(check_runs::Column::ClLink.eq(cl_link))
+            .filter(check_runs::Column::CommitId.eq(commit_id))
+            .order_by_asc(check_runs::Column::Name)
+            .all(self.get_connection())
+            .await?;
+        Ok(models)
+    }
+
+    /// Names of the check runs required to merge under `path`. For each name the config of the
+    /// closest ancestor wins, so a subdirectory can relax a check required by its parent.
+    pub async fn get_required_check_runs(&self, path: &str) -> Result<Vec<String>, MegaError> {
+        let mut configs: Vec<path_check_run_configs::Model> =
+            path_check_run_configs::Entity::find()
+                .all(self.get_connection())
+                .await?
+                .into_iter()
+                .filter(|config| path_is_under(path, &config.path))
+                .collect();
+        configs.sort_by_key(|config| config.path.trim_end_matches('/').len());
+        let mut required: BTreeMap<String, bool> = BTreeMap::new();
+        for config in configs {
+            required.insert(config.name, config.required);
+        }
+        Ok(required
+            .into_iter()
+            .filter_map(|(name, required)| required.then_some(name))
+            .collect())
+    }
+
+    /// Inserts the config, or replaces the existing config of the same path and name.
+    pub async fn save_check_run_config(
+        &self,
+        config: path_check_run_configs::Model,
+    ) -> Result<(), MegaError> {
+        let existing = path_check_run_configs::Entity::find()
+            .filter(path_check_run_configs::Column::Path.eq(&config.path))
+            .filter(path_check_run_configs::Column::Name.eq(&config.name))
+            .one(self.get_connection())
+            .await?;
+        match existing {
+            Some(existing) => {
+                let mut a_model = existing.into_active_model();
+                a_model.required = Set(config.required);
+                a_model.updated_at = Set(chrono::Utc::now().naive_utc());
+                a_model.update(self.get_connection()).await?;
+            }
+            None => {
+                config
+                    .into_active_model()
+                    .insert(self.get_connection())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn save_check_results(
         &self,
         models: Vec<check_result::Model>,
@@ -430,15 +608,29 @@ impl ClStorage {
         Ok(models)
     }
 
+    /// Appends `commits`, oldest first, after the commits already recorded for the CL.
     pub async fn save_cl_commits(&self, link: &str, commits: Vec<Commit>) -> Result<(), MegaError> {
+        let last_position: Option<i32> = callisto::mega_cl_commits::Entity::find()
+            .select_only()
+            .column_as(
+                callisto::mega_cl_commits::Column::Position.max(),
+                "position",
+            )
+            .filter(callisto::mega_cl_commits::Column::ClLink.eq(link))
+            .into_tuple()
+            .one(self.get_connection())
+            .await?
+            .flatten();
+        let first_position = last_position.map_or(0, |p| p + 1);
         let mut save_models = vec![];
-        for commit in commits {
+        for (position, commit) in (first_position..).zip(commits) {
             let model = callisto::mega_cl_commits::ActiveModel {
                 cl_link: Set(link.to_string()),
                 commit_sha: Set(commit.id.to_string()),
                 author_name: Set(commit.author.name.clone()),
                 author_email: Set(commit.author.email.clone()),
                 message: Set(commit.format_message()),
+                position: Set(position),
                 created_at: Set(chrono::Utc::now().naive_utc()),
                 updated_at: Set(chrono::Utc::now().naive_utc()),
             };
@@ -447,4 +639,210 @@ impl ClStorage {
         self.batch_save_model(save_models).await?;
         Ok(())
     }
+
+    /// Commits of the CL in the order they were pushed.
+    pub async fn get_cl_commits(
+        &self,
+        link: &str,
+    ) -> Result<Vec<callisto::mega_cl_commits::Model>, MegaError> {
+        let models = callisto::mega_cl_commits::Entity::find()
+            .filter(callisto::mega_cl_commits::Column::ClLink.eq(link))
+            .order_by_asc(callisto::mega_cl_commits::Column::Position)
+            .order_by_asc(callisto::mega_cl_commits::Column::CreatedAt)
+            .all(self.get_connection())
+            .await?;
+        Ok(models)
+    }
+}
+
+/// Whether `path` is `base` itself or lies below it, comparing whole path components.
+fn path_is_under(path: &str, base: &str) -> bool {
+    let base = base.trim_end_matches('/');
+    let path = path.trim_end_matches('/');
+    base.is_empty()
+        || path == base
+        || path
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::tests::test_storage;
+
+    fn rule(path: &str, required_approvals: i32) -> path_protection_rules::Model {
+        let now = chrono::Utc::now().naive_utc();
+        path_protection_rules::Model {
+            id: callisto::entity_ext::generate_id(),
+            path: path.to_string(),
+            allowed_merge_strategies: "[]".to_string(),
+            required_approvals,
+            forbid_self_approval: false,
+            require_linear_history: false,
+            block_direct_push: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protection_rule_uses_closest_ancestor() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let cl_storage = storage.cl_storage();
+        cl_storage.save_protection_rule(rule("/", 1)).await.unwrap();
+        cl_storage
+            .save_protection_rule(rule("/project", 2))
+            .await
+            .unwrap();
+        // saving the same path again replaces the rule
+        cl_storage
+            .save_protection_rule(rule("/project", 3))
+            .await
+            .unwrap();
+
+        let found = |path: &'static str| {
+            let cl_storage = cl_storage.clone();
+            async move {
+                cl_storage
+                    .get_protection_rule_by_path(path)
+                    .await
+                    .unwrap()
+                    .map(|r| r.required_approvals)
+            }
+        };
+        assert_eq!(found("/project/mega").await, Some(3));
+        assert_eq!(found("/project").await, Some(3));
+        assert_eq!(found("/projects").await, Some(1));
+        assert_eq!(found("/").await, Some(1));
+
+        let listed = |path: &'static str| {
+            let cl_storage = cl_storage.clone();
+            async move {
+                cl_storage
+                    .list_protection_rules(path)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| r.path)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(listed("/").await, ["/", "/project"]);
+        assert_eq!(listed("/project").await, ["/project"]);
+        assert!(listed("/projects").await.is_empty());
+    }
+
+    fn check_run_config(path: &str, name: &str, required: bool) -> path_check_run_configs::Model {
+        let now = chrono::Utc::now().naive_utc();
+        path_check_run_configs::Model {
+            id: callisto::entity_ext::generate_id(),
+            path: path.to_string(),
+            name: name.to_string(),
+            required,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_required_check_runs_follow_closest_config() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let cl_storage = storage.cl_storage();
+        for config in [
+            check_run_config("/", "lint", true),
+            check_run_config("/project", "e2e", true),
+            check_run_config("/project/docs", "e2e", false),
+        ] {
+            cl_storage.save_check_run_config(config).await.unwrap();
+        }
+
+        let required = |path: &'static str| {
+            let cl_storage = cl_storage.clone();
+            async move { cl_storage.get_required_check_runs(path).await.unwrap() }
+        };
+        assert_eq!(required("/project/mega").await, vec!["e2e", "lint"]);
+        assert_eq!(required("/project/docs/guide").await, vec!["lint"]);
+        assert_eq!(required("/other").await, vec!["lint"]);
+    }
+
+    #[tokio::test]
+    async fn test_check_run_is_only_updated_by_its_creator() {
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let cl_storage = storage.cl_storage();
+        let check_run = |bot_id: i64, status: &str| {
+            let now = chrono::Utc::now().naive_utc();
+            check_runs::Model {
+                id: callisto::entity_ext::generate_id(),
+                cl_link: "CL1".to_string(),
+                commit_id: "abc".to_string(),
+                name: "ci".to_string(),
+                bot_id,
+                status: status.to_string(),
+                summary: String::new(),
+                annotations: "[]".to_string(),
+                created_at: now,
+                updated_at: now,
+            }
+        };
+
+        let created = cl_storage
+            .save_check_run(check_run(1, "PENDING"))
+            .await
+            .unwrap();
+        let err = cl_storage
+            .save_check_run(check_run(2, "PASSED"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MegaError::Forbidden(_)), "{err:?}");
+
+        let updated = cl_storage
+            .save_check_run(check_run(1, "FAILED"))
+            .await
+            .unwrap();
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.status, "FAILED");
+        let runs = cl_storage.list_check_runs("CL1", "abc").await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].bot_id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cl_commits_keep_push_order() {
+        use std::str::FromStr;
+
+        use git_internal::hash::ObjectHash;
+
+        let temp_dir = tempdir().unwrap();
+        let storage = test_storage(temp_dir.path()).await;
+        let cl_storage = storage.cl_storage();
+        let tree = ObjectHash::from_str(&"1".repeat(40)).unwrap();
+        let commits: Vec<Commit> = ["c1", "c2", "c3"]
+            .into_iter()
+            .map(|message| Commit::from_tree_id(tree, vec![], message))
+            .collect();
+
+        cl_storage
+            .save_cl_commits("CL1", commits[..2].to_vec())
+            .await
+            .unwrap();
+        cl_storage
+            .save_cl_commits("CL1", commits[2..].to_vec())
+            .await
+            .unwrap();
+        let saved: Vec<String> = cl_storage
+            .get_cl_commits("CL1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.commit_sha)
+            .collect();
+        let pushed: Vec<String> = commits.iter().map(|c| c.id.to_string()).collect();
+        assert_eq!(saved, pushed);
+    }
 }
@@ -109,6 +109,14 @@ impl GitDbStorage {
         Ok(result)
     }
 
+    /// Refs of every imported repository.
+    pub async fn list_all_refs(&self) -> Result<Vec<import_refs::Model>, MegaError> {
+        Ok(import_refs::Entity::find()
+            .order_by_asc(import_refs::Column::RepoId)
+            .all(self.get_connection())
+            .await?)
+    }
+
     pub async fn update_ref(
         &self,
         repo_id: i64,
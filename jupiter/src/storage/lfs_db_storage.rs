@@ -1,8 +1,13 @@
 use std::ops::Deref;
 
-use callisto::{lfs_locks, lfs_objects};
+use callisto::{lfs_locks, lfs_objects, lfs_tus_uploads};
 use common::errors::MegaError;
-use sea_orm::{EntityTrait, InsertResult, IntoActiveModel, Set};
+use sea_orm::{
+    ColumnTrait, Condition, DbErr, EntityTrait, InsertResult, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+    prelude::DateTime,
+    sea_query::{Alias, Expr, OnConflict},
+};
 
 use crate::storage::base_storage::{BaseStorage, StorageConnector};
 
@@ -34,6 +39,16 @@ impl LfsDbStorage {
         Ok(result)
     }
 
+    /// Marks an object as seen by a batch request, restarting its GC grace window.
+    pub async fn touch_lfs_object(&self, oid: &str, now: DateTime) -> Result<(), MegaError> {
+        lfs_objects::Entity::update_many()
+            .col_expr(lfs_objects::Column::LastSeenAt, Expr::value(now))
+            .filter(lfs_objects::Column::Oid.eq(oid))
+            .exec(self.get_connection())
+            .await?;
+        Ok(())
+    }
+
     pub async fn delete_lfs_object(&self, oid: String) -> Result<(), MegaError> {
         lfs_objects::Entity::delete_by_id(oid)
             .exec(self.get_connection())
@@ -42,6 +57,119 @@ impl LfsDbStorage {
         Ok(())
     }
 
+    /// Total size of the LFS objects attributed to a monorepo root directory.
+    pub async fn lfs_usage_by_root_dir(&self, root_dir: &str) -> Result<i64, MegaError> {
+        let total: Option<Option<i64>> = lfs_objects::Entity::find()
+            .select_only()
+            .column_as(
+                Expr::col(lfs_objects::Column::Size)
+                    .sum()
+                    .cast_as(Alias::new("BIGINT")),
+                "total",
+            )
+            .filter(lfs_objects::Column::RootDir.eq(root_dir))
+            .into_tuple()
+            .one(self.get_connection())
+            .await?;
+        Ok(total.flatten().unwrap_or(0))
+    }
+
+    /// LFS objects last requested before `cutoff` (or never stamped), ordered by oid and
+    /// starting after `after_oid`.
+    pub async fn list_lfs_gc_candidates(
+        &self,
+        cutoff: DateTime,
+        after_oid: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<lfs_objects::Model>, MegaError> {
+        let mut query = lfs_objects::Entity::find().filter(
+            Condition::any()
+                .add(lfs_objects::Column::LastSeenAt.is_null())
+                .add(lfs_objects::Column::LastSeenAt.lt(cutoff)),
+        );
+        if let Some(after) = after_oid {
+            query = query.filter(lfs_objects::Column::Oid.gt(after));
+        }
+        Ok(query
+            .order_by_asc(lfs_objects::Column::Oid)
+            .limit(limit)
+            .all(self.get_connection())
+            .await?)
+    }
+
+    pub async fn get_tus_upload(
+        &self,
+        oid: &str,
+    ) -> Result<Option<lfs_tus_uploads::Model>, MegaError> {
+        Ok(lfs_tus_uploads::Entity::find_by_id(oid)
+            .one(self.get_connection())
+            .await?)
+    }
+
+    /// Starts tracking an upload; `false` if another request already started one for the oid.
+    pub async fn save_tus_upload(&self, upload: lfs_tus_uploads::Model) -> Result<bool, MegaError> {
+        let res = lfs_tus_uploads::Entity::insert(upload.into_active_model())
+            .on_conflict(
+                OnConflict::column(lfs_tus_uploads::Column::Oid)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec(self.get_connection())
+            .await;
+        match res {
+            Ok(_) => Ok(true),
+            Err(DbErr::RecordNotInserted) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Records the bytes an upload has persisted so far and the parts written for it, provided
+    /// it is still at `expected_offset`. Returns `false` when another request moved it first.
+    pub async fn update_tus_upload_progress(
+        &self,
+        oid: &str,
+        expected_offset: i64,
+        upload_offset: i64,
+        parts: serde_json::Value,
+    ) -> Result<bool, MegaError> {
+        let res = lfs_tus_uploads::Entity::update_many()
+            .col_expr(
+                lfs_tus_uploads::Column::UploadOffset,
+                Expr::value(upload_offset),
+            )
+            .col_expr(lfs_tus_uploads::Column::Parts, Expr::value(parts))
+            .col_expr(
+                lfs_tus_uploads::Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(lfs_tus_uploads::Column::Oid.eq(oid))
+            .filter(lfs_tus_uploads::Column::UploadOffset.eq(expected_offset))
+            .exec(self.get_connection())
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn delete_tus_upload(&self, oid: &str) -> Result<(), MegaError> {
+        lfs_tus_uploads::Entity::delete_by_id(oid)
+            .exec(self.get_connection())
+            .await?;
+        Ok(())
+    }
+
+    /// Uploads that have not received data since `cutoff`.
+    pub async fn list_stale_tus_uploads(
+        &self,
+        cutoff: DateTime,
+        limit: u64,
+    ) -> Result<Vec<lfs_tus_uploads::Model>, MegaError> {
+        Ok(lfs_tus_uploads::Entity::find()
+            .filter(lfs_tus_uploads::Column::UpdatedAt.lt(cutoff))
+            .order_by_asc(lfs_tus_uploads::Column::UpdatedAt)
+            .limit(limit)
+            .all(self.get_connection())
+            .await?)
+    }
+
     pub async fn new_lock(
         &self,
         lfs_lock: lfs_locks::Model,
@@ -6,7 +6,7 @@ use callisto::{
 };
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
-    QuerySelect, Set,
+    QuerySelect, Set, prelude::Expr,
 };
 
 use crate::storage::base_storage::{BaseStorage, StorageConnector};
@@ -76,6 +76,8 @@ impl MergeQueueStorage {
             last_retry_at: Set(None),
             failure_type: Set(None),
             error_message: Set(None),
+            batch_link: Set(None),
+            build_task_id: Set(None),
             created_at: Set(now.naive_utc()),
             updated_at: Set(now.naive_utc()),
         };
@@ -167,6 +169,54 @@ impl MergeQueueStorage {
             .map_err(|e| format!("Failed to find waiting items: {}", e))
     }
 
+    /// Returns up to `limit` waiting items in queue order.
+    pub async fn get_waiting_items(&self, limit: u64) -> Result<Vec<Model>, String> {
+        Entity::find()
+            .filter(Column::Status.eq(QueueStatusEnum::Waiting))
+            .order_by_asc(Column::Position)
+            .limit(limit)
+            .all(self.get_connection())
+            .await
+            .map_err(|e| format!("Failed to find waiting items: {}", e))
+    }
+
+    /// Groups the items into the batch `batch_link`, clearing the build of any previous batch.
+    pub async fn assign_batch(&self, cl_links: &[String], batch_link: &str) -> Result<(), String> {
+        Entity::update_many()
+            .col_expr(Column::BatchLink, Expr::value(batch_link))
+            .col_expr(Column::BuildTaskId, Expr::value(Option::<String>::None))
+            .col_expr(
+                Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(Column::ClLink.is_in(cl_links.iter().cloned()))
+            .exec(self.get_connection())
+            .await
+            .map_err(|e| format!("Failed to assign batch {}: {}", batch_link, e))?;
+        Ok(())
+    }
+
+    /// Records the Orion task validating `batch_link` on every item of the batch.
+    pub async fn set_batch_build(&self, batch_link: &str, task_id: &str) -> Result<(), String> {
+        Entity::update_many()
+            .col_expr(Column::BuildTaskId, Expr::value(task_id))
+            .filter(Column::BatchLink.eq(batch_link))
+            .exec(self.get_connection())
+            .await
+            .map_err(|e| format!("Failed to record build of batch {}: {}", batch_link, e))?;
+        Ok(())
+    }
+
+    /// Items of a batch in queue order.
+    pub async fn get_batch_items(&self, batch_link: &str) -> Result<Vec<Model>, String> {
+        Entity::find()
+            .filter(Column::BatchLink.eq(batch_link))
+            .order_by_asc(Column::Position)
+            .all(self.get_connection())
+            .await
+            .map_err(|e| format!("Failed to find items of batch {}: {}", batch_link, e))
+    }
+
     /// Updates item status for normal workflow transitions.
     /// Returns false if item is already Failed (cancelled) - use retry_failed_item to re-queue.
     pub async fn update_item_status(
@@ -392,6 +442,8 @@ impl MergeQueueStorage {
             active_model.position = Set(chrono::Utc::now().timestamp_millis());
             active_model.failure_type = Set(None);
             active_model.error_message = Set(None);
+            active_model.batch_link = Set(None);
+            active_model.build_task_id = Set(None);
 
             active_model
                 .update(db)
@@ -426,6 +478,7 @@ impl MergeQueueStorage {
             let mut active_model: ActiveModel = item.into();
             active_model.status = Set(QueueStatusEnum::Waiting);
             active_model.position = Set(chrono::Utc::now().timestamp_millis());
+            active_model.batch_link = Set(None);
             active_model.updated_at = Set(chrono::Utc::now().naive_utc());
 
             active_model
@@ -133,6 +133,14 @@ impl MonoStorage {
         Ok(result)
     }
 
+    /// Every monorepo ref, CL refs included.
+    pub async fn list_all_refs(&self) -> Result<Vec<mega_refs::Model>, MegaError> {
+        Ok(mega_refs::Entity::find()
+            .order_by_asc(mega_refs::Column::Id)
+            .all(self.get_connection())
+            .await?)
+    }
+
     pub async fn get_main_ref(&self, path: &str) -> Result<Option<mega_refs::Model>, MegaError> {
         let result = mega_refs::Entity::find()
             .filter(mega_refs::Column::Path.eq(path))
@@ -1,14 +1,25 @@
 use std::ops::Deref;
 
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE_NO_PAD};
 use callisto::{access_token, ssh_keys};
-use common::{errors::MegaError, utils::generate_id};
+use chrono::NaiveDateTime;
+use common::{enums::TokenScope, errors::MegaError, utils::generate_id};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter,
+    prelude::Expr,
 };
-use uuid::Uuid;
+use sha2::{Digest, Sha256};
 
 use crate::storage::base_storage::{BaseStorage, StorageConnector};
 
+/// Marks personal access tokens so that they are easy to recognise in logs and secret scanners.
+const TOKEN_PREFIX: &str = "mpat_";
+const TOKEN_RANDOM_LEN: usize = 32;
+const TOKEN_SALT_LEN: usize = 16;
+const TOKEN_LOOKUP_PREFIX_LEN: usize = 12;
+/// `last_used_at` is only rewritten once per interval to keep token checks read-mostly.
+const LAST_USED_RESOLUTION: chrono::TimeDelta = chrono::TimeDelta::minutes(1);
+
 #[derive(Debug, Clone)]
 pub struct UserStorage {
     pub base: BaseStorage,
@@ -78,17 +89,33 @@ impl UserStorage {
         Ok(res)
     }
 
-    pub async fn generate_token(&self, username: String) -> Result<String, MegaError> {
-        let token_str = Uuid::new_v4().to_string();
+    /// Create a personal access token and return the stored row with the plaintext token.
+    ///
+    /// Only a salted SHA-256 hash is persisted, so the plaintext cannot be recovered later.
+    pub async fn generate_token(
+        &self,
+        username: String,
+        scopes: &[TokenScope],
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<(access_token::Model, String), MegaError> {
+        let token_plain = generate_token_plain()?;
+        let token_salt = generate_token_salt()?;
         let model = access_token::Model {
             id: generate_id(),
             username,
-            token: token_str.clone(),
+            token_prefix: token_lookup_prefix(&token_plain).to_owned(),
+            token_hash: compute_token_hash(&token_salt, &token_plain),
+            token_salt,
+            scopes: serde_json::to_value(scopes)?,
+            expires_at,
+            last_used_at: None,
             created_at: chrono::Utc::now().naive_utc(),
         };
-        let a_model = model.into_active_model();
-        a_model.insert(self.get_connection()).await.unwrap();
-        Ok(token_str.to_owned())
+        let model = model
+            .into_active_model()
+            .insert(self.get_connection())
+            .await?;
+        Ok((model, token_plain))
     }
 
     pub async fn delete_token(&self, username: String, id: i64) -> Result<(), MegaError> {
@@ -115,36 +142,203 @@ impl UserStorage {
     }
 
     pub async fn check_token(&self, username: &str, token: &str) -> Result<bool, MegaError> {
-        let res = access_token::Entity::find()
-            .filter(access_token::Column::Username.eq(username))
-            .filter(access_token::Column::Token.eq(token))
-            .one(self.get_connection())
+        Ok(self
+            .find_token(token)
+            .await?
+            .is_some_and(|model| model.username == username))
+    }
+
+    /// Resolve a plaintext personal access token to its row, rejecting unknown and expired
+    /// tokens. Successful lookups refresh `last_used_at`.
+    pub async fn find_token(&self, token: &str) -> Result<Option<access_token::Model>, MegaError> {
+        let conn = self.get_connection();
+        let candidates = access_token::Entity::find()
+            .filter(access_token::Column::TokenPrefix.eq(token_lookup_prefix(token)))
+            .all(conn)
             .await?;
-        match res {
-            Some(_) => Ok(true),
-            None => Ok(false),
+        let Some(model) = candidates
+            .into_iter()
+            .find(|model| compute_token_hash(&model.token_salt, token) == model.token_hash)
+        else {
+            return Ok(None);
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        if model.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Ok(None);
         }
+
+        let stale = model
+            .last_used_at
+            .is_none_or(|last_used_at| now - last_used_at >= LAST_USED_RESOLUTION);
+        if stale {
+            access_token::Entity::update_many()
+                .col_expr(access_token::Column::LastUsedAt, Expr::value(now))
+                .filter(access_token::Column::Id.eq(model.id))
+                .exec(conn)
+                .await?;
+        }
+        Ok(Some(model))
     }
 
     pub async fn find_user_by_token(&self, token: &str) -> Result<Option<String>, MegaError> {
-        let res = access_token::Entity::find()
-            .filter(access_token::Column::Token.eq(token))
-            .one(self.get_connection())
-            .await?;
-        match res {
-            Some(token_model) => Ok(Some(token_model.username)),
-            None => Ok(None),
-        }
+        Ok(self.find_token(token).await?.map(|model| model.username))
+    }
+}
+
+/// Scopes recorded on a token row; unknown entries are ignored rather than rejected so
+/// that a rollback does not lock users out of tokens minted by a newer release.
+pub fn token_scopes(model: &access_token::Model) -> Vec<TokenScope> {
+    model
+        .scopes
+        .as_array()
+        .map(|scopes| {
+            scopes
+                .iter()
+                .filter_map(|scope| scope.as_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn generate_token_plain() -> Result<String, MegaError> {
+    let bytes = secure_random_bytes::<TOKEN_RANDOM_LEN>()?;
+    Ok(format!(
+        "{TOKEN_PREFIX}{}",
+        BASE64_URL_SAFE_NO_PAD.encode(bytes)
+    ))
+}
+
+fn generate_token_salt() -> Result<String, MegaError> {
+    Ok(hex::encode(secure_random_bytes::<TOKEN_SALT_LEN>()?))
+}
+
+fn secure_random_bytes<const N: usize>() -> Result<[u8; N], MegaError> {
+    use ring::rand::{SecureRandom, SystemRandom};
+
+    let mut bytes = [0u8; N];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| {
+        MegaError::Other("failed to generate secure random bytes for access token".to_string())
+    })?;
+    Ok(bytes)
+}
+
+/// Non-secret prefix used as the index key; legacy UUID tokens are keyed the same way.
+fn token_lookup_prefix(token: &str) -> &str {
+    match token.char_indices().nth(TOKEN_LOOKUP_PREFIX_LEN) {
+        Some((idx, _)) => &token[..idx],
+        None => token,
     }
 }
 
+fn compute_token_hash(salt: &str, token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(token.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
 #[cfg(test)]
 mod test {
-    use uuid::Uuid;
+    use common::enums::TokenScope;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::tests::test_storage;
+
+    #[test]
+    fn generated_token_has_prefix_and_fresh_salt() {
+        let token = generate_token_plain().unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_ne!(token, generate_token_plain().unwrap());
+        assert_eq!(token_lookup_prefix(&token).len(), TOKEN_LOOKUP_PREFIX_LEN);
+        assert_ne!(
+            generate_token_salt().unwrap(),
+            generate_token_salt().unwrap()
+        );
+    }
+
+    #[test]
+    fn token_hash_depends_on_salt_and_token() {
+        let hash = compute_token_hash("salt", "mpat_secret");
+        assert_eq!(hash, compute_token_hash("salt", "mpat_secret"));
+        assert_ne!(hash, compute_token_hash("pepper", "mpat_secret"));
+        assert_ne!(hash, compute_token_hash("salt", "mpat_secreT"));
+        assert!(!hash.contains("secret"));
+    }
+
+    #[test]
+    fn token_lookup_prefix_handles_short_tokens() {
+        assert_eq!(token_lookup_prefix("abc"), "abc");
+        assert_eq!(
+            token_lookup_prefix("a1b2c3d4-e5f6-7890-abcd-ef1234567890"),
+            "a1b2c3d4-e5f"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_lifecycle_hashes_and_expires() {
+        let temp_dir = TempDir::new().expect("failed to create temporary directory");
+        let storage = test_storage(temp_dir.path()).await;
+        let user_storage = storage.user_storage();
+
+        let (model, token) = user_storage
+            .generate_token("alice".to_owned(), &[TokenScope::RepoRead], None)
+            .await
+            .unwrap();
+        assert!(!model.token_hash.contains(&token));
+        assert_ne!(model.token_hash, token);
+        assert_eq!(token_scopes(&model), vec![TokenScope::RepoRead]);
+
+        let found = user_storage.find_token(&token).await.unwrap().unwrap();
+        assert_eq!(found.id, model.id);
+        assert!(user_storage.check_token("alice", &token).await.unwrap());
+        assert!(!user_storage.check_token("bob", &token).await.unwrap());
+        assert!(
+            user_storage
+                .find_token(&format!("{token}x"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let listed = user_storage.list_token("alice".to_owned()).await.unwrap();
+        assert!(listed[0].last_used_at.is_some());
+
+        let expired_at = chrono::Utc::now().naive_utc() - chrono::TimeDelta::minutes(5);
+        let (_, expired) = user_storage
+            .generate_token("alice".to_owned(), &[TokenScope::Admin], Some(expired_at))
+            .await
+            .unwrap();
+        assert!(user_storage.find_token(&expired).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn token_scopes_skip_unknown_entries() {
+        let model = access_token::Model {
+            id: 1,
+            username: "alice".to_owned(),
+            token_prefix: String::new(),
+            token_salt: String::new(),
+            token_hash: String::new(),
+            scopes: json!(["repo:read", "cl:write", "packages:write"]),
+            expires_at: None,
+            last_used_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        assert_eq!(
+            token_scopes(&model),
+            vec![TokenScope::RepoRead, TokenScope::ClWrite]
+        );
+    }
 
     #[test]
-    fn token_format() {
-        let uuid = Uuid::new_v4().to_string();
-        println!("{uuid:?}");
+    fn token_scope_grants() {
+        assert!(TokenScope::Admin.grants(TokenScope::RepoWrite));
+        assert!(TokenScope::RepoWrite.grants(TokenScope::RepoRead));
+        assert!(!TokenScope::RepoRead.grants(TokenScope::RepoWrite));
+        assert!(!TokenScope::ClWrite.grants(TokenScope::RepoWrite));
+        assert_eq!("cl:write".parse(), Ok(TokenScope::ClWrite));
+        assert!("repo:delete".parse::<TokenScope>().is_err());
     }
 }
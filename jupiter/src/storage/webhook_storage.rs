@@ -8,11 +8,19 @@ use callisto::{
 use common::errors::MegaError;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, JoinType, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, sea_query::Expr,
 };
 
 use crate::storage::base_storage::{BaseStorage, StorageConnector};
 
+/// Delivery waiting for its next attempt at `next_retry_at`.
+pub const DELIVERY_STATUS_PENDING: &str = "pending";
+/// Delivery claimed by a worker until `next_retry_at`; afterwards it is picked up again.
+pub const DELIVERY_STATUS_SENDING: &str = "sending";
+pub const DELIVERY_STATUS_DELIVERED: &str = "delivered";
+/// Delivery that exhausted its attempts or whose webhook is inactive.
+pub const DELIVERY_STATUS_FAILED: &str = "failed";
+
 #[derive(Clone)]
 pub struct WebhookWithEventTypes {
     pub webhook: mega_webhook::Model,
@@ -135,12 +143,94 @@ impl WebhookStorage {
     pub async fn save_delivery(
         &self,
         model: mega_webhook_delivery::Model,
-    ) -> Result<(), MegaError> {
-        model
+    ) -> Result<mega_webhook_delivery::Model, MegaError> {
+        Ok(model
             .into_active_model()
             .insert(self.get_connection())
+            .await?)
+    }
+
+    pub async fn update_delivery(
+        &self,
+        model: mega_webhook_delivery::Model,
+    ) -> Result<mega_webhook_delivery::Model, MegaError> {
+        Ok(model
+            .into_active_model()
+            .reset_all()
+            .update(self.get_connection())
+            .await?)
+    }
+
+    pub async fn get_delivery(
+        &self,
+        id: i64,
+    ) -> Result<Option<mega_webhook_delivery::Model>, MegaError> {
+        Ok(mega_webhook_delivery::Entity::find_by_id(id)
+            .one(self.get_connection())
+            .await?)
+    }
+
+    /// Newest deliveries of `webhook_id` first.
+    pub async fn list_deliveries(
+        &self,
+        webhook_id: i64,
+        page: Pagination,
+    ) -> Result<(Vec<mega_webhook_delivery::Model>, u64), MegaError> {
+        let paginator = mega_webhook_delivery::Entity::find()
+            .filter(mega_webhook_delivery::Column::WebhookId.eq(webhook_id))
+            .order_by_desc(mega_webhook_delivery::Column::CreatedAt)
+            .order_by_desc(mega_webhook_delivery::Column::Id)
+            .paginate(self.get_connection(), page.per_page);
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(page.page.saturating_sub(1)).await?;
+        Ok((items, total))
+    }
+
+    /// Pending deliveries whose retry time has come, plus claimed ones whose lease expired.
+    pub async fn fetch_due_deliveries(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<mega_webhook_delivery::Model>, MegaError> {
+        let now = chrono::Utc::now().naive_utc();
+        Ok(mega_webhook_delivery::Entity::find()
+            .filter(
+                mega_webhook_delivery::Column::Status
+                    .is_in([DELIVERY_STATUS_PENDING, DELIVERY_STATUS_SENDING]),
+            )
+            .filter(mega_webhook_delivery::Column::NextRetryAt.lte(now))
+            .order_by_asc(mega_webhook_delivery::Column::NextRetryAt)
+            .limit(limit)
+            .all(self.get_connection())
+            .await?)
+    }
+
+    /// Claims a due delivery until `lease_until`.
+    ///
+    /// The claim only succeeds while the row still has the `next_retry_at` it was fetched
+    /// with, so concurrent workers never send the same attempt twice.
+    pub async fn try_claim_delivery(
+        &self,
+        delivery: &mega_webhook_delivery::Model,
+        lease_until: chrono::NaiveDateTime,
+    ) -> Result<bool, MegaError> {
+        let res = mega_webhook_delivery::Entity::update_many()
+            .col_expr(
+                mega_webhook_delivery::Column::Status,
+                Expr::value(DELIVERY_STATUS_SENDING),
+            )
+            .col_expr(
+                mega_webhook_delivery::Column::NextRetryAt,
+                Expr::value(lease_until),
+            )
+            .filter(mega_webhook_delivery::Column::Id.eq(delivery.id))
+            .filter(
+                mega_webhook_delivery::Column::Status
+                    .is_in([DELIVERY_STATUS_PENDING, DELIVERY_STATUS_SENDING]),
+            )
+            .filter(mega_webhook_delivery::Column::NextRetryAt.eq(delivery.next_retry_at))
+            .exec(self.get_connection())
             .await?;
-        Ok(())
+        Ok(res.rows_affected == 1)
     }
 
     async fn replace_event_types(
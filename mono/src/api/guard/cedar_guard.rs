@@ -1,14 +1,16 @@
-use std::{collections::HashMap, str::FromStr};
+use std::collections::HashMap;
 
 use axum::{
-    extract::{FromRef, FromRequestParts, Request, State},
+    body::{Body, to_bytes},
+    extract::{FromRequestParts, Query, Request, State},
     middleware::Next,
     response::Response,
 };
-use cedar_policy::{Context, EntityId, EntityTypeName, EntityUid};
 use common::errors::MegaError;
+use http::{Uri, request::Parts};
 use once_cell::sync::Lazy;
-use saturn::{ActionEnum, context::CedarContext, entitystore::EntityStore, util::SaturnEUid};
+use saturn::{ActionEnum, context::ANONYMOUS_USER};
+use serde::Deserialize;
 
 use crate::api::{
     MonoApiServiceState,
@@ -16,20 +18,41 @@ use crate::api::{
     oauth::{BotAuth, model::LoginUser},
 };
 
-// TODO: All users are temporary allowed during development stage
-const POLICY_CONTENT: &str = r#"
-permit (
-    principal,  
-    action,
-    resource
-);"#;
+/// Largest request body read to find the path of a guarded endpoint; matches axum's default
+/// limit for `Json` bodies, which the handlers apply anyway.
+const GUARDED_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Guarded REST endpoints by where they take the monorepo path they act on from.
+#[derive(Debug, Default, Deserialize)]
+struct EndPointConfig {
+    /// CL endpoints, acting on the path of the CL named by `{link}`
+    #[serde(rename = "/cl", default)]
+    cl: HashMap<String, String>,
+    /// Endpoints acting on the path given in their `path` query parameter
+    #[serde(default)]
+    query: HashMap<String, String>,
+    /// Endpoints acting on the path found at a JSON pointer of their request body
+    #[serde(default)]
+    body: HashMap<String, BodyPathEndpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BodyPathEndpoint {
+    action: String,
+    pointer: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PathQuery {
+    #[serde(default)]
+    path: String,
+}
 
-type EndPointConfig = HashMap<String, HashMap<String, String>>;
 static GURADED_ENDPOINTS: Lazy<EndPointConfig> = Lazy::new(|| {
     let endpoints_config_dict: &str = include_str!("guarded_endpoints.json");
     serde_json::from_str(endpoints_config_dict).unwrap_or_else(|e| {
         tracing::error!("Failed to read endpoints configuration for guard {:}", e);
-        EndPointConfig::new()
+        EndPointConfig::default()
     })
 });
 
@@ -41,11 +64,7 @@ pub fn resolve_cl_action(req_path: &str) -> Result<(ActionEnum, String), MegaErr
     }
     let path = req_path.trim_start_matches(cl_path_prefix);
 
-    let cl_config = GURADED_ENDPOINTS.get(cl_path_prefix).ok_or_else(|| {
-        MegaError::Other("No CL config found in guarded_endpoints.json".to_string())
-    })?;
-
-    let Some((action, mr_link)) = match_operation(path, cl_config) else {
+    let Some((action, mr_link)) = match_operation(path, &GURADED_ENDPOINTS.cl) else {
         tracing::warn!("No matching CL action for path: {}", req_path);
         return Ok((ActionEnum::UnprotectedRequest, String::new()));
     };
@@ -54,7 +73,7 @@ pub fn resolve_cl_action(req_path: &str) -> Result<(ActionEnum, String), MegaErr
 }
 
 //TODO: Only match cl api paths for now, extend when need in the future
-/// return (ActionEnum, mr_link)
+/// return (ActionEnum, mr_link); a trailing `{id}` in a pattern matches any last segment
 fn match_operation(
     suffix: &str,
     patterns: &HashMap<String, String>,
@@ -69,6 +88,13 @@ fn match_operation(
             if parts.len() == 2 {
                 let prefix = parts[0].trim_matches('/');
                 let op = parts[1].trim_matches('/');
+                let (suffix, op) = match op.strip_suffix("{id}") {
+                    Some(op) => match suffix.rsplit_once('/') {
+                        Some((head, id)) if !id.is_empty() => (head, op.trim_end_matches('/')),
+                        _ => continue,
+                    },
+                    None => (suffix, op),
+                };
 
                 if (prefix.is_empty() || suffix.starts_with(prefix))
                     && (op.is_empty() || suffix.ends_with(op))
@@ -106,63 +132,143 @@ fn match_operation(
     None
 }
 
+/// Resolve a guarded endpoint that names its path in the query string or the request body.
+///
+/// The body is read to find the path, so it is handed back for the handler; unknown or
+/// missing paths resolve to the root.
+async fn resolve_path_request(
+    uri: &Uri,
+    body: Body,
+) -> Result<(Option<(ActionEnum, String)>, Body), MegaError> {
+    let req_path = uri.path();
+    if let Some(action) = GURADED_ENDPOINTS.query.get(req_path) {
+        let path = Query::<PathQuery>::try_from_uri(uri)
+            .map(|Query(query)| query.path)
+            .unwrap_or_default();
+        return Ok((
+            Some((ActionEnum::from(action.as_str()), root_if_empty(path))),
+            body,
+        ));
+    }
+    let Some(endpoint) = GURADED_ENDPOINTS.body.get(req_path) else {
+        return Ok((None, body));
+    };
+    let bytes = to_bytes(body, GUARDED_BODY_LIMIT)
+        .await
+        .map_err(|e| MegaError::BadRequest(format!("Failed to read request body: {e}")))?;
+    let path = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|json| {
+            json.pointer(&endpoint.pointer)
+                .and_then(|path| path.as_str())
+                .map(str::to_owned)
+        })
+        .unwrap_or_default();
+    let action = ActionEnum::from(endpoint.action.as_str());
+    Ok((Some((action, root_if_empty(path))), Body::from(bytes)))
+}
+
+fn root_if_empty(path: String) -> String {
+    if path.trim_matches('/').is_empty() {
+        "/".to_string()
+    } else {
+        path
+    }
+}
+
+/// Resolve a guarded REST request (relative to `/api/v1`) into the Cedar action and the
+/// monorepo path it acts on, handing back the request body. `None` for unprotected requests.
+pub async fn resolve_guarded_request(
+    state: &MonoApiServiceState,
+    uri: &Uri,
+    body: Body,
+) -> Result<(Option<(ActionEnum, String)>, Body), MegaError> {
+    let (resolved, body) = resolve_path_request(uri, body).await?;
+    if resolved.is_some() {
+        return Ok((resolved, body));
+    }
+    Ok((resolve_cl_request(state, uri.path()).await?, body))
+}
+
+async fn resolve_cl_request(
+    state: &MonoApiServiceState,
+    req_path: &str,
+) -> Result<Option<(ActionEnum, String)>, MegaError> {
+    let (action, link) = resolve_cl_action(req_path)?;
+    if action == ActionEnum::UnprotectedRequest {
+        return Ok(None);
+    }
+    if link.is_empty() {
+        return Ok(Some((action, "/".to_string())));
+    }
+    let cl = state
+        .services()
+        .storage()
+        .cl_storage()
+        .get_cl(&link)
+        .await?
+        .ok_or_else(|| MegaError::NotFound(format!("Change list not found for link: {link}")))?;
+    Ok(Some((action, cl.path)))
+}
+
+/// Cedar principal for a request: a bot acts as `User::"bot:<id>"` so that policies can grant
+/// it access explicitly, a browser session as its username, anything else is anonymous.
+async fn request_principal(parts: &mut Parts, state: &MonoApiServiceState) -> Option<String> {
+    if let Ok(bot) = BotAuth::from_request_parts(parts, state).await {
+        Some(format!("bot:{}", bot.bot_id))
+    } else if let Ok(user) = LoginUser::from_request_parts(parts, state).await {
+        Some(user.username)
+    } else {
+        None
+    }
+}
+
 pub async fn cedar_guard(
     State(state): State<MonoApiServiceState>,
     req: Request,
     next: Next,
 ) -> Result<Response, ApiError> {
-    let request_path = req.uri().path().to_owned();
-    tracing::debug!("Processing request: {}", request_path);
-
-    let (action, link) = resolve_cl_action(&request_path).map_err(|e| {
-        tracing::error!("Failed to resolve CL action: {}", e);
-        ApiError::from(e)
-    })?;
-    tracing::debug!("Resolved action: {:?}, link: {}", action, link);
-
-    // Skip authorization for unprotected requests
-    if action.eq(&ActionEnum::UnprotectedRequest) {
-        tracing::debug!("Unprotected request for path: {}", request_path);
+    if !state
+        .services()
+        .storage()
+        .config()
+        .monorepo
+        .enforce_cedar_policies
+    {
         return Ok(next.run(req).await);
     }
 
-    // TODO: Fetch repo path from CL model
-    // let cl_model = state
-    //     .cl_stg()
-    //     .get_cl(&link)
-    //     .await?
-    //     .ok_or_else(|| MegaError::with_message(format!("Change list not found for link: {}", link)))?;
-    // let repo_path: PathBuf = cl_model.path.into();
+    let request_path = req.uri().path().to_owned();
+    tracing::debug!("Processing request: {}", request_path);
 
     let (mut parts, body) = req.into_parts();
+    let (resolved, body) = resolve_guarded_request(&state, &parts.uri, body)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to resolve guarded request: {}", e);
+            ApiError::from(e)
+        })?;
+    let Some((action, repo_path)) = resolved else {
+        tracing::debug!("Unprotected request for path: {}", request_path);
+        return Ok(next.run(Request::from_parts(parts, body)).await);
+    };
+    tracing::debug!("Resolved action: {:?}, path: {}", action, repo_path);
 
-    let (principal_type, principal_id) =
-        if let Ok(bot) = BotAuth::from_request_parts(&mut parts, &state).await {
-            ("Bot".to_string(), bot.bot_id.to_string())
-        } else if let Ok(user) = LoginUser::from_request_parts(&mut parts, &state).await {
-            ("User".to_string(), user.username.clone())
-        } else {
-            ("User".to_string(), "reader".to_string())
-        };
-
-    // let policy_path = repo_path.join("cedar/policies.cedar");
-    // let policy_content = get_blob_string(&state, &policy_path).await?;
-    let policy_content = POLICY_CONTENT.to_string();
-
-    let entity_store = EntityStore::from_ref(&state);
-    let c = CedarContext::from(entity_store, &policy_content)?;
+    let principal = request_principal(&mut parts, &state).await;
 
-    authorize(&c, &principal_type, &principal_id, &action.to_string())
+    state
+        .services()
+        .admin()
+        .authorize_path(principal.as_deref(), action, &repo_path)
         .await
         .map_err(|e| {
             tracing::debug!(
-                "Authorization failed for {}: {}",
-                &principal_id,
-                &action.to_string()
+                "Authorization failed for {}: {} on {}",
+                principal.as_deref().unwrap_or(ANONYMOUS_USER),
+                action,
+                repo_path
             );
-            ApiError::from(MegaError::forbidden(format!(
-                "Guard Authorization failed: {e}"
-            )))
+            ApiError::from(e)
         })?;
 
     let req = Request::from_parts(parts, body);
@@ -179,38 +285,6 @@ pub async fn cedar_guard(
     Ok(response)
 }
 
-async fn authorize(
-    cedar_context: &CedarContext,
-    principal_type: &str,
-    principal_id: &str,
-    action: &str,
-) -> Result<(), MegaError> {
-    let user_entity = EntityId::from_str(principal_id)?;
-    let action_entity = EntityId::from_str(action)?;
-
-    let role_entity = EntityTypeName::from_str(principal_type)?;
-    let actiontype_entity = EntityTypeName::from_str("Action")?;
-
-    let principal = SaturnEUid::from(EntityUid::from_type_name_and_id(role_entity, user_entity));
-    let action = SaturnEUid::from(EntityUid::from_type_name_and_id(
-        actiontype_entity,
-        action_entity,
-    ));
-    // TODO: repository is currently hardcoded to "0", need to change it to actual repo id
-    let resource = SaturnEUid::from(EntityUid::from_type_name_and_id(
-        EntityTypeName::from_str("Repository")?,
-        EntityId::from_str("0")?,
-    ));
-
-    let context = Context::empty();
-
-    cedar_context
-        .is_authorized(&principal, &action, &resource, context)
-        .map_err(|e| MegaError::forbidden(format!("Authorization failed: {e}")))?;
-
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,6 +300,7 @@ mod tests {
                 "/{link}/review/delete".to_string(),
                 "editMergeRequest".to_string(),
             ),
+            ("/{link}/runs/{id}".to_string(), "viewRepo".to_string()),
         ]);
 
         let suffix = "/my-cl-link/approve";
@@ -246,6 +321,13 @@ mod tests {
         let result = match_operation(suffix, &patterns);
         assert_eq!(result, None);
 
+        let suffix = "/my-cl-link/runs/42";
+        let result = match_operation(suffix, &patterns);
+        assert_eq!(
+            result,
+            Some((ActionEnum::ViewRepo, "my-cl-link".to_string()))
+        );
+
         let suffix = "/path/subpath/review/delete";
         let result = match_operation(suffix, &patterns);
         assert_eq!(
@@ -253,4 +335,42 @@ mod tests {
             Some((ActionEnum::EditMergeRequest, "path/subpath".to_string()))
         );
     }
+
+    #[tokio::test]
+    async fn test_path_endpoints_resolve_query_and_body_paths() {
+        let uri: Uri = "/tree?refs=main&path=/project/private".parse().unwrap();
+        let (resolved, _) = resolve_path_request(&uri, Body::empty()).await.unwrap();
+        assert_eq!(
+            resolved,
+            Some((ActionEnum::ViewRepo, "/project/private".to_string()))
+        );
+
+        let uri: Uri = "/tree".parse().unwrap();
+        let (resolved, _) = resolve_path_request(&uri, Body::empty()).await.unwrap();
+        assert_eq!(resolved, Some((ActionEnum::ViewRepo, "/".to_string())));
+
+        let payload = r#"{"path":"/project/private/a.rs","content":"x","commit_message":"m"}"#;
+        let uri: Uri = "/edit/save".parse().unwrap();
+        let (resolved, body) = resolve_path_request(&uri, Body::from(payload))
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved,
+            Some((ActionEnum::PushRepo, "/project/private/a.rs".to_string()))
+        );
+        // the handler still receives the whole body
+        let body = to_bytes(body, GUARDED_BODY_LIMIT).await.unwrap();
+        assert_eq!(body, payload.as_bytes());
+
+        let uri: Uri = "/commits/history".parse().unwrap();
+        let history = r#"{"pagination":{"page":1,"per_page":10},"additional":{"path":"/doc"}}"#;
+        let (resolved, _) = resolve_path_request(&uri, Body::from(history))
+            .await
+            .unwrap();
+        assert_eq!(resolved, Some((ActionEnum::ViewRepo, "/doc".to_string())));
+
+        let uri: Uri = "/cl/abc/approve".parse().unwrap();
+        let (resolved, _) = resolve_path_request(&uri, Body::empty()).await.unwrap();
+        assert_eq!(resolved, None);
+    }
 }
@@ -3,9 +3,9 @@
 //! Git smart HTTP (`/git-receive-pack`, etc.) is handled by [`crate::server::http_server::handle_smart_protocol`],
 //! which takes a raw [`axum::http::Request`] and does not run `FromRequestParts`. For the same Mono access-token
 //! validation as [`AccessTokenUser`], call [`bearer_token_from_authorization_value`] and
-//! [`login_user_from_mono_access_token`] from that code path instead of the extractor.
+//! [`resolve_mono_access_token`] from that code path instead of the extractor, then check the scope it needs.
 
-use std::ops::Deref;
+use std::{marker::PhantomData, ops::Deref};
 
 use axum::{
     RequestPartsExt,
@@ -18,9 +18,9 @@ use axum_extra::{
     headers::{self, Authorization, authorization::Bearer},
 };
 pub use ceres::model::bots::BotIdentity;
-use common::errors::MegaError;
+use common::{enums::TokenScope, errors::MegaError};
 use http::request::Parts;
-use jupiter::storage::user_storage::UserStorage;
+use jupiter::storage::user_storage::{UserStorage, token_scopes};
 use model::LoginUser;
 
 use crate::api::{MonoApiServiceState, oauth::api_store::OAuthApiStore};
@@ -37,7 +37,88 @@ impl IntoResponse for AuthRedirect {
         (StatusCode::UNAUTHORIZED, "Login first").into_response()
     }
 }
-pub struct AccessTokenUser(pub LoginUser);
+/// Rejection for personal access token extractors: a missing or invalid token is a 401, a valid
+/// token without the scope the route needs is a 403.
+pub enum TokenRejection {
+    Unauthenticated,
+    MissingScope(TokenScope),
+}
+
+impl IntoResponse for TokenRejection {
+    fn into_response(self) -> Response {
+        match self {
+            TokenRejection::Unauthenticated => AuthRedirect.into_response(),
+            TokenRejection::MissingScope(scope) => (
+                StatusCode::FORBIDDEN,
+                format!("Access token lacks the `{scope}` scope"),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Authenticated user resolved from a Mono personal access token in `Authorization: Bearer`.
+///
+/// The extractor requires `repo:read` for safe methods (`GET`, `HEAD`, ...) and `repo:write` for
+/// everything else. Routes that need another scope should take [`ScopedTokenUser`] instead.
+pub struct AccessTokenUser {
+    pub user: LoginUser,
+    pub scopes: Vec<TokenScope>,
+}
+
+impl AccessTokenUser {
+    pub fn has_scope(&self, required: TokenScope) -> bool {
+        TokenScope::any_grants(&self.scopes, required)
+    }
+
+    pub fn require_scope(&self, required: TokenScope) -> Result<(), TokenRejection> {
+        if self.has_scope(required) {
+            Ok(())
+        } else {
+            Err(TokenRejection::MissingScope(required))
+        }
+    }
+}
+
+/// Scope demanded by a [`ScopedTokenUser`] extractor.
+pub trait RequiredScope {
+    const SCOPE: TokenScope;
+}
+
+pub struct RepoReadScope;
+pub struct RepoWriteScope;
+pub struct ClWriteScope;
+pub struct AdminScope;
+
+impl RequiredScope for RepoReadScope {
+    const SCOPE: TokenScope = TokenScope::RepoRead;
+}
+
+impl RequiredScope for RepoWriteScope {
+    const SCOPE: TokenScope = TokenScope::RepoWrite;
+}
+
+impl RequiredScope for ClWriteScope {
+    const SCOPE: TokenScope = TokenScope::ClWrite;
+}
+
+impl RequiredScope for AdminScope {
+    const SCOPE: TokenScope = TokenScope::Admin;
+}
+
+/// User of a route that needs `R::SCOPE`, e.g. `ScopedTokenUser<ClWriteScope>`.
+///
+/// A request with `Authorization: Bearer` must carry a Mono access token that grants the scope;
+/// otherwise the browser session is used, which is not scope-restricted.
+pub struct ScopedTokenUser<R: RequiredScope>(pub LoginUser, PhantomData<R>);
+
+impl<R: RequiredScope> Deref for ScopedTokenUser<R> {
+    type Target = LoginUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 /// Authenticated user resolved from a **browser session cookie** (Campsite or Tinyship),
 /// not from `Authorization: Bearer` or the Mono DB access-token table.
@@ -56,20 +137,55 @@ pub fn bearer_token_from_authorization_value(value: &str) -> Option<&str> {
         .map(str::trim)
 }
 
-/// Validates a Mono DB access token; same as [`AccessTokenUser`] but usable outside Axum extractors.
-pub async fn login_user_from_mono_access_token(
+/// Validates a Mono DB access token (hash and expiry) and returns its owner and scopes; same as
+/// [`AccessTokenUser`] but usable outside Axum extractors. Callers must check the scope they need.
+pub async fn resolve_mono_access_token(
     user_storage: &UserStorage,
     token: &str,
-) -> Result<Option<LoginUser>, MegaError> {
-    let Some(username) = user_storage.find_user_by_token(token).await? else {
+) -> Result<Option<AccessTokenUser>, MegaError> {
+    let Some(model) = user_storage.find_token(token).await? else {
         return Ok(None);
     };
-    Ok(Some(LoginUser {
-        username,
-        ..Default::default()
+    Ok(Some(AccessTokenUser {
+        scopes: token_scopes(&model),
+        user: LoginUser {
+            username: model.username,
+            ..Default::default()
+        },
     }))
 }
 
+async fn access_token_user_from_parts<S>(
+    parts: &mut Parts,
+    state: &S,
+) -> Result<AccessTokenUser, TokenRejection>
+where
+    UserStorage: FromRef<S>,
+    S: Send + Sync,
+{
+    let user_storage = UserStorage::from_ref(state);
+
+    let TypedHeader(Authorization(bearer)) = parts
+        .extract::<TypedHeader<Authorization<Bearer>>>()
+        .await
+        .map_err(|e| {
+            tracing::debug!("AccessTokenUser: missing or invalid bearer token: {e}");
+            TokenRejection::Unauthenticated
+        })?;
+
+    match resolve_mono_access_token(&user_storage, bearer.token()).await {
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => {
+            tracing::debug!("AccessTokenUser: invalid or expired bearer token");
+            Err(TokenRejection::Unauthenticated)
+        }
+        Err(e) => {
+            tracing::warn!("AccessTokenUser: error validating bearer token: {e:?}");
+            Err(TokenRejection::Unauthenticated)
+        }
+    }
+}
+
 /// Axum extractor for bot bearer tokens (`bot_` prefix).
 pub struct BotAuth(pub BotIdentity);
 
@@ -136,30 +252,39 @@ where
     UserStorage: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = AuthRedirect;
+    type Rejection = TokenRejection;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let user_storage = UserStorage::from_ref(state);
+        let user = access_token_user_from_parts(parts, state).await?;
+        let required = if parts.method.is_safe() {
+            TokenScope::RepoRead
+        } else {
+            TokenScope::RepoWrite
+        };
+        user.require_scope(required)?;
+        Ok(user)
+    }
+}
 
-        let TypedHeader(Authorization(bearer)) = parts
-            .extract::<TypedHeader<Authorization<Bearer>>>()
-            .await
-            .map_err(|e| {
-                tracing::debug!("AccessTokenUser: missing or invalid bearer token: {e}");
-                AuthRedirect
-            })?;
+impl<S, R> FromRequestParts<S> for ScopedTokenUser<R>
+where
+    UserStorage: FromRef<S>,
+    OAuthApiStore: FromRef<S>,
+    S: Send + Sync,
+    R: RequiredScope,
+{
+    type Rejection = TokenRejection;
 
-        match login_user_from_mono_access_token(&user_storage, bearer.token()).await {
-            Ok(Some(user)) => Ok(AccessTokenUser(user)),
-            Ok(None) => {
-                tracing::debug!("AccessTokenUser: invalid or expired bearer token");
-                Err(AuthRedirect)
-            }
-            Err(e) => {
-                tracing::warn!("AccessTokenUser: error validating bearer token: {e:?}");
-                Err(AuthRedirect)
-            }
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(http::header::AUTHORIZATION) {
+            let SessionUser(user) = SessionUser::from_request_parts(parts, state)
+                .await
+                .map_err(|_| TokenRejection::Unauthenticated)?;
+            return Ok(ScopedTokenUser(user, PhantomData));
         }
+        let user = access_token_user_from_parts(parts, state).await?;
+        user.require_scope(R::SCOPE)?;
+        Ok(ScopedTokenUser(user.user, PhantomData))
     }
 }
 
@@ -218,3 +343,96 @@ where
         Ok(user)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::{FromRef, FromRequestParts};
+    use common::enums::TokenScope;
+    use http::{Request, StatusCode};
+    use jupiter::storage::user_storage::UserStorage;
+    use tempfile::tempdir;
+
+    use super::{
+        AdminScope, ClWriteScope, OAuthApiStore, ScopedTokenUser, campsite_store::CampsiteApiStore,
+    };
+
+    #[derive(Clone)]
+    struct TestState {
+        users: UserStorage,
+        sessions: OAuthApiStore,
+    }
+
+    impl FromRef<TestState> for UserStorage {
+        fn from_ref(state: &TestState) -> Self {
+            state.users.clone()
+        }
+    }
+
+    impl FromRef<TestState> for OAuthApiStore {
+        fn from_ref(state: &TestState) -> Self {
+            state.sessions.clone()
+        }
+    }
+
+    fn bearer_parts(token: &str) -> http::request::Parts {
+        Request::post("/api/v1/cl/abc/merge")
+            .header("Authorization", format!("Bearer {token}"))
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_scoped_extractor_rejects_read_only_token() {
+        let temp_dir = tempdir().unwrap();
+        let storage = jupiter::tests::test_storage(temp_dir.path()).await;
+        let state = TestState {
+            users: storage.user_storage(),
+            sessions: OAuthApiStore::Campsite(CampsiteApiStore::new(String::new())),
+        };
+        let (_, read_only) = state
+            .users
+            .generate_token("alice".to_owned(), &[TokenScope::RepoRead], None)
+            .await
+            .unwrap();
+        let (_, cl_writer) = state
+            .users
+            .generate_token("bob".to_owned(), &[TokenScope::ClWrite], None)
+            .await
+            .unwrap();
+
+        let rejection = ScopedTokenUser::<ClWriteScope>::from_request_parts(
+            &mut bearer_parts(&read_only),
+            &state,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(
+            axum::response::IntoResponse::into_response(rejection).status(),
+            StatusCode::FORBIDDEN
+        );
+
+        let user = ScopedTokenUser::<ClWriteScope>::from_request_parts(
+            &mut bearer_parts(&cl_writer),
+            &state,
+        )
+        .await
+        .ok()
+        .unwrap();
+        assert_eq!(user.username, "bob");
+
+        let rejection = ScopedTokenUser::<AdminScope>::from_request_parts(
+            &mut bearer_parts(&cl_writer),
+            &state,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(
+            axum::response::IntoResponse::into_response(rejection).status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -3,19 +3,37 @@
 //! Provides endpoints for admin permission checks:
 //! - `GET /api/v1/admin/me` - Check if current user is admin
 //! - `GET /api/v1/admin/list` - List all admins (admin-only)
+//! - `POST /api/v1/admin/policy/explain` - Dry-run a Cedar policy decision for a path
+//! - `GET /api/v1/admin/protection-rules/list` - List path protection rules (admin-only)
+//! - `POST /api/v1/admin/protection-rules/create` - Protect a path (admin-only)
+//! - `POST /api/v1/admin/protection-rules/edit` - Change the protection of a path (admin-only)
 //!
 //! # Auth Behavior
-//! - 401 Unauthorized: No valid session (handled by `LoginUser` extractor)
-//! - 403 Forbidden: Logged in but not admin (for `/list` endpoint)
+//! - 401 Unauthorized: No valid session or access token
+//! - 403 Forbidden: Logged in but not admin (for `/list`, `/protection-rules/*`, and
+//!   `/policy/explain` on behalf of another user), or an access token without the `admin`
+//!   scope (for `/list` and `/protection-rules/*`)
 
 use api_model::common::CommonResult;
-use axum::{Json, extract::State};
-use ceres::model::admin::{AdminListResponse, IsAdminResponse};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use ceres::model::{
+    admin::{AdminListResponse, IsAdminResponse, PolicyExplainRequest, PolicyExplainResponse},
+    protection_rule::{
+        CreateProtectionRuleReq, ProtectionRuleQuery, ProtectionRuleRes, UpdateProtectionRuleReq,
+    },
+};
+use saturn::{ActionEnum, context::ANONYMOUS_USER};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::api::{
-    MonoApiServiceState, api_common::group_permission::ensure_admin, api_doc::USER_TAG,
-    error::ApiError, oauth::model::LoginUser,
+    MonoApiServiceState,
+    api_common::group_permission::ensure_admin,
+    api_doc::USER_TAG,
+    error::ApiError,
+    oauth::{AdminScope, ScopedTokenUser, model::LoginUser},
 };
 
 /// Build the admin router.
@@ -24,7 +42,11 @@ pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
         "/admin",
         OpenApiRouter::new()
             .routes(routes!(is_admin_me))
-            .routes(routes!(admin_list)),
+            .routes(routes!(admin_list))
+            .routes(routes!(explain_policy))
+            .routes(routes!(list_protection_rules))
+            .routes(routes!(create_protection_rule))
+            .routes(routes!(update_protection_rule)),
     )
 }
 
@@ -70,7 +92,7 @@ async fn is_admin_me(
     tag = USER_TAG
 )]
 async fn admin_list(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<AdminListResponse>>, ApiError> {
     ensure_admin(&state, &user).await?;
@@ -81,3 +103,140 @@ async fn admin_list(
         admins,
     }))))
 }
+
+/// POST /api/v1/admin/policy/explain
+///
+/// Evaluates the Cedar policies that apply to a path without enforcing them, and reports
+/// which policies permit or forbid the action.
+#[utoipa::path(
+    post,
+    path = "/policy/explain",
+    request_body = PolicyExplainRequest,
+    responses(
+        (status = 200, body = CommonResult<PolicyExplainResponse>),
+        (status = 400, description = "Unknown action or invalid policy"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - not admin"),
+    ),
+    tag = USER_TAG
+)]
+async fn explain_policy(
+    user: LoginUser,
+    State(state): State<MonoApiServiceState>,
+    Json(req): Json<PolicyExplainRequest>,
+) -> Result<Json<CommonResult<PolicyExplainResponse>>, ApiError> {
+    let username = req.username.unwrap_or_else(|| user.username.clone());
+    if username != user.username {
+        ensure_admin(&state, &user).await?;
+    }
+    let action = ActionEnum::from(req.action.as_str());
+    if action == ActionEnum::UnprotectedRequest {
+        return Err(ApiError::bad_request(anyhow::anyhow!(
+            "Unknown action: {}",
+            req.action
+        )));
+    }
+
+    let principal = (username != ANONYMOUS_USER).then_some(username.as_str());
+    let decision = state
+        .services()
+        .admin()
+        .explain_path_policy(principal, action, &req.path)
+        .await?;
+    let enforced = state
+        .services()
+        .storage()
+        .config()
+        .monorepo
+        .enforce_cedar_policies;
+
+    Ok(Json(CommonResult::success(Some(
+        PolicyExplainResponse::new(username, req.action, req.path, enforced, decision),
+    ))))
+}
+
+/// GET /api/v1/admin/protection-rules/list
+///
+/// Lists the protection rules configured on a path or below it.
+/// Only admins can access this endpoint.
+#[utoipa::path(
+    get,
+    path = "/protection-rules/list",
+    params(("path" = Option<String>, Query, description = "Defaults to the whole monorepo")),
+    responses(
+        (status = 200, body = CommonResult<Vec<ProtectionRuleRes>>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - not admin"),
+    ),
+    tag = USER_TAG
+)]
+async fn list_protection_rules(
+    user: ScopedTokenUser<AdminScope>,
+    State(state): State<MonoApiServiceState>,
+    Query(query): Query<ProtectionRuleQuery>,
+) -> Result<Json<CommonResult<Vec<ProtectionRuleRes>>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+
+    let rules = state
+        .services()
+        .cl()
+        .list_protection_rules(&query.path)
+        .await?;
+    Ok(Json(CommonResult::success(Some(rules))))
+}
+
+/// POST /api/v1/admin/protection-rules/create
+///
+/// Creates the protection rule of a path, covering the path and everything below it.
+/// Only admins can access this endpoint.
+#[utoipa::path(
+    post,
+    path = "/protection-rules/create",
+    request_body = CreateProtectionRuleReq,
+    responses(
+        (status = 200, body = CommonResult<ProtectionRuleRes>),
+        (status = 400, description = "Invalid path or rule"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - not admin"),
+        (status = 409, description = "The path already has a protection rule"),
+    ),
+    tag = USER_TAG
+)]
+async fn create_protection_rule(
+    user: ScopedTokenUser<AdminScope>,
+    State(state): State<MonoApiServiceState>,
+    Json(req): Json<CreateProtectionRuleReq>,
+) -> Result<Json<CommonResult<ProtectionRuleRes>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+
+    let rule = state.services().cl().create_protection_rule(req).await?;
+    Ok(Json(CommonResult::success(Some(rule))))
+}
+
+/// POST /api/v1/admin/protection-rules/edit
+///
+/// Changes the given fields of the protection rule of a path.
+/// Only admins can access this endpoint.
+#[utoipa::path(
+    post,
+    path = "/protection-rules/edit",
+    request_body = UpdateProtectionRuleReq,
+    responses(
+        (status = 200, body = CommonResult<ProtectionRuleRes>),
+        (status = 400, description = "Invalid path or rule"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - not admin"),
+        (status = 404, description = "The path has no protection rule"),
+    ),
+    tag = USER_TAG
+)]
+async fn update_protection_rule(
+    user: ScopedTokenUser<AdminScope>,
+    State(state): State<MonoApiServiceState>,
+    Json(req): Json<UpdateProtectionRuleReq>,
+) -> Result<Json<CommonResult<ProtectionRuleRes>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+
+    let rule = state.services().cl().update_protection_rule(req).await?;
+    Ok(Json(CommonResult::success(Some(rule))))
+}
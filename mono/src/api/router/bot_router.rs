@@ -13,8 +13,11 @@ use jupiter::sea_orm::prelude::DateTimeWithTimeZone;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::api::{
-    MonoApiServiceState, api_common::group_permission::ensure_admin, api_doc::BOT_TAG,
-    error::ApiError, oauth::model::LoginUser,
+    MonoApiServiceState,
+    api_common::group_permission::ensure_admin,
+    api_doc::BOT_TAG,
+    error::ApiError,
+    oauth::{AdminScope, ScopedTokenUser},
 };
 
 /// Maximum allowed expires_in in seconds (10 years).
@@ -162,7 +165,7 @@ async fn uninstall_bot(
     tag = BOT_TAG
 )]
 async fn create_bot_token(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(bot_id): Path<i64>,
     Json(req): Json<CreateBotTokenRequest>,
@@ -212,7 +215,7 @@ async fn create_bot_token(
     tag = BOT_TAG
 )]
 async fn list_bot_tokens(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(bot_id): Path<i64>,
 ) -> Result<Json<CommonResult<Vec<ListBotTokenItem>>>, ApiError> {
@@ -243,7 +246,7 @@ async fn list_bot_tokens(
     tag = BOT_TAG
 )]
 async fn revoke_bot_token(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((bot_id, token_id)): Path<(i64, i64)>,
 ) -> Result<Json<CommonResult<()>>, ApiError> {
@@ -277,7 +280,7 @@ async fn revoke_bot_token(
     tag = BOT_TAG
 )]
 async fn revoke_all_bot_tokens(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(bot_id): Path<i64>,
 ) -> Result<Json<CommonResult<()>>, ApiError> {
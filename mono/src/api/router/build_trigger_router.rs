@@ -6,26 +6,53 @@
 use api_model::common::{CommonPage, CommonResult, PageParams};
 use axum::{
     Json,
-    extract::{Path, State},
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::HeaderMap,
 };
 use ceres::application::build_trigger::{
-    CreateTriggerRequest, ListTriggersParams, TriggerResponse,
+    CreateScheduleRequest, CreateTriggerRequest, CreateWebhookSourceRequest, ListSchedulesQuery,
+    ListTriggersParams, ScheduleResponse, TriggerResponse, UpdateScheduleRequest,
+    WebhookSourceResponse, WebhookTriggerRequest,
 };
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::api::{
-    MonoApiServiceState, api_doc::BUILD_TRIGGER_TAG, error::ApiError, oauth::model::LoginUser,
+    MonoApiServiceState,
+    api_common::group_permission::ensure_admin,
+    api_doc::BUILD_TRIGGER_TAG,
+    error::ApiError,
+    oauth::{AdminScope, ScopedTokenUser, model::LoginUser},
 };
 
+/// HMAC-SHA256 of the raw request body, `sha256=<hex>`, keyed with the source's secret.
+const WEBHOOK_SIGNATURE_HEADER: &str = "X-Mega-Signature";
+/// Sender-chosen id of a delivery; redeliveries with the same id don't build again.
+const WEBHOOK_DELIVERY_HEADER: &str = "X-Mega-Delivery";
+
 pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
-    OpenApiRouter::new().nest(
-        "/triggers",
-        OpenApiRouter::new()
-            .routes(routes!(create_trigger))
-            .routes(routes!(list_triggers))
-            .routes(routes!(get_trigger))
-            .routes(routes!(retry_trigger)),
-    )
+    OpenApiRouter::new()
+        .nest(
+            "/triggers",
+            OpenApiRouter::new()
+                .routes(routes!(create_trigger))
+                .routes(routes!(list_triggers))
+                .routes(routes!(get_trigger))
+                .routes(routes!(retry_trigger))
+                .routes(routes!(webhook_trigger)),
+        )
+        .nest(
+            "/webhook-sources",
+            OpenApiRouter::new()
+                .routes(routes!(create_webhook_source, list_webhook_sources))
+                .routes(routes!(delete_webhook_source)),
+        )
+        .nest(
+            "/schedules",
+            OpenApiRouter::new()
+                .routes(routes!(create_schedule, list_schedules))
+                .routes(routes!(get_schedule, update_schedule, delete_schedule)),
+        )
 }
 
 /// Create a new build trigger
@@ -147,3 +174,275 @@ async fn retry_trigger(
     let response = service.retry_trigger(id, user.username).await?;
     Ok(Json(CommonResult::success(Some(response))))
 }
+
+/// Create a build schedule
+///
+/// Creates a named cron schedule for a repository path. At each fire time the
+/// whole tree of the path is built at the head of main; runs are skipped when main
+/// has not moved. Five-field cron expressions are evaluated in UTC.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 200, body = CommonResult<ScheduleResponse>, content_type = "application/json"),
+        (status = 400, description = "Invalid name or cron expression"),
+        (status = 409, description = "A schedule with this name already exists for the path"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn create_schedule(
+    user: LoginUser,
+    state: State<MonoApiServiceState>,
+    Json(req): Json<CreateScheduleRequest>,
+) -> Result<Json<CommonResult<ScheduleResponse>>, ApiError> {
+    let service = state.services().build_trigger();
+    let response = service.create_schedule(req, user.username).await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// List build schedules
+///
+/// Use `POST /triggers/list` with `schedule_id` for the trigger history of a schedule.
+#[utoipa::path(
+    get,
+    path = "",
+    params(ListSchedulesQuery),
+    responses(
+        (status = 200, body = CommonResult<Vec<ScheduleResponse>>, content_type = "application/json"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn list_schedules(
+    _user: LoginUser,
+    state: State<MonoApiServiceState>,
+    Query(query): Query<ListSchedulesQuery>,
+) -> Result<Json<CommonResult<Vec<ScheduleResponse>>>, ApiError> {
+    let service = state.services().build_trigger();
+    let response = service.list_schedules(query.repo_path).await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// Get a build schedule by ID
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(
+        ("id" = i64, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, body = CommonResult<ScheduleResponse>, content_type = "application/json"),
+        (status = 404, description = "Schedule not found"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn get_schedule(
+    _user: LoginUser,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+) -> Result<Json<CommonResult<ScheduleResponse>>, ApiError> {
+    let service = state.services().build_trigger();
+    let response = service.get_schedule(id).await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// Update a build schedule
+///
+/// Renames, reschedules, enables or disables a schedule. The next run time is
+/// recomputed from now. Only the schedule's creator or an admin may edit it.
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    params(
+        ("id" = i64, Path, description = "Schedule ID")
+    ),
+    request_body = UpdateScheduleRequest,
+    responses(
+        (status = 200, body = CommonResult<ScheduleResponse>, content_type = "application/json"),
+        (status = 400, description = "Invalid name or cron expression"),
+        (status = 403, description = "Only the schedule's creator or an admin may edit it"),
+        (status = 404, description = "Schedule not found"),
+        (status = 409, description = "A schedule with this name already exists for the path"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn update_schedule(
+    user: LoginUser,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateScheduleRequest>,
+) -> Result<Json<CommonResult<ScheduleResponse>>, ApiError> {
+    let is_admin = state
+        .services()
+        .admin()
+        .check_is_admin(&user.username)
+        .await?;
+    let service = state.services().build_trigger();
+    let response = service
+        .update_schedule(id, req, &user.username, is_admin)
+        .await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// Delete a build schedule
+///
+/// Only the schedule's creator or an admin may delete it. Triggers already fired by the
+/// schedule are kept.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(
+        ("id" = i64, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, body = CommonResult<String>, content_type = "application/json"),
+        (status = 403, description = "Only the schedule's creator or an admin may delete it"),
+        (status = 404, description = "Schedule not found"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn delete_schedule(
+    user: LoginUser,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+) -> Result<Json<CommonResult<String>>, ApiError> {
+    let is_admin = state
+        .services()
+        .admin()
+        .check_is_admin(&user.username)
+        .await?;
+    let service = state.services().build_trigger();
+    service
+        .delete_schedule(id, &user.username, is_admin)
+        .await?;
+    Ok(Json(CommonResult::success(None)))
+}
+
+/// Trigger a build from an external system
+///
+/// Inbound webhook for systems registered as webhook sources. The raw body must be
+/// signed with the source's secret in the `X-Mega-Signature` header (`sha256=<hex hmac>`),
+/// and `X-Mega-Delivery` must carry an id unique per delivery: redelivering an accepted id
+/// returns the trigger it created without building again.
+#[utoipa::path(
+    post,
+    path = "/webhooks/{source}",
+    params(
+        ("source" = String, Path, description = "Webhook source name"),
+        ("X-Mega-Signature" = String, Header, description = "sha256=<hex HMAC-SHA256 of the body>"),
+        ("X-Mega-Delivery" = String, Header, description = "Unique delivery id"),
+    ),
+    request_body = WebhookTriggerRequest,
+    responses(
+        (status = 200, body = CommonResult<TriggerResponse>, content_type = "application/json"),
+        (status = 400, description = "Invalid payload or missing delivery id"),
+        (status = 401, description = "Unknown source or invalid signature"),
+        (status = 403, description = "Path not allowed for this source"),
+        (status = 404, description = "Commit or ref not found"),
+        (status = 409, description = "Delivery is still being processed"),
+        (status = 503, description = "Build system not enabled")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn webhook_trigger(
+    state: State<MonoApiServiceState>,
+    Path(source): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<CommonResult<TriggerResponse>>, ApiError> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let service = state.services().build_trigger();
+    let response = service
+        .trigger_for_webhook(
+            &source,
+            &header(WEBHOOK_DELIVERY_HEADER),
+            &header(WEBHOOK_SIGNATURE_HEADER),
+            &body,
+        )
+        .await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// Register an inbound webhook source
+///
+/// Admin only. The secret is stored encrypted and never returned.
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateWebhookSourceRequest,
+    responses(
+        (status = 200, body = CommonResult<WebhookSourceResponse>, content_type = "application/json"),
+        (status = 400, description = "Invalid name or empty secret"),
+        (status = 403, description = "Admin access required"),
+        (status = 409, description = "A source with this name already exists")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn create_webhook_source(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+    Json(req): Json<CreateWebhookSourceRequest>,
+) -> Result<Json<CommonResult<WebhookSourceResponse>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let service = state.services().build_trigger();
+    let response = service.create_webhook_source(req, user.username).await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// List inbound webhook sources
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = 200, body = CommonResult<Vec<WebhookSourceResponse>>, content_type = "application/json"),
+        (status = 403, description = "Admin access required")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn list_webhook_sources(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+) -> Result<Json<CommonResult<Vec<WebhookSourceResponse>>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let service = state.services().build_trigger();
+    let response = service.list_webhook_sources().await?;
+    Ok(Json(CommonResult::success(Some(response))))
+}
+
+/// Delete an inbound webhook source
+///
+/// Triggers already created by the source are kept.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(
+        ("id" = i64, Path, description = "Webhook source ID")
+    ),
+    responses(
+        (status = 200, body = CommonResult<String>, content_type = "application/json"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Webhook source not found")
+    ),
+    tag = BUILD_TRIGGER_TAG
+)]
+async fn delete_webhook_source(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+) -> Result<Json<CommonResult<String>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let service = state.services().build_trigger();
+    service.delete_webhook_source(id).await?;
+    Ok(Json(CommonResult::success(None)))
+}
@@ -5,9 +5,10 @@ use axum::{
 };
 use ceres::model::{
     change_list::{
-        AssigneeUpdatePayload, CLDetailRes, ClFilesRes, FilesChangedPage, ListPayload, MergeBoxRes,
-        MuiTreeNode, UpdateBranchStatusRes, UpdateClStatusPayload,
+        AssigneeUpdatePayload, CLDetailRes, ClCommitRes, ClFilesRes, FilesChangedPage, ListPayload,
+        MergeBoxRes, MuiTreeNode, UpdateBranchStatusRes, UpdateClStatusPayload,
     },
+    check_run::{CheckRunRes, CreateCheckRunReq, UpdateCheckRunReq},
     conversation::ContentPayload,
     issue::ItemRes,
     label::LabelUpdatePayload,
@@ -19,7 +20,7 @@ use crate::api::{
     api_common::{self},
     api_doc::CL_TAG,
     error::ApiError,
-    oauth::model::LoginUser,
+    oauth::{BotAuth, ClWriteScope, ScopedTokenUser, model::LoginUser},
 };
 
 pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
@@ -30,12 +31,15 @@ pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
             .routes(routes!(cl_detail))
             .routes(routes!(merge))
             .routes(routes!(merge_box))
+            .routes(routes!(list_check_runs, create_check_run))
+            .routes(routes!(update_check_run))
             .routes(routes!(merge_no_auth))
             .routes(routes!(close_cl))
             .routes(routes!(reopen_cl))
             .routes(routes!(cl_mui_tree))
             .routes(routes!(cl_files_changed_by_page))
             .routes(routes!(cl_files_list))
+            .routes(routes!(cl_commits))
             .routes(routes!(save_comment))
             .routes(routes!(labels))
             .routes(routes!(assignees))
@@ -59,7 +63,7 @@ pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
     tag = CL_TAG
 )]
 async fn reopen_cl(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<String>>, ApiError> {
@@ -84,7 +88,7 @@ async fn reopen_cl(
     tag = CL_TAG
 )]
 async fn close_cl(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<String>>, ApiError> {
@@ -109,7 +113,7 @@ async fn close_cl(
     tag = CL_TAG
 )]
 async fn merge(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<String>>, ApiError> {
@@ -244,12 +248,34 @@ async fn cl_files_changed_by_page(
     Ok(Json(res))
 }
 
-/// Get Change List file list
+/// Get the commits pushed to a Change List, oldest first
 #[utoipa::path(
     get,
     params(
         ("link", description = "CL link"),
     ),
+    path = "/{link}/commits",
+    responses(
+        (status = 200, body = CommonResult<Vec<ClCommitRes>>, content_type = "application/json")
+    ),
+    tag = CL_TAG
+)]
+async fn cl_commits(
+    Path(link): Path<String>,
+    state: State<MonoApiServiceState>,
+) -> Result<Json<CommonResult<Vec<ClCommitRes>>>, ApiError> {
+    let commits = state.services().cl().get_cl_commits(&link).await?;
+    Ok(Json(CommonResult::success(Some(commits))))
+}
+
+/// Get Change List file list
+///
+/// Also accepts a merge queue batch link, returning the changes of every CL in the batch.
+#[utoipa::path(
+    get,
+    params(
+        ("link", description = "CL link or merge queue batch link"),
+    ),
     path = "/{link}/files-list",
     responses(
         (status = 200, body = CommonResult<Vec<ClFilesRes>>, content_type = "application/json")
@@ -260,12 +286,7 @@ async fn cl_files_list(
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<Vec<ClFilesRes>>>, ApiError> {
-    let cl = state.services().cl().get_cl_model(&link).await?;
-
-    let cl_svc = state.services().cl();
-    let old_files = cl_svc.get_commit_blobs(&cl.from_hash).await?;
-    let new_files = cl_svc.get_commit_blobs(&cl.to_hash).await?;
-    let cl_diff_files = cl_svc.cl_files_list(old_files, new_files.clone()).await?; // TODO
+    let cl_diff_files = state.services().cl().get_cl_diff_files(&link).await?;
 
     let res = cl_diff_files
         .into_iter()
@@ -310,7 +331,7 @@ async fn update_branch_status(
     tag = CL_TAG
 )]
 async fn update_branch(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
 ) -> Result<Json<CommonResult<String>>, ApiError> {
@@ -342,6 +363,81 @@ async fn merge_box(
     Ok(Json(CommonResult::success(Some(res))))
 }
 
+/// List the external check runs reported on the CL head commit
+#[utoipa::path(
+    get,
+    params(
+        ("link", description = "CL link"),
+    ),
+    path = "/{link}/check-runs",
+    responses(
+        (status = 200, body = CommonResult<Vec<CheckRunRes>>, content_type = "application/json")
+    ),
+    tag = CL_TAG
+)]
+async fn list_check_runs(
+    Path(link): Path<String>,
+    state: State<MonoApiServiceState>,
+) -> Result<Json<CommonResult<Vec<CheckRunRes>>>, ApiError> {
+    let res = state.services().cl().list_check_runs(&link).await?;
+    Ok(Json(CommonResult::success(Some(res))))
+}
+
+/// Report an external check run on the CL head commit (bot token required)
+#[utoipa::path(
+    post,
+    params(
+        ("link", description = "CL link"),
+    ),
+    path = "/{link}/check-runs",
+    request_body = CreateCheckRunReq,
+    responses(
+        (status = 200, body = CommonResult<CheckRunRes>, content_type = "application/json")
+    ),
+    tag = CL_TAG
+)]
+async fn create_check_run(
+    bot: BotAuth,
+    Path(link): Path<String>,
+    state: State<MonoApiServiceState>,
+    Json(payload): Json<CreateCheckRunReq>,
+) -> Result<Json<CommonResult<CheckRunRes>>, ApiError> {
+    let res = state
+        .services()
+        .cl()
+        .create_check_run(&link, bot.bot_id, payload)
+        .await?;
+    Ok(Json(CommonResult::success(Some(res))))
+}
+
+/// Update the status, summary or annotations of a check run (bot token required)
+#[utoipa::path(
+    patch,
+    params(
+        ("link", description = "CL link"),
+        ("id", description = "Check run ID"),
+    ),
+    path = "/{link}/check-runs/{id}",
+    request_body = UpdateCheckRunReq,
+    responses(
+        (status = 200, body = CommonResult<CheckRunRes>, content_type = "application/json")
+    ),
+    tag = CL_TAG
+)]
+async fn update_check_run(
+    bot: BotAuth,
+    Path((link, id)): Path<(String, i64)>,
+    state: State<MonoApiServiceState>,
+    Json(payload): Json<UpdateCheckRunReq>,
+) -> Result<Json<CommonResult<CheckRunRes>>, ApiError> {
+    let res = state
+        .services()
+        .cl()
+        .update_check_run(&link, id, bot.bot_id, payload)
+        .await?;
+    Ok(Json(CommonResult::success(Some(res))))
+}
+
 /// Add new comment on Change List
 #[utoipa::path(
     post,
@@ -356,7 +452,7 @@ async fn merge_box(
     tag = CL_TAG
 )]
 async fn save_comment(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
     Json(payload): Json<ContentPayload>,
@@ -366,7 +462,7 @@ async fn save_comment(
         .cl()
         .save_cl_comment(&link, &user.username, &payload.content)
         .await?;
-    api_common::comment::check_comment_ref(user, state, &payload.content, &link).await
+    api_common::comment::check_comment_ref(user.0, state, &payload.content, &link).await
 }
 
 /// Edit CL title
@@ -383,7 +479,7 @@ async fn save_comment(
     tag = CL_TAG
 )]
 async fn edit_title(
-    _: LoginUser,
+    _: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
     Json(payload): Json<ContentPayload>,
@@ -407,11 +503,11 @@ async fn edit_title(
     tag = CL_TAG
 )]
 async fn labels(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     state: State<MonoApiServiceState>,
     Json(payload): Json<LabelUpdatePayload>,
 ) -> Result<Json<CommonResult<()>>, ApiError> {
-    api_common::label_assignee::label_update(user, state, payload, String::from("cl")).await
+    api_common::label_assignee::label_update(user.0, state, payload, String::from("cl")).await
 }
 
 /// Update CL related assignees
@@ -425,11 +521,11 @@ async fn labels(
     tag = CL_TAG
 )]
 async fn assignees(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     state: State<MonoApiServiceState>,
     Json(payload): Json<AssigneeUpdatePayload>,
 ) -> Result<Json<CommonResult<()>>, ApiError> {
-    api_common::label_assignee::assignees_update(user, state, payload, String::from("cl")).await
+    api_common::label_assignee::assignees_update(user.0, state, payload, String::from("cl")).await
 }
 
 /// Update CL status (Draft or Open)
@@ -446,7 +542,7 @@ async fn assignees(
     tag = CL_TAG
 )]
 async fn update_cl_status(
-    user: LoginUser,
+    user: ScopedTokenUser<ClWriteScope>,
     Path(link): Path<String>,
     state: State<MonoApiServiceState>,
     Json(payload): Json<UpdateClStatusPayload>,
@@ -19,7 +19,7 @@ use crate::api::{
     },
     api_doc::GROUP_PERMISSION_TAG,
     error::ApiError,
-    oauth::model::LoginUser,
+    oauth::{AdminScope, ScopedTokenUser},
 };
 
 pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
@@ -57,7 +57,7 @@ pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
     tag = GROUP_PERMISSION_TAG
 )]
 async fn create_group(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Json(req): Json<CreateGroupRequest>,
 ) -> Result<Json<CommonResult<GroupResponse>>, ApiError> {
@@ -81,7 +81,7 @@ async fn create_group(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn list_groups(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Json(json): Json<PageParams<EmptyListAdditional>>,
 ) -> Result<Json<CommonResult<CommonPage<GroupResponse>>>, ApiError> {
@@ -115,7 +115,7 @@ async fn list_groups(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn get_group(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(group_id): Path<i64>,
 ) -> Result<Json<CommonResult<GroupResponse>>, ApiError> {
@@ -156,7 +156,7 @@ async fn get_group(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn update_group(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(group_id): Path<i64>,
     Json(req): Json<UpdateGroupRequest>,
@@ -193,7 +193,7 @@ async fn update_group(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn delete_group(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(group_id): Path<i64>,
 ) -> Result<Json<CommonResult<DeleteGroupResponse>>, ApiError> {
@@ -226,7 +226,7 @@ async fn delete_group(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn add_group_members(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(group_id): Path<i64>,
     Json(req): Json<AddMembersRequest>,
@@ -259,7 +259,7 @@ async fn add_group_members(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn remove_group_member(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((group_id, username)): Path<(i64, String)>,
 ) -> Result<Json<CommonResult<RemoveMemberResponse>>, ApiError> {
@@ -294,7 +294,7 @@ async fn remove_group_member(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn list_group_members(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(group_id): Path<i64>,
     Json(json): Json<PageParams<EmptyListAdditional>>,
@@ -332,7 +332,7 @@ async fn list_group_members(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn set_resource_permissions(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((resource_type, resource_id)): Path<(String, String)>,
     Json(req): Json<SetPermissionsRequest>,
@@ -368,7 +368,7 @@ async fn set_resource_permissions(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn get_resource_permissions(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((resource_type, resource_id)): Path<(String, String)>,
 ) -> Result<Json<CommonResult<Vec<ResourcePermissionResponse>>>, ApiError> {
@@ -404,7 +404,7 @@ async fn get_resource_permissions(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn update_resource_permissions(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((resource_type, resource_id)): Path<(String, String)>,
     Json(req): Json<SetPermissionsRequest>,
@@ -440,7 +440,7 @@ async fn update_resource_permissions(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn delete_resource_permissions(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((resource_type, resource_id)): Path<(String, String)>,
 ) -> Result<Json<CommonResult<DeletePermissionsResponse>>, ApiError> {
@@ -477,7 +477,7 @@ async fn delete_resource_permissions(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn get_user_groups(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path(username): Path<String>,
 ) -> Result<Json<CommonResult<UserGroupsResponse>>, ApiError> {
@@ -510,7 +510,7 @@ async fn get_user_groups(
     tag = GROUP_PERMISSION_TAG
 )]
 async fn get_user_effective_permission(
-    user: LoginUser,
+    user: ScopedTokenUser<AdminScope>,
     State(state): State<MonoApiServiceState>,
     Path((username, resource_type, resource_id)): Path<(String, String, String)>,
 ) -> Result<Json<CommonResult<UserEffectivePermissionResponse>>, ApiError> {
@@ -26,6 +26,7 @@
 //! - `lfs_process_batch`: Handles batch processing requests for Git LFS objects.
 //! - `lfs_download_object`: Handles downloading Git LFS objects.
 //! - `lfs_upload_object`: Handles uploading Git LFS objects.
+//! - `lfs_tus_status` / `lfs_tus_append`: Handle resumable uploads over the tus protocol.
 //!
 //! # Errors
 //!
@@ -42,15 +43,18 @@
 //! Ensure proper authentication and authorization mechanisms are implemented
 //! when using these handlers in a web application to prevent unauthorized access.
 use axum::{
-    Json,
+    Extension, Json,
     body::Body,
     extract::{Path, Query, State},
-    http::{Request, StatusCode},
+    http::{HeaderMap, Request, StatusCode, header},
     response::Response,
 };
-use ceres::lfs::lfs_structs::{
-    BatchRequest, BatchResponse, LockList, LockListQuery, LockRequest, LockResponse, RequestObject,
-    UnlockRequest, UnlockResponse, VerifiableLockList, VerifiableLockRequest,
+use ceres::lfs::{
+    lfs_structs::{
+        BatchRequest, BatchResponse, LockList, LockListQuery, LockRequest, LockResponse,
+        RequestObject, UnlockRequest, UnlockResponse, VerifiableLockList, VerifiableLockRequest,
+    },
+    tus::TUS_VERSION,
 };
 use common::errors::GitLFSError;
 use futures::TryStreamExt;
@@ -59,12 +63,22 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 use crate::api::{MonoApiServiceState, api_doc::LFS_TAG};
 
 const LFS_CONTENT_TYPE: &str = "application/vnd.git-lfs+json";
+
+/// Repository path of a `/{repo}.git/info/lfs/...` request, recorded before the URI is
+/// rewritten onto the shared LFS routes so batch uploads can be charged to its root directory.
+#[derive(Clone, Debug)]
+pub struct LfsRepoPath(pub String);
 const LFS_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+const TUS_CONTENT_TYPE: &str = "application/offset+octet-stream";
+const TUS_RESUMABLE: &str = "Tus-Resumable";
+const TUS_UPLOAD_OFFSET: &str = "Upload-Offset";
+const TUS_UPLOAD_LENGTH: &str = "Upload-Length";
 
 pub fn lfs_routes() -> OpenApiRouter<MonoApiServiceState> {
     OpenApiRouter::new()
         .routes(routes!(lfs_upload_object))
         .routes(routes!(lfs_download_object))
+        .routes(routes!(lfs_tus_status, lfs_tus_append))
         .routes(routes!(list_locks))
         .routes(routes!(create_lock))
         .routes(routes!(list_locks_for_verification))
@@ -108,6 +122,8 @@ fn map_lfs_error<E: ToString>(err: E) -> (StatusCode, String) {
         (StatusCode::NOT_FOUND, msg)
     } else if msg.contains("Invalid") || msg.contains("invalid") || msg.contains("Bad request") {
         (StatusCode::BAD_REQUEST, msg)
+    } else if msg.starts_with("Forbidden") {
+        (StatusCode::FORBIDDEN, msg)
     } else {
         (StatusCode::INTERNAL_SERVER_ERROR, msg)
     }
@@ -302,19 +318,22 @@ pub async fn delete_lock(
         (status = 200, description = "Batch response with object actions", body = BatchResponse, content_type = "application/vnd.git-lfs+json"),
         (status = 400, description = "Bad request", content_type = "application/vnd.git-lfs+json"),
         (status = 404, description = "Object(s) not found", content_type = "application/vnd.git-lfs+json"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 507, description = "Upload exceeds the LFS quota of the repository's root directory", content_type = "application/vnd.git-lfs+json")
     ),
     tag = LFS_TAG,
-    description = "Process LFS batch request. This handler is also available at `/info/lfs/objects/batch` for Git LFS client compatibility."
+    description = "Process LFS batch request. This handler is also available at `/{repo}.git/info/lfs/objects/batch` for Git LFS client compatibility; only requests addressed to a repository are subject to root directory quotas."
 )]
 pub async fn lfs_process_batch(
     state: State<MonoApiServiceState>,
+    repo: Option<Extension<LfsRepoPath>>,
     Json(json): Json<BatchRequest>,
 ) -> Result<Response<Body>, (StatusCode, String)> {
+    let repo_path = repo.map(|Extension(LfsRepoPath(path))| path);
     let result = state
         .services()
         .lfs()
-        .lfs_process_batch(json, state.listen_addr())
+        .lfs_process_batch(json, state.listen_addr(), repo_path.as_deref())
         .await;
 
     match result {
@@ -325,6 +344,10 @@ pub async fn lfs_process_batch(
                 .body(Body::from(body))
                 .unwrap())
         }
+        Err(err @ GitLFSError::InsufficientStorage(_)) => Ok(lfs_error_response(
+            StatusCode::INSUFFICIENT_STORAGE,
+            err.to_string(),
+        )),
         Err(err) => {
             let (code, msg) = map_lfs_error(err);
             tracing::error!("Error: {}", msg);
@@ -374,7 +397,8 @@ pub async fn lfs_download_object(
 
 /// Upload an LFS object
 ///
-/// Uploads an LFS object to the server. The object data should be sent in the request body.
+/// Uploads an LFS object to the server. The object data should be sent in the request body;
+/// it is rejected unless its size and SHA-256 match the object announced in the batch request.
 #[utoipa::path(
     put,
     path = "/objects/{object_id}",
@@ -384,7 +408,7 @@ pub async fn lfs_download_object(
     request_body(content = Vec<u8>, content_type = "application/octet-stream", description = "Object data"),
     responses(
         (status = 200, description = "Object uploaded successfully", content_type = "application/vnd.git-lfs+json"),
-        (status = 400, description = "Bad request", content_type = "application/vnd.git-lfs+json"),
+        (status = 400, description = "Size or oid mismatch", content_type = "application/vnd.git-lfs+json"),
         (status = 404, description = "Object not found", content_type = "application/vnd.git-lfs+json"),
         (status = 500, description = "Internal server error")
     ),
@@ -401,21 +425,17 @@ pub async fn lfs_upload_object(
         ..Default::default()
     };
 
-    // Collect bytes asynchronously from the stream into a Vec<u8>
-    let body_bytes: Vec<u8> = req
-        .into_body()
-        .into_data_stream()
-        .try_fold(Vec::new(), |mut acc, chunk| async move {
-            acc.extend_from_slice(&chunk);
-            Ok(acc)
-        })
-        .await
-        .unwrap();
+    // Stream the body straight into object storage; the handler verifies size and oid.
+    let body = Box::pin(
+        req.into_body()
+            .into_data_stream()
+            .map_err(std::io::Error::other),
+    );
 
     let result = state
         .services()
         .lfs()
-        .lfs_upload_object(&req_obj, body_bytes)
+        .lfs_upload_object(&req_obj, body)
         .await;
     match result {
         Ok(_) => Ok(Response::builder()
@@ -429,6 +449,153 @@ pub async fn lfs_upload_object(
     }
 }
 
+/// Rejection for tus requests that don't speak the protocol version the server supports.
+fn tus_version_mismatch(headers: &HeaderMap) -> Option<Response<Body>> {
+    if headers
+        .get(TUS_RESUMABLE)
+        .is_some_and(|v| v.as_bytes() == TUS_VERSION.as_bytes())
+    {
+        return None;
+    }
+    Some(
+        Response::builder()
+            .status(StatusCode::PRECONDITION_FAILED)
+            .header("Tus-Version", TUS_VERSION)
+            .body(Body::empty())
+            .unwrap(),
+    )
+}
+
+fn tus_response(status: StatusCode) -> axum::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header(TUS_RESUMABLE, TUS_VERSION)
+        .header(header::CACHE_CONTROL, "no-store")
+}
+
+fn tus_error_response(err: GitLFSError) -> Response<Body> {
+    let (code, msg) = match err {
+        err @ GitLFSError::Conflict(_) => (StatusCode::CONFLICT, err.to_string()),
+        err => map_lfs_error(err),
+    };
+    tus_response(code)
+        .header("Content-Type", LFS_CONTENT_TYPE)
+        .body(Body::from(
+            serde_json::json!({ "message": msg }).to_string(),
+        ))
+        .unwrap()
+}
+
+/// Get the offset of a resumable LFS upload
+///
+/// Returns how many bytes of the object the server already holds, so an interrupted
+/// upload can continue from there.
+#[utoipa::path(
+    head,
+    path = "/objects/{object_id}/tus",
+    params(
+        ("object_id" = String, Path, description = "Object ID (OID) being uploaded"),
+        ("Tus-Resumable" = String, Header, description = "tus protocol version, must be 1.0.0"),
+    ),
+    responses(
+        (status = 200, description = "Current offset in the `Upload-Offset` header and object size in `Upload-Length`"),
+        (status = 404, description = "Object not announced in a batch request"),
+        (status = 412, description = "Unsupported tus version"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = LFS_TAG,
+    description = "tus upload status. This handler is also available at `/info/lfs/objects/{object_id}/tus` for Git LFS client compatibility."
+)]
+pub async fn lfs_tus_status(
+    state: State<MonoApiServiceState>,
+    Path(oid): Path<String>,
+    headers: HeaderMap,
+) -> Response<Body> {
+    if let Some(res) = tus_version_mismatch(&headers) {
+        return res;
+    }
+    match state.services().lfs().lfs_tus_status(&oid).await {
+        Ok(status) => tus_response(StatusCode::OK)
+            .header(TUS_UPLOAD_OFFSET, status.offset)
+            .header(TUS_UPLOAD_LENGTH, status.size)
+            .body(Body::empty())
+            .unwrap(),
+        Err(err) => tus_error_response(err),
+    }
+}
+
+/// Append to a resumable LFS upload
+///
+/// Appends the request body at `Upload-Offset`. Bytes received before a dropped connection
+/// are kept; the object is verified against its size and SHA-256 once the last byte arrives.
+#[utoipa::path(
+    patch,
+    path = "/objects/{object_id}/tus",
+    params(
+        ("object_id" = String, Path, description = "Object ID (OID) being uploaded"),
+        ("Tus-Resumable" = String, Header, description = "tus protocol version, must be 1.0.0"),
+        ("Upload-Offset" = i64, Header, description = "Offset the body starts at"),
+    ),
+    request_body(content = Vec<u8>, content_type = "application/offset+octet-stream", description = "Object data from `Upload-Offset` on"),
+    responses(
+        (status = 204, description = "Data stored, new offset in the `Upload-Offset` header"),
+        (status = 400, description = "Missing offset, or size or oid mismatch", content_type = "application/vnd.git-lfs+json"),
+        (status = 404, description = "Object not announced in a batch request", content_type = "application/vnd.git-lfs+json"),
+        (status = 409, description = "Offset does not match the upload, or another request moved the upload first", content_type = "application/vnd.git-lfs+json"),
+        (status = 412, description = "Unsupported tus version"),
+        (status = 415, description = "Content type is not application/offset+octet-stream"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = LFS_TAG,
+    description = "tus upload append. This handler is also available at `/info/lfs/objects/{object_id}/tus` for Git LFS client compatibility."
+)]
+pub async fn lfs_tus_append(
+    state: State<MonoApiServiceState>,
+    Path(oid): Path<String>,
+    req: Request<Body>,
+) -> Response<Body> {
+    let headers = req.headers();
+    if let Some(res) = tus_version_mismatch(headers) {
+        return res;
+    }
+    if headers
+        .get(header::CONTENT_TYPE)
+        .is_none_or(|v| v.as_bytes() != TUS_CONTENT_TYPE.as_bytes())
+    {
+        return tus_response(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+            .body(Body::empty())
+            .unwrap();
+    }
+    let Some(offset) = headers
+        .get(TUS_UPLOAD_OFFSET)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|offset| *offset >= 0)
+    else {
+        return tus_error_response(GitLFSError::GeneralError(format!(
+            "Invalid {TUS_UPLOAD_OFFSET} header"
+        )));
+    };
+
+    let body = Box::pin(
+        req.into_body()
+            .into_data_stream()
+            .map_err(std::io::Error::other),
+    );
+    match state
+        .services()
+        .lfs()
+        .lfs_tus_append(&oid, offset, body)
+        .await
+    {
+        Ok(offset) => tus_response(StatusCode::NO_CONTENT)
+            .header(TUS_UPLOAD_OFFSET, offset)
+            .body(Body::empty())
+            .unwrap(),
+        Err(err) => tus_error_response(err),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -9,7 +9,8 @@ use ceres::model::{
         NotificationEventTypeInfo, UpdateUserNotificationConfig, UserNotificationConfig,
     },
     user::{
-        AddSSHKey, ClaContentRes, ClaSignStatusRes, ListSSHKey, ListToken, UpdateClaContentPayload,
+        AddSSHKey, ClaContentRes, ClaSignStatusRes, GenerateTokenPayload, GeneratedToken,
+        ListSSHKey, ListToken, UpdateClaContentPayload,
     },
 };
 use common::errors::MegaError;
@@ -136,23 +137,27 @@ async fn list_key(
     Ok(Json(CommonResult::success(Some(res))))
 }
 
-/// Generate Token For http push
+/// Generate a scoped personal access token for the API and Git HTTP.
+///
+/// The plaintext token is only returned in this response.
 #[utoipa::path(
     post,
     path = "/token/generate",
+    request_body = GenerateTokenPayload,
     responses(
-        (status = 200, body = CommonResult<String>, content_type = "application/json")
+        (status = 200, body = CommonResult<GeneratedToken>, content_type = "application/json")
     ),
     tag = USER_TAG
 )]
 async fn generate_token(
     user: LoginUser,
     state: State<MonoApiServiceState>,
-) -> Result<Json<CommonResult<String>>, ApiError> {
+    Json(payload): Json<GenerateTokenPayload>,
+) -> Result<Json<CommonResult<GeneratedToken>>, ApiError> {
     let res = state
         .services()
         .user()
-        .generate_user_token(user.username)
+        .generate_user_token(user.username, payload)
         .await?;
     Ok(Json(CommonResult::success(Some(res))))
 }
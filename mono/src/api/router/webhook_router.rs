@@ -3,16 +3,27 @@ use axum::{
     Json,
     extract::{Path, Query, State},
 };
-use ceres::model::webhook::{CreateWebhookRequest, ListWebhooksQuery, WebhookResponse};
+use ceres::model::webhook::{
+    CreateWebhookRequest, ListWebhooksQuery, WebhookDeliveryResponse, WebhookResponse,
+};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
-use crate::api::{MonoApiServiceState, api_doc::WEBHOOK_TAG, error::ApiError};
+use crate::api::{
+    MonoApiServiceState,
+    api_common::group_permission::ensure_admin,
+    api_doc::WEBHOOK_TAG,
+    error::ApiError,
+    oauth::{AdminScope, ScopedTokenUser},
+};
 
 pub fn routers() -> OpenApiRouter<MonoApiServiceState> {
     OpenApiRouter::new()
         .routes(routes!(create_webhook))
         .routes(routes!(list_webhooks))
         .routes(routes!(delete_webhook))
+        .routes(routes!(list_webhook_deliveries))
+        .routes(routes!(redeliver_webhook_delivery))
+        .routes(routes!(ping_webhook))
 }
 
 /// Create a webhook
@@ -77,6 +88,94 @@ async fn delete_webhook(
     Ok(Json(CommonResult::success(None)))
 }
 
+/// List recent deliveries of a webhook
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    params(
+        ("id", description = "Webhook ID"),
+        ("page" = Option<u64>, Query, description = "Page number, starts from 1. Default: 1"),
+        ("per_page" = Option<u64>, Query, description = "Items per page. Default: 20")
+    ),
+    responses(
+        (status = 200, body = CommonResult<CommonPage<WebhookDeliveryResponse>>, content_type = "application/json"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = WEBHOOK_TAG
+)]
+async fn list_webhook_deliveries(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+    Query(query): Query<ListWebhooksQuery>,
+) -> Result<Json<CommonResult<CommonPage<WebhookDeliveryResponse>>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let pagination = build_webhook_pagination(query)?;
+    let (items, total) = state
+        .services()
+        .webhook()
+        .list_webhook_deliveries(id, pagination)
+        .await?;
+    Ok(Json(CommonResult::success(Some(CommonPage {
+        total,
+        items,
+    }))))
+}
+
+/// Redeliver a webhook delivery
+///
+/// Queues the payload of the delivery again; the new delivery references the original one.
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+    params(
+        ("id", description = "Webhook ID"),
+        ("delivery_id", description = "Delivery ID")
+    ),
+    responses(
+        (status = 200, body = CommonResult<WebhookDeliveryResponse>, content_type = "application/json"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Delivery not found")
+    ),
+    tag = WEBHOOK_TAG
+)]
+async fn redeliver_webhook_delivery(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+    Path((id, delivery_id)): Path<(i64, i64)>,
+) -> Result<Json<CommonResult<WebhookDeliveryResponse>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let queued = state
+        .services()
+        .webhook()
+        .redeliver_webhook_delivery(id, delivery_id)
+        .await?;
+    Ok(Json(CommonResult::success(Some(queued))))
+}
+
+/// Send a test ping to a webhook
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/ping",
+    params(("id", description = "Webhook ID")),
+    responses(
+        (status = 200, body = CommonResult<WebhookDeliveryResponse>, content_type = "application/json"),
+        (status = 403, description = "Admin access required"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = WEBHOOK_TAG
+)]
+async fn ping_webhook(
+    user: ScopedTokenUser<AdminScope>,
+    state: State<MonoApiServiceState>,
+    Path(id): Path<i64>,
+) -> Result<Json<CommonResult<WebhookDeliveryResponse>>, ApiError> {
+    ensure_admin(&state, &user).await?;
+    let queued = state.services().webhook().ping_webhook(id).await?;
+    Ok(Json(CommonResult::success(Some(queued))))
+}
+
 fn build_webhook_pagination(query: ListWebhooksQuery) -> Result<Pagination, ApiError> {
     let mut pagination = Pagination::default();
     if let Some(page) = query.page {
@@ -10,33 +10,51 @@ use bytes::{Bytes, BytesMut};
 use ceres::{
     TransportRuntime,
     infra::pack_stream::into_pack_byte_stream,
-    transport::protocol::{PushUserInfo, ServiceType, SmartSession, TransportProtocol, smart},
+    transport::protocol::{
+        ProtocolVersion, PushUserInfo, ServiceType, SmartSession, TransportProtocol, smart,
+    },
+};
+use common::{
+    enums::TokenScope,
+    errors::{ProtocolError, mega_to_protocol_error},
 };
-use common::errors::{ProtocolError, mega_to_protocol_error};
 use futures::{TryStreamExt, stream};
 use http::header::AUTHORIZATION;
 use tokio::io::AsyncReadExt;
 use tokio_stream::StreamExt;
 
 use crate::{
-    api::oauth::{bearer_token_from_authorization_value, login_user_from_mono_access_token},
+    api::oauth::{bearer_token_from_authorization_value, resolve_mono_access_token},
     git_protocol::InfoRefsParams,
 };
 
+const GIT_PROTOCOL_HEADER: &str = "Git-Protocol";
+
 // # Discovering Reference
 // HTTP clients that support the "smart" protocol (or both the "smart" and "dumb" protocols) MUST
 // discover references by making a parameterized request for the info/refs file of the repository.
 // The request MUST contain exactly one query parameter, service=$servicename,
 // where $servicename MUST be the service name the client wishes to contact to complete the operation.
 // The request MUST NOT contain additional query parameters.
+//
+// Clients asking for protocol v2 send `Git-Protocol: version=2`, in which case upload-pack
+// answers with the v2 capability advertisement instead of the ref list.
 pub async fn git_info_refs(
     state: &TransportRuntime,
     params: InfoRefsParams,
     repo_path: std::path::PathBuf,
+    headers: &http::HeaderMap,
 ) -> Result<Response<Body>, ProtocolError> {
     let service_name = params.service.unwrap();
     let service_type = service_name.parse::<ServiceType>().unwrap();
-    let session = SmartSession::new(repo_path, service_type, TransportProtocol::Http);
+    let mut session = SmartSession::new(repo_path, service_type, TransportProtocol::Http);
+    session.protocol_version = protocol_version_from_headers(headers);
+    // Credentials are optional here; Cedar policies decide whether anonymous access is allowed.
+    if git_token_auth(state, &mut session, headers, required_scope(service_type)).await?
+        == TokenAuth::Rejected
+    {
+        return auth_failed();
+    }
     let pkt_line_stream = session.git_info_refs(state).await?;
 
     let content_type = format!("application/x-{service_name}-advertisement");
@@ -49,6 +67,15 @@ pub async fn git_info_refs(
     Ok(response)
 }
 
+/// Reads the wire protocol version requested through the `Git-Protocol` header.
+fn protocol_version_from_headers(headers: &http::HeaderMap) -> ProtocolVersion {
+    headers
+        .get(GIT_PROTOCOL_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(ProtocolVersion::from_git_protocol)
+        .unwrap_or_default()
+}
+
 fn auth_failed() -> Result<Response<Body>, ProtocolError> {
     let resp = Response::builder()
         .status(401)
@@ -75,13 +102,25 @@ fn basic_auth_password_from_authorization_value(value: &str) -> Option<String> {
     Some(decoded_str.split(':').nth(1)?.to_owned())
 }
 
-/// Uses [`crate::api::oauth::login_user_from_mono_access_token`] (same as [`crate::api::oauth::AccessTokenUser`]).
-/// Supports both Bearer tokens and Basic Auth (with token as password).
-async fn git_receive_pack_auth(
+/// Outcome of resolving the access token sent with a Git HTTP request.
+#[derive(Debug, PartialEq, Eq)]
+enum TokenAuth {
+    /// No credentials were sent.
+    Anonymous,
+    /// The token is valid, grants the required scope and its owner is recorded on the session.
+    Authenticated,
+    /// A token was sent but is unknown, expired or lacks the required scope.
+    Rejected,
+}
+
+/// Uses [`crate::api::oauth::resolve_mono_access_token`] (same as [`crate::api::oauth::AccessTokenUser`]).
+/// Supports both Bearer tokens and Basic Auth (with token as password); the token needs `required`.
+async fn git_token_auth(
     state: &TransportRuntime,
     pack_protocol: &mut SmartSession,
     headers: &http::HeaderMap,
-) -> Result<bool, ProtocolError> {
+    required: TokenScope,
+) -> Result<TokenAuth, ProtocolError> {
     let auth_header = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok());
 
     // Try Bearer token first
@@ -93,20 +132,36 @@ async fn git_receive_pack_auth(
         .or_else(|| auth_header.and_then(basic_auth_password_from_authorization_value));
 
     let Some(token) = token else {
-        return Ok(false);
+        return Ok(TokenAuth::Anonymous);
     };
 
-    let user = login_user_from_mono_access_token(&state.storage.user_storage(), &token)
+    let user = resolve_mono_access_token(&state.storage.user_storage(), &token)
         .await
         .map_err(mega_to_protocol_error)?;
     let Some(user) = user else {
-        return Ok(false);
+        return Ok(TokenAuth::Rejected);
     };
+    if !user.has_scope(required) {
+        tracing::warn!(
+            "rejecting {} from {}: access token lacks the `{required}` scope",
+            pack_protocol.service_type,
+            user.user.username
+        );
+        return Ok(TokenAuth::Rejected);
+    }
 
-    let username = user.username;
+    let username = user.user.username;
     pack_protocol.auth.username = Some(username.clone());
     pack_protocol.auth.authenticated_user = Some(PushUserInfo { username });
-    Ok(true)
+    Ok(TokenAuth::Authenticated)
+}
+
+/// Scope a token must carry for the session's service.
+fn required_scope(service_type: ServiceType) -> TokenScope {
+    match service_type {
+        ServiceType::UploadPack => TokenScope::RepoRead,
+        ServiceType::ReceivePack => TokenScope::RepoWrite,
+    }
 }
 
 /// # Handles a Git upload pack request and prepares the response.
@@ -118,8 +173,10 @@ async fn git_receive_pack_auth(
 /// of the request body using `body.next().await`. The chunks are concatenated into the `upload_request`
 /// buffer.
 ///
-/// The `pack_protocol` is then used to process the `upload_request` using the `git_upload_pack` method.
-/// It returns the `send_pack_data` and `buf` containing the response data.
+/// The `pack_protocol` is then used to process the `upload_request` using the `git_upload_pack` method,
+/// or `git_command_v2` when the client negotiated protocol v2 through the `Git-Protocol` header.
+/// It returns the `send_pack_data` and `buf` containing the response data; v2 commands other than
+/// `fetch` and v0 shallow negotiation rounds have no pack data and the `buf` is the whole response.
 ///
 /// A response header is constructed using the `build_res_header` function with a content type of
 /// "application/x-git-upload-pack-result". The response body channel is created using `Body::channel()`.
@@ -136,6 +193,18 @@ pub async fn git_upload_pack(
 ) -> Result<Response<Body>, ProtocolError> {
     let mut pack_protocol =
         SmartSession::new(repo_path, ServiceType::UploadPack, TransportProtocol::Http);
+    pack_protocol.protocol_version = protocol_version_from_headers(req.headers());
+    if git_token_auth(
+        state,
+        &mut pack_protocol,
+        req.headers(),
+        TokenScope::RepoRead,
+    )
+    .await?
+        == TokenAuth::Rejected
+    {
+        return auth_failed();
+    }
     let upload_request: BytesMut = req
         .into_body()
         .into_data_stream()
@@ -146,31 +215,41 @@ pub async fn git_upload_pack(
         .await
         .unwrap();
     tracing::debug!("Receive bytes: <-------- {:?}", upload_request);
-    let (mut send_pack_data, protocol_buf) = pack_protocol
-        .git_upload_pack(state, &mut upload_request.freeze())
-        .await?;
+    let (send_pack_data, protocol_buf) = match pack_protocol.protocol_version {
+        ProtocolVersion::V2 => pack_protocol
+            .git_command_v2(state, &mut upload_request.freeze())
+            .await?
+            .unwrap_or_default(),
+        ProtocolVersion::V0 => {
+            pack_protocol
+                .git_upload_pack(state, &mut upload_request.freeze())
+                .await?
+        }
+    };
 
     let body_stream = async_stream::stream! {
         tracing::info!("send ack/nak message buf: --------> {:?}", &protocol_buf);
         yield Ok::<_, Infallible>(Bytes::copy_from_slice(&protocol_buf));
-        // send packdata with sideband64k
-        while let Some(chunk) = send_pack_data.next().await {
-            let mut reader = chunk.as_slice();
-            loop {
-                let mut temp = BytesMut::new();
-                temp.reserve(65500);
-                let length = reader.read_buf(&mut temp).await.unwrap();
-                if length == 0 {
-                    break;
+        if let Some(mut send_pack_data) = send_pack_data {
+            // send packdata with sideband64k
+            while let Some(chunk) = send_pack_data.next().await {
+                let mut reader = chunk.as_slice();
+                loop {
+                    let mut temp = BytesMut::new();
+                    temp.reserve(65500);
+                    let length = reader.read_buf(&mut temp).await.unwrap();
+                    if length == 0 {
+                        break;
+                    }
+                    let bytes_out = pack_protocol.build_side_band_format(temp, length);
+                    // tracing::info!("send pack file: length: {:?}", bytes_out.len());
+                    yield Ok::<_, Infallible>(bytes_out.freeze());
                 }
-                let bytes_out = pack_protocol.build_side_band_format(temp, length);
-                // tracing::info!("send pack file: length: {:?}", bytes_out.len());
-                yield Ok::<_, Infallible>(bytes_out.freeze());
             }
+            let bytes_out = Bytes::from_static(smart::PKT_LINE_END_MARKER);
+            tracing::info!("send back pkt-flush line '0000', actually: {:?}", bytes_out);
+            yield Ok::<_, Infallible>(bytes_out);
         }
-        let bytes_out = Bytes::from_static(smart::PKT_LINE_END_MARKER);
-        tracing::info!("send back pkt-flush line '0000', actually: {:?}", bytes_out);
-        yield Ok::<_, Infallible>(bytes_out);
     };
     let response = add_default_header(
         String::from("application/x-git-upload-pack-result"),
@@ -203,7 +282,15 @@ pub async fn git_receive_pack(
 ) -> Result<Response<Body>, ProtocolError> {
     let mut pack_protocol =
         SmartSession::new(repo_path, ServiceType::ReceivePack, TransportProtocol::Http);
-    if !git_receive_pack_auth(state, &mut pack_protocol, req.headers()).await? {
+    if git_token_auth(
+        state,
+        &mut pack_protocol,
+        req.headers(),
+        TokenScope::RepoWrite,
+    )
+    .await?
+        != TokenAuth::Authenticated
+    {
         return auth_failed();
     }
     // Convert the request body into a data stream.
@@ -260,4 +347,16 @@ fn add_default_header<T>(content_type: String, mut response: Response<T>) -> Res
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use ceres::transport::protocol::ProtocolVersion;
+
+    use super::protocol_version_from_headers;
+
+    #[test]
+    fn test_protocol_version_from_headers() {
+        let mut headers = http::HeaderMap::new();
+        assert_eq!(protocol_version_from_headers(&headers), ProtocolVersion::V0);
+        headers.insert("git-protocol", "version=2".parse().unwrap());
+        assert_eq!(protocol_version_from_headers(&headers), ProtocolVersion::V2);
+    }
+}
@@ -1,7 +1,7 @@
 use api_model::common::CommonResult;
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use common::errors::{ProtocolError, protocol_error_http_status, protocol_error_is_client_safe};
@@ -23,7 +23,16 @@ pub fn into_response(err: ProtocolError) -> Response {
         "Something went wrong".to_owned()
     };
 
-    (status, Json(CommonResult::<String>::failed(&response_msg))).into_response()
+    let mut response =
+        (status, Json(CommonResult::<String>::failed(&response_msg))).into_response();
+    // Let Git clients prompt for credentials when anonymous access is denied.
+    if status == StatusCode::UNAUTHORIZED {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"Mega\", Bearer realm=\"Mega\""),
+        );
+    }
+    response
 }
 
 /// User-visible message for SSH stderr / channel data.
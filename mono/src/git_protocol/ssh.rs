@@ -4,14 +4,18 @@ use bytes::{Bytes, BytesMut};
 use ceres::{
     TransportRuntime,
     infra::pack_stream::into_pack_byte_stream,
-    lfs::lfs_structs::Link,
+    lfs::{
+        lfs_structs::{LfsUploadScope, Link, Operation},
+        transfer::{LfsTransferSession, TransferResponse},
+    },
     transport::protocol::{
-        ServiceType, SmartSession, TransportProtocol,
+        ProtocolVersion, PushUserInfo, ServiceType, SmartSession, TransportProtocol,
         smart::{self},
+        smart_v2,
     },
 };
 use chrono::{DateTime, Duration, Utc};
-use common::errors::ProtocolError;
+use common::errors::{GitLFSError, ProtocolError};
 use futures::{StreamExt, stream};
 use russh::{
     Channel, ChannelId,
@@ -19,6 +23,7 @@ use russh::{
     server::{self, Auth, Msg, Session},
 };
 use tokio::{io::AsyncReadExt, sync::Mutex};
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::git_protocol::{
     http::search_subsequence,
@@ -34,6 +39,12 @@ pub struct SshServer {
     pub smart_protocol: Option<SmartSession>,
     pub state: TransportRuntime,
     pub data_combined: BytesMut,
+    /// Protocol version requested by the client through the `GIT_PROTOCOL` environment variable.
+    pub protocol_version: ProtocolVersion,
+    /// Set while the channel runs `git-lfs-transfer` instead of a Git service.
+    pub lfs_transfer: Option<Arc<Mutex<LfsTransferSession>>>,
+    /// Owner of the public key the client authenticated with.
+    pub username: Option<String>,
 }
 
 impl server::Server for SshServer {
@@ -61,6 +72,22 @@ impl server::Handler for SshServer {
         Ok(true)
     }
 
+    /// Git forwards `GIT_PROTOCOL` (e.g. `version=2`) with `SendEnv` before running the command.
+    async fn env_request(
+        &mut self,
+        channel: ChannelId,
+        variable_name: &str,
+        variable_value: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        tracing::debug!("env_request: {}={}", variable_name, variable_value);
+        if variable_name == "GIT_PROTOCOL" {
+            self.protocol_version = ProtocolVersion::from_git_protocol(variable_value);
+        }
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
     /// # Executes a request on the SSH server.
     ///
     /// This function processes the received data from the specified channel and performs the
@@ -84,12 +111,18 @@ impl server::Handler for SshServer {
         // Push: git-receive-pack '/path/to/repo.git'
         // Pull: git-upload-pack '/path/to/repo.git'
         // LFS HTTP Authenticate: git-lfs-authenticate '/path/to/repo.git' download/upload
+        // LFS SSH transfer: git-lfs-transfer '/path/to/repo.git' download/upload
         let command: Vec<_> = data.split(' ').collect();
         let path = command[1];
         let path = path.replace(".git", "").replace('\'', "");
         let service_type = ServiceType::from_str(command[0]).unwrap_or(ServiceType::UploadPack);
-        let smart_protocol =
+        let mut smart_protocol =
             SmartSession::new(PathBuf::from(&path), service_type, TransportProtocol::Ssh);
+        smart_protocol.protocol_version = self.protocol_version;
+        if let Some(username) = self.username.clone() {
+            smart_protocol.auth.username = Some(username.clone());
+            smart_protocol.auth.authenticated_user = Some(PushUserInfo { username });
+        }
         match command[0] {
             "git-upload-pack" | "git-receive-pack" => {
                 match smart_protocol.git_info_refs(&self.state).await {
@@ -103,10 +136,35 @@ impl server::Handler for SshServer {
                     }
                 }
             }
-            //Note that currently mega does not support pure ssh to transfer files, still relay on the https server.
-            //see https://github.com/git-lfs/git-lfs/blob/main/docs/proposals/ssh_adapter.md for more details about pure ssh file transfer.
+            // Pure SSH transfer, see
+            // https://github.com/git-lfs/git-lfs/blob/main/docs/proposals/ssh_adapter.md
             "git-lfs-transfer" => {
-                session.data(channel, "not implemented yet".as_bytes().to_vec())?;
+                let operation = match command.get(2) {
+                    Some(&"upload") => Operation::Upload,
+                    Some(&"download") => Operation::Download,
+                    _ => {
+                        session.extended_data(
+                            channel,
+                            1,
+                            "git-lfs-transfer: expected upload or download operation\n"
+                                .as_bytes()
+                                .to_vec(),
+                        )?;
+                        session.exit_status_request(channel, 1)?;
+                        session.close(channel)?;
+                        return Ok(());
+                    }
+                };
+                let lfs_service = self.state.storage.lfs_service.clone();
+                let scope = LfsUploadScope::for_repo(&self.state.storage.config().lfs, &path);
+                self.lfs_transfer = Some(Arc::new(Mutex::new(LfsTransferSession::new(
+                    lfs_service,
+                    operation,
+                    scope,
+                    self.username.clone(),
+                ))));
+                session.data(channel, LfsTransferSession::capabilities().to_vec())?;
+                session.channel_success(channel)?;
             }
             // When connecting over SSH, the first attempt will be made to use
             // `git-lfs-transfer`, the pure SSH protocol, and if it fails, Git LFS will fall
@@ -146,8 +204,9 @@ impl server::Handler for SshServer {
             .search_ssh_key_finger(&fingerprint)
             .await
             .unwrap();
-        if !res.is_empty() {
+        if let Some(key) = res.first() {
             tracing::info!("Client public key verified successfully!");
+            self.username = Some(key.username.clone());
             Ok(Auth::Accept)
         } else {
             tracing::warn!("Client public key verification failed!");
@@ -164,6 +223,11 @@ impl server::Handler for SshServer {
         data: &[u8],
         session: &mut Session,
     ) -> Result<(), Self::Error> {
+        if let Some(lfs_transfer) = self.lfs_transfer.clone() {
+            return self
+                .handle_lfs_transfer(&lfs_transfer, channel, data, session)
+                .await;
+        }
         let smart_protocol = self.smart_protocol.as_mut().unwrap();
         tracing::info!(
             "receiving data length:{}",
@@ -171,7 +235,11 @@ impl server::Handler for SshServer {
             data.len()
         );
         let service_type = smart_protocol.service_type;
+        let protocol_version = smart_protocol.protocol_version;
         match service_type {
+            ServiceType::UploadPack if protocol_version == ProtocolVersion::V2 => {
+                self.handle_upload_pack_v2(channel, data, session).await?;
+            }
             ServiceType::UploadPack => {
                 self.handle_upload_pack(channel, data, session).await?;
             }
@@ -215,6 +283,59 @@ impl SshServer {
         Ok(())
     }
 
+    async fn handle_lfs_transfer(
+        &mut self,
+        lfs_transfer: &Mutex<LfsTransferSession>,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), anyhow::Error> {
+        let mut lfs_transfer = lfs_transfer.lock().await;
+        let result = match lfs_transfer.receive(data).await {
+            Ok(responses) => Self::send_lfs_responses(session, channel, responses).await?,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => {
+                if lfs_transfer.is_closed() {
+                    session.exit_status_request(channel, 0)?;
+                    session.eof(channel)?;
+                    session.close(channel)?;
+                }
+            }
+            Err(err) => {
+                tracing::warn!("git-lfs-transfer failed: {}", err);
+                session.extended_data(channel, 1, format!("{err}\n").into_bytes())?;
+                session.exit_status_request(channel, 1)?;
+                session.close(channel)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends `git-lfs-transfer` responses, streaming object downloads chunk by chunk.
+    /// The inner error is a storage failure that interrupted a download.
+    async fn send_lfs_responses(
+        session: &mut Session,
+        channel: ChannelId,
+        responses: Vec<TransferResponse>,
+    ) -> Result<Result<(), GitLFSError>, anyhow::Error> {
+        for response in responses {
+            match response {
+                TransferResponse::Complete(bytes) => session.data(channel, bytes.to_vec())?,
+                TransferResponse::Streaming(mut stream) => {
+                    while let Some(chunk) = stream.next().await {
+                        match chunk {
+                            Ok(chunk) => session.data(channel, chunk.to_vec())?,
+                            Err(err) => return Ok(Err(err)),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Ok(()))
+    }
+
     async fn handle_upload_pack(
         &mut self,
         channel: ChannelId,
@@ -223,7 +344,7 @@ impl SshServer {
     ) -> Result<(), anyhow::Error> {
         let smart_protocol = self.smart_protocol.as_mut().unwrap();
 
-        let (mut send_pack_data, buf) = match smart_protocol
+        let (send_pack_data, buf) = match smart_protocol
             .git_upload_pack(&self.state, &mut Bytes::copy_from_slice(data))
             .await
         {
@@ -239,6 +360,63 @@ impl SshServer {
             .data(channel, String::from_utf8(buf.to_vec())?)
             .map_err(anyhow::Error::from)?;
 
+        if let Some(mut send_pack_data) = send_pack_data {
+            Self::send_pack_data(session, channel, smart_protocol, &mut send_pack_data).await?;
+        }
+        Ok(())
+    }
+
+    /// Protocol v2 over SSH is stateful: the client may issue several commands (typically
+    /// `ls-refs` then `fetch`) on the same channel, each terminated by a flush packet, so
+    /// requests are buffered until complete and answered one by one.
+    async fn handle_upload_pack_v2(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), anyhow::Error> {
+        self.data_combined.extend_from_slice(data);
+        loop {
+            let mut request = match smart_v2::split_command_request(&mut self.data_combined) {
+                Ok(Some(request)) => request,
+                Ok(None) => break,
+                Err(err) => {
+                    // Nothing after a malformed length can be framed.
+                    self.data_combined.clear();
+                    Self::send_protocol_error(session, channel, err)?;
+                    return Ok(());
+                }
+            };
+            let smart_protocol = self.smart_protocol.as_mut().unwrap();
+            let (send_pack_data, buf) = match smart_protocol
+                .git_command_v2(&self.state, &mut request)
+                .await
+            {
+                Ok(Some(v)) => v,
+                // An empty request ends the session, the client closes the channel next.
+                Ok(None) => continue,
+                Err(err) => {
+                    Self::send_protocol_error(session, channel, err)?;
+                    return Ok(());
+                }
+            };
+
+            tracing::info!("buf is {:?}", buf);
+            session.data(channel, buf.to_vec())?;
+            if let Some(mut send_pack_data) = send_pack_data {
+                Self::send_pack_data(session, channel, smart_protocol, &mut send_pack_data).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Streams pack data in the sideband format, followed by a flush packet.
+    async fn send_pack_data(
+        session: &mut Session,
+        channel: ChannelId,
+        smart_protocol: &SmartSession,
+        send_pack_data: &mut ReceiverStream<Vec<u8>>,
+    ) -> Result<(), anyhow::Error> {
         while let Some(chunk) = send_pack_data.next().await {
             let mut reader = chunk.as_slice();
             loop {
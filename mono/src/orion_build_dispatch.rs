@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use api_model::buck2::types::{BuildStatus, TargetStatusResponse, TestResultResponse};
 use async_trait::async_trait;
 use ceres::application::build_trigger::BuildDispatchPort;
 use common::errors::MegaError;
@@ -35,4 +36,35 @@ impl BuildDispatchPort for OrionBuildDispatch {
             .await
             .map_err(|e| MegaError::Other(format!("Failed to dispatch build to Orion: {e}")))
     }
+
+    fn required_targets(&self, path: &str) -> Vec<String> {
+        self.inner.required_targets(path).to_vec()
+    }
+
+    async fn latest_build_result(&self, task_id: &str) -> Result<Option<BuildStatus>, MegaError> {
+        self.inner
+            .latest_build_result(task_id)
+            .await
+            .map_err(|e| MegaError::Other(format!("Failed to query Orion build result: {e}")))
+    }
+
+    async fn target_statuses(&self, task_id: &str) -> Result<Vec<TargetStatusResponse>, MegaError> {
+        self.inner
+            .target_statuses(task_id)
+            .await
+            .map_err(|e| MegaError::Other(format!("Failed to query Orion target status: {e}")))
+    }
+
+    async fn test_results(&self, task_id: &str) -> Result<Vec<TestResultResponse>, MegaError> {
+        self.inner
+            .test_results(task_id)
+            .await
+            .map_err(|e| MegaError::Other(format!("Failed to query Orion test results: {e}")))
+    }
+
+    async fn quarantined_targets(&self, path: &str) -> Result<Vec<String>, MegaError> {
+        self.inner.quarantined_targets(path).await.map_err(|e| {
+            MegaError::Other(format!("Failed to query Orion quarantined targets: {e}"))
+        })
+    }
 }
@@ -12,7 +12,12 @@ use axum::{
 };
 use ceres::{
     TransportRuntime,
-    application::{api_service::cache::GitObjectCache, artifact::ArtifactApplicationService},
+    application::{
+        api_service::{cache::GitObjectCache, mono::MonoAppServices},
+        artifact::ArtifactApplicationService,
+        webhook::WebhookOutboxWorker,
+    },
+    lfs::gc,
 };
 use common::errors::ProtocolError;
 use http::{HeaderName, HeaderValue, Method};
@@ -143,6 +148,15 @@ fn spawn_email_dispatcher_task(ctx: AppContext, token: CancellationToken) -> Joi
     })
 }
 
+/// Spawns the worker that sends queued webhook deliveries and their retries
+fn spawn_webhook_outbox_task(ctx: AppContext, token: CancellationToken) -> JoinHandle<()> {
+    let worker = WebhookOutboxWorker::from_storage(&ctx.storage);
+
+    tokio::spawn(async move {
+        worker.run(token).await;
+    })
+}
+
 /// Background GC for `artifact_objects` with no manifest references (`docs/artifacts-protocol.md` §10.6).
 fn spawn_artifact_gc_task(ctx: AppContext, token: CancellationToken) -> Option<JoinHandle<()>> {
     let cfg = ctx.storage.config().artifacts_gc.clone();
@@ -197,6 +211,116 @@ fn spawn_artifact_gc_task(ctx: AppContext, token: CancellationToken) -> Option<J
     }))
 }
 
+/// Spawns periodic garbage collection of unreferenced Git LFS objects.
+///
+/// Returns `None` if `lfs.gc.enable` is false.
+fn spawn_lfs_gc_task(ctx: AppContext, token: CancellationToken) -> Option<JoinHandle<()>> {
+    let cfg = ctx.storage.config().lfs.gc.clone();
+    if !cfg.enable {
+        return None;
+    }
+
+    let interval_secs = cfg.interval_secs.max(1);
+    let grace_secs = cfg.grace_secs;
+    let batch_limit = cfg.batch_limit.max(1);
+    let storage = ctx.storage.clone();
+
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        tracing::info!(
+            "LFS GC task started (interval={interval_secs}s, grace={grace_secs}s, batch_limit={batch_limit})"
+        );
+
+        let grace = std::time::Duration::from_secs(grace_secs);
+        let mut marks = gc::LfsGcMarks::default();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    match gc::gc_unreferenced_lfs_objects_once(&storage, &mut marks, grace, batch_limit).await {
+                        Ok(s) if s.deleted > 0 || s.candidates > 0 || s.aborted_uploads > 0 => {
+                            tracing::info!(
+                                candidates = s.candidates,
+                                deleted = s.deleted,
+                                skipped_still_referenced = s.skipped_still_referenced,
+                                skipped_recently_seen = s.skipped_recently_seen,
+                                storage_delete_errors = s.storage_delete_errors,
+                                db_delete_errors = s.db_delete_errors,
+                                aborted_uploads = s.aborted_uploads,
+                                "LFS GC tick"
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(e) => tracing::error!(error = %e, "LFS GC tick failed"),
+                    }
+                }
+                _ = token.cancelled() => {
+                    tracing::info!("LFS GC task received shutdown signal");
+                    break;
+                }
+            }
+        }
+
+        tracing::info!("LFS GC task stopped gracefully");
+    }))
+}
+
+/// Interval between scans for due build schedules and finished watched builds.
+const BUILD_SCHEDULE_TICK_SECS: u64 = 30;
+
+/// Spawns the scheduler that fires cron build schedules once they are due and emits
+/// `build.completed` webhooks for watched builds once they finish.
+///
+/// Returns `None` if the build system is disabled in configuration.
+fn spawn_build_schedule_task(ctx: AppContext, token: CancellationToken) -> Option<JoinHandle<()>> {
+    let config = ctx.storage.config();
+    if !config.build.enable_build {
+        return None;
+    }
+
+    let git_object_cache = Arc::new(GitObjectCache {
+        connection: ctx.connection.clone(),
+        prefix: "git-object-rkyv:v1".to_string(),
+    });
+    let build_dispatch =
+        OrionBuildDispatch::new(Arc::new(OrionBuildClient::new(config.build.clone()))).into_arc();
+    let services =
+        MonoAppServices::new(ctx.storage.clone(), git_object_cache, Some(build_dispatch));
+
+    Some(tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(std::time::Duration::from_secs(BUILD_SCHEDULE_TICK_SECS));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        tracing::info!("Build schedule task started (interval={BUILD_SCHEDULE_TICK_SECS}s)");
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    match services.build_trigger().run_due_schedules().await {
+                        Ok(0) => {}
+                        Ok(fired) => tracing::info!(fired, "Fired scheduled builds"),
+                        Err(e) => tracing::error!(error = %e, "Build schedule tick failed"),
+                    }
+                    match services.build_trigger().notify_completed_builds().await {
+                        Ok(0) => {}
+                        Ok(reported) => tracing::info!(reported, "Reported completed builds"),
+                        Err(e) => tracing::error!(error = %e, "Build completion tick failed"),
+                    }
+                }
+                _ = token.cancelled() => {
+                    tracing::info!("Build schedule task received shutdown signal");
+                    break;
+                }
+            }
+        }
+
+        tracing::info!("Build schedule task stopped gracefully");
+    }))
+}
+
 /// Returns a future that completes when the cancellation token is triggered.
 async fn shutdown_signal(token: CancellationToken) {
     token.cancelled().await;
@@ -210,7 +334,10 @@ pub async fn start_http(ctx: AppContext, options: CommonHttpOptions) {
     let shutdown_token = CancellationToken::new();
     let cleanup_handle = spawn_cleanup_task(ctx.clone(), shutdown_token.clone());
     let dispatcher_handle = spawn_email_dispatcher_task(ctx.clone(), shutdown_token.clone());
+    let webhook_outbox_handle = spawn_webhook_outbox_task(ctx.clone(), shutdown_token.clone());
     let artifact_gc_handle = spawn_artifact_gc_task(ctx.clone(), shutdown_token.clone());
+    let lfs_gc_handle = spawn_lfs_gc_task(ctx.clone(), shutdown_token.clone());
+    let build_schedule_handle = spawn_build_schedule_task(ctx.clone(), shutdown_token.clone());
     let server_token = shutdown_token.clone();
 
     let app = app(ctx, host.clone(), port).await;
@@ -248,7 +375,15 @@ pub async fn start_http(ctx: AppContext, options: CommonHttpOptions) {
     tracing::info!("Broadcasting shutdown signal to all tasks...");
     shutdown_token.cancel();
 
-    let (cleanup_result, dispatcher_result, artifact_gc_result, server_result) = tokio::join!(
+    let (
+        cleanup_result,
+        dispatcher_result,
+        webhook_outbox_result,
+        artifact_gc_result,
+        lfs_gc_result,
+        build_schedule_result,
+        server_result,
+    ) = tokio::join!(
         async {
             if let Some(handle) = cleanup_handle {
                 match tokio::time::timeout(std::time::Duration::from_secs(30), handle).await {
@@ -295,6 +430,26 @@ pub async fn start_http(ctx: AppContext, options: CommonHttpOptions) {
                 }
             }
         },
+        async {
+            match tokio::time::timeout(std::time::Duration::from_secs(30), webhook_outbox_handle)
+                .await
+            {
+                Ok(Ok(_)) => {
+                    tracing::info!("Webhook outbox task stopped successfully");
+                    Ok(())
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Webhook outbox task panicked: {}", e);
+                    Err(())
+                }
+                Err(_) => {
+                    tracing::error!(
+                        "Webhook outbox task did not stop within 30s timeout. The task will be detached."
+                    );
+                    Err(())
+                }
+            }
+        },
         async {
             if let Some(handle) = artifact_gc_handle {
                 match tokio::time::timeout(std::time::Duration::from_secs(30), handle).await {
@@ -317,6 +472,50 @@ pub async fn start_http(ctx: AppContext, options: CommonHttpOptions) {
                 Ok(())
             }
         },
+        async {
+            if let Some(handle) = lfs_gc_handle {
+                match tokio::time::timeout(std::time::Duration::from_secs(30), handle).await {
+                    Ok(Ok(_)) => {
+                        tracing::info!("LFS GC task stopped successfully");
+                        Ok(())
+                    }
+                    Ok(Err(e)) => {
+                        tracing::error!("LFS GC task panicked: {}", e);
+                        Err(())
+                    }
+                    Err(_) => {
+                        tracing::error!(
+                            "LFS GC task did not stop within 30s timeout. The task will be detached."
+                        );
+                        Err(())
+                    }
+                }
+            } else {
+                Ok(())
+            }
+        },
+        async {
+            if let Some(handle) = build_schedule_handle {
+                match tokio::time::timeout(std::time::Duration::from_secs(30), handle).await {
+                    Ok(Ok(_)) => {
+                        tracing::info!("Build schedule task stopped successfully");
+                        Ok(())
+                    }
+                    Ok(Err(e)) => {
+                        tracing::error!("Build schedule task panicked: {}", e);
+                        Err(())
+                    }
+                    Err(_) => {
+                        tracing::error!(
+                            "Build schedule task did not stop within 30s timeout. The task will be detached."
+                        );
+                        Err(())
+                    }
+                }
+            } else {
+                Ok(())
+            }
+        },
         async {
             match server_handle.as_mut().await {
                 Ok(_) => {
@@ -334,10 +533,13 @@ pub async fn start_http(ctx: AppContext, options: CommonHttpOptions) {
     match (
         cleanup_result,
         dispatcher_result,
+        webhook_outbox_result,
         artifact_gc_result,
+        lfs_gc_result,
+        build_schedule_result,
         server_result,
     ) {
-        (Ok(_), Ok(_), Ok(_), Ok(_)) => {
+        (Ok(_), Ok(_), Ok(_), Ok(_), Ok(_), Ok(_), Ok(_)) => {
             tracing::info!("Graceful shutdown completed successfully");
         }
         _ => {
@@ -492,6 +694,7 @@ fn rewrite_lfs_request_uri<B>(mut req: Request<B>) -> Request<B> {
 
     if let Some(pos) = full_path.rfind("/info/lfs/") {
         let lfs_subpath = &full_path[pos..];
+        let repo_path = full_path[..pos].to_owned();
 
         let new_path_and_query = if let Some(query) = req.uri().query() {
             format!("{}?{}", lfs_subpath, query)
@@ -514,6 +717,10 @@ fn rewrite_lfs_request_uri<B>(mut req: Request<B>) -> Request<B> {
 
         tracing::debug!("rewrite: old uri {:?}", req.uri());
         *req.uri_mut() = new_uri;
+        if !repo_path.is_empty() {
+            req.extensions_mut()
+                .insert(lfs_router::LfsRepoPath(repo_path));
+        }
         tracing::debug!("rewrite: new uri {:?}", req.uri());
     }
     req
@@ -534,7 +741,7 @@ async fn handle_smart_protocol(
         let uri = req.uri();
         let query_str = uri.query().unwrap_or("");
         let params: InfoRefsParams = serde_urlencoded::from_str(query_str).unwrap();
-        crate::git_protocol::http::git_info_refs(&state, params, repo_path).await
+        crate::git_protocol::http::git_info_refs(&state, params, repo_path, req.headers()).await
     } else if full_path.ends_with("/git-upload-pack") && req.method().eq(&Method::POST) {
         let repo_path = remove_git_suffix(full_path, "/git-upload-pack");
         crate::git_protocol::http::git_upload_pack(&state, req, repo_path).await
@@ -579,6 +786,24 @@ mod tests {
         assert_eq!(new_req.uri().path(), "/info/lfs/objects/123");
     }
 
+    #[test]
+    fn test_rewrite_records_repo_path() {
+        let req = Request::builder()
+            .uri("/project/app.git/info/lfs/objects/batch")
+            .body(())
+            .unwrap();
+
+        let new_req = rewrite_lfs_request_uri(req);
+
+        assert_eq!(
+            new_req
+                .extensions()
+                .get::<lfs_router::LfsRepoPath>()
+                .map(|p| p.0.as_str()),
+            Some("/project/app.git")
+        );
+    }
+
     #[test]
     fn test_rewrite_keeps_query_string() {
         let req = Request::builder()
@@ -1,7 +1,10 @@
 use std::{collections::HashMap, net::SocketAddr, str::FromStr, sync::Arc};
 
 use bytes::BytesMut;
-use ceres::{TransportRuntime, application::api_service::cache::GitObjectCache};
+use ceres::{
+    TransportRuntime, application::api_service::cache::GitObjectCache,
+    transport::protocol::ProtocolVersion,
+};
 use clap::Args;
 use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
 use russh::{
@@ -75,6 +78,9 @@ pub async fn start_server(ctx: AppContext, command: &SshOptions) {
         id: 0,
         smart_protocol: None,
         data_combined: BytesMut::new(),
+        protocol_version: ProtocolVersion::V0,
+        lfs_transfer: None,
+        username: None,
     };
     let server_url = format!("{host}:{ssh_port}");
     let addr = SocketAddr::from_str(&server_url).unwrap();
@@ -5862,6 +5862,16 @@ export type BuildEventDTO = {
   task_id: string
 }
 
+/**
+ * Scheduling class of a build. Queued builds of a more urgent class are
+ * dispatched first; variants are ordered from most to least urgent.
+ */
+export enum BuildPriority {
+  MergeQueue = 'merge_queue',
+  ChangeList = 'change_list',
+  Scheduled = 'scheduled'
+}
+
 export enum BuildStatus {
   Running = 'Running',
   Completed = 'Completed',
@@ -5922,6 +5932,7 @@ export type MessageResponse = {
 }
 
 export type OrionClientInfo = {
+  capabilities: WorkerCapabilities
   client_id: string
   hostname: string
   /** @format date-time */
@@ -5931,6 +5942,20 @@ export type OrionClientInfo = {
   start_time: string
 }
 
+/** Resources and labels a worker advertises when it registers. */
+export type WorkerCapabilities = {
+  /** @format int32 */
+  cpu_cores?: number | null
+  /** Free-form labels set by the operator, e.g. `large` or `gpu`. */
+  labels?: string[]
+  /** @format int64 */
+  memory_mb?: number | null
+  /** Operating system as reported by `std::env::consts::OS`, e.g. `linux`. */
+  os?: string | null
+  /** Installed toolchains, e.g. `rust` or `go`. */
+  toolchains?: string[]
+}
+
 export type OrionClientQuery = {
   hostname?: string | null
   phase?: null | TaskPhase
@@ -5968,6 +5993,8 @@ export type RetryBuildRequest = {
   /** @format int64 */
   cl_id: number
   cl_link: string
+  /** Scheduling class of the retried build. */
+  priority?: BuildPriority
   targets?: any[] | null
 }
 
@@ -6057,6 +6084,8 @@ export type TaskBuildRequest = {
   cl_id: number
   /** The change list link (URL) */
   cl_link: string
+  /** Scheduling class used to order this build in the queue. */
+  priority?: BuildPriority
   /** The Buck2 project path within the monorepo (for example `/jupiter/callisto`). */
   repo: string
   /** Buck2 target path (e.g. //app:server). Optional for backward compatibility. */
@@ -6077,7 +6106,8 @@ export type TaskHistoryQuery = {
 /** Task phase when in buck2 build */
 export enum TaskPhase {
   DownloadingSource = 'DownloadingSource',
-  RunningBuild = 'RunningBuild'
+  RunningBuild = 'RunningBuild',
+  RunningTests = 'RunningTests'
 }
 
 export type PostActivityViewsData = UserNotificationCounts
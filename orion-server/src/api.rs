@@ -5,8 +5,9 @@ use api_model::{
     buck2::{
         api::{RetryBuildRequest, TaskBuildRequest},
         types::{
-            LogErrorResponse, LogLinesResponse, TargetLogLinesResponse, TargetLogQuery,
-            TargetStatusResponse, TaskHistoryQuery,
+            BuildStatus, BuildTestResultsResponse, FlakyTarget, FlakyTargetQuery, LogErrorResponse,
+            LogLinesResponse, QuarantineQuery, QuarantineTargetRequest, TargetLogLinesResponse,
+            TargetLogQuery, TargetQuarantine, TargetStatusResponse, TaskHistoryQuery,
         },
     },
     common::{CommonPage, PageParams},
@@ -14,9 +15,9 @@ use api_model::{
 use axum::{
     Json, Router,
     extract::{ConnectInfo, Path, Query, State, WebSocketUpgrade},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::{any, get, post},
+    routing::{any, delete, get, post},
 };
 use uuid::Uuid;
 
@@ -24,14 +25,14 @@ use crate::{
     app_state::AppState,
     model::{
         dto::{
-            BuildEventDTO, BuildStatus, BuildTargetDTO, MessageResponse, OrionClientInfo,
-            OrionClientQuery, OrionClientStatus, OrionTaskDTO,
+            BuildEventDTO, BuildTargetDTO, MessageResponse, OrionClientInfo, OrionClientQuery,
+            OrionClientStatus, OrionTaskDTO,
         },
         internal::target_build_status::orion_target_status_to_api_str,
     },
     repository::target_build_status_repo::TargetBuildStatusRepo,
     scheduler::TaskQueueStats,
-    service::{api_v2_service, ws_service},
+    service::{api_v2_service, flaky_target_service, ws_service},
 };
 
 /// Creates and configures all API routes
@@ -62,14 +63,20 @@ fn task_routes() -> Router<AppState> {
 fn build_routes() -> Router<AppState> {
     Router::new()
         .route("/v2/retry-build", post(build_retry_handler))
+        .route("/v2/builds/{build_id}/cancel", post(build_cancel_handler))
         .route("/v2/build-events/{task_id}", get(build_event_get_handler))
         .route("/v2/targets/{task_id}", get(targets_get_handler))
         .route("/v2/build-state/{build_id}", get(build_state_handler))
         .route("/v2/builds/{build_id}/logs", get(build_logs_handler))
+        .route("/v2/builds/{build_id}/tests", get(build_tests_handler))
         .route(
             "/v2/latest_build_result/{task_id}",
             get(latest_build_result_handler),
         )
+        .route(
+            "/v2/latest_build_tests/{task_id}",
+            get(latest_build_tests_handler),
+        )
 }
 
 fn worker_routes() -> Router<AppState> {
@@ -92,6 +99,12 @@ fn target_status_routes() -> Router<AppState> {
             "/v2/target-status/{target_id}",
             get(single_target_status_handle),
         )
+        .route("/v2/flaky-targets", get(flaky_targets_handler))
+        .route(
+            "/v2/quarantine",
+            get(list_quarantines_handler).post(quarantine_target_handler),
+        )
+        .route("/v2/quarantine/{id}", delete(release_quarantine_handler))
 }
 
 /// API endpoint for getting queue statistics
@@ -286,6 +299,28 @@ pub async fn build_retry_handler(
     api_v2_service::build_retry(&state, req).await
 }
 
+/// Cancel a queued or running build
+#[utoipa::path(
+    post,
+    path = "/v2/builds/{build_id}/cancel",
+    tag = "Build",
+    params(("build_id" = String, Path, description = "Build ID")),
+    responses(
+        (status = 200, description = "Queued build cancelled", body = serde_json::Value),
+        (status = 202, description = "Cancellation sent to the worker", body = serde_json::Value),
+        (status = 400, description = "Invalid build ID format", body = serde_json::Value),
+        (status = 404, description = "Build Id not found", body = serde_json::Value),
+        (status = 409, description = "Build has already finished", body = serde_json::Value),
+        (status = 502, description = "Send to worker error", body = serde_json::Value)
+    )
+)]
+pub async fn build_cancel_handler(
+    State(state): State<AppState>,
+    Path(build_id): Path<String>,
+) -> impl IntoResponse {
+    api_v2_service::build_cancel(&state, &build_id).await
+}
+
 #[utoipa::path(
     get,
     path = "/v2/task/{cl}",
@@ -362,6 +397,26 @@ pub async fn build_logs_handler(
     api_v2_service::build_logs(&state, &build_id).await
 }
 
+/// Get per-test results of a build, failures first
+#[utoipa::path(
+    get,
+    path = "/v2/builds/{build_id}/tests",
+    tag = "Build",
+    params(("build_id" = String, Path, description = "Build event ID")),
+    responses(
+        (status = 200, description = "Test results of the build", body = BuildTestResultsResponse),
+        (status = 400, description = "Invalid build ID", body = MessageResponse),
+        (status = 404, description = "Not found build", body = MessageResponse),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn build_tests_handler(
+    State(state): State<AppState>,
+    Path(build_id): Path<String>,
+) -> Result<Json<BuildTestResultsResponse>, (StatusCode, Json<MessageResponse>)> {
+    api_v2_service::build_tests(&state, &build_id).await
+}
+
 /// Get build state by build ID
 #[utoipa::path(
     get,
@@ -398,6 +453,26 @@ pub async fn latest_build_result_handler(
     api_v2_service::latest_build_result(&state, &task_id).await
 }
 
+/// Get per-test results of the latest build of a task, failures first
+#[utoipa::path(
+    get,
+    path = "/v2/latest_build_tests/{task_id}",
+    tag = "Build",
+    params(("task_id" = String, Path, description = "Task ID")),
+    responses(
+        (status = 200, description = "Test results of the latest build", body = BuildTestResultsResponse),
+        (status = 400, description = "Invalid task ID", body = MessageResponse),
+        (status = 404, description = "Task has no build yet", body = MessageResponse),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn latest_build_tests_handler(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<BuildTestResultsResponse>, (StatusCode, Json<MessageResponse>)> {
+    api_v2_service::latest_build_tests(&state, &task_id).await
+}
+
 /// Get target status with task_id
 #[utoipa::path(
     get,
@@ -440,6 +515,97 @@ pub async fn single_target_status_handle(
     target_status_by_id(&state.conn, &target_id).await.map(Json)
 }
 
+/// List targets that both passed and failed on the same commit in recent builds
+#[utoipa::path(
+    get,
+    path = "/v2/flaky-targets",
+    tag = "TargetStatus",
+    params(
+        ("path" = Option<String>, Query, description = "Only targets built in this repo path or below it"),
+    ),
+    responses(
+        (status = 200, description = "Flaky targets, most flaky first", body = Vec<FlakyTarget>),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn flaky_targets_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FlakyTargetQuery>,
+) -> Result<Json<Vec<FlakyTarget>>, (StatusCode, Json<MessageResponse>)> {
+    flaky_target_service::flaky_targets(&state, &query).await
+}
+
+/// List quarantined targets
+#[utoipa::path(
+    get,
+    path = "/v2/quarantine",
+    tag = "TargetStatus",
+    params(
+        ("path" = Option<String>, Query, description = "Only quarantines that apply to builds of this path"),
+    ),
+    responses(
+        (status = 200, description = "Quarantined targets", body = Vec<TargetQuarantine>),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn list_quarantines_handler(
+    State(state): State<AppState>,
+    Query(query): Query<QuarantineQuery>,
+) -> Result<Json<Vec<TargetQuarantine>>, (StatusCode, Json<MessageResponse>)> {
+    flaky_target_service::list_quarantines(&state, &query).await
+}
+
+/// Quarantine a target so the CI status gate ignores its failures under a path
+#[utoipa::path(
+    post,
+    path = "/v2/quarantine",
+    tag = "TargetStatus",
+    request_body = QuarantineTargetRequest,
+    responses(
+        (status = 200, description = "Target quarantined", body = TargetQuarantine),
+        (status = 400, description = "Invalid path or target", body = MessageResponse),
+        (status = 401, description = "Missing or wrong admin token", body = MessageResponse),
+        (status = 403, description = "No admin token is configured", body = MessageResponse),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn quarantine_target_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<QuarantineTargetRequest>,
+) -> Result<Json<TargetQuarantine>, (StatusCode, Json<MessageResponse>)> {
+    flaky_target_service::quarantine_target(&state, authorization(&headers), req).await
+}
+
+/// Release a quarantined target
+#[utoipa::path(
+    delete,
+    path = "/v2/quarantine/{id}",
+    tag = "TargetStatus",
+    params(("id" = String, Path, description = "Quarantine ID")),
+    responses(
+        (status = 200, description = "Quarantine released", body = MessageResponse),
+        (status = 400, description = "Invalid quarantine ID", body = MessageResponse),
+        (status = 401, description = "Missing or wrong admin token", body = MessageResponse),
+        (status = 403, description = "No admin token is configured", body = MessageResponse),
+        (status = 404, description = "Quarantine not found", body = MessageResponse),
+        (status = 500, description = "Database error", body = MessageResponse),
+    )
+)]
+pub async fn release_quarantine_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<MessageResponse>)> {
+    flaky_target_service::release_quarantine(&state, authorization(&headers), &id).await
+}
+
+fn authorization(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+}
+
 async fn targets_status_by_task_id(
     conn: &sea_orm::DatabaseConnection,
     task_id: &str,
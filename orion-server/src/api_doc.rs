@@ -14,11 +14,14 @@ use crate::api;
         api::task_get_handler,
         // Build domain
         api::build_retry_handler,
+        api::build_cancel_handler,
         api::build_event_get_handler,
         api::targets_get_handler,
         api::build_state_handler,
         api::build_logs_handler,
+        api::build_tests_handler,
         api::latest_build_result_handler,
+        api::latest_build_tests_handler,
         // Worker domain
         api::get_orion_clients_info,
         api::get_orion_client_status_by_id,
@@ -26,6 +29,10 @@ use crate::api;
         api::target_logs_handler,
         api::targets_status_handler,
         api::single_target_status_handle,
+        api::flaky_targets_handler,
+        api::list_quarantines_handler,
+        api::quarantine_target_handler,
+        api::release_quarantine_handler,
         // System domain
         api::health_check_handler
     ),
@@ -47,8 +54,18 @@ use crate::api;
             crate::model::dto::BuildEventDTO,
             crate::model::dto::OrionTaskDTO,
             crate::model::dto::BuildTargetDTO,
-            crate::model::dto::BuildStatus,
+            api_model::buck2::types::BuildStatus,
             api_model::buck2::types::TargetStatusResponse,
+            api_model::buck2::types::TestStatus,
+            api_model::buck2::types::TestResultResponse,
+            api_model::buck2::types::TestSummary,
+            api_model::buck2::types::BuildTestResultsResponse,
+            api_model::buck2::types::BuildPriority,
+            api_model::buck2::types::FlakyTarget,
+            api_model::buck2::types::QuarantineSource,
+            api_model::buck2::types::TargetQuarantine,
+            api_model::buck2::types::QuarantineTargetRequest,
+            crate::scheduler::QueuedBuildStats,
         )
     ),
     tags(
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use common::config::FlakyQuarantineConfig;
 use dashmap::DashMap;
 use sea_orm::DatabaseConnection;
 use tokio::sync::watch;
@@ -45,6 +46,7 @@ pub struct AppState {
     pub conn: DatabaseConnection,
     pub log_service: LogService,
     pub target_status_cache: TargetStatusCache,
+    pub flaky_quarantine: FlakyQuarantineConfig,
     shutdown_tx: watch::Sender<bool>,
 }
 
@@ -53,6 +55,8 @@ impl AppState {
         conn: DatabaseConnection,
         queue_config: Option<crate::scheduler::TaskQueueConfig>,
         log_service: LogService,
+        routing_rules: Vec<common::config::BuildRoutingRule>,
+        flaky_quarantine: FlakyQuarantineConfig,
     ) -> Self {
         let workers = Arc::new(DashMap::new());
         let active_builds = Arc::new(DashMap::new());
@@ -62,6 +66,7 @@ impl AppState {
             active_builds,
             queue_config,
             log_service.clone(),
+            routing_rules,
         );
         let target_status_cache = TargetStatusCache::new();
         let (shutdown_tx, _) = watch::channel(false);
@@ -71,6 +76,7 @@ impl AppState {
             conn,
             log_service,
             target_status_cache,
+            flaky_quarantine,
             shutdown_tx,
         }
     }
@@ -1,4 +1,4 @@
-use api_model::buck2::types::TaskPhase;
+use api_model::buck2::types::{TaskPhase, WorkerCapabilities};
 use chrono::Utc;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
@@ -16,6 +16,7 @@ pub struct OrionClientInfo {
     pub start_time: DateTimeUtc,
     #[schema(value_type = String, format = "date-time")]
     pub last_heartbeat: DateTimeUtc,
+    pub capabilities: WorkerCapabilities,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
@@ -125,10 +126,3 @@ impl From<&callisto::orion_tasks::Model> for OrionTaskDTO {
         }
     }
 }
-
-#[derive(ToSchema, Serialize)]
-pub enum BuildStatus {
-    Running,
-    Completed,
-    Failed,
-}
@@ -21,6 +21,8 @@ pub enum TargetState {
     Interrupted,
     #[sea_orm(string_value = "Uninitialized")]
     Uninitialized,
+    #[sea_orm(string_value = "Cancelled")]
+    Cancelled,
 }
 
 impl TargetState {
@@ -28,7 +30,10 @@ impl TargetState {
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            TargetState::Completed | TargetState::Failed | TargetState::Interrupted
+            TargetState::Completed
+                | TargetState::Failed
+                | TargetState::Interrupted
+                | TargetState::Cancelled
         )
     }
 }
@@ -42,6 +47,7 @@ impl From<String> for TargetState {
             "Failed" => TargetState::Failed,
             "Interrupted" => TargetState::Interrupted,
             "Uninitialized" => TargetState::Uninitialized,
+            "Cancelled" => TargetState::Cancelled,
             _ => TargetState::Pending,
         }
     }
@@ -56,6 +62,7 @@ impl Display for TargetState {
             TargetState::Failed => "Failed",
             TargetState::Interrupted => "Interrupted",
             TargetState::Uninitialized => "Uninitialized",
+            TargetState::Cancelled => "Cancelled",
         };
         write!(f, "{}", s)
     }
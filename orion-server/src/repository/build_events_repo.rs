@@ -1,7 +1,7 @@
 use chrono::Utc;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    QueryFilter as _, QueryOrder,
+    QueryFilter as _, QueryOrder, QuerySelect as _,
 };
 use uuid::Uuid;
 
@@ -73,6 +73,21 @@ impl BuildEventsRepo {
             .await
     }
 
+    /// The `limit` most recently finished builds that ran to completion, i.e. reported
+    /// an exit code, newest first.
+    pub async fn list_recently_completed(
+        conn: &impl ConnectionTrait,
+        limit: u64,
+    ) -> Result<Vec<callisto::build_events::Model>, DbErr> {
+        callisto::build_events::Entity::find()
+            .filter(callisto::build_events::Column::EndAt.is_not_null())
+            .filter(callisto::build_events::Column::ExitCode.is_not_null())
+            .order_by_desc(callisto::build_events::Column::EndAt)
+            .limit(limit)
+            .all(conn)
+            .await
+    }
+
     pub async fn insert_build(
         conn: &impl ConnectionTrait,
         build_id: Uuid,
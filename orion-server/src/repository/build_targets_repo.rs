@@ -4,12 +4,10 @@ use sea_orm::{
 };
 use uuid::Uuid;
 
-use crate::model::{internal::BuildTargetStateDTO, target_state::TargetState};
+use crate::model::target_state::TargetState;
 
 pub struct BuildTargetsRepo;
 
-type BuildTargetDTO = BuildTargetStateDTO;
-
 impl BuildTargetsRepo {
     pub fn create_default_target(id: Uuid, task_id: Uuid) -> callisto::build_targets::Model {
         let default_path = "//";
@@ -48,48 +46,6 @@ impl BuildTargetsRepo {
         Ok(path.to_string())
     }
 
-    pub(crate) async fn find_initialized_build_targets(
-        build_id: Uuid,
-        task_id: Uuid,
-        db: &impl ConnectionTrait,
-    ) -> Result<Vec<BuildTargetDTO>, DbErr> {
-        if Self::has_uninitialized_target(task_id, db).await? {
-            return Ok(vec![]);
-        }
-
-        let all_targets = callisto::build_targets::Entity::find()
-            .filter(callisto::build_targets::Column::TaskId.eq(task_id))
-            .filter(
-                callisto::build_targets::Column::LatestState
-                    .ne(TargetState::Uninitialized.to_string()),
-            )
-            .all(db)
-            .await?;
-
-        let mut result = Vec::with_capacity(all_targets.len());
-
-        for target in all_targets {
-            let status = callisto::target_state_histories::Entity::find()
-                .filter(callisto::target_state_histories::Column::BuildTargetId.eq(target.id))
-                .filter(callisto::target_state_histories::Column::BuildEventId.eq(build_id))
-                .one(db)
-                .await?;
-
-            let state = match status {
-                Some(s) => TargetState::from(s.target_state),
-                None => TargetState::Uninitialized,
-            };
-
-            result.push(BuildTargetStateDTO {
-                id: target.id,
-                path: target.path,
-                state,
-            });
-        }
-
-        Ok(result)
-    }
-
     pub async fn find_by_id(
         conn: &impl ConnectionTrait,
         id: Uuid,
@@ -0,0 +1,53 @@
+use api_model::buck2::ws::WSTestResult;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter as _,
+    QueryOrder as _,
+};
+use uuid::Uuid;
+
+pub struct BuildTestResultsRepo;
+
+impl BuildTestResultsRepo {
+    pub async fn insert_batch(
+        conn: &impl ConnectionTrait,
+        build_event_id: Uuid,
+        task_id: Uuid,
+        results: Vec<WSTestResult>,
+        created_at: sea_orm::prelude::DateTimeWithTimeZone,
+    ) -> Result<(), DbErr> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        let models = results
+            .into_iter()
+            .map(|result| callisto::build_test_results::ActiveModel {
+                id: Set(Uuid::now_v7()),
+                build_event_id: Set(build_event_id),
+                task_id: Set(task_id),
+                target: Set(result.target),
+                test_name: Set(result.name),
+                status: Set(result.status.as_str().to_string()),
+                duration_ms: Set(result
+                    .duration_ms
+                    .map(|ms| i64::try_from(ms).unwrap_or(i64::MAX))),
+                message: Set(result.message),
+                created_at: Set(created_at),
+            });
+        callisto::build_test_results::Entity::insert_many(models)
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Test results of a build event in the order they were reported.
+    pub async fn list_by_build(
+        conn: &impl ConnectionTrait,
+        build_event_id: Uuid,
+    ) -> Result<Vec<callisto::build_test_results::Model>, DbErr> {
+        callisto::build_test_results::Entity::find()
+            .filter(callisto::build_test_results::Column::BuildEventId.eq(build_event_id))
+            .order_by_asc(callisto::build_test_results::Column::Id)
+            .all(conn)
+            .await
+    }
+}
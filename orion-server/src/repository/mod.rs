@@ -1,5 +1,21 @@
 pub mod build_events_repo;
 pub mod build_targets_repo;
+pub mod build_test_results_repo;
+pub mod orion_build_queue_repo;
 pub mod orion_tasks_repo;
 pub mod target_build_status_repo;
+pub mod target_quarantines_repo;
 pub mod target_state_histories_repo;
+
+use sea_orm::{ColumnTrait, Condition};
+
+/// Rows whose repo `column` is `path` or below it; every row when `path` is `None` or `/`.
+pub(crate) fn repo_at_or_below(column: impl ColumnTrait, path: Option<&str>) -> Condition {
+    let path = path.map(|path| path.trim_end_matches('/'));
+    match path.filter(|path| !path.is_empty()) {
+        Some(path) => Condition::any()
+            .add(column.eq(path))
+            .add(column.starts_with(format!("{path}/"))),
+        None => Condition::all(),
+    }
+}
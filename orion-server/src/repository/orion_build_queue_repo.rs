@@ -0,0 +1,38 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter as _, QueryOrder as _,
+};
+use uuid::Uuid;
+
+/// Persisted copy of the scheduler's in-memory build queue.
+pub struct OrionBuildQueueRepo;
+
+impl OrionBuildQueueRepo {
+    pub async fn insert(
+        conn: &impl ConnectionTrait,
+        entry: callisto::orion_build_queue::Model,
+    ) -> Result<(), DbErr> {
+        entry.into_active_model().insert(conn).await?;
+        Ok(())
+    }
+
+    /// Remove a build from the queue. Removing a build that is not queued is a no-op.
+    pub async fn delete(conn: &impl ConnectionTrait, build_event_id: Uuid) -> Result<(), DbErr> {
+        callisto::orion_build_queue::Entity::delete_many()
+            .filter(callisto::orion_build_queue::Column::BuildEventId.eq(build_event_id))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// All queued builds, oldest first.
+    pub async fn list_all(
+        conn: &impl ConnectionTrait,
+    ) -> Result<Vec<callisto::orion_build_queue::Model>, DbErr> {
+        callisto::orion_build_queue::Entity::find()
+            .order_by_asc(callisto::orion_build_queue::Column::EnqueuedAt)
+            .order_by_asc(callisto::orion_build_queue::Column::BuildEventId)
+            .all(conn)
+            .await
+    }
+}
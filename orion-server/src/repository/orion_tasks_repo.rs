@@ -1,4 +1,7 @@
-use api_model::buck2::{status::Status, types::ProjectRelativePath};
+use api_model::buck2::{
+    status::Status,
+    types::{BuildRequirements, ProjectRelativePath},
+};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
     QueryFilter as _, QuerySelect as _,
@@ -48,6 +51,7 @@ impl OrionTasksRepo {
         cl_link: &str,
         repo: &str,
         changes: &Vec<Status<ProjectRelativePath>>,
+        requirements: &BuildRequirements,
     ) -> Result<callisto::orion_tasks::Model, serde_json::Error> {
         Ok(callisto::orion_tasks::Model {
             id: task_id,
@@ -55,17 +59,20 @@ impl OrionTasksRepo {
             repo_name: repo.to_string(),
             changes: to_value(changes)?,
             created_at: chrono::Utc::now().into(),
+            requirements: Some(to_value(requirements)?),
         })
     }
 
+    /// Insert a task with the worker requirements resolved for its builds.
     pub async fn insert_task(
         task_id: Uuid,
         cl_link: &str,
         repo: &str,
         changes: &Vec<Status<ProjectRelativePath>>,
+        requirements: &BuildRequirements,
         db: &impl ConnectionTrait,
     ) -> Result<callisto::orion_tasks::Model, DbErr> {
-        let task_model = Self::create_task_model(task_id, cl_link, repo, changes)
+        let task_model = Self::create_task_model(task_id, cl_link, repo, changes, requirements)
             .map_err(|e| DbErr::Custom(e.to_string()))?;
         task_model.into_active_model().insert(db).await
     }
@@ -1,15 +1,40 @@
-use callisto::target_build_status;
+use callisto::{orion_tasks, sea_orm_active_enums::OrionTargetStatusEnum, target_build_status};
 use chrono::Utc;
 use sea_orm::{
-    ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter as _,
-    QuerySelect as _, sea_query::OnConflict,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, JoinType, QueryFilter as _,
+    QuerySelect as _, RelationTrait as _,
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, OnConflict},
 };
 use uuid::Uuid;
 
-use crate::model::internal::target_build_status::NewTargetStatusInput;
+use crate::{
+    model::internal::target_build_status::NewTargetStatusInput, repository::repo_at_or_below,
+};
 
 pub struct TargetBuildStatusRepo;
 
+/// When a target that passed in a task was first and last reported.
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct PassedTargetSpan {
+    pub task_id: Uuid,
+    pub target_package: String,
+    pub target_name: String,
+    pub first_reported_at: DateTimeWithTimeZone,
+    pub last_reported_at: DateTimeWithTimeZone,
+}
+
+/// Tasks that built a target and those it failed in.
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct TargetBuildTotals {
+    pub repo: String,
+    pub target_package: String,
+    pub target_name: String,
+    pub builds: i64,
+    pub failures: i64,
+}
+
 impl TargetBuildStatusRepo {
     pub(crate) fn new_active_model(
         input: NewTargetStatusInput,
@@ -71,6 +96,83 @@ impl TargetBuildStatusRepo {
             .await
     }
 
+    /// Report times of the targets that passed in `task_ids`, one row per task and target.
+    pub async fn passed_target_spans(
+        conn: &impl ConnectionTrait,
+        task_ids: Vec<Uuid>,
+    ) -> Result<Vec<PassedTargetSpan>, DbErr> {
+        if task_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        target_build_status::Entity::find()
+            .select_only()
+            .column(target_build_status::Column::TaskId)
+            .column(target_build_status::Column::TargetPackage)
+            .column(target_build_status::Column::TargetName)
+            .expr_as(
+                Expr::col(target_build_status::Column::CreatedAt).min(),
+                "first_reported_at",
+            )
+            .expr_as(
+                Expr::col(target_build_status::Column::UpdatedAt).max(),
+                "last_reported_at",
+            )
+            .filter(target_build_status::Column::TaskId.is_in(task_ids))
+            .filter(target_build_status::Column::Status.eq(OrionTargetStatusEnum::Success))
+            .group_by(target_build_status::Column::TaskId)
+            .group_by(target_build_status::Column::TargetPackage)
+            .group_by(target_build_status::Column::TargetName)
+            .into_model::<PassedTargetSpan>()
+            .all(conn)
+            .await
+    }
+
+    /// Builds and failures since `since` of the targets in `packages`, per repo at or
+    /// below `path` and target. Counts are in tasks, so retries of a commit count once.
+    pub async fn target_totals(
+        conn: &impl ConnectionTrait,
+        path: Option<&str>,
+        since: DateTimeWithTimeZone,
+        packages: Vec<String>,
+    ) -> Result<Vec<TargetBuildTotals>, DbErr> {
+        if packages.is_empty() {
+            return Ok(vec![]);
+        }
+        let task_id = || {
+            Expr::col((
+                target_build_status::Entity,
+                target_build_status::Column::TaskId,
+            ))
+        };
+        target_build_status::Entity::find()
+            .select_only()
+            .join(
+                JoinType::InnerJoin,
+                target_build_status::Relation::OrionTasks.def(),
+            )
+            .column_as(orion_tasks::Column::RepoName, "repo")
+            .column(target_build_status::Column::TargetPackage)
+            .column(target_build_status::Column::TargetName)
+            .expr_as(task_id().count_distinct(), "builds")
+            .expr_as(
+                Expr::expr(Expr::case(
+                    target_build_status::Column::Status.eq(OrionTargetStatusEnum::Failed),
+                    task_id(),
+                ))
+                .count_distinct(),
+                "failures",
+            )
+            .filter(target_build_status::Column::CreatedAt.gte(since))
+            .filter(target_build_status::Column::TargetPackage.is_in(packages))
+            .filter(repo_at_or_below(orion_tasks::Column::RepoName, path))
+            .group_by(orion_tasks::Column::RepoName)
+            .group_by(target_build_status::Column::TargetPackage)
+            .group_by(target_build_status::Column::TargetName)
+            .into_model::<TargetBuildTotals>()
+            .all(conn)
+            .await
+    }
+
     pub async fn find_by_id(
         conn: &impl ConnectionTrait,
         id: Uuid,
@@ -0,0 +1,66 @@
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter as _,
+    QueryOrder as _, prelude::DateTimeWithTimeZone, sea_query::OnConflict,
+};
+use uuid::Uuid;
+
+/// Targets whose failures the CI status gate ignores, by path.
+pub struct TargetQuarantinesRepo;
+
+impl TargetQuarantinesRepo {
+    /// Quarantine `target` under `path`, keeping the existing entry if there is one.
+    pub async fn insert(
+        conn: &impl ConnectionTrait,
+        path: &str,
+        target: &str,
+        reason: Option<String>,
+        source: &str,
+        created_at: DateTimeWithTimeZone,
+    ) -> Result<callisto::target_quarantines::Model, DbErr> {
+        callisto::target_quarantines::Entity::insert(callisto::target_quarantines::ActiveModel {
+            id: Set(Uuid::now_v7()),
+            path: Set(path.to_string()),
+            target: Set(target.to_string()),
+            reason: Set(reason),
+            source: Set(source.to_string()),
+            created_at: Set(created_at),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                callisto::target_quarantines::Column::Path,
+                callisto::target_quarantines::Column::Target,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(conn)
+        .await?;
+
+        callisto::target_quarantines::Entity::find()
+            .filter(callisto::target_quarantines::Column::Path.eq(path))
+            .filter(callisto::target_quarantines::Column::Target.eq(target))
+            .one(conn)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("quarantine of {target} under {path}")))
+    }
+
+    /// Remove a quarantine. Returns whether it existed.
+    pub async fn delete(conn: &impl ConnectionTrait, id: Uuid) -> Result<bool, DbErr> {
+        let result = callisto::target_quarantines::Entity::delete_by_id(id)
+            .exec(conn)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// All quarantines, oldest first.
+    pub async fn list_all(
+        conn: &impl ConnectionTrait,
+    ) -> Result<Vec<callisto::target_quarantines::Model>, DbErr> {
+        callisto::target_quarantines::Entity::find()
+            .order_by_asc(callisto::target_quarantines::Column::CreatedAt)
+            .order_by_asc(callisto::target_quarantines::Column::Id)
+            .all(conn)
+            .await
+    }
+}
@@ -1,11 +1,30 @@
+use callisto::{build_events, orion_tasks, target_state_histories};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    IntoActiveModel, QueryFilter as _,
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, IntoActiveModel, JoinType,
+    PaginatorTrait as _, QueryFilter as _, QuerySelect as _, RelationTrait as _,
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, Query},
 };
 use uuid::Uuid;
 
+use crate::{model::target_state::TargetState, repository::repo_at_or_below};
+
 pub struct TargetStateHistoriesRepo;
 
+/// The builds of one task that ended in one state.
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct TaskStateSummary {
+    pub task_id: Uuid,
+    pub repo: String,
+    pub target_state: String,
+    pub builds: i64,
+    pub first_end_at: Option<DateTimeWithTimeZone>,
+    pub last_start_at: Option<DateTimeWithTimeZone>,
+    pub last_end_at: Option<DateTimeWithTimeZone>,
+}
+
 impl TargetStateHistoriesRepo {
     pub async fn upsert_state(
         conn: &impl ConnectionTrait,
@@ -40,4 +59,77 @@ impl TargetStateHistoriesRepo {
         }
         Ok(())
     }
+
+    /// Whether any target of `build_event_id` recorded `target_state`.
+    pub async fn build_has_state(
+        conn: &impl ConnectionTrait,
+        build_event_id: Uuid,
+        target_state: &str,
+    ) -> Result<bool, DbErr> {
+        let count = callisto::target_state_histories::Entity::find()
+            .filter(callisto::target_state_histories::Column::BuildEventId.eq(build_event_id))
+            .filter(callisto::target_state_histories::Column::TargetState.eq(target_state))
+            .count(conn)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Completed and failed builds of every task retried since `since` in a repo at or
+    /// below `path`, summarized per task and final state.
+    ///
+    /// Only tasks with a `build_retry` can have flipped on their commit, so the rows stay
+    /// bounded by the retries instead of every build.
+    pub async fn retried_task_states(
+        conn: &impl ConnectionTrait,
+        path: Option<&str>,
+        since: DateTimeWithTimeZone,
+    ) -> Result<Vec<TaskStateSummary>, DbErr> {
+        let retried = Query::select()
+            .column(build_events::Column::TaskId)
+            .from(build_events::Entity)
+            .and_where(build_events::Column::RetryCount.gt(0))
+            .and_where(build_events::Column::StartAt.gte(since))
+            .to_owned();
+        target_state_histories::Entity::find()
+            .select_only()
+            .join(
+                JoinType::InnerJoin,
+                target_state_histories::Relation::BuildEvents.def(),
+            )
+            .join(
+                JoinType::InnerJoin,
+                build_events::Relation::OrionTasks.def(),
+            )
+            .column(build_events::Column::TaskId)
+            .column_as(orion_tasks::Column::RepoName, "repo")
+            .column(target_state_histories::Column::TargetState)
+            .expr_as(
+                Expr::col((build_events::Entity, build_events::Column::Id)).count(),
+                "builds",
+            )
+            .expr_as(
+                Expr::col((build_events::Entity, build_events::Column::EndAt)).min(),
+                "first_end_at",
+            )
+            .expr_as(
+                Expr::col((build_events::Entity, build_events::Column::StartAt)).max(),
+                "last_start_at",
+            )
+            .expr_as(
+                Expr::col((build_events::Entity, build_events::Column::EndAt)).max(),
+                "last_end_at",
+            )
+            .filter(build_events::Column::TaskId.in_subquery(retried))
+            .filter(target_state_histories::Column::TargetState.is_in([
+                TargetState::Completed.to_string(),
+                TargetState::Failed.to_string(),
+            ]))
+            .filter(repo_at_or_below(orion_tasks::Column::RepoName, path))
+            .group_by(build_events::Column::TaskId)
+            .group_by(orion_tasks::Column::RepoName)
+            .group_by(target_state_histories::Column::TargetState)
+            .into_model::<TaskStateSummary>()
+            .all(conn)
+            .await
+    }
 }
@@ -1,17 +1,18 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use api_model::buck2::{
     status::Status,
-    types::{ProjectRelativePath, TaskPhase},
+    types::{BuildPriority, BuildRequirements, ProjectRelativePath, TaskPhase, WorkerCapabilities},
     ws::WSMessage,
 };
 use chrono::FixedOffset;
+use common::config::BuildRoutingRule;
 use dashmap::DashMap;
-use rand::RngExt;
+use rand::{RngExt, seq::SliceRandom};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait,
     QueryFilter as _, prelude::DateTimeUtc,
@@ -26,7 +27,8 @@ use crate::{
     model::{dto::CoreWorkerStatus, internal::BuildTargetStateDTO, target_state::TargetState},
     repository::{
         build_events_repo::BuildEventsRepo, build_targets_repo::BuildTargetsRepo,
-        orion_tasks_repo::OrionTasksRepo, target_state_histories_repo::TargetStateHistoriesRepo,
+        orion_build_queue_repo::OrionBuildQueueRepo, orion_tasks_repo::OrionTasksRepo,
+        target_state_histories_repo::TargetStateHistoriesRepo,
     },
 };
 
@@ -45,13 +47,25 @@ impl Default for TaskQueueConfig {
     fn default() -> Self {
         Self {
             max_queue_size: 1000,
-            max_wait_time: Duration::from_secs(30), // Fail builds with no worker after 30s
+            max_wait_time: Duration::from_secs(2 * 60 * 60), // Fail builds still waiting for a worker after 2 hours
             cleanup_interval: Duration::from_secs(10), // Scan for expired builds every 10 seconds
         }
     }
 }
 
-/// Simple FIFO task queue
+/// Number of recently completed builds averaged for queue ETAs.
+const ETA_SAMPLE_SIZE: u64 = 50;
+
+/// How long a disconnected worker still counts as able to run builds, so that a restarting
+/// worker does not fail the builds waiting for it. Workers get the same time to reconnect
+/// after orion-server starts.
+const WORKER_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Prioritized task queue.
+///
+/// Builds are taken by priority class first. Within a class, the repo with the fewest
+/// running builds goes first so one busy repo cannot starve the others, and builds of
+/// the same repo keep FIFO order.
 #[derive(Debug)]
 pub struct TaskQueue {
     /// Queue storage for v2 (should replace the original queue in the future)
@@ -60,6 +74,25 @@ pub struct TaskQueue {
     config: TaskQueueConfig,
 }
 
+/// Index of the build to take next among `candidates`, given the number of builds
+/// already running per repo. Ties keep queue order.
+fn next_in_line<'a>(
+    candidates: impl Iterator<Item = (usize, &'a PendingBuildEventV2)>,
+    running: &HashMap<String, usize>,
+) -> Option<usize> {
+    candidates
+        .min_by_key(|(_, task)| {
+            (
+                task.priority,
+                running
+                    .get(&task.event_payload.repo)
+                    .copied()
+                    .unwrap_or_default(),
+            )
+        })
+        .map(|(index, _)| index)
+}
+
 impl TaskQueue {
     pub fn new(config: TaskQueueConfig) -> Self {
         Self {
@@ -78,6 +111,72 @@ impl TaskQueue {
         Ok(())
     }
 
+    /// Remove queued builds matching `predicate`, e.g. because they were cancelled.
+    pub fn remove_where_v2(
+        &mut self,
+        mut predicate: impl FnMut(&PendingBuildEventV2) -> bool,
+    ) -> Vec<PendingBuildEventV2> {
+        let mut removed = Vec::new();
+        self.queue_v2.retain(|task| {
+            if predicate(task) {
+                removed.push(task.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Take queued builds that one of the `idle` workers can run, in dispatch order,
+    /// pairing each with the first matching worker. `running` counts the builds each
+    /// repo already has running. Builds no idle worker can run stay queued in order.
+    pub fn take_dispatchable_v2(
+        &mut self,
+        idle: &mut Vec<(String, WorkerCapabilities)>,
+        running: &HashMap<String, usize>,
+    ) -> Vec<(PendingBuildEventV2, String)> {
+        let mut running = running.clone();
+        let mut dispatchable = Vec::new();
+        while !idle.is_empty() {
+            let candidates = self.queue_v2.iter().enumerate().filter(|(_, task)| {
+                idle.iter()
+                    .any(|(_, capabilities)| task.requirements.is_satisfied_by(capabilities))
+            });
+            let Some(index) = next_in_line(candidates, &running) else {
+                break;
+            };
+            let Some(task) = self.queue_v2.remove(index) else {
+                break;
+            };
+            let Some(worker_index) = idle
+                .iter()
+                .position(|(_, capabilities)| task.requirements.is_satisfied_by(capabilities))
+            else {
+                self.queue_v2.insert(index, task);
+                break;
+            };
+            let (worker_id, _) = idle.remove(worker_index);
+            *running.entry(task.event_payload.repo.clone()).or_default() += 1;
+            dispatchable.push((task, worker_id));
+        }
+        dispatchable
+    }
+
+    /// Queued builds in the order they would be dispatched if every worker could run
+    /// every build.
+    pub fn dispatch_order_v2(&self, running: &HashMap<String, usize>) -> Vec<&PendingBuildEventV2> {
+        let mut running = running.clone();
+        let mut remaining: Vec<&PendingBuildEventV2> = self.queue_v2.iter().collect();
+        let mut order = Vec::with_capacity(remaining.len());
+        while let Some(index) = next_in_line(remaining.iter().copied().enumerate(), &running) {
+            let task = remaining.remove(index);
+            *running.entry(task.event_payload.repo.clone()).or_default() += 1;
+            order.push(task);
+        }
+        order
+    }
+
     /// Clean up expired queued builds (v2)
     pub fn cleanup_expired_v2(&mut self) -> Vec<PendingBuildEventV2> {
         let now = Instant::now();
@@ -95,14 +194,33 @@ impl TaskQueue {
         expired_tasks
     }
 
-    /// Get queue statistics
-    pub fn get_stats(&self) -> TaskQueueStats {
+    /// Get queue statistics, listing queued builds with their position in dispatch
+    /// order. ETAs are left for [`TaskScheduler::get_queue_stats`] to fill in.
+    pub fn get_stats(&self, running: &HashMap<String, usize>) -> TaskQueueStats {
+        let now = Instant::now();
+        let builds = self
+            .dispatch_order_v2(running)
+            .into_iter()
+            .enumerate()
+            .map(|(index, task)| QueuedBuildStats {
+                build_id: task.event_payload.build_event_id.to_string(),
+                task_id: task.event_payload.task_id.to_string(),
+                repo: task.event_payload.repo.clone(),
+                cl_link: task.event_payload.cl_link.clone(),
+                priority: task.priority,
+                position: index + 1,
+                waiting_seconds: now.duration_since(task.created_at).as_secs(),
+                eta_seconds: None,
+            })
+            .collect();
         TaskQueueStats {
             total_queued: self.queue_v2.len(),
             oldest_task_age_seconds: self
                 .queue_v2
-                .front()
-                .map(|task| Instant::now().duration_since(task.created_at).as_secs()),
+                .iter()
+                .map(|task| now.duration_since(task.created_at).as_secs())
+                .max(),
+            builds,
         }
     }
 }
@@ -113,6 +231,41 @@ pub struct TaskQueueStats {
     pub total_queued: usize,
     /// Age of oldest task in seconds
     pub oldest_task_age_seconds: Option<u64>,
+    /// Queued builds in dispatch order
+    pub builds: Vec<QueuedBuildStats>,
+}
+
+/// A queued build and its place in the queue.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueuedBuildStats {
+    pub build_id: String,
+    pub task_id: String,
+    pub repo: String,
+    pub cl_link: String,
+    pub priority: BuildPriority,
+    /// 1-based position in dispatch order
+    pub position: usize,
+    /// Time spent in the queue so far
+    pub waiting_seconds: u64,
+    /// Estimated seconds until the build starts, from recent build durations and the
+    /// number of workers; `None` when there is nothing to estimate from.
+    pub eta_seconds: Option<u64>,
+}
+
+/// Estimated seconds until the build at 1-based `position` starts, assuming `workers`
+/// workers take builds in waves of `average_build_secs`, after the `running` builds.
+fn estimate_start_seconds(
+    position: usize,
+    running: usize,
+    workers: usize,
+    average_build_secs: Option<u64>,
+) -> Option<u64> {
+    let average_build_secs = average_build_secs?;
+    if workers == 0 {
+        return None;
+    }
+    let waves = (position.saturating_sub(1) + running) / workers;
+    Some(waves as u64 * average_build_secs)
 }
 
 /// Mandatory Information for building a task
@@ -130,7 +283,72 @@ pub struct PendingBuildEventV2 {
     pub event_payload: BuildEventPayload,
     pub(crate) targets: Vec<BuildTargetStateDTO>,
     pub(crate) changes: Vec<Status<ProjectRelativePath>>,
+    pub(crate) requirements: BuildRequirements,
+    pub(crate) priority: BuildPriority,
     pub(crate) created_at: Instant,
+    /// Wall-clock enqueue time, persisted so a restart keeps the queue order
+    pub(crate) enqueued_at: DateTimeUtc,
+}
+
+/// Row persisting `task` in the `orion_build_queue` table.
+fn queue_entry(
+    task: &PendingBuildEventV2,
+) -> Result<callisto::orion_build_queue::Model, serde_json::Error> {
+    let (build_target_id, target_path) = task
+        .targets
+        .first()
+        .map(|target| (target.id, target.path.clone()))
+        .unwrap_or_else(|| (Uuid::nil(), "//".to_string()));
+    Ok(callisto::orion_build_queue::Model {
+        build_event_id: task.event_payload.build_event_id,
+        task_id: task.event_payload.task_id,
+        repo: task.event_payload.repo.clone(),
+        cl_link: task.event_payload.cl_link.clone(),
+        build_target_id,
+        target_path,
+        changes: serde_json::to_value(&task.changes)?,
+        requirements: serde_json::to_value(&task.requirements)?,
+        priority: task.priority.as_str().to_string(),
+        retry_count: task.event_payload.retry_count,
+        enqueued_at: task
+            .enqueued_at
+            .with_timezone(&FixedOffset::east_opt(0).unwrap()),
+    })
+}
+
+/// Queued build restored from its `orion_build_queue` row.
+fn pending_from_queue_entry(
+    entry: callisto::orion_build_queue::Model,
+) -> Result<PendingBuildEventV2, String> {
+    let changes = serde_json::from_value(entry.changes)
+        .map_err(|e| format!("invalid persisted changes: {e}"))?;
+    let requirements = serde_json::from_value(entry.requirements)
+        .map_err(|e| format!("invalid persisted requirements: {e}"))?;
+    let enqueued_at = entry.enqueued_at.with_timezone(&chrono::Utc);
+    // Keep the time already spent in the queue so the wait limit still applies.
+    let waited = (chrono::Utc::now() - enqueued_at)
+        .to_std()
+        .unwrap_or_default();
+    let now = Instant::now();
+    Ok(PendingBuildEventV2 {
+        event_payload: BuildEventPayload::new(
+            entry.build_event_id,
+            entry.task_id,
+            entry.cl_link,
+            entry.repo,
+            entry.retry_count,
+        ),
+        targets: vec![BuildTargetStateDTO {
+            id: entry.build_target_id,
+            path: entry.target_path,
+            state: TargetState::Uninitialized,
+        }],
+        changes,
+        requirements,
+        priority: BuildPriority::parse(&entry.priority).unwrap_or_default(),
+        created_at: now.checked_sub(waited).unwrap_or(now),
+        enqueued_at,
+    })
 }
 
 /// Information for an active model
@@ -187,6 +405,18 @@ impl WorkerStatus {
     }
 }
 
+/// Result of [`TaskScheduler::cancel_build`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The build never reached a worker (still queued, or its worker is gone) and has been
+    /// finalized as `Cancelled`.
+    Finalized,
+    /// `TaskCancel` was sent to the worker; the build is finalized once it reports back.
+    Requested,
+    /// The build is neither queued nor running on this server.
+    NotRunning,
+}
+
 /// Information about a connected worker
 #[derive(Debug)]
 pub struct WorkerInfo {
@@ -196,6 +426,50 @@ pub struct WorkerInfo {
     pub hostname: String,
     pub start_time: DateTimeUtc,
     pub orion_version: String,
+    pub capabilities: WorkerCapabilities,
+}
+
+/// Capabilities of workers that disconnected within the grace period.
+#[derive(Debug)]
+pub struct DepartedWorkers {
+    grace: Duration,
+    started_at: Instant,
+    workers: DashMap<String, (WorkerCapabilities, Instant)>,
+}
+
+impl DepartedWorkers {
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            started_at: Instant::now(),
+            workers: DashMap::new(),
+        }
+    }
+
+    fn depart(&self, worker_id: &str, capabilities: WorkerCapabilities) {
+        self.workers
+            .insert(worker_id.to_owned(), (capabilities, Instant::now()));
+    }
+
+    fn rejoin(&self, worker_id: &str) {
+        self.workers.remove(worker_id);
+    }
+
+    /// Whether workers may still be reconnecting after the scheduler started.
+    fn settling(&self) -> bool {
+        self.started_at.elapsed() < self.grace
+    }
+
+    /// Capabilities of the workers that left within the grace period; older ones are
+    /// forgotten.
+    fn recent(&self) -> Vec<WorkerCapabilities> {
+        self.workers
+            .retain(|_, (_, departed_at)| departed_at.elapsed() < self.grace);
+        self.workers
+            .iter()
+            .map(|entry| entry.value().0.clone())
+            .collect()
+    }
 }
 
 /// Task scheduler - manages task queue and worker assignment
@@ -207,12 +481,69 @@ pub struct TaskScheduler {
     pub task_notifier: Arc<Notify>,
     /// Worker information
     pub workers: Arc<DashMap<String, WorkerInfo>>,
+    /// Workers that disconnected recently, see [`Self::unsatisfiable_reason`]
+    departed_workers: Arc<DepartedWorkers>,
     /// Active build tasks
     pub active_builds: Arc<DashMap<String, BuildInfo>>,
     /// Database connection
     pub conn: DatabaseConnection,
     /// Log service, used to persist explanatory logs for timed-out builds
     pub log_service: LogService,
+    /// Worker requirements per repo path or target, see [`requirements_for`]
+    routing_rules: Arc<Vec<BuildRoutingRule>>,
+}
+
+/// Worker requirements of a build of `repo` for `targets`: `requested` combined with
+/// every routing rule whose path and target prefixes match.
+pub fn requirements_for(
+    rules: &[BuildRoutingRule],
+    repo: &str,
+    targets: &[String],
+    requested: &BuildRequirements,
+) -> BuildRequirements {
+    let mut requirements = requested.clone();
+    for rule in rules {
+        let path_matches = rule
+            .path_prefix
+            .as_deref()
+            .is_none_or(|prefix| path_has_prefix(repo, prefix));
+        let target_matches = rule.target_prefix.as_deref().is_none_or(|prefix| {
+            targets
+                .iter()
+                .any(|target| target_has_prefix(target, prefix))
+        });
+        if path_matches && target_matches {
+            requirements.merge(&BuildRequirements {
+                labels: rule.labels.clone(),
+                min_cpu_cores: rule.min_cpu_cores,
+                min_memory_mb: rule.min_memory_mb,
+                os: rule.os.clone(),
+                toolchains: rule.toolchains.clone(),
+            });
+        }
+    }
+    requirements
+}
+
+/// Whether `path` is `prefix` or below it, comparing whole path components.
+pub(crate) fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    let path = path.trim_matches('/');
+    let prefix = prefix.trim_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Whether `target` is `prefix` or inside it, comparing whole package components:
+/// `//orion` matches `//orion:orion` and `//orion/sub:lib` but not `//orion_ext:lib`.
+pub(crate) fn target_has_prefix(target: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches(['/', ':']);
+    target == prefix
+        || target
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/') || rest.starts_with(':'))
 }
 
 impl TaskScheduler {
@@ -223,20 +554,78 @@ impl TaskScheduler {
         active_builds: Arc<DashMap<String, BuildInfo>>,
         queue_config: Option<TaskQueueConfig>,
         log_service: LogService,
+        routing_rules: Vec<BuildRoutingRule>,
     ) -> Self {
         let config = queue_config.unwrap_or_default();
         Self {
             pending_tasks: Arc::new(Mutex::new(TaskQueue::new(config))),
             task_notifier: Arc::new(Notify::new()),
             workers,
+            departed_workers: Arc::new(DepartedWorkers::new(WORKER_GRACE_PERIOD)),
             active_builds,
             conn,
             log_service,
+            routing_rules: Arc::new(routing_rules),
         }
     }
 
+    /// Add a worker that connected and registered.
+    pub fn register_worker(&self, worker_id: String, info: WorkerInfo) {
+        self.departed_workers.rejoin(&worker_id);
+        self.workers.insert(worker_id, info);
+    }
+
+    /// Remove a worker that disconnected; its capabilities still count for
+    /// [`WORKER_GRACE_PERIOD`].
+    pub fn remove_worker(&self, worker_id: &str) {
+        if let Some((_, info)) = self.workers.remove(worker_id) {
+            self.departed_workers.depart(worker_id, info.capabilities);
+        }
+    }
+
+    /// Worker requirements of a build, see [`requirements_for`].
+    pub fn build_requirements(
+        &self,
+        repo: &str,
+        targets: &[String],
+        requested: &BuildRequirements,
+    ) -> BuildRequirements {
+        requirements_for(&self.routing_rules, repo, targets, requested)
+    }
+
+    /// Explain why no worker can ever run a build with `requirements`, or `None` if one
+    /// can. Workers that disconnected within [`WORKER_GRACE_PERIOD`] still count, and
+    /// nothing is judged during that period after startup or with no workers known at all.
+    pub fn unsatisfiable_reason(&self, requirements: &BuildRequirements) -> Option<String> {
+        if requirements.is_empty() || self.departed_workers.settling() {
+            return None;
+        }
+        let connected: Vec<WorkerCapabilities> = self
+            .workers
+            .iter()
+            .map(|entry| entry.value().capabilities.clone())
+            .collect();
+        let mut closest: Option<Vec<String>> = None;
+        for capabilities in connected.iter().chain(&self.departed_workers.recent()) {
+            let unmet = requirements.unmet_by(capabilities);
+            if unmet.is_empty() {
+                return None;
+            }
+            if closest.as_ref().is_none_or(|best| unmet.len() < best.len()) {
+                closest = Some(unmet);
+            }
+        }
+        closest.map(|unmet| {
+            format!(
+                "No worker satisfies the build requirements; the closest worker lacks {}",
+                unmet.join(", ")
+            )
+        })
+    }
+
     // NOTE: old `targets` table has been migrated away in favor of `callisto::build_targets`.
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn enqueue_task_v2(
         &self,
         task_id: Uuid,
@@ -244,6 +633,8 @@ impl TaskScheduler {
         repo: String,
         changes: Vec<Status<ProjectRelativePath>>,
         retry_count: i32,
+        requirements: BuildRequirements,
+        priority: BuildPriority,
     ) -> Result<Uuid, String> {
         let build_event_id = Uuid::now_v7();
 
@@ -254,6 +645,8 @@ impl TaskScheduler {
             repo,
             changes,
             retry_count,
+            requirements,
+            priority,
         )
         .await?;
 
@@ -269,6 +662,8 @@ impl TaskScheduler {
         repo: String,
         changes: Vec<Status<ProjectRelativePath>>,
         retry_count: i32,
+        requirements: BuildRequirements,
+        priority: BuildPriority,
     ) -> Result<(), String> {
         // Ensure the build event row exists in DB for queued tasks.
         BuildEventsRepo::insert_build(&self.conn, build_event_id, task_id, repo.clone())
@@ -277,27 +672,16 @@ impl TaskScheduler {
 
         // Ensure there is at least one default build target.
         let default_target_id = Uuid::now_v7();
-        let _default_path =
+        let default_path =
             BuildTargetsRepo::insert_default_target(default_target_id, task_id, &self.conn)
                 .await
                 .map_err(|e| format!("Failed to insert default build target: {e}"))?;
 
-        // Initialize a per-build target history record so UI can show "Pending" immediately.
-        let now = chrono::Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
-        let _ = callisto::target_state_histories::ActiveModel {
-            id: Set(Uuid::now_v7()),
-            build_target_id: Set(default_target_id),
-            build_event_id: Set(build_event_id),
-            target_state: Set(TargetState::Pending.to_string()),
-            created_at: Set(now),
-        }
-        .insert(&self.conn)
-        .await;
-
-        let targets =
-            BuildTargetsRepo::find_initialized_build_targets(build_event_id, task_id, &self.conn)
-                .await
-                .map_err(|e| format!("Failed to find initialized build targets: {e}"))?;
+        let target = BuildTargetStateDTO {
+            id: default_target_id,
+            path: default_path,
+            state: TargetState::Uninitialized,
+        };
         let event = BuildEventPayload::new(
             build_event_id,
             task_id,
@@ -305,17 +689,68 @@ impl TaskScheduler {
             repo,
             retry_count,
         );
+        self.enqueue_build_v2(event, target, changes, requirements, priority)
+            .await
+    }
+
+    /// Queue a build whose build event and target already exist, e.g. a retry.
+    ///
+    /// The build is persisted before it enters the in-memory queue so that it is
+    /// restored by [`Self::restore_queued_builds_on_startup`] after a restart. If it
+    /// cannot be queued, the build is finalized as `Interrupted`.
+    pub(crate) async fn enqueue_build_v2(
+        &self,
+        event_payload: BuildEventPayload,
+        target: BuildTargetStateDTO,
+        changes: Vec<Status<ProjectRelativePath>>,
+        requirements: BuildRequirements,
+        priority: BuildPriority,
+    ) -> Result<(), String> {
+        // Initialize a per-build target history record so UI can show "Pending" immediately.
+        let now = chrono::Utc::now();
+        let _ = TargetStateHistoriesRepo::upsert_state(
+            &self.conn,
+            target.id,
+            event_payload.build_event_id,
+            TargetState::Pending.to_string(),
+            now.with_timezone(&FixedOffset::east_opt(0).unwrap()),
+        )
+        .await;
 
         let pending_build_event = PendingBuildEventV2 {
-            event_payload: event,
-            targets,
+            event_payload,
+            targets: vec![target],
             changes,
+            requirements,
+            priority,
             created_at: Instant::now(),
+            enqueued_at: now,
         };
+        let build_event_id = pending_build_event.event_payload.build_event_id;
 
-        {
-            let mut queue = self.pending_tasks.lock().await;
-            queue.enqueue_v2(pending_build_event)?;
+        let result = match queue_entry(&pending_build_event) {
+            Ok(entry) => OrionBuildQueueRepo::insert(&self.conn, entry)
+                .await
+                .map_err(|e| format!("Failed to persist queued build: {e}")),
+            Err(e) => Err(format!("Failed to serialize queued build: {e}")),
+        };
+        let result = match result {
+            Ok(()) => {
+                let mut queue = self.pending_tasks.lock().await;
+                queue.enqueue_v2(pending_build_event.clone())
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Cannot queue build {}: {}", build_event_id, e);
+            self.finalize_interrupted_build(
+                build_event_id,
+                pending_build_event.event_payload.task_id,
+                &pending_build_event.event_payload.repo,
+                &format!("Build could not be queued ({e}); build marked as Interrupted.\n"),
+            )
+            .await;
+            return Err(e);
         }
 
         // Notify that there's a new task to process
@@ -323,10 +758,131 @@ impl TaskScheduler {
         Ok(())
     }
 
-    /// Get queue statistics
+    /// Reload builds persisted in the queue by a previous instance. Must run before
+    /// [`Self::reconcile_orphaned_builds_on_startup`], which leaves queued builds alone.
+    pub async fn restore_queued_builds_on_startup(&self) {
+        let entries = match OrionBuildQueueRepo::list_all(&self.conn).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::error!("Failed to load the persisted build queue: {}", e);
+                return;
+            }
+        };
+        if entries.is_empty() {
+            return;
+        }
+
+        let mut restored = 0;
+        for entry in entries {
+            let build_event_id = entry.build_event_id;
+            // A build finalized while its queue row was left behind is not queued anymore.
+            match BuildEventsRepo::find_by_id(&self.conn, build_event_id).await {
+                Ok(Some(build)) if build.end_at.is_none() => {}
+                Ok(_) => {
+                    let _ = OrionBuildQueueRepo::delete(&self.conn, build_event_id).await;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to load queued build {} on startup: {}",
+                        build_event_id,
+                        e
+                    );
+                    continue;
+                }
+            }
+
+            let (task_id, repo) = (entry.task_id, entry.repo.clone());
+            let result = match pending_from_queue_entry(entry) {
+                Ok(task) => self.pending_tasks.lock().await.enqueue_v2(task),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => restored += 1,
+                Err(e) => {
+                    tracing::warn!("Cannot restore queued build {}: {}", build_event_id, e);
+                    self.finalize_interrupted_build(
+                        build_event_id,
+                        task_id,
+                        &repo,
+                        &format!(
+                            "Build could not be restored to the queue after a server restart ({e}); build marked as Interrupted.\n"
+                        ),
+                    )
+                    .await;
+                }
+            }
+        }
+
+        tracing::info!("Restored {} queued build(s) from the database", restored);
+        self.task_notifier.notify_one();
+    }
+
+    /// Number of running builds per repo, for fair sharing between repos.
+    fn running_builds_per_repo(&self) -> HashMap<String, usize> {
+        let mut running: HashMap<String, usize> = HashMap::new();
+        for build in self.active_builds.iter() {
+            *running.entry(build.event_payload.repo.clone()).or_default() += 1;
+        }
+        running
+    }
+
+    /// Get queue statistics, with each queued build's position and estimated start.
     pub async fn get_queue_stats(&self) -> TaskQueueStats {
+        let running = self.running_builds_per_repo();
+        let mut stats = {
+            let queue = self.pending_tasks.lock().await;
+            queue.get_stats(&running)
+        };
+        if stats.builds.is_empty() {
+            return stats;
+        }
+
+        let average_build_secs =
+            match BuildEventsRepo::list_recently_completed(&self.conn, ETA_SAMPLE_SIZE).await {
+                Ok(builds) => {
+                    let durations: Vec<i64> = builds
+                        .iter()
+                        .filter_map(|build| {
+                            build.end_at.map(|end| (end - build.start_at).num_seconds())
+                        })
+                        .filter(|secs| *secs >= 0)
+                        .collect();
+                    (!durations.is_empty())
+                        .then(|| durations.iter().sum::<i64>() as u64 / durations.len() as u64)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to load recent builds for queue ETAs: {}", e);
+                    None
+                }
+            };
+        let workers = self
+            .workers
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.value().status,
+                    WorkerStatus::Idle | WorkerStatus::Busy { .. }
+                )
+            })
+            .count();
+        let running = self.active_builds.len();
+        for build in &mut stats.builds {
+            build.eta_seconds =
+                estimate_start_seconds(build.position, running, workers, average_build_secs);
+        }
+        stats
+    }
+
+    /// 1-based position of a queued build in dispatch order.
+    pub async fn queue_position(&self, build_event_id: Uuid) -> Option<usize> {
+        let running = self.running_builds_per_repo();
         let queue = self.pending_tasks.lock().await;
-        queue.get_stats()
+        queue
+            .dispatch_order_v2(&running)
+            .iter()
+            .position(|task| task.event_payload.build_event_id == build_event_id)
+            .map(|index| index + 1)
     }
 
     /// Clean up expired task-bound builds
@@ -349,33 +905,121 @@ impl TaskScheduler {
             .await;
     }
 
-    /// Immediately finalize a build that cannot be scheduled because no worker is
-    /// available, marking it `Interrupted` with an explanatory log. Used instead
-    /// of queuing the build when there are no idle workers, so the UI shows a
-    /// clear terminal state (retryable) rather than a build stuck in the queue.
-    pub async fn fail_unschedulable_build(&self, build_event_id: Uuid, task_id: Uuid, repo: &str) {
-        self.finalize_interrupted_build(
+    /// Finalize a build whose requirements no registered worker meets, logging `reason`
+    /// from [`Self::unsatisfiable_reason`].
+    pub async fn fail_unsatisfiable_build(
+        &self,
+        build_event_id: Uuid,
+        task_id: Uuid,
+        repo: &str,
+        reason: &str,
+    ) {
+        let message = format!("{reason}; build marked as Interrupted.\n");
+        self.finalize_interrupted_build(build_event_id, task_id, repo, &message)
+            .await;
+    }
+
+    /// Fail queued builds that no connected or recently departed worker can run instead of
+    /// letting them wait for the queue timeout.
+    async fn fail_unsatisfiable_queued_builds(&self) {
+        let unsatisfiable: Vec<(PendingBuildEventV2, String)> = {
+            let mut reasons = std::collections::HashMap::new();
+            let mut queue = self.pending_tasks.lock().await;
+            let removed =
+                queue.remove_where_v2(|task| match self.unsatisfiable_reason(&task.requirements) {
+                    Some(reason) => {
+                        reasons.insert(task.event_payload.build_event_id, reason);
+                        true
+                    }
+                    None => false,
+                });
+            removed
+                .into_iter()
+                .map(|task| {
+                    let reason = reasons
+                        .remove(&task.event_payload.build_event_id)
+                        .unwrap_or_default();
+                    (task, reason)
+                })
+                .collect()
+        };
+        for (task, reason) in unsatisfiable {
+            tracing::warn!(
+                "Queued build {}/{} ({}) cannot run on any registered worker: {}",
+                task.event_payload.task_id,
+                task.event_payload.build_event_id,
+                task.event_payload.repo,
+                reason
+            );
+            self.fail_unsatisfiable_build(
+                task.event_payload.build_event_id,
+                task.event_payload.task_id,
+                &task.event_payload.repo,
+                &reason,
+            )
+            .await;
+        }
+    }
+
+    async fn finalize_interrupted_build(
+        &self,
+        build_event_id: Uuid,
+        task_id: Uuid,
+        repo: &str,
+        message: &str,
+    ) {
+        self.finalize_unfinished_build(
+            build_event_id,
+            task_id,
+            repo,
+            message,
+            TargetState::Interrupted,
+        )
+        .await;
+    }
+
+    /// Finalize a build stopped by a cancellation request, see [`Self::cancel_build`].
+    pub async fn finalize_cancelled_build(
+        &self,
+        build_event_id: Uuid,
+        task_id: Uuid,
+        repo: &str,
+        message: &str,
+    ) {
+        let message = format!("{}\n", message.trim_end());
+        self.finalize_unfinished_build(
             build_event_id,
             task_id,
             repo,
-            "No build worker is currently available; build marked as Interrupted.\n",
+            &message,
+            TargetState::Cancelled,
         )
         .await;
     }
 
-    /// Core, idempotent build finalization: atomically claim the interrupt
+    /// Core, idempotent build finalization: atomically claim the end-of-build
     /// transition, then (only if we won the claim) persist an explanatory log
     /// line, mark the local log complete, upload it, and move the task's targets
-    /// to `Interrupted`.
-    async fn finalize_interrupted_build(
+    /// to `final_state`.
+    async fn finalize_unfinished_build(
         &self,
         build_event_id: Uuid,
         task_id: Uuid,
         repo: &str,
         message: &str,
+        final_state: TargetState,
     ) {
         let now = chrono::Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
 
+        // A finalized build must not be restored into the queue after a restart.
+        if let Err(e) = OrionBuildQueueRepo::delete(&self.conn, build_event_id).await {
+            tracing::error!(
+                "Failed to remove build {} from the persisted queue: {}",
+                build_event_id,
+                e
+            );
+        }
+
         // Atomically claim the interrupt transition first. If another path (the
         // in-memory timeout, the DB stale-build sweep, or startup reconciliation)
         // already finalized this build, `rows_affected` is 0 and we must not write
@@ -426,16 +1070,14 @@ impl TaskScheduler {
                     if TargetState::from(target.latest_state.clone()).is_terminal() {
                         continue;
                     }
-                    if let Err(e) = BuildTargetsRepo::update_latest_state(
-                        &self.conn,
-                        target.id,
-                        TargetState::Interrupted,
-                    )
-                    .await
+                    if let Err(e) =
+                        BuildTargetsRepo::update_latest_state(&self.conn, target.id, final_state)
+                            .await
                     {
                         tracing::error!(
-                            "Failed to set target {} to Interrupted for expired build {}: {}",
+                            "Failed to set target {} to {} for build {}: {}",
                             target.id,
+                            final_state,
                             build_event_id,
                             e
                         );
@@ -444,13 +1086,14 @@ impl TaskScheduler {
                         &self.conn,
                         target.id,
                         build_event_id,
-                        TargetState::Interrupted.to_string(),
+                        final_state.to_string(),
                         now,
                     )
                     .await
                     {
                         tracing::error!(
-                            "Failed to record Interrupted history for target {} build {}: {}",
+                            "Failed to record {} history for target {} build {}: {}",
+                            final_state,
                             target.id,
                             build_event_id,
                             e
@@ -458,10 +1101,132 @@ impl TaskScheduler {
                     }
                 }
             }
-            Err(e) => tracing::error!("Failed to list targets for expired task {}: {}", task_id, e),
+            Err(e) => tracing::error!("Failed to list targets for task {}: {}", task_id, e),
         }
     }
 
+    /// Cancel a queued or running build.
+    ///
+    /// Queued builds are removed and finalized right away. Running builds get a
+    /// `TaskCancel` message; the worker kills buck2, releases its mounts and answers
+    /// with `TaskBuildCancelled`, which finalizes the build as `Cancelled`.
+    pub async fn cancel_build(
+        &self,
+        build_event_id: Uuid,
+        reason: &str,
+    ) -> Result<CancelOutcome, String> {
+        let dequeued = {
+            let mut queue = self.pending_tasks.lock().await;
+            queue.remove_where_v2(|task| task.event_payload.build_event_id == build_event_id)
+        };
+        if let Some(task) = dequeued.first() {
+            tracing::info!("Cancelled queued build {}: {}", build_event_id, reason);
+            self.finalize_cancelled_build(
+                build_event_id,
+                task.event_payload.task_id,
+                &task.event_payload.repo,
+                &format!("Build cancelled before it started: {reason}"),
+            )
+            .await;
+            return Ok(CancelOutcome::Finalized);
+        }
+
+        let build_key = build_event_id.to_string();
+        let Some((worker_id, task_id, repo)) = self.active_builds.get(&build_key).map(|build| {
+            (
+                build.worker_id.clone(),
+                build.event_payload.task_id,
+                build.event_payload.repo.clone(),
+            )
+        }) else {
+            return Ok(CancelOutcome::NotRunning);
+        };
+
+        let sent = self.workers.get(&worker_id).map(|worker| {
+            worker
+                .sender
+                .send(WSMessage::TaskCancel {
+                    build_id: build_key.clone(),
+                    reason: reason.to_string(),
+                })
+                .is_ok()
+        });
+        match sent {
+            Some(true) => {
+                tracing::info!(
+                    "Requested cancellation of build {} on worker {}: {}",
+                    build_event_id,
+                    worker_id,
+                    reason
+                );
+                Ok(CancelOutcome::Requested)
+            }
+            Some(false) => Err(format!("Failed to send cancellation to worker {worker_id}")),
+            None => {
+                // The worker is gone, so nothing is left to stop.
+                self.active_builds.remove(&build_key);
+                self.finalize_cancelled_build(
+                    build_event_id,
+                    task_id,
+                    &repo,
+                    &format!("Build cancelled: {reason}"),
+                )
+                .await;
+                Ok(CancelOutcome::Finalized)
+            }
+        }
+    }
+
+    /// Cancel queued and running builds of `repo` for `cl_link` that belong to
+    /// another task, i.e. builds superseded by a newer push to the same CL.
+    /// Returns the ids of the builds that were cancelled.
+    pub async fn cancel_superseded_builds(
+        &self,
+        cl_link: &str,
+        repo: &str,
+        current_task_id: Uuid,
+    ) -> Vec<Uuid> {
+        if cl_link.is_empty() {
+            return Vec::new();
+        }
+        let is_superseded = |payload: &BuildEventPayload| {
+            payload.cl_link == cl_link && payload.repo == repo && payload.task_id != current_task_id
+        };
+
+        let mut superseded: Vec<Uuid> = {
+            let queue = self.pending_tasks.lock().await;
+            queue
+                .queue_v2
+                .iter()
+                .filter(|task| is_superseded(&task.event_payload))
+                .map(|task| task.event_payload.build_event_id)
+                .collect()
+        };
+        superseded.extend(
+            self.active_builds
+                .iter()
+                .filter(|build| is_superseded(&build.event_payload))
+                .map(|build| build.event_payload.build_event_id),
+        );
+
+        let mut cancelled = Vec::with_capacity(superseded.len());
+        for build_event_id in superseded {
+            match self
+                .cancel_build(build_event_id, "superseded by a newer build of the same CL")
+                .await
+            {
+                Ok(CancelOutcome::NotRunning) => {}
+                Ok(_) => cancelled.push(build_event_id),
+                Err(e) => tracing::warn!(
+                    "Failed to cancel superseded build {}: {}",
+                    build_event_id,
+                    e
+                ),
+            }
+        }
+        cancelled
+    }
+
     /// Mark a build that timed out in the in-memory queue (no worker became
     /// available) as ended.
     async fn fail_expired_build(&self, expired: &PendingBuildEventV2) {
@@ -480,8 +1245,10 @@ impl TaskScheduler {
     }
 
     /// DB-level safety net for queued builds that are no longer present in this
-    /// process's in-memory queue (for example after restart or multi-instance
-    /// routing). These would otherwise remain `Uninitialized` forever.
+    /// process's in-memory queue (for example multi-instance routing, or a build
+    /// lost while being dispatched). These would otherwise remain `Uninitialized`
+    /// forever. Builds restored from the persisted queue are in memory again and
+    /// left alone.
     async fn cleanup_stale_queued_builds(&self) {
         let max_wait_time = {
             let queue = self.pending_tasks.lock().await;
@@ -501,9 +1268,10 @@ impl TaskScheduler {
             }
         };
 
+        let queued = self.queued_build_ids().await;
         for build in stale_builds {
             let build_id = build.id.to_string();
-            if self.active_builds.contains_key(&build_id) {
+            if self.active_builds.contains_key(&build_id) || queued.contains(&build.id) {
                 continue;
             }
 
@@ -566,6 +1334,9 @@ impl TaskScheduler {
     /// targets — would never reach a terminal state, since the periodic queue
     /// sweep only reconciles `Uninitialized` (still-queued) builds and the
     /// dead-worker check only covers currently-registered workers.
+    ///
+    /// Builds restored by [`Self::restore_queued_builds_on_startup`] are still
+    /// waiting for a worker and are not orphans.
     pub async fn reconcile_orphaned_builds_on_startup(&self) {
         let now = chrono::Utc::now().with_timezone(&FixedOffset::east_opt(0).unwrap());
 
@@ -580,6 +1351,11 @@ impl TaskScheduler {
             }
         };
 
+        let queued = self.queued_build_ids().await;
+        let orphaned: Vec<_> = orphaned
+            .into_iter()
+            .filter(|build| !queued.contains(&build.id))
+            .collect();
         if orphaned.is_empty() {
             return;
         }
@@ -631,6 +1407,15 @@ impl TaskScheduler {
         }
     }
 
+    async fn queued_build_ids(&self) -> HashSet<Uuid> {
+        let queue = self.pending_tasks.lock().await;
+        queue
+            .queue_v2
+            .iter()
+            .map(|task| task.event_payload.build_event_id)
+            .collect()
+    }
+
     /// Check if there are available workers
     pub fn has_idle_workers(&self) -> bool {
         self.workers
@@ -638,6 +1423,14 @@ impl TaskScheduler {
             .any(|entry| matches!(entry.value().status, WorkerStatus::Idle))
     }
 
+    /// Check if an idle worker meets `requirements`
+    pub fn has_idle_worker_for(&self, requirements: &BuildRequirements) -> bool {
+        self.workers.iter().any(|entry| {
+            matches!(entry.value().status, WorkerStatus::Idle)
+                && requirements.is_satisfied_by(&entry.value().capabilities)
+        })
+    }
+
     /// Get list of idle workers
     pub fn get_idle_workers(&self) -> Vec<String> {
         self.workers
@@ -647,14 +1440,28 @@ impl TaskScheduler {
             .collect()
     }
 
-    /// Search available worker and claim the worker for current build
-    pub fn search_and_claim_worker(&self, build_id: &str) -> Option<String> {
-        let idle_workers: Vec<String> = self
-            .workers
+    /// Get idle workers meeting `requirements`
+    pub fn get_idle_workers_for(&self, requirements: &BuildRequirements) -> Vec<String> {
+        self.workers
             .iter()
-            .filter(|entry| matches!(entry.value().status, WorkerStatus::Idle))
+            .filter(|entry| {
+                matches!(entry.value().status, WorkerStatus::Idle)
+                    && requirements.is_satisfied_by(&entry.value().capabilities)
+            })
             .map(|entry| entry.key().clone())
-            .collect();
+            .collect()
+    }
+
+    /// Search an idle worker meeting `requirements` and claim it for current build
+    pub fn search_and_claim_worker(
+        &self,
+        build_id: &str,
+        requirements: &BuildRequirements,
+    ) -> Option<String> {
+        let idle_workers = self.get_idle_workers_for(requirements);
+        if idle_workers.is_empty() {
+            return None;
+        }
         let chosen_worker_idx = {
             let mut rng = rand::rng();
             rng.random_range(0..idle_workers.len())
@@ -680,36 +1487,34 @@ impl TaskScheduler {
 
     /// Try to dispatch queued task-bound builds (concurrent safe)
     pub async fn process_pending_tasks(&self) {
-        // Get available workers
-        let idle_workers = self.get_idle_workers();
+        // Get available workers with what they can run, in random order to spread load
+        let mut idle_workers: Vec<(String, WorkerCapabilities)> = self
+            .workers
+            .iter()
+            .filter(|entry| matches!(entry.value().status, WorkerStatus::Idle))
+            .map(|entry| (entry.key().clone(), entry.value().capabilities.clone()))
+            .collect();
         if idle_workers.is_empty() {
             return;
         }
+        idle_workers.shuffle(&mut rand::rng());
 
-        // Process tasks in batches, up to the number of idle workers
-        let max_tasks = idle_workers.len();
-        let mut tasks_to_dispatch = Vec::with_capacity(max_tasks);
-
-        // Batch dequeue tasks
-        {
+        // Pair queued builds with idle workers meeting their requirements, by priority
+        // and fair share between repos
+        let running = self.running_builds_per_repo();
+        let tasks_to_dispatch = {
             let mut queue = self.pending_tasks.lock().await;
-            for _ in 0..max_tasks {
-                if let Some(task) = queue.queue_v2.pop_front() {
-                    tasks_to_dispatch.push(task);
-                } else {
-                    break;
-                }
-            }
-        }
+            queue.take_dispatchable_v2(&mut idle_workers, &running)
+        };
 
         // Dispatch tasks concurrently
         if !tasks_to_dispatch.is_empty() {
             let dispatch_futures: Vec<_> = tasks_to_dispatch
                 .into_iter()
-                .map(|task| {
+                .map(|(task, worker_id)| {
                     let scheduler = self.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = scheduler.dispatch_task_v2(task).await {
+                        if let Err(e) = scheduler.dispatch_task_v2(task, worker_id).await {
                             tracing::error!("Failed to dispatch queued task: {}", e);
                         }
                     })
@@ -723,22 +1528,23 @@ impl TaskScheduler {
         }
     }
 
-    /// Dispatch single queued v2 task
+    /// Dispatch single queued v2 task to the chosen idle worker
     async fn dispatch_task_v2(
         &self,
         pending_build_event: PendingBuildEventV2,
+        chosen_id: String,
     ) -> Result<(), String> {
-        let idle_workers = self.get_idle_workers();
-        if idle_workers.is_empty() {
-            return Err("No idle workers available".to_string());
+        if !self
+            .workers
+            .get(&chosen_id)
+            .is_some_and(|worker| matches!(worker.status, WorkerStatus::Idle))
+        {
+            // The worker was claimed or lost meanwhile; retry with the next round.
+            let mut queue = self.pending_tasks.lock().await;
+            queue.queue_v2.push_front(pending_build_event);
+            return Err(format!("Worker {chosen_id} is no longer idle"));
         }
 
-        // Randomly select an idle worker
-        let chosen_index = {
-            let mut rng = rand::rng();
-            rng.random_range(0..idle_workers.len())
-        };
-        let chosen_id = idle_workers[chosen_index].clone();
         let start_at = chrono::Utc::now();
         let start_at_tz = start_at.with_timezone(&FixedOffset::east_opt(0).unwrap());
 
@@ -787,7 +1593,7 @@ impl TaskScheduler {
         // Create WebSocket message
         let msg = WSMessage::TaskBuild {
             build_id: pending_build_event.event_payload.build_event_id.to_string(),
-            repo: pending_build_event.event_payload.repo,
+            repo: pending_build_event.event_payload.repo.clone(),
             cl_link: pending_build_event.event_payload.cl_link.to_string(),
             changes: pending_build_event.changes.clone(),
         };
@@ -805,8 +1611,25 @@ impl TaskScheduler {
             if worker.sender.send(msg).is_err() {
                 self.active_builds.remove(&build_key);
                 worker.status = WorkerStatus::Idle;
+                drop(worker);
+                // Keep the build at the head of the queue for the next round.
+                let mut queue = self.pending_tasks.lock().await;
+                queue.queue_v2.push_front(pending_build_event);
                 return Err(format!("Failed to send task to worker {chosen_id}"));
             }
+            drop(worker);
+            if let Err(e) = OrionBuildQueueRepo::delete(
+                &self.conn,
+                pending_build_event.event_payload.build_event_id,
+            )
+            .await
+            {
+                tracing::error!(
+                    "Failed to remove dispatched build {} from the persisted queue: {}",
+                    build_key,
+                    e
+                );
+            }
 
             tracing::info!(
                 "Queued task {}/{} dispatched to worker {}",
@@ -878,6 +1701,7 @@ impl TaskScheduler {
                     }
                 }
 
+                cleanup_scheduler.fail_unsatisfiable_queued_builds().await;
                 cleanup_scheduler.cleanup_stale_queued_builds().await;
             }
         });
@@ -915,7 +1739,10 @@ mod tests {
             event_payload: build_event1.clone(),
             targets: vec![],
             changes: vec![],
+            requirements: BuildRequirements::default(),
+            priority: BuildPriority::default(),
             created_at: Instant::now(),
+            enqueued_at: chrono::Utc::now(),
         };
 
         let build_event2 = BuildEventPayload::new(
@@ -930,14 +1757,17 @@ mod tests {
             event_payload: build_event2.clone(),
             targets: vec![],
             changes: vec![],
+            requirements: BuildRequirements::default(),
+            priority: BuildPriority::default(),
             created_at: Instant::now(),
+            enqueued_at: chrono::Utc::now(),
         };
 
         assert!(queue.enqueue_v2(task1).is_ok());
         assert!(queue.enqueue_v2(task2).is_ok());
 
         // check stats
-        let stats = queue.get_stats();
+        let stats = queue.get_stats(&HashMap::new());
         assert_eq!(stats.total_queued, 2);
     }
 
@@ -962,7 +1792,10 @@ mod tests {
             event_payload: build_event.clone(),
             targets: vec![],
             changes: vec![],
+            requirements: BuildRequirements::default(),
+            priority: BuildPriority::default(),
             created_at: Instant::now(),
+            enqueued_at: chrono::Utc::now(),
         };
 
         // Fill queue to capacity
@@ -972,4 +1805,280 @@ mod tests {
         // Should fail when full
         assert!(queue.enqueue_v2(task).is_err());
     }
+
+    #[test]
+    fn test_remove_where_keeps_other_tasks_in_order() {
+        let mut queue = TaskQueue::new(TaskQueueConfig::default());
+        let tasks: Vec<PendingBuildEventV2> = (0..3)
+            .map(|i| PendingBuildEventV2 {
+                event_payload: BuildEventPayload::new(
+                    Uuid::now_v7(),
+                    Uuid::now_v7(),
+                    format!("cl_{i}"),
+                    "/test/repo".to_string(),
+                    0,
+                ),
+                targets: vec![],
+                changes: vec![],
+                requirements: BuildRequirements::default(),
+                priority: BuildPriority::default(),
+                created_at: Instant::now(),
+                enqueued_at: chrono::Utc::now(),
+            })
+            .collect();
+        for task in &tasks {
+            queue.enqueue_v2(task.clone()).unwrap();
+        }
+
+        let cancelled = tasks[1].event_payload.build_event_id;
+        let removed = queue.remove_where_v2(|task| task.event_payload.build_event_id == cancelled);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].event_payload.build_event_id, cancelled);
+
+        let remaining: Vec<&str> = queue
+            .queue_v2
+            .iter()
+            .map(|task| task.event_payload.cl_link.as_str())
+            .collect();
+        assert_eq!(remaining, ["cl_0", "cl_2"]);
+    }
+
+    fn labelled_task(cl: &str, labels: &[&str]) -> PendingBuildEventV2 {
+        PendingBuildEventV2 {
+            event_payload: BuildEventPayload::new(
+                Uuid::now_v7(),
+                Uuid::now_v7(),
+                cl.to_string(),
+                "/test/repo".to_string(),
+                0,
+            ),
+            targets: vec![],
+            changes: vec![],
+            requirements: BuildRequirements {
+                labels: labels.iter().map(|l| l.to_string()).collect(),
+                ..Default::default()
+            },
+            priority: BuildPriority::default(),
+            created_at: Instant::now(),
+            enqueued_at: chrono::Utc::now(),
+        }
+    }
+
+    fn labelled_worker(id: &str, labels: &[&str]) -> (String, WorkerCapabilities) {
+        (
+            id.to_string(),
+            WorkerCapabilities {
+                labels: labels.iter().map(|l| l.to_string()).collect(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_take_dispatchable_matches_requirements_and_keeps_the_rest_queued() {
+        let mut queue = TaskQueue::new(TaskQueueConfig::default());
+        queue.enqueue_v2(labelled_task("cl_gpu", &["gpu"])).unwrap();
+        queue.enqueue_v2(labelled_task("cl_any", &[])).unwrap();
+        queue
+            .enqueue_v2(labelled_task("cl_large", &["large"]))
+            .unwrap();
+        queue.enqueue_v2(labelled_task("cl_any_2", &[])).unwrap();
+
+        let mut idle = vec![
+            labelled_worker("large-1", &["large"]),
+            labelled_worker("small-1", &[]),
+        ];
+        let dispatched: Vec<(String, String)> = queue
+            .take_dispatchable_v2(&mut idle, &HashMap::new())
+            .into_iter()
+            .map(|(task, worker)| (task.event_payload.cl_link, worker))
+            .collect();
+
+        assert_eq!(
+            dispatched,
+            [
+                ("cl_any".to_string(), "large-1".to_string()),
+                ("cl_any_2".to_string(), "small-1".to_string()),
+            ]
+        );
+        assert!(idle.is_empty());
+        let remaining: Vec<&str> = queue
+            .queue_v2
+            .iter()
+            .map(|task| task.event_payload.cl_link.as_str())
+            .collect();
+        assert_eq!(remaining, ["cl_gpu", "cl_large"]);
+    }
+
+    fn queued_task(cl: &str, repo: &str, priority: BuildPriority) -> PendingBuildEventV2 {
+        PendingBuildEventV2 {
+            event_payload: BuildEventPayload::new(
+                Uuid::now_v7(),
+                Uuid::now_v7(),
+                cl.to_string(),
+                repo.to_string(),
+                0,
+            ),
+            targets: vec![],
+            changes: vec![],
+            requirements: BuildRequirements::default(),
+            priority,
+            created_at: Instant::now(),
+            enqueued_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_order_by_priority_then_fair_share() {
+        let mut queue = TaskQueue::new(TaskQueueConfig::default());
+        for task in [
+            queued_task("scheduled", "/a", BuildPriority::Scheduled),
+            queued_task("a_1", "/a", BuildPriority::ChangeList),
+            queued_task("a_2", "/a", BuildPriority::ChangeList),
+            queued_task("b_1", "/b", BuildPriority::ChangeList),
+            queued_task("merge", "/m", BuildPriority::MergeQueue),
+            queued_task("c_1", "/c", BuildPriority::ChangeList),
+        ] {
+            queue.enqueue_v2(task).unwrap();
+        }
+
+        // `/c` already has a build running, so `/b` goes before it and the second `/a`
+        // build ties with it.
+        let running = HashMap::from([("/c".to_string(), 1)]);
+        let order: Vec<&str> = queue
+            .dispatch_order_v2(&running)
+            .into_iter()
+            .map(|task| task.event_payload.cl_link.as_str())
+            .collect();
+        assert_eq!(order, ["merge", "a_1", "b_1", "a_2", "c_1", "scheduled"]);
+
+        let stats = queue.get_stats(&running);
+        assert_eq!(stats.total_queued, 6);
+        assert_eq!(stats.builds[0].priority, BuildPriority::MergeQueue);
+        assert_eq!(stats.builds[5].position, 6);
+
+        let mut idle = vec![labelled_worker("w1", &[]), labelled_worker("w2", &[])];
+        let dispatched: Vec<String> = queue
+            .take_dispatchable_v2(&mut idle, &running)
+            .into_iter()
+            .map(|(task, _)| task.event_payload.cl_link)
+            .collect();
+        assert_eq!(dispatched, ["merge", "a_1"]);
+        assert_eq!(queue.queue_v2.len(), 4);
+    }
+
+    #[test]
+    fn test_estimate_start_seconds() {
+        // Two workers, both busy: the first two queued builds start after one build.
+        assert_eq!(estimate_start_seconds(1, 2, 2, Some(60)), Some(60));
+        assert_eq!(estimate_start_seconds(2, 2, 2, Some(60)), Some(60));
+        assert_eq!(estimate_start_seconds(3, 2, 2, Some(60)), Some(120));
+        // An idle worker starts the head of the queue right away.
+        assert_eq!(estimate_start_seconds(1, 0, 1, Some(60)), Some(0));
+        assert_eq!(estimate_start_seconds(1, 0, 0, Some(60)), None);
+        assert_eq!(estimate_start_seconds(1, 0, 1, None), None);
+    }
+
+    #[test]
+    fn test_queue_entry_round_trip() {
+        let mut task = queued_task("cl", "/project/mega", BuildPriority::MergeQueue);
+        task.targets = vec![BuildTargetStateDTO {
+            id: Uuid::now_v7(),
+            path: "//".to_string(),
+            state: TargetState::Uninitialized,
+        }];
+        task.changes = vec![Status::Modified(ProjectRelativePath::new("src/main.rs"))];
+        task.requirements.labels = vec!["large".to_string()];
+        task.enqueued_at = chrono::Utc::now() - chrono::Duration::seconds(90);
+
+        let entry = queue_entry(&task).unwrap();
+        assert_eq!(entry.priority, "merge_queue");
+        let restored = pending_from_queue_entry(entry).unwrap();
+        assert_eq!(
+            restored.event_payload.build_event_id,
+            task.event_payload.build_event_id
+        );
+        assert_eq!(restored.targets[0].id, task.targets[0].id);
+        assert_eq!(restored.changes, task.changes);
+        assert_eq!(restored.requirements, task.requirements);
+        assert_eq!(restored.priority, BuildPriority::MergeQueue);
+        // Time already spent in the queue carries over.
+        assert!(restored.created_at.elapsed() >= Duration::from_secs(89));
+    }
+
+    #[test]
+    fn test_departed_workers_count_within_the_grace_period() {
+        let capabilities = WorkerCapabilities {
+            labels: vec!["gpu".to_string()],
+            ..Default::default()
+        };
+
+        let departed = DepartedWorkers::new(Duration::from_secs(3600));
+        assert!(departed.settling());
+        departed.depart("w1", capabilities.clone());
+        assert_eq!(departed.recent(), vec![capabilities.clone()]);
+        departed.rejoin("w1");
+        assert!(departed.recent().is_empty());
+
+        let departed = DepartedWorkers::new(Duration::ZERO);
+        assert!(!departed.settling());
+        departed.depart("w1", capabilities);
+        assert!(departed.recent().is_empty());
+    }
+
+    #[test]
+    fn test_requirements_for_merges_matching_rules() {
+        let rules = vec![
+            BuildRoutingRule {
+                path_prefix: Some("/project/mega".to_string()),
+                labels: vec!["large".to_string()],
+                min_cpu_cores: Some(8),
+                ..Default::default()
+            },
+            BuildRoutingRule {
+                path_prefix: Some("/project/mega".to_string()),
+                target_prefix: Some("//orion".to_string()),
+                toolchains: vec!["rust".to_string()],
+                min_cpu_cores: Some(16),
+                ..Default::default()
+            },
+            BuildRoutingRule {
+                path_prefix: Some("/project/other".to_string()),
+                labels: vec!["gpu".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let requested = BuildRequirements {
+            os: Some("linux".to_string()),
+            ..Default::default()
+        };
+        let requirements = requirements_for(
+            &rules,
+            "/project/mega/",
+            &["//orion:orion".to_string()],
+            &requested,
+        );
+        assert_eq!(requirements.labels, ["large"]);
+        assert_eq!(requirements.toolchains, ["rust"]);
+        assert_eq!(requirements.min_cpu_cores, Some(16));
+        assert_eq!(requirements.os.as_deref(), Some("linux"));
+
+        let requirements = requirements_for(
+            &rules,
+            "/project/mega",
+            &["//orion_client:lib".to_string()],
+            &BuildRequirements::default(),
+        );
+        assert_eq!(requirements.min_cpu_cores, Some(8));
+        assert!(requirements.toolchains.is_empty());
+
+        let requirements = requirements_for(
+            &rules,
+            "/project/megaphone",
+            &[],
+            &BuildRequirements::default(),
+        );
+        assert!(requirements.is_empty());
+    }
 }
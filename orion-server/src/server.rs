@@ -146,10 +146,18 @@ pub async fn start_server() {
         std::process::exit(1);
     });
 
-    let state = AppState::new(conn, None, log_service);
+    let state = AppState::new(
+        conn,
+        None,
+        log_service,
+        orion_server_config.routing_rules,
+        orion_server_config.flaky_quarantine,
+    );
 
-    // Reconcile builds orphaned by a previous instance (e.g. left stuck in
-    // `Building` after a crash/restart) before we accept new work or workers.
+    // Restore builds still waiting in the persisted queue, then reconcile builds
+    // orphaned by a previous instance (e.g. left stuck in `Building` after a
+    // crash/restart) before we accept new work or workers.
+    state.scheduler.restore_queued_builds_on_startup().await;
     state.scheduler.reconcile_orphaned_builds_on_startup().await;
 
     // Start background health check task
@@ -11,8 +11,9 @@ use api_model::{
         api::{OrionBuildResult, OrionServerResponse, TaskBuildRequest},
         status::Status,
         types::{
-            LogErrorResponse, LogLinesResponse, LogReadMode, ProjectRelativePath,
-            TargetLogLinesResponse, TargetLogQuery, TaskHistoryQuery,
+            BuildRequirements, BuildStatus, BuildTestResultsResponse, LogErrorResponse,
+            LogLinesResponse, LogReadMode, ProjectRelativePath, TargetLogLinesResponse,
+            TargetLogQuery, TaskHistoryQuery, TestResultResponse, TestStatus, TestSummary,
         },
         ws::WSMessage,
     },
@@ -35,19 +36,21 @@ use crate::{
     app_state::AppState,
     model::{
         dto::{
-            BuildEventDTO, BuildStatus, BuildTargetDTO, MessageResponse, OrionClientInfo,
-            OrionClientQuery, OrionClientStatus, OrionTaskDTO,
+            BuildEventDTO, BuildTargetDTO, MessageResponse, OrionClientInfo, OrionClientQuery,
+            OrionClientStatus, OrionTaskDTO,
         },
+        internal::BuildTargetStateDTO,
         target_state::TargetState,
     },
     repository::{
         build_events_repo::BuildEventsRepo, build_targets_repo::BuildTargetsRepo,
-        orion_tasks_repo::OrionTasksRepo, target_state_histories_repo::TargetStateHistoriesRepo,
+        build_test_results_repo::BuildTestResultsRepo, orion_tasks_repo::OrionTasksRepo,
+        target_state_histories_repo::TargetStateHistoriesRepo,
     },
-    scheduler::{BuildEventPayload, BuildInfo, TaskQueueStats, WorkerStatus},
+    scheduler::{BuildEventPayload, BuildInfo, CancelOutcome, TaskQueueStats, WorkerStatus},
 };
 
-type MessageErrorResponse = (StatusCode, Json<MessageResponse>);
+pub(crate) type MessageErrorResponse = (StatusCode, Json<MessageResponse>);
 type JsonValueErrorResponse = (StatusCode, Json<Value>);
 type LogSseStream = Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>;
 
@@ -173,13 +176,16 @@ pub async fn task_output(state: &AppState, id: &str) -> Result<Sse<LogSseStream>
     Ok(Sse::new(Box::pin(select(log_stream, heartbeat_stream))))
 }
 
-fn message_response(message: impl Into<String>) -> Json<MessageResponse> {
+pub(crate) fn message_response(message: impl Into<String>) -> Json<MessageResponse> {
     Json(MessageResponse {
         message: message.into(),
     })
 }
 
-fn message_error(status: StatusCode, message: impl Into<String>) -> MessageErrorResponse {
+pub(crate) fn message_error(
+    status: StatusCode,
+    message: impl Into<String>,
+) -> MessageErrorResponse {
     (status, message_response(message))
 }
 
@@ -187,7 +193,7 @@ fn value_error(status: StatusCode, message: impl Into<String>) -> JsonValueError
     (status, Json(json!({ "message": message.into() })))
 }
 
-fn parse_uuid_or_message_error(
+pub(crate) fn parse_uuid_or_message_error(
     raw_id: &str,
     invalid_message: &str,
 ) -> Result<Uuid, MessageErrorResponse> {
@@ -368,13 +374,40 @@ pub async fn build_state(
         })?
         .ok_or_else(|| message_error(StatusCode::NOT_FOUND, "Build not found"))?;
 
-    let state_enum = match (build_event.end_at, build_event.exit_code) {
+    Ok(Json(finished_build_status(state, &build_event).await))
+}
+
+/// Build-level status of a persisted build event. Builds that ended without an
+/// exit code are reported as `Cancelled` when their targets were cancelled.
+async fn finished_build_status(
+    state: &AppState,
+    build_event: &callisto::build_events::Model,
+) -> BuildStatus {
+    match (build_event.end_at, build_event.exit_code) {
         (None, _) => BuildStatus::Running,
         (Some(_), Some(0)) => BuildStatus::Completed,
         (Some(_), Some(_)) => BuildStatus::Failed,
-        (Some(_), None) => BuildStatus::Failed,
-    };
-    Ok(Json(state_enum))
+        (Some(_), None) => {
+            match TargetStateHistoriesRepo::build_has_state(
+                &state.conn,
+                build_event.id,
+                &TargetState::Cancelled.to_string(),
+            )
+            .await
+            {
+                Ok(true) => BuildStatus::Cancelled,
+                Ok(false) => BuildStatus::Failed,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to check cancellation of build {}: {}",
+                        build_event.id,
+                        e
+                    );
+                    BuildStatus::Failed
+                }
+            }
+        }
+    }
 }
 
 pub async fn latest_build_result(
@@ -407,13 +440,101 @@ pub async fn latest_build_result(
         message_error(StatusCode::NOT_FOUND, "No build events found for this task")
     })?;
 
-    let state_enum = match (build_event.end_at, build_event.exit_code) {
-        (None, _) => BuildStatus::Running,
-        (Some(_), Some(0)) => BuildStatus::Completed,
-        (Some(_), Some(_)) => BuildStatus::Failed,
-        (Some(_), None) => BuildStatus::Failed,
+    Ok(Json(finished_build_status(state, &build_event).await))
+}
+
+fn test_result_response(model: callisto::build_test_results::Model) -> TestResultResponse {
+    TestResultResponse {
+        id: model.id.to_string(),
+        build_id: model.build_event_id.to_string(),
+        target: model.target,
+        name: model.test_name,
+        // Rows are only written from `TestStatus::as_str`.
+        status: TestStatus::parse(&model.status).unwrap_or(TestStatus::Fatal),
+        duration_ms: model.duration_ms,
+        message: model.message,
+    }
+}
+
+/// Count the results and order them failures first, keeping report order otherwise.
+fn summarize_test_results(results: &mut [TestResultResponse]) -> TestSummary {
+    results.sort_by_key(|result| !result.status.is_failure());
+    let mut summary = TestSummary {
+        total: results.len(),
+        ..Default::default()
     };
-    Ok(Json(state_enum))
+    for result in results.iter() {
+        match result.status {
+            TestStatus::Pass => summary.passed += 1,
+            TestStatus::Skip | TestStatus::Omitted => summary.skipped += 1,
+            TestStatus::Fail | TestStatus::Timeout | TestStatus::Fatal => summary.failed += 1,
+        }
+    }
+    summary
+}
+
+pub async fn build_tests(
+    state: &AppState,
+    build_id: &str,
+) -> Result<Json<BuildTestResultsResponse>, MessageErrorResponse> {
+    let build_uuid = parse_uuid_or_message_error(build_id, "Invalid build ID")?;
+    BuildEventsRepo::find_by_id(&state.conn, build_uuid)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch build event {}: {}", build_id, e);
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?
+        .ok_or_else(|| message_error(StatusCode::NOT_FOUND, "Build not found"))?;
+
+    test_results_of_build(state, build_uuid).await
+}
+
+/// Test results of the latest build of a task, which is what CI judges the task by.
+pub async fn latest_build_tests(
+    state: &AppState,
+    task_id: &str,
+) -> Result<Json<BuildTestResultsResponse>, MessageErrorResponse> {
+    let task_uuid = parse_uuid_or_message_error(task_id, "Invalid task ID")?;
+    let build_event = BuildEventsRepo::latest_by_task_id(&state.conn, task_uuid)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to fetch latest build event for task {}: {}",
+                task_id,
+                e
+            );
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?
+        .ok_or_else(|| {
+            message_error(StatusCode::NOT_FOUND, "No build events found for this task")
+        })?;
+
+    test_results_of_build(state, build_event.id).await
+}
+
+async fn test_results_of_build(
+    state: &AppState,
+    build_uuid: Uuid,
+) -> Result<Json<BuildTestResultsResponse>, MessageErrorResponse> {
+    let models = BuildTestResultsRepo::list_by_build(&state.conn, build_uuid)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to fetch test results for build {}: {}",
+                build_uuid,
+                e
+            );
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?;
+    let mut results: Vec<TestResultResponse> =
+        models.into_iter().map(test_result_response).collect();
+    let summary = summarize_test_results(&mut results);
+
+    Ok(Json(BuildTestResultsResponse {
+        build_id: build_uuid.to_string(),
+        summary,
+        results,
+    }))
 }
 
 pub async fn queue_stats(state: &AppState) -> (StatusCode, Json<TaskQueueStats>) {
@@ -715,10 +836,26 @@ pub async fn build_retry(
         };
 
     let new_build_id = Uuid::now_v7();
+    // Tasks created before requirements were persisted with them only get the path rules.
+    let requirements = match task.requirements.clone().map(serde_json::from_value) {
+        Some(Ok(requirements)) => requirements,
+        Some(Err(e)) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"message": format!("Invalid persisted requirements: {e}")})),
+            )
+                .into_response();
+        }
+        None => state
+            .scheduler
+            .build_requirements(&repo, &[], &BuildRequirements::default()),
+    };
+    let unsatisfiable = state.scheduler.unsatisfiable_reason(&requirements);
+    let idle_workers = state.scheduler.get_idle_workers_for(&requirements);
 
-    // If no idle workers, do not queue: create the build and immediately mark it
-    // Interrupted so it surfaces as a clear, retryable terminal state.
-    if !state.scheduler.has_idle_workers() {
+    // Without an idle worker the retry is queued; if no registered worker can ever
+    // run it, it is marked Interrupted right away.
+    if unsatisfiable.is_some() || idle_workers.is_empty() {
         if let Err(e) =
             BuildEventsRepo::insert_build(&state.conn, new_build_id, task.id, repo.clone()).await
         {
@@ -729,15 +866,44 @@ pub async fn build_retry(
                 .into_response();
         }
 
-        state
-            .scheduler
-            .fail_unschedulable_build(new_build_id, task.id, &repo)
-            .await;
+        let message = match &unsatisfiable {
+            Some(reason) => {
+                state
+                    .scheduler
+                    .fail_unsatisfiable_build(new_build_id, task.id, &repo, reason)
+                    .await;
+                format!("{reason}; build marked as Interrupted")
+            }
+            None => {
+                let event_payload = BuildEventPayload::new(
+                    new_build_id,
+                    task.id,
+                    cl_link,
+                    repo.clone(),
+                    retry_count,
+                );
+                let target = BuildTargetStateDTO {
+                    id: build_target.id,
+                    path: build_target.path.clone(),
+                    state: TargetState::from(build_target.latest_state.clone()),
+                };
+                match state
+                    .scheduler
+                    .enqueue_build_v2(event_payload, target, changes, requirements, req.priority)
+                    .await
+                {
+                    Ok(()) => queued_build_result(state, new_build_id).await.message,
+                    Err(e) => {
+                        format!("Unable to queue build retry ({e}); build marked as Interrupted")
+                    }
+                }
+            }
+        };
 
         (
             StatusCode::OK,
             Json(json!({
-                "message": "No build worker available; build marked as Interrupted",
+                "message": message,
                 "build_id": new_build_id.to_string(),
             })),
         )
@@ -754,7 +920,6 @@ pub async fn build_retry(
         }
 
         // Dispatch immediately to a chosen worker.
-        let idle_workers = state.scheduler.get_idle_workers();
         let chosen_index = {
             let mut rng = rand::rng();
             rng.random_range(0..idle_workers.len())
@@ -835,6 +1000,41 @@ pub async fn build_retry(
     }
 }
 
+pub async fn build_cancel(state: &AppState, build_id: &str) -> Response {
+    let build_uuid = match parse_uuid_or_value_error(build_id, "Invalid build ID format") {
+        Ok(uuid) => uuid,
+        Err(err) => return err.into_response(),
+    };
+
+    match state
+        .scheduler
+        .cancel_build(build_uuid, "cancelled by user request")
+        .await
+    {
+        Ok(CancelOutcome::Finalized) => (
+            StatusCode::OK,
+            Json(json!({"message": "Build cancelled", "build_id": build_id})),
+        )
+            .into_response(),
+        Ok(CancelOutcome::Requested) => (
+            StatusCode::ACCEPTED,
+            Json(json!({"message": "Cancellation sent to worker", "build_id": build_id})),
+        )
+            .into_response(),
+        Ok(CancelOutcome::NotRunning) => {
+            match BuildEventsRepo::find_by_id(&state.conn, build_uuid).await {
+                Ok(Some(_)) => {
+                    value_error(StatusCode::CONFLICT, "Build has already finished").into_response()
+                }
+                Ok(None) => value_error(StatusCode::NOT_FOUND, "Build not found").into_response(),
+                Err(_) => value_error(StatusCode::INTERNAL_SERVER_ERROR, "Database find failed")
+                    .into_response(),
+            }
+        }
+        Err(e) => value_error(StatusCode::BAD_GATEWAY, e).into_response(),
+    }
+}
+
 async fn activate_worker(
     build_info: &BuildInfo,
     scheduler: &crate::scheduler::TaskScheduler,
@@ -879,11 +1079,12 @@ async fn handle_immediate_task_dispatch_v2(
     repo: &str,
     cl_link: &str,
     changes: Vec<Status<ProjectRelativePath>>,
+    requirements: &BuildRequirements,
 ) -> OrionBuildResult {
     let build_event_id = Uuid::now_v7();
     let Some(chosen_id) = state
         .scheduler
-        .search_and_claim_worker(&build_event_id.to_string())
+        .search_and_claim_worker(&build_event_id.to_string(), requirements)
     else {
         return OrionBuildResult {
             build_id: "".to_string(),
@@ -956,13 +1157,16 @@ async fn handle_immediate_task_dispatch_v2(
     activate_worker(&build_info, &state.scheduler).await
 }
 
-/// Create a build event + default target for a task that cannot be scheduled
-/// (no idle worker) and immediately mark it `Interrupted`. Returns the new build
-/// id so callers can report it to the client.
-async fn reject_unschedulable_build(
+/// Create a build event + default target for a task whose requirements no
+/// registered worker meets (see
+/// [`crate::scheduler::TaskScheduler::unsatisfiable_reason`]) and immediately mark
+/// it `Interrupted` with `reason`. Returns the new build id so callers can report it
+/// to the client.
+async fn reject_unsatisfiable_build(
     state: &AppState,
     task_id: Uuid,
     repo: &str,
+    reason: &str,
 ) -> Result<Uuid, sea_orm::DbErr> {
     let build_event_id = Uuid::now_v7();
     BuildEventsRepo::insert_build(&state.conn, build_event_id, task_id, repo.to_string()).await?;
@@ -972,21 +1176,46 @@ async fn reject_unschedulable_build(
 
     state
         .scheduler
-        .fail_unschedulable_build(build_event_id, task_id, repo)
+        .fail_unsatisfiable_build(build_event_id, task_id, repo, reason)
         .await;
 
     Ok(build_event_id)
 }
 
+/// Result reported for a build that entered the queue.
+async fn queued_build_result(state: &AppState, build_id: Uuid) -> OrionBuildResult {
+    let message = match state.scheduler.queue_position(build_id).await {
+        Some(position) => {
+            format!("No build worker available; build queued at position {position}")
+        }
+        None => "No build worker available; build queued".to_string(),
+    };
+    OrionBuildResult {
+        build_id: build_id.to_string(),
+        status: "queued".to_string(),
+        message,
+    }
+}
+
 pub async fn task_handler_v2(state: &AppState, req: TaskBuildRequest) -> Response {
     let req = TaskBuildRequest {
         changes: normalize_repo_root_changes(&req.repo, req.changes),
         ..req
     };
     let task_id = Uuid::now_v7();
-    if let Err(err) =
-        OrionTasksRepo::insert_task(task_id, &req.cl_link, &req.repo, &req.changes, &state.conn)
-            .await
+    let requirements =
+        state
+            .scheduler
+            .build_requirements(&req.repo, &req.targets(), &req.requirements);
+    if let Err(err) = OrionTasksRepo::insert_task(
+        task_id,
+        &req.cl_link,
+        &req.repo,
+        &req.changes,
+        &requirements,
+        &state.conn,
+    )
+    .await
     {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -994,18 +1223,25 @@ pub async fn task_handler_v2(state: &AppState, req: TaskBuildRequest) -> Respons
         )
             .into_response();
     }
-    let result = if state.scheduler.has_idle_workers() {
-        handle_immediate_task_dispatch_v2(state, task_id, &req.repo, &req.cl_link, req.changes)
-            .await
-    } else {
-        // No worker is available: instead of queuing (which can leave a build
-        // stuck), create the build and immediately mark it Interrupted so the UI
-        // shows a clear, retryable terminal state.
-        match reject_unschedulable_build(state, task_id, &req.repo).await {
+    // A newer push to the CL makes in-flight builds of the previous revision useless.
+    let superseded = state
+        .scheduler
+        .cancel_superseded_builds(&req.cl_link, &req.repo, task_id)
+        .await;
+    if !superseded.is_empty() {
+        tracing::info!(
+            "Cancelled {} superseded build(s) of {} for CL {}",
+            superseded.len(),
+            req.repo,
+            req.cl_link
+        );
+    }
+    let result = if let Some(reason) = state.scheduler.unsatisfiable_reason(&requirements) {
+        match reject_unsatisfiable_build(state, task_id, &req.repo, &reason).await {
             Ok(build_id) => OrionBuildResult {
                 build_id: build_id.to_string(),
-                status: "interrupted".to_string(),
-                message: "No build worker available; build marked as Interrupted".to_string(),
+                status: "unsatisfiable".to_string(),
+                message: format!("{reason}; build marked as Interrupted"),
             },
             Err(e) => {
                 return (
@@ -1015,6 +1251,39 @@ pub async fn task_handler_v2(state: &AppState, req: TaskBuildRequest) -> Respons
                     .into_response();
             }
         }
+    } else if state.scheduler.has_idle_worker_for(&requirements) {
+        handle_immediate_task_dispatch_v2(
+            state,
+            task_id,
+            &req.repo,
+            &req.cl_link,
+            req.changes,
+            &requirements,
+        )
+        .await
+    } else {
+        // No worker is available right now: queue the build. The queue is persisted,
+        // so it is dispatched once a worker frees up, even across server restarts.
+        match state
+            .scheduler
+            .enqueue_task_v2(
+                task_id,
+                &req.cl_link,
+                req.repo.clone(),
+                req.changes,
+                0,
+                requirements,
+                req.priority,
+            )
+            .await
+        {
+            Ok(build_id) => queued_build_result(state, build_id).await,
+            Err(e) => OrionBuildResult {
+                build_id: "".to_string(),
+                status: "error".to_string(),
+                message: format!("Unable to queue build: {e}"),
+            },
+        }
     };
     (
         StatusCode::OK,
@@ -1062,6 +1331,7 @@ pub async fn get_orion_clients_info(
                     orion_version: entry.value().orion_version.clone(),
                     start_time: entry.value().start_time,
                     last_heartbeat: entry.value().last_heartbeat,
+                    capabilities: entry.value().capabilities.clone(),
                 });
             }
         }
@@ -1084,9 +1354,48 @@ pub async fn get_orion_client_status_by_id(
 
 #[cfg(test)]
 mod tests {
-    use api_model::buck2::{status::Status, types::ProjectRelativePath};
+    use api_model::buck2::{
+        status::Status,
+        types::{ProjectRelativePath, TestResultResponse, TestStatus, TestSummary},
+    };
+
+    use super::{normalize_repo_root_changes, summarize_test_results};
+
+    fn test_result(name: &str, status: TestStatus) -> TestResultResponse {
+        TestResultResponse {
+            id: name.to_string(),
+            build_id: "build".to_string(),
+            target: "root//app:app-unittest".to_string(),
+            name: name.to_string(),
+            status,
+            duration_ms: None,
+            message: None,
+        }
+    }
 
-    use super::normalize_repo_root_changes;
+    #[test]
+    fn test_summarize_test_results_counts_and_puts_failures_first() {
+        let mut results = vec![
+            test_result("a", TestStatus::Pass),
+            test_result("b", TestStatus::Fail),
+            test_result("c", TestStatus::Skip),
+            test_result("d", TestStatus::Timeout),
+        ];
+
+        let summary = summarize_test_results(&mut results);
+
+        assert_eq!(
+            summary,
+            TestSummary {
+                total: 4,
+                passed: 1,
+                failed: 2,
+                skipped: 1,
+            }
+        );
+        let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "d", "a", "c"]);
+    }
 
     #[test]
     fn test_normalize_repo_root_changes_trims_leading_slashes_and_deduplicates() {
@@ -0,0 +1,504 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use api_model::buck2::types::{
+    FlakyTarget, FlakyTargetQuery, QuarantineQuery, QuarantineSource, QuarantineTargetRequest,
+    TargetQuarantine,
+};
+use axum::{Json, http::StatusCode};
+use common::config::FlakyQuarantineConfig;
+use sea_orm::{ConnectionTrait, DbErr, prelude::DateTimeWithTimeZone};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    model::{dto::MessageResponse, target_state::TargetState},
+    repository::{
+        target_build_status_repo::{PassedTargetSpan, TargetBuildStatusRepo, TargetBuildTotals},
+        target_quarantines_repo::TargetQuarantinesRepo,
+        target_state_histories_repo::{TargetStateHistoriesRepo, TaskStateSummary},
+    },
+    scheduler::path_has_prefix,
+    service::api_v2_service::{
+        MessageErrorResponse, message_error, message_response, parse_uuid_or_message_error,
+    },
+};
+
+/// Build history analyzed by `GET /v2/flaky-targets`.
+const FLAKY_HISTORY_DAYS: i64 = 30;
+
+fn now() -> DateTimeWithTimeZone {
+    chrono::Utc::now().fixed_offset()
+}
+
+/// Whether a quarantine of `quarantine_path` applies to builds of `path`: the path
+/// is `quarantine_path` or below it.
+pub(crate) fn quarantine_applies(quarantine_path: &str, path: &str) -> bool {
+    path_has_prefix(path, quarantine_path)
+}
+
+/// A task (one commit) whose build failed and later passed on a `build_retry`.
+#[derive(Debug, Clone)]
+pub(crate) struct FlippedTask {
+    task_id: Uuid,
+    repo: String,
+    /// End of the first failed build.
+    failed_at: DateTimeWithTimeZone,
+    /// Start of the last passing build.
+    passed_from: DateTimeWithTimeZone,
+    passed_at: Option<DateTimeWithTimeZone>,
+    /// Failed builds of the task, each turned into a pass by a retry.
+    failed_builds: usize,
+}
+
+/// Tasks of `states` that passed in a build started after one of their builds failed.
+pub(crate) fn flipped_tasks(states: &[TaskStateSummary]) -> Vec<FlippedTask> {
+    let mut by_task: BTreeMap<Uuid, (Option<&TaskStateSummary>, Option<&TaskStateSummary>)> =
+        BTreeMap::new();
+    for state in states {
+        let entry = by_task.entry(state.task_id).or_default();
+        if state.target_state == TargetState::Failed.to_string() {
+            entry.0 = Some(state);
+        } else if state.target_state == TargetState::Completed.to_string() {
+            entry.1 = Some(state);
+        }
+    }
+    by_task
+        .into_values()
+        .filter_map(|(failed, passed)| {
+            let (failed, passed) = (failed?, passed?);
+            let failed_at = failed.first_end_at?;
+            let passed_from = passed.last_start_at.filter(|start| *start >= failed_at)?;
+            Some(FlippedTask {
+                task_id: failed.task_id,
+                repo: failed.repo.clone(),
+                failed_at,
+                passed_from,
+                passed_at: passed.last_end_at,
+                failed_builds: failed.builds as usize,
+            })
+        })
+        .collect()
+}
+
+/// Flakiness of the targets that flipped on a commit, most flaky first.
+///
+/// Target statuses are kept per task, so a target is taken to have flipped when it was
+/// reported by the failed build and rebuilt, passing, by the retry that passed.
+pub(crate) fn analyze_history(
+    flipped: &[FlippedTask],
+    passed: &[PassedTargetSpan],
+    totals: &[TargetBuildTotals],
+) -> Vec<FlakyTarget> {
+    let flipped: HashMap<Uuid, &FlippedTask> =
+        flipped.iter().map(|task| (task.task_id, task)).collect();
+    // (repo, target) -> (flaky commits, passed on retry, last flip)
+    let mut flips: BTreeMap<(&str, String), (usize, usize, Option<DateTimeWithTimeZone>)> =
+        BTreeMap::new();
+    for span in passed {
+        let Some(task) = flipped.get(&span.task_id) else {
+            continue;
+        };
+        if span.first_reported_at > task.failed_at || span.last_reported_at < task.passed_from {
+            continue;
+        }
+        let entry = flips
+            .entry((
+                task.repo.as_str(),
+                format!("{}:{}", span.target_package, span.target_name),
+            ))
+            .or_default();
+        entry.0 += 1;
+        entry.1 += task.failed_builds;
+        entry.2 = entry.2.max(task.passed_at);
+    }
+
+    let totals: HashMap<(&str, String), &TargetBuildTotals> = totals
+        .iter()
+        .map(|t| {
+            (
+                (
+                    t.repo.as_str(),
+                    format!("{}:{}", t.target_package, t.target_name),
+                ),
+                t,
+            )
+        })
+        .collect();
+    let mut flaky: Vec<FlakyTarget> = flips
+        .into_iter()
+        .map(
+            |((repo, target), (flaky_commits, passed_on_retry, last_flip_at))| {
+                let (builds, failures) = totals
+                    .get(&(repo, target.clone()))
+                    .map_or((0, 0), |t| (t.builds as usize, t.failures as usize));
+                let builds = builds.max(flaky_commits);
+                FlakyTarget {
+                    repo: repo.to_string(),
+                    target,
+                    builds,
+                    failures,
+                    flaky_commits,
+                    passed_on_retry,
+                    flakiness: flaky_commits as f64 / builds as f64,
+                    last_flip_at: last_flip_at.map(|at| at.to_rfc3339()),
+                    quarantined: false,
+                }
+            },
+        )
+        .collect();
+    flaky.sort_by(|a, b| {
+        b.flakiness
+            .total_cmp(&a.flakiness)
+            .then(b.flaky_commits.cmp(&a.flaky_commits))
+            .then_with(|| (&a.repo, &a.target).cmp(&(&b.repo, &b.target)))
+    });
+    flaky
+}
+
+/// Flaky targets of repos at or below `path` built since `since`.
+async fn load_flaky_targets(
+    conn: &impl ConnectionTrait,
+    path: Option<&str>,
+    since: DateTimeWithTimeZone,
+) -> Result<Vec<FlakyTarget>, DbErr> {
+    let states = TargetStateHistoriesRepo::retried_task_states(conn, path, since).await?;
+    let flipped = flipped_tasks(&states);
+    let passed = TargetBuildStatusRepo::passed_target_spans(
+        conn,
+        flipped.iter().map(|task| task.task_id).collect(),
+    )
+    .await?;
+    let packages: BTreeSet<String> = passed
+        .iter()
+        .map(|span| span.target_package.clone())
+        .collect();
+    let totals =
+        TargetBuildStatusRepo::target_totals(conn, path, since, packages.into_iter().collect())
+            .await?;
+    Ok(analyze_history(&flipped, &passed, &totals))
+}
+
+fn quarantine_response(model: callisto::target_quarantines::Model) -> TargetQuarantine {
+    TargetQuarantine {
+        id: model.id.to_string(),
+        path: model.path,
+        target: model.target,
+        reason: model.reason,
+        // Rows are only written from `QuarantineSource::as_str`.
+        source: QuarantineSource::parse(&model.source).unwrap_or(QuarantineSource::Manual),
+        created_at: model.created_at.to_rfc3339(),
+    }
+}
+
+fn is_quarantined(
+    quarantines: &[callisto::target_quarantines::Model],
+    repo: &str,
+    target: &str,
+) -> bool {
+    quarantines
+        .iter()
+        .any(|q| q.target == target && quarantine_applies(&q.path, repo))
+}
+
+pub async fn flaky_targets(
+    state: &AppState,
+    query: &FlakyTargetQuery,
+) -> Result<Json<Vec<FlakyTarget>>, MessageErrorResponse> {
+    let since = now() - chrono::Duration::days(FLAKY_HISTORY_DAYS);
+    let mut flaky = load_flaky_targets(&state.conn, query.path.as_deref(), since)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to analyze target build history: {}", e);
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?;
+    let quarantines = TargetQuarantinesRepo::list_all(&state.conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch quarantined targets: {}", e);
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?;
+
+    for target in &mut flaky {
+        target.quarantined = is_quarantined(&quarantines, &target.repo, &target.target);
+    }
+    Ok(Json(flaky))
+}
+
+pub async fn list_quarantines(
+    state: &AppState,
+    query: &QuarantineQuery,
+) -> Result<Json<Vec<TargetQuarantine>>, MessageErrorResponse> {
+    let quarantines = TargetQuarantinesRepo::list_all(&state.conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch quarantined targets: {}", e);
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?;
+    Ok(Json(
+        quarantines
+            .into_iter()
+            .filter(|q| {
+                query
+                    .path
+                    .as_deref()
+                    .is_none_or(|path| quarantine_applies(&q.path, path))
+            })
+            .map(quarantine_response)
+            .collect(),
+    ))
+}
+
+/// Rejects a quarantine change unless `authorization` carries the configured admin token.
+pub(crate) fn authorize_admin(
+    admin_token: Option<&str>,
+    authorization: Option<&str>,
+) -> Result<(), MessageErrorResponse> {
+    let Some(expected) = admin_token.filter(|token| !token.is_empty()) else {
+        return Err(message_error(
+            StatusCode::FORBIDDEN,
+            "Quarantine management is disabled: flaky_quarantine.admin_token is not set",
+        ));
+    };
+    let provided = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+    if !bool::from(provided.as_bytes().ct_eq(expected.as_bytes())) {
+        return Err(message_error(
+            StatusCode::UNAUTHORIZED,
+            "A valid admin bearer token is required",
+        ));
+    }
+    Ok(())
+}
+
+pub async fn quarantine_target(
+    state: &AppState,
+    authorization: Option<&str>,
+    req: QuarantineTargetRequest,
+) -> Result<Json<TargetQuarantine>, MessageErrorResponse> {
+    authorize_admin(state.flaky_quarantine.admin_token.as_deref(), authorization)?;
+    let path = req.path.trim();
+    let target = req.target.trim();
+    if !path.starts_with('/') || target.is_empty() {
+        return Err(message_error(
+            StatusCode::BAD_REQUEST,
+            "path must be absolute and target must not be empty",
+        ));
+    }
+    let model = TargetQuarantinesRepo::insert(
+        &state.conn,
+        path,
+        target,
+        req.reason,
+        QuarantineSource::Manual.as_str(),
+        now(),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to quarantine {} under {}: {}", target, path, e);
+        message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+    })?;
+    Ok(Json(quarantine_response(model)))
+}
+
+pub async fn release_quarantine(
+    state: &AppState,
+    authorization: Option<&str>,
+    id: &str,
+) -> Result<Json<MessageResponse>, MessageErrorResponse> {
+    authorize_admin(state.flaky_quarantine.admin_token.as_deref(), authorization)?;
+    let id = parse_uuid_or_message_error(id, "Invalid quarantine ID")?;
+    let deleted = TargetQuarantinesRepo::delete(&state.conn, id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to release quarantine {}: {}", id, e);
+            message_error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        })?;
+    if !deleted {
+        return Err(message_error(StatusCode::NOT_FOUND, "Quarantine not found"));
+    }
+    Ok(message_response("Quarantine released"))
+}
+
+/// Quarantine the targets of `repo` whose recent history crosses the configured
+/// flakiness threshold. Returns the newly flagged targets.
+pub(crate) async fn auto_quarantine(
+    conn: &impl ConnectionTrait,
+    config: &FlakyQuarantineConfig,
+    repo: &str,
+) -> Result<Vec<String>, DbErr> {
+    let since = now() - chrono::Duration::days(config.history_days);
+    let flaky = load_flaky_targets(conn, Some(repo), since).await?;
+    let quarantines = TargetQuarantinesRepo::list_all(conn).await?;
+
+    let mut flagged = Vec::new();
+    for target in flaky {
+        if target.repo != repo
+            || target.flaky_commits < config.min_flaky_commits
+            || target.flakiness < config.threshold
+            || is_quarantined(&quarantines, repo, &target.target)
+        {
+            continue;
+        }
+        let reason = format!(
+            "flaky on {} of the last {} days' commits ({:.0}%), passed on retry {} times",
+            target.flaky_commits,
+            config.history_days,
+            target.flakiness * 100.0,
+            target.passed_on_retry,
+        );
+        TargetQuarantinesRepo::insert(
+            conn,
+            repo,
+            &target.target,
+            Some(reason),
+            QuarantineSource::Auto.as_str(),
+            now(),
+        )
+        .await?;
+        flagged.push(target.target);
+    }
+    Ok(flagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::*;
+
+    fn at(minute: i64) -> DateTimeWithTimeZone {
+        (Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap() + Duration::minutes(minute))
+            .fixed_offset()
+    }
+
+    fn state(
+        task_id: Uuid,
+        state: TargetState,
+        builds: i64,
+        first_end: i64,
+        last_start: i64,
+    ) -> TaskStateSummary {
+        TaskStateSummary {
+            task_id,
+            repo: "/project/mega".to_string(),
+            target_state: state.to_string(),
+            builds,
+            first_end_at: Some(at(first_end)),
+            last_start_at: Some(at(last_start)),
+            last_end_at: Some(at(last_start + 4)),
+        }
+    }
+
+    fn passed(task_id: Uuid, name: &str, first: i64, last: i64) -> PassedTargetSpan {
+        PassedTargetSpan {
+            task_id,
+            target_package: "root//app".to_string(),
+            target_name: name.to_string(),
+            first_reported_at: at(first),
+            last_reported_at: at(last),
+        }
+    }
+
+    fn totals(name: &str, builds: i64, failures: i64) -> TargetBuildTotals {
+        TargetBuildTotals {
+            repo: "/project/mega".to_string(),
+            target_package: "root//app".to_string(),
+            target_name: name.to_string(),
+            builds,
+            failures,
+        }
+    }
+
+    #[test]
+    fn test_flipped_tasks_need_a_pass_after_a_failure() {
+        let (flipped, broken, passed_first) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        let states = vec![
+            // Failed twice, then passed on the second retry.
+            state(flipped, TargetState::Failed, 2, 5, 6),
+            state(flipped, TargetState::Completed, 1, 15, 11),
+            // Never passed.
+            state(broken, TargetState::Failed, 2, 25, 26),
+            // Passed before it failed.
+            state(passed_first, TargetState::Completed, 1, 35, 30),
+            state(passed_first, TargetState::Failed, 1, 45, 40),
+        ];
+
+        let tasks = flipped_tasks(&states);
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].task_id, flipped);
+        assert_eq!(tasks[0].failed_at, at(5));
+        assert_eq!(tasks[0].passed_from, at(11));
+        assert_eq!(tasks[0].failed_builds, 2);
+    }
+
+    #[test]
+    fn test_analyze_history_counts_targets_rebuilt_by_the_passing_retry() {
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+        let flipped = flipped_tasks(&[
+            state(first, TargetState::Failed, 1, 5, 0),
+            state(first, TargetState::Completed, 1, 15, 10),
+            state(second, TargetState::Failed, 1, 25, 20),
+            state(second, TargetState::Completed, 1, 35, 30),
+        ]);
+        let spans = vec![
+            // Reported by the failed build and rebuilt by the retry.
+            passed(first, "app", 1, 12),
+            passed(second, "app", 21, 31),
+            // Passed in the failed build and not rebuilt.
+            passed(first, "lib", 1, 2),
+            // Only built by the retry.
+            passed(second, "lib", 31, 32),
+        ];
+        let totals = vec![totals("app", 4, 1), totals("lib", 4, 0)];
+
+        let flaky = analyze_history(&flipped, &spans, &totals);
+
+        assert_eq!(flaky.len(), 1);
+        let app = &flaky[0];
+        assert_eq!(app.target, "root//app:app");
+        assert_eq!((app.builds, app.failures), (4, 1));
+        assert_eq!((app.flaky_commits, app.passed_on_retry), (2, 2));
+        assert_eq!(app.flakiness, 0.5);
+        assert_eq!(
+            app.last_flip_at.as_deref(),
+            Some("2026-10-01T00:34:00+00:00")
+        );
+    }
+
+    #[test]
+    fn test_quarantine_applies_to_its_path_and_below() {
+        assert!(quarantine_applies("/project/mega", "/project/mega"));
+        assert!(quarantine_applies("/project/mega", "/project/mega/orion"));
+        assert!(!quarantine_applies("/project/mega/orion", "/project/mega"));
+        assert!(quarantine_applies("/", "/project/mega"));
+        assert!(!quarantine_applies("/project/mega", "/project/megadata"));
+        assert!(!quarantine_applies("/project/mega", "/project/other"));
+    }
+
+    #[test]
+    fn test_quarantine_changes_require_the_admin_token() {
+        let status = |admin_token, authorization| {
+            authorize_admin(admin_token, authorization)
+                .err()
+                .map(|(status, _)| status)
+        };
+        assert_eq!(
+            status(None, Some("Bearer s3cret")),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            status(Some(""), Some("Bearer ")),
+            Some(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(status(Some("s3cret"), None), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(
+            status(Some("s3cret"), Some("Bearer wrong")),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(status(Some("s3cret"), Some("Bearer s3cret")), None);
+    }
+}
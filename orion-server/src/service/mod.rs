@@ -1,3 +1,4 @@
 pub mod api_v2_service;
+pub mod flaky_target_service;
 pub mod target_status_cache_service;
 pub mod ws_service;
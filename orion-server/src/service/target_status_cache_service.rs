@@ -33,15 +33,7 @@ impl TargetStatusCache {
         }
     }
 
-    pub async fn insert_event(&self, event: WSTargetBuildStatusEvent) {
-        let task_id = match Uuid::parse_str(&event.context.task_id) {
-            Ok(id) => id,
-            Err(_) => {
-                tracing::error!("Invalid task_id: {}", event.context.task_id);
-                return;
-            }
-        };
-
+    pub async fn insert_event(&self, task_id: Uuid, event: WSTargetBuildStatusEvent) {
         let key = ActionKey {
             package: event.target.configured_target_package.clone(),
             name: event.target.configured_target_name.clone(),
@@ -18,9 +18,12 @@ use crate::{
     model::target_state::TargetState,
     repository::{
         build_events_repo::BuildEventsRepo, build_targets_repo::BuildTargetsRepo,
+        build_test_results_repo::BuildTestResultsRepo,
+        target_build_status_repo::TargetBuildStatusRepo,
         target_state_histories_repo::TargetStateHistoriesRepo,
     },
     scheduler::{WorkerInfo, WorkerStatus},
+    service::flaky_target_service,
 };
 
 pub async fn ws_handler(
@@ -77,7 +80,7 @@ async fn handle_socket(socket: WebSocket, who: SocketAddr, state: AppState) {
 
     if let Some(id) = &worker_id {
         tracing::info!("Cleaning up for worker: {id} from {who}.");
-        state.scheduler.workers.remove(id);
+        state.scheduler.remove_worker(id);
     } else {
         tracing::info!("Cleaning up unregistered connection from {who}.");
     }
@@ -105,10 +108,13 @@ async fn process_message(
                     id,
                     hostname,
                     orion_version,
+                    capabilities,
                 } = ws_msg
                 {
-                    tracing::info!("Worker from {who} registered as: {id}");
-                    state.scheduler.workers.insert(
+                    tracing::info!(
+                        "Worker from {who} registered as: {id} with capabilities {capabilities:?}"
+                    );
+                    state.scheduler.register_worker(
                         id.clone(),
                         WorkerInfo {
                             sender: tx.clone(),
@@ -117,6 +123,7 @@ async fn process_message(
                             start_time: chrono::Utc::now(),
                             hostname,
                             orion_version,
+                            capabilities,
                         },
                     );
                     *worker_id = Some(id);
@@ -204,16 +211,13 @@ async fn process_message(
                     exit_code,
                     message,
                 } => {
-                    let (repo, task_id, target_id) =
-                        if let Some(build_info) = state.scheduler.active_builds.get(&build_id) {
-                            (
-                                build_info.event_payload.repo.clone(),
-                                build_info.event_payload.task_id,
-                                build_info.target_id,
-                            )
-                        } else {
-                            return ControlFlow::Continue(());
-                        };
+                    let Some((_, build_info)) = state.scheduler.active_builds.remove(&build_id)
+                    else {
+                        return ControlFlow::Continue(());
+                    };
+                    let repo = build_info.event_payload.repo.clone();
+                    let task_id = build_info.event_payload.task_id;
+                    let target_id = build_info.target_id;
 
                     state.log_service.publish(LogEvent {
                         task_id: task_id.to_string(),
@@ -222,7 +226,6 @@ async fn process_message(
                         line: String::new(),
                         is_end: true,
                     });
-                    state.scheduler.active_builds.remove(&build_id);
 
                     let _ = BuildEventsRepo::update_build_complete_result(
                         &build_id,
@@ -250,9 +253,8 @@ async fn process_message(
                         (_, None) => TargetState::Interrupted,
                         _ => TargetState::Failed,
                     };
-                    let now = chrono::Utc::now().with_timezone(
-                        &chrono::FixedOffset::east_opt(0).unwrap_or_else(|| unreachable!()),
-                    );
+                    let now = chrono::Utc::now()
+                        .with_timezone(&chrono::FixedOffset::east_opt(0).unwrap());
                     let build_event_uuid = build_id.parse::<Uuid>().unwrap_or_else(|_| Uuid::nil());
 
                     let _ =
@@ -267,6 +269,14 @@ async fn process_message(
                     )
                     .await;
 
+                    // A retry that passed after a failure is when a target can start
+                    // flipping on the same commit.
+                    if target_state == TargetState::Completed
+                        && build_info.event_payload.retry_count > 0
+                    {
+                        quarantine_flaky_targets(state, repo.clone()).await;
+                    }
+
                     if let Some(mut worker) = state.scheduler.workers.get_mut(&current_worker_id) {
                         worker.status = if success {
                             WorkerStatus::Idle
@@ -276,6 +286,39 @@ async fn process_message(
                     }
                     state.scheduler.notify_task_available();
                 }
+                WSMessage::TaskBuildCancelled { build_id, message } => {
+                    let Some((_, build_info)) = state.scheduler.active_builds.remove(&build_id)
+                    else {
+                        return ControlFlow::Continue(());
+                    };
+                    tracing::info!(
+                        "Build {build_id} cancelled on worker {current_worker_id}: {message}"
+                    );
+                    let repo = build_info.event_payload.repo;
+                    let task_id = build_info.event_payload.task_id;
+
+                    state
+                        .scheduler
+                        .finalize_cancelled_build(
+                            build_info.event_payload.build_event_id,
+                            task_id,
+                            &repo,
+                            &message,
+                        )
+                        .await;
+                    state.log_service.publish(LogEvent {
+                        task_id: task_id.to_string(),
+                        repo_name: LogService::last_segment(&repo).to_string(),
+                        build_id,
+                        line: String::new(),
+                        is_end: true,
+                    });
+
+                    if let Some(mut worker) = state.scheduler.workers.get_mut(&current_worker_id) {
+                        worker.status = WorkerStatus::Idle;
+                    }
+                    state.scheduler.notify_task_available();
+                }
                 WSMessage::TaskPhaseUpdate { build_id, phase } => {
                     if let Some(mut worker) = state.scheduler.workers.get_mut(&current_worker_id)
                         && let WorkerStatus::Busy { build_id: id, .. } = &worker.status
@@ -287,9 +330,65 @@ async fn process_message(
                         };
                     }
                 }
+                WSMessage::TestResultBatch { build_id, results } => {
+                    let ids = state
+                        .scheduler
+                        .active_builds
+                        .get(&build_id)
+                        .map(|build_info| {
+                            (
+                                build_info.event_payload.build_event_id,
+                                build_info.event_payload.task_id,
+                            )
+                        });
+                    let Some((build_event_id, task_id)) = ids else {
+                        tracing::warn!(
+                            "Dropping {} test results for unknown build {build_id}",
+                            results.len()
+                        );
+                        return ControlFlow::Continue(());
+                    };
+                    let now = chrono::Utc::now()
+                        .with_timezone(&chrono::FixedOffset::east_opt(0).unwrap());
+                    if let Err(e) = BuildTestResultsRepo::insert_batch(
+                        &state.conn,
+                        build_event_id,
+                        task_id,
+                        results,
+                        now,
+                    )
+                    .await
+                    {
+                        tracing::error!(
+                            "failed to persist test results, build_id={}, error={:?}",
+                            build_id,
+                            e
+                        );
+                    }
+                }
                 WSMessage::TargetBuildStatusBatch { events } => {
+                    let Some(id) = events.first().map(|event| event.context.task_id.clone()) else {
+                        return ControlFlow::Continue(());
+                    };
+                    // Workers report the build id in the context; statuses are stored
+                    // per task.
+                    let running_task_id = state
+                        .scheduler
+                        .active_builds
+                        .get(&id)
+                        .map(|build_info| build_info.event_payload.task_id);
+                    let task_id = match running_task_id {
+                        Some(task_id) => task_id,
+                        None => match finished_build_task_id(state, &id).await {
+                            Some(task_id) => task_id,
+                            None => return ControlFlow::Continue(()),
+                        },
+                    };
                     for update in events {
-                        state.target_status_cache.insert_event(update).await;
+                        state
+                            .target_status_cache
+                            .insert_event(task_id, update)
+                            .await;
                     }
                 }
                 _ => {}
@@ -307,3 +406,49 @@ async fn process_message(
     }
     ControlFlow::Continue(())
 }
+
+/// Task of a status batch whose build is no longer active. Ids that are not a known
+/// build are taken as the task id itself.
+async fn finished_build_task_id(state: &AppState, id: &str) -> Option<Uuid> {
+    let Ok(id) = Uuid::parse_str(id) else {
+        tracing::error!("Invalid build id in target status batch: {id}");
+        return None;
+    };
+    match BuildEventsRepo::find_by_id(&state.conn, id).await {
+        Ok(Some(build)) => Some(build.task_id),
+        Ok(None) => Some(id),
+        Err(e) => {
+            tracing::error!("Failed to resolve task of build {id}: {e}");
+            None
+        }
+    }
+}
+
+/// Quarantine the targets of `repo` that became too flaky, when enabled.
+///
+/// Buffered target statuses are stored first so the analysis sees the build that just
+/// finished.
+async fn quarantine_flaky_targets(state: &AppState, repo: String) {
+    if !state.flaky_quarantine.enabled {
+        return;
+    }
+    let models = state.target_status_cache.flush_all().await;
+    if !models.is_empty()
+        && let Err(e) = TargetBuildStatusRepo::upsert_batch(&state.conn, models).await
+    {
+        tracing::error!("Failed to flush target statuses: {:?}", e);
+    }
+    let conn = state.conn.clone();
+    let config = state.flaky_quarantine.clone();
+    tokio::spawn(async move {
+        match flaky_target_service::auto_quarantine(&conn, &config, &repo).await {
+            Ok(flagged) if !flagged.is_empty() => {
+                tracing::warn!("Quarantined flaky targets of {}: {:?}", repo, flagged);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("Failed to quarantine flaky targets of {}: {:?}", repo, e);
+            }
+        }
+    });
+}
@@ -45,4 +45,5 @@ pub mod target_graph;
 pub mod target_map;
 pub mod target_status;
 pub mod targets;
+pub mod test_results;
 pub mod types;
@@ -0,0 +1,205 @@
+//! Parsing of per-test results from the `buck2 test` event log.
+//!
+//! `buck2 test --event-log <file>` writes one JSON event per line. Every
+//! finished test (and every test listing) is an instant `TestResult` event:
+//! ```text
+//! {"Event":{..,"data":{"Instant":{"data":{"TestResult":{
+//!     "name":"tests::parses_config","status":1,"duration":{"secs":0,"nanos":200000000},
+//!     "msg":null,"target_label":{"label":{"package":"root//app","name":"app-unittest"},..}}}}}}}
+//! ```
+//! All other events (spans, console snapshots, the invocation record) are
+//! ignored, so the parsing does not depend on the console format.
+
+use api_model::buck2::{types::TestStatus, ws::WSTestResult};
+use serde::Deserialize;
+
+/// Event log written by `buck2 test`, next to the build's `EVENT_LOG_FILE`.
+pub const TEST_EVENT_LOG_FILE: &str = "test_event.jsonl";
+
+#[derive(Debug, Deserialize)]
+struct TestResultEvent {
+    name: String,
+    status: StatusValue,
+    #[serde(default)]
+    duration: Option<EventDuration>,
+    #[serde(default)]
+    msg: Option<TestMessage>,
+    target_label: Option<ConfiguredLabel>,
+}
+
+/// `buck2_data::TestStatus`, serialized either by number or by name.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StatusValue {
+    Code(i64),
+    Name(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct EventDuration {
+    #[serde(default, alias = "seconds")]
+    secs: u64,
+    #[serde(default)]
+    nanos: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestMessage {
+    msg: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfiguredLabel {
+    label: Label,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    package: String,
+    name: String,
+}
+
+/// What a `TestResult` event reports.
+enum ResultKind {
+    Test(TestStatus),
+    ListingFailed,
+    Ignored,
+}
+
+impl StatusValue {
+    fn kind(&self) -> ResultKind {
+        let name = match self {
+            StatusValue::Code(code) => match code {
+                1 => "PASS",
+                2 => "FAIL",
+                3 => "SKIP",
+                4 => "OMITTED",
+                5 => "FATAL",
+                6 => "TIMEOUT",
+                10 => "LISTING_FAILED",
+                _ => "",
+            },
+            StatusValue::Name(name) => name.as_str(),
+        };
+        match name.to_ascii_uppercase().as_str() {
+            "PASS" => ResultKind::Test(TestStatus::Pass),
+            "FAIL" => ResultKind::Test(TestStatus::Fail),
+            "SKIP" => ResultKind::Test(TestStatus::Skip),
+            "OMITTED" => ResultKind::Test(TestStatus::Omitted),
+            "FATAL" => ResultKind::Test(TestStatus::Fatal),
+            "TIMEOUT" => ResultKind::Test(TestStatus::Timeout),
+            "LISTING_FAILED" => ResultKind::ListingFailed,
+            // Not set, unknown, rerun markers and successful listings are not results.
+            _ => ResultKind::Ignored,
+        }
+    }
+}
+
+/// Parse one line of the `buck2 test` event log into a test result.
+///
+/// Returns `None` for events that are not per-test results. Listing
+/// failures (buck2 failing to enumerate the tests of a target) are reported
+/// against the target with an empty test name.
+pub fn parse_test_event(line: &str) -> Option<WSTestResult> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let event = value.pointer("/Event/data/Instant/data/TestResult")?;
+    let event: TestResultEvent = serde_json::from_value(event.clone()).ok()?;
+    let label = event.target_label?.label;
+
+    let (status, listing) = match event.status.kind() {
+        ResultKind::Test(status) => (status, false),
+        ResultKind::ListingFailed => (TestStatus::Fail, true),
+        ResultKind::Ignored => return None,
+    };
+    let message = if listing {
+        Some("failed to list tests of target".to_string())
+    } else {
+        event
+            .msg
+            .and_then(|m| m.msg)
+            .filter(|msg| !msg.trim().is_empty())
+    };
+
+    Some(WSTestResult {
+        target: format!("{}:{}", label.package, label.name),
+        name: if listing { String::new() } else { event.name },
+        status,
+        duration_ms: event
+            .duration
+            .map(|d| d.secs * 1000 + (d.nanos + 500_000) / 1_000_000),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_result_event(result: &str) -> String {
+        format!(
+            r#"{{"Event":{{"timestamp":[1700000000,0],"trace_id":"t","span_id":null,"parent_id":null,"data":{{"Instant":{{"data":{{"TestResult":{result}}}}}}}}}}}"#
+        )
+    }
+
+    #[test]
+    fn test_parse_pass_event_with_duration() {
+        let line = test_result_event(
+            r#"{"name":"tests::parses_config","status":1,"duration":{"secs":0,"nanos":200000000},"msg":null,"details":"","target_label":{"label":{"package":"root//app","name":"app-unittest"},"configuration":{"full_name":"prelude//platforms:default"}}}"#,
+        );
+        let result = parse_test_event(&line).unwrap();
+        assert_eq!(result.target, "root//app:app-unittest");
+        assert_eq!(result.name, "tests::parses_config");
+        assert_eq!(result.status, TestStatus::Pass);
+        assert_eq!(result.duration_ms, Some(200));
+        assert_eq!(result.message, None);
+    }
+
+    #[test]
+    fn test_parse_failure_and_skip_events() {
+        let fail = parse_test_event(&test_result_event(
+            r#"{"name":"tests::rejects","status":"FAIL","duration":{"secs":0,"nanos":15000000},"msg":{"msg":"assertion failed"},"target_label":{"label":{"package":"root//lib","name":"lib-unittest"}}}"#,
+        ))
+        .unwrap();
+        assert_eq!(fail.status, TestStatus::Fail);
+        assert_eq!(fail.duration_ms, Some(15));
+        assert_eq!(fail.message.as_deref(), Some("assertion failed"));
+
+        let skip = parse_test_event(&test_result_event(
+            r#"{"name":"tests::slow","status":3,"target_label":{"label":{"package":"root//lib","name":"lib-unittest"}}}"#,
+        ))
+        .unwrap();
+        assert_eq!(skip.status, TestStatus::Skip);
+        assert_eq!(skip.name, "tests::slow");
+        assert_eq!(skip.duration_ms, None);
+    }
+
+    #[test]
+    fn test_parse_listing_failure_reports_target() {
+        let result = parse_test_event(&test_result_event(
+            r#"{"name":"main","status":10,"target_label":{"label":{"package":"root//lib","name":"lib-unittest"}}}"#,
+        ))
+        .unwrap();
+        assert_eq!(result.target, "root//lib:lib-unittest");
+        assert_eq!(result.name, "");
+        assert_eq!(result.status, TestStatus::Fail);
+        assert!(result.message.is_some());
+
+        assert!(
+            parse_test_event(&test_result_event(
+                r#"{"name":"main","status":9,"target_label":{"label":{"package":"root//lib","name":"lib-unittest"}}}"#,
+            ))
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_ignores_non_result_events() {
+        assert!(parse_test_event(r#"{"command_line_args":["buck2","test"]}"#).is_none());
+        assert!(parse_test_event(
+            r#"{"Event":{"trace_id":"t","data":{"SpanStart":{"data":{"ActionExecution":{}}}}}}"#
+        )
+        .is_none());
+        assert!(parse_test_event("✓ Pass: root//app:app-unittest - tests::x (0.2s)").is_none());
+        assert!(parse_test_event("").is_none());
+    }
+}
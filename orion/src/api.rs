@@ -6,7 +6,7 @@ use serde::Serialize;
 use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
-use crate::buck_controller;
+use crate::buck_controller::{self, BuildCancelled, RunningBuildGuard};
 
 // /// Parameters required to execute a buck build operation.
 // #[derive(Debug)]
@@ -41,6 +41,7 @@ pub struct BuildResult {
 /// # Arguments
 /// * `id` - Unique identifier for tracking the build task
 /// * `req` - Build parameters including repository, target, and arguments
+/// * `running` - Registration of the build, made when the task arrived so it can be cancelled
 /// * `sender` - Channel for sending WebSocket messages during build execution
 ///
 /// # Returns
@@ -50,6 +51,7 @@ pub async fn buck_build(
     cl_link: String,
     repo: String,
     changes: Vec<Status<ProjectRelativePath>>,
+    running: RunningBuildGuard,
     sender: UnboundedSender<WSMessage>,
 ) -> BuildResult {
     let id_str = id.to_string();
@@ -64,6 +66,7 @@ pub async fn buck_build(
             cl_link,
             sender.clone(),
             changes,
+            running,
         ))
         .catch_unwind()
         .await;
@@ -90,6 +93,23 @@ pub async fn buck_build(
                     message,
                 }
             }
+            Ok(Err(e)) if e.is::<BuildCancelled>() => {
+                let message = e.to_string();
+                tracing::info!("[Task {}] {}", id_str, message);
+                if sender
+                    .send(WSMessage::TaskBuildCancelled {
+                        build_id: id_str.clone(),
+                        message,
+                    })
+                    .is_err()
+                {
+                    tracing::error!(
+                        "[Task {}] Failed to send BuildCancelled message. Connection likely lost.",
+                        id_str
+                    );
+                }
+                return;
+            }
             Ok(Err(e)) => {
                 let error_msg = format!("Build execution failed: {e}");
                 tracing::error!("[Task {}] {}", id_str, error_msg);
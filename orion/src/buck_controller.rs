@@ -1,16 +1,18 @@
 use std::{
     collections::{HashMap, HashSet},
     error::Error,
+    fmt,
     io::BufReader,
     path::{Path, PathBuf},
     process::{ExitStatus, Stdio},
+    sync::Mutex,
 };
 
 use anyhow::anyhow;
 use api_model::buck2::{
     status::Status,
     types::{ProjectRelativePath, TaskPhase},
-    ws::{WSBuildContext, WSMessage, WSTargetBuildStatusEvent},
+    ws::{WSBuildContext, WSMessage, WSTargetBuildStatusEvent, WSTestResult},
 };
 use common::config::BuildConfig;
 use once_cell::sync::Lazy;
@@ -25,6 +27,7 @@ use td_util_buck::{
     run::{Buck2, targets_arguments},
     target_status::{BuildState, EVENT_LOG_FILE, Event, LogicalActionId, TargetBuildStatusUpdate},
     targets::{BuckTarget, Targets},
+    test_results::{TEST_EVENT_LOG_FILE, parse_test_event},
     types::{RuleType, TargetLabel},
 };
 use tokio::{
@@ -50,6 +53,90 @@ static PROJECT_ROOT: Lazy<String> =
 const DEFAULT_PREHEAT_SHALLOW_DEPTH: usize = 3;
 static BUILD_CONFIG: Lazy<Option<BuildConfig>> = Lazy::new(load_build_config);
 
+/// Builds running on this worker, keyed by build id, so the server can cancel them.
+static RUNNING_BUILDS: Lazy<Mutex<HashMap<String, RunningBuild>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct RunningBuild {
+    token: CancellationToken,
+    reason: Option<String>,
+}
+
+/// Entry of a build in [`RUNNING_BUILDS`], removed when dropped at the end of [`build`].
+///
+/// Registered as soon as the task arrives, so a cancellation that comes in before the build
+/// starts still stops it at its first checkpoint.
+pub struct RunningBuildGuard {
+    id: String,
+    token: CancellationToken,
+}
+
+impl RunningBuildGuard {
+    pub fn register(id: &str) -> Self {
+        let token = CancellationToken::new();
+        let mut builds = RUNNING_BUILDS.lock().unwrap_or_else(|e| e.into_inner());
+        builds.insert(
+            id.to_string(),
+            RunningBuild {
+                token: token.clone(),
+                reason: None,
+            },
+        );
+        Self {
+            id: id.to_string(),
+            token,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    fn cancelled_error(&self) -> Box<dyn Error + Send + Sync> {
+        let builds = RUNNING_BUILDS.lock().unwrap_or_else(|e| e.into_inner());
+        let reason = builds
+            .get(&self.id)
+            .and_then(|build| build.reason.clone())
+            .unwrap_or_else(|| "cancelled by server".to_string());
+        Box::new(BuildCancelled(reason))
+    }
+}
+
+impl Drop for RunningBuildGuard {
+    fn drop(&mut self) {
+        let mut builds = RUNNING_BUILDS.lock().unwrap_or_else(|e| e.into_inner());
+        builds.remove(&self.id);
+    }
+}
+
+/// Error returned by [`build`] when the build was stopped by [`cancel_build`].
+#[derive(Debug)]
+pub struct BuildCancelled(pub String);
+
+impl fmt::Display for BuildCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Build cancelled: {}", self.0)
+    }
+}
+
+impl Error for BuildCancelled {}
+
+/// Request cancellation of a running build.
+///
+/// The build stops at its next checkpoint: before mounting, after target discovery,
+/// or immediately while `buck2 build` runs. Returns `false` if the build is not
+/// running on this worker.
+pub fn cancel_build(id: &str, reason: &str) -> bool {
+    let mut builds = RUNNING_BUILDS.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(build) = builds.get_mut(id) else {
+        return false;
+    };
+    build.reason.get_or_insert_with(|| reason.to_string());
+    build.token.cancel();
+    true
+}
+
 #[derive(Debug, Clone)]
 struct AntaresMountPair {
     old_mount_id: String,
@@ -73,6 +160,26 @@ fn buck_remote_cache_enabled() -> bool {
     }
 }
 
+/// Run `buck2 test` after a successful build when `ORION_RUN_TESTS` is set to
+/// `1`, `true`, `yes`, or `on` (or `0`, `false`, `no`, `off` to disable), falling
+/// back to `[build] orion_run_tests` in the MEGA config.
+///
+/// Default: disabled.
+fn run_tests_enabled() -> bool {
+    if let Ok(value) = std::env::var("ORION_RUN_TESTS") {
+        let normalized = value.trim().to_ascii_lowercase();
+        match normalized.as_str() {
+            "1" | "true" | "yes" | "on" => return true,
+            "0" | "false" | "no" | "off" => return false,
+            _ => tracing::warn!("Invalid ORION_RUN_TESTS={value:?}, ignoring."),
+        }
+    }
+
+    build_config()
+        .map(|config| config.orion_run_tests)
+        .unwrap_or(false)
+}
+
 fn retain_antares_mounts() -> bool {
     match std::env::var("ORION_RETAIN_ANTARES_MOUNTS") {
         Ok(value) => {
@@ -1616,13 +1723,261 @@ async fn flush_buffer(
     }
 }
 
+/// Stop the buck2 daemon of an isolation dir, together with the actions it runs.
+async fn kill_buck2_daemon(id: &str, isolation_dir: &str, project_root: &Path) {
+    let mut kill_cmd = Command::new("buck2");
+    kill_cmd
+        .arg("kill")
+        .arg("--isolation-dir")
+        .arg(isolation_dir)
+        .current_dir(project_root)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    match kill_cmd.status().await {
+        Ok(status) if !status.success() => {
+            tracing::debug!("[Task {}] Buck2 daemon was not running (expected)", id);
+        }
+        Err(e) => {
+            tracing::warn!("[Task {}] Failed to kill buck2 daemon: {}", id, e);
+        }
+        _ => {}
+    }
+}
+
+/// Kill the buck2 client and every process in its process group.
+async fn kill_process_group(id: &str, child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let group = format!("-{pid}");
+        match Command::new("kill")
+            .args(["-KILL", "--", &group])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+        {
+            Ok(status) if status.success() => return,
+            Ok(status) => tracing::debug!(
+                "[Task {}] kill of process group {} exited with {}",
+                id,
+                pid,
+                status
+            ),
+            Err(e) => tracing::warn!("[Task {}] Failed to kill process group {}: {}", id, pid, e),
+        }
+    }
+    if let Err(e) = child.kill().await {
+        tracing::warn!("[Task {}] Failed to kill buck2: {}", id, e);
+    }
+}
+
+/// Send parsed test results to the server. Returns false if the WebSocket is closed.
+fn flush_test_results(
+    sender: &UnboundedSender<WSMessage>,
+    build_id: &str,
+    buffer: &mut Vec<WSTestResult>,
+) -> bool {
+    if buffer.is_empty() {
+        return true;
+    }
+    sender
+        .send(WSMessage::TestResultBatch {
+            build_id: build_id.to_string(),
+            results: std::mem::take(buffer),
+        })
+        .is_ok()
+}
+
+/// Read the per-test results from the event log of a finished `buck2 test` run.
+async fn read_test_results(id: &str, project_root: &Path) -> Vec<WSTestResult> {
+    let event_log_path = project_root.join(TEST_EVENT_LOG_FILE);
+    if !event_log_path.exists() {
+        tracing::warn!(
+            "[Task {}] buck2 test wrote no event log at {}",
+            id,
+            event_log_path.display()
+        );
+        return Vec::new();
+    }
+
+    let (tx, mut rx) = mpsc::channel::<String>(4096);
+    let tail_handle = tokio::spawn(async move {
+        tail_compressed_buck2_events(event_log_path, tx, Duration::from_millis(50)).await
+    });
+    let mut results = Vec::new();
+    while let Some(line) = rx.recv().await {
+        if let Some(result) = parse_test_event(&line) {
+            results.push(result);
+        }
+    }
+    match tail_handle.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("[Task {}] Failed to read test event log: {:?}", id, e),
+        Err(e) => tracing::warn!("[Task {}] Test event log reader panicked: {}", id, e),
+    }
+    results
+}
+
+/// Run `buck2 test` on the already built targets, reusing the build's daemon.
+///
+/// Output is streamed like build output. Per-test results are read from the
+/// test run's event log once buck2 exits and sent as `TestResultBatch`
+/// messages. Returns `None` if the build was cancelled while tests were running.
+async fn run_buck2_test(
+    id: &str,
+    project_root: &Path,
+    isolation_dir: &str,
+    targets: &[TargetLabel],
+    remote_cache: bool,
+    sender: &UnboundedSender<WSMessage>,
+    running: &RunningBuildGuard,
+) -> Result<Option<ExitStatus>, Box<dyn Error + Send + Sync>> {
+    tracing::info!(
+        "[Task {}] Starting buck2 test. project_root={}, targets={}",
+        id,
+        project_root.display(),
+        targets.len()
+    );
+
+    let mut cmd = Command::new("buck2");
+    cmd.env("BUCKD_STARTUP_TIMEOUT", "30")
+        .env("BUCKD_STARTUP_INIT_TIMEOUT", "1200")
+        .arg("test");
+    for flag in platform_config_flags() {
+        cmd.arg(flag);
+    }
+    // Results come from the event log; the console output is only streamed.
+    let _ = tokio::fs::remove_file(project_root.join(TEST_EVENT_LOG_FILE)).await;
+    cmd.args(["--event-log", TEST_EVENT_LOG_FILE])
+        .args(targets)
+        .arg("--console=simple");
+    if !remote_cache {
+        cmd.arg("--no-remote-cache");
+    }
+    cmd.arg("--isolation-dir")
+        .arg(isolation_dir)
+        .current_dir(project_root)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    tracing::debug!("[Task {}] Executing command: {:?}", id, cmd);
+    let mut child = cmd.spawn()?;
+
+    if let Err(e) = sender.send(WSMessage::TaskPhaseUpdate {
+        build_id: id.to_string(),
+        phase: TaskPhase::RunningTests,
+    }) {
+        tracing::error!("Failed to send RunningTests phase update: {}", e);
+    }
+
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let mut stdout_reader = tokio::io::BufReader::new(stdout).lines();
+    let mut stderr_reader = tokio::io::BufReader::new(stderr).lines();
+    let mut stdout_open = true;
+    let mut stderr_open = true;
+
+    let mut exit_status: Option<ExitStatus> = None;
+    let mut cancelled = false;
+    loop {
+        let line = tokio::select! {
+            _ = running.token.cancelled() => {
+                tracing::info!("[Task {}] Cancellation requested; stopping buck2 test.", id);
+                cancelled = true;
+                break;
+            },
+            result = stdout_reader.next_line(), if stdout_open => match result {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    stdout_open = false;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!("[Task {}] Error reading test stdout: {}", id, e);
+                    stdout_open = false;
+                    continue;
+                }
+            },
+            result = stderr_reader.next_line(), if stderr_open => match result {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    stderr_open = false;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!("[Task {}] Error reading test stderr: {}", id, e);
+                    stderr_open = false;
+                    continue;
+                }
+            },
+            status = child.wait(), if !stdout_open && !stderr_open => {
+                let status = status?;
+                tracing::info!("[Task {}] Buck2 test finished with status: {}", id, status);
+                exit_status = Some(status);
+                break;
+            }
+        };
+
+        tracing::info!("[Task {}] buck2 test: {}", id, line);
+        if sender
+            .send(WSMessage::TaskBuildOutput {
+                build_id: id.to_string(),
+                output: line,
+            })
+            .is_err()
+        {
+            kill_process_group(id, &mut child).await;
+            return Err("WebSocket connection lost during tests.".into());
+        }
+    }
+
+    if cancelled {
+        kill_process_group(id, &mut child).await;
+        kill_buck2_daemon(id, isolation_dir, project_root).await;
+        let _ = child.wait().await;
+        return Ok(None);
+    }
+
+    let status = match exit_status {
+        Some(s) => s,
+        None => child.wait().await?,
+    };
+
+    let mut results = read_test_results(id, project_root).await;
+    tracing::info!("[Task {}] Collected {} test results", id, results.len());
+    while !results.is_empty() {
+        let rest = results.split_off(results.len().min(MAX_BATCH_SIZE));
+        let mut batch = std::mem::replace(&mut results, rest);
+        if !flush_test_results(sender, id, &mut batch) {
+            tracing::warn!("[Task {}] Failed to send test result batch", id);
+            break;
+        }
+    }
+    if status.success() {
+        tracing::info!("[Task {}] Buck2 tests passed", id);
+    } else {
+        tracing::error!(
+            "[Task {}] Buck2 test failed with exit code: {}",
+            id,
+            status
+                .code()
+                .map_or("unknown".to_string(), |c| c.to_string())
+        );
+    }
+    Ok(Some(status))
+}
+
 /// Executes buck build with filesystem mounting and output streaming.
 ///
 /// Process flow:
 /// 1. Mount repository filesystem via remote API
 /// 2. Execute buck build command with specified target and arguments  
 /// 3. Stream build output in real-time via WebSocket
-/// 4. Return final build status
+/// 4. Optionally run `buck2 test` on the same targets (see `run_tests_enabled`)
+/// 5. Return final build status
 ///
 /// # Arguments
 /// * `id` - Build task identifier for logging and tracking
@@ -1632,6 +1987,7 @@ async fn flush_buffer(
 /// * `cl` - Change List context identifier
 /// * `sender` - WebSocket channel for streaming build output
 /// * `changes` - Commit's file change information
+/// * `running` - Registration of the build for cancellation, see [`RunningBuildGuard`]
 ///
 /// # Returns
 /// Process exit status indicating build success or failure
@@ -1641,6 +1997,7 @@ pub async fn build(
     cl: String,
     sender: UnboundedSender<WSMessage>,
     changes: Vec<Status<ProjectRelativePath>>,
+    running: RunningBuildGuard,
 ) -> Result<ExitStatus, Box<dyn Error + Send + Sync>> {
     tracing::info!("[Task {}] Building in repo {}", id, repo);
 
@@ -1677,6 +2034,9 @@ pub async fn build(
     let mut last_targets_error: Option<anyhow::Error> = None;
 
     for attempt in 1..=MAX_TARGETS_ATTEMPTS {
+        if running.is_cancelled() {
+            return Err(running.cancelled_error());
+        }
         // Mount TWO independent views of the same monorepo:
         //
         //   old_repo  — base revision (no CL), used as the "before" snapshot
@@ -1853,6 +2213,14 @@ pub async fn build(
         unmount_discovery_old_mount(&id, &mut mounts).await;
     }
 
+    if running.is_cancelled() {
+        tracing::info!("[Task {}] Cancelled after target discovery.", id);
+        if !retain_mounts {
+            cleanup_antares_mount_pair(&id, &mounts, "cleanup after cancellation").await;
+        }
+        return Err(running.cancelled_error());
+    }
+
     if finish_without_build_if_no_targets(&id, &targets, &sender)? {
         tracing::info!(
             "[Task {}] No impacted Buck targets detected; skipping buck2 build.",
@@ -1894,24 +2262,7 @@ pub async fn build(
         // Note: Buck2 requires isolation-dir to be a simple directory name without path separators
         let isolation_dir = format!("buck-isolation-{}", id);
         let remote_cache = buck_remote_cache_enabled();
-        let mut kill_cmd = Command::new("buck2");
-        kill_cmd
-            .arg("kill")
-            .arg("--isolation-dir")
-            .arg(&isolation_dir)
-            .current_dir(&project_root)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null());
-
-        match kill_cmd.status().await {
-            Ok(status) if !status.success() => {
-                tracing::debug!("[Task {}] Buck2 daemon was not running (expected)", id);
-            }
-            Err(e) => {
-                tracing::warn!("[Task {}] Failed to kill buck2 daemon: {}", id, e);
-            }
-            _ => {}
-        }
+        kill_buck2_daemon(&id, &isolation_dir, &project_root).await;
 
         // Wait for daemon to fully stop before starting a new build
         tokio::time::sleep(Duration::from_millis(500)).await;
@@ -1946,6 +2297,9 @@ pub async fn build(
             .current_dir(&project_root)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        // Own process group, so cancellation can stop buck2 together with its children.
+        #[cfg(unix)]
+        cmd.process_group(0);
 
         tracing::debug!("[Task {}] Executing command: {:?}", id, cmd);
 
@@ -1980,8 +2334,14 @@ pub async fn build(
         let mut stderr_reader = tokio::io::BufReader::new(stderr).lines();
 
         let mut exit_status: Option<ExitStatus> = None;
+        let mut cancelled = false;
         loop {
             tokio::select! {
+                _ = running.token.cancelled() => {
+                    tracing::info!("[Task {}] Cancellation requested; stopping buck2.", id);
+                    cancelled = true;
+                    break;
+                },
                 result = stdout_reader.next_line() => {
                     match result {
                         Ok(Some(line)) => {
@@ -2025,6 +2385,11 @@ pub async fn build(
             }
         }
 
+        if cancelled {
+            kill_process_group(&id, &mut child).await;
+            kill_buck2_daemon(&id, &isolation_dir, &project_root).await;
+        }
+
         let status = match exit_status {
             Some(s) => s,
             None => child.wait().await?,
@@ -2061,6 +2426,26 @@ pub async fn build(
             tracing::warn!("Build status processing loop did not finish within 2s grace period");
         }
         tracing::info!("Target build status track finished");
+        if cancelled {
+            return Err(running.cancelled_error());
+        }
+
+        if status.success() && run_tests_enabled() {
+            let test_status = run_buck2_test(
+                &id,
+                &project_root,
+                &isolation_dir,
+                &targets,
+                remote_cache,
+                &sender,
+                &running,
+            )
+            .await?;
+            return match test_status {
+                Some(test_status) => Ok(test_status),
+                None => Err(running.cancelled_error()),
+            };
+        }
         Ok(status)
     }
     .await;
@@ -2111,12 +2496,13 @@ mod tests {
     use tokio::sync::mpsc;
 
     use super::{
-        all_changes_are_added, antares_unmount_grace_duration, buck_remote_cache_enabled,
-        filter_owner_seed_changes, finish_without_build_if_no_targets, get_build_targets,
-        get_repo_targets, is_toolchain_or_platform_rule, is_toolchain_or_platform_target,
+        BuildCancelled, RunningBuildGuard, all_changes_are_added, antares_unmount_grace_duration,
+        buck_remote_cache_enabled, cancel_build, filter_owner_seed_changes,
+        finish_without_build_if_no_targets, get_build_targets, get_repo_targets,
+        is_toolchain_or_platform_rule, is_toolchain_or_platform_target,
         label_is_toolchain_or_platform, normalize_owner_targets_to_rust,
         owner_seed_changes_for_discovery, remap_repo_local_change_paths, retain_antares_mounts,
-        validate_project_root_exists,
+        run_tests_enabled, validate_project_root_exists,
     };
 
     struct JsonlCleanupGuard {
@@ -2308,6 +2694,23 @@ mod tests {
         set_mount_retention_env(None);
     }
 
+    #[test]
+    #[serial]
+    fn test_run_tests_env_overrides_config() {
+        // SAFETY: this test is marked serial and only mutates process env inside the test.
+        unsafe {
+            std::env::set_var("ORION_RUN_TESTS", "on");
+        }
+        assert!(run_tests_enabled());
+        unsafe {
+            std::env::set_var("ORION_RUN_TESTS", "off");
+        }
+        assert!(!run_tests_enabled());
+        unsafe {
+            std::env::remove_var("ORION_RUN_TESTS");
+        }
+    }
+
     #[test]
     #[serial]
     fn test_antares_unmount_grace_duration_defaults_to_150ms() {
@@ -2888,4 +3291,21 @@ edition = "2024"
             "root//project/aardvark-dns:aardvark-dns"
         );
     }
+
+    #[test]
+    fn test_cancel_build_signals_registered_build_only() {
+        assert!(!cancel_build("not-running", "test"));
+
+        let running = RunningBuildGuard::register("cancel-test");
+        assert!(!running.is_cancelled());
+        assert!(cancel_build("cancel-test", "superseded"));
+        assert!(running.is_cancelled());
+
+        let err = running.cancelled_error();
+        assert!(err.is::<BuildCancelled>());
+        assert_eq!(err.to_string(), "Build cancelled: superseded");
+
+        drop(running);
+        assert!(!cancel_build("cancel-test", "again"));
+    }
 }
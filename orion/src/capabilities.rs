@@ -0,0 +1,94 @@
+//! Worker capabilities advertised to orion-server on registration.
+//!
+//! The scheduler only routes builds to workers whose capabilities meet the
+//! build's requirements, see `BuildRequirements` in `api-model`.
+
+use std::path::Path;
+
+use api_model::buck2::types::WorkerCapabilities;
+
+/// Toolchains probed on `PATH` when `ORION_WORKER_TOOLCHAINS` is not set,
+/// as `(toolchain, binary)`.
+const PROBED_TOOLCHAINS: &[(&str, &str)] = &[
+    ("rust", "rustc"),
+    ("go", "go"),
+    ("python", "python3"),
+    ("node", "node"),
+    ("java", "javac"),
+    ("cxx", "c++"),
+];
+
+/// Detect this worker's capabilities.
+///
+/// - labels: `ORION_WORKER_LABELS`, comma-separated
+/// - toolchains: `ORION_WORKER_TOOLCHAINS`, comma-separated; probed on `PATH` when unset
+/// - CPU cores, memory and OS: detected from the host
+pub fn detect() -> WorkerCapabilities {
+    let toolchains = match std::env::var("ORION_WORKER_TOOLCHAINS") {
+        Ok(value) => parse_list(&value),
+        Err(_) => probe_toolchains(),
+    };
+    WorkerCapabilities {
+        labels: std::env::var("ORION_WORKER_LABELS")
+            .map(|value| parse_list(&value))
+            .unwrap_or_default(),
+        cpu_cores: std::thread::available_parallelism()
+            .ok()
+            .and_then(|cores| u32::try_from(cores.get()).ok()),
+        memory_mb: total_memory_mb(),
+        os: Some(std::env::consts::OS.to_string()),
+        toolchains,
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for item in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if !items.iter().any(|existing| existing == item) {
+            items.push(item.to_string());
+        }
+    }
+    items
+}
+
+fn probe_toolchains() -> Vec<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let dirs: Vec<_> = std::env::split_paths(&path).collect();
+    PROBED_TOOLCHAINS
+        .iter()
+        .filter(|(_, binary)| dirs.iter().any(|dir| dir.join(binary).is_file()))
+        .map(|(toolchain, _)| toolchain.to_string())
+        .collect()
+}
+
+fn total_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string(Path::new("/proc/meminfo")).ok()?;
+    parse_mem_total_mb(&meminfo)
+}
+
+/// Parse `MemTotal:   16318412 kB` from `/proc/meminfo`.
+fn parse_mem_total_mb(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_list, parse_mem_total_mb};
+
+    #[test]
+    fn test_parse_list_trims_and_deduplicates() {
+        assert_eq!(parse_list(" large, gpu,,large "), vec!["large", "gpu"]);
+        assert!(parse_list("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_mem_total_mb() {
+        let meminfo = "MemTotal:       16318412 kB\nMemFree:         1234567 kB\n";
+        assert_eq!(parse_mem_total_mb(meminfo), Some(15935));
+        assert_eq!(parse_mem_total_mb("MemFree: 1 kB"), None);
+    }
+}
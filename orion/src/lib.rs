@@ -1,6 +1,7 @@
 mod antares;
 mod api;
 mod buck_controller;
+mod capabilities;
 pub mod repo;
 mod util;
 pub mod ws;
@@ -2,6 +2,7 @@
 mod antares;
 mod api;
 mod buck_controller;
+mod capabilities;
 pub mod repo;
 mod util;
 mod ws;
@@ -22,7 +22,7 @@ use tokio_tungstenite::{
 use url::Url;
 use uuid::Uuid;
 
-use crate::api::buck_build;
+use crate::{api::buck_build, buck_controller, capabilities};
 
 const WS_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 const PROXY_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -275,6 +275,7 @@ async fn handle_connection(
             id: worker_id_clone,
             hostname: hostname_clone,
             orion_version,
+            capabilities: capabilities::detect(),
         };
         let register_str = match serde_json::to_string(&register) {
             Ok(s) => s,
@@ -376,6 +377,9 @@ async fn process_server_message(
                             changes,
                         } => {
                             tracing::info!("Received task: id={}", build_id);
+                            // Registered before spawning, so a TaskCancel that arrives
+                            // before the build starts still stops it.
+                            let running = buck_controller::RunningBuildGuard::register(&build_id);
                             tokio::spawn(async move {
                                 let task_id_uuid = match Uuid::parse_str(&build_id) {
                                     Ok(uuid) => uuid,
@@ -394,6 +398,7 @@ async fn process_server_message(
                                     cl_link,
                                     repo,
                                     changes,
+                                    running,
                                     sender.clone(),
                                 )
                                 .await;
@@ -407,6 +412,25 @@ async fn process_server_message(
                                 }
                             });
                         }
+                        WSMessage::TaskCancel { build_id, reason } => {
+                            tracing::info!(
+                                "Received cancellation: id={} reason={}",
+                                build_id,
+                                reason
+                            );
+                            if !buck_controller::cancel_build(&build_id, &reason) {
+                                tracing::warn!(
+                                    "Build {} is not running on this worker; reporting it cancelled",
+                                    build_id
+                                );
+                                if let Err(e) = sender.send(WSMessage::TaskBuildCancelled {
+                                    build_id,
+                                    message: format!("Build cancelled: {reason}"),
+                                }) {
+                                    tracing::error!("Failed to send TaskBuildCancelled: {}", e);
+                                }
+                            }
+                        }
                         // Log unexpected message types
                         _ => {
                             tracing::warn!("Received unexpected message from server: {:?}", ws_msg);
@@ -1,16 +1,42 @@
+use std::str::FromStr;
+
 use cedar_policy::{
-    Authorizer, CedarSchemaError, Context, Decision, Diagnostics, ParseErrors, PolicySet,
-    PolicySetError, Request, Schema, SchemaError, ValidationMode, Validator,
+    Authorizer, CedarSchemaError, Context, Decision, Diagnostics, Effect, EntityId, EntityTypeName,
+    EntityUid, ParseErrors, PolicyId, PolicySet, PolicySetError, Request, Schema, SchemaError,
+    ValidationMode, Validator,
 };
 use itertools::Itertools;
+use serde::Serialize;
 use thiserror::Error;
 
-use crate::{entitystore::EntityStore, util::SaturnEUid};
+use crate::{ActionEnum, entitystore::EntityStore, util::SaturnEUid};
+
+/// Principal id used for requests that carry no authenticated user.
+pub const ANONYMOUS_USER: &str = "anonymous";
+
+/// Name given to the built-in policies in decision explanations.
+pub const BUILTIN_POLICY_FILE: &str = "mega_policies.cedar";
+
+/// Outcome of evaluating a request, with enough detail to explain a denial.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyDecision {
+    pub allowed: bool,
+    /// Repository entity the request was evaluated against, `None` when no entity covers the
+    /// path and the request is therefore unrestricted.
+    pub resource: Option<String>,
+    /// Policies that determined the decision: the matching `permit`s when allowed, the
+    /// matching `forbid`s when denied. Empty on a denial means no policy permits the request.
+    pub policies: Vec<String>,
+    /// Policies that failed to evaluate; they are ignored by the decision.
+    pub errors: Vec<String>,
+}
 
 pub struct CedarContext {
     pub entities: EntityStore,
     authorizer: Authorizer,
     policies: PolicySet,
+    /// The `forbid` policies of `policies`, checked against enclosing repositories.
+    forbids: PolicySet,
     schema: Schema,
 }
 
@@ -43,46 +69,59 @@ pub enum SaturnContextError {
 #[allow(clippy::result_large_err)]
 impl CedarContext {
     pub fn from(entities: EntityStore, policy_content: &str) -> Result<Self, ContextError> {
-        let (schema, _) = Schema::from_cedarschema_str(include_str!("../mega.cedarschema"))?;
-        let policies = policy_content.parse()?;
-        let validator = Validator::new(schema.clone());
-        let output = validator.validate(&policies, ValidationMode::default());
+        Self::validated(entities, policy_content.parse()?)
+    }
 
-        if output.validation_passed() {
-            tracing::debug!("All policy validation passed!");
-            let authorizer = Authorizer::new();
-            let c = Self {
-                entities,
-                authorizer,
-                policies,
-                schema,
-            };
+    pub fn new(entities: EntityStore) -> Result<Self, ContextError> {
+        Self::validated(entities, include_str!("../mega_policies.cedar").parse()?)
+    }
 
-            Ok(c)
-        } else {
-            let error_string = output
-                .validation_errors()
-                .map(|err| format!("{err}"))
-                .join("\n");
-            Err(ContextError::Validation(error_string))
+    /// Build a context from the built-in `mega_policies.cedar` plus policy files committed in the
+    /// monorepo, given as `(file path, content)` pairs ordered from root to leaf.
+    ///
+    /// Cedar policies are additive: a nested file can grant more access with `permit` or take
+    /// it away with `forbid`, and any matching `forbid` wins, also over a repository entity
+    /// declared deeper (see [`Self::is_authorized_for_path`]). Each policy is renamed to
+    /// `<file path>#<@id or index>` so that decisions can be traced back to their file.
+    pub fn with_policy_files(
+        entities: EntityStore,
+        files: &[(String, String)],
+    ) -> Result<Self, ContextError> {
+        let mut policies = PolicySet::new();
+        let builtin = [(
+            BUILTIN_POLICY_FILE.to_string(),
+            include_str!("../mega_policies.cedar").to_string(),
+        )];
+        for (file, content) in builtin.iter().chain(files) {
+            let parsed: PolicySet = content.parse()?;
+            for (index, policy) in parsed.policies().enumerate() {
+                let name = policy
+                    .annotation("id")
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| index.to_string());
+                policies.add(policy.new_id(PolicyId::new(format!("{file}#{name}"))))?;
+            }
         }
+        Self::validated(entities, policies)
     }
 
-    pub fn new(entities: EntityStore) -> Result<Self, ContextError> {
-        let schema_content = include_str!("../mega.cedarschema");
-        let policy_content = include_str!("../mega_policies.cedar");
-        let (schema, _) = Schema::from_cedarschema_str(schema_content).unwrap();
-        let policies = policy_content.parse()?;
+    fn validated(entities: EntityStore, policies: PolicySet) -> Result<Self, ContextError> {
+        let (schema, _) = Schema::from_cedarschema_str(include_str!("../mega.cedarschema"))?;
         let validator = Validator::new(schema.clone());
         let output = validator.validate(&policies, ValidationMode::default());
 
         if output.validation_passed() {
             tracing::debug!("All policy validation passed!");
             let authorizer = Authorizer::new();
+            let mut forbids = PolicySet::new();
+            for policy in policies.policies().filter(|p| p.effect() == Effect::Forbid) {
+                forbids.add(policy.clone())?;
+            }
             let c = Self {
                 entities,
                 authorizer,
                 policies,
+                forbids,
                 schema,
             };
 
@@ -127,4 +166,104 @@ impl CedarContext {
             )),
         }
     }
+
+    /// Authorize `action` by `username` (or [`ANONYMOUS_USER`]) on the repository entity that
+    /// governs the monorepo `path`, see [`EntityStore::repository_for_path`].
+    ///
+    /// The `forbid`s are also checked against every enclosing repository entity, so declaring
+    /// a nested repository does not lift a restriction placed on its parent. Paths that no
+    /// repository entity covers are not restricted.
+    pub fn is_authorized_for_path(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<(), SaturnContextError> {
+        match self.evaluate_path(username, action, path)? {
+            Some((_, Decision::Deny, diagnostics)) => {
+                Err(SaturnContextError::AuthDenied(diagnostics))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Same evaluation as [`Self::is_authorized_for_path`], reported as a [`PolicyDecision`].
+    pub fn explain_for_path(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<PolicyDecision, SaturnContextError> {
+        let Some((resource, decision, diagnostics)) = self.evaluate_path(username, action, path)?
+        else {
+            return Ok(PolicyDecision {
+                allowed: true,
+                resource: None,
+                policies: Vec::new(),
+                errors: Vec::new(),
+            });
+        };
+        let mut policies: Vec<String> = diagnostics.reason().map(|id| id.to_string()).collect();
+        policies.sort();
+        Ok(PolicyDecision {
+            allowed: decision == Decision::Allow,
+            resource: Some(resource.to_string()),
+            policies,
+            errors: diagnostics.errors().map(|e| e.to_string()).collect(),
+        })
+    }
+
+    /// Decision for a path request and the repository entity that produced it; `None` when
+    /// no repository entity covers the path.
+    fn evaluate_path(
+        &self,
+        username: Option<&str>,
+        action: ActionEnum,
+        path: &str,
+    ) -> Result<Option<(SaturnEUid, Decision, Diagnostics)>, SaturnContextError> {
+        let repositories = self.entities.repositories_for_path(path);
+        let Some((resource, enclosing)) = repositories.split_first() else {
+            return Ok(None);
+        };
+        let principal = entity_uid("User", username.unwrap_or(ANONYMOUS_USER))?;
+        let action = entity_uid("Action", &action.to_string())?;
+        let (decision, diagnostics) =
+            self.evaluate(&principal, &action, resource, &self.policies)?;
+        if decision == Decision::Allow {
+            for repository in enclosing {
+                let (_, forbidden) =
+                    self.evaluate(&principal, &action, repository, &self.forbids)?;
+                if forbidden.reason().next().is_some() {
+                    return Ok(Some(((*repository).clone(), Decision::Deny, forbidden)));
+                }
+            }
+        }
+        Ok(Some(((*resource).clone(), decision, diagnostics)))
+    }
+
+    fn evaluate(
+        &self,
+        principal: &SaturnEUid,
+        action: &SaturnEUid,
+        resource: &SaturnEUid,
+        policies: &PolicySet,
+    ) -> Result<(Decision, Diagnostics), SaturnContextError> {
+        let es = self.entities.as_entities(&self.schema);
+        let q = Request::new(
+            principal.clone().into(),
+            action.clone().into(),
+            resource.clone().into(),
+            Context::empty(),
+            Some(&self.schema),
+        )
+        .map_err(|e| SaturnContextError::Request(e.to_string()))?;
+        let response = self.authorizer.is_authorized(&q, policies, &es);
+        Ok((response.decision(), response.diagnostics().clone()))
+    }
+}
+
+fn entity_uid(type_name: &str, id: &str) -> Result<SaturnEUid, SaturnContextError> {
+    let type_name = EntityTypeName::from_str(type_name)
+        .map_err(|e| SaturnContextError::Request(e.to_string()))?;
+    Ok(EntityUid::from_type_name_and_id(type_name, EntityId::new(id)).into())
 }
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use cedar_policy::{Entities, Schema};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, to_string_pretty};
 
@@ -52,6 +53,70 @@ impl EntityStore {
         self.user_groups.extend(other.user_groups);
     }
 
+    /// Add the entities of a file nested below the ones merged so far.
+    ///
+    /// Entities already in the store are authoritative: a nested file can declare new
+    /// entities but cannot redefine one declared above it.
+    pub fn merge_nested(&mut self, nested: EntityStore) {
+        fn add<V>(store: &mut HashMap<SaturnEUid, V>, nested: HashMap<SaturnEUid, V>) {
+            for (euid, entity) in nested {
+                store.entry(euid).or_insert(entity);
+            }
+        }
+        add(&mut self.users, nested.users);
+        add(&mut self.repos, nested.repos);
+        add(&mut self.merge_requests, nested.merge_requests);
+        add(&mut self.issues, nested.issues);
+        add(&mut self.user_groups, nested.user_groups);
+    }
+
+    /// Find the repository entity that governs a monorepo `path`.
+    ///
+    /// Repository ids are directory paths (`Repository::"/project/private"`); the deepest id
+    /// that is `path` itself or one of its ancestors wins. A missing leading `/` is tolerated.
+    pub fn repository_for_path(&self, path: &str) -> Option<&SaturnEUid> {
+        self.repositories_for_path(path).into_iter().next()
+    }
+
+    /// Every repository entity covering a monorepo `path`, deepest first.
+    pub fn repositories_for_path(&self, path: &str) -> Vec<&SaturnEUid> {
+        let path = normalize_repo_path(path);
+        self.repos
+            .values()
+            .map(Repo::euid)
+            .filter_map(|euid| {
+                let id: &str = euid.id().as_ref();
+                let repo_path = normalize_repo_path(id);
+                let covers = repo_path == "/"
+                    || path == repo_path
+                    || path
+                        .strip_prefix(repo_path.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'));
+                covers.then_some((repo_path.len(), euid))
+            })
+            .sorted_by_key(|(len, _)| std::cmp::Reverse(*len))
+            .map(|(_, euid)| euid)
+            .collect()
+    }
+
+    /// Paths of the repository entities declared strictly below a monorepo `path`, which
+    /// [`Self::repository_for_path`] never returns for `path` itself.
+    pub fn repository_paths_below(&self, path: &str) -> Vec<String> {
+        let path = normalize_repo_path(path);
+        self.repos
+            .values()
+            .map(|repo| normalize_repo_path(repo.euid().id().as_ref()))
+            .filter(|repo_path| {
+                repo_path != &path
+                    && (path == "/"
+                        || repo_path
+                            .strip_prefix(path.as_str())
+                            .is_some_and(|rest| rest.starts_with('/')))
+            })
+            .sorted()
+            .collect()
+    }
+
     pub fn extract_admin_usernames(&self) -> HashSet<String> {
         const ADMIN_GROUP: &str = "UserGroup::\"admin\"";
 
@@ -134,3 +199,8 @@ pub fn generate_entity(
     }
     Ok(to_string_pretty(&json_data)?)
 }
+
+fn normalize_repo_path(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    format!("/{trimmed}")
+}
@@ -12,9 +12,9 @@ pub enum ActionEnum {
     // ** Anyone
     UnprotectedRequest,
     ViewRepo,
-    // PullRepo,
-    // ForkRepo,
-    // PushRepo,
+    PullRepo,
+    ForkRepo,
+    PushRepo,
     // OpenIssue,
     // ** Maintainer
     CreateMergeRequest,
@@ -35,6 +35,9 @@ impl Display for ActionEnum {
         let s = match self {
             ActionEnum::UnprotectedRequest => "unprotectedRequest",
             ActionEnum::ViewRepo => "viewRepo",
+            ActionEnum::PullRepo => "pullRepo",
+            ActionEnum::ForkRepo => "forkRepo",
+            ActionEnum::PushRepo => "pushRepo",
             ActionEnum::CreateMergeRequest => "createMergeRequest",
             ActionEnum::EditIssue => "editIssue",
             ActionEnum::EditMergeRequest => "editMergeRequest",
@@ -54,6 +57,9 @@ impl From<&str> for ActionEnum {
     fn from(s: &str) -> Self {
         match s {
             "viewRepo" => ActionEnum::ViewRepo,
+            "pullRepo" => ActionEnum::PullRepo,
+            "forkRepo" => ActionEnum::ForkRepo,
+            "pushRepo" => ActionEnum::PushRepo,
             "createMergeRequest" => ActionEnum::CreateMergeRequest,
             "editIssue" => ActionEnum::EditIssue,
             "editMergeRequest" => ActionEnum::EditMergeRequest,
@@ -76,6 +82,7 @@ mod test {
     use cedar_policy::{Authorizer, Context, Entities, PolicySet, Request};
 
     use crate::{
+        ActionEnum,
         context::{CedarContext, SaturnContextError},
         entitystore::EntityStore,
         util::SaturnEUid,
@@ -268,4 +275,188 @@ mod test {
                 .is_err_and(|e| matches!(e, SaturnContextError::AuthDenied(_)))
         );
     }
+
+    #[test]
+    fn test_git_path_policy() {
+        init_tracing();
+        let parent_entities_file = fs::File::open("./test/project/.mega.json").unwrap();
+        let parent_entities: EntityStore = serde_json::from_reader(parent_entities_file).unwrap();
+        let entities_file = fs::File::open("./test/project/private/.mega.json").unwrap();
+        let mut entities: EntityStore = serde_json::from_reader(entities_file).unwrap();
+        entities.merge(parent_entities);
+        let app_context = load_context(entities);
+
+        // paths outside every repository entity are not restricted
+        assert!(
+            app_context
+                .is_authorized_for_path(None, ActionEnum::PushRepo, "/doc")
+                .is_ok()
+        );
+        // public project can be pulled anonymously, nested paths inherit it
+        assert!(
+            app_context
+                .is_authorized_for_path(None, ActionEnum::PullRepo, "/project/common/lib")
+                .is_ok()
+        );
+        // the deepest repository entity governs, and it is private
+        assert!(
+            app_context
+                .is_authorized_for_path(None, ActionEnum::PullRepo, "/project/bens_private/src")
+                .is_err_and(|e| matches!(e, SaturnContextError::AuthDenied(_)))
+        );
+        assert!(
+            app_context
+                .is_authorized_for_path(
+                    Some("anyone"),
+                    ActionEnum::PushRepo,
+                    "/project/bens_private"
+                )
+                .is_err_and(|e| matches!(e, SaturnContextError::AuthDenied(_)))
+        );
+        assert!(
+            app_context
+                .is_authorized_for_path(
+                    Some("private"),
+                    ActionEnum::PushRepo,
+                    "/project/bens_private"
+                )
+                .is_ok()
+        );
+        // a sibling sharing the prefix is not covered by the private entity
+        assert!(
+            app_context
+                .is_authorized_for_path(None, ActionEnum::PullRepo, "/project/bens_private2")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_policy_files_explain_decisions() {
+        init_tracing();
+        let entities_file = fs::File::open("./test/project/.mega.json").unwrap();
+        let entities: EntityStore = serde_json::from_reader(entities_file).unwrap();
+        let files = vec![(
+            "/project/.mega_policies.cedar".to_string(),
+            r#"
+            @id("readOnly")
+            forbid (principal, action == Action::"pushRepo", resource)
+            unless { principal in resource.admins };
+            "#
+            .to_string(),
+        )];
+        let app_context = CedarContext::with_policy_files(entities, &files).unwrap();
+
+        let denied = app_context
+            .explain_for_path(Some("besscroft"), ActionEnum::PushRepo, "/project/a")
+            .unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.resource.as_deref(), Some(r#"Repository::"project""#));
+        assert_eq!(
+            denied.policies,
+            vec!["/project/.mega_policies.cedar#readOnly"]
+        );
+
+        let allowed = app_context
+            .explain_for_path(Some("besscroft"), ActionEnum::PullRepo, "/project/a")
+            .unwrap();
+        assert!(allowed.allowed);
+        assert!(
+            allowed
+                .policies
+                .iter()
+                .all(|id| id.starts_with("mega_policies.cedar#"))
+        );
+        assert!(
+            app_context
+                .is_authorized_for_path(Some("genedna"), ActionEnum::PushRepo, "/project/a")
+                .is_ok()
+        );
+
+        let unrestricted = app_context
+            .explain_for_path(None, ActionEnum::PushRepo, "/doc")
+            .unwrap();
+        assert!(unrestricted.allowed && unrestricted.resource.is_none());
+
+        let invalid = vec![(
+            "/project/.mega_policies.cedar".to_string(),
+            r#"permit (principal, action == Action::"mergeEverything", resource);"#.to_string(),
+        )];
+        assert!(CedarContext::with_policy_files(EntityStore::new(), &invalid).is_err());
+    }
+
+    #[test]
+    fn test_nested_entities_cannot_lift_parent_restrictions() {
+        init_tracing();
+        let entities_file = fs::File::open("./test/project/.mega.json").unwrap();
+        let mut entities: EntityStore = serde_json::from_reader(entities_file).unwrap();
+        let nested: EntityStore = serde_json::from_value(serde_json::json!({
+            "users": {
+                "User::\"besscroft\"": {
+                    "euid": "User::\"besscroft\"",
+                    "parents": ["UserGroup::\"admin\""]
+                },
+                "User::\"mallory\"": {
+                    "euid": "User::\"mallory\"",
+                    "parents": ["UserGroup::\"sub_admin\""]
+                }
+            },
+            "repos": {
+                "Repository::\"/project/sub\"": {
+                    "euid": "Repository::\"/project/sub\"",
+                    "is_private": false,
+                    "admins": "UserGroup::\"sub_admin\"",
+                    "maintainers": "UserGroup::\"sub_admin\"",
+                    "readers": "UserGroup::\"sub_admin\"",
+                    "parents": []
+                }
+            },
+            "user_groups": {
+                "UserGroup::\"sub_admin\"": {
+                    "euid": "UserGroup::\"sub_admin\"",
+                    "parents": []
+                }
+            },
+            "merge_requests": {},
+            "issues": {}
+        }))
+        .unwrap();
+        entities.merge_nested(nested);
+        assert_eq!(
+            entities.repository_paths_below("/project"),
+            vec!["/project/sub"]
+        );
+        assert!(entities.repository_paths_below("/project/sub").is_empty());
+        let files = vec![(
+            "/project/.mega_policies.cedar".to_string(),
+            r#"
+            @id("readOnly")
+            forbid (principal, action == Action::"pushRepo", resource)
+            unless { principal in resource.admins };
+            "#
+            .to_string(),
+        )];
+        let app_context = CedarContext::with_policy_files(entities, &files).unwrap();
+
+        // the nested file cannot promote a user declared above it
+        assert!(
+            app_context
+                .is_authorized_for_path(Some("besscroft"), ActionEnum::PushRepo, "/project/a")
+                .is_err_and(|e| matches!(e, SaturnContextError::AuthDenied(_)))
+        );
+        // its own repository entity governs the subtree, but the parent forbid still applies
+        let denied = app_context
+            .explain_for_path(Some("mallory"), ActionEnum::PushRepo, "/project/sub/src")
+            .unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.resource.as_deref(), Some(r#"Repository::"project""#));
+        assert_eq!(
+            denied.policies,
+            vec!["/project/.mega_policies.cedar#readOnly"]
+        );
+        assert!(
+            app_context
+                .is_authorized_for_path(Some("mallory"), ActionEnum::PullRepo, "/project/sub")
+                .is_ok()
+        );
+    }
 }
@@ -57,6 +57,13 @@ pub struct Repo {
     parents: HashSet<SaturnEUid>,
 }
 
+impl Repo {
+    /// Get the entity unique identifier.
+    pub fn euid(&self) -> &SaturnEUid {
+        &self.euid
+    }
+}
+
 impl From<Repo> for Entity {
     fn from(value: Repo) -> Self {
         let attrs = [